@@ -6,15 +6,17 @@
 //!    to `benches/` folder, applying LZW compression with Horizontal differencing
 //!    predictor using the following command:
 //!    `gdal raster convert --co COMPRESS=LZW --co TILED=YES --co PREDICTOR=2 benches/TCI.tif benches/TCI_lzw.tif`
-//! 2. Run `cargo bench`
+//! 2. Run `cargo bench`, or `cargo bench --features mmap` to compare with memory-mapped reads
 
 use std::path::PathBuf;
 use std::sync::Arc;
 
-use async_tiff::decoder::DecoderRegistry;
+use async_tiff::decoder::{BufferPool, DecoderRegistry};
 use async_tiff::error::{AsyncTiffError, AsyncTiffResult};
 use async_tiff::metadata::cache::ReadaheadMetadataCache;
 use async_tiff::metadata::TiffMetadataReader;
+#[cfg(feature = "mmap")]
+use async_tiff::reader::MmapReader;
 use async_tiff::reader::{AsyncFileReader, ObjectReader};
 use async_tiff::{Array, ImageFileDirectory, Tile};
 use criterion::{criterion_group, criterion_main, Criterion, Throughput};
@@ -59,7 +61,18 @@ fn open_tiff(fpath: &str) -> AsyncTiffResult<Vec<Tile>> {
     let (store, path): (Box<dyn ObjectStore>, Path) = parse_url(&tif_url)?;
 
     let reader = ObjectReader::new(Arc::new(store), path);
+    fetch_tiles(reader)
+}
+
+// Open TIFF file through a memory map, fetching compressed tile bytes without copies
+#[cfg(feature = "mmap")]
+fn open_tiff_mmap(fpath: &str) -> AsyncTiffResult<Vec<Tile>> {
+    // SAFETY: the benchmark file isn't modified while mapped
+    let reader = unsafe { MmapReader::open(fpath)? };
+    fetch_tiles(reader)
+}
 
+fn fetch_tiles<R: AsyncFileReader + Clone>(reader: R) -> AsyncTiffResult<Vec<Tile>> {
     // Initialize async runtime
     let runtime = runtime::Builder::new_current_thread()
         .enable_all()
@@ -101,6 +114,33 @@ fn decode_tiff(tiles: Vec<Tile>) -> AsyncTiffResult<Vec<Array>> {
     Ok(tile_arrays)
 }
 
+// Decode tiles into buffers reused through a BufferPool, without allocating an Array per tile
+fn decode_tiff_into(tiles: &[Tile]) -> AsyncTiffResult<BufferPool> {
+    let decoder_registry = DecoderRegistry::default();
+    let buffer_pool = BufferPool::new(4);
+
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(4)
+        .build()
+        .map_err(|err| AsyncTiffError::External(Box::new(err)))?;
+
+    let tile_bytes: usize = pool.install(|| {
+        tiles
+            .into_par_iter()
+            .map(|tile| {
+                let mut buffer = buffer_pool.take();
+                tile.decode_into(&decoder_registry, &mut buffer).unwrap();
+                let len = buffer.len();
+                buffer_pool.recycle(buffer);
+                len
+            })
+            .sum()
+    });
+    assert_eq!(tile_bytes, 363528192);
+
+    Ok(buffer_pool)
+}
+
 fn read_tiff(fpath: &str) -> AsyncTiffResult<()> {
     let compressed_tiles: Vec<Tile> = open_tiff(fpath)?;
     let _decoded_tiles: Vec<Array> = decode_tiff(compressed_tiles)?;
@@ -118,6 +158,21 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     group.bench_function("async-tiff", move |b| {
         b.iter(|| read_tiff("benches/TCI_lzw.tif"))
     });
+
+    // CPU decoding into pooled buffers
+    let compressed_tiles: Vec<Tile> = open_tiff("benches/TCI_lzw.tif").unwrap();
+    group.bench_function("async-tiff-decode-into", |b| {
+        b.iter(|| decode_tiff_into(&compressed_tiles).unwrap())
+    });
+
+    // Fetching compressed tiles without decoding, from a local file
+    group.bench_function("fetch-object-store", |b| {
+        b.iter(|| open_tiff("benches/TCI_lzw.tif").unwrap())
+    });
+    #[cfg(feature = "mmap")]
+    group.bench_function("fetch-mmap", |b| {
+        b.iter(|| open_tiff_mmap("benches/TCI_lzw.tif").unwrap())
+    });
     group.finish();
 }
 
@@ -2,12 +2,22 @@ from . import enums
 from ._async_tiff import (
     TIFF,
     Array,
+    AsyncTiffError,
     Colormap,
     DecoderRegistry,
+    ExtensionRegistry,
     GeoKeyDirectory,
     ImageFileDirectory,
+    NoCache,
+    Prefetch,
+    Readahead,
     ThreadPool,
     Tile,
+    TiffDecodeError,
+    TiffFormatError,
+    TiffNotFoundError,
+    TiffUnsupportedError,
+    set_decoder_threads,
     ___version,  # noqa: F403 # pyright:ignore[reportAttributeAccessIssue]
 )
 from ._decoder_runtime import Decoder
@@ -18,13 +28,23 @@ __version__: str = ___version()
 __all__ = [
     "enums",
     "Array",
+    "AsyncTiffError",
     "Colormap",
     "Decoder",
     "DecoderRegistry",
+    "ExtensionRegistry",
     "GeoKeyDirectory",
     "ImageFileDirectory",
+    "NoCache",
+    "Prefetch",
+    "Readahead",
     "ThreadPool",
     "TIFF",
     "ObspecInput",
     "Tile",
+    "TiffDecodeError",
+    "TiffFormatError",
+    "TiffNotFoundError",
+    "TiffUnsupportedError",
+    "set_decoder_threads",
 ]
@@ -1,10 +1,16 @@
 import sys
+from typing import Any
 
 if sys.version_info >= (3, 12):
     from collections.abc import Buffer
 else:
     from typing_extensions import Buffer
 
+if sys.version_info >= (3, 13):
+    from types import CapsuleType
+else:
+    from typing_extensions import CapsuleType
+
 class Array(Buffer):
     """A 3D array that implements Python's buffer protocol.
 
@@ -26,6 +32,16 @@ class Array(Buffer):
     assert np_arr.shape == (1, 2, 3)
     assert np_arr.dtype == np.uint8
     ```
+
+    The array also implements the DLPack protocol, so frameworks such as PyTorch or
+    JAX can consume it without numpy. Consumers supporting DLPack 1.0 share the
+    array's memory as a read-only tensor, while older consumers get a copy:
+
+    ```python
+    import torch
+
+    tensor = torch.from_dlpack(arr)
+    ```
     """
 
     # This is intended only for tests
@@ -33,6 +49,25 @@ class Array(Buffer):
     #     self, data: Buffer, shape: tuple[int, int, int], format: str
     # ) -> None: ...
     def __buffer__(self, flags: int) -> memoryview[int]: ...
+    def __dlpack__(
+        self,
+        *,
+        stream: int | Any | None = None,
+        max_version: tuple[int, int] | None = None,
+        dl_device: tuple[int, int] | None = None,
+        copy: bool | None = None,
+    ) -> CapsuleType:
+        """Export this array as a DLPack capsule.
+
+        If `max_version` is at least `(1, 0)`, the exported tensor shares memory with
+        this array, which is kept alive until the consumer releases the tensor, and is
+        flagged read-only. Older consumers can't be told that the memory is read-only,
+        so they get a copy, and passing `copy=False` without `max_version` raises a
+        `BufferError`. Passing `copy=True` always exports a writable copy. Only CPU
+        export is supported.
+        """
+    def __dlpack_device__(self) -> tuple[int, int]:
+        """The DLPack device of this array, which is always the CPU: `(1, 0)`."""
     @property
     def shape(self) -> tuple[int, int, int]:
         """The shape of the array.
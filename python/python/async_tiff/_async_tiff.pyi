@@ -1,19 +1,38 @@
 from ._array import Array
 from ._colormap import Colormap
 from ._decoder import DecoderRegistry
+from ._error import (
+    AsyncTiffError,
+    TiffDecodeError,
+    TiffFormatError,
+    TiffNotFoundError,
+    TiffUnsupportedError,
+)
+from ._extension import ExtensionRegistry
 from ._geo import GeoKeyDirectory
 from ._ifd import ImageFileDirectory
-from ._thread_pool import ThreadPool
+from ._metadata_cache import NoCache, Prefetch, Readahead
+from ._thread_pool import ThreadPool, set_decoder_threads
 from ._tiff import TIFF
 from ._tile import Tile
 
 __all__ = [
     "Array",
+    "AsyncTiffError",
     "Colormap",
     "DecoderRegistry",
+    "ExtensionRegistry",
     "GeoKeyDirectory",
     "ImageFileDirectory",
+    "NoCache",
+    "Prefetch",
+    "Readahead",
     "ThreadPool",
     "TIFF",
     "Tile",
+    "TiffDecodeError",
+    "TiffFormatError",
+    "TiffNotFoundError",
+    "TiffUnsupportedError",
+    "set_decoder_threads",
 ]
@@ -1,10 +1,16 @@
 from ._decoder_runtime import Decoder
-from .enums import Compression
+from .enums import Compression, PhotometricInterpretation
 
 class DecoderRegistry:
     """A registry holding multiple decoder methods."""
     def __init__(
-        self, custom_decoders: dict[Compression | int, Decoder] | None = None
+        self,
+        custom_decoders: dict[
+            Compression | int
+            | tuple[Compression | int, PhotometricInterpretation | int],
+            Decoder,
+        ]
+        | None = None,
     ) -> None:
         """Construct a new decoder registry.
 
@@ -14,6 +20,9 @@ class DecoderRegistry:
 
         Args:
             custom_decoders: any custom decoder methods to use. This will be applied
-                _after_ (and override) any default provided Rust decoders. Defaults to
-                None.
+                _after_ (and override) any default provided Rust decoders. Keys are
+                either a compression method, or a `(compression,
+                photometric_interpretation)` tuple to only use the decoder for tiles
+                with that photometric interpretation. Tuple keys take precedence over
+                compression-only keys. Defaults to None.
         """
@@ -0,0 +1,15 @@
+class AsyncTiffError(Exception):
+    """Base class of errors from the underlying Rust async-tiff library."""
+
+class TiffFormatError(AsyncTiffError):
+    """The file is not a valid TIFF, is truncated or corrupted, or exceeds size limits."""
+
+class TiffUnsupportedError(AsyncTiffError):
+    """The TIFF uses a feature, such as a compression or sample format, that isn't
+    supported."""
+
+class TiffDecodeError(AsyncTiffError):
+    """Compressed tile or strip data could not be decoded."""
+
+class TiffNotFoundError(AsyncTiffError, FileNotFoundError):
+    """The file does not exist in the store."""
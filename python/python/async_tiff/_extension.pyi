@@ -0,0 +1,37 @@
+from collections.abc import Callable, Sequence
+from typing import Any
+
+from ._ifd import Value
+
+class ExtensionRegistry:
+    """A registry of parsers for private or vendor-specific tags."""
+    def __init__(
+        self,
+        tag_parsers: dict[str, tuple[Sequence[int], Callable[[dict[int, Value]], Any]]]
+        | None = None,
+    ) -> None:
+        """Construct a new extension registry.
+
+        GeoTIFF tags are always parsed into the geospatial properties of
+        [`ImageFileDirectory`][async_tiff.ImageFileDirectory].
+
+        Examples:
+
+        ```py
+        def parse_vendor(tags: dict[int, Value]) -> dict:
+            return {"version": tags.get(65000), "name": tags.get(65001)}
+
+        registry = ExtensionRegistry({"vendor": ([65000, 65001], parse_vendor)})
+        tiff = await TIFF.open("path/to/image.tif", extensions=registry)
+        tiff.ifd(0).extensions["vendor"]
+        ```
+
+        Args:
+            tag_parsers: Parsers by name. Each parser is a sequence of tag ids and a
+                callback. The callback is called for every IFD containing at least one
+                of the tags, with the values of the tags present, keyed by tag id. Its
+                result is available from
+                [`ImageFileDirectory.extensions`][async_tiff.ImageFileDirectory.extensions]
+                under the parser's name, and the tags no longer appear in
+                `other_tags`. Defaults to None.
+        """
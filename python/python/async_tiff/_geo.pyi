@@ -1,6 +1,8 @@
 from collections.abc import Iterable
 from typing import Any
 
+from ._ifd import Value
+
 class GeoKeyDirectory:
     def keys(self) -> list[str]:
         """A list of string keys representing the GeoKey fields."""
@@ -9,6 +11,16 @@ class GeoKeyDirectory:
         """An iterable of string keys representing the GeoKey fields."""
     def __getitem__(self, key: str) -> Any:
         """Access GeoKey fields by string key."""
+    def crs(self) -> str:
+        """The coordinate reference system of these keys.
+
+        Returns `EPSG:<code>` when the CRS is part of the EPSG registry, and a PROJJSON
+        string for user-defined CRSs. Either can be passed to
+        `pyproj.CRS.from_user_input` or `rasterio.crs.CRS.from_user_input`.
+
+        Raises:
+            AsyncTiffError: if the CRS is user-defined in a way that isn't supported.
+        """
 
     @property
     def model_type(self) -> int | None: ...
@@ -100,3 +112,6 @@ class GeoKeyDirectory:
     def vertical_datum(self) -> int | None: ...
     @property
     def vertical_units(self) -> int | None: ...
+    @property
+    def other_geo_keys(self) -> dict[int, Value]:
+        """Geo keys that aren't part of the GeoTIFF spec, by key id."""
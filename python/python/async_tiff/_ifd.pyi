@@ -1,4 +1,4 @@
-from collections.abc import Iterable, Sequence
+from collections.abc import Buffer, Iterable, Sequence
 from typing import Any
 
 from ._colormap import Colormap
@@ -7,11 +7,14 @@ from ._tile import Tile
 from .enums import (
     Compression,
     ExtraSamples,
+    FillOrder,
+    Orientation,
     PhotometricInterpretation,
     PlanarConfiguration,
     Predictor,
     ResolutionUnit,
     SampleFormat,
+    Threshholding,
 )
 
 Value = int | float | str | tuple[int, int] | list[Value]
@@ -47,13 +50,28 @@ class ImageFileDirectory:
     @property
     def photometric_interpretation(self) -> PhotometricInterpretation: ...
     @property
+    def threshholding(self) -> Threshholding | None:
+        """The technique used to convert from gray to black and white pixels."""
+    @property
+    def fill_order(self) -> FillOrder | int | None:
+        """The logical order of bits within a byte.
+
+        An `int` will be returned if the fill order is not one of the values in
+        `FillOrder`.
+        """
+    @property
     def document_name(self) -> str | None: ...
     @property
     def image_description(self) -> str | None: ...
     @property
     def strip_offsets(self) -> list[int] | None: ...
     @property
-    def orientation(self) -> int | None: ...
+    def orientation(self) -> Orientation | int | None:
+        """The orientation of the image with respect to the rows and columns.
+
+        An `int` will be returned if the orientation is not one of the values in
+        `Orientation`.
+        """
     @property
     def samples_per_pixel(self) -> int:
         """
@@ -108,10 +126,25 @@ class ImageFileDirectory:
     @property
     def sample_format(self) -> list[SampleFormat]: ...
     @property
+    def data_type(self) -> str | None:
+        """The numpy dtype string of the decoded pixels, such as `"u1"` or `"f4"`.
+
+        Derived from `sample_format` and `bits_per_sample`. Unsigned samples with
+        different bit depths are widened to the smallest type fitting all of them. `None`
+        if the combination is not supported, or if samples have different formats.
+        Decoded data is always in native byte order.
+        """
+    @property
     def jpeg_tables(self) -> bytes | None: ...
     @property
+    def ycbcr_subsampling(self) -> tuple[int, int] | None:
+        """Horizontal and vertical chroma subsampling factors of YCbCr data."""
+    @property
     def copyright(self) -> str | None: ...
     @property
+    def xmp(self) -> str | None:
+        """The XMP metadata packet, decoded as UTF-8 with invalid sequences replaced."""
+    @property
     def geo_key_directory(self) -> GeoKeyDirectory | None: ...
     @property
     def model_pixel_scale(self) -> list[float] | None: ...
@@ -119,19 +152,92 @@ class ImageFileDirectory:
     def model_tiepoint(self) -> list[float] | None: ...
     @property
     def model_transformation(self) -> list[float] | None: ...
+    def geotransform(
+        self,
+    ) -> tuple[float, float, float, float, float, float] | None:
+        """The affine transform from pixel to model coordinates, in GDAL order.
+
+        Returns `(origin_x, pixel_width, row_rotation, origin_y, column_rotation,
+        pixel_height)`, derived from `model_transformation` if present and otherwise
+        from `model_tiepoint` and `model_pixel_scale`, or `None` if neither is
+        available.
+
+        Raises:
+            AsyncTiffError: if `model_transformation` can't be expressed as a 2D
+                affine transform.
+        """
+    def bounds(self) -> tuple[float, float, float, float] | None:
+        """The bounding box of the image in model coordinates.
+
+        Returns `(min_x, min_y, max_x, max_y)`, or `None` if there is no
+        [`geotransform`][async_tiff.ImageFileDirectory.geotransform].
+        """
+    def bounds_for_tile(self, x: int, y: int) -> tuple[float, float, float, float] | None:
+        """The bounding box of a tile in model coordinates.
+
+        Returns `(min_x, min_y, max_x, max_y)`, with tiles on the right and bottom
+        edges clipped to the image, or `None` if this isn't a tiled TIFF or there is
+        no [`geotransform`][async_tiff.ImageFileDirectory.geotransform].
+
+        Args:
+            x: The column index of the tile.
+            y: The row index of the tile.
+
+        Raises:
+            AsyncTiffError: if the tile index is out of range.
+        """
+    def tiles_in_bounds(
+        self, bbox: tuple[float, float, float, float]
+    ) -> list[tuple[int, int]] | None:
+        """The `(x, y)` indices of the tiles that intersect a bounding box.
+
+        Tiles are returned in row-major order, and tiles that only touch the edge of
+        the box aren't included. Returns `None` if this isn't a tiled TIFF or there is
+        no [`geotransform`][async_tiff.ImageFileDirectory.geotransform].
+
+        Args:
+            bbox: `(min_x, min_y, max_x, max_y)` in the image's native CRS.
+        """
     @property
     def gdal_nodata(self) -> str | None: ...
     @property
+    def nodata(self) -> int | float | bool | None:
+        """The GDAL NoData value parsed at the precision of this image's data type.
+
+        This is `None` if there is no nodata value or it can't be represented in the
+        data type, e.g. a negative value for an unsigned image.
+        """
+    @property
     def gdal_metadata(self) -> str | None: ...
     @property
     def other_tags(self) -> dict[int, Value]: ...
     @property
+    def extensions(self) -> dict[str, Any]:
+        """The results of the tag parsers of an
+        [`ExtensionRegistry`][async_tiff.ExtensionRegistry], by parser name.
+
+        Only parsers for which at least one tag is present in this IFD are included.
+        """
+    @property
+    def raw_tags(self) -> list[tuple[int, int, int, Buffer]]:
+        """Entries with a field type that can't be interpreted.
+
+        Each entry is `(tag, field_type, count, value)`, where `value` holds the raw
+        value/offset field of the entry in the file's byte order.
+        """
+    @property
     def lerc_parameters(self) -> list[int] | None:
         """The LERC parameters for LERC-compressed images."""
     @property
     def colormap(self) -> Colormap | None:
         """The colormap for palette-color images."""
         ...
+    @property
+    def transparent_index(self) -> int | None:
+        """The colormap entry representing nodata pixels.
+
+        This is the GDAL NoData value, if it is an integer indexing into the colormap.
+        """
     async def fetch_tile(self, x: int, y: int) -> Tile:
         """Fetch a single tile.
 
@@ -142,16 +248,63 @@ class ImageFileDirectory:
         Returns:
             Tile response.
         """
-    async def fetch_tiles(self, xy: Sequence[tuple[int, int]]) -> list[Tile]:
+    async def fetch_tiles(
+        self,
+        xy: Sequence[tuple[int, int]],
+        *,
+        concurrency: int | None = None,
+        coalesce: int | None = None,
+    ) -> list[Tile]:
         """Fetch multiple tiles concurrently.
 
         Args:
             xy: The (column, row) indexes within the ifd to read from.
+            concurrency: The maximum number of requests in flight. By default all
+                ranges are passed to the store at once, which decides how to parallelize
+                them.
+            coalesce: Merge tiles whose byte ranges are at most this many bytes apart
+                into a single request. By default tiles are requested separately.
 
         Returns:
             Tile responses.
         """
 
+    async def fetch_strip(self, y: int) -> Tile:
+        """Fetch a single strip of a stripped TIFF.
+
+        The strip covers the full image width. The last strip only covers the
+        remaining rows of the image.
+
+        Args:
+            y: The strip index within the ifd to read from.
+
+        Returns:
+            Tile response, which can be decoded like any other tile.
+        """
+    async def fetch_strips(
+        self,
+        start: int,
+        stop: int,
+        *,
+        concurrency: int | None = None,
+        coalesce: int | None = None,
+    ) -> list[Tile]:
+        """Fetch the strips with indices in `range(start, stop)` concurrently.
+
+        Args:
+            start: The first strip index to read.
+            stop: One past the last strip index to read.
+            concurrency: The maximum number of requests in flight.
+            coalesce: Merge strips whose byte ranges are at most this many bytes apart
+                into a single request.
+
+        Returns:
+            Tile responses.
+        """
+    @property
+    def strip_count(self) -> int | None:
+        """The number of strips per band, or `None` if this is not a stripped TIFF."""
+
     def tile_byte_range(
         self,
         x: int,
@@ -0,0 +1,52 @@
+class Prefetch:
+    """Fetch a fixed number of bytes up front when reading metadata.
+
+    Metadata outside of the prefetched bytes is fetched as needed, without reading
+    ahead. This suits files whose header size is known in advance, for example from
+    [`TIFF.header_byte_size`][async_tiff.TIFF.header_byte_size].
+    """
+    def __init__(self, size: int) -> None:
+        """Construct a new Prefetch strategy.
+
+        Args:
+            size: The number of bytes to fetch on the first metadata read.
+        """
+    @property
+    def size(self) -> int:
+        """The number of bytes fetched on the first metadata read."""
+
+class Readahead:
+    """Fetch metadata in exponentially growing blocks.
+
+    This is the default strategy. It keeps the number of requests low for files
+    with long chains of IFDs.
+    """
+    def __init__(
+        self,
+        initial: int | None = None,
+        multiplier: int | float | None = None,
+    ) -> None:
+        """Construct a new Readahead strategy.
+
+        Args:
+            initial: The number of bytes to fetch on the first metadata read. Defaults
+                to the `ASYNC_TIFF_PREFETCH` environment variable, or 32768.
+            multiplier: The growth factor of each subsequent fetch. Must be greater
+                than 1.0. Defaults to the `ASYNC_TIFF_PREFETCH_MULTIPLIER` environment
+                variable, or 2.0.
+        """
+    @property
+    def initial(self) -> int | None:
+        """The number of bytes fetched on the first metadata read, if set."""
+    @property
+    def multiplier(self) -> float | None:
+        """The growth factor of each subsequent fetch, if set."""
+
+class NoCache:
+    """Fetch exactly the bytes of each metadata read, without caching.
+
+    This makes one request per read, which is only sensible for stores with cheap
+    requests, such as local files or in-memory stores.
+    """
+    def __init__(self) -> None:
+        """Construct a new NoCache strategy."""
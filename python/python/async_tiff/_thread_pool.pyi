@@ -2,3 +2,15 @@ class ThreadPool:
     """A Rust-managed thread pool."""
     def __init__(self, num_threads: int) -> None:
         """Construct a new ThreadPool with the given number of threads."""
+
+def set_decoder_threads(num_threads: int) -> None:
+    """Set the number of threads of the default thread pool.
+
+    The default pool decodes tiles whenever no `pool` is passed, for example in
+    [`Tile.decode`][async_tiff.Tile.decode] or [`TIFF.read`][async_tiff.TIFF.read].
+    It is created with one thread per CPU core on first use. Decodes that are already
+    running finish on the previous pool.
+
+    Args:
+        num_threads: The number of threads. `0` uses one thread per CPU core.
+    """
@@ -1,31 +1,94 @@
-from typing import Sequence
+from typing import Any, Protocol, Sequence, TypedDict
 
+from ._array import Array
+from ._decoder import DecoderRegistry
+from ._extension import ExtensionRegistry
 from ._ifd import ImageFileDirectory
+from ._metadata_cache import NoCache, Prefetch, Readahead
 from ._input import ObspecInput
+from ._thread_pool import ThreadPool
 from ._tile import Tile
 from .enums import Endianness
 from .store import ObjectStore
 
+class WindowLike(Protocol):
+    """A pixel window, such as a `rasterio.windows.Window`."""
+
+    @property
+    def col_off(self) -> float: ...
+    @property
+    def row_off(self) -> float: ...
+    @property
+    def width(self) -> float: ...
+    @property
+    def height(self) -> float: ...
+
+class ReadStats(TypedDict):
+    requests: int
+    """The number of byte ranges requested."""
+    bytes: int
+    """The total number of bytes returned."""
+    seconds: float
+    """The total time spent waiting for requests. Concurrent requests each count
+    their own duration."""
+
+class RequestStats(TypedDict):
+    metadata: ReadStats
+    """Reads of header metadata while opening the file."""
+    image: ReadStats
+    """Reads of tiles and strips since the file was opened."""
+
 class TIFF:
     @classmethod
     async def open(
         cls,
         path: str,
         *,
-        store: ObjectStore | ObspecInput,
-        prefetch: int = 32768,
-        multiplier: int | float = 2.0,
+        store: ObjectStore | ObspecInput | None = None,
+        prefetch: int | None = None,
+        multiplier: int | float | None = None,
+        metadata_cache: Prefetch | Readahead | NoCache | None = None,
+        extensions: ExtensionRegistry | None = None,
+        **options: str | bool | int,
     ) -> TIFF:
         """Open a new TIFF.
 
+        Examples:
+
+        ```py
+        tiff = await TIFF.open("s3://bucket/path/to/image.tif", region="us-west-2")
+        tiff = await TIFF.open("path/to/local/image.tif")
+        ```
+
         Args:
-            path: The path within the store to read from.
-            store: The backend to use for data fetching.
-            prefetch: The number of initial bytes to read up front.
+            path: The path within the store to read from. If `store` is not provided,
+                a URL such as `s3://bucket/key.tif`, `https://example.com/image.tif`,
+                or a local file path, from which the store is inferred.
+            store: The backend to use for data fetching. If not provided, a store is
+                constructed from `path`. Requests to obspec-compatible backends that
+                are made concurrently are batched into a single `get_ranges_async`
+                call. If the backend also implements `head_async`, it is used to learn
+                the file size.
+            prefetch: The number of initial bytes to read up front. Defaults to the
+                `ASYNC_TIFF_PREFETCH` environment variable, or 32768.
             multiplier: The multiplier to use for readahead size growth. Must be
                 greater than 1.0. For example, for a value of `2.0`, the first metadata
                 read will be of size `prefetch`, and then the next read will be of size
-                `prefetch * 2`.
+                `prefetch * 2`. Defaults to the `ASYNC_TIFF_PREFETCH_MULTIPLIER`
+                environment variable, or 2.0.
+            metadata_cache: How metadata reads are fetched and cached, as a
+                [`Prefetch`][async_tiff.Prefetch],
+                [`Readahead`][async_tiff.Readahead] or
+                [`NoCache`][async_tiff.NoCache] strategy. Can't be combined with
+                `prefetch` or `multiplier`. Defaults to readahead using `prefetch`
+                and `multiplier`.
+            extensions: Parsers for private tags, whose results are available from
+                [`ImageFileDirectory.extensions`][async_tiff.ImageFileDirectory.extensions].
+                Defaults to None.
+            options: Configuration for the inferred store, such as credentials or
+                region, using the `object_store` configuration keys (for example
+                `aws_access_key_id` or `skip_signature`). Only allowed when `store` is
+                not provided.
 
         Returns:
             A TIFF instance.
@@ -56,6 +119,46 @@ class TIFF:
             The requested IFD.
         """
 
+    async def read_ifd_at(
+        self, offset: int
+    ) -> tuple[ImageFileDirectory, int | None]:
+        """Parse the IFD starting at an absolute byte offset in the file.
+
+        Only this IFD is read, without walking the chain of IFDs from the header, so
+        IFDs whose offsets are already known, e.g. from a previous manifest, can be
+        read directly. Private tags are parsed with the `extensions` the file was
+        opened with.
+
+        Examples:
+
+        ```py
+        tiff = await TIFF.open("path/to/image.tif")
+        ifd, next_offset = await tiff.read_ifd_at(8)
+        ```
+
+        Args:
+            offset: The byte offset of the IFD.
+
+        Returns:
+            The IFD and the offset of the IFD that follows it in the chain, or `None`
+            if it is the last IFD.
+        """
+
+    def request_stats(self) -> RequestStats:
+        """Statistics of the requests made to the store since this TIFF was opened.
+
+        Metadata reads while opening the file are reported separately from tile and
+        strip reads made through this TIFF or its IFDs.
+
+        Examples:
+
+        ```py
+        tiff = await TIFF.open("s3://bucket/path/to/image.tif", region="us-west-2")
+        await tiff.fetch_tile(0, 0, 0)
+        tiff.request_stats()["image"]["requests"]  # 1
+        ```
+        """
+
     @property
     def ifds(self) -> list[ImageFileDirectory]:
         """Access the underlying IFDs of this TIFF.
@@ -74,13 +177,106 @@ class TIFF:
         Returns:
             Tile response.
         """
-    async def fetch_tiles(self, xy: Sequence[tuple[int, int]], z: int) -> list[Tile]:
+    async def fetch_tiles(
+        self,
+        xy: Sequence[tuple[int, int]],
+        z: int,
+        *,
+        concurrency: int | None = None,
+        coalesce: int | None = None,
+    ) -> list[Tile]:
         """Fetch multiple tiles concurrently.
 
         Args:
             xy: The (column, row) indexes within the ifd to read from.
             z: The IFD index to read from.
+            concurrency: The maximum number of requests in flight. By default all
+                ranges are passed to the store at once, which decides how to parallelize
+                them.
+            coalesce: Merge tiles whose byte ranges are at most this many bytes apart
+                into a single request. By default tiles are requested separately.
 
         Returns:
             Tile responses.
         """
+    async def read_window(
+        self,
+        ifd_index: int,
+        col_off: int,
+        row_off: int,
+        width: int,
+        height: int,
+        *,
+        decoder_registry: DecoderRegistry | None = None,
+        pool: ThreadPool | None = None,
+    ) -> Array:
+        """Read a pixel window of an IFD at its native resolution.
+
+        The tiles or strips intersecting the window are fetched concurrently, decoded
+        in parallel on `pool`, and mosaicked into a single array.
+
+        Examples:
+
+        ```py
+        tiff = await TIFF.open("path/to/image.tif")
+        array = await tiff.read_window(0, 1024, 1024, 512, 512)
+        ```
+
+        Args:
+            ifd_index: The IFD index to read from.
+            col_off: The column of the left edge of the window.
+            row_off: The row of the top edge of the window.
+            width: The width of the window.
+            height: The height of the window.
+            decoder_registry: The decoders to use for decompressing tiles.
+            pool: The thread pool to decode tiles on.
+
+        Returns:
+            An array of shape `(height, width, bands)` for chunky images, or
+            `(bands, height, width)` for planar images.
+        """
+    async def read(
+        self,
+        indexes: int | Sequence[int] | None = None,
+        window: WindowLike | tuple[tuple[int, int], tuple[int, int]] | None = None,
+        out_shape: tuple[int, int] | tuple[int, int, int] | None = None,
+        resampling: str = "nearest",
+        masked: bool = False,
+        *,
+        decoder_registry: DecoderRegistry | None = None,
+        pool: ThreadPool | None = None,
+    ) -> Array | Any:
+        """Read a window of the image, similar to rasterio's `DatasetReader.read`.
+
+        When `out_shape` is smaller than the window, the coarsest overview with enough
+        resolution is read instead of the full-resolution image, so only the tiles
+        or strips needed for the output are fetched.
+
+        Examples:
+
+        ```py
+        from rasterio.windows import Window
+
+        tiff = await TIFF.open("s3://bucket/path/to/image.tif", region="us-west-2")
+        thumbnail = await tiff.read(indexes=[1, 2, 3], out_shape=(256, 256))
+        window = await tiff.read(window=Window(1024, 1024, 512, 512))
+        ```
+
+        Args:
+            indexes: 1-based band numbers to read. Defaults to all bands.
+            window: The window of the full-resolution image to read, either a
+                `rasterio.windows.Window` or `((row_start, row_stop), (col_start,
+                col_stop))`. Defaults to the whole image. The window is clipped to the
+                image bounds.
+            out_shape: The `(height, width)` or `(bands, height, width)` of the output.
+                Defaults to the size of the window.
+            resampling: The resampling method. Only `"nearest"` is currently supported.
+            masked: If `True`, return a `numpy.ma.MaskedArray` with pixels equal to the
+                GDAL nodata value masked. Requires numpy.
+            decoder_registry: The decoders to use for decompressing tiles.
+            pool: The thread pool to decode tiles on.
+
+        Returns:
+            An array of shape `(bands, height, width)`, or a masked numpy array if
+            `masked` is `True`.
+        """
@@ -1,12 +1,22 @@
-from collections.abc import Buffer
+from collections.abc import Buffer, Sequence
 
 from ._array import Array
 from ._decoder import DecoderRegistry
 from ._thread_pool import ThreadPool
-from .enums import Compression
+from .enums import Compression, Predictor
 
 class Tile:
-    """A representation of a TIFF image tile."""
+    """A representation of a TIFF image tile.
+
+    Tiles returned by `fetch_tile` and `fetch_tiles` hold the compressed bytes only,
+    so I/O and decoding can happen in different places. Tiles can be pickled, e.g. to
+    send them to Dask workers or a process pool and call `decode_sync` there:
+
+    ```py
+    tiles = await ifd.fetch_tiles([(0, 0), (1, 0)])
+    arrays = process_pool.map(lambda tile: tile.decode_sync(), tiles)
+    ```
+    """
     @property
     def x(self) -> int:
         """The column index this tile represents."""
@@ -23,29 +33,86 @@ class Tile:
     @property
     def compression_method(self) -> Compression | int:
         """The compression method used by this tile."""
+    @property
+    def predictor(self) -> Predictor | int:
+        """The predictor applied to this tile's samples before compression.
+
+        [`decode`][async_tiff.Tile.decode] reverses it automatically.
+        """
+    def serialize(self) -> bytes:
+        """Serialize this tile, including its compressed bytes and everything needed
+        to decode it.
+
+        The result can only be restored by the same version of async-tiff.
+        """
+    @staticmethod
+    def from_serialized(data: Buffer) -> Tile:
+        """Restore a tile created with [`serialize`][async_tiff.Tile.serialize]."""
     def decode_sync(
         self,
         *,
         decoder_registry: DecoderRegistry | None = None,
+        pool: ThreadPool | None = None,
+        unpack_bits: bool = False,
+        reverse_predictor: bool = True,
+        expand_colormap: bool = False,
+        bands: Sequence[int] | None = None,
     ) -> Array:
         """Decode this tile's data.
 
-        **Note**: This is a blocking function and will perform the tile decompression on
-        the current thread. Prefer using the asynchronous `decode` method, which will
-        offload decompression to a thread pool.
+        **Note**: This is a blocking function. Decompression runs on a thread pool
+        with the GIL released, so other Python threads can run or decode tiles
+        concurrently, but the calling thread waits for the result. Prefer using the
+        asynchronous `decode` method from async code.
 
         Keyword Args:
             decoder_registry: the decoders to use for decompression. Defaults to None, in which case a default decoder registry is used.
+            pool: the thread pool on which to run decompression. Defaults to None, in
+                which case, a default thread pool is used.
+            unpack_bits: expand unsigned samples of 1 to 7 bits to uint8 and of 9 to 15
+                bits, such as 12-bit data, to uint16. Defaults to False.
+            reverse_predictor: reverse the predictor of the image. Defaults to True.
+            expand_colormap: map the indices of palette images to 16-bit RGB through
+                their colormap. Defaults to False.
+            bands: only return the bands at these indices, in this order. Defaults to
+                None, which returns all bands.
 
         Returns:
             Decoded tile data as an Array instance.
         """
 
+    def decompress_sync(
+        self,
+        *,
+        decoder_registry: DecoderRegistry | None = None,
+        pool: ThreadPool | None = None,
+    ) -> Buffer:
+        """Decompress this tile's data without reversing the predictor.
+
+        The result holds the samples exactly as stored in the file, in the file's byte
+        order, with planar bands concatenated. Use this to handle the predictor yourself.
+
+        Like `decode_sync`, this blocks the calling thread but releases the GIL while
+        decompressing on a thread pool.
+
+        Keyword Args:
+            decoder_registry: the decoders to use for decompression. Defaults to None, in which case a default decoder registry is used.
+            pool: the thread pool on which to run decompression. Defaults to None, in
+                which case, a default thread pool is used.
+
+        Returns:
+            The decompressed bytes.
+        """
+
     async def decode(
         self,
         *,
         decoder_registry: DecoderRegistry | None = None,
         pool: ThreadPool | None = None,
+        unpack_bits: bool = False,
+        reverse_predictor: bool = True,
+        expand_colormap: bool = False,
+        bands: Sequence[int] | None = None,
     ) -> Array:
         """Decode this tile's data.
 
@@ -56,6 +123,13 @@ class Tile:
             decoder_registry: the decoders to use for decompression. Defaults to None, in which case a default decoder registry is used.
             pool: the thread pool on which to run decompression. Defaults to None, in
                 which case, a default thread pool is used.
+            unpack_bits: expand unsigned samples of 1 to 7 bits to uint8 and of 9 to 15
+                bits, such as 12-bit data, to uint16. Defaults to False.
+            reverse_predictor: reverse the predictor of the image. Defaults to True.
+            expand_colormap: map the indices of palette images to 16-bit RGB through
+                their colormap. Defaults to False.
+            bands: only return the bands at these indices, in this order. Defaults to
+                None, which returns all bands.
 
         Returns:
             Decoded tile data as an Array instance.
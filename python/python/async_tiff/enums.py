@@ -22,6 +22,8 @@ class Compression(IntEnum):
     WebP = 50001
     LZMA = 34925
     JPEG2k = 34712
+    SGILog = 34676
+    SGILog24 = 34677
     ZSTD = 0xC350
 
 
@@ -36,6 +38,22 @@ class ExtraSamples(IntEnum):
     UnassociatedAlpha = 2
 
 
+class FillOrder(IntEnum):
+    MsbToLsb = 1
+    LsbToMsb = 2
+
+
+class Orientation(IntEnum):
+    TopLeft = 1
+    TopRight = 2
+    BottomRight = 3
+    BottomLeft = 4
+    LeftTop = 5
+    RightTop = 6
+    RightBottom = 7
+    LeftBottom = 8
+
+
 class PhotometricInterpretation(IntEnum):
     WhiteIsZero = 0
     BlackIsZero = 1
@@ -45,6 +63,8 @@ class PhotometricInterpretation(IntEnum):
     CMYK = 5
     YCbCr = 6
     CIELab = 8
+    LogL = 32844
+    LogLuv = 32845
 
 
 class PlanarConfiguration(IntEnum):
@@ -69,3 +89,9 @@ class SampleFormat(IntEnum):
     Int = 2
     Float = 3
     Void = 4
+
+
+class Threshholding(IntEnum):
+    Bilevel = 1
+    Halftone = 2
+    ErrorDiffuse = 3
@@ -39,6 +39,7 @@ use pyo3::ffi;
 use pyo3::prelude::*;
 use pyo3_bytes::PyBytes;
 
+use crate::dlpack;
 use crate::error::PyAsyncTiffResult;
 
 /// Returns the numpy dtype type character for this data type.
@@ -50,8 +51,7 @@ use crate::error::PyAsyncTiffResult;
 ///
 /// Combined with endianness and size, this forms a complete dtype string
 /// like "<u2" (little-endian uint16) or ">f4" (big-endian float32).
-#[expect(unused)]
-fn data_type_to_numpy_char(dtype: &DataType) -> char {
+pub(crate) fn data_type_to_numpy_char(dtype: &DataType) -> char {
     match dtype {
         // Represented as uint8 in numpy
         DataType::Bool => 'u',
@@ -201,7 +201,14 @@ impl PyArray {
         let data_type = data_type.ok_or(PyValueError::new_err(
             "Unknown data types are not currently supported.",
         ))?;
+        Ok(Self::from_parts(typed_data, shape, data_type))
+    }
 
+    pub(crate) fn from_parts(
+        typed_data: TypedArray,
+        shape: [usize; 3],
+        data_type: DataType,
+    ) -> Self {
         let itemsize = data_type.size();
         let shape = [shape[0] as isize, shape[1] as isize, shape[2] as isize];
         // Row-major (C-contiguous) strides: [dim1 * dim2 * itemsize, dim2 * itemsize, itemsize]
@@ -210,12 +217,33 @@ impl PyArray {
             (shape[2] as usize * itemsize) as isize,
             itemsize as isize,
         ];
-        Ok(Self {
+        Self {
             data: typed_data,
             shape,
             strides,
             data_type,
-        })
+        }
+    }
+
+    pub(crate) fn data_type(&self) -> DataType {
+        self.data_type
+    }
+
+    pub(crate) fn data_ptr(&self) -> *mut std::ffi::c_void {
+        data_as_ptr(&self.data)
+    }
+
+    /// The array's data as bytes.
+    pub(crate) fn data(&self) -> &[u8] {
+        self.data.as_ref()
+    }
+
+    pub(crate) fn shape_array(&self) -> [isize; 3] {
+        self.shape
+    }
+
+    pub(crate) fn strides_array(&self) -> [isize; 3] {
+        self.strides
     }
 }
 
@@ -246,6 +274,26 @@ impl PyArray {
         (self.shape[0], self.shape[1], self.shape[2])
     }
 
+    /// Export this array through the DLPack protocol. See [`crate::dlpack`].
+    #[pyo3(signature = (*, stream=None, max_version=None, dl_device=None, copy=None))]
+    fn __dlpack__<'py>(
+        slf: &Bound<'py, Self>,
+        stream: Option<Bound<'py, PyAny>>,
+        max_version: Option<(u32, u32)>,
+        dl_device: Option<(i32, i32)>,
+        copy: Option<bool>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        // CPU arrays have no stream to synchronize
+        let _ = stream;
+        dlpack::check_request(dl_device)?;
+        let versioned = max_version.is_some_and(|(major, _)| major >= 1);
+        dlpack::to_capsule(slf, versioned, copy)
+    }
+
+    fn __dlpack_device__(&self) -> (i32, i32) {
+        (dlpack::DL_CPU, 0)
+    }
+
     /// Implements the buffer protocol's `__getbuffer__` method (PEP 3118).
     ///
     /// This is called when Python code requests a buffer view of this object,
@@ -1,9 +1,8 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
-use async_tiff::decoder::{Decoder, DecoderRegistry};
+use async_tiff::decoder::{DecodeContext, Decoder, DecoderRegistry};
 use async_tiff::error::{AsyncTiffError, AsyncTiffResult};
-use async_tiff::tags::PhotometricInterpretation;
 use bytes::Bytes;
 use pyo3::exceptions::PyTypeError;
 use pyo3::intern;
@@ -12,7 +11,7 @@ use pyo3::sync::PyOnceLock;
 use pyo3::types::{PyDict, PyTuple};
 use pyo3_bytes::PyBytes;
 
-use crate::enums::PyCompression;
+use crate::enums::{PyCompression, PyPhotometricInterpretation};
 
 static DEFAULT_DECODER_REGISTRY: PyOnceLock<Arc<DecoderRegistry>> = PyOnceLock::new();
 
@@ -30,18 +29,37 @@ pub(crate) struct PyDecoderRegistry(Arc<DecoderRegistry>);
 impl PyDecoderRegistry {
     #[new]
     #[pyo3(signature = (custom_decoders = None))]
-    pub(crate) fn new(custom_decoders: Option<HashMap<PyCompression, PyDecoder>>) -> Self {
+    pub(crate) fn new(custom_decoders: Option<HashMap<DecoderKey, PyDecoder>>) -> Self {
         let mut decoder_registry = DecoderRegistry::default();
         if let Some(custom_decoders) = custom_decoders {
-            for (compression, decoder) in custom_decoders.into_iter() {
-                decoder_registry
-                    .as_mut()
-                    .insert(compression.into(), Box::new(decoder));
+            for (key, decoder) in custom_decoders.into_iter() {
+                match key {
+                    DecoderKey::Compression(compression) => {
+                        decoder_registry
+                            .as_mut()
+                            .insert(compression.into(), Arc::new(decoder));
+                    }
+                    DecoderKey::Photometric(compression, photometric_interpretation) => {
+                        decoder_registry.insert_for_photometric(
+                            compression.into(),
+                            photometric_interpretation.into(),
+                            Arc::new(decoder),
+                        );
+                    }
+                }
             }
         }
         Self(Arc::new(decoder_registry))
     }
 }
+
+/// A key of `custom_decoders`: either a compression method or a
+/// `(compression, photometric_interpretation)` tuple.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPyObject)]
+pub(crate) enum DecoderKey {
+    Compression(PyCompression),
+    Photometric(PyCompression, PyPhotometricInterpretation),
+}
 impl PyDecoderRegistry {
     pub(crate) fn inner(&self) -> &Arc<DecoderRegistry> {
         &self.0
@@ -74,15 +92,7 @@ impl<'py> FromPyObject<'_, 'py> for PyDecoder {
 }
 
 impl Decoder for PyDecoder {
-    fn decode_tile(
-        &self,
-        buffer: Bytes,
-        _photometric_interpretation: PhotometricInterpretation,
-        _jpeg_tables: Option<&[u8]>,
-        _samples_per_pixel: u16,
-        _bits_per_sample: u16,
-        _lerc_parameters: Option<&[u32]>,
-    ) -> AsyncTiffResult<Vec<u8>> {
+    fn decode(&self, buffer: Bytes, _context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
         Python::attach(|py| self.call(py, buffer))
             .map_err(|err| AsyncTiffError::General(err.to_string()))
     }
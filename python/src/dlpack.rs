@@ -0,0 +1,297 @@
+//! Export of `PyArray` through the [DLPack] protocol.
+//!
+//! `__dlpack__` returns a `PyCapsule` wrapping a managed tensor. The array's data is immutable
+//! and may be shared, e.g. with a data cache, so consumers must not write to it:
+//!
+//! - Consumers that pass `max_version >= (1, 0)` get a `DLManagedTensorVersioned` in a capsule
+//!   named `"dltensor_versioned"`, which points directly at the array's data and is flagged
+//!   read-only. The tensor holds a strong reference to the `PyArray`, so the data stays alive
+//!   until the consumer calls the tensor's deleter.
+//! - Legacy consumers get an unversioned `DLManagedTensor` in a capsule named `"dltensor"`,
+//!   which can't be flagged read-only, so it points at a copy of the data owned by the tensor.
+//!   Requesting `copy=False` from a legacy consumer raises a `BufferError`.
+//!
+//! Passing `copy=True` always exports a writable copy.
+//!
+//! ## Capsule ownership
+//!
+//! A consumer takes ownership of the tensor by renaming the capsule to `"used_dltensor"` or
+//! `"used_dltensor_versioned"`, and then calls the deleter once it no longer needs the data. If
+//! the capsule is garbage collected without being consumed, its destructor calls the deleter
+//! instead.
+//!
+//! [DLPack]: https://dmlc.github.io/dlpack/latest/python_spec.html
+
+use std::ffi::{c_void, CStr};
+
+use async_tiff::DataType;
+use pyo3::exceptions::PyBufferError;
+use pyo3::ffi;
+use pyo3::prelude::*;
+
+use crate::array::PyArray;
+
+/// `kDLCPU` from `DLDeviceType`.
+pub(crate) const DL_CPU: i32 = 1;
+
+/// `DLPACK_FLAG_BITMASK_READ_ONLY`: the consumer must not write to the tensor.
+const FLAG_READ_ONLY: u64 = 1 << 0;
+/// `DLPACK_FLAG_BITMASK_IS_COPIED`: the tensor is a copy of the producer's data.
+const FLAG_IS_COPIED: u64 = 1 << 1;
+
+#[repr(C)]
+struct DLDevice {
+    device_type: i32,
+    device_id: i32,
+}
+
+#[repr(C)]
+struct DLDataType {
+    code: u8,
+    bits: u8,
+    lanes: u16,
+}
+
+#[repr(C)]
+struct DLTensor {
+    data: *mut c_void,
+    device: DLDevice,
+    ndim: i32,
+    dtype: DLDataType,
+    shape: *mut i64,
+    strides: *mut i64,
+    byte_offset: u64,
+}
+
+#[repr(C)]
+struct DLManagedTensor {
+    dl_tensor: DLTensor,
+    manager_ctx: *mut c_void,
+    deleter: Option<unsafe extern "C" fn(*mut DLManagedTensor)>,
+}
+
+#[repr(C)]
+struct DLPackVersion {
+    major: u32,
+    minor: u32,
+}
+
+#[repr(C)]
+struct DLManagedTensorVersioned {
+    version: DLPackVersion,
+    manager_ctx: *mut c_void,
+    deleter: Option<unsafe extern "C" fn(*mut DLManagedTensorVersioned)>,
+    flags: u64,
+    dl_tensor: DLTensor,
+}
+
+/// The two kinds of managed tensor, which differ in layout and capsule name.
+trait ManagedTensor: Sized {
+    /// The name of an unconsumed capsule holding this tensor.
+    const NAME: &'static CStr;
+
+    /// Wrap `dl_tensor` with `deleter`, setting `flags` if the tensor kind supports them.
+    fn new(dl_tensor: DLTensor, deleter: unsafe extern "C" fn(*mut Self), flags: u64) -> Self;
+
+    fn dl_tensor(&mut self) -> &mut DLTensor;
+
+    fn set_manager_ctx(&mut self, manager_ctx: *mut c_void);
+}
+
+impl ManagedTensor for DLManagedTensor {
+    const NAME: &'static CStr = c"dltensor";
+
+    fn new(dl_tensor: DLTensor, deleter: unsafe extern "C" fn(*mut Self), _flags: u64) -> Self {
+        Self {
+            dl_tensor,
+            manager_ctx: std::ptr::null_mut(),
+            deleter: Some(deleter),
+        }
+    }
+
+    fn dl_tensor(&mut self) -> &mut DLTensor {
+        &mut self.dl_tensor
+    }
+
+    fn set_manager_ctx(&mut self, manager_ctx: *mut c_void) {
+        self.manager_ctx = manager_ctx;
+    }
+}
+
+impl ManagedTensor for DLManagedTensorVersioned {
+    const NAME: &'static CStr = c"dltensor_versioned";
+
+    fn new(dl_tensor: DLTensor, deleter: unsafe extern "C" fn(*mut Self), flags: u64) -> Self {
+        Self {
+            version: DLPackVersion { major: 1, minor: 0 },
+            manager_ctx: std::ptr::null_mut(),
+            deleter: Some(deleter),
+            flags,
+            dl_tensor,
+        }
+    }
+
+    fn dl_tensor(&mut self) -> &mut DLTensor {
+        &mut self.dl_tensor
+    }
+
+    fn set_manager_ctx(&mut self, manager_ctx: *mut c_void) {
+        self.manager_ctx = manager_ctx;
+    }
+}
+
+/// What keeps the memory of an exported tensor alive.
+enum Owner {
+    /// The exported array, whose data the tensor points at.
+    Array(Py<PyArray>),
+    /// A copy of the array's data, as `u64`s so that every data type is aligned.
+    Copy(Vec<u64>),
+}
+
+/// A managed tensor together with the memory it points to.
+///
+/// `managed` is the first field of this `repr(C)` struct, so a pointer to the managed tensor is
+/// also a pointer to the `ExportedTensor`.
+#[repr(C)]
+struct ExportedTensor<M> {
+    managed: M,
+    shape: [i64; 3],
+    strides: [i64; 3],
+    owner: Owner,
+}
+
+/// The DLPack type code and bit width of `data_type`.
+fn dl_data_type(data_type: DataType) -> DLDataType {
+    // kDLInt = 0, kDLUInt = 1, kDLFloat = 2, kDLBool = 6
+    let code = match data_type {
+        DataType::Int8 | DataType::Int16 | DataType::Int32 | DataType::Int64 => 0,
+        DataType::UInt8 | DataType::UInt16 | DataType::UInt32 | DataType::UInt64 => 1,
+        DataType::Float32 | DataType::Float64 => 2,
+        DataType::Bool => 6,
+    };
+    DLDataType {
+        code,
+        bits: (data_type.size() * 8) as u8,
+        lanes: 1,
+    }
+}
+
+unsafe extern "C" fn delete_tensor<M: ManagedTensor>(managed: *mut M) {
+    // SAFETY: every managed tensor passed to a consumer is the first field of an
+    // ExportedTensor<M> allocated with Box in `export`, and the deleter is called once.
+    let tensor = unsafe { Box::from_raw(managed as *mut ExportedTensor<M>) };
+    // Releasing the reference to the array needs the GIL, which consumers may not hold
+    Python::attach(|_| drop(tensor));
+}
+
+unsafe extern "C" fn capsule_destructor<M: ManagedTensor>(capsule: *mut ffi::PyObject) {
+    // SAFETY: called by Python with a valid capsule while holding the GIL. A consumed capsule
+    // has been renamed, in which case the consumer is responsible for the tensor.
+    unsafe {
+        if ffi::PyCapsule_IsValid(capsule, M::NAME.as_ptr()) == 1 {
+            let managed = ffi::PyCapsule_GetPointer(capsule, M::NAME.as_ptr());
+            delete_tensor(managed as *mut M);
+        }
+    }
+}
+
+/// Export `array` as a DLPack capsule.
+///
+/// A versioned tensor shares the array's memory and is flagged read-only, unless `copy` is
+/// `Some(true)`. A legacy tensor can't be flagged read-only, so it always holds a copy, and
+/// `copy == Some(false)` is refused.
+pub(crate) fn to_capsule<'py>(
+    array: &Bound<'py, PyArray>,
+    versioned: bool,
+    copy: Option<bool>,
+) -> PyResult<Bound<'py, PyAny>> {
+    match (versioned, copy) {
+        (true, Some(true)) => export::<DLManagedTensorVersioned>(array, true, FLAG_IS_COPIED),
+        (true, _) => export::<DLManagedTensorVersioned>(array, false, FLAG_READ_ONLY),
+        (false, Some(false)) => Err(PyBufferError::new_err(
+            "Array can only be exported without copying as a read-only tensor, which requires \
+             a consumer supporting DLPack 1.0",
+        )),
+        (false, _) => export::<DLManagedTensor>(array, true, 0),
+    }
+}
+
+fn export<'py, M: ManagedTensor>(
+    array: &Bound<'py, PyArray>,
+    copy: bool,
+    flags: u64,
+) -> PyResult<Bound<'py, PyAny>> {
+    let py = array.py();
+    let inner = array.get();
+    let itemsize = inner.data_type().size() as i64;
+    let owner = if copy {
+        let data = inner.data();
+        let mut copied = vec![0u64; data.len().div_ceil(8)];
+        // SAFETY: copied has room for at least data.len() bytes and doesn't overlap data.
+        unsafe {
+            std::ptr::copy_nonoverlapping(data.as_ptr(), copied.as_mut_ptr() as *mut u8, data.len())
+        };
+        Owner::Copy(copied)
+    } else {
+        Owner::Array(array.clone().unbind())
+    };
+    let data = match &owner {
+        Owner::Array(array) => array.get().data_ptr(),
+        // The Vec's heap buffer doesn't move when the Vec is moved into the Box below
+        Owner::Copy(copied) => copied.as_ptr() as *mut c_void,
+    };
+    let dl_tensor = DLTensor {
+        data,
+        device: DLDevice {
+            device_type: DL_CPU,
+            device_id: 0,
+        },
+        ndim: 3,
+        dtype: dl_data_type(inner.data_type()),
+        shape: std::ptr::null_mut(),
+        strides: std::ptr::null_mut(),
+        byte_offset: 0,
+    };
+    let mut tensor = Box::new(ExportedTensor {
+        managed: M::new(dl_tensor, delete_tensor::<M>, flags),
+        shape: inner.shape_array().map(|d| d as i64),
+        // DLPack strides are in elements rather than bytes
+        strides: inner.strides_array().map(|s| s as i64 / itemsize),
+        owner,
+    });
+    // The Box keeps its heap address when converted into a raw pointer below
+    tensor.managed.dl_tensor().shape = tensor.shape.as_mut_ptr();
+    tensor.managed.dl_tensor().strides = tensor.strides.as_mut_ptr();
+    let raw = Box::into_raw(tensor);
+    // SAFETY: raw points to a live, fully initialized ExportedTensor.
+    unsafe {
+        (*raw).managed.set_manager_ctx(raw as *mut c_void);
+    }
+
+    // SAFETY: the capsule takes ownership of raw, which is freed either by the consumer through
+    // the deleter, or by the capsule destructor if it is never consumed.
+    let capsule = unsafe {
+        ffi::PyCapsule_New(
+            raw as *mut c_void,
+            M::NAME.as_ptr(),
+            Some(capsule_destructor::<M>),
+        )
+    };
+    if capsule.is_null() {
+        // SAFETY: the capsule wasn't created, so raw is still exclusively owned here.
+        unsafe { delete_tensor(raw as *mut M) };
+        return Err(PyErr::fetch(py));
+    }
+    // SAFETY: PyCapsule_New returned a new, non-null reference.
+    Ok(unsafe { Bound::from_owned_ptr(py, capsule) })
+}
+
+/// Check the arguments of `__dlpack__` that this CPU-only exporter can't honour.
+pub(crate) fn check_request(dl_device: Option<(i32, i32)>) -> PyResult<()> {
+    if dl_device.is_some_and(|device| device != (DL_CPU, 0)) {
+        return Err(PyBufferError::new_err(
+            "Array can only be exported to the CPU",
+        ));
+    }
+    Ok(())
+}
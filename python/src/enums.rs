@@ -1,8 +1,9 @@
 use async_tiff::reader::Endianness;
 use async_tiff::tags::{
-    Compression, ExtraSamples, PhotometricInterpretation, PlanarConfiguration, Predictor,
-    ResolutionUnit, SampleFormat,
+    Compression, ExtraSamples, FillOrder, Orientation, PhotometricInterpretation,
+    PlanarConfiguration, Predictor, ResolutionUnit, SampleFormat, Threshholding,
 };
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::types::{PyString, PyTuple};
 use pyo3::{intern, IntoPyObjectExt};
@@ -82,6 +83,7 @@ impl<'py> IntoPyObject<'py> for PyExtraSamples {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub(crate) struct PyPhotometricInterpretation(PhotometricInterpretation);
 
 impl From<PhotometricInterpretation> for PyPhotometricInterpretation {
@@ -90,6 +92,25 @@ impl From<PhotometricInterpretation> for PyPhotometricInterpretation {
     }
 }
 
+impl From<PyPhotometricInterpretation> for PhotometricInterpretation {
+    fn from(value: PyPhotometricInterpretation) -> Self {
+        value.0
+    }
+}
+
+impl<'py> FromPyObject<'_, 'py> for PyPhotometricInterpretation {
+    type Error = PyErr;
+
+    fn extract(obj: Borrowed<'_, 'py, PyAny>) -> Result<Self, Self::Error> {
+        let value = obj.extract()?;
+        PhotometricInterpretation::from_u16(value)
+            .map(Self)
+            .ok_or_else(|| {
+                PyValueError::new_err(format!("Unknown photometric interpretation: {value}"))
+            })
+    }
+}
+
 impl<'py> IntoPyObject<'py> for PyPhotometricInterpretation {
     type Target = PyAny;
     type Output = Bound<'py, PyAny>;
@@ -176,6 +197,60 @@ impl<'py> IntoPyObject<'py> for PySampleFormat {
     }
 }
 
+pub(crate) struct PyFillOrder(FillOrder);
+
+impl From<FillOrder> for PyFillOrder {
+    fn from(value: FillOrder) -> Self {
+        Self(value)
+    }
+}
+
+impl<'py> IntoPyObject<'py> for PyFillOrder {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        to_py_enum_variant(py, intern!(py, "FillOrder"), self.0.to_u16())
+    }
+}
+
+pub(crate) struct PyOrientation(Orientation);
+
+impl From<Orientation> for PyOrientation {
+    fn from(value: Orientation) -> Self {
+        Self(value)
+    }
+}
+
+impl<'py> IntoPyObject<'py> for PyOrientation {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        to_py_enum_variant(py, intern!(py, "Orientation"), self.0.to_u16())
+    }
+}
+
+pub(crate) struct PyThreshholding(Threshholding);
+
+impl From<Threshholding> for PyThreshholding {
+    fn from(value: Threshholding) -> Self {
+        Self(value)
+    }
+}
+
+impl<'py> IntoPyObject<'py> for PyThreshholding {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        to_py_enum_variant(py, intern!(py, "Threshholding"), self.0.to_u16())
+    }
+}
+
 fn to_py_enum_variant<'py>(
     py: Python<'py>,
     enum_name: &Bound<'py, PyString>,
@@ -1,14 +1,78 @@
-use async_tiff::error::AsyncTiffError;
+use async_tiff::error::{self, TiffError};
 use pyo3::create_exception;
-use pyo3::exceptions::PyFileNotFoundError;
+use pyo3::exceptions::{PyException, PyFileNotFoundError};
+use pyo3::intern;
 use pyo3::prelude::*;
+use pyo3::sync::PyOnceLock;
+use pyo3::types::{PyDict, PyTuple, PyType};
 
 create_exception!(
     async_tiff,
-    AsyncTiffException,
-    pyo3::exceptions::PyException,
-    "A general error from the underlying Rust async-tiff library."
+    AsyncTiffError,
+    PyException,
+    "Base class of errors from the underlying Rust async-tiff library."
 );
+create_exception!(
+    async_tiff,
+    TiffFormatError,
+    AsyncTiffError,
+    "The file is not a valid TIFF, is truncated or corrupted, or exceeds size limits."
+);
+create_exception!(
+    async_tiff,
+    TiffUnsupportedError,
+    AsyncTiffError,
+    "The TIFF uses a feature, such as a compression or sample format, that isn't supported."
+);
+create_exception!(
+    async_tiff,
+    TiffDecodeError,
+    AsyncTiffError,
+    "Compressed tile or strip data could not be decoded."
+);
+
+static TIFF_NOT_FOUND_ERROR: PyOnceLock<Py<PyType>> = PyOnceLock::new();
+
+/// The `TiffNotFoundError` exception type.
+///
+/// This derives from both `AsyncTiffError` and the builtin `FileNotFoundError`, which
+/// `create_exception!` can't express, so the type is created with `type()` instead.
+pub(crate) fn tiff_not_found_error(py: Python<'_>) -> PyResult<&Bound<'_, PyType>> {
+    TIFF_NOT_FOUND_ERROR
+        .get_or_try_init(py, || {
+            let bases = PyTuple::new(
+                py,
+                [
+                    py.get_type::<AsyncTiffError>(),
+                    py.get_type::<PyFileNotFoundError>(),
+                ],
+            )?;
+            let dict = PyDict::new(py);
+            dict.set_item(intern!(py, "__module__"), "async_tiff")?;
+            dict.set_item(
+                intern!(py, "__doc__"),
+                "The file does not exist in the store.",
+            )?;
+            let cls = py
+                .get_type::<PyType>()
+                .call1(("TiffNotFoundError", bases, dict))?;
+            Ok::<_, PyErr>(cls.cast_into::<PyType>()?.unbind())
+        })
+        .map(|cls| cls.bind(py))
+}
+
+/// Register the exception types on the module `m`.
+pub(crate) fn register_exceptions(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
+    m.add("AsyncTiffError", py.get_type::<AsyncTiffError>())?;
+    m.add("TiffFormatError", py.get_type::<TiffFormatError>())?;
+    m.add(
+        "TiffUnsupportedError",
+        py.get_type::<TiffUnsupportedError>(),
+    )?;
+    m.add("TiffDecodeError", py.get_type::<TiffDecodeError>())?;
+    m.add("TiffNotFoundError", tiff_not_found_error(py)?)?;
+    Ok(())
+}
 
 #[allow(missing_docs)]
 pub enum PyAsyncTiffError {
@@ -16,8 +80,8 @@ pub enum PyAsyncTiffError {
     PyErr(PyErr),
 }
 
-impl From<AsyncTiffError> for PyAsyncTiffError {
-    fn from(value: AsyncTiffError) -> Self {
+impl From<error::AsyncTiffError> for PyAsyncTiffError {
+    fn from(value: error::AsyncTiffError) -> Self {
         Self::AsyncTiffError(value)
     }
 }
@@ -28,19 +92,55 @@ impl From<PyErr> for PyAsyncTiffError {
     }
 }
 
+/// Raise `message` as a `TiffNotFoundError`.
+fn not_found(message: String) -> PyErr {
+    Python::attach(|py| match tiff_not_found_error(py) {
+        Ok(cls) => PyErr::from_type(cls.clone(), message),
+        Err(err) => err,
+    })
+}
+
 impl From<PyAsyncTiffError> for PyErr {
     fn from(error: PyAsyncTiffError) -> Self {
-        match error {
-            PyAsyncTiffError::AsyncTiffError(err) => match err {
-                AsyncTiffError::ObjectStore(err) => match err {
-                    object_store::Error::NotFound { path: _, source: _ } => {
-                        PyFileNotFoundError::new_err(err.to_string())
-                    }
-                    _ => AsyncTiffException::new_err(err.to_string()),
-                },
-                _ => AsyncTiffException::new_err(err.to_string()),
+        let err = match error {
+            PyAsyncTiffError::AsyncTiffError(err) => err,
+            PyAsyncTiffError::PyErr(err) => return err,
+        };
+        let message = err.to_string();
+        match err {
+            error::AsyncTiffError::ObjectStore(object_store::Error::NotFound { .. }) => {
+                not_found(message)
+            }
+            error::AsyncTiffError::IOError(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                not_found(message)
+            }
+            error::AsyncTiffError::InternalTIFFError(TiffError::IoError(err))
+                if err.kind() == std::io::ErrorKind::NotFound =>
+            {
+                not_found(message)
+            }
+            error::AsyncTiffError::EndOfFile(..)
+            | error::AsyncTiffError::LimitExceeded { .. }
+            | error::AsyncTiffError::InvalidByteRange { .. }
+            | error::AsyncTiffError::InternalTIFFError(
+                TiffError::FormatError(_) | TiffError::IntSizeError,
+            ) => TiffFormatError::new_err(message),
+            error::AsyncTiffError::InternalTIFFError(TiffError::UnsupportedError(_)) => {
+                TiffUnsupportedError::new_err(message)
+            }
+            error::AsyncTiffError::JPEGDecodingError(_)
+            | error::AsyncTiffError::JPEG2kDecodingError(_)
+            | error::AsyncTiffError::LZWDecodingError(_)
+            | error::AsyncTiffError::TileDecodingError { .. }
+            | error::AsyncTiffError::DecodedSizeMismatch { .. } => {
+                TiffDecodeError::new_err(message)
+            }
+            // Errors raised by Python readers, such as obspec backends, are re-raised unchanged
+            error::AsyncTiffError::External(err) => match err.downcast::<PyErr>() {
+                Ok(err) => *err,
+                Err(_) => AsyncTiffError::new_err(message),
             },
-            PyAsyncTiffError::PyErr(err) => err,
+            _ => AsyncTiffError::new_err(message),
         }
     }
 }
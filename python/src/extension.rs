@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_tiff::error::{AsyncTiffError, AsyncTiffResult};
+use async_tiff::extension::{ExtensionRegistry, TiffExtension, TiffExtensionFactory};
+use async_tiff::metadata::ParseOptions;
+use async_tiff::tags::Tag;
+use async_tiff::TagValue;
+use pyo3::exceptions::PyTypeError;
+use pyo3::intern;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::value::PyValue;
+
+#[pyclass(name = "ExtensionRegistry", frozen)]
+#[derive(Debug, Default)]
+pub(crate) struct PyExtensionRegistry(ExtensionRegistry);
+
+#[pymethods]
+impl PyExtensionRegistry {
+    #[new]
+    #[pyo3(signature = (tag_parsers = None))]
+    fn new(tag_parsers: Option<HashMap<String, (Vec<u16>, PyTagCallback)>>) -> Self {
+        let mut registry = ExtensionRegistry::default();
+        for (name, (tags, callback)) in tag_parsers.into_iter().flatten() {
+            registry.register(Arc::new(PyTagParser {
+                name,
+                tags: tags.into_iter().map(Tag::from_u16_exhaustive).collect(),
+                callback,
+            }));
+        }
+        Self(registry)
+    }
+}
+
+impl PyExtensionRegistry {
+    pub(crate) fn parse_options(&self) -> ParseOptions {
+        ParseOptions::new().with_extensions(self.0.clone())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct PyTagCallback(Py<PyAny>);
+
+impl<'py> FromPyObject<'_, 'py> for PyTagCallback {
+    type Error = PyErr;
+
+    fn extract(obj: Borrowed<'_, 'py, PyAny>) -> Result<Self, Self::Error> {
+        if !obj.hasattr(intern!(obj.py(), "__call__"))? {
+            return Err(PyTypeError::new_err(
+                "Expected callable object for tag parser.",
+            ));
+        }
+        Ok(Self(obj.as_unbound().clone_ref(obj.py())))
+    }
+}
+
+/// An extension calling a Python function with the claimed tags of an IFD.
+#[derive(Debug)]
+struct PyTagParser {
+    name: String,
+    tags: Vec<Tag>,
+    callback: PyTagCallback,
+}
+
+impl PyTagParser {
+    fn call(&self, py: Python, tags: HashMap<Tag, TagValue>) -> PyResult<Py<PyAny>> {
+        let dict = PyDict::new(py);
+        for (tag, value) in tags {
+            dict.set_item(tag.to_u16(), PyValue::from(value))?;
+        }
+        self.callback.0.call1(py, (dict,))
+    }
+}
+
+impl TiffExtensionFactory for PyTagParser {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn tags(&self) -> &[Tag] {
+        &self.tags
+    }
+
+    fn parse_tags(
+        &self,
+        tags: HashMap<Tag, TagValue>,
+        _options: &ParseOptions,
+        _warnings: &mut Vec<String>,
+    ) -> AsyncTiffResult<Arc<dyn TiffExtension>> {
+        let value = Python::attach(|py| self.call(py, tags))
+            .map_err(|err| AsyncTiffError::General(err.to_string()))?;
+        Ok(Arc::new(PyExtensionValue(value)))
+    }
+}
+
+/// The object returned by a Python tag parser.
+#[derive(Debug)]
+pub(crate) struct PyExtensionValue(Py<PyAny>);
+
+impl PyExtensionValue {
+    pub(crate) fn value(&self, py: Python) -> Py<PyAny> {
+        self.0.clone_ref(py)
+    }
+}
+
+impl PartialEq for PyExtensionValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_ptr() == other.0.as_ptr()
+    }
+}
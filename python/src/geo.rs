@@ -1,9 +1,16 @@
+use std::collections::HashMap;
+
 use async_tiff::geo::GeoKeyDirectory;
+use async_tiff::TagValue;
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use pyo3::IntoPyObjectExt;
 
-#[pyclass(name = "GeoKeyDirectory", frozen, eq, get_all)]
-#[derive(PartialEq)]
+use crate::error::PyAsyncTiffResult;
+use crate::value::PyValue;
+
+#[pyclass(name = "GeoKeyDirectory", frozen, eq, get_all, skip_from_py_object)]
+#[derive(Clone, PartialEq)]
 pub(crate) struct PyGeoKeyDirectory {
     model_type: Option<u16>,
     raster_type: Option<u16>,
@@ -52,10 +59,35 @@ pub(crate) struct PyGeoKeyDirectory {
     vertical_citation: Option<String>,
     vertical_datum: Option<u16>,
     vertical_units: Option<u16>,
+
+    other_geo_keys: PyOtherGeoKeys,
+}
+
+/// Geo keys that aren't part of the GeoTIFF spec, exposed to Python as a dict by key id.
+#[derive(Clone, PartialEq)]
+pub(crate) struct PyOtherGeoKeys(HashMap<u16, TagValue>);
+
+impl<'py> IntoPyObject<'py> for &PyOtherGeoKeys {
+    type Target = PyDict;
+    type Output = Bound<'py, Self::Target>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> Result<Self::Output, Self::Error> {
+        let dict = PyDict::new(py);
+        for (key, value) in &self.0 {
+            dict.set_item(key, PyValue::from(value.clone()))?;
+        }
+        Ok(dict)
+    }
 }
 
 #[pymethods]
 impl PyGeoKeyDirectory {
+    /// The CRS as `EPSG:<code>` or as a PROJJSON string.
+    fn crs(&self) -> PyAsyncTiffResult<String> {
+        Ok(GeoKeyDirectory::from(self.clone()).crs()?.to_string())
+    }
+
     /// This exists to implement the Mapping protocol, so we support `dict(gkd)`.`
     fn keys(&self) -> Vec<&'static str> {
         let mut keys = vec![];
@@ -194,6 +226,9 @@ impl PyGeoKeyDirectory {
         if self.vertical_units.is_some() {
             keys.push("vertical_units");
         }
+        if !self.other_geo_keys.0.is_empty() {
+            keys.push("other_geo_keys");
+        }
 
         keys
     }
@@ -254,6 +289,7 @@ impl PyGeoKeyDirectory {
             "vertical_citation" => self.vertical_citation.as_ref().into_bound_py_any(py),
             "vertical_datum" => self.vertical_datum.into_bound_py_any(py),
             "vertical_units" => self.vertical_units.into_bound_py_any(py),
+            "other_geo_keys" => self.other_geo_keys.into_bound_py_any(py),
             _ => Err(pyo3::exceptions::PyKeyError::new_err(format!(
                 "Unknown IFD property: {}",
                 key
@@ -310,6 +346,7 @@ impl From<PyGeoKeyDirectory> for GeoKeyDirectory {
             vertical_citation: value.vertical_citation,
             vertical_datum: value.vertical_datum,
             vertical_units: value.vertical_units,
+            other_geo_keys: value.other_geo_keys.0,
         }
     }
 }
@@ -362,6 +399,7 @@ impl From<GeoKeyDirectory> for PyGeoKeyDirectory {
             vertical_citation: value.vertical_citation,
             vertical_datum: value.vertical_datum,
             vertical_units: value.vertical_units,
+            other_geo_keys: PyOtherGeoKeys(value.other_geo_keys),
         }
     }
 }
@@ -1,23 +1,30 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use async_tiff::config::AsyncTiffConfig;
 use async_tiff::reader::AsyncFileReader;
-use async_tiff::{ImageFileDirectory, TileByteRange};
-use pyo3::exceptions::{PyTypeError, PyValueError};
+use async_tiff::{FetchOptions, ImageFileDirectory, Nodata, TileByteRange};
+use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
 use pyo3::IntoPyObjectExt;
 use pyo3_async_runtimes::tokio::future_into_py;
+use pyo3_bytes::PyBytes;
 
+use crate::array::data_type_to_numpy_char;
 use crate::colormap::PyColormap;
 use crate::enums::{
-    PyCompression, PyExtraSamples, PyPhotometricInterpretation, PyPlanarConfiguration, PyPredictor,
-    PyResolutionUnit, PySampleFormat,
+    PyCompression, PyExtraSamples, PyFillOrder, PyOrientation, PyPhotometricInterpretation,
+    PyPlanarConfiguration, PyPredictor, PyResolutionUnit, PySampleFormat, PyThreshholding,
 };
-use crate::error::PyAsyncTiffResult;
+use crate::error::{PyAsyncTiffError, PyAsyncTiffResult};
+use crate::extension::PyExtensionValue;
 use crate::geo::PyGeoKeyDirectory;
 use crate::tile::PyTile;
 use crate::value::PyValue;
 
+/// A GDAL-ordered geotransform, returned to Python as a tuple.
+type GeoTransform = (f64, f64, f64, f64, f64, f64);
+
 #[pyclass(name = "ImageFileDirectory", frozen, eq, skip_from_py_object)]
 #[derive(Debug, Clone)]
 pub(crate) struct PyImageFileDirectory {
@@ -68,6 +75,16 @@ impl PyImageFileDirectory {
         self.ifd.photometric_interpretation().into()
     }
 
+    #[getter]
+    pub fn threshholding(&self) -> Option<PyThreshholding> {
+        self.ifd.threshholding().map(|x| x.into())
+    }
+
+    #[getter]
+    pub fn fill_order(&self) -> Option<PyFillOrder> {
+        self.ifd.fill_order().map(|x| x.into())
+    }
+
     #[getter]
     pub fn document_name(&self) -> Option<&str> {
         self.ifd.document_name()
@@ -84,8 +101,8 @@ impl PyImageFileDirectory {
     }
 
     #[getter]
-    pub fn orientation(&self) -> Option<u16> {
-        self.ifd.orientation()
+    pub fn orientation(&self) -> Option<PyOrientation> {
+        self.ifd.orientation().map(|x| x.into())
     }
 
     /// The number of components per pixel.
@@ -219,16 +236,38 @@ impl PyImageFileDirectory {
             .collect()
     }
 
+    #[getter]
+    pub fn data_type(&self) -> Option<String> {
+        let data_type = self.ifd.data_type()?;
+        Some(format!(
+            "{}{}",
+            data_type_to_numpy_char(&data_type),
+            data_type.size()
+        ))
+    }
+
     #[getter]
     pub fn jpeg_tables(&self) -> Option<&[u8]> {
         self.ifd.jpeg_tables()
     }
 
+    #[getter]
+    pub fn ycbcr_subsampling(&self) -> Option<(u16, u16)> {
+        self.ifd
+            .ycbcr_subsampling()
+            .map(|[horizontal, vertical]| (horizontal, vertical))
+    }
+
     #[getter]
     pub fn copyright(&self) -> Option<&str> {
         self.ifd.copyright()
     }
 
+    #[getter]
+    pub fn xmp(&self) -> Option<&str> {
+        self.ifd.xmp()
+    }
+
     // Geospatial tags
     #[getter]
     pub fn geo_key_directory(&self) -> Option<PyGeoKeyDirectory> {
@@ -250,11 +289,68 @@ impl PyImageFileDirectory {
         self.ifd.model_transformation()
     }
 
+    fn geotransform(&self) -> PyAsyncTiffResult<Option<GeoTransform>> {
+        let Some(geotransform) = self.ifd.geotransform() else {
+            return Ok(None);
+        };
+        let [a, b, c, d, e, f] = geotransform?;
+        Ok(Some((a, b, c, d, e, f)))
+    }
+
+    fn bounds(&self) -> PyAsyncTiffResult<Option<(f64, f64, f64, f64)>> {
+        let Some(bounds) = self.ifd.native_bounds() else {
+            return Ok(None);
+        };
+        let [min_x, min_y, max_x, max_y] = bounds?;
+        Ok(Some((min_x, min_y, max_x, max_y)))
+    }
+
+    fn bounds_for_tile(
+        &self,
+        x: usize,
+        y: usize,
+    ) -> PyAsyncTiffResult<Option<(f64, f64, f64, f64)>> {
+        let Some(bounds) = self.ifd.bounds_for_tile(x, y) else {
+            return Ok(None);
+        };
+        let [min_x, min_y, max_x, max_y] = bounds?;
+        Ok(Some((min_x, min_y, max_x, max_y)))
+    }
+
+    fn tiles_in_bounds(
+        &self,
+        bbox: (f64, f64, f64, f64),
+    ) -> PyAsyncTiffResult<Option<Vec<(usize, usize)>>> {
+        let (min_x, min_y, max_x, max_y) = bbox;
+        let Some(tiles) = self.ifd.tiles_in_bounds([min_x, min_y, max_x, max_y]) else {
+            return Ok(None);
+        };
+        Ok(Some(tiles?))
+    }
+
     #[getter]
     pub fn gdal_nodata(&self) -> Option<&str> {
         self.ifd.gdal_nodata()
     }
 
+    #[getter]
+    fn nodata<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        match self.ifd.nodata() {
+            None => Ok(py.None().into_bound(py)),
+            Some(Nodata::Bool(v)) => v.into_bound_py_any(py),
+            Some(Nodata::UInt8(v)) => v.into_bound_py_any(py),
+            Some(Nodata::UInt16(v)) => v.into_bound_py_any(py),
+            Some(Nodata::UInt32(v)) => v.into_bound_py_any(py),
+            Some(Nodata::UInt64(v)) => v.into_bound_py_any(py),
+            Some(Nodata::Int8(v)) => v.into_bound_py_any(py),
+            Some(Nodata::Int16(v)) => v.into_bound_py_any(py),
+            Some(Nodata::Int32(v)) => v.into_bound_py_any(py),
+            Some(Nodata::Int64(v)) => v.into_bound_py_any(py),
+            Some(Nodata::Float32(v)) => v.into_bound_py_any(py),
+            Some(Nodata::Float64(v)) => v.into_bound_py_any(py),
+        }
+    }
+
     #[getter]
     pub fn gdal_metadata(&self) -> Option<&str> {
         self.ifd.gdal_metadata()
@@ -270,6 +366,34 @@ impl PyImageFileDirectory {
         HashMap::from_iter(iter)
     }
 
+    #[getter]
+    pub fn extensions(&self, py: Python) -> HashMap<String, Py<PyAny>> {
+        self.ifd
+            .extensions()
+            .iter()
+            .filter_map(|(name, extension)| {
+                let value = extension.as_any().downcast_ref::<PyExtensionValue>()?;
+                Some((name.clone(), value.value(py)))
+            })
+            .collect()
+    }
+
+    #[getter]
+    fn raw_tags(&self) -> Vec<(u16, u16, u64, PyBytes)> {
+        self.ifd
+            .raw_tags()
+            .iter()
+            .map(|raw| {
+                (
+                    raw.tag.to_u16(),
+                    raw.field_type,
+                    raw.count,
+                    PyBytes::new(raw.value.clone()),
+                )
+            })
+            .collect()
+    }
+
     #[getter]
     pub fn lerc_parameters(&self) -> Option<&[u32]> {
         self.ifd.lerc_parameters()
@@ -280,6 +404,11 @@ impl PyImageFileDirectory {
         self.ifd.colormap().map(|c| PyColormap::new(c.clone()))
     }
 
+    #[getter]
+    pub fn transparent_index(&self) -> Option<usize> {
+        self.ifd.transparent_index()
+    }
+
     /// This exists to implement the Mapping protocol, so we support `dict(ifd)`.`
     fn keys(&self) -> Vec<&'static str> {
         // Always present keys
@@ -293,12 +422,19 @@ impl PyImageFileDirectory {
             "planar_configuration",
             "sample_format",
             "other_tags",
+            "extensions",
         ];
 
         // Optional keys
         if self.new_subfile_type().is_some() {
             keys.push("new_subfile_type");
         }
+        if self.threshholding().is_some() {
+            keys.push("threshholding");
+        }
+        if self.fill_order().is_some() {
+            keys.push("fill_order");
+        }
         if self.document_name().is_some() {
             keys.push("document_name");
         }
@@ -365,9 +501,15 @@ impl PyImageFileDirectory {
         if self.jpeg_tables().is_some() {
             keys.push("jpeg_tables");
         }
+        if self.ycbcr_subsampling().is_some() {
+            keys.push("ycbcr_subsampling");
+        }
         if self.copyright().is_some() {
             keys.push("copyright");
         }
+        if self.xmp().is_some() {
+            keys.push("xmp");
+        }
         if self.geo_key_directory().is_some() {
             keys.push("geo_key_directory");
         }
@@ -411,6 +553,8 @@ impl PyImageFileDirectory {
             "bits_per_sample" => self.bits_per_sample().into_bound_py_any(py),
             "compression" => self.compression().into_bound_py_any(py),
             "photometric_interpretation" => self.photometric_interpretation().into_bound_py_any(py),
+            "threshholding" => self.threshholding().into_bound_py_any(py),
+            "fill_order" => self.fill_order().into_bound_py_any(py),
             "document_name" => self.document_name().into_bound_py_any(py),
             "image_description" => self.image_description().into_bound_py_any(py),
             "strip_offsets" => self.strip_offsets().into_bound_py_any(py),
@@ -436,12 +580,15 @@ impl PyImageFileDirectory {
             "extra_samples" => self.extra_samples().into_bound_py_any(py),
             "sample_format" => self.sample_format().into_bound_py_any(py),
             "jpeg_tables" => self.jpeg_tables().into_bound_py_any(py),
+            "ycbcr_subsampling" => self.ycbcr_subsampling().into_bound_py_any(py),
             "copyright" => self.copyright().into_bound_py_any(py),
+            "xmp" => self.xmp().into_bound_py_any(py),
             "geo_key_directory" => self.geo_key_directory().into_bound_py_any(py),
             "model_pixel_scale" => self.model_pixel_scale().into_bound_py_any(py),
             "model_tiepoint" => self.model_tiepoint().into_bound_py_any(py),
             "model_transformation" => self.model_transformation().into_bound_py_any(py),
             "other_tags" => self.other_tags().into_bound_py_any(py),
+            "extensions" => self.extensions(py).into_bound_py_any(py),
             "gdal_nodata" => self.gdal_nodata().into_bound_py_any(py),
             "gdal_metadata" => self.gdal_metadata().into_bound_py_any(py),
             "lerc_parameters" => self.lerc_parameters().into_bound_py_any(py),
@@ -465,29 +612,71 @@ impl PyImageFileDirectory {
             let tile = ifd
                 .fetch_tile(x, y, reader.as_ref())
                 .await
-                .map_err(|err| PyTypeError::new_err(err.to_string()))?;
+                .map_err(PyAsyncTiffError::from)?;
 
             Ok(PyTile::new(tile))
         })
     }
 
+    #[pyo3(signature = (xy, *, concurrency=None, coalesce=None))]
     pub(crate) fn fetch_tiles<'py>(
         &'py self,
         py: Python<'py>,
         xy: Vec<(usize, usize)>,
+        concurrency: Option<usize>,
+        coalesce: Option<u64>,
     ) -> PyResult<Bound<'py, PyAny>> {
+        let options = fetch_options(concurrency, coalesce);
         let reader = self.reader.clone();
         let ifd = self.ifd.clone();
         future_into_py(py, async move {
             let tiles = ifd
-                .fetch_tiles(&xy, reader.as_ref())
+                .fetch_tiles_with_options(&xy, reader.as_ref(), &options)
                 .await
-                .map_err(|err| PyTypeError::new_err(err.to_string()))?;
+                .map_err(PyAsyncTiffError::from)?;
             let py_tiles = tiles.into_iter().map(PyTile::new).collect::<Vec<_>>();
             Ok(py_tiles)
         })
     }
 
+    fn fetch_strip<'py>(&'py self, py: Python<'py>, y: usize) -> PyResult<Bound<'py, PyAny>> {
+        let reader = self.reader.clone();
+        let ifd = self.ifd.clone();
+        future_into_py(py, async move {
+            let strip = ifd
+                .fetch_strip(y, reader.as_ref())
+                .await
+                .map_err(PyAsyncTiffError::from)?;
+            Ok(PyTile::new(strip))
+        })
+    }
+
+    #[pyo3(signature = (start, stop, *, concurrency=None, coalesce=None))]
+    fn fetch_strips<'py>(
+        &'py self,
+        py: Python<'py>,
+        start: usize,
+        stop: usize,
+        concurrency: Option<usize>,
+        coalesce: Option<u64>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let options = fetch_options(concurrency, coalesce);
+        let reader = self.reader.clone();
+        let ifd = self.ifd.clone();
+        future_into_py(py, async move {
+            let strips = ifd
+                .fetch_strips_with_options(start..stop, reader.as_ref(), &options)
+                .await
+                .map_err(PyAsyncTiffError::from)?;
+            Ok(strips.into_iter().map(PyTile::new).collect::<Vec<_>>())
+        })
+    }
+
+    #[getter]
+    fn strip_count(&self) -> Option<usize> {
+        self.ifd.strip_count()
+    }
+
     fn tile_byte_range(&self, x: usize, y: usize) -> PyAsyncTiffResult<PyTileByteRange> {
         let byte_range = self
             .ifd
@@ -502,6 +691,19 @@ impl PyImageFileDirectory {
     }
 }
 
+/// Build the Rust fetch options from the `fetch_tiles` keyword arguments.
+/// Fetch options from the Python arguments, falling back to the global defaults.
+pub(crate) fn fetch_options(concurrency: Option<usize>, coalesce: Option<u64>) -> FetchOptions {
+    let mut options = AsyncTiffConfig::global().fetch_options();
+    if let Some(concurrency) = concurrency {
+        options = options.with_concurrency(concurrency);
+    }
+    if let Some(coalesce) = coalesce {
+        options = options.with_coalesce(coalesce);
+    }
+    options
+}
+
 impl PartialEq for PyImageFileDirectory {
     fn eq(&self, other: &Self) -> bool {
         self.ifd == other.ifd
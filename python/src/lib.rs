@@ -3,10 +3,14 @@
 mod array;
 mod colormap;
 mod decoder;
+mod dlpack;
 mod enums;
 mod error;
+mod extension;
 mod geo;
 mod ifd;
+mod metadata_cache;
+mod read;
 mod reader;
 mod thread_pool;
 mod tiff;
@@ -18,9 +22,11 @@ use pyo3::prelude::*;
 use crate::array::PyArray;
 use crate::colormap::PyColormap;
 use crate::decoder::PyDecoderRegistry;
+use crate::extension::PyExtensionRegistry;
 use crate::geo::PyGeoKeyDirectory;
 use crate::ifd::PyImageFileDirectory;
-use crate::thread_pool::PyThreadPool;
+use crate::metadata_cache::{PyNoCache, PyPrefetch, PyReadahead};
+use crate::thread_pool::{set_decoder_threads, PyThreadPool};
 use crate::tiff::PyTIFF;
 use crate::tile::PyTile;
 
@@ -56,9 +62,15 @@ fn _async_tiff(py: Python, m: &Bound<PyModule>) -> PyResult<()> {
     check_debug_build(py)?;
 
     m.add_wrapped(wrap_pyfunction!(___version))?;
+    m.add_wrapped(wrap_pyfunction!(set_decoder_threads))?;
+    error::register_exceptions(py, m)?;
     m.add_class::<PyDecoderRegistry>()?;
+    m.add_class::<PyExtensionRegistry>()?;
     m.add_class::<PyGeoKeyDirectory>()?;
     m.add_class::<PyImageFileDirectory>()?;
+    m.add_class::<PyNoCache>()?;
+    m.add_class::<PyPrefetch>()?;
+    m.add_class::<PyReadahead>()?;
     m.add_class::<PyThreadPool>()?;
     m.add_class::<PyTIFF>()?;
     m.add_class::<PyTile>()?;
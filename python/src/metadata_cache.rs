@@ -0,0 +1,130 @@
+use async_tiff::config::{AsyncTiffConfig, MetadataCacheMode};
+use pyo3::exceptions::{PyTypeError, PyValueError};
+use pyo3::prelude::*;
+
+#[pyclass(name = "Prefetch", frozen, skip_from_py_object)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PyPrefetch {
+    size: u64,
+}
+
+#[pymethods]
+impl PyPrefetch {
+    #[new]
+    fn new(size: u64) -> Self {
+        Self { size }
+    }
+
+    #[getter]
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Prefetch({})", self.size)
+    }
+}
+
+#[pyclass(name = "Readahead", frozen, skip_from_py_object)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PyReadahead {
+    initial: Option<u64>,
+    multiplier: Option<f64>,
+}
+
+#[pymethods]
+impl PyReadahead {
+    #[new]
+    #[pyo3(signature = (initial = None, multiplier = None))]
+    fn new(initial: Option<u64>, multiplier: Option<f64>) -> PyResult<Self> {
+        if multiplier.is_some_and(|multiplier| multiplier <= 1.0) {
+            return Err(PyValueError::new_err("multiplier must be greater than 1.0"));
+        }
+        Ok(Self {
+            initial,
+            multiplier,
+        })
+    }
+
+    #[getter]
+    fn initial(&self) -> Option<u64> {
+        self.initial
+    }
+
+    #[getter]
+    fn multiplier(&self) -> Option<f64> {
+        self.multiplier
+    }
+
+    fn __repr__(&self) -> String {
+        let initial = self.initial.map_or("None".to_string(), |v| v.to_string());
+        let multiplier = self
+            .multiplier
+            .map_or("None".to_string(), |v| v.to_string());
+        format!("Readahead(initial={initial}, multiplier={multiplier})")
+    }
+}
+
+#[pyclass(name = "NoCache", frozen, skip_from_py_object)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PyNoCache;
+
+#[pymethods]
+impl PyNoCache {
+    #[new]
+    fn new() -> Self {
+        Self
+    }
+
+    fn __repr__(&self) -> &'static str {
+        "NoCache()"
+    }
+}
+
+/// A metadata cache strategy passed to `TIFF.open`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum PyMetadataCache {
+    Prefetch(PyPrefetch),
+    Readahead(PyReadahead),
+    NoCache,
+}
+
+impl<'py> FromPyObject<'_, 'py> for PyMetadataCache {
+    type Error = PyErr;
+
+    fn extract(obj: Borrowed<'_, 'py, PyAny>) -> Result<Self, Self::Error> {
+        if let Ok(prefetch) = obj.cast::<PyPrefetch>() {
+            Ok(Self::Prefetch(*prefetch.get()))
+        } else if let Ok(readahead) = obj.cast::<PyReadahead>() {
+            Ok(Self::Readahead(*readahead.get()))
+        } else if obj.cast::<PyNoCache>().is_ok() {
+            Ok(Self::NoCache)
+        } else {
+            Err(PyTypeError::new_err(
+                "Expected Prefetch, Readahead or NoCache for metadata_cache.",
+            ))
+        }
+    }
+}
+
+impl PyMetadataCache {
+    /// Apply this strategy to `config`, keeping its defaults for unset values.
+    pub(crate) fn apply(self, config: AsyncTiffConfig) -> AsyncTiffConfig {
+        match self {
+            Self::Prefetch(prefetch) => config
+                .with_metadata_cache(MetadataCacheMode::Prefetch)
+                .with_prefetch(prefetch.size),
+            Self::Readahead(readahead) => {
+                let mut config = config.with_metadata_cache(MetadataCacheMode::Readahead);
+                if let Some(initial) = readahead.initial {
+                    config = config.with_prefetch(initial);
+                }
+                if let Some(multiplier) = readahead.multiplier {
+                    config = config.with_multiplier(multiplier);
+                }
+                config
+            }
+            Self::NoCache => config.with_metadata_cache(MetadataCacheMode::None),
+        }
+    }
+}
@@ -0,0 +1,272 @@
+//! Windowed reads backing `TIFF.read` and `TIFF.read_window`.
+
+use std::sync::Arc;
+
+use async_tiff::decoder::DecoderRegistry;
+use async_tiff::error::{AsyncTiffError, AsyncTiffResult};
+use async_tiff::reader::AsyncFileReader;
+use async_tiff::tags::PlanarConfiguration;
+use async_tiff::{Array, DataType, FetchOptions, ImageFileDirectory, Nodata, TypedArray, Window};
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use rayon::ThreadPool;
+use tokio_rayon::AsyncThreadPool;
+
+/// A window of the full-resolution image, accepting either a rasterio `Window` or a
+/// `((row_start, row_stop), (col_start, col_stop))` tuple.
+#[derive(Debug, Clone, Copy, FromPyObject)]
+pub(crate) enum PyWindow {
+    Ranges((i64, i64), (i64, i64)),
+    Window {
+        col_off: f64,
+        row_off: f64,
+        width: f64,
+        height: f64,
+    },
+}
+
+/// Clip `window` to an image of `width` by `height` pixels, or cover the whole image if no window
+/// is given.
+pub(crate) fn clip_window(window: Option<PyWindow>, width: u32, height: u32) -> PyResult<Window> {
+    let (col_start, col_stop, row_start, row_stop) = match window {
+        None => return Ok(Window::new(0, 0, width, height)),
+        Some(PyWindow::Ranges((row_start, row_stop), (col_start, col_stop))) => {
+            (col_start, col_stop, row_start, row_stop)
+        }
+        Some(PyWindow::Window {
+            col_off,
+            row_off,
+            width,
+            height,
+        }) => (
+            col_off.floor() as i64,
+            (col_off + width).ceil() as i64,
+            row_off.floor() as i64,
+            (row_off + height).ceil() as i64,
+        ),
+    };
+    let clamp = |v: i64, max: u32| v.clamp(0, max as i64) as u32;
+    let (col_start, col_stop) = (clamp(col_start, width), clamp(col_stop, width));
+    let (row_start, row_stop) = (clamp(row_start, height), clamp(row_stop, height));
+    if col_start >= col_stop || row_start >= row_stop {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "Window does not intersect the image",
+        ));
+    }
+    Ok(Window::new(
+        col_start,
+        row_start,
+        col_stop - col_start,
+        row_stop - row_start,
+    ))
+}
+
+/// The result of [`read`]: band-sequential data of shape `(bands, height, width)`, and the
+/// nodata mask with the same layout if requested.
+pub(crate) struct ReadResult {
+    pub(crate) data: TypedArray,
+    pub(crate) shape: [usize; 3],
+    pub(crate) data_type: DataType,
+    pub(crate) mask: Option<Vec<bool>>,
+}
+
+/// Read `window` of the full-resolution image of a pyramid, given as its `levels` from the
+/// full-resolution image to the coarsest overview, resampled with nearest neighbour to `out_shape`
+/// `(height, width)`.
+///
+/// The coarsest level with enough resolution for `out_shape` is read, so downsampled reads of
+/// COGs only fetch overview tiles or strips. `indexes` are 1-based band numbers.
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn read(
+    levels: Vec<Arc<ImageFileDirectory>>,
+    reader: Arc<dyn AsyncFileReader>,
+    indexes: Option<Vec<usize>>,
+    window: Window,
+    out_shape: (usize, usize),
+    masked: bool,
+    options: FetchOptions,
+    decoder_registry: Arc<DecoderRegistry>,
+    pool: Arc<ThreadPool>,
+) -> AsyncTiffResult<ReadResult> {
+    let (out_height, out_width) = out_shape;
+    // The coarsest level that still has the resolution needed for `out_shape`, like
+    // `Pyramid::level_for_scale`
+    let full = &levels[0];
+    let scale = window.width as f64 / out_width as f64;
+    let level = levels
+        .iter()
+        .rposition(|ifd| full.image_width() as f64 / ifd.image_width() as f64 <= scale)
+        .unwrap_or(0);
+    let ifd = levels[level].clone();
+    let (level_width, level_height) = (ifd.image_width(), ifd.image_height());
+    let scale_x = full.image_width() as f64 / level_width as f64;
+    let scale_y = full.image_height() as f64 / level_height as f64;
+
+    let data_type = ifd.data_type().ok_or(AsyncTiffError::General(
+        "read() requires a known data type".to_string(),
+    ))?;
+    let bands = ifd.samples_per_pixel() as usize;
+    let indexes = match indexes {
+        Some(indexes) => indexes
+            .into_iter()
+            .map(|index| {
+                if (1..=bands).contains(&index) {
+                    Ok(index - 1)
+                } else {
+                    Err(AsyncTiffError::General(format!(
+                        "Band index {index} out of range for an image with {bands} bands"
+                    )))
+                }
+            })
+            .collect::<AsyncTiffResult<Vec<_>>>()?,
+        None => (0..bands).collect(),
+    };
+
+    // The level pixel sampled for each output column and row
+    let sample = |offset: u32, size: u32, out_size: usize, scale: f64, level_size: u32| {
+        (0..out_size)
+            .map(|i| {
+                let full = offset as f64 + (i as f64 + 0.5) * size as f64 / out_size as f64;
+                ((full / scale) as u32).min(level_size - 1)
+            })
+            .collect::<Vec<_>>()
+    };
+    let src_cols = sample(
+        window.col_off,
+        window.width,
+        out_width,
+        scale_x,
+        level_width,
+    );
+    let src_rows = sample(
+        window.row_off,
+        window.height,
+        out_height,
+        scale_y,
+        level_height,
+    );
+
+    let (Some(&first_col), Some(&last_col), Some(&first_row), Some(&last_row)) = (
+        src_cols.first(),
+        src_cols.last(),
+        src_rows.first(),
+        src_rows.last(),
+    ) else {
+        return Err(AsyncTiffError::General(
+            "Output shape must not be empty".to_string(),
+        ));
+    };
+    // The part of the level covering all sampled pixels
+    let level_window = Window::new(
+        first_col,
+        first_row,
+        last_col - first_col + 1,
+        last_row - first_row + 1,
+    );
+    let chunks = ifd
+        .fetch_window(level_window, reader.as_ref(), &options)
+        .await?;
+
+    let nodata = ifd.nodata_for_dtype(data_type);
+    let planar = ifd.planar_configuration() == PlanarConfiguration::Planar;
+    pool.spawn_fifo_async(move || {
+        let decoded = chunks
+            .into_par_iter()
+            .map(|tile| {
+                let (x, y) = (tile.x(), tile.y());
+                tile.decode(&decoder_registry).map(|array| (x, y, array))
+            })
+            .collect::<AsyncTiffResult<Vec<_>>>()?;
+        let mosaic = ifd.mosaic_window(level_window, decoded)?;
+
+        let size = data_type.size();
+        let data = mosaic.data().as_ref();
+        let [_, d1, d2] = mosaic.shape();
+        let mut out = vec![0; indexes.len() * out_height * out_width * size];
+        for (out_band, &band) in indexes.iter().enumerate() {
+            for (out_row, &row) in src_rows.iter().enumerate() {
+                let row = (row - first_row) as usize;
+                for (out_col, &col) in src_cols.iter().enumerate() {
+                    let col = (col - first_col) as usize;
+                    let src = if planar {
+                        (band * d1 + row) * d2 + col
+                    } else {
+                        (row * d1 + col) * d2 + band
+                    };
+                    let dst = (out_band * out_height + out_row) * out_width + out_col;
+                    out[dst * size..(dst + 1) * size]
+                        .copy_from_slice(&data[src * size..(src + 1) * size]);
+                }
+            }
+        }
+
+        let mask = masked.then(|| {
+            out.chunks_exact(size)
+                .map(|value| nodata.is_some_and(|nodata| is_nodata(value, nodata)))
+                .collect()
+        });
+        let data = match data_type {
+            DataType::Bool => TypedArray::Bool(out.iter().map(|b| *b != 0).collect()),
+            _ => TypedArray::try_new(out, Some(data_type))?,
+        };
+        Ok(ReadResult {
+            data,
+            shape: [indexes.len(), out_height, out_width],
+            data_type,
+            mask,
+        })
+    })
+    .await
+}
+
+/// Read `window` of `ifd` at its native resolution, decoding the tiles or strips in parallel on
+/// `pool`.
+pub(crate) async fn read_window(
+    ifd: Arc<ImageFileDirectory>,
+    reader: Arc<dyn AsyncFileReader>,
+    window: Window,
+    options: FetchOptions,
+    decoder_registry: Arc<DecoderRegistry>,
+    pool: Arc<ThreadPool>,
+) -> AsyncTiffResult<Array> {
+    let chunks = ifd.fetch_window(window, reader.as_ref(), &options).await?;
+    pool.spawn_fifo_async(move || {
+        let decoded = chunks
+            .into_par_iter()
+            .map(|tile| {
+                let (x, y) = (tile.x(), tile.y());
+                tile.decode(&decoder_registry).map(|array| (x, y, array))
+            })
+            .collect::<AsyncTiffResult<Vec<_>>>()?;
+        ifd.mosaic_window(window, decoded)
+    })
+    .await
+}
+
+/// Whether the native-endian `value` equals `nodata`, treating all NaNs as equal.
+fn is_nodata(value: &[u8], nodata: Nodata) -> bool {
+    macro_rules! eq {
+        ($ty:ty, $nodata:expr) => {
+            <$ty>::from_ne_bytes(value.try_into().unwrap()) == $nodata
+        };
+    }
+    match nodata {
+        Nodata::Bool(v) => (value[0] != 0) == v,
+        Nodata::UInt8(v) => value[0] == v,
+        Nodata::UInt16(v) => eq!(u16, v),
+        Nodata::UInt32(v) => eq!(u32, v),
+        Nodata::UInt64(v) => eq!(u64, v),
+        Nodata::Int8(v) => value[0] as i8 == v,
+        Nodata::Int16(v) => eq!(i16, v),
+        Nodata::Int32(v) => eq!(i32, v),
+        Nodata::Int64(v) => eq!(i64, v),
+        Nodata::Float32(v) => {
+            let value = f32::from_ne_bytes(value.try_into().unwrap());
+            value == v || (value.is_nan() && v.is_nan())
+        }
+        Nodata::Float64(v) => {
+            let value = f64::from_ne_bytes(value.try_into().unwrap());
+            value == v || (value.is_nan() && v.is_nan())
+        }
+    }
+}
@@ -1,15 +1,16 @@
 use std::ops::Range;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use async_tiff::error::{AsyncTiffError, AsyncTiffResult};
 use async_tiff::reader::{AsyncFileReader, ObjectReader};
 use async_trait::async_trait;
 use bytes::Bytes;
-use pyo3::exceptions::PyTypeError;
+use futures::channel::oneshot;
+use pyo3::exceptions::{PyTypeError, PyValueError};
 use pyo3::intern;
 use pyo3::prelude::*;
 use pyo3::types::PyDict;
-use pyo3_async_runtimes::tokio::into_future;
+use pyo3_async_runtimes::tokio::{get_runtime, into_future};
 use pyo3_bytes::PyBytes;
 use pyo3_object_store::PyObjectStore;
 
@@ -25,7 +26,7 @@ impl StoreInput {
             Self::ObjectStore(store) => {
                 Arc::new(ObjectReader::new(store.into_inner(), path.into()))
             }
-            Self::ObspecBackend(backend) => Arc::new(ObspecReader { backend, path }),
+            Self::ObspecBackend(backend) => Arc::new(ObspecReader::new(backend, path)),
         }
     }
 }
@@ -70,27 +71,50 @@ impl ObspecBackend {
             into_future(coroutine.bind(py).clone())
         })?;
         let result = future.await?;
-        Python::attach(|py| result.extract(py))
+        let buffers: Vec<PyBytes> = Python::attach(|py| result.extract(py))?;
+        if buffers.len() != ranges.len() {
+            return Err(PyValueError::new_err(format!(
+                "get_ranges_async returned {} buffers for {} ranges",
+                buffers.len(),
+                ranges.len()
+            )));
+        }
+        Ok(buffers)
     }
 
-    async fn get_range_wrapper(&self, path: &str, range: Range<u64>) -> AsyncTiffResult<Bytes> {
-        let result = self
-            .get_range(path, range)
-            .await
-            .map_err(|err| AsyncTiffError::External(Box::new(err)))?;
-        Ok(result.into_inner())
+    /// The size of the file at `path` from the `head_async` method of the obspec `HeadAsync`
+    /// protocol, or `None` if the backend doesn't implement it.
+    async fn head(&self, path: &str) -> PyResult<Option<u64>> {
+        let future = Python::attach(|py| {
+            if !self.0.bind(py).hasattr(intern!(py, "head_async"))? {
+                return Ok(None);
+            }
+            let coroutine = self
+                .0
+                .call_method1(py, intern!(py, "head_async"), (path,))?;
+            into_future(coroutine.bind(py).clone()).map(Some)
+        })?;
+        let Some(future) = future else {
+            return Ok(None);
+        };
+        let meta = future.await?;
+        Python::attach(|py| meta.bind(py).get_item(intern!(py, "size"))?.extract()).map(Some)
     }
 
-    async fn get_ranges_wrapper(
-        &self,
-        path: &str,
-        ranges: Vec<Range<u64>>,
-    ) -> AsyncTiffResult<Vec<Bytes>> {
-        let result = self
-            .get_ranges(path, &ranges)
-            .await
-            .map_err(|err| AsyncTiffError::External(Box::new(err)))?;
-        Ok(result.into_iter().map(|b| b.into_inner()).collect())
+    /// Fetch `ranges` with a single Python call, using `get_range_async` for a single range.
+    async fn fetch(&self, path: &str, ranges: &[Range<u64>]) -> PyResult<Vec<Bytes>> {
+        match ranges {
+            [range] => Ok(vec![self
+                .get_range(path, range.clone())
+                .await?
+                .into_inner()]),
+            ranges => Ok(self
+                .get_ranges(path, ranges)
+                .await?
+                .into_iter()
+                .map(|b| b.into_inner())
+                .collect()),
+        }
     }
 }
 
@@ -109,19 +133,98 @@ impl<'py> FromPyObject<'_, 'py> for ObspecBackend {
     }
 }
 
+/// The requests waiting to be sent to the backend in the next batch.
+#[derive(Debug, Default)]
+struct PendingBatch {
+    ranges: Vec<Range<u64>>,
+    /// The number of ranges requested by each caller, in order, and where to send their bytes.
+    requests: Vec<(usize, oneshot::Sender<AsyncTiffResult<Vec<Bytes>>>)>,
+}
+
+/// An [`AsyncFileReader`] for obspec backends that batches concurrent requests.
+///
+/// Every call into Python has a fixed overhead, so ranges requested concurrently, such as by
+/// many `fetch_tile` calls at once or by fetches with a `concurrency` limit, are collected and
+/// sent to the backend in a single `get_ranges_async` call.
 #[derive(Debug)]
 struct ObspecReader {
-    backend: ObspecBackend,
-    path: String,
+    backend: Arc<ObspecBackend>,
+    path: Arc<str>,
+    pending: Arc<Mutex<Option<PendingBatch>>>,
+}
+
+impl ObspecReader {
+    fn new(backend: ObspecBackend, path: String) -> Self {
+        Self {
+            backend: Arc::new(backend),
+            path: path.into(),
+            pending: Default::default(),
+        }
+    }
+
+    /// Send all pending requests to the backend and distribute the results.
+    async fn flush(
+        backend: Arc<ObspecBackend>,
+        path: Arc<str>,
+        pending: Arc<Mutex<Option<PendingBatch>>>,
+    ) {
+        let Some(batch) = pending.lock().unwrap().take() else {
+            return;
+        };
+        match backend.fetch(&path, &batch.ranges).await {
+            Ok(buffers) => {
+                let mut buffers = buffers.into_iter();
+                for (len, sender) in batch.requests {
+                    let _ = sender.send(Ok(buffers.by_ref().take(len).collect()));
+                }
+            }
+            Err(err) => Python::attach(|py| {
+                for (_, sender) in batch.requests {
+                    let err = AsyncTiffError::External(Box::new(err.clone_ref(py)));
+                    let _ = sender.send(Err(err));
+                }
+            }),
+        }
+    }
 }
 
 #[async_trait]
 impl AsyncFileReader for ObspecReader {
     async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
-        self.backend.get_range_wrapper(&self.path, range).await
+        let mut buffers = self.get_byte_ranges(vec![range]).await?;
+        Ok(buffers.remove(0))
     }
 
     async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
-        self.backend.get_ranges_wrapper(&self.path, ranges).await
+        if ranges.is_empty() {
+            return Ok(vec![]);
+        }
+        let (sender, receiver) = oneshot::channel();
+        let is_first = {
+            let mut pending = self.pending.lock().unwrap();
+            let batch = pending.get_or_insert_with(Default::default);
+            batch.requests.push((ranges.len(), sender));
+            batch.ranges.extend(ranges);
+            batch.requests.len() == 1
+        };
+        if is_first {
+            // The flush runs as its own task, so that it completes even if this caller is
+            // cancelled, and requests made until it starts join the batch.
+            get_runtime().spawn(Self::flush(
+                self.backend.clone(),
+                self.path.clone(),
+                self.pending.clone(),
+            ));
+        }
+        receiver.await.map_err(|_| {
+            AsyncTiffError::General("Batched obspec request was cancelled".to_string())
+        })?
+    }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        self.backend
+            .head(&self.path)
+            .await
+            .map_err(|err| AsyncTiffError::External(Box::new(err)))
     }
 }
@@ -1,20 +1,41 @@
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use pyo3::sync::PyOnceLock;
 use rayon::{ThreadPool, ThreadPoolBuilder};
 
-static DEFAULT_POOL: PyOnceLock<Arc<ThreadPool>> = PyOnceLock::new();
+/// The pool used when no `pool` is passed, replaced by `set_decoder_threads`.
+static DEFAULT_POOL: RwLock<Option<Arc<ThreadPool>>> = RwLock::new(None);
 
-pub fn get_default_pool(py: Python<'_>) -> PyResult<Arc<ThreadPool>> {
-    let runtime = DEFAULT_POOL.get_or_try_init(py, || {
-        let pool = ThreadPoolBuilder::new().build().map_err(|err| {
-            PyValueError::new_err(format!("Could not create rayon threadpool. {err}"))
-        })?;
-        Ok::<_, PyErr>(Arc::new(pool))
-    })?;
-    Ok(runtime.clone())
+fn build_pool(num_threads: Option<usize>) -> PyResult<ThreadPool> {
+    let mut builder = ThreadPoolBuilder::new();
+    if let Some(num_threads) = num_threads {
+        builder = builder.num_threads(num_threads);
+    }
+    builder
+        .build()
+        .map_err(|err| PyValueError::new_err(format!("Could not create rayon threadpool. {err}")))
+}
+
+pub fn get_default_pool(_py: Python<'_>) -> PyResult<Arc<ThreadPool>> {
+    if let Some(pool) = DEFAULT_POOL.read().unwrap().as_ref() {
+        return Ok(pool.clone());
+    }
+    let mut default = DEFAULT_POOL.write().unwrap();
+    match default.as_ref() {
+        Some(pool) => Ok(pool.clone()),
+        None => Ok(default.insert(Arc::new(build_pool(None)?)).clone()),
+    }
+}
+
+/// Replace the default decoding pool with one of `num_threads` threads.
+///
+/// Decodes already running keep using the previous pool until they finish.
+#[pyfunction]
+pub(crate) fn set_decoder_threads(num_threads: usize) -> PyResult<()> {
+    let pool = Arc::new(build_pool(Some(num_threads))?);
+    *DEFAULT_POOL.write().unwrap() = Some(pool);
+    Ok(())
 }
 
 #[pyclass(name = "ThreadPool", frozen, module = "async_tiff")]
@@ -24,13 +45,7 @@ pub(crate) struct PyThreadPool(Arc<ThreadPool>);
 impl PyThreadPool {
     #[new]
     fn new(num_threads: usize) -> PyResult<Self> {
-        let pool = ThreadPoolBuilder::new()
-            .num_threads(num_threads)
-            .build()
-            .map_err(|err| {
-                PyValueError::new_err(format!("Could not create rayon threadpool. {err}"))
-            })?;
-        Ok(Self(Arc::new(pool)))
+        Ok(Self(Arc::new(build_pool(Some(num_threads))?)))
     }
 }
 
@@ -1,63 +1,133 @@
 use std::sync::Arc;
 
+use async_tiff::config::AsyncTiffConfig;
 use async_tiff::metadata::cache::ReadaheadMetadataCache;
-use async_tiff::metadata::TiffMetadataReader;
-use async_tiff::reader::{AsyncFileReader, Endianness};
-use async_tiff::ImageFileDirectory;
-use pyo3::exceptions::{PyIndexError, PyTypeError};
+use async_tiff::metadata::{ParseOptions, TiffMetadataReader};
+use async_tiff::reader::{from_url, AsyncFileReader, Endianness, ReadStats, StatsReader};
+use async_tiff::{ImageFileDirectory, Pyramid, TIFF};
+use pyo3::exceptions::{PyIndexError, PyTypeError, PyValueError};
+use pyo3::intern;
 use pyo3::prelude::*;
-use pyo3::types::PyType;
+use pyo3::types::{PyDict, PyType};
 use pyo3_async_runtimes::tokio::future_into_py;
 
+use crate::array::PyArray;
+use crate::decoder::get_default_decoder_registry;
 use crate::enums::PyEndianness;
-use crate::error::PyAsyncTiffResult;
+use crate::error::{PyAsyncTiffError, PyAsyncTiffResult};
+use crate::extension::PyExtensionRegistry;
+use crate::ifd::fetch_options;
+use crate::metadata_cache::PyMetadataCache;
+use crate::read::{clip_window, read, read_window, PyWindow};
 use crate::reader::StoreInput;
+use crate::thread_pool::{get_default_pool, PyThreadPool};
 use crate::tile::PyTile;
-use crate::PyImageFileDirectory;
+use crate::{PyDecoderRegistry, PyImageFileDirectory};
 
 #[pyclass(name = "TIFF", frozen, subclass)]
 pub(crate) struct PyTIFF {
     endianness: Endianness,
     ifds: Vec<Arc<ImageFileDirectory>>,
+    /// The indices into `ifds` of the pyramid levels, see [`Pyramid::level_indices`].
+    pyramid: Option<Vec<usize>>,
     reader: Arc<dyn AsyncFileReader>,
+    metadata_reader: Arc<dyn AsyncFileReader>,
+    parse_options: ParseOptions,
+    metadata_stats: Arc<ReadStats>,
+    image_stats: Arc<ReadStats>,
 }
 
 async fn open(
     reader: Arc<dyn AsyncFileReader>,
-    prefetch: u64,
-    multiplier: f64,
+    config: AsyncTiffConfig,
 ) -> PyAsyncTiffResult<PyTIFF> {
-    let metadata_fetch = ReadaheadMetadataCache::new(reader.clone())
-        .with_initial_size(prefetch)
-        .with_multiplier(multiplier);
-    let mut metadata_reader = TiffMetadataReader::try_open(&metadata_fetch).await?;
-    let ifds = metadata_reader.read_all_ifds(&metadata_fetch).await?;
+    // Metadata and image data reads are counted separately for `request_stats`
+    let metadata_stats = Arc::new(ReadStats::new());
+    let image_stats = Arc::new(ReadStats::new());
+    let metadata_reader = Arc::new(StatsReader::with_stats(
+        reader.clone(),
+        metadata_stats.clone(),
+    )) as Arc<dyn AsyncFileReader>;
+    let tiff = TIFF::open_with_config(metadata_reader.clone(), &config).await?;
     Ok(PyTIFF {
-        endianness: metadata_reader.endianness(),
-        ifds: ifds.into_iter().map(Arc::new).collect(),
-        reader,
+        endianness: tiff.endianness(),
+        ifds: tiff.ifds().iter().cloned().map(Arc::new).collect(),
+        pyramid: Pyramid::level_indices(tiff.ifds()),
+        reader: Arc::new(StatsReader::with_stats(reader, image_stats.clone())),
+        metadata_reader,
+        parse_options: config.parse_options().clone(),
+        metadata_stats,
+        image_stats,
     })
 }
 
+fn stats_dict<'py>(py: Python<'py>, stats: &ReadStats) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("requests", stats.requests())?;
+    dict.set_item("bytes", stats.bytes())?;
+    dict.set_item("seconds", stats.elapsed().as_secs_f64())?;
+    Ok(dict)
+}
+
 #[pymethods]
 impl PyTIFF {
     #[classmethod]
-    #[pyo3(signature = (path, *, store, prefetch=32768, multiplier=2.0))]
+    #[pyo3(signature = (path, *, store=None, prefetch=None, multiplier=None, metadata_cache=None, extensions=None, **options))]
+    #[allow(clippy::too_many_arguments)]
     fn open<'py>(
         _cls: &Bound<'py, PyType>,
         py: Python<'py>,
         path: String,
-        store: StoreInput,
-        prefetch: u64,
-        multiplier: f64,
+        store: Option<StoreInput>,
+        prefetch: Option<u64>,
+        multiplier: Option<f64>,
+        metadata_cache: Option<PyMetadataCache>,
+        extensions: Option<PyRef<'py, PyExtensionRegistry>>,
+        options: Option<&Bound<'py, PyDict>>,
     ) -> PyResult<Bound<'py, PyAny>> {
-        let reader = store.into_async_file_reader(path);
+        let reader = match store {
+            Some(store) => {
+                if options.is_some_and(|options| !options.is_empty()) {
+                    return Err(PyTypeError::new_err(
+                        "Store options can only be passed when store is not provided.",
+                    ));
+                }
+                store.into_async_file_reader(path)
+            }
+            None => {
+                let options = options
+                    .map(|options| {
+                        options
+                            .iter()
+                            .map(|(k, v)| Ok((k.extract::<String>()?, v.str()?.to_string())))
+                            .collect::<PyResult<Vec<_>>>()
+                    })
+                    .transpose()?
+                    .unwrap_or_default();
+                from_url(&path, options).map_err(PyAsyncTiffError::from)?
+            }
+        };
+
+        if metadata_cache.is_some() && (prefetch.is_some() || multiplier.is_some()) {
+            return Err(PyTypeError::new_err(
+                "prefetch and multiplier can't be passed together with metadata_cache.",
+            ));
+        }
 
-        let cog_reader =
-            future_into_py(
-                py,
-                async move { Ok(open(reader, prefetch, multiplier).await?) },
-            )?;
+        let mut config = AsyncTiffConfig::global();
+        if let Some(metadata_cache) = metadata_cache {
+            config = metadata_cache.apply(config);
+        }
+        if let Some(prefetch) = prefetch {
+            config = config.with_prefetch(prefetch);
+        }
+        if let Some(multiplier) = multiplier {
+            config = config.with_multiplier(multiplier);
+        }
+        if let Some(extensions) = extensions {
+            config = config.with_parse_options(extensions.parse_options());
+        }
+        let cog_reader = future_into_py(py, async move { Ok(open(reader, config).await?) })?;
         Ok(cog_reader)
     }
 
@@ -91,6 +161,34 @@ impl PyTIFF {
         Ok(PyImageFileDirectory::new(ifd, self.reader.clone()))
     }
 
+    fn read_ifd_at<'py>(&self, py: Python<'py>, offset: u64) -> PyResult<Bound<'py, PyAny>> {
+        let metadata_reader = self.metadata_reader.clone();
+        let parse_options = self.parse_options.clone();
+        let reader = self.reader.clone();
+        future_into_py(py, async move {
+            let cache = ReadaheadMetadataCache::new(metadata_reader);
+            let read = async {
+                TiffMetadataReader::try_open(&cache)
+                    .await?
+                    .with_parse_options(parse_options)
+                    .read_ifd_at(&cache, offset)
+                    .await
+            };
+            let (ifd, next_ifd_offset) = read.await.map_err(PyAsyncTiffError::from)?;
+            Ok((
+                PyImageFileDirectory::new(Arc::new(ifd), reader),
+                next_ifd_offset,
+            ))
+        })
+    }
+
+    fn request_stats<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("metadata", stats_dict(py, &self.metadata_stats)?)?;
+        dict.set_item("image", stats_dict(py, &self.image_stats)?)?;
+        Ok(dict)
+    }
+
     #[getter]
     fn ifds(&self) -> Vec<PyImageFileDirectory> {
         self.ifds
@@ -116,18 +214,22 @@ impl PyTIFF {
             let tile = ifd
                 .fetch_tile(x, y, reader.as_ref())
                 .await
-                .map_err(|err| PyTypeError::new_err(err.to_string()))?;
+                .map_err(PyAsyncTiffError::from)?;
 
             Ok(PyTile::new(tile))
         })
     }
 
+    #[pyo3(signature = (xy, z, *, concurrency=None, coalesce=None))]
     fn fetch_tiles<'py>(
         &'py self,
         py: Python<'py>,
         xy: Vec<(usize, usize)>,
         z: usize,
+        concurrency: Option<usize>,
+        coalesce: Option<u64>,
     ) -> PyResult<Bound<'py, PyAny>> {
+        let options = fetch_options(concurrency, coalesce);
         let reader = self.reader.clone();
         let ifd = self
             .ifds
@@ -136,11 +238,150 @@ impl PyTIFF {
             .clone();
         future_into_py(py, async move {
             let tiles = ifd
-                .fetch_tiles(&xy, reader.as_ref())
+                .fetch_tiles_with_options(&xy, reader.as_ref(), &options)
                 .await
-                .map_err(|err| PyTypeError::new_err(err.to_string()))?;
+                .map_err(PyAsyncTiffError::from)?;
             let py_tiles = tiles.into_iter().map(PyTile::new).collect::<Vec<_>>();
             Ok(py_tiles)
         })
     }
+
+    #[pyo3(signature = (
+        ifd_index,
+        col_off,
+        row_off,
+        width,
+        height,
+        *,
+        decoder_registry=None,
+        pool=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn read_window<'py>(
+        &self,
+        py: Python<'py>,
+        ifd_index: usize,
+        col_off: u32,
+        row_off: u32,
+        width: u32,
+        height: u32,
+        decoder_registry: Option<&PyDecoderRegistry>,
+        pool: Option<&PyThreadPool>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let ifd = self
+            .ifds
+            .get(ifd_index)
+            .ok_or_else(|| PyIndexError::new_err(format!("No IFD found for index={ifd_index}")))?
+            .clone();
+        let window = async_tiff::Window::new(col_off, row_off, width, height);
+        let decoder_registry = decoder_registry
+            .map(|r| r.inner().clone())
+            .unwrap_or_else(|| get_default_decoder_registry(py));
+        let pool = pool
+            .map(|p| Ok(p.inner().clone()))
+            .unwrap_or_else(|| get_default_pool(py))?;
+        let options = fetch_options(None, None);
+        let reader = self.reader.clone();
+
+        future_into_py(py, async move {
+            let array = read_window(ifd, reader, window, options, decoder_registry, pool)
+                .await
+                .map_err(PyAsyncTiffError::from)?;
+            Ok(PyArray::try_new(array)?)
+        })
+    }
+
+    #[pyo3(signature = (
+        indexes=None,
+        window=None,
+        out_shape=None,
+        resampling="nearest",
+        masked=false,
+        *,
+        decoder_registry=None,
+        pool=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn read<'py>(
+        &self,
+        py: Python<'py>,
+        indexes: Option<Bound<'py, PyAny>>,
+        window: Option<PyWindow>,
+        out_shape: Option<Vec<usize>>,
+        resampling: &str,
+        masked: bool,
+        decoder_registry: Option<&PyDecoderRegistry>,
+        pool: Option<&PyThreadPool>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        if resampling != "nearest" {
+            return Err(PyValueError::new_err(format!(
+                "Unsupported resampling method: {resampling}. Only 'nearest' is supported."
+            )));
+        }
+        let levels = self
+            .pyramid
+            .as_ref()
+            .ok_or_else(|| PyValueError::new_err("TIFF has no full-resolution image"))?
+            .iter()
+            .map(|i| self.ifds[*i].clone())
+            .collect::<Vec<_>>();
+        let full = &levels[0];
+        let window = clip_window(window, full.image_width(), full.image_height())?;
+        let out_shape = match out_shape.as_deref() {
+            None => (window.height as usize, window.width as usize),
+            Some([.., height, width]) => (*height, *width),
+            Some(_) => {
+                return Err(PyValueError::new_err(
+                    "out_shape must be (height, width) or (bands, height, width)",
+                ))
+            }
+        };
+        // A single band index returns a single band, like rasterio, but keeps
+        // the 3D shape of Array
+        let indexes = indexes
+            .map(|indexes| match indexes.extract::<usize>() {
+                Ok(index) => Ok(vec![index]),
+                Err(_) => indexes.extract::<Vec<usize>>(),
+            })
+            .transpose()?;
+        let decoder_registry = decoder_registry
+            .map(|r| r.inner().clone())
+            .unwrap_or_else(|| get_default_decoder_registry(py));
+        let pool = pool
+            .map(|p| Ok(p.inner().clone()))
+            .unwrap_or_else(|| get_default_pool(py))?;
+        let options = fetch_options(None, None);
+        let reader = self.reader.clone();
+
+        future_into_py(py, async move {
+            let result = read(
+                levels,
+                reader,
+                indexes,
+                window,
+                out_shape,
+                masked,
+                options,
+                decoder_registry,
+                pool,
+            )
+            .await
+            .map_err(PyAsyncTiffError::from)?;
+            let array = PyArray::from_parts(result.data, result.shape, result.data_type);
+            let Some(mask) = result.mask else {
+                return Python::attach(|py| Ok(array.into_pyobject(py)?.into_any().unbind()));
+            };
+            Python::attach(|py| {
+                let np = py.import(intern!(py, "numpy"))?;
+                let data = np.call_method1(intern!(py, "asarray"), (array,))?;
+                let mask = np
+                    .call_method1(intern!(py, "asarray"), (mask,))?
+                    .call_method1(intern!(py, "reshape"), (result.shape,))?;
+                let masked = np
+                    .getattr(intern!(py, "ma"))?
+                    .call_method1(intern!(py, "MaskedArray"), (data, mask))?;
+                Ok(masked.unbind())
+            })
+        })
+    }
 }
@@ -1,5 +1,6 @@
-use async_tiff::{CompressedBytes, Tile};
+use async_tiff::{CompressedBytes, DecodeOptions, Tile};
 use pyo3::exceptions::PyValueError;
+use pyo3::intern;
 use pyo3::prelude::*;
 use pyo3_async_runtimes::tokio::future_into_py;
 use pyo3_bytes::PyBytes;
@@ -7,8 +8,8 @@ use tokio_rayon::AsyncThreadPool;
 
 use crate::array::PyArray;
 use crate::decoder::get_default_decoder_registry;
-use crate::enums::PyCompression;
-use crate::error::PyAsyncTiffResult;
+use crate::enums::{PyCompression, PyPredictor};
+use crate::error::{PyAsyncTiffError, PyAsyncTiffResult};
 use crate::thread_pool::{get_default_pool, PyThreadPool};
 use crate::PyDecoderRegistry;
 
@@ -56,29 +57,117 @@ impl PyTile {
             .map(|t| t.compression_method().into())
     }
 
+    #[getter]
+    fn predictor(&self) -> PyResult<PyPredictor> {
+        self.0
+            .as_ref()
+            .ok_or(PyValueError::new_err("Tile has been consumed"))
+            .map(|t| t.predictor().into())
+    }
+
+    fn serialize(&self) -> PyResult<PyBytes> {
+        let tile = self
+            .0
+            .as_ref()
+            .ok_or(PyValueError::new_err("Tile has been consumed"))?;
+        Ok(PyBytes::new(tile.serialize().into()))
+    }
+
+    #[staticmethod]
+    fn from_serialized(data: PyBytes) -> PyAsyncTiffResult<Self> {
+        Ok(Self::new(Tile::from_serialized(data.as_slice())?))
+    }
+
+    fn __reduce__<'py>(&self, py: Python<'py>) -> PyResult<(Bound<'py, PyAny>, (PyBytes,))> {
+        let from_serialized = py
+            .get_type::<Self>()
+            .getattr(intern!(py, "from_serialized"))?;
+        Ok((from_serialized, (self.serialize()?,)))
+    }
+
+    /// Decode on `pool` without holding the GIL, blocking until done.
+    #[pyo3(signature = (
+        decoder_registry=None,
+        *,
+        pool=None,
+        unpack_bits=false,
+        reverse_predictor=true,
+        expand_colormap=false,
+        bands=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn decode_sync<'py>(
         &mut self,
         py: Python<'py>,
         decoder_registry: Option<&PyDecoderRegistry>,
-    ) -> PyAsyncTiffResult<PyArray> {
+        pool: Option<&PyThreadPool>,
+        unpack_bits: bool,
+        reverse_predictor: bool,
+        expand_colormap: bool,
+        bands: Option<Vec<usize>>,
+    ) -> PyResult<PyArray> {
+        let options = decode_options(unpack_bits, reverse_predictor, expand_colormap, bands);
         let decoder_registry = decoder_registry
             .map(|r| r.inner().clone())
             .unwrap_or_else(|| get_default_decoder_registry(py));
+        let pool = pool
+            .map(|p| Ok(p.inner().clone()))
+            .unwrap_or_else(|| get_default_pool(py))?;
         let tile = self
             .0
             .take()
             .ok_or(PyValueError::new_err("Tile has been consumed"))?;
-        let array = tile.decode(&decoder_registry)?;
-        PyArray::try_new(array)
+        let array = py
+            .detach(|| pool.install(|| tile.decode_with_options(&decoder_registry, &options)))
+            .map_err(PyAsyncTiffError::from)?;
+        Ok(PyArray::try_new(array)?)
     }
 
+    /// Decompress on `pool` without holding the GIL, blocking until done.
     #[pyo3(signature = (*, decoder_registry=None, pool=None))]
+    fn decompress_sync<'py>(
+        &mut self,
+        py: Python<'py>,
+        decoder_registry: Option<&PyDecoderRegistry>,
+        pool: Option<&PyThreadPool>,
+    ) -> PyResult<PyBytes> {
+        let decoder_registry = decoder_registry
+            .map(|r| r.inner().clone())
+            .unwrap_or_else(|| get_default_decoder_registry(py));
+        let pool = pool
+            .map(|p| Ok(p.inner().clone()))
+            .unwrap_or_else(|| get_default_pool(py))?;
+        let tile = self
+            .0
+            .take()
+            .ok_or(PyValueError::new_err("Tile has been consumed"))?;
+        let bytes = py
+            .detach(|| pool.install(|| tile.decompress(&decoder_registry)))
+            .map_err(PyAsyncTiffError::from)?;
+        Ok(PyBytes::new(bytes.into()))
+    }
+
+    #[pyo3(signature = (
+        *,
+        decoder_registry=None,
+        pool=None,
+        unpack_bits=false,
+        reverse_predictor=true,
+        expand_colormap=false,
+        bands=None,
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn decode<'py>(
         &mut self,
         py: Python<'py>,
         decoder_registry: Option<&PyDecoderRegistry>,
         pool: Option<&PyThreadPool>,
+        unpack_bits: bool,
+        reverse_predictor: bool,
+        expand_colormap: bool,
+        bands: Option<Vec<usize>>,
     ) -> PyResult<Bound<'py, PyAny>> {
+        let options = decode_options(unpack_bits, reverse_predictor, expand_colormap, bands);
         let decoder_registry = decoder_registry
             .map(|r| r.inner().clone())
             .unwrap_or_else(|| get_default_decoder_registry(py));
@@ -92,14 +181,33 @@ impl PyTile {
 
         future_into_py(py, async move {
             let array = pool
-                .spawn_fifo_async(move || tile.decode(&decoder_registry))
+                .spawn_fifo_async(move || tile.decode_with_options(&decoder_registry, &options))
                 .await
-                .map_err(|e| PyValueError::new_err(e.to_string()))?;
+                .map_err(PyAsyncTiffError::from)?;
             PyArray::try_new(array).map_err(|err| err.into())
         })
     }
 }
 
+/// The [`DecodeOptions`] for the keyword arguments of the decode methods.
+///
+/// Samples are always converted to native byte order, as the array's dtype assumes it.
+fn decode_options(
+    unpack_bits: bool,
+    reverse_predictor: bool,
+    expand_colormap: bool,
+    bands: Option<Vec<usize>>,
+) -> DecodeOptions {
+    let mut options = DecodeOptions::new()
+        .with_unpack_bits(unpack_bits)
+        .with_reverse_predictor(reverse_predictor)
+        .with_expand_colormap(expand_colormap);
+    if let Some(bands) = bands {
+        options = options.with_bands(bands);
+    }
+    options
+}
+
 #[derive(IntoPyObject)]
 enum PyCompressedBytes {
     Chunky(PyBytes),
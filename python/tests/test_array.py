@@ -35,6 +35,49 @@ def test_round_trip(dtype, format_str):
     assert np.array_equal(np_array, np_view)
 
 
+@pytest.mark.parametrize(
+    "dtype,format_str",
+    [
+        (np.uint8, "<B"),
+        (np.uint16, "<H"),
+        (np.uint32, "<I"),
+        (np.uint64, "<Q"),
+        (np.int8, "<b"),
+        (np.int16, "<h"),
+        (np.int32, "<i"),
+        (np.int64, "<q"),
+        (np.float32, "<f"),
+        (np.float64, "<d"),
+    ],
+)
+def test_dlpack(dtype, format_str):
+    np_array = np.arange(24, dtype=dtype).reshape(2, 3, 4)
+    rust_array = Array(np_array.tobytes(), shape=(2, 3, 4), format=format_str)
+
+    assert rust_array.__dlpack_device__() == (1, 0)
+    dlpack_view = np.from_dlpack(rust_array)
+    assert dlpack_view.dtype == np_array.dtype
+    assert not dlpack_view.flags.writeable
+    np.testing.assert_array_equal(dlpack_view, np_array)
+
+    dlpack_copy = np.from_dlpack(rust_array, copy=True)
+    assert dlpack_copy.flags.writeable
+    np.testing.assert_array_equal(dlpack_copy, np_array)
+
+    # The capsule keeps the array alive
+    del rust_array
+    np.testing.assert_array_equal(dlpack_view, np_array)
+
+
+def test_dlpack_unsupported():
+    rust_array = Array(bytes(6), shape=(1, 2, 3), format="<B")
+    # Legacy consumers can't be given read-only memory
+    with pytest.raises(BufferError):
+        rust_array.__dlpack__(copy=False)
+    with pytest.raises(BufferError):
+        rust_array.__dlpack__(dl_device=(2, 0))
+
+
 async def test_loading_bitmask():
     tiff = await load_tiff(
         "geotiff-test-data/real_data/vantor/maxar_opendata_yellowstone_visual.tif"
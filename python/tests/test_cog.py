@@ -1,3 +1,5 @@
+from pathlib import Path
+
 import numpy as np
 import pytest
 from async_tiff import TIFF, enums
@@ -47,3 +49,30 @@ async def test_cog_missing_file():
     store = LocalStore()
     with pytest.raises(FileNotFoundError):
         await TIFF.open(path="imaginary_file.tif", store=store)
+
+
+async def test_cog_s3_url():
+    """
+    Ensure that TIFF.open infers an S3 store from an s3:// URL.
+    """
+    url = (
+        "s3://sentinel-cogs/"
+        "sentinel-s2-l2a-cogs/12/S/UF/2022/6/S2B_12SUF_20220609_0_L2A/B04.tif"
+    )
+    tiff = await TIFF.open(url, region="us-west-2", skip_signature=True)
+    assert len(tiff.ifds) == 5
+
+
+async def test_local_path_without_store():
+    """
+    Ensure that TIFF.open can open a local file path with no store.
+    """
+    fixtures_dir = Path(__file__).parent.parent.parent / "fixtures"
+    path = fixtures_dir / "other" / "geogtowgs_subset_USGS_13_s14w171.tif"
+    tiff = await TIFF.open(str(path))
+    assert tiff.endianness == enums.Endianness.LittleEndian
+
+
+async def test_options_require_inferred_store():
+    with pytest.raises(TypeError):
+        await TIFF.open("image.tif", store=LocalStore(), region="us-west-2")
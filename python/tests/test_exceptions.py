@@ -3,13 +3,22 @@ Unit tests to ensure that proper errors are raised instead of a panic.
 """
 
 import pytest
-from async_tiff import TIFF
-from async_tiff.store import HTTPStore
+from async_tiff import (
+    TIFF,
+    Array,
+    AsyncTiffError,
+    TiffFormatError,
+    TiffNotFoundError,
+)
+from async_tiff.store import HTTPStore, LocalStore
 
+from .utils import load_tiff
 
-async def test_raise_typeerror_fetch_tile_striped_tiff():
+
+async def test_raise_fetch_tile_striped_tiff():
     """
-    Ensure that a TypeError is raised when trying to fetch a tile from a striped TIFF.
+    Ensure that an AsyncTiffError is raised when trying to fetch a tile from a striped
+    TIFF.
     """
     store = HTTPStore(url="https://github.com/")
     path = "OSGeo/gdal/raw/refs/tags/v3.11.0/autotest/gdrivers/data/gtiff/int8.tif"
@@ -17,5 +26,33 @@ async def test_raise_typeerror_fetch_tile_striped_tiff():
     tiff = await TIFF.open(path=path, store=store)
     assert len(tiff.ifds) >= 1
 
-    with pytest.raises(TypeError):
+    with pytest.raises(AsyncTiffError):
         await tiff.fetch_tile(0, 0, 0)
+
+
+async def test_raise_not_found():
+    with pytest.raises(TiffNotFoundError):
+        await load_tiff("does-not-exist.tif")
+
+    # Also catchable as the builtin exception
+    with pytest.raises(FileNotFoundError):
+        await load_tiff("does-not-exist.tif")
+
+
+async def test_raise_format_error(tmp_path):
+    (tmp_path / "not-a-tiff.tif").write_bytes(b"definitely not a TIFF file")
+    store = LocalStore(tmp_path)
+    with pytest.raises(TiffFormatError) as excinfo:
+        await TIFF.open("not-a-tiff.tif", store=store)
+    assert isinstance(excinfo.value, AsyncTiffError)
+
+
+def test_exception_hierarchy():
+    assert AsyncTiffError.__name__ == "AsyncTiffError"
+    assert AsyncTiffError.__module__ == "async_tiff"
+    assert issubclass(TiffFormatError, AsyncTiffError)
+    assert issubclass(TiffNotFoundError, AsyncTiffError)
+    assert issubclass(TiffNotFoundError, FileNotFoundError)
+    # Errors from Python arguments are not wrapped
+    with pytest.raises(ValueError):
+        Array(b"", shape=(0, 0, 0), format="x")
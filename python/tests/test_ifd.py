@@ -1,3 +1,5 @@
+import pytest
+from async_tiff import TIFF, ExtensionRegistry
 from async_tiff.enums import (
     Compression,
     PhotometricInterpretation,
@@ -5,7 +7,9 @@ from async_tiff.enums import (
     SampleFormat,
 )
 
-from .utils import load_tiff
+from async_tiff.store import LocalStore
+
+from .utils import FIXTURES_DIR, load_tiff
 
 
 async def test_ifd_dict():
@@ -23,12 +27,14 @@ async def test_ifd_dict():
         "planar_configuration": PlanarConfiguration.Chunky,
         "sample_format": [SampleFormat.Float],
         "other_tags": {},
+        "extensions": {},
         "strip_offsets": [8],
         "rows_per_strip": 1,
         "strip_byte_counts": [15],
         "geo_key_directory": first_ifd.geo_key_directory,
     }
     assert dict(first_ifd) == expected_ifd
+    assert first_ifd.data_type == "f4"
 
     gkd = first_ifd.geo_key_directory
     assert gkd is not None
@@ -40,5 +46,59 @@ async def test_ifd_dict():
         "geog_angular_units": 9102,
         "geog_semi_major_axis": 6378137.0,
         "geog_inv_flattening": 298.257222101004,
+        "other_geo_keys": gkd.other_geo_keys,
     }
     assert dict(gkd) == expected_gkd
+    # GeogTOWGS84GeoKey isn't part of the GeoTIFF spec
+    assert list(gkd.other_geo_keys) == [2062]
+
+
+async def test_geotransform_bounds():
+    tiff = await load_tiff("image-tiff/geo-5b.tif")
+    ifd = tiff.ifds[0]
+    i, j, _, x, y, _ = ifd.model_tiepoint
+    scale_x, scale_y, _ = ifd.model_pixel_scale
+
+    origin_x = x - i * scale_x
+    origin_y = y + j * scale_y
+    assert ifd.geotransform() == (origin_x, scale_x, 0.0, origin_y, 0.0, -scale_y)
+
+    min_x, min_y, max_x, max_y = ifd.bounds()
+    assert min_x == origin_x
+    assert max_y == origin_y
+    assert max_x - min_x == pytest.approx(ifd.image_width * scale_x)
+    assert max_y - min_y == pytest.approx(ifd.image_height * scale_y)
+
+    # Spatial tile queries need a tiled TIFF
+    assert ifd.tiles_in_bounds((min_x, min_y, max_x, max_y)) is None
+
+
+async def test_extension_registry():
+    def parse_extra_samples(tags):
+        return {"count": len(tags[338])}
+
+    registry = ExtensionRegistry({"extra": ([338, 65000], parse_extra_samples)})
+    store = LocalStore(FIXTURES_DIR)
+    tiff = await TIFF.open("image-tiff/geo-5b.tif", store=store, extensions=registry)
+    ifd = tiff.ifds[0]
+    assert ifd.extensions == {"extra": {"count": 4}}
+    assert ifd["extensions"] == ifd.extensions
+    # Claimed tags are no longer parsed into their usual property
+    assert ifd.extra_samples is None
+    # GeoTIFF tags are still parsed
+    assert ifd.model_pixel_scale is not None
+
+
+async def test_fetch_tiles_options():
+    tiff = await load_tiff("image-tiff/tiled-rgb-u8.tif")
+    ifd = tiff.ifds[0]
+    x_count, y_count = ifd.tile_count
+    xy = [(x, y) for y in range(y_count) for x in range(x_count)]
+
+    expected = await ifd.fetch_tiles(xy)
+    for kwargs in [{"concurrency": 2}, {"coalesce": 1024}]:
+        tiles = await ifd.fetch_tiles(xy, **kwargs)
+        assert [(t.x, t.y) for t in tiles] == [(t.x, t.y) for t in expected]
+        assert [bytes(t.compressed_bytes) for t in tiles] == [
+            bytes(t.compressed_bytes) for t in expected
+        ]
@@ -72,3 +72,37 @@ async def test_read_with_obspec():
     filename = "other/geogtowgs_subset_USGS_13_s14w171.tif"
     tiff = await TIFF.open(path=filename, store=wrapper)
     assert len(tiff.ifds) > 0
+
+
+class CountingWrapper(ObstoreWrapper):
+    def __init__(self, store: LocalStore):
+        super().__init__(store)
+        self.calls = 0
+
+    async def get_range_async(self, path: str, **kwargs) -> Buffer:
+        self.calls += 1
+        return await super().get_range_async(path, **kwargs)
+
+    async def get_ranges_async(self, path: str, **kwargs) -> Sequence[Buffer]:
+        self.calls += 1
+        return await super().get_ranges_async(path, **kwargs)
+
+
+@pytest.mark.asyncio
+async def test_obspec_batches_requests():
+    filename = "image-tiff/tiled-rgb-u8.tif"
+    expected_tiff = await load_tiff(filename)
+    expected_ifd = expected_tiff.ifds[0]
+    x_count, y_count = expected_ifd.tile_count
+    xy = [(x, y) for y in range(y_count) for x in range(x_count)]
+    expected = await expected_ifd.fetch_tiles(xy)
+
+    wrapper = CountingWrapper(LocalStore(FIXTURES_DIR))
+    tiff = await TIFF.open(path=filename, store=wrapper)
+    wrapper.calls = 0
+
+    # Concurrent single-range requests are merged into fewer Python calls
+    tiles = await tiff.ifds[0].fetch_tiles(xy, concurrency=len(xy))
+    assert wrapper.calls < len(xy)
+    for tile, expected_tile in zip(tiles, expected):
+        assert bytes(tile.compressed_bytes) == bytes(expected_tile.compressed_bytes)
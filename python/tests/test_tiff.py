@@ -4,8 +4,12 @@ from typing import TYPE_CHECKING
 
 import numpy as np
 import pytest
+from async_tiff import TIFF, NoCache, Prefetch, Readahead
+from async_tiff.store import LocalStore
 from rasterio.windows import Window
 
+from .utils import FIXTURES_DIR
+
 if TYPE_CHECKING:
     from .conftest import LoadRasterio, LoadTIFF
 
@@ -53,3 +57,147 @@ async def test_header_byte_size(
         if offset != 0
     )
     assert header == expected
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize(("variant", "file_name"), [("eox", "eox_cloudless")])
+async def test_request_stats(
+    load_tiff: LoadTIFF,
+    variant: str,
+    file_name: str,
+) -> None:
+    tiff = await load_tiff(file_name, variant=variant)
+
+    stats = tiff.request_stats()
+    assert stats["metadata"]["requests"] > 0
+    assert stats["image"] == {"requests": 0, "bytes": 0, "seconds": 0.0}
+
+    await tiff.ifds[0].fetch_tile(0, 0)
+    stats = tiff.request_stats()
+    assert stats["image"]["requests"] == 1
+    assert stats["image"]["bytes"] > 0
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize(("variant", "file_name"), [("eox", "eox_cloudless")])
+async def test_read_window(
+    load_tiff: LoadTIFF,
+    load_rasterio: LoadRasterio,
+    variant: str,
+    file_name: str,
+) -> None:
+    tiff = await load_tiff(file_name, variant=variant)
+
+    window = Window(100, 200, 300, 150)
+    data = np.asarray(await tiff.read(indexes=[1, 3], window=window))
+    with load_rasterio(file_name, variant=variant) as rasterio_ds:
+        rasterio_data = rasterio_ds.read(indexes=[1, 3], window=window)
+
+    np.testing.assert_array_equal(data, rasterio_data)
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize(("variant", "file_name"), [("eox", "eox_cloudless")])
+async def test_read_window_native(
+    load_tiff: LoadTIFF,
+    load_rasterio: LoadRasterio,
+    variant: str,
+    file_name: str,
+) -> None:
+    tiff = await load_tiff(file_name, variant=variant)
+
+    # The planar image is returned as (bands, height, width)
+    data = np.asarray(await tiff.read_window(0, 100, 200, 300, 150))
+    with load_rasterio(file_name, variant=variant) as rasterio_ds:
+        rasterio_data = rasterio_ds.read(window=Window(100, 200, 300, 150))
+
+    np.testing.assert_array_equal(data, rasterio_data)
+
+    with pytest.raises(IndexError):
+        await tiff.read_window(len(tiff.ifds), 0, 0, 1, 1)
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize(("variant", "file_name"), [("eox", "eox_cloudless")])
+async def test_read_out_shape(
+    load_tiff: LoadTIFF,
+    variant: str,
+    file_name: str,
+) -> None:
+    tiff = await load_tiff(file_name, variant=variant)
+
+    data = np.asarray(await tiff.read(indexes=1, out_shape=(64, 32)))
+    assert data.shape == (1, 64, 32)
+
+    masked = await tiff.read(out_shape=(8, 8), masked=True)
+    assert isinstance(masked, np.ma.MaskedArray)
+    assert masked.shape == (tiff.ifds[0].samples_per_pixel, 8, 8)
+
+    with pytest.raises(ValueError, match="resampling"):
+        await tiff.read(resampling="bilinear")
+
+
+@pytest.mark.asyncio
+async def test_read_stripped() -> None:
+    store = LocalStore(FIXTURES_DIR)
+    tiff = await TIFF.open("image-tiff/minisblack-1c-8b.tiff", store=store)
+    assert tiff.ifds[0].tile_width is None
+
+    data = np.asarray(await tiff.read(window=((2, 10), (3, 9))))
+    native = np.asarray(await tiff.read_window(0, 3, 2, 6, 8))
+    np.testing.assert_array_equal(data[0], native[..., 0])
+
+    data = np.asarray(await tiff.read(out_shape=(4, 4)))
+    assert data.shape == (1, 4, 4)
+
+
+@pytest.mark.asyncio
+async def test_metadata_cache() -> None:
+    store = LocalStore(FIXTURES_DIR)
+    path = "geotiff-test-data/real_data/eox/eox_cloudless.tif"
+    readahead = await TIFF.open(path, store=store)
+    header = readahead.header_byte_size
+
+    tiff = await TIFF.open(path, store=store, metadata_cache=Prefetch(header))
+    assert tiff.request_stats()["metadata"]["requests"] == 1
+    assert len(tiff.ifds) == len(readahead.ifds)
+
+    tiff = await TIFF.open(path, store=store, metadata_cache=NoCache())
+    stats = tiff.request_stats()["metadata"]
+    assert stats["requests"] > readahead.request_stats()["metadata"]["requests"]
+    assert len(tiff.ifds) == len(readahead.ifds)
+
+    tiff = await TIFF.open(
+        path,
+        store=store,
+        metadata_cache=Readahead(initial=1024, multiplier=4),
+    )
+    assert len(tiff.ifds) == len(readahead.ifds)
+
+    with pytest.raises(TypeError):
+        await TIFF.open(
+            path,
+            store=store,
+            prefetch=1024,
+            metadata_cache=NoCache(),
+        )
+    with pytest.raises(ValueError, match="multiplier"):
+        Readahead(multiplier=0.5)
+
+
+@pytest.mark.asyncio
+async def test_read_ifd_at() -> None:
+    store = LocalStore(FIXTURES_DIR)
+    path = "image-tiff/tiled-rgb-u8.tif"
+    tiff = await TIFF.open(path, store=store)
+
+    # Classic little-endian TIFF: the first IFD offset follows the byte order mark
+    header = (FIXTURES_DIR / path).read_bytes()[:8]
+    assert header[:2] == b"II"
+    first_offset = int.from_bytes(header[4:8], "little")
+
+    ifd, next_offset = await tiff.read_ifd_at(first_offset)
+    assert ifd.image_width == tiff.ifds[0].image_width
+    assert ifd.tile_offsets == tiff.ifds[0].tile_offsets
+    assert (next_offset is None) == (len(tiff.ifds) == 1)
+    assert tiff.request_stats()["metadata"]["requests"] > 0
@@ -0,0 +1,62 @@
+import pickle
+
+import numpy as np
+from async_tiff import Tile
+
+from .utils import load_tiff
+
+
+async def test_pickle_tile():
+    tiff = await load_tiff("image-tiff/tiled-jpeg-ycbcr.tif")
+    ifd = tiff.ifds[0]
+    tile = await ifd.fetch_tile(0, 0)
+
+    restored = pickle.loads(pickle.dumps(tile))
+    assert isinstance(restored, Tile)
+    assert (restored.x, restored.y) == (0, 0)
+    assert bytes(restored.compressed_bytes) == bytes(tile.compressed_bytes)
+
+    expected = np.asarray(await tile.decode())
+    np.testing.assert_array_equal(np.asarray(restored.decode_sync()), expected)
+
+
+async def test_serialize_tile():
+    tiff = await load_tiff("image-tiff/tiled-jpeg-ycbcr.tif")
+    tile = await tiff.ifds[0].fetch_tile(0, 0)
+    restored = Tile.from_serialized(tile.serialize())
+    assert restored.compression_method == tile.compression_method
+
+
+async def test_decode_sync_threads():
+    from concurrent.futures import ThreadPoolExecutor
+
+    from async_tiff import ThreadPool, set_decoder_threads
+
+    tiff = await load_tiff("image-tiff/tiled-rgb-u8.tif")
+    ifd = tiff.ifds[0]
+    x_count, y_count = ifd.tile_count
+    xy = [(x, y) for y in range(y_count) for x in range(x_count)]
+    expected = [np.asarray(await tile.decode()) for tile in await ifd.fetch_tiles(xy)]
+
+    set_decoder_threads(2)
+    tiles = await ifd.fetch_tiles(xy)
+    with ThreadPoolExecutor(4) as executor:
+        decoded = list(executor.map(lambda tile: np.asarray(tile.decode_sync()), tiles))
+    for array, expected_array in zip(decoded, expected):
+        np.testing.assert_array_equal(array, expected_array)
+
+    tile = await ifd.fetch_tile(0, 0)
+    np.testing.assert_array_equal(
+        np.asarray(tile.decode_sync(pool=ThreadPool(1))), expected[0]
+    )
+
+
+async def test_decode_options():
+    tiff = await load_tiff("image-tiff/tiled-rgb-u8.tif")
+    tile = await tiff.ifds[0].fetch_tile(0, 0)
+    full = np.asarray(tile.decode_sync())
+
+    green = np.asarray(tile.decode_sync(bands=[1]))
+    np.testing.assert_array_equal(green, full[:, :, 1:2])
+    reordered = np.asarray(await tile.decode(bands=[2, 0]))
+    np.testing.assert_array_equal(reordered, full[:, :, [2, 0]])
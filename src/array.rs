@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use bytemuck::{cast_slice, cast_vec, try_cast_vec};
 
 use crate::data_type::DataType;
@@ -88,6 +90,185 @@ impl Array {
     pub fn data_type(&self) -> Option<DataType> {
         self.data_type
     }
+
+    /// Borrow this array as an [`ArrayView`], which can be cropped without copying.
+    pub fn view(&self) -> ArrayView<'_> {
+        ArrayView::from(self)
+    }
+
+    /// Copy the elements at `indices` along `axis`, in that order, into a new array, e.g. to
+    /// select and reorder bands.
+    pub(crate) fn take(&self, axis: usize, indices: &[usize]) -> AsyncTiffResult<Self> {
+        if let Some(index) = indices.iter().find(|&&i| i >= self.shape[axis]) {
+            return Err(AsyncTiffError::General(format!(
+                "index {index} out of bounds for axis {axis} with length {}",
+                self.shape[axis]
+            )));
+        }
+        let [_, d1, d2] = self.shape;
+        let mut shape = self.shape;
+        shape[axis] = indices.len();
+        let mut positions = Vec::with_capacity(shape.iter().product());
+        for i in 0..shape[0] {
+            for j in 0..shape[1] {
+                for k in 0..shape[2] {
+                    let mut index = [i, j, k];
+                    index[axis] = indices[index[axis]];
+                    positions.push((index[0] * d1 + index[1]) * d2 + index[2]);
+                }
+            }
+        }
+        let data = match &self.data {
+            TypedArray::Bool(data) => TypedArray::Bool(pick(data, &positions)),
+            TypedArray::UInt8(data) => TypedArray::UInt8(pick(data, &positions)),
+            TypedArray::UInt16(data) => TypedArray::UInt16(pick(data, &positions)),
+            TypedArray::UInt32(data) => TypedArray::UInt32(pick(data, &positions)),
+            TypedArray::UInt64(data) => TypedArray::UInt64(pick(data, &positions)),
+            TypedArray::Int8(data) => TypedArray::Int8(pick(data, &positions)),
+            TypedArray::Int16(data) => TypedArray::Int16(pick(data, &positions)),
+            TypedArray::Int32(data) => TypedArray::Int32(pick(data, &positions)),
+            TypedArray::Int64(data) => TypedArray::Int64(pick(data, &positions)),
+            TypedArray::Float32(data) => TypedArray::Float32(pick(data, &positions)),
+            TypedArray::Float64(data) => TypedArray::Float64(pick(data, &positions)),
+        };
+        Ok(Self {
+            data,
+            shape,
+            data_type: self.data_type,
+        })
+    }
+}
+
+/// The elements of `data` at `positions`.
+fn pick<T: Copy>(data: &[T], positions: &[usize]) -> Vec<T> {
+    positions.iter().map(|&p| data[p]).collect()
+}
+
+/// A strided, borrowed view of an [`Array`].
+///
+/// Views are cheap to create and crop, so windows and band subsets of decoded tiles can be
+/// described without copying any pixel data. Use [`to_array`][Self::to_array] to copy the
+/// selected elements into a new contiguous [`Array`] once they're needed.
+#[derive(Debug, Clone, Copy)]
+pub struct ArrayView<'a> {
+    data: &'a TypedArray,
+    /// The element index of the first element of the view.
+    offset: usize,
+    shape: [usize; 3],
+    /// The distance in elements between consecutive indices along each axis.
+    strides: [usize; 3],
+    data_type: Option<DataType>,
+}
+
+impl<'a> From<&'a Array> for ArrayView<'a> {
+    fn from(array: &'a Array) -> Self {
+        let [_, d1, d2] = array.shape;
+        Self {
+            data: &array.data,
+            offset: 0,
+            shape: array.shape,
+            strides: [d1 * d2, d2, 1],
+            data_type: array.data_type,
+        }
+    }
+}
+
+impl<'a> ArrayView<'a> {
+    /// The underlying data of the viewed array, including elements outside the view.
+    pub fn data(&self) -> &'a TypedArray {
+        self.data
+    }
+
+    /// The shape of the view, with the same axis ordering as [`Array::shape`].
+    pub fn shape(&self) -> [usize; 3] {
+        self.shape
+    }
+
+    /// The distance, in elements of [`data`][Self::data], between consecutive indices along each
+    /// axis.
+    pub fn strides(&self) -> [usize; 3] {
+        self.strides
+    }
+
+    /// The index into [`data`][Self::data] of the first element of the view.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The logical data type of the array elements.
+    pub fn data_type(&self) -> Option<DataType> {
+        self.data_type
+    }
+
+    /// Whether the elements of this view are contiguous in row-major order.
+    pub fn is_contiguous(&self) -> bool {
+        let [_, d1, d2] = self.shape;
+        let expected = [d1 * d2, d2, 1];
+        (0..3).all(|axis| self.shape[axis] <= 1 || self.strides[axis] == expected[axis])
+    }
+
+    /// Crop the view to the given index range along each axis, without copying.
+    ///
+    /// The ranges are relative to this view. Returns an error if a range is out of bounds.
+    pub fn slice(&self, ranges: [Range<usize>; 3]) -> AsyncTiffResult<Self> {
+        let mut view = *self;
+        for (axis, range) in ranges.into_iter().enumerate() {
+            if range.start > range.end || range.end > self.shape[axis] {
+                return Err(AsyncTiffError::General(format!(
+                    "slice {range:?} out of bounds for axis {axis} with length {}",
+                    self.shape[axis]
+                )));
+            }
+            view.offset += range.start * self.strides[axis];
+            view.shape[axis] = range.len();
+        }
+        Ok(view)
+    }
+
+    /// The index into [`data`][Self::data] of the element at `index` within this view.
+    pub fn element_index(&self, index: [usize; 3]) -> Option<usize> {
+        (0..3)
+            .all(|axis| index[axis] < self.shape[axis])
+            .then(|| self.offset + (0..3).map(|a| index[a] * self.strides[a]).sum::<usize>())
+    }
+
+    /// Copy the elements of this view into a new contiguous [`Array`].
+    pub fn to_array(&self) -> Array {
+        let data = match self.data {
+            TypedArray::Bool(data) => TypedArray::Bool(self.gather(data)),
+            TypedArray::UInt8(data) => TypedArray::UInt8(self.gather(data)),
+            TypedArray::UInt16(data) => TypedArray::UInt16(self.gather(data)),
+            TypedArray::UInt32(data) => TypedArray::UInt32(self.gather(data)),
+            TypedArray::UInt64(data) => TypedArray::UInt64(self.gather(data)),
+            TypedArray::Int8(data) => TypedArray::Int8(self.gather(data)),
+            TypedArray::Int16(data) => TypedArray::Int16(self.gather(data)),
+            TypedArray::Int32(data) => TypedArray::Int32(self.gather(data)),
+            TypedArray::Int64(data) => TypedArray::Int64(self.gather(data)),
+            TypedArray::Float32(data) => TypedArray::Float32(self.gather(data)),
+            TypedArray::Float64(data) => TypedArray::Float64(self.gather(data)),
+        };
+        Array {
+            data,
+            shape: self.shape,
+            data_type: self.data_type,
+        }
+    }
+
+    fn gather<T: Copy>(&self, data: &[T]) -> Vec<T> {
+        let [d0, d1, d2] = self.shape;
+        let mut out = Vec::with_capacity(d0 * d1 * d2);
+        for i in 0..d0 {
+            for j in 0..d1 {
+                let start = self.offset + i * self.strides[0] + j * self.strides[1];
+                if self.strides[2] == 1 {
+                    out.extend_from_slice(&data[start..start + d2]);
+                } else {
+                    out.extend((0..d2).map(|k| data[start + k * self.strides[2]]));
+                }
+            }
+        }
+        out
+    }
 }
 
 /// An enum representing a typed view of the array data.
@@ -343,3 +524,39 @@ fn expand_bitmask(data: &[u8], len: usize) -> Vec<bool> {
     }
     result
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_array_view_slice() {
+        // 2 rows, 3 columns, 2 bands
+        let data = (0..12).collect::<Vec<u8>>();
+        let array = Array::try_new(data, [2, 3, 2], Some(DataType::UInt8)).unwrap();
+        let view = array.view();
+        assert!(view.is_contiguous());
+        assert_eq!(view.to_array().data().as_ref(), array.data().as_ref());
+
+        // Second band of the last two columns
+        let cropped = view.slice([0..2, 1..3, 1..2]).unwrap();
+        assert_eq!(cropped.shape(), [2, 2, 1]);
+        assert!(!cropped.is_contiguous());
+        assert_eq!(cropped.element_index([1, 0, 0]), Some(9));
+        assert_eq!(cropped.element_index([2, 0, 0]), None);
+        let copied = cropped.to_array();
+        assert_eq!(copied.shape(), [2, 2, 1]);
+        assert_eq!(copied.data().as_ref(), [3, 5, 9, 11]);
+
+        // Slicing is relative to the view
+        let row = cropped.slice([1..2, 0..2, 0..1]).unwrap();
+        assert_eq!(row.to_array().data().as_ref(), [9, 11]);
+
+        // Whole rows stay contiguous
+        let rows = view.slice([1..2, 0..3, 0..2]).unwrap();
+        assert!(rows.is_contiguous());
+        assert_eq!(rows.offset(), 6);
+
+        assert!(view.slice([0..3, 0..3, 0..2]).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use crate::decoder::DecoderRegistry;
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::reader::AsyncFileReader;
+use crate::tags::PlanarConfiguration;
+use crate::{Array, DataType, ImageFileDirectory, Nodata, Window};
+
+/// A single band of an image, like a GDAL raster band.
+///
+/// Created with [`ImageFileDirectory::band`]. Reads return only this band, sliced out of the
+/// interleaved samples of chunky images or the band planes of planar images, so callers that
+/// want one band of an RGB image don't need to handle the layout themselves.
+///
+/// The returned arrays keep the axis order of the image, `(height, width, 1)` for chunky and
+/// `(1, height, width)` for planar images, which have the same memory layout.
+#[derive(Debug, Clone, Copy)]
+pub struct RasterBand<'a> {
+    ifd: &'a ImageFileDirectory,
+    index: usize,
+}
+
+impl<'a> RasterBand<'a> {
+    pub(crate) fn new(ifd: &'a ImageFileDirectory, index: usize) -> Option<Self> {
+        (index < ifd.samples_per_pixel() as usize).then_some(Self { ifd, index })
+    }
+
+    /// The image this band belongs to.
+    pub fn ifd(&self) -> &'a ImageFileDirectory {
+        self.ifd
+    }
+
+    /// The zero-based index of this band.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The data type of this band's pixels.
+    pub fn data_type(&self) -> Option<DataType> {
+        self.ifd.data_type()
+    }
+
+    /// The GDAL NoData value of this band.
+    ///
+    /// GeoTIFF stores a single nodata value, which applies to every band of the image.
+    pub fn nodata(&self) -> Option<Nodata> {
+        self.ifd.nodata()
+    }
+
+    /// Read an arbitrary pixel window of this band into a single [`Array`].
+    ///
+    /// The tiles or strips intersecting `window` are fetched and decoded for all bands, as the
+    /// bands of a tile are compressed together, and then this band is copied out.
+    pub async fn read_window(
+        &self,
+        window: Window,
+        reader: &dyn AsyncFileReader,
+        decoder_registry: &DecoderRegistry,
+    ) -> AsyncTiffResult<Array> {
+        let array = self
+            .ifd
+            .read_window(window, reader, decoder_registry)
+            .await?;
+        self.extract(&array)
+    }
+
+    /// Fetch and decode the tile located at `x` column and `y` row, keeping only this band.
+    pub async fn read_tile(
+        &self,
+        x: usize,
+        y: usize,
+        reader: &dyn AsyncFileReader,
+        decoder_registry: &DecoderRegistry,
+    ) -> AsyncTiffResult<Array> {
+        let tile = self.ifd.fetch_tile(x, y, reader).await?;
+        self.extract(&tile.decode_async(decoder_registry).await?)
+    }
+
+    /// Copy this band out of an array holding all bands of the image, such as a decoded tile or
+    /// the result of [`ImageFileDirectory::read_window`].
+    pub fn extract(&self, array: &Array) -> AsyncTiffResult<Array> {
+        let bands = self.ifd.samples_per_pixel() as usize;
+        let [d0, d1, d2] = array.shape();
+        let (axis, ranges) = match self.ifd.planar_configuration() {
+            PlanarConfiguration::Chunky => (2, [0..d0, 0..d1, self.index..self.index + 1]),
+            PlanarConfiguration::Planar => (0, [self.index..self.index + 1, 0..d1, 0..d2]),
+        };
+        if array.shape()[axis] != bands {
+            return Err(AsyncTiffError::General(format!(
+                "Expected {bands} bands along axis {axis} of an array with shape {:?}",
+                array.shape()
+            )));
+        }
+        Ok(array.view().slice(ranges)?.to_array())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::util::open_tiff;
+
+    #[tokio::test]
+    async fn test_raster_band() {
+        let (reader, tiff) = open_tiff("image-tiff/tiled-rgb-u8.tif").await;
+        let ifd = &tiff.ifds()[0];
+        assert_eq!(ifd.samples_per_pixel(), 3);
+        assert!(ifd.band(3).is_none());
+
+        let registry = DecoderRegistry::default();
+        let window = Window::new(3, 5, 20, 10);
+        let all = ifd
+            .read_window(window, reader.as_ref(), &registry)
+            .await
+            .unwrap();
+        let band = ifd.band(1).unwrap();
+        assert_eq!(band.index(), 1);
+        assert_eq!(band.data_type(), Some(DataType::UInt8));
+        let green = band
+            .read_window(window, reader.as_ref(), &registry)
+            .await
+            .unwrap();
+        assert_eq!(green.shape(), [10, 20, 1]);
+        let (all, green) = (all.data().as_ref(), green.data().as_ref());
+        let expected = all.iter().skip(1).step_by(3).copied().collect::<Vec<_>>();
+        assert_eq!(green, expected);
+
+        let tile = band
+            .read_tile(0, 0, reader.as_ref(), &registry)
+            .await
+            .unwrap();
+        assert_eq!(tile.shape()[2], 1);
+        assert!(band.extract(&tile).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_raster_band_planar() {
+        let (reader, tiff) = open_tiff("image-tiff/planar-rgb-u8.tif").await;
+        let ifd = &tiff.ifds()[0];
+        assert_eq!(ifd.planar_configuration(), PlanarConfiguration::Planar);
+
+        let registry = DecoderRegistry::default();
+        let window = Window::new(0, 0, ifd.image_width().min(16), ifd.image_height().min(8));
+        let (height, width) = (window.height as usize, window.width as usize);
+        let all = ifd
+            .read_window(window, reader.as_ref(), &registry)
+            .await
+            .unwrap();
+        let blue = ifd
+            .band(2)
+            .unwrap()
+            .read_window(window, reader.as_ref(), &registry)
+            .await
+            .unwrap();
+        assert_eq!(blue.shape(), [1, height, width]);
+        let plane = height * width;
+        assert_eq!(
+            blue.data().as_ref(),
+            &all.data().as_ref()[2 * plane..3 * plane]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! A synchronous API for callers that don't use async Rust, enabled by the `blocking` feature.
+//!
+//! [`BlockingTiff`] drives the async API on a single-threaded Tokio runtime that it owns, so
+//! scripts and GDAL-style tools can read TIFFs without setting up a runtime themselves.
+//!
+//! The blocking calls must not be made from within an async runtime, as Tokio panics when a
+//! runtime is blocked on from inside another. Use the async API there instead.
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//!
+//! use async_tiff::blocking::BlockingTiff;
+//! use async_tiff::decoder::DecoderRegistry;
+//! use async_tiff::reader::ObjectReader;
+//! use async_tiff::Window;
+//! use object_store::local::LocalFileSystem;
+//!
+//! let store = Arc::new(LocalFileSystem::new());
+//! let reader = Arc::new(ObjectReader::new(store, "/data/image.tif".into()));
+//! let tiff = BlockingTiff::open(reader).unwrap();
+//!
+//! let tile = tiff.fetch_tile(0, 0, 0).unwrap();
+//! let array = tiff
+//!     .read_window(0, Window::new(0, 0, 256, 256), &DecoderRegistry::default())
+//!     .unwrap();
+//! ```
+
+use std::future::Future;
+use std::sync::Arc;
+
+use tokio::runtime::Runtime;
+
+use crate::config::AsyncTiffConfig;
+use crate::decoder::DecoderRegistry;
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::reader::AsyncFileReader;
+use crate::{Array, ImageFileDirectory, Tile, Window, TIFF};
+
+/// A TIFF opened with blocking calls.
+///
+/// Cloning is cheap, and clones share the same runtime and reader.
+#[derive(Debug, Clone)]
+pub struct BlockingTiff {
+    tiff: Arc<TIFF>,
+    reader: Arc<dyn AsyncFileReader>,
+    runtime: Arc<Runtime>,
+}
+
+impl BlockingTiff {
+    /// Read all metadata from `reader` using the process-wide defaults of
+    /// [`AsyncTiffConfig::global`].
+    pub fn open(reader: Arc<dyn AsyncFileReader>) -> AsyncTiffResult<Self> {
+        Self::open_with_config(reader, &AsyncTiffConfig::global())
+    }
+
+    /// Read all metadata from `reader`, prefetching and limiting requests according to `config`.
+    pub fn open_with_config(
+        reader: Arc<dyn AsyncFileReader>,
+        config: &AsyncTiffConfig,
+    ) -> AsyncTiffResult<Self> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        Self::open_with_runtime(reader, config, Arc::new(runtime))
+    }
+
+    /// Read all metadata from `reader` like [`open_with_config`][Self::open_with_config], driving
+    /// requests on an existing `runtime`, e.g. to share one runtime between many files.
+    pub fn open_with_runtime(
+        reader: Arc<dyn AsyncFileReader>,
+        config: &AsyncTiffConfig,
+        runtime: Arc<Runtime>,
+    ) -> AsyncTiffResult<Self> {
+        let tiff = runtime.block_on(TIFF::open_with_config(reader.clone(), config))?;
+        Ok(Self {
+            tiff: Arc::new(tiff),
+            reader,
+            runtime,
+        })
+    }
+
+    /// Access the underlying [`TIFF`], e.g. to pass its IFDs to other async APIs.
+    pub fn tiff(&self) -> &TIFF {
+        &self.tiff
+    }
+
+    /// The Image File Directories of this TIFF, which are all read by [`open`][Self::open].
+    pub fn read_ifds(&self) -> &[ImageFileDirectory] {
+        self.tiff.ifds()
+    }
+
+    /// Fetch the tile located at `x` column and `y` row of the IFD at index `ifd`.
+    pub fn fetch_tile(&self, ifd: usize, x: usize, y: usize) -> AsyncTiffResult<Tile> {
+        let ifd = self.ifd(ifd)?;
+        self.block_on(ifd.fetch_tile(x, y, self.reader.as_ref()))
+    }
+
+    /// Fetch the tiles located at `x` column and `y` row of the IFD at index `ifd`.
+    pub fn fetch_tiles(&self, ifd: usize, xy: &[(usize, usize)]) -> AsyncTiffResult<Vec<Tile>> {
+        let ifd = self.ifd(ifd)?;
+        self.block_on(ifd.fetch_tiles(xy, self.reader.as_ref()))
+    }
+
+    /// Read an arbitrary pixel window of the IFD at index `ifd` into a single [`Array`]. See
+    /// [`ImageFileDirectory::read_window`].
+    pub fn read_window(
+        &self,
+        ifd: usize,
+        window: Window,
+        decoder_registry: &DecoderRegistry,
+    ) -> AsyncTiffResult<Array> {
+        let ifd = self.ifd(ifd)?;
+        self.block_on(ifd.read_window(window, self.reader.as_ref(), decoder_registry))
+    }
+
+    /// Run any other future, e.g. one from the async API, to completion on this TIFF's runtime.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    fn ifd(&self, index: usize) -> AsyncTiffResult<&ImageFileDirectory> {
+        self.tiff.ifds().get(index).ok_or_else(|| {
+            AsyncTiffError::General(format!(
+                "IFD index {index} out of range for a TIFF with {} IFDs",
+                self.tiff.ifds().len()
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use object_store::local::LocalFileSystem;
+
+    use super::*;
+    use crate::reader::ObjectReader;
+
+    fn open(filename: &str) -> BlockingTiff {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let store = Arc::new(LocalFileSystem::new_with_prefix(manifest_dir).unwrap());
+        let path = format!("fixtures/{filename}");
+        let reader = Arc::new(ObjectReader::new(store, path.as_str().into()));
+        BlockingTiff::open(reader).unwrap()
+    }
+
+    #[test]
+    fn test_blocking_tiff() {
+        let tiff = open("image-tiff/tiled-rgb-u8.tif");
+        assert_eq!(tiff.read_ifds().len(), tiff.tiff().ifds().len());
+        let ifd = &tiff.read_ifds()[0];
+
+        let tile = tiff.fetch_tile(0, 1, 0).unwrap();
+        assert_eq!((tile.x(), tile.y()), (1, 0));
+        let tiles = tiff.fetch_tiles(0, &[(0, 0), (1, 0)]).unwrap();
+        assert_eq!(tiles.len(), 2);
+
+        let registry = DecoderRegistry::default();
+        let window = Window::new(0, 0, ifd.image_width().min(20), 10);
+        let array = tiff.read_window(0, window, &registry).unwrap();
+        assert_eq!(array.shape()[..2], [10, window.cols().unwrap().len()]);
+
+        // Clones share the runtime
+        let clone = tiff.clone();
+        let tile = clone.fetch_tile(0, 0, 0).unwrap();
+        assert_eq!(
+            tile.decode(&registry).unwrap().data().as_ref(),
+            tiles[0].clone().decode(&registry).unwrap().data().as_ref()
+        );
+
+        assert!(tiff.fetch_tile(tiff.read_ifds().len(), 0, 0).is_err());
+    }
+}
@@ -0,0 +1,384 @@
+//! Validation of Cloud-Optimized GeoTIFFs.
+//!
+//! [`validate`] checks the rules of the [COG specification] that make a file efficient to read
+//! over HTTP, in the spirit of GDAL's `validate_cloud_optimized_geotiff.py` and rio-cogeo: the
+//! image is tiled and has overviews, and all IFDs are stored at the start of the file, before
+//! the image data.
+//!
+//! [COG specification]: https://docs.ogc.org/is/21-026/21-026.html
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::metadata::{ImageFileDirectoryReader, MetadataFetch, TiffMetadataReader};
+use crate::{ImageFileDirectory, TIFF};
+
+/// The prefix of the GDAL structural metadata block that directly follows the TIFF header.
+const STRUCTURAL_METADATA_PREFIX: &[u8] = b"GDAL_STRUCTURAL_METADATA_SIZE=";
+
+/// The length of the line announcing the size of the structural metadata, e.g.
+/// `GDAL_STRUCTURAL_METADATA_SIZE=000140 bytes\n`.
+const STRUCTURAL_METADATA_HEADER_SIZE: u64 = 43;
+
+/// Images larger than this in either dimension are expected to be tiled and have overviews.
+const MAX_UNTILED_SIZE: u32 = 512;
+
+/// The number of bytes GDAL reads in its first request, which the metadata should fit in.
+const RECOMMENDED_HEADER_SIZE: u64 = 16 * 1024;
+
+/// Where the IFDs of a file are stored, which isn't kept in the parsed [`TIFF`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataLayout {
+    /// Whether the file is a BigTIFF.
+    pub bigtiff: bool,
+    /// The byte offsets of the IFDs in the main IFD chain, in chain order.
+    pub ifd_offsets: Vec<u64>,
+    /// The GDAL structural metadata stored directly after the TIFF header, if any.
+    pub structural_metadata: Option<String>,
+}
+
+impl MetadataLayout {
+    /// Read the layout of the file by walking its IFD chain, without parsing any tags.
+    pub async fn read<F: MetadataFetch>(fetch: &F) -> AsyncTiffResult<Self> {
+        let metadata_reader = TiffMetadataReader::try_open(fetch).await?;
+        let bigtiff = metadata_reader.bigtiff();
+        let endianness = metadata_reader.endianness();
+
+        let mut ifd_offsets = vec![];
+        let mut visited = HashSet::new();
+        let mut next_ifd_offset = metadata_reader.next_ifd_offset();
+        while let Some(offset) = next_ifd_offset {
+            if !visited.insert(offset) {
+                return Err(AsyncTiffError::General(format!(
+                    "IFD chain loops back to offset {offset}"
+                )));
+            }
+            ifd_offsets.push(offset);
+            next_ifd_offset = ImageFileDirectoryReader::open(fetch, offset, bigtiff, endianness)
+                .await?
+                .finish(fetch)
+                .await?;
+        }
+
+        let header_size = header_size(bigtiff);
+        let header = fetch
+            .fetch(header_size..header_size + STRUCTURAL_METADATA_HEADER_SIZE)
+            .await
+            .unwrap_or_default();
+        let mut structural_metadata = None;
+        if let Some(size) = header
+            .strip_prefix(STRUCTURAL_METADATA_PREFIX)
+            .and_then(|rest| std::str::from_utf8(rest.get(..6)?).ok())
+            .and_then(|size| size.parse::<u64>().ok())
+        {
+            let start = header_size + STRUCTURAL_METADATA_HEADER_SIZE;
+            let bytes = fetch.fetch(start..start + size).await?;
+            structural_metadata = Some(String::from_utf8_lossy(&bytes).into_owned());
+        }
+
+        Ok(Self {
+            bigtiff,
+            ifd_offsets,
+            structural_metadata,
+        })
+    }
+
+    /// The byte offset right after the TIFF header and the structural metadata, where the first
+    /// IFD of a COG starts.
+    fn metadata_start(&self) -> u64 {
+        let header_size = header_size(self.bigtiff);
+        match &self.structural_metadata {
+            Some(metadata) => header_size + STRUCTURAL_METADATA_HEADER_SIZE + metadata.len() as u64,
+            None => header_size,
+        }
+    }
+
+    /// The value of `key` in the structural metadata, which is stored as `KEY=VALUE` lines.
+    fn structural_metadata_value(&self, key: &str) -> Option<&str> {
+        self.structural_metadata
+            .as_deref()?
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+    }
+}
+
+fn header_size(bigtiff: bool) -> u64 {
+    if bigtiff {
+        16
+    } else {
+        8
+    }
+}
+
+/// How serious a [`ValidationIssue`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// The file is not a valid COG.
+    Error,
+    /// The file is a valid COG, but can't be read as efficiently as it could be.
+    Warning,
+}
+
+/// A problem found by [`validate`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationIssue {
+    /// How serious the problem is.
+    pub severity: Severity,
+    /// The index of the IFD the problem is about, if it's about a single IFD.
+    pub ifd: Option<usize>,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match self.ifd {
+            Some(ifd) => write!(f, "{severity}: IFD {ifd}: {}", self.message),
+            None => write!(f, "{severity}: {}", self.message),
+        }
+    }
+}
+
+/// The result of [`validate`].
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValidationReport {
+    /// All problems found, in the order they were checked.
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Whether the file is a valid COG, i.e. no errors were found. There may still be warnings.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// The problems that make the file an invalid COG.
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Error)
+    }
+
+    /// The problems that make the file less efficient to read.
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity == Severity::Warning)
+    }
+
+    fn push(&mut self, severity: Severity, ifd: Option<usize>, message: String) {
+        self.issues.push(ValidationIssue {
+            severity,
+            ifd,
+            message,
+        });
+    }
+}
+
+/// Check whether `tiff` is a valid Cloud-Optimized GeoTIFF.
+///
+/// `layout` must be read from the same file as `tiff`. The following is checked:
+///
+/// - Every image IFD is tiled. Small images without overviews may be stripped.
+/// - Images larger than 512 pixels have reduced-resolution overviews.
+/// - The first IFD directly follows the header, and all IFDs are stored in chain order before
+///   any image data.
+/// - The image data of smaller overviews is stored before that of larger ones, with the
+///   full-resolution image last, and the tiles of each image are stored in row-major order.
+/// - The metadata fits in the first 16 KiB that GDAL reads.
+/// - The GDAL structural metadata, if present, doesn't flag the file as modified after it was
+///   written.
+pub fn validate(tiff: &TIFF, layout: &MetadataLayout) -> ValidationReport {
+    let mut report = ValidationReport::default();
+    let ifds = tiff.ifds();
+    if ifds.len() != layout.ifd_offsets.len() {
+        report.push(
+            Severity::Error,
+            None,
+            format!(
+                "the layout has {} IFDs but the TIFF has {}",
+                layout.ifd_offsets.len(),
+                ifds.len()
+            ),
+        );
+        return report;
+    }
+    let Some(main) = ifds.first() else {
+        report.push(Severity::Error, None, "the file has no IFDs".to_string());
+        return report;
+    };
+
+    // Tiling and overviews
+    let is_mask = |ifd: &ImageFileDirectory| ifd.new_subfile_type().unwrap_or(0) & 4 != 0;
+    let overview_count = ifds[1..]
+        .iter()
+        .filter(|ifd| !is_mask(ifd) && ifd.new_subfile_type().unwrap_or(0) & 1 != 0)
+        .count();
+    let large = main.image_width() > MAX_UNTILED_SIZE || main.image_height() > MAX_UNTILED_SIZE;
+    for (i, ifd) in ifds.iter().enumerate() {
+        if ifd.tile_offsets().is_none() && (large || ifds.len() > 1) {
+            report.push(
+                Severity::Error,
+                Some(i),
+                "the image is not tiled".to_string(),
+            );
+        }
+    }
+    if large && overview_count == 0 {
+        report.push(
+            Severity::Warning,
+            None,
+            format!("the image is larger than {MAX_UNTILED_SIZE} pixels but has no overviews",),
+        );
+    }
+
+    // IFDs before the image data
+    let first_offset = layout.ifd_offsets[0];
+    let metadata_start = layout.metadata_start();
+    if first_offset > metadata_start.next_multiple_of(8) {
+        report.push(
+            Severity::Error,
+            Some(0),
+            format!(
+                "the first IFD is at offset {first_offset} instead of directly after the header at \
+                 offset {metadata_start}"
+            ),
+        );
+    }
+    for (i, pair) in layout.ifd_offsets.windows(2).enumerate() {
+        if pair[1] < pair[0] {
+            report.push(
+                Severity::Error,
+                Some(i + 1),
+                format!(
+                    "the IFD is at offset {}, before the previous IFD at offset {}",
+                    pair[1], pair[0]
+                ),
+            );
+        }
+    }
+    let data_offsets = |ifd: &ImageFileDirectory| {
+        ifd.tile_offsets()
+            .or(ifd.strip_offsets())
+            .unwrap_or_default()
+            .iter()
+            .copied()
+            .filter(|&offset| offset != 0)
+            .collect::<Vec<_>>()
+    };
+    let data_start = ifds.iter().flat_map(data_offsets).min().unwrap_or(u64::MAX);
+    for (i, &offset) in layout.ifd_offsets.iter().enumerate() {
+        if offset > data_start {
+            report.push(
+                Severity::Error,
+                Some(i),
+                format!("the IFD is at offset {offset}, after image data at offset {data_start}"),
+            );
+        }
+    }
+    if data_start != u64::MAX && data_start > RECOMMENDED_HEADER_SIZE {
+        report.push(
+            Severity::Warning,
+            None,
+            format!(
+                "the metadata takes {data_start} bytes, more than the {RECOMMENDED_HEADER_SIZE} \
+                 bytes read by GDAL in its first request"
+            ),
+        );
+    }
+
+    // Order of the image data
+    let mut previous: Option<(usize, u64)> = None;
+    for (i, ifd) in ifds.iter().enumerate().rev() {
+        let offsets = data_offsets(ifd);
+        if offsets.windows(2).any(|pair| pair[1] < pair[0]) {
+            report.push(
+                Severity::Warning,
+                Some(i),
+                "the tiles are not stored in row-major order".to_string(),
+            );
+        }
+        if is_mask(ifd) {
+            continue;
+        }
+        let Some(&first) = offsets.first() else {
+            continue;
+        };
+        if let Some((previous_ifd, previous_first)) = previous {
+            if first < previous_first {
+                report.push(
+                    Severity::Error,
+                    Some(i),
+                    format!(
+                        "the image data starts at offset {first}, before that of the smaller \
+                         IFD {previous_ifd} at offset {previous_first}"
+                    ),
+                );
+            }
+        }
+        previous = Some((i, first));
+    }
+
+    if layout.structural_metadata_value("KNOWN_INCOMPATIBLE_EDITION") == Some("YES") {
+        report.push(
+            Severity::Error,
+            None,
+            "the GDAL structural metadata flags the file as modified after it was written"
+                .to_string(),
+        );
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::util::open_tiff;
+
+    #[tokio::test]
+    async fn test_validate_stripped_tiff() {
+        let (reader, tiff) = open_tiff("image-tiff/rgb-3c-8b.tiff").await;
+        let layout = MetadataLayout::read(&reader).await.unwrap();
+        assert_eq!(layout.ifd_offsets.len(), 1);
+        assert_eq!(layout.structural_metadata, None);
+
+        // libtiff writes the IFD after the image data
+        let report = validate(&tiff, &layout);
+        assert!(!report.is_valid());
+        assert!(report
+            .errors()
+            .any(|issue| issue.message.contains("after image data")));
+    }
+
+    #[cfg(feature = "transcode")]
+    #[tokio::test]
+    async fn test_validate_transcoded() {
+        use crate::test::util::{open_tiff_path, temp_path};
+        use crate::writer::{transcode, TranscodeOptions};
+
+        let (reader, tiff) = open_tiff("image-tiff/rgb-3c-8b.tiff").await;
+        let options = TranscodeOptions::default().with_tile_size(16);
+        let path = temp_path("cog.tif");
+        let file = std::fs::File::create(&path).unwrap();
+        transcode(&tiff, reader.as_ref(), &options, file)
+            .await
+            .unwrap();
+
+        let (reader, tiff) = open_tiff_path(&path).await;
+        let layout = MetadataLayout::read(&reader).await.unwrap();
+        assert_eq!(layout.ifd_offsets.len(), tiff.ifds().len());
+        let report = validate(&tiff, &layout);
+        assert!(report.is_valid(), "{:?}", report.issues);
+
+        // Dropping the overviews from the TIFF no longer matches the layout
+        let main = TIFF::new(vec![tiff.ifds()[0].clone()], tiff.endianness());
+        assert!(!validate(&main, &layout).is_valid());
+        std::fs::remove_file(path).unwrap();
+    }
+}
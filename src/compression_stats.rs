@@ -0,0 +1,146 @@
+use crate::tags::PlanarConfiguration;
+use crate::ImageFileDirectory;
+
+/// Summary statistics of the compressed sizes of the tiles or strips in an IFD.
+///
+/// These are computed purely from the `TileByteCounts` or `StripByteCounts` tag, so no image
+/// data needs to be fetched. Use [`ImageFileDirectory::compression_stats`] to create them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompressionStats {
+    /// The number of tiles or strips, including empty ones.
+    pub count: usize,
+    /// The number of tiles or strips with a byte count of zero, which are not stored in the
+    /// file and decode to fill values.
+    pub empty_count: usize,
+    /// The smallest byte count of any non-empty tile or strip, or 0 if all are empty.
+    pub min: u64,
+    /// The largest byte count of any tile or strip.
+    pub max: u64,
+    /// The mean byte count of the non-empty tiles or strips, or 0 if all are empty.
+    pub mean: f64,
+    /// The total number of compressed bytes.
+    pub total: u64,
+    /// The size in bytes of one full tile or strip when decoded.
+    pub uncompressed_chunk_size: u64,
+    /// Byte counts of non-empty tiles or strips bucketed by powers of two: `histogram[i]` is the
+    /// number of chunks with `2^i <= byte_count < 2^(i + 1)`. Trailing empty buckets are omitted.
+    pub histogram: Vec<usize>,
+}
+
+impl CompressionStats {
+    pub(crate) fn from_ifd(ifd: &ImageFileDirectory) -> Option<Self> {
+        let (byte_counts, chunk_width, chunk_height) = match ifd.tile_byte_counts() {
+            Some(byte_counts) => (byte_counts, ifd.tile_width()?, ifd.tile_height()?),
+            None => (
+                ifd.strip_byte_counts()?,
+                ifd.image_width(),
+                ifd.strip_height(),
+            ),
+        };
+        let samples = match ifd.planar_configuration() {
+            PlanarConfiguration::Planar => 1,
+            _ => ifd.samples_per_pixel() as u64,
+        };
+        let bits_per_sample = ifd.bits_per_sample().first().copied().unwrap_or(8) as u64;
+        let row_bits = (chunk_width as u64)
+            .checked_mul(samples)?
+            .checked_mul(bits_per_sample)?;
+        let uncompressed_chunk_size = row_bits.div_ceil(8).checked_mul(chunk_height as u64)?;
+        Self::from_byte_counts(byte_counts, uncompressed_chunk_size)
+    }
+
+    /// Returns `None` if the byte counts sum to more than `u64::MAX`, which only a corrupt file
+    /// can declare.
+    fn from_byte_counts(byte_counts: &[u64], uncompressed_chunk_size: u64) -> Option<Self> {
+        let mut histogram = vec![];
+        for &byte_count in byte_counts.iter().filter(|&&b| b > 0) {
+            let bucket = byte_count.ilog2() as usize;
+            if histogram.len() <= bucket {
+                histogram.resize(bucket + 1, 0);
+            }
+            histogram[bucket] += 1;
+        }
+        let empty_count = byte_counts.iter().filter(|&&b| b == 0).count();
+        let non_empty = byte_counts.len() - empty_count;
+        let total = byte_counts
+            .iter()
+            .try_fold(0u64, |total, &b| total.checked_add(b))?;
+        Some(Self {
+            count: byte_counts.len(),
+            empty_count,
+            min: byte_counts
+                .iter()
+                .copied()
+                .filter(|&b| b > 0)
+                .min()
+                .unwrap_or(0),
+            max: byte_counts.iter().copied().max().unwrap_or(0),
+            mean: if non_empty > 0 {
+                total as f64 / non_empty as f64
+            } else {
+                0.0
+            },
+            total,
+            uncompressed_chunk_size,
+            histogram,
+        })
+    }
+
+    /// The ratio of decoded to compressed size over all non-empty tiles or strips.
+    ///
+    /// Partial tiles at the image edge are stored at full size, while the last strip may be
+    /// stored shorter, so this slightly underestimates the ratio for stripped images. Returns
+    /// `None` if every chunk is empty.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        if self.total == 0 {
+            return None;
+        }
+        // Multiplying in floating point can't overflow for chunk sizes declared by corrupt files
+        let non_empty = (self.count - self.empty_count) as f64;
+        Some(non_empty * self.uncompressed_chunk_size as f64 / self.total as f64)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::util::open_tiff;
+
+    #[test]
+    fn test_from_byte_counts() {
+        let stats = CompressionStats::from_byte_counts(&[0, 1, 3, 4, 100, 0], 400).unwrap();
+        assert_eq!(stats.count, 6);
+        assert_eq!(stats.empty_count, 2);
+        assert_eq!(stats.min, 1);
+        assert_eq!(stats.max, 100);
+        assert_eq!(stats.total, 108);
+        assert_eq!(stats.mean, 27.0);
+        assert_eq!(stats.histogram, vec![1, 1, 1, 0, 0, 0, 1]);
+        assert_eq!(stats.compression_ratio(), Some(1600.0 / 108.0));
+
+        let stats = CompressionStats::from_byte_counts(&[0, 0], 400).unwrap();
+        assert_eq!(stats.min, 0);
+        assert_eq!(stats.mean, 0.0);
+        assert!(stats.histogram.is_empty());
+        assert_eq!(stats.compression_ratio(), None);
+
+        assert!(CompressionStats::from_byte_counts(&[u64::MAX, 1], 400).is_none());
+        let stats = CompressionStats::from_byte_counts(&[1, 1], u64::MAX).unwrap();
+        assert_eq!(stats.compression_ratio(), Some(u64::MAX as f64));
+    }
+
+    #[tokio::test]
+    async fn test_compression_stats_tiled() {
+        let (_, tiff) = open_tiff("image-tiff/tiled-rgb-u8.tif").await;
+        let ifd = &tiff.ifds()[0];
+        let stats = ifd.compression_stats().unwrap();
+        let byte_counts = ifd.tile_byte_counts().unwrap();
+        assert_eq!(stats.count, byte_counts.len());
+        assert_eq!(stats.total, byte_counts.iter().sum::<u64>());
+        assert_eq!(
+            stats.uncompressed_chunk_size,
+            ifd.tile_width().unwrap() as u64 * ifd.tile_height().unwrap() as u64 * 3
+        );
+        assert_eq!(stats.histogram.iter().sum::<usize>(), stats.count);
+    }
+}
@@ -0,0 +1,423 @@
+//! Process-wide default configuration.
+//!
+//! Services often want to set defaults such as the metadata prefetch size once, rather than at
+//! every call site. [`AsyncTiffConfig::global`] holds these defaults; it is initialized from
+//! environment variables and can be replaced with [`AsyncTiffConfig::set_global`]. It is
+//! consulted by convenience constructors such as [`TIFF::open`][crate::TIFF::open] and by the
+//! Python bindings.
+//!
+//! | Environment variable               | Setting                                          |
+//! | ---------------------------------- | ------------------------------------------------ |
+//! | `ASYNC_TIFF_PREFETCH`              | [`prefetch`][AsyncTiffConfig::prefetch]          |
+//! | `ASYNC_TIFF_PREFETCH_MULTIPLIER`   | [`multiplier`][AsyncTiffConfig::multiplier]      |
+//! | `ASYNC_TIFF_REQUEST_BUDGET`        | [`request_budget`][AsyncTiffConfig::request_budget] |
+//! | `ASYNC_TIFF_FETCH_CONCURRENCY`     | [`fetch_options`][AsyncTiffConfig::fetch_options] concurrency |
+//! | `ASYNC_TIFF_FETCH_COALESCE`        | [`fetch_options`][AsyncTiffConfig::fetch_options] coalescing gap |
+//! | `ASYNC_TIFF_FETCH_MAX_COALESCED_SIZE` | [`fetch_options`][AsyncTiffConfig::fetch_options] maximum merged request size |
+//! | `ASYNC_TIFF_METADATA_CACHE`        | [`metadata_cache`][AsyncTiffConfig::metadata_cache]: `readahead`, `prefetch` or `none` |
+//! | `ASYNC_TIFF_MAX_RETRIES`           | [`retry`][AsyncTiffConfig::retry] attempts after the first |
+//! | `ASYNC_TIFF_RETRY_INITIAL_BACKOFF_MS` | [`retry`][AsyncTiffConfig::retry] delay before the first retry |
+//! | `ASYNC_TIFF_RETRY_MAX_BACKOFF_MS`  | [`retry`][AsyncTiffConfig::retry] maximum delay between attempts |
+//!
+//! The retry variables require the `reqwest` feature, and setting any of them enables retries.
+
+use std::str::FromStr;
+use std::sync::RwLock;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::metadata::ParseOptions;
+#[cfg(feature = "reqwest")]
+use crate::reader::RetryPolicy;
+use crate::FetchOptions;
+
+/// How metadata is cached while opening a TIFF.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MetadataCacheMode {
+    /// Fetch [`prefetch`][AsyncTiffConfig::prefetch] bytes up front, growing each later fetch by
+    /// [`multiplier`][AsyncTiffConfig::multiplier]. See
+    /// [`ReadaheadMetadataCache`][crate::metadata::cache::ReadaheadMetadataCache].
+    #[default]
+    Readahead,
+    /// Fetch [`prefetch`][AsyncTiffConfig::prefetch] bytes up front, and afterwards only the bytes
+    /// that are needed. See [`PrefetchBuffer`][crate::metadata::cache::PrefetchBuffer].
+    Prefetch,
+    /// Fetch exactly the bytes of each metadata read, without caching.
+    None,
+}
+
+impl FromStr for MetadataCacheMode {
+    type Err = AsyncTiffError;
+
+    /// Parse `readahead`, `prefetch` or `none`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "readahead" => Ok(Self::Readahead),
+            "prefetch" => Ok(Self::Prefetch),
+            "none" => Ok(Self::None),
+            _ => Err(AsyncTiffError::General(format!(
+                "unknown metadata cache mode {s:?}, expected readahead, prefetch or none"
+            ))),
+        }
+    }
+}
+
+static GLOBAL: RwLock<Option<AsyncTiffConfig>> = RwLock::new(None);
+
+/// Default settings for opening TIFFs and fetching their data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AsyncTiffConfig {
+    prefetch: u64,
+    multiplier: f64,
+    metadata_cache: MetadataCacheMode,
+    request_budget: Option<usize>,
+    fetch_concurrency: Option<usize>,
+    fetch_coalesce: Option<u64>,
+    fetch_max_coalesced_size: Option<u64>,
+    parse_options: ParseOptions,
+    #[cfg(feature = "reqwest")]
+    retry: Option<RetryPolicy>,
+}
+
+impl Default for AsyncTiffConfig {
+    fn default() -> Self {
+        Self {
+            prefetch: 32 * 1024,
+            multiplier: 2.0,
+            metadata_cache: MetadataCacheMode::default(),
+            request_budget: None,
+            fetch_concurrency: None,
+            fetch_coalesce: None,
+            fetch_max_coalesced_size: None,
+            parse_options: ParseOptions::default(),
+            #[cfg(feature = "reqwest")]
+            retry: None,
+        }
+    }
+}
+
+impl AsyncTiffConfig {
+    /// Create a new config with the default settings, ignoring the environment.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new config from the `ASYNC_TIFF_*` environment variables, using the defaults for
+    /// variables that are not set.
+    ///
+    /// Returns an error if a variable is set to a value that can't be parsed.
+    pub fn from_env() -> AsyncTiffResult<Self> {
+        let (config, errors) = Self::from_vars(|key| std::env::var(key).ok());
+        errors.into_iter().next().map_or(Ok(config), Err)
+    }
+
+    /// Create a new config from the variables returned by `var`, along with an error for each
+    /// variable whose value can't be parsed. Such variables keep their default, so one invalid
+    /// variable doesn't discard the others.
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> (Self, Vec<AsyncTiffError>) {
+        let mut errors = vec![];
+        let mut config = Self::default();
+        if let Some(prefetch) = parse_var(&var, "ASYNC_TIFF_PREFETCH", &mut errors) {
+            config.prefetch = prefetch;
+        }
+        if let Some(multiplier) = parse_var(&var, "ASYNC_TIFF_PREFETCH_MULTIPLIER", &mut errors) {
+            config.multiplier = multiplier;
+        }
+        config.request_budget = parse_var(&var, "ASYNC_TIFF_REQUEST_BUDGET", &mut errors);
+        config.fetch_concurrency = parse_var(&var, "ASYNC_TIFF_FETCH_CONCURRENCY", &mut errors);
+        config.fetch_coalesce = parse_var(&var, "ASYNC_TIFF_FETCH_COALESCE", &mut errors);
+        config.fetch_max_coalesced_size =
+            parse_var(&var, "ASYNC_TIFF_FETCH_MAX_COALESCED_SIZE", &mut errors);
+        if let Some(mode) = parse_var(&var, "ASYNC_TIFF_METADATA_CACHE", &mut errors) {
+            config.metadata_cache = mode;
+        }
+        #[cfg(feature = "reqwest")]
+        {
+            use std::time::Duration;
+
+            let retries: Option<u32> = parse_var(&var, "ASYNC_TIFF_MAX_RETRIES", &mut errors);
+            let initial_backoff: Option<u64> =
+                parse_var(&var, "ASYNC_TIFF_RETRY_INITIAL_BACKOFF_MS", &mut errors);
+            let max_backoff: Option<u64> =
+                parse_var(&var, "ASYNC_TIFF_RETRY_MAX_BACKOFF_MS", &mut errors);
+            if retries.is_some() || initial_backoff.is_some() || max_backoff.is_some() {
+                let mut policy = RetryPolicy::new();
+                if let Some(retries) = retries {
+                    policy = policy.with_max_attempts(retries.saturating_add(1));
+                }
+                if let Some(ms) = initial_backoff {
+                    policy = policy.with_initial_backoff(Duration::from_millis(ms));
+                }
+                if let Some(ms) = max_backoff {
+                    policy = policy.with_max_backoff(Duration::from_millis(ms));
+                }
+                config.retry = Some(policy);
+            }
+        }
+        (config, errors)
+    }
+
+    /// The process-wide default config.
+    ///
+    /// Unless replaced with [`set_global`][Self::set_global], this is read from the environment
+    /// on first use. Invalid environment variables keep their default in that case, and are
+    /// logged with the `tracing` feature; call [`from_env`][Self::from_env] to check them.
+    pub fn global() -> Self {
+        if let Some(config) = GLOBAL.read().unwrap().as_ref() {
+            return config.clone();
+        }
+        GLOBAL
+            .write()
+            .unwrap()
+            .get_or_insert_with(|| {
+                let (config, errors) = Self::from_vars(|key| std::env::var(key).ok());
+                #[cfg(feature = "tracing")]
+                for err in &errors {
+                    tracing::warn!("ignoring environment variable: {err}");
+                }
+                #[cfg(not(feature = "tracing"))]
+                let _ = errors;
+                config
+            })
+            .clone()
+    }
+
+    /// Replace the process-wide default config.
+    pub fn set_global(config: Self) {
+        *GLOBAL.write().unwrap() = Some(config);
+    }
+
+    /// Set the number of bytes fetched up front when reading metadata, otherwise defaults to
+    /// 32 KiB.
+    pub fn with_prefetch(mut self, prefetch: u64) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
+    /// Set the multiplier for subsequent metadata fetch sizes, otherwise defaults to 2.0.
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Set how metadata is cached while opening a file, otherwise defaults to
+    /// [`MetadataCacheMode::Readahead`].
+    pub fn with_metadata_cache(mut self, mode: MetadataCacheMode) -> Self {
+        self.metadata_cache = mode;
+        self
+    }
+
+    /// Fail opening a file that needs more than `budget` metadata requests. See
+    /// [`RequestBudget`][crate::metadata::RequestBudget].
+    pub fn with_request_budget(mut self, budget: usize) -> Self {
+        self.request_budget = Some(budget);
+        self
+    }
+
+    /// Set the default fetch options for fetching multiple tiles.
+    pub fn with_fetch_options(mut self, options: &FetchOptions) -> Self {
+        self.fetch_concurrency = options.concurrency();
+        self.fetch_coalesce = options.coalesce();
+        self.fetch_max_coalesced_size = options.max_coalesced_size();
+        self
+    }
+
+    /// Set the options for parsing IFDs, including the extensions that parse their tags.
+    /// Defaults to [`ParseOptions::default`].
+    pub fn with_parse_options(mut self, parse_options: ParseOptions) -> Self {
+        self.parse_options = parse_options;
+        self
+    }
+
+    /// Retry failed HTTP requests of readers created without their own policy, such as
+    /// [`ReqwestReader::new`][crate::reader::ReqwestReader::new], according to `policy`. By
+    /// default requests are not retried.
+    #[cfg(feature = "reqwest")]
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// The number of bytes fetched up front when reading metadata.
+    pub fn prefetch(&self) -> u64 {
+        self.prefetch
+    }
+
+    /// The multiplier for subsequent metadata fetch sizes.
+    pub fn multiplier(&self) -> f64 {
+        self.multiplier
+    }
+
+    /// How metadata is cached while opening a file.
+    pub fn metadata_cache(&self) -> MetadataCacheMode {
+        self.metadata_cache
+    }
+
+    /// The maximum number of metadata requests when opening a file, if limited.
+    pub fn request_budget(&self) -> Option<usize> {
+        self.request_budget
+    }
+
+    /// The default options for fetching multiple tiles.
+    pub fn fetch_options(&self) -> FetchOptions {
+        let mut options = FetchOptions::new();
+        if let Some(concurrency) = self.fetch_concurrency {
+            options = options.with_concurrency(concurrency);
+        }
+        if let Some(gap) = self.fetch_coalesce {
+            options = options.with_coalesce(gap);
+        }
+        if let Some(size) = self.fetch_max_coalesced_size {
+            options = options.with_max_coalesced_size(size);
+        }
+        options
+    }
+
+    /// The options for parsing IFDs.
+    pub fn parse_options(&self) -> &ParseOptions {
+        &self.parse_options
+    }
+
+    /// The default retry policy of HTTP readers, if requests are retried.
+    #[cfg(feature = "reqwest")]
+    pub fn retry(&self) -> Option<&RetryPolicy> {
+        self.retry.as_ref()
+    }
+}
+
+/// Parse the variable `key`, returning `None` and recording an error in `errors` if its value is
+/// invalid.
+fn parse_var<T: FromStr>(
+    var: &impl Fn(&str) -> Option<String>,
+    key: &str,
+    errors: &mut Vec<AsyncTiffError>,
+) -> Option<T> {
+    let value = var(key)?;
+    match value.trim().parse() {
+        Ok(parsed) => Some(parsed),
+        Err(_) => {
+            errors.push(AsyncTiffError::General(format!(
+                "invalid value for {key}: {value:?}"
+            )));
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::reader::{from_url, AsyncFileReader};
+    use crate::TIFF;
+
+    fn from_map(vars: &[(&str, &str)]) -> (AsyncTiffConfig, Vec<AsyncTiffError>) {
+        let vars = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<HashMap<_, _>>();
+        AsyncTiffConfig::from_vars(|key| vars.get(key).cloned())
+    }
+
+    #[test]
+    fn test_from_vars() {
+        let (config, errors) = from_map(&[]);
+        assert_eq!(config, AsyncTiffConfig::default());
+        assert!(errors.is_empty());
+
+        let config = from_map(&[
+            ("ASYNC_TIFF_PREFETCH", "65536"),
+            ("ASYNC_TIFF_PREFETCH_MULTIPLIER", "1.5"),
+            ("ASYNC_TIFF_REQUEST_BUDGET", "2"),
+            ("ASYNC_TIFF_FETCH_CONCURRENCY", "8"),
+            ("ASYNC_TIFF_FETCH_COALESCE", "1024"),
+            ("ASYNC_TIFF_FETCH_MAX_COALESCED_SIZE", "1048576"),
+        ])
+        .0;
+        assert_eq!(config.prefetch(), 65536);
+        assert_eq!(config.multiplier(), 1.5);
+        assert_eq!(config.request_budget(), Some(2));
+        assert_eq!(config.fetch_options().concurrency(), Some(8));
+        assert_eq!(config.fetch_options().coalesce(), Some(1024));
+        assert_eq!(config.fetch_options().max_coalesced_size(), Some(1048576));
+
+        // An invalid variable keeps its default without discarding the others
+        let (config, errors) = from_map(&[
+            ("ASYNC_TIFF_PREFETCH", "lots"),
+            ("ASYNC_TIFF_REQUEST_BUDGET", "2"),
+        ]);
+        assert_eq!(config.prefetch(), AsyncTiffConfig::default().prefetch());
+        assert_eq!(config.request_budget(), Some(2));
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].to_string().contains("ASYNC_TIFF_PREFETCH"));
+
+        let (config, errors) = from_map(&[("ASYNC_TIFF_METADATA_CACHE", "Prefetch")]);
+        assert_eq!(config.metadata_cache(), MetadataCacheMode::Prefetch);
+        assert!(errors.is_empty());
+        let (config, errors) = from_map(&[("ASYNC_TIFF_METADATA_CACHE", "lru")]);
+        assert_eq!(config.metadata_cache(), MetadataCacheMode::Readahead);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn test_retry_from_vars() {
+        use std::time::Duration;
+
+        use crate::reader::RetryPolicy;
+
+        assert_eq!(from_map(&[]).0.retry(), None);
+
+        let (config, errors) = from_map(&[
+            ("ASYNC_TIFF_MAX_RETRIES", "4"),
+            ("ASYNC_TIFF_RETRY_INITIAL_BACKOFF_MS", "50"),
+            ("ASYNC_TIFF_RETRY_MAX_BACKOFF_MS", "2000"),
+        ]);
+        assert!(errors.is_empty());
+        let expected = RetryPolicy::new()
+            .with_max_attempts(5)
+            .with_initial_backoff(Duration::from_millis(50))
+            .with_max_backoff(Duration::from_secs(2));
+        assert_eq!(config.retry(), Some(&expected));
+
+        // Any retry variable enables the default policy
+        let (config, _) = from_map(&[("ASYNC_TIFF_RETRY_MAX_BACKOFF_MS", "2000")]);
+        assert_eq!(config.retry().unwrap().max_attempts(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_open_with_config() {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/image-tiff/tiled-jpeg-ycbcr.tif");
+        let reader: Arc<dyn AsyncFileReader> =
+            from_url(path.to_str().unwrap(), Vec::<(&str, &str)>::new()).unwrap();
+
+        let config = AsyncTiffConfig::new().with_request_budget(1);
+        let tiff = TIFF::open_with_config(reader.clone(), &config)
+            .await
+            .unwrap();
+        assert!(!tiff.ifds().is_empty());
+
+        let config = config.with_prefetch(8).with_multiplier(1.1);
+        let err = TIFF::open_with_config(reader.clone(), &config)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AsyncTiffError::RequestBudgetExceeded { .. }));
+
+        // Every metadata read is a separate request without a cache
+        let config = AsyncTiffConfig::new()
+            .with_metadata_cache(MetadataCacheMode::None)
+            .with_request_budget(1);
+        let err = TIFF::open_with_config(reader.clone(), &config)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AsyncTiffError::RequestBudgetExceeded { .. }));
+
+        let config = config
+            .with_metadata_cache(MetadataCacheMode::Prefetch)
+            .with_prefetch(1024 * 1024);
+        let tiff = TIFF::open_with_config(reader, &config).await.unwrap();
+        assert!(!tiff.ifds().is_empty());
+    }
+}
@@ -2,6 +2,11 @@ use crate::tags::SampleFormat;
 
 /// Supported numeric data types for array elements.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize),
+    serde(rename_all = "lowercase")
+)]
 pub enum DataType {
     /// Boolean mask data.
     Bool,
@@ -88,6 +93,27 @@ impl DataType {
             _ => None,
         }
     }
+
+    /// The smallest unsigned integer type that fits every sample of an image whose unsigned
+    /// samples have different bit depths.
+    ///
+    /// Returns `None` if not all samples are unsigned integers, or if any sample is wider than 64
+    /// bits.
+    pub(crate) fn from_mixed_tags(
+        sample_format: &[SampleFormat],
+        bits_per_sample: &[u16],
+    ) -> Option<Self> {
+        if sample_format.is_empty() || !sample_format.iter().all(|f| *f == SampleFormat::Uint) {
+            return None;
+        }
+        match bits_per_sample.iter().max()? {
+            1..=8 => Some(DataType::UInt8),
+            9..=16 => Some(DataType::UInt16),
+            17..=32 => Some(DataType::UInt32),
+            33..=64 => Some(DataType::UInt64),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -204,6 +230,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_from_mixed_tags() {
+        assert_eq!(
+            DataType::from_mixed_tags(&[SampleFormat::Uint; 4], &[8, 8, 8, 1]),
+            Some(DataType::UInt8)
+        );
+        assert_eq!(
+            DataType::from_mixed_tags(&[SampleFormat::Uint; 2], &[8, 16]),
+            Some(DataType::UInt16)
+        );
+        assert_eq!(
+            DataType::from_mixed_tags(&[SampleFormat::Int; 2], &[8, 16]),
+            None
+        );
+    }
+
     #[test]
     fn test_from_tags_empty_arrays() {
         assert_eq!(
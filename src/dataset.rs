@@ -0,0 +1,347 @@
+//! Opening many TIFFs that share one store, connection pool, caches and concurrency limit.
+//!
+//! A tile service may open thousands of COGs from the same bucket. A [`TiffDataset`] creates the
+//! reader of each file from a single [`ReaderFactory`], so all files share its client and
+//! credentials, and wraps them with a shared [`Throttle`] and optional [`ImageDataCache`]. Parsed
+//! metadata is kept in a bounded cache, so reopening a recently used file doesn't refetch its
+//! header.
+//!
+//! ```
+//! # tokio_test::block_on(async {
+//! use std::sync::Arc;
+//!
+//! use async_tiff::dataset::{ObjectStoreFactory, TiffDataset};
+//! use object_store::local::LocalFileSystem;
+//!
+//! let store = Arc::new(LocalFileSystem::new_with_prefix("fixtures").unwrap());
+//! let dataset = TiffDataset::new(ObjectStoreFactory::new(store))
+//!     .with_max_concurrency(64)
+//!     .with_metadata_capacity(1000);
+//!
+//! let tiff = dataset.open("image-tiff/tiled-rgb-u8.tif").await.unwrap();
+//! let reader = dataset.reader("image-tiff/tiled-rgb-u8.tif").unwrap();
+//! let tile = tiff.ifds()[0].fetch_tile(0, 0, &reader).await.unwrap();
+//! # })
+//! ```
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use crate::config::AsyncTiffConfig;
+use crate::error::AsyncTiffResult;
+use crate::lru::Lru;
+use crate::reader::{AsyncFileReader, DataCacheReader, ImageDataCache, Throttle, ThrottledReader};
+use crate::TIFF;
+
+/// The default number of files whose metadata a [`TiffDataset`] keeps.
+const DEFAULT_METADATA_CAPACITY: usize = 1024;
+
+/// Creates the [`AsyncFileReader`] for a path, sharing clients and credentials between files.
+pub trait ReaderFactory: Debug + Send + Sync + 'static {
+    /// Create a reader for the file at `path`.
+    fn reader(&self, path: &str) -> AsyncTiffResult<Arc<dyn AsyncFileReader>>;
+}
+
+/// A [`ReaderFactory`] creating [`ObjectReader`][crate::reader::ObjectReader]s for paths in one
+/// [`ObjectStore`][object_store::ObjectStore].
+#[cfg(feature = "object_store")]
+#[derive(Debug, Clone)]
+pub struct ObjectStoreFactory {
+    store: Arc<dyn object_store::ObjectStore>,
+}
+
+#[cfg(feature = "object_store")]
+impl ObjectStoreFactory {
+    /// Create a factory for paths in `store`.
+    pub fn new(store: Arc<dyn object_store::ObjectStore>) -> Self {
+        Self { store }
+    }
+
+    /// Access the store of this factory.
+    pub fn store(&self) -> &Arc<dyn object_store::ObjectStore> {
+        &self.store
+    }
+}
+
+#[cfg(feature = "object_store")]
+impl ReaderFactory for ObjectStoreFactory {
+    fn reader(&self, path: &str) -> AsyncTiffResult<Arc<dyn AsyncFileReader>> {
+        let path = object_store::path::Path::parse(path).map_err(object_store::Error::from)?;
+        Ok(Arc::new(crate::reader::ObjectReader::new(
+            self.store.clone(),
+            path,
+        )))
+    }
+}
+
+/// A [`ReaderFactory`] creating [`ReqwestReader`][crate::reader::ReqwestReader]s for URLs
+/// relative to a base URL, all sharing one connection pool.
+#[cfg(feature = "reqwest")]
+#[derive(Debug, Clone)]
+pub struct ReqwestFactory {
+    client: reqwest::Client,
+    base_url: reqwest::Url,
+    retry: Option<crate::reader::RetryPolicy>,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestFactory {
+    /// Create a factory resolving paths against `base_url` and requesting them with `client`.
+    ///
+    /// As with [`Url::join`][reqwest::Url::join], `base_url` should end with a `/` for paths to
+    /// be resolved below it.
+    pub fn new(client: reqwest::Client, base_url: reqwest::Url) -> Self {
+        Self {
+            client,
+            base_url,
+            retry: AsyncTiffConfig::global().retry().cloned(),
+        }
+    }
+
+    /// Retry failed requests of every reader according to `policy`, otherwise defaults to the
+    /// [`retry`][AsyncTiffConfig::retry] policy of the global config.
+    pub fn with_retry(mut self, policy: crate::reader::RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// The URL paths are resolved against.
+    pub fn base_url(&self) -> &reqwest::Url {
+        &self.base_url
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl ReaderFactory for ReqwestFactory {
+    fn reader(&self, path: &str) -> AsyncTiffResult<Arc<dyn AsyncFileReader>> {
+        let url = self.base_url.join(path).map_err(|err| {
+            crate::error::AsyncTiffError::General(format!("invalid path {path}: {err}"))
+        })?;
+        let mut reader = crate::reader::ReqwestReader::new(self.client.clone(), url);
+        if let Some(retry) = &self.retry {
+            reader = reader.with_retry(retry.clone());
+        }
+        Ok(Arc::new(reader))
+    }
+}
+
+/// A collection of TIFFs opened by path through a shared [`ReaderFactory`].
+///
+/// Readers created by [`reader`][Self::reader] are limited by the dataset's [`Throttle`], if one
+/// is [set][Self::with_throttle], and serve repeated image data
+/// requests from the [data cache][Self::with_data_cache], if any. Metadata is read with the
+/// dataset's [`AsyncTiffConfig`], bypassing the data cache, and the parsed [`TIFF`]s of the
+/// [`metadata_capacity`][Self::with_metadata_capacity] most recently used files are kept.
+///
+/// A dataset can be shared between tasks as an `Arc<TiffDataset>`.
+#[derive(Debug)]
+pub struct TiffDataset {
+    factory: Arc<dyn ReaderFactory>,
+    config: AsyncTiffConfig,
+    throttle: Option<Arc<Throttle>>,
+    data_cache: Option<Arc<dyn ImageDataCache>>,
+    metadata_capacity: usize,
+    metadata: Mutex<Lru<String, Arc<TIFF>>>,
+}
+
+impl TiffDataset {
+    /// Create a dataset reading files through `factory`, with the process-wide
+    /// [`AsyncTiffConfig::global`] configuration and no throttle or data cache.
+    pub fn new(factory: impl ReaderFactory) -> Self {
+        Self::from_factory(Arc::new(factory))
+    }
+
+    /// Create a dataset from a shared factory.
+    pub fn from_factory(factory: Arc<dyn ReaderFactory>) -> Self {
+        Self {
+            factory,
+            config: AsyncTiffConfig::global(),
+            throttle: None,
+            data_cache: None,
+            metadata_capacity: DEFAULT_METADATA_CAPACITY,
+            metadata: Mutex::new(Lru::with_len(DEFAULT_METADATA_CAPACITY)),
+        }
+    }
+
+    /// Read metadata with `config`.
+    pub fn with_config(mut self, config: AsyncTiffConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Allow at most `max_concurrency` requests at once across all files of the dataset.
+    pub fn with_max_concurrency(self, max_concurrency: usize) -> Self {
+        self.with_throttle(Arc::new(Throttle::new(max_concurrency)))
+    }
+
+    /// Limit requests with `throttle`, which may be shared with other datasets and readers.
+    pub fn with_throttle(mut self, throttle: Arc<Throttle>) -> Self {
+        self.throttle = Some(throttle);
+        self
+    }
+
+    /// Serve repeated image data requests of all files from `cache`.
+    pub fn with_data_cache(mut self, cache: Arc<dyn ImageDataCache>) -> Self {
+        self.data_cache = Some(cache);
+        self
+    }
+
+    /// Keep the metadata of at most `capacity` files, evicting the least recently used.
+    pub fn with_metadata_capacity(mut self, capacity: usize) -> Self {
+        self.metadata_capacity = capacity;
+        self.metadata = Mutex::new(Lru::with_len(capacity));
+        self
+    }
+
+    /// Access the factory creating the readers of this dataset.
+    pub fn factory(&self) -> &Arc<dyn ReaderFactory> {
+        &self.factory
+    }
+
+    /// The configuration metadata is read with.
+    pub fn config(&self) -> &AsyncTiffConfig {
+        &self.config
+    }
+
+    /// The throttle limiting requests, if any.
+    pub fn throttle(&self) -> Option<&Arc<Throttle>> {
+        self.throttle.as_ref()
+    }
+
+    /// The cache of image data, if any.
+    pub fn data_cache(&self) -> Option<&Arc<dyn ImageDataCache>> {
+        self.data_cache.as_ref()
+    }
+
+    /// The maximum number of files whose metadata is kept.
+    pub fn metadata_capacity(&self) -> usize {
+        self.metadata_capacity
+    }
+
+    /// Create a reader for the file at `path`, sharing the dataset's throttle and data cache.
+    pub fn reader(&self, path: &str) -> AsyncTiffResult<Arc<dyn AsyncFileReader>> {
+        let reader = self.throttled_reader(path)?;
+        Ok(match &self.data_cache {
+            Some(cache) => Arc::new(DataCacheReader::new(reader, path, cache.clone())),
+            None => reader,
+        })
+    }
+
+    /// A reader for `path` that shares the throttle but not the data cache, so metadata requests
+    /// don't fill the cache.
+    fn throttled_reader(&self, path: &str) -> AsyncTiffResult<Arc<dyn AsyncFileReader>> {
+        let reader = self.factory.reader(path)?;
+        Ok(match &self.throttle {
+            Some(throttle) => Arc::new(ThrottledReader::new(reader, throttle.clone())),
+            None => reader,
+        })
+    }
+
+    /// Open the file at `path`, reusing its metadata if it is cached.
+    ///
+    /// Concurrent calls for a path that isn't cached yet each read the metadata.
+    pub async fn open(&self, path: &str) -> AsyncTiffResult<Arc<TIFF>> {
+        if let Some(tiff) = self.cached(path) {
+            return Ok(tiff);
+        }
+        let reader = self.throttled_reader(path)?;
+        let tiff = Arc::new(TIFF::open_with_config(reader, &self.config).await?);
+        self.insert(path, tiff.clone());
+        Ok(tiff)
+    }
+
+    /// The cached metadata of the file at `path`, if any.
+    pub fn cached(&self, path: &str) -> Option<Arc<TIFF>> {
+        self.metadata.lock().unwrap().get(path).cloned()
+    }
+
+    /// Cache `tiff` as the metadata of the file at `path`, e.g. metadata read by another process.
+    pub fn insert(&self, path: &str, tiff: Arc<TIFF>) {
+        self.metadata.lock().unwrap().insert(path.to_string(), tiff);
+    }
+
+    /// Remove the cached metadata of the file at `path`, e.g. after the file was overwritten,
+    /// returning it if it was cached.
+    pub fn evict(&self, path: &str) -> Option<Arc<TIFF>> {
+        self.metadata.lock().unwrap().remove(path)
+    }
+
+    /// Remove all cached metadata.
+    pub fn clear(&self) {
+        self.metadata.lock().unwrap().clear();
+    }
+
+    /// The number of files whose metadata is cached.
+    pub fn cached_len(&self) -> usize {
+        self.metadata.lock().unwrap().len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use object_store::local::LocalFileSystem;
+
+    use super::*;
+    use crate::decoder::DecoderRegistry;
+    use crate::reader::LruDataCache;
+
+    fn dataset() -> TiffDataset {
+        let fixtures = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        let store = Arc::new(LocalFileSystem::new_with_prefix(fixtures).unwrap());
+        TiffDataset::new(ObjectStoreFactory::new(store))
+    }
+
+    #[tokio::test]
+    async fn test_dataset_metadata_cache() {
+        let dataset = dataset().with_metadata_capacity(2);
+        let rgb = dataset.open("image-tiff/tiled-rgb-u8.tif").await.unwrap();
+        let again = dataset.open("image-tiff/tiled-rgb-u8.tif").await.unwrap();
+        assert!(Arc::ptr_eq(&rgb, &again));
+
+        dataset.open("image-tiff/planar-rgb-u8.tif").await.unwrap();
+        // Using the RGB file again makes the planar file the least recently used
+        dataset.cached("image-tiff/tiled-rgb-u8.tif").unwrap();
+        dataset
+            .open("image-tiff/tiled-rect-rgb-u8.tif")
+            .await
+            .unwrap();
+        assert_eq!(dataset.cached_len(), 2);
+        assert!(dataset.cached("image-tiff/planar-rgb-u8.tif").is_none());
+
+        let evicted = dataset.evict("image-tiff/tiled-rgb-u8.tif").unwrap();
+        assert!(Arc::ptr_eq(&rgb, &evicted));
+        let reopened = dataset.open("image-tiff/tiled-rgb-u8.tif").await.unwrap();
+        assert!(!Arc::ptr_eq(&rgb, &reopened));
+
+        dataset.clear();
+        assert_eq!(dataset.cached_len(), 0);
+        assert!(dataset.open("image-tiff/missing.tif").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dataset_shared_reader() {
+        let cache = Arc::new(LruDataCache::new(1 << 20));
+        let dataset = dataset()
+            .with_max_concurrency(1)
+            .with_data_cache(cache.clone());
+        assert_eq!(dataset.throttle().unwrap().max_in_flight(), 1);
+
+        let path = "image-tiff/tiled-rgb-u8.tif";
+        let tiff = dataset.open(path).await.unwrap();
+        let reader = dataset.reader(path).unwrap();
+        let ifd = &tiff.ifds()[0];
+        let registry = DecoderRegistry::default();
+        let tiles = ifd
+            .read_tiles_with_errors(
+                &[(0, 0), (1, 0)],
+                reader.as_ref(),
+                &registry,
+                &Default::default(),
+            )
+            .await;
+        assert!(tiles.iter().all(|tile| tile.is_ok()));
+        assert_eq!(cache.len(), 2);
+    }
+}
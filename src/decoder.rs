@@ -3,103 +3,655 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::io::{Cursor, Read};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use bytes::Bytes;
 use flate2::bufread::ZlibDecoder;
 
 use crate::error::{AsyncTiffError, AsyncTiffResult, TiffError, TiffUnsupportedError};
-use crate::tags::{Compression, PhotometricInterpretation};
+use crate::ifd::ImageFileDirectory;
+use crate::metadata::Limits;
+use crate::reader::Endianness;
+use crate::tags::{
+    Compression, PhotometricInterpretation, PlanarConfiguration, Predictor, SampleFormat,
+};
 
 /// A registry of decoders.
 ///
 /// This allows end users to register their own decoders, for custom compression methods, or
 /// override the default decoder implementations.
 ///
+/// Decoders are normally registered per compression method, but a decoder can also be registered
+/// for a specific combination of compression method and photometric interpretation, e.g. to only
+/// handle JPEG-compressed CMYK tiles. Such pairs take precedence over the decoder registered for
+/// the compression method alone.
+///
 /// ```
-/// use async_tiff::decoder::DecoderRegistry;
+/// use std::sync::Arc;
+///
+/// use async_tiff::decoder::{DecoderRegistry, JPEGDecoder};
+/// use async_tiff::tags::{Compression, PhotometricInterpretation};
+///
+/// // Default registry includes Deflate, LZW, JPEG, PackBits, ZSTD.
+/// let mut registry = DecoderRegistry::default();
 ///
-/// // Default registry includes Deflate, LZW, JPEG, ZSTD.
-/// let registry = DecoderRegistry::default();
+/// // Use a specific decoder for JPEG-in-YCbCr tiles only.
+/// registry.insert_for_photometric(
+///     Compression::ModernJPEG,
+///     PhotometricInterpretation::YCbCr,
+///     Arc::new(JPEGDecoder),
+/// );
 ///
 /// // Empty registry for manual configuration.
 /// let empty = DecoderRegistry::empty();
 /// ```
-#[derive(Debug)]
-pub struct DecoderRegistry(HashMap<Compression, Box<dyn Decoder>>);
+///
+/// Decoders are stored in an [`Arc`], so cloning a registry is cheap, and a registry can be shared
+/// between threads, e.g. behind an `Arc` in a server's state.
+///
+/// The registry also holds the [`Limits`] on the size of decoded tiles, and optionally a
+/// [`DecodeExecutor`] that async decoding runs on.
+#[derive(Debug, Clone)]
+pub struct DecoderRegistry {
+    decoders: HashMap<Compression, Arc<dyn Decoder>>,
+    photometric_decoders: HashMap<(Compression, PhotometricInterpretation), Arc<dyn Decoder>>,
+    limits: Limits,
+    executor: Option<Arc<dyn DecodeExecutor>>,
+}
 
 impl DecoderRegistry {
     /// Create a new decoder registry with no decoders registered
     pub fn empty() -> Self {
-        Self(HashMap::new())
+        Self {
+            decoders: HashMap::new(),
+            photometric_decoders: HashMap::new(),
+            limits: Limits::default(),
+            executor: None,
+        }
+    }
+
+    /// Set the limits checked before decoding a tile, otherwise defaults to
+    /// [`Limits::default`]. Only [`Limits::max_decoded_chunk_bytes`] applies to decoding.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The limits checked before decoding a tile.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Run the decoding of [`Tile::decode_async`][crate::Tile::decode_async], and of async reads
+    /// like [`ImageFileDirectory::read_window`], on `executor` instead of the calling task.
+    ///
+    /// Decompressing large Deflate or LZW tiles can take milliseconds, during which an async
+    /// runtime thread can't make progress on other requests. Servers that decode many tiles
+    /// offload this work, e.g. to Tokio's blocking pool with [`SpawnBlocking`], to keep their
+    /// IO latency low. Without an executor, tiles are decoded on the calling task.
+    pub fn with_executor(mut self, executor: Arc<dyn DecodeExecutor>) -> Self {
+        self.executor = Some(executor);
+        self
+    }
+
+    /// The executor that async decoding runs on, if any.
+    pub fn executor(&self) -> Option<&dyn DecodeExecutor> {
+        self.executor.as_deref()
+    }
+
+    /// Register a decoder for tiles with the given compression method and photometric
+    /// interpretation, returning the decoder previously registered for this pair, if any.
+    pub fn insert_for_photometric(
+        &mut self,
+        compression: Compression,
+        photometric_interpretation: PhotometricInterpretation,
+        decoder: Arc<dyn Decoder>,
+    ) -> Option<Arc<dyn Decoder>> {
+        self.photometric_decoders
+            .insert((compression, photometric_interpretation), decoder)
+    }
+
+    /// Find the decoder for tiles with the given compression method and photometric
+    /// interpretation.
+    ///
+    /// A decoder registered for this exact pair is preferred, falling back to the decoder
+    /// registered for the compression method.
+    pub fn get(
+        &self,
+        compression: Compression,
+        photometric_interpretation: PhotometricInterpretation,
+    ) -> Option<&dyn Decoder> {
+        self.photometric_decoders
+            .get(&(compression, photometric_interpretation))
+            .or_else(|| self.decoders.get(&compression))
+            .map(|decoder| decoder.as_ref())
     }
 }
 
-impl AsRef<HashMap<Compression, Box<dyn Decoder>>> for DecoderRegistry {
-    fn as_ref(&self) -> &HashMap<Compression, Box<dyn Decoder>> {
-        &self.0
+impl AsRef<HashMap<Compression, Arc<dyn Decoder>>> for DecoderRegistry {
+    fn as_ref(&self) -> &HashMap<Compression, Arc<dyn Decoder>> {
+        &self.decoders
     }
 }
 
-impl AsMut<HashMap<Compression, Box<dyn Decoder>>> for DecoderRegistry {
-    fn as_mut(&mut self) -> &mut HashMap<Compression, Box<dyn Decoder>> {
-        &mut self.0
+impl AsMut<HashMap<Compression, Arc<dyn Decoder>>> for DecoderRegistry {
+    fn as_mut(&mut self) -> &mut HashMap<Compression, Arc<dyn Decoder>> {
+        &mut self.decoders
     }
 }
 
 impl Default for DecoderRegistry {
     fn default() -> Self {
-        let mut registry = HashMap::with_capacity(6);
-        registry.insert(Compression::None, Box::new(UncompressedDecoder) as _);
-        registry.insert(Compression::Deflate, Box::new(DeflateDecoder) as _);
-        registry.insert(Compression::OldDeflate, Box::new(DeflateDecoder) as _);
+        let mut registry = HashMap::with_capacity(7);
+        registry.insert(Compression::None, Arc::new(UncompressedDecoder) as _);
+        registry.insert(Compression::Deflate, Arc::new(DeflateDecoder) as _);
+        registry.insert(Compression::OldDeflate, Arc::new(DeflateDecoder) as _);
         #[cfg(feature = "lerc")]
-        registry.insert(Compression::LERC, Box::new(LercDecoder) as _);
+        registry.insert(Compression::LERC, Arc::new(LercDecoder) as _);
         #[cfg(feature = "lzma")]
-        registry.insert(Compression::LZMA, Box::new(LZMADecoder) as _);
-        registry.insert(Compression::LZW, Box::new(LZWDecoder) as _);
-        registry.insert(Compression::ModernJPEG, Box::new(JPEGDecoder) as _);
+        registry.insert(Compression::LZMA, Arc::new(LZMADecoder) as _);
+        registry.insert(Compression::LZW, Arc::new(LZWDecoder) as _);
+        registry.insert(Compression::ModernJPEG, Arc::new(JPEGDecoder) as _);
+        registry.insert(Compression::PackBits, Arc::new(PackBitsDecoder) as _);
         #[cfg(feature = "jpeg2k")]
-        registry.insert(Compression::JPEG2k, Box::new(JPEG2kDecoder) as _);
+        registry.insert(Compression::JPEG2k, Arc::new(JPEG2kDecoder) as _);
         #[cfg(feature = "webp")]
-        registry.insert(Compression::WebP, Box::new(WebPDecoder) as _);
-        registry.insert(Compression::ZSTD, Box::new(ZstdDecoder) as _);
-        Self(registry)
+        registry.insert(Compression::WebP, Arc::new(WebPDecoder) as _);
+        registry.insert(Compression::ZSTD, Arc::new(ZstdDecoder) as _);
+        Self {
+            decoders: registry,
+            photometric_decoders: HashMap::new(),
+            limits: Limits::default(),
+            executor: None,
+        }
+    }
+}
+
+/// Runs decoding work, e.g. on a thread pool, for [`DecoderRegistry::with_executor`].
+///
+/// Each task decodes one tile and sends the result back to the future awaiting it. Dropping a
+/// task without running it fails that decode with an error.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use async_tiff::decoder::{DecodeExecutor, DecoderRegistry};
+///
+/// /// Decodes every tile on a new thread.
+/// #[derive(Debug)]
+/// struct ThreadExecutor;
+///
+/// impl DecodeExecutor for ThreadExecutor {
+///     fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+///         std::thread::spawn(task);
+///     }
+/// }
+///
+/// let registry = DecoderRegistry::default().with_executor(Arc::new(ThreadExecutor));
+/// ```
+pub trait DecodeExecutor: Debug + Send + Sync {
+    /// Run `task` to completion, without blocking the caller.
+    fn execute(&self, task: Box<dyn FnOnce() + Send>);
+}
+
+/// A [`DecodeExecutor`] that runs tasks on the blocking thread pool of the current Tokio runtime
+/// with [`tokio::task::spawn_blocking`], enabled by the `tokio` feature.
+///
+/// Decoding must be started from within a Tokio runtime, as `spawn_blocking` panics otherwise.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SpawnBlocking;
+
+#[cfg(feature = "tokio")]
+impl DecodeExecutor for SpawnBlocking {
+    fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+        tokio::task::spawn_blocking(task);
     }
 }
 
 /// A trait to decode a TIFF tile.
+///
+/// Decoders receive the compressed bytes of one tile or strip, or of one band of a planar tile,
+/// with a [`DecodeContext`] describing its layout, and return the decompressed bytes as stored in
+/// the file. Reversing predictors and converting the byte order is left to
+/// [`Tile::decode`][crate::Tile::decode].
+///
+/// Decoders must be thread-safe, as a [`DecoderRegistry`] may be used from multiple threads at once.
+///
+/// # Implementing
+///
+/// **Every decoder must implement [`decode`][Self::decode].** It only has a default
+/// implementation so that decoders written against the deprecated
+/// [`decode_tile`][Self::decode_tile] keep compiling. A decoder that implements neither still
+/// compiles, but fails with an error for every tile it is asked to decode.
+///
+/// Decoders should fail with [`AsyncTiffError::LimitExceeded`] rather than produce more than
+/// [`DecodeContext::max_decoded_bytes`], so that a small corrupt or malicious tile can't exhaust
+/// memory.
+///
+/// ```
+/// use async_tiff::decoder::{DecodeContext, Decoder};
+/// use async_tiff::error::AsyncTiffResult;
+/// use bytes::Bytes;
+///
+/// /// Decodes data stored with every byte inverted.
+/// #[derive(Debug)]
+/// struct InvertDecoder;
+///
+/// impl Decoder for InvertDecoder {
+///     fn decode(&self, buffer: Bytes, context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
+///         assert_eq!(buffer.len(), context.expected_len());
+///         Ok(buffer.iter().map(|b| !b).collect())
+///     }
+/// }
+///
+/// let context = DecodeContext::new(2, 2, 1, 8);
+/// let decoded = InvertDecoder.decode(Bytes::from_static(&[0, 1, 2, 3]), &context).unwrap();
+/// assert_eq!(decoded, [255, 254, 253, 252]);
+/// ```
 pub trait Decoder: Debug + Send + Sync {
     /// Decode a TIFF tile.
-    fn decode_tile(
+    ///
+    /// The default implementation calls the deprecated [`decode_tile`][Self::decode_tile], so
+    /// decoders written against it keep working. New decoders implement this method instead.
+    fn decode(&self, buffer: Bytes, context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
+        #[allow(deprecated)]
+        self.decode_tile(
+            buffer,
+            context.photometric_interpretation(),
+            context.jpeg_tables(),
+            context.samples_per_pixel(),
+            context.bits_per_sample(),
+            context.lerc_parameters(),
+        )
+    }
+
+    /// Decode a TIFF tile, appending the decoded bytes to `output`.
+    ///
+    /// Decoding into a reused `output` avoids allocating a buffer for every tile. The default
+    /// implementation appends the result of [`decode`][Self::decode], keeping the allocation of
+    /// `output` unless it has none; decoders that can write into an existing buffer override it.
+    fn decode_into(
         &self,
         buffer: Bytes,
-        photometric_interpretation: PhotometricInterpretation,
-        jpeg_tables: Option<&[u8]>,
-        samples_per_pixel: u16,
-        bits_per_sample: u16,
-        lerc_parameters: Option<&[u32]>,
-    ) -> AsyncTiffResult<Vec<u8>>;
-}
-
-/// A decoder for the Deflate compression method.
-#[derive(Debug, Clone)]
-pub struct DeflateDecoder;
+        context: &DecodeContext<'_>,
+        output: &mut Vec<u8>,
+    ) -> AsyncTiffResult<()> {
+        let decoded = self.decode(buffer, context)?;
+        if output.capacity() == 0 {
+            *output = decoded;
+        } else {
+            output.extend_from_slice(&decoded);
+        }
+        Ok(())
+    }
 
-impl Decoder for DeflateDecoder {
+    /// Decode a TIFF tile given only some of its properties.
+    ///
+    /// This is only called by the default implementation of [`decode`][Self::decode], and
+    /// returns an error unless overridden.
+    #[deprecated(note = "implement `Decoder::decode`, which receives a `DecodeContext`")]
     fn decode_tile(
         &self,
-        buffer: Bytes,
+        _buffer: Bytes,
         _photometric_interpretation: PhotometricInterpretation,
         _jpeg_tables: Option<&[u8]>,
         _samples_per_pixel: u16,
         _bits_per_sample: u16,
         _lerc_parameters: Option<&[u32]>,
     ) -> AsyncTiffResult<Vec<u8>> {
-        let mut decoder = ZlibDecoder::new(Cursor::new(buffer));
+        Err(AsyncTiffError::General(format!(
+            "{self:?} implements neither Decoder::decode nor Decoder::decode_tile"
+        )))
+    }
+}
+
+/// The properties of a tile passed to a [`Decoder`].
+///
+/// Tiles are decoded independently of the IFD they were fetched from, so the context holds a
+/// copy of the relevant IFD fields and borrows the larger ones, like the JPEG tables, from the
+/// tile. It can also be built from an IFD with [`from_ifd`][Self::from_ifd], e.g. to call a
+/// decoder directly on bytes fetched with
+/// [`ImageFileDirectory::tile_byte_range`][crate::ImageFileDirectory::tile_byte_range].
+///
+/// The samples per pixel are those of the buffer being decoded, i.e. 1 for each band of a planar
+/// tile.
+#[derive(Debug, Clone, Copy)]
+pub struct DecodeContext<'a> {
+    width: u32,
+    height: u32,
+    samples_per_pixel: u16,
+    bits_per_sample: u16,
+    sample_format: SampleFormat,
+    predictor: Predictor,
+    planar_configuration: PlanarConfiguration,
+    endianness: Endianness,
+    photometric_interpretation: PhotometricInterpretation,
+    jpeg_tables: Option<&'a [u8]>,
+    lerc_parameters: Option<&'a [u32]>,
+    max_decoded_bytes: Option<u64>,
+}
+
+impl<'a> DecodeContext<'a> {
+    /// Create a context for an uncompressed, unpredicted tile of `width` by `height` pixels of
+    /// unsigned samples, in little-endian byte order with a BlackIsZero interpretation. Use the
+    /// `with_*` methods to describe other tiles.
+    pub fn new(width: u32, height: u32, samples_per_pixel: u16, bits_per_sample: u16) -> Self {
+        Self {
+            width,
+            height,
+            samples_per_pixel,
+            bits_per_sample,
+            sample_format: SampleFormat::Uint,
+            predictor: Predictor::None,
+            planar_configuration: PlanarConfiguration::Chunky,
+            endianness: Endianness::LittleEndian,
+            photometric_interpretation: PhotometricInterpretation::BlackIsZero,
+            jpeg_tables: None,
+            lerc_parameters: None,
+            max_decoded_bytes: None,
+        }
+    }
+
+    /// Create the context of the tiles or strips of `ifd`.
+    ///
+    /// The samples per pixel are those of the IFD, so callers decoding one band of a planar tile
+    /// set them to 1 with [`with_samples_per_pixel`][Self::with_samples_per_pixel].
+    pub fn from_ifd(ifd: &'a ImageFileDirectory) -> Self {
+        Self {
+            width: ifd.tile_width().unwrap_or(ifd.image_width()),
+            height: ifd.tile_height().unwrap_or(ifd.image_height()),
+            samples_per_pixel: ifd.samples_per_pixel(),
+            bits_per_sample: ifd.bits_per_sample()[0],
+            sample_format: ifd
+                .sample_format()
+                .first()
+                .copied()
+                .unwrap_or(SampleFormat::Uint),
+            predictor: ifd.predictor().unwrap_or(Predictor::None),
+            planar_configuration: ifd.planar_configuration(),
+            endianness: ifd.endianness,
+            photometric_interpretation: ifd.photometric_interpretation(),
+            jpeg_tables: ifd.jpeg_tables(),
+            lerc_parameters: ifd.lerc_parameters(),
+            max_decoded_bytes: None,
+        }
+    }
+
+    /// Set the number of samples per pixel of the buffer being decoded.
+    pub fn with_samples_per_pixel(mut self, samples_per_pixel: u16) -> Self {
+        self.samples_per_pixel = samples_per_pixel;
+        self
+    }
+
+    /// Set the format of the samples.
+    pub fn with_sample_format(mut self, sample_format: SampleFormat) -> Self {
+        self.sample_format = sample_format;
+        self
+    }
+
+    /// Set the predictor applied before compression.
+    pub fn with_predictor(mut self, predictor: Predictor) -> Self {
+        self.predictor = predictor;
+        self
+    }
+
+    /// Set the planar configuration of the tile.
+    pub fn with_planar_configuration(mut self, planar_configuration: PlanarConfiguration) -> Self {
+        self.planar_configuration = planar_configuration;
+        self
+    }
+
+    /// Set the byte order of the file.
+    pub fn with_endianness(mut self, endianness: Endianness) -> Self {
+        self.endianness = endianness;
+        self
+    }
+
+    /// Set the photometric interpretation of the tile.
+    pub fn with_photometric_interpretation(
+        mut self,
+        photometric_interpretation: PhotometricInterpretation,
+    ) -> Self {
+        self.photometric_interpretation = photometric_interpretation;
+        self
+    }
+
+    /// Set the JPEG tables shared by all tiles.
+    pub fn with_jpeg_tables(mut self, jpeg_tables: Option<&'a [u8]>) -> Self {
+        self.jpeg_tables = jpeg_tables;
+        self
+    }
+
+    /// Set the values of the LercParameters tag.
+    pub fn with_lerc_parameters(mut self, lerc_parameters: Option<&'a [u32]>) -> Self {
+        self.lerc_parameters = lerc_parameters;
+        self
+    }
+
+    /// Set the most bytes the decoder may produce, otherwise defaults to
+    /// [`expected_len`][Self::expected_len].
+    ///
+    /// A few bytes of compressed data can claim gigabytes of decoded output, so the built-in
+    /// decoders stop and fail with [`AsyncTiffError::LimitExceeded`] once they produce more.
+    pub fn with_max_decoded_bytes(mut self, max: u64) -> Self {
+        self.max_decoded_bytes = Some(max);
+        self
+    }
+
+    /// The width of the tile in pixels, which for tiles at the right and bottom edges of an image
+    /// includes padding.
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// The height of the tile in pixels, including any padding.
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The number of samples per pixel of the buffer being decoded.
+    pub fn samples_per_pixel(&self) -> u16 {
+        self.samples_per_pixel
+    }
+
+    /// The number of bits of each sample.
+    pub fn bits_per_sample(&self) -> u16 {
+        self.bits_per_sample
+    }
+
+    /// The format of the first sample.
+    pub fn sample_format(&self) -> SampleFormat {
+        self.sample_format
+    }
+
+    /// The predictor applied before compression, which the decoder doesn't need to reverse.
+    pub fn predictor(&self) -> Predictor {
+        self.predictor
+    }
+
+    /// The planar configuration of the tile.
+    pub fn planar_configuration(&self) -> PlanarConfiguration {
+        self.planar_configuration
+    }
+
+    /// The byte order of the file.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// The photometric interpretation of the tile.
+    pub fn photometric_interpretation(&self) -> PhotometricInterpretation {
+        self.photometric_interpretation
+    }
+
+    /// The JPEG tables shared by all tiles, if any.
+    pub fn jpeg_tables(&self) -> Option<&'a [u8]> {
+        self.jpeg_tables
+    }
+
+    /// The values of the LercParameters tag, if any.
+    pub fn lerc_parameters(&self) -> Option<&'a [u32]> {
+        self.lerc_parameters
+    }
+
+    /// The number of bytes of the decoded buffer, with each row padded to whole bytes.
+    pub fn expected_len(&self) -> usize {
+        let row_bits =
+            self.width as usize * self.samples_per_pixel as usize * self.bits_per_sample as usize;
+        row_bits.div_ceil(8) * self.height as usize
+    }
+
+    /// The most bytes the decoder may produce.
+    pub fn max_decoded_bytes(&self) -> u64 {
+        self.max_decoded_bytes.unwrap_or(self.expected_len() as u64)
+    }
+}
+
+/// Read `reader` to the end, appending to `output`, and fail with
+/// [`AsyncTiffError::LimitExceeded`] if it holds more than `max` bytes.
+///
+/// At most `max + 1` bytes are read, so a decompression bomb can't exhaust memory.
+fn read_bounded(reader: impl Read, max: u64, output: &mut Vec<u8>) -> AsyncTiffResult<()> {
+    let start = output.len();
+    reader.take(max.saturating_add(1)).read_to_end(output)?;
+    check_decoded_bytes((output.len() - start) as u64, max)
+}
+
+/// Fail with [`AsyncTiffError::LimitExceeded`] if a decoder produced more than `max` bytes.
+fn check_decoded_bytes(actual: u64, max: u64) -> AsyncTiffResult<()> {
+    if actual > max {
+        return Err(AsyncTiffError::LimitExceeded {
+            limit: "decoded chunk bytes",
+            actual,
+            max,
+        });
+    }
+    Ok(())
+}
+
+/// A pool of byte buffers for [`Tile::decode_into`][crate::Tile::decode_into].
+///
+/// High-throughput servers decode many tiles of the same size. Taking the output buffer from a
+/// pool and returning it once the decoded data has been consumed lets its allocation be reused
+/// for the next tile. The pool is thread-safe and keeps at most a fixed number of idle buffers.
+///
+/// ```
+/// use async_tiff::decoder::BufferPool;
+///
+/// let pool = BufferPool::new(8);
+/// let mut buffer = pool.take();
+/// buffer.extend_from_slice(&[1, 2, 3]);
+/// pool.recycle(buffer);
+///
+/// // The next buffer reuses the allocation
+/// let buffer = pool.take();
+/// assert!(buffer.is_empty() && buffer.capacity() >= 3);
+/// assert_eq!((pool.hits(), pool.misses()), (1, 1));
+/// ```
+#[derive(Debug)]
+pub struct BufferPool {
+    buffers: Mutex<Vec<Vec<u8>>>,
+    max_buffers: usize,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    discarded: AtomicU64,
+}
+
+impl BufferPool {
+    /// Create an empty pool that keeps at most `max_buffers` idle buffers.
+    pub fn new(max_buffers: usize) -> Self {
+        Self {
+            buffers: Mutex::new(Vec::with_capacity(max_buffers)),
+            max_buffers,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            discarded: AtomicU64::new(0),
+        }
+    }
+
+    /// Take an empty buffer from the pool, or a new one if the pool is empty.
+    pub fn take(&self) -> Vec<u8> {
+        match self.buffers.lock().unwrap().pop() {
+            Some(buffer) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                buffer
+            }
+            None => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Return a buffer to the pool, clearing it but keeping its allocation.
+    ///
+    /// The buffer is dropped instead if the pool is full.
+    pub fn recycle(&self, mut buffer: Vec<u8>) {
+        buffer.clear();
+        let mut buffers = self.buffers.lock().unwrap();
+        if buffers.len() < self.max_buffers {
+            buffers.push(buffer);
+        } else {
+            self.discarded.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// The number of [`take`][Self::take] calls that reused a pooled buffer.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of [`take`][Self::take] calls that found the pool empty.
+    ///
+    /// Many misses relative to hits mean more buffers are in use at once than are recycled, e.g.
+    /// because decoded data is kept or the pool is shared by many threads.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// The number of recycled buffers that were dropped because the pool was full.
+    pub fn discarded(&self) -> u64 {
+        self.discarded.load(Ordering::Relaxed)
+    }
+
+    /// The number of idle buffers in the pool.
+    pub fn len(&self) -> usize {
+        self.buffers.lock().unwrap().len()
+    }
+
+    /// Whether the pool holds no idle buffers.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The total capacity in bytes of the idle buffers in the pool.
+    pub fn pooled_bytes(&self) -> usize {
+        self.buffers.lock().unwrap().iter().map(Vec::capacity).sum()
+    }
+}
+
+/// A decoder for the Deflate compression method.
+#[derive(Debug, Clone)]
+pub struct DeflateDecoder;
+
+impl Decoder for DeflateDecoder {
+    fn decode(&self, buffer: Bytes, context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
         let mut buf = Vec::new();
-        decoder.read_to_end(&mut buf)?;
+        self.decode_into(buffer, context, &mut buf)?;
         Ok(buf)
     }
+
+    fn decode_into(
+        &self,
+        buffer: Bytes,
+        context: &DecodeContext<'_>,
+        output: &mut Vec<u8>,
+    ) -> AsyncTiffResult<()> {
+        let decoder = ZlibDecoder::new(Cursor::new(buffer));
+        read_bounded(decoder, context.max_decoded_bytes(), output)
+    }
 }
 
 /// A decoder for the JPEG compression method.
@@ -107,16 +659,12 @@ impl Decoder for DeflateDecoder {
 pub struct JPEGDecoder;
 
 impl Decoder for JPEGDecoder {
-    fn decode_tile(
-        &self,
-        buffer: Bytes,
-        photometric_interpretation: PhotometricInterpretation,
-        jpeg_tables: Option<&[u8]>,
-        _samples_per_pixel: u16,
-        _bits_per_sample: u16,
-        _lerc_parameters: Option<&[u32]>,
-    ) -> AsyncTiffResult<Vec<u8>> {
-        decode_modern_jpeg(buffer, photometric_interpretation, jpeg_tables)
+    fn decode(&self, buffer: Bytes, context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
+        decode_modern_jpeg(
+            buffer,
+            context.photometric_interpretation(),
+            context.jpeg_tables(),
+        )
     }
 }
 
@@ -148,29 +696,26 @@ fn decode_lerc<T: lerc::LercDataType + bytemuck::Pod>(
 
 #[cfg(feature = "lerc")]
 impl Decoder for LercDecoder {
-    fn decode_tile(
-        &self,
-        buffer: Bytes,
-        _photometric_interpretation: PhotometricInterpretation,
-        _jpeg_tables: Option<&[u8]>,
-        _samples_per_pixel: u16,
-        _bits_per_sample: u16,
-        lerc_parameters: Option<&[u32]>,
-    ) -> AsyncTiffResult<Vec<u8>> {
+    fn decode(&self, buffer: Bytes, context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
         // LercParameters[1] is the inner compression type:
         //   0 = none, 1 = deflate, 2 = zstd
         // Decompress the outer wrapper before passing to the LERC decoder.
-        let lerc_blob: Vec<u8> = match lerc_parameters.and_then(|p| p.get(1).copied()) {
+        // A blob holds at most the raw values, a validity mask and its headers
+        let max_blob_bytes = context
+            .max_decoded_bytes()
+            .saturating_mul(2)
+            .saturating_add(1024);
+        let lerc_blob: Vec<u8> = match context.lerc_parameters().and_then(|p| p.get(1).copied()) {
             Some(1) => {
-                let mut decoder = ZlibDecoder::new(Cursor::new(buffer));
                 let mut buf = Vec::new();
-                decoder.read_to_end(&mut buf)?;
+                let decoder = ZlibDecoder::new(Cursor::new(buffer));
+                read_bounded(decoder, max_blob_bytes, &mut buf)?;
                 buf
             }
             Some(2) => {
-                let mut decoder = zstd::Decoder::new(Cursor::new(buffer))?;
                 let mut buf = Vec::new();
-                decoder.read_to_end(&mut buf)?;
+                let decoder = zstd::Decoder::new(Cursor::new(buffer))?;
+                read_bounded(decoder, max_blob_bytes, &mut buf)?;
                 buf
             }
             _ => buffer.to_vec(),
@@ -178,6 +723,18 @@ impl Decoder for LercDecoder {
 
         let info = lerc::get_blob_info(&lerc_blob)
             .map_err(|e| AsyncTiffError::General(format!("LERC get_blob_info failed: {e}")))?;
+        // The decoder allocates the size declared by the blob, so check it first
+        let value_size = match info.data_type {
+            0 | 1 => 1,
+            2 | 3 => 2,
+            4..=6 => 4,
+            _ => 8,
+        };
+        let decoded_bytes = [info.width, info.height, info.depth, info.bands]
+            .iter()
+            .try_fold(value_size, |bytes: u64, &n| bytes.checked_mul(n as u64))
+            .unwrap_or(u64::MAX);
+        check_decoded_bytes(decoded_bytes, context.max_decoded_bytes())?;
 
         // LERC data_type mapping (from LERC C API):
         // 0=i8, 1=u8, 2=i16, 3=u16, 4=i32, 5=u32, 6=f32, 7=f64
@@ -205,21 +762,13 @@ pub struct LZMADecoder;
 
 #[cfg(feature = "lzma")]
 impl Decoder for LZMADecoder {
-    fn decode_tile(
-        &self,
-        buffer: Bytes,
-        _photometric_interpretation: PhotometricInterpretation,
-        _jpeg_tables: Option<&[u8]>,
-        _samples_per_pixel: u16,
-        _bits_per_sample: u16,
-        _lerc_parameters: Option<&[u32]>,
-    ) -> AsyncTiffResult<Vec<u8>> {
+    fn decode(&self, buffer: Bytes, context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
         use bytes::Buf;
         use lzma_rust2::XzReader;
 
-        let mut reader = XzReader::new(buffer.reader(), false);
+        let reader = XzReader::new(buffer.reader(), false);
         let mut out = Vec::new();
-        reader.read_to_end(&mut out)?;
+        read_bounded(reader, context.max_decoded_bytes(), &mut out)?;
         Ok(out)
     }
 }
@@ -229,19 +778,72 @@ impl Decoder for LZMADecoder {
 pub struct LZWDecoder;
 
 impl Decoder for LZWDecoder {
-    fn decode_tile(
+    fn decode(&self, buffer: Bytes, context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.decode_into(buffer, context, &mut buf)?;
+        Ok(buf)
+    }
+
+    fn decode_into(
         &self,
         buffer: Bytes,
-        _photometric_interpretation: PhotometricInterpretation,
-        _jpeg_tables: Option<&[u8]>,
-        _samples_per_pixel: u16,
-        _bits_per_sample: u16,
-        _lerc_parameters: Option<&[u32]>,
-    ) -> AsyncTiffResult<Vec<u8>> {
+        context: &DecodeContext<'_>,
+        output: &mut Vec<u8>,
+    ) -> AsyncTiffResult<()> {
         // https://github.com/image-rs/image-tiff/blob/90ae5b8e54356a35e266fb24e969aafbcb26e990/src/decoder/stream.rs#L147
         let mut decoder = weezl::decode::Decoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8);
-        let decoded = decoder.decode(&buffer).expect("failed to decode LZW data");
-        Ok(decoded)
+        let max = context.max_decoded_bytes();
+        let start = output.len();
+        let mut input = buffer.as_ref();
+        let mut chunk = [0; 4096];
+        loop {
+            let result = decoder.decode_bytes(input, &mut chunk);
+            input = &input[result.consumed_in..];
+            output.extend_from_slice(&chunk[..result.consumed_out]);
+            check_decoded_bytes((output.len() - start) as u64, max)?;
+            match result.status? {
+                weezl::LzwStatus::Ok => {}
+                weezl::LzwStatus::Done => return Ok(()),
+                // The data ended without an end code
+                weezl::LzwStatus::NoProgress => return Err(weezl::LzwError::InvalidCode.into()),
+            }
+        }
+    }
+}
+
+/// A decoder for the PackBits compression method.
+#[derive(Debug, Clone)]
+pub struct PackBitsDecoder;
+
+impl Decoder for PackBitsDecoder {
+    fn decode(&self, buffer: Bytes, context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
+        let truncated = || AsyncTiffError::General("Truncated PackBits data".to_string());
+        let max = context.max_decoded_bytes();
+        let mut out = Vec::with_capacity(buffer.len() * 2);
+        let mut input = buffer.as_ref();
+        while let Some((&header, rest)) = input.split_first() {
+            let header = header as i8;
+            input = rest;
+            match header {
+                // Copy the next `header + 1` bytes literally
+                0.. => {
+                    let len = header as usize + 1;
+                    let literal = input.get(..len).ok_or_else(truncated)?;
+                    out.extend_from_slice(literal);
+                    input = &input[len..];
+                }
+                // A no-op
+                -128 => {}
+                // Repeat the next byte `1 - header` times
+                _ => {
+                    let (&value, rest) = input.split_first().ok_or_else(truncated)?;
+                    out.resize(out.len() + (1 - header as isize) as usize, value);
+                    input = rest;
+                }
+            }
+            check_decoded_bytes(out.len() as u64, max)?;
+        }
+        Ok(out)
     }
 }
 
@@ -252,15 +854,7 @@ pub struct JPEG2kDecoder;
 
 #[cfg(feature = "jpeg2k")]
 impl Decoder for JPEG2kDecoder {
-    fn decode_tile(
-        &self,
-        buffer: Bytes,
-        _photometric_interpretation: PhotometricInterpretation,
-        _jpeg_tables: Option<&[u8]>,
-        _samples_per_pixel: u16,
-        _bits_per_sample: u16,
-        _lerc_parameters: Option<&[u32]>,
-    ) -> AsyncTiffResult<Vec<u8>> {
+    fn decode(&self, buffer: Bytes, _context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
         let decoder = jpeg2k::DecodeParameters::new();
 
         let image = jpeg2k::Image::from_bytes_with(&buffer, decoder)?;
@@ -286,15 +880,7 @@ pub struct WebPDecoder;
 
 #[cfg(feature = "webp")]
 impl Decoder for WebPDecoder {
-    fn decode_tile(
-        &self,
-        buffer: Bytes,
-        _photometric_interpretation: PhotometricInterpretation,
-        _jpeg_tables: Option<&[u8]>,
-        samples_per_pixel: u16,
-        bits_per_sample: u16,
-        _lerc_parameters: Option<&[u32]>,
-    ) -> AsyncTiffResult<Vec<u8>> {
+    fn decode(&self, buffer: Bytes, context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
         let decoded = webp::Decoder::new(&buffer)
             .decode()
             .ok_or(AsyncTiffError::General("WebP decoding failed".to_string()))?;
@@ -304,7 +890,8 @@ impl Decoder for WebPDecoder {
         // WebP lossy compression may discard fully-opaque alpha channels.
         // If the TIFF expects 4 samples but WebP decoded to 3, expand RGB to RGBA.
         // Only do this for 8-bit data since WebP only supports 8-bit.
-        if samples_per_pixel == 4 && bits_per_sample == 8 && !decoded.is_alpha() {
+        if context.samples_per_pixel() == 4 && context.bits_per_sample() == 8 && !decoded.is_alpha()
+        {
             let mut rgba = Vec::with_capacity(data.len() / 3 * 4);
             for chunk in data.chunks_exact(3) {
                 rgba.extend_from_slice(chunk);
@@ -322,16 +909,18 @@ impl Decoder for WebPDecoder {
 pub struct UncompressedDecoder;
 
 impl Decoder for UncompressedDecoder {
-    fn decode_tile(
+    fn decode(&self, buffer: Bytes, _context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
+        Ok(buffer.to_vec())
+    }
+
+    fn decode_into(
         &self,
         buffer: Bytes,
-        _photometric_interpretation: PhotometricInterpretation,
-        _jpeg_tables: Option<&[u8]>,
-        _samples_per_pixel: u16,
-        _bits_per_sample: u16,
-        _lerc_parameters: Option<&[u32]>,
-    ) -> AsyncTiffResult<Vec<u8>> {
-        Ok(buffer.to_vec())
+        _context: &DecodeContext<'_>,
+        output: &mut Vec<u8>,
+    ) -> AsyncTiffResult<()> {
+        output.extend_from_slice(&buffer);
+        Ok(())
     }
 }
 
@@ -340,20 +929,21 @@ impl Decoder for UncompressedDecoder {
 pub struct ZstdDecoder;
 
 impl Decoder for ZstdDecoder {
-    fn decode_tile(
-        &self,
-        buffer: Bytes,
-        _photometric_interpretation: PhotometricInterpretation,
-        _jpeg_tables: Option<&[u8]>,
-        _samples_per_pixel: u16,
-        _bits_per_sample: u16,
-        _lerc_parameters: Option<&[u32]>,
-    ) -> AsyncTiffResult<Vec<u8>> {
-        let mut decoder = zstd::Decoder::new(Cursor::new(buffer))?;
+    fn decode(&self, buffer: Bytes, context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
         let mut buf = Vec::new();
-        decoder.read_to_end(&mut buf)?;
+        self.decode_into(buffer, context, &mut buf)?;
         Ok(buf)
     }
+
+    fn decode_into(
+        &self,
+        buffer: Bytes,
+        context: &DecodeContext<'_>,
+        output: &mut Vec<u8>,
+    ) -> AsyncTiffResult<()> {
+        let decoder = zstd::Decoder::new(Cursor::new(buffer))?;
+        read_bounded(decoder, context.max_decoded_bytes(), output)
+    }
 }
 
 // https://github.com/image-rs/image-tiff/blob/3bfb43e83e31b0da476832067ada68a82b378b7b/src/decoder/image.rs#L389-L450
@@ -378,9 +968,13 @@ fn decode_modern_jpeg(
         Some(jpeg_tables) => {
             let mut reader = reader;
             reader.read_exact(&mut [0; 2])?;
+            let jpeg_tables = jpeg_tables
+                .len()
+                .checked_sub(2)
+                .map(|len| &jpeg_tables[..len])
+                .ok_or(AsyncTiffError::General("Truncated JPEG tables".to_string()))?;
 
-            Box::new(Cursor::new(&jpeg_tables[..jpeg_tables.len() - 2]).chain(reader))
-                as Box<dyn Read>
+            Box::new(Cursor::new(jpeg_tables).chain(reader)) as Box<dyn Read>
         }
         None => Box::new(reader),
     };
@@ -409,3 +1003,158 @@ fn decode_modern_jpeg(
     let data = decoder.decode()?;
     Ok(data)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode_packbits(data: &[u8]) -> AsyncTiffResult<Vec<u8>> {
+        PackBitsDecoder.decode(
+            Bytes::copy_from_slice(data),
+            &DecodeContext::new(24, 1, 1, 8),
+        )
+    }
+
+    #[test]
+    fn test_packbits() {
+        // The example from the TIFF 6.0 specification
+        let encoded = [
+            0xFE, 0xAA, 0x02, 0x80, 0x00, 0x2A, 0xFD, 0xAA, 0x03, 0x80, 0x00, 0x2A, 0x22, 0xF7,
+            0xAA,
+        ];
+        let expected = [
+            0xAA, 0xAA, 0xAA, 0x80, 0x00, 0x2A, 0xAA, 0xAA, 0xAA, 0xAA, 0x80, 0x00, 0x2A, 0x22,
+            0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA, 0xAA,
+        ];
+        assert_eq!(decode_packbits(&encoded).unwrap(), expected);
+        // A no-op header is skipped
+        assert_eq!(decode_packbits(&[0x80, 0x00, 0x07]).unwrap(), [0x07]);
+    }
+
+    #[test]
+    fn test_decompression_bomb() {
+        use std::io::Write;
+
+        // 64 MiB of zeros compress to a few kilobytes, but the tile only holds 16x16 bytes
+        let zeros = vec![0; 64 * 1024 * 1024];
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+        encoder.write_all(&zeros).unwrap();
+        let deflate = Bytes::from(encoder.finish().unwrap());
+        let zstd = Bytes::from(zstd::encode_all(zeros.as_slice(), 19).unwrap());
+        let lzw = weezl::encode::Encoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+            .encode(&zeros[..1024 * 1024])
+            .unwrap();
+        let packbits = Bytes::from([0x81, 0x00].repeat(1024));
+        assert!(deflate.len() < 1024 * 1024 && zstd.len() < 1024 * 1024);
+
+        let context = DecodeContext::new(16, 16, 1, 8);
+        let cases: [(&dyn Decoder, Bytes); 4] = [
+            (&DeflateDecoder, deflate),
+            (&ZstdDecoder, zstd),
+            (&LZWDecoder, Bytes::from(lzw)),
+            (&PackBitsDecoder, packbits),
+        ];
+        for (decoder, buffer) in cases {
+            let err = decoder.decode(buffer.clone(), &context).unwrap_err();
+            assert!(
+                matches!(err, AsyncTiffError::LimitExceeded { max: 256, .. }),
+                "{decoder:?}: {err}"
+            );
+            let mut output = vec![1, 2, 3];
+            assert!(decoder.decode_into(buffer, &context, &mut output).is_err());
+            assert!(output.len() <= 3 + 256 + 4096, "{decoder:?}");
+        }
+
+        // The expected size of the tile is allowed
+        let buffer = Bytes::from(zstd::encode_all(&zeros[..256], 0).unwrap());
+        assert_eq!(
+            ZstdDecoder.decode(buffer.clone(), &context).unwrap().len(),
+            256
+        );
+        let context = context.with_max_decoded_bytes(100);
+        assert!(ZstdDecoder.decode(buffer, &context).is_err());
+    }
+
+    #[test]
+    fn test_lzw_invalid() {
+        let context = DecodeContext::new(2, 2, 1, 8);
+        let err = LZWDecoder
+            .decode(Bytes::from_static(&[0xFF; 4]), &context)
+            .unwrap_err();
+        assert!(matches!(err, AsyncTiffError::LZWDecodingError(_)));
+
+        let mut output = vec![];
+        assert!(LZWDecoder
+            .decode_into(Bytes::from_static(&[0xFF; 4]), &context, &mut output)
+            .is_err());
+    }
+
+    #[test]
+    fn test_jpeg_tables_truncated() {
+        let context = DecodeContext::new(1, 1, 3, 8)
+            .with_photometric_interpretation(PhotometricInterpretation::RGB)
+            .with_jpeg_tables(Some(&[0xFF]));
+        let err = JPEGDecoder.decode(Bytes::from_static(&[0xFF, 0xD8, 0xFF, 0xD9]), &context);
+        assert!(err.is_err());
+    }
+
+    /// A decoder written against the deprecated API.
+    #[derive(Debug)]
+    struct LegacyDecoder;
+
+    impl Decoder for LegacyDecoder {
+        fn decode_tile(
+            &self,
+            buffer: Bytes,
+            photometric_interpretation: PhotometricInterpretation,
+            _jpeg_tables: Option<&[u8]>,
+            samples_per_pixel: u16,
+            _bits_per_sample: u16,
+            _lerc_parameters: Option<&[u32]>,
+        ) -> AsyncTiffResult<Vec<u8>> {
+            assert_eq!(photometric_interpretation, PhotometricInterpretation::RGB);
+            Ok(buffer.repeat(samples_per_pixel as usize))
+        }
+    }
+
+    #[derive(Debug)]
+    struct NoopDecoder;
+
+    impl Decoder for NoopDecoder {}
+
+    #[test]
+    fn test_decode_context_compat() {
+        let context = DecodeContext::new(1, 1, 3, 8)
+            .with_photometric_interpretation(PhotometricInterpretation::RGB);
+        let mut output = vec![9];
+        LegacyDecoder
+            .decode_into(Bytes::from_static(&[1]), &context, &mut output)
+            .unwrap();
+        assert_eq!(output, [9, 1, 1, 1]);
+        assert!(NoopDecoder
+            .decode(Bytes::from_static(&[1]), &context)
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_decode_context_from_ifd() {
+        let (_, tiff) = crate::test::util::open_tiff("image-tiff/tiled-rgb-u8.tif").await;
+        let ifd = &tiff.ifds()[0];
+        let context = DecodeContext::from_ifd(ifd);
+        assert_eq!(context.width(), ifd.tile_width().unwrap());
+        assert_eq!(context.height(), ifd.tile_height().unwrap());
+        assert_eq!(context.samples_per_pixel(), 3);
+        assert_eq!(context.bits_per_sample(), 8);
+        assert_eq!(
+            context.expected_len(),
+            context.width() as usize * context.height() as usize * 3
+        );
+        assert_eq!(context.with_samples_per_pixel(1).samples_per_pixel(), 1);
+    }
+
+    #[test]
+    fn test_packbits_truncated() {
+        assert!(decode_packbits(&[0x03, 0x01, 0x02]).is_err());
+        assert!(decode_packbits(&[0xFE]).is_err());
+    }
+}
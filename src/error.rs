@@ -28,6 +28,68 @@ pub enum AsyncTiffError {
     #[error("Tile index out of bounds: {0}, {1}")]
     TileIndexError(u32, u32),
 
+    /// A decoder produced fewer bytes than required for the tile's size.
+    #[error("Decoded tile ({x}, {y}) has {actual_bytes} bytes, expected {expected_bytes} bytes")]
+    DecodedSizeMismatch {
+        /// The column index of the tile.
+        x: usize,
+        /// The row index of the tile.
+        y: usize,
+        /// The number of bytes the decoder produced.
+        actual_bytes: usize,
+        /// The number of bytes required for the tile's size and data type.
+        expected_bytes: usize,
+    },
+
+    /// A decoder failed on the compressed data of a tile or strip.
+    #[error("Failed to decode tile ({x}, {y}): {source}")]
+    TileDecodingError {
+        /// The column index of the tile.
+        x: usize,
+        /// The row index of the tile, or the index of the strip.
+        y: usize,
+        /// The error returned by the decoder.
+        source: Box<AsyncTiffError>,
+    },
+
+    /// More fetches were needed than allowed by a
+    /// [`RequestBudget`][crate::metadata::RequestBudget].
+    #[error("Request budget of {budget} exceeded, requested byte ranges: {ranges:?}")]
+    RequestBudgetExceeded {
+        /// The maximum number of requests that were allowed.
+        budget: usize,
+        /// Every byte range requested so far, including the one that exceeded the budget.
+        ranges: Vec<std::ops::Range<u64>>,
+    },
+
+    /// A size claimed by the file exceeded one of the configured
+    /// [`Limits`][crate::metadata::Limits].
+    #[error("Limit exceeded: {limit} is {actual}, the maximum is {max}")]
+    LimitExceeded {
+        /// The name of the limit, such as `"tag count"`.
+        limit: &'static str,
+        /// The size claimed by the file.
+        actual: u64,
+        /// The maximum allowed size.
+        max: u64,
+    },
+
+    /// A tile or strip byte range is reversed or extends past the end of the file.
+    #[error(
+        "Invalid byte range {range:?}{}",
+        file_size.map(|size| format!(" for a file of {size} bytes")).unwrap_or_default()
+    )]
+    InvalidByteRange {
+        /// The requested byte range.
+        range: std::ops::Range<u64>,
+        /// The size of the file, if known.
+        file_size: Option<u64>,
+    },
+
+    /// LZW-compressed data is invalid.
+    #[error("LZW decoding error: {0}")]
+    LZWDecodingError(#[from] weezl::LzwError),
+
     /// IO Error.
     #[error(transparent)]
     IOError(#[from] std::io::Error),
@@ -0,0 +1,420 @@
+//! EXIF and GPS metadata stored in the private IFDs referenced by the `ExifIFD` and `GPSInfo`
+//! tags.
+//!
+//! Cameras and drones record the capture time, exposure settings and position of an image here.
+//! The IFDs are read together with the IFD that points to them, and exposed through
+//! [`ImageFileDirectory::exif`][crate::ImageFileDirectory::exif] and
+//! [`ImageFileDirectory::gps`][crate::ImageFileDirectory::gps].
+//!
+//! <https://www.cipa.jp/std/documents/download_e.html?DC-008-Translation-2023-E>
+
+use std::collections::HashMap;
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+
+use crate::error::{TiffError, TiffResult};
+use crate::tag_value::TagValue;
+use crate::tags::Tag;
+
+/// Tags of the EXIF IFD
+#[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive, IntoPrimitive, Eq, Hash)]
+#[repr(u16)]
+enum ExifTag {
+    ExposureTime = 33434,
+    FNumber = 33437,
+    ExposureProgram = 34850,
+    PhotographicSensitivity = 34855,
+    ExifVersion = 36864,
+    DateTimeOriginal = 36867,
+    DateTimeDigitized = 36868,
+    OffsetTimeOriginal = 36881,
+    OffsetTimeDigitized = 36882,
+    ExposureBiasValue = 37380,
+    FocalLength = 37386,
+    SubSecTimeOriginal = 37521,
+    SubSecTimeDigitized = 37522,
+    FocalLengthIn35mmFilm = 41989,
+    ImageUniqueId = 42016,
+    BodySerialNumber = 42033,
+    LensMake = 42035,
+    LensModel = 42036,
+}
+
+/// Tags of the GPS IFD
+#[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive, IntoPrimitive, Eq, Hash)]
+#[repr(u16)]
+enum GpsTag {
+    VersionId = 0,
+    LatitudeRef = 1,
+    Latitude = 2,
+    LongitudeRef = 3,
+    Longitude = 4,
+    AltitudeRef = 5,
+    Altitude = 6,
+    TimeStamp = 7,
+    Satellites = 8,
+    Dop = 11,
+    SpeedRef = 12,
+    Speed = 13,
+    TrackRef = 14,
+    Track = 15,
+    ImgDirectionRef = 16,
+    ImgDirection = 17,
+    MapDatum = 18,
+    DateStamp = 29,
+}
+
+/// Capture settings from the EXIF IFD.
+///
+/// Dates and times are kept as stored, in the `YYYY:MM:DD HH:MM:SS` format of the EXIF spec.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exif {
+    /// Exposure time, in seconds.
+    pub exposure_time: Option<f64>,
+    /// The F number of the aperture.
+    pub f_number: Option<f64>,
+    /// The program used to set the exposure, such as 2 for normal program or 3 for aperture
+    /// priority.
+    pub exposure_program: Option<u16>,
+    /// The ISO sensitivity.
+    pub iso_speed: Option<u16>,
+    /// The version of the EXIF standard, such as `"0232"`.
+    pub exif_version: Option<String>,
+    /// When the image was captured.
+    pub date_time_original: Option<String>,
+    /// When the image was stored as digital data.
+    pub date_time_digitized: Option<String>,
+    /// The UTC offset of [`date_time_original`][Self::date_time_original], such as `"+01:00"`.
+    pub offset_time_original: Option<String>,
+    /// The UTC offset of [`date_time_digitized`][Self::date_time_digitized].
+    pub offset_time_digitized: Option<String>,
+    /// Fractions of a second of [`date_time_original`][Self::date_time_original], as digits.
+    pub sub_sec_time_original: Option<String>,
+    /// Fractions of a second of [`date_time_digitized`][Self::date_time_digitized], as digits.
+    pub sub_sec_time_digitized: Option<String>,
+    /// Exposure bias, in EV.
+    pub exposure_bias: Option<f64>,
+    /// Focal length of the lens, in millimeters.
+    pub focal_length: Option<f64>,
+    /// The equivalent focal length for a 35mm film camera, in millimeters.
+    pub focal_length_in_35mm_film: Option<u16>,
+    /// A unique identifier of the image.
+    pub image_unique_id: Option<String>,
+    /// The serial number of the camera body.
+    pub body_serial_number: Option<String>,
+    /// The manufacturer of the lens.
+    pub lens_make: Option<String>,
+    /// The model of the lens.
+    pub lens_model: Option<String>,
+
+    /// Tags of the EXIF IFD without a dedicated field, by tag id.
+    pub other_tags: HashMap<u16, TagValue>,
+}
+
+impl Exif {
+    /// Construct a new [`Exif`] from the tags of an EXIF IFD.
+    pub(crate) fn from_tags(tags: HashMap<Tag, TagValue>) -> TiffResult<Self> {
+        let mut exposure_time = None;
+        let mut f_number = None;
+        let mut exposure_program = None;
+        let mut iso_speed = None;
+        let mut exif_version = None;
+        let mut date_time_original = None;
+        let mut date_time_digitized = None;
+        let mut offset_time_original = None;
+        let mut offset_time_digitized = None;
+        let mut sub_sec_time_original = None;
+        let mut sub_sec_time_digitized = None;
+        let mut exposure_bias = None;
+        let mut focal_length = None;
+        let mut focal_length_in_35mm_film = None;
+        let mut image_unique_id = None;
+        let mut body_serial_number = None;
+        let mut lens_make = None;
+        let mut lens_model = None;
+        let mut other_tags = HashMap::new();
+
+        tags.into_iter().try_for_each(|(tag, value)| {
+            let Ok(tag) = ExifTag::try_from(tag.to_u16()) else {
+                other_tags.insert(tag.to_u16(), value);
+                return Ok(());
+            };
+            match tag {
+                ExifTag::ExposureTime => exposure_time = Some(value.into_f64()?),
+                ExifTag::FNumber => f_number = Some(value.into_f64()?),
+                ExifTag::ExposureProgram => exposure_program = Some(value.into_u16()?),
+                // Can have several values, of which the first is the ISO speed
+                ExifTag::PhotographicSensitivity => {
+                    iso_speed = value.into_u16_vec()?.first().copied()
+                }
+                ExifTag::ExifVersion => {
+                    exif_version = Some(String::from_utf8_lossy(&value.into_u8_vec()?).into())
+                }
+                ExifTag::DateTimeOriginal => date_time_original = Some(value.into_string()?),
+                ExifTag::DateTimeDigitized => date_time_digitized = Some(value.into_string()?),
+                ExifTag::OffsetTimeOriginal => offset_time_original = Some(value.into_string()?),
+                ExifTag::OffsetTimeDigitized => offset_time_digitized = Some(value.into_string()?),
+                ExifTag::SubSecTimeOriginal => sub_sec_time_original = Some(value.into_string()?),
+                ExifTag::SubSecTimeDigitized => sub_sec_time_digitized = Some(value.into_string()?),
+                ExifTag::ExposureBiasValue => exposure_bias = Some(value.into_f64()?),
+                ExifTag::FocalLength => focal_length = Some(value.into_f64()?),
+                ExifTag::FocalLengthIn35mmFilm => {
+                    focal_length_in_35mm_film = Some(value.into_u16()?)
+                }
+                ExifTag::ImageUniqueId => image_unique_id = Some(value.into_string()?),
+                ExifTag::BodySerialNumber => body_serial_number = Some(value.into_string()?),
+                ExifTag::LensMake => lens_make = Some(value.into_string()?),
+                ExifTag::LensModel => lens_model = Some(value.into_string()?),
+            };
+            Ok::<_, TiffError>(())
+        })?;
+
+        Ok(Self {
+            exposure_time,
+            f_number,
+            exposure_program,
+            iso_speed,
+            exif_version,
+            date_time_original,
+            date_time_digitized,
+            offset_time_original,
+            offset_time_digitized,
+            sub_sec_time_original,
+            sub_sec_time_digitized,
+            exposure_bias,
+            focal_length,
+            focal_length_in_35mm_film,
+            image_unique_id,
+            body_serial_number,
+            lens_make,
+            lens_model,
+            other_tags,
+        })
+    }
+}
+
+/// Position and motion of the camera from the GPS IFD.
+///
+/// Angles are stored as degrees, minutes and seconds with a separate reference for the
+/// hemisphere. Use [`latitude_degrees`][Self::latitude_degrees],
+/// [`longitude_degrees`][Self::longitude_degrees] and
+/// [`altitude_meters`][Self::altitude_meters] for signed values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gps {
+    /// The version of the GPS IFD, such as `[2, 3, 0, 0]`.
+    pub version_id: Option<Vec<u8>>,
+    /// `"N"` for north or `"S"` for south latitude.
+    pub latitude_ref: Option<String>,
+    /// Latitude as degrees, minutes and seconds.
+    pub latitude: Option<Vec<f64>>,
+    /// `"E"` for east or `"W"` for west longitude.
+    pub longitude_ref: Option<String>,
+    /// Longitude as degrees, minutes and seconds.
+    pub longitude: Option<Vec<f64>>,
+    /// 0 if the altitude is above sea level, 1 if it is below.
+    pub altitude_ref: Option<u8>,
+    /// Altitude relative to sea level, in meters.
+    pub altitude: Option<f64>,
+    /// UTC time of the fix as hours, minutes and seconds.
+    pub time_stamp: Option<Vec<f64>>,
+    /// The satellites used for the fix.
+    pub satellites: Option<String>,
+    /// Dilution of precision of the fix.
+    pub dop: Option<f64>,
+    /// Unit of [`speed`][Self::speed]: `"K"` for km/h, `"M"` for mph or `"N"` for knots.
+    pub speed_ref: Option<String>,
+    /// Speed of the receiver.
+    pub speed: Option<f64>,
+    /// `"T"` if [`track`][Self::track] is relative to true north, `"M"` for magnetic north.
+    pub track_ref: Option<String>,
+    /// Direction of movement, in degrees.
+    pub track: Option<f64>,
+    /// `"T"` if [`img_direction`][Self::img_direction] is relative to true north, `"M"` for
+    /// magnetic north.
+    pub img_direction_ref: Option<String>,
+    /// Direction the camera was pointing, in degrees.
+    pub img_direction: Option<f64>,
+    /// The geodetic datum of the position, such as `"WGS-84"`.
+    pub map_datum: Option<String>,
+    /// UTC date of the fix, in the `YYYY:MM:DD` format.
+    pub date_stamp: Option<String>,
+
+    /// Tags of the GPS IFD without a dedicated field, by tag id.
+    pub other_tags: HashMap<u16, TagValue>,
+}
+
+impl Gps {
+    /// Construct a new [`Gps`] from the tags of a GPS IFD.
+    pub(crate) fn from_tags(tags: HashMap<Tag, TagValue>) -> TiffResult<Self> {
+        let mut version_id = None;
+        let mut latitude_ref = None;
+        let mut latitude = None;
+        let mut longitude_ref = None;
+        let mut longitude = None;
+        let mut altitude_ref = None;
+        let mut altitude = None;
+        let mut time_stamp = None;
+        let mut satellites = None;
+        let mut dop = None;
+        let mut speed_ref = None;
+        let mut speed = None;
+        let mut track_ref = None;
+        let mut track = None;
+        let mut img_direction_ref = None;
+        let mut img_direction = None;
+        let mut map_datum = None;
+        let mut date_stamp = None;
+        let mut other_tags = HashMap::new();
+
+        tags.into_iter().try_for_each(|(tag, value)| {
+            let Ok(tag) = GpsTag::try_from(tag.to_u16()) else {
+                other_tags.insert(tag.to_u16(), value);
+                return Ok(());
+            };
+            match tag {
+                GpsTag::VersionId => version_id = Some(value.into_u8_vec()?),
+                GpsTag::LatitudeRef => latitude_ref = Some(value.into_string()?),
+                GpsTag::Latitude => latitude = Some(value.into_f64_vec()?),
+                GpsTag::LongitudeRef => longitude_ref = Some(value.into_string()?),
+                GpsTag::Longitude => longitude = Some(value.into_f64_vec()?),
+                GpsTag::AltitudeRef => altitude_ref = Some(value.into_u8()?),
+                GpsTag::Altitude => altitude = Some(value.into_f64()?),
+                GpsTag::TimeStamp => time_stamp = Some(value.into_f64_vec()?),
+                GpsTag::Satellites => satellites = Some(value.into_string()?),
+                GpsTag::Dop => dop = Some(value.into_f64()?),
+                GpsTag::SpeedRef => speed_ref = Some(value.into_string()?),
+                GpsTag::Speed => speed = Some(value.into_f64()?),
+                GpsTag::TrackRef => track_ref = Some(value.into_string()?),
+                GpsTag::Track => track = Some(value.into_f64()?),
+                GpsTag::ImgDirectionRef => img_direction_ref = Some(value.into_string()?),
+                GpsTag::ImgDirection => img_direction = Some(value.into_f64()?),
+                GpsTag::MapDatum => map_datum = Some(value.into_string()?),
+                GpsTag::DateStamp => date_stamp = Some(value.into_string()?),
+            };
+            Ok::<_, TiffError>(())
+        })?;
+
+        Ok(Self {
+            version_id,
+            latitude_ref,
+            latitude,
+            longitude_ref,
+            longitude,
+            altitude_ref,
+            altitude,
+            time_stamp,
+            satellites,
+            dop,
+            speed_ref,
+            speed,
+            track_ref,
+            track,
+            img_direction_ref,
+            img_direction,
+            map_datum,
+            date_stamp,
+            other_tags,
+        })
+    }
+
+    /// The latitude in decimal degrees, negative south of the equator.
+    ///
+    /// Returns `None` if the latitude is missing or doesn't have three components.
+    pub fn latitude_degrees(&self) -> Option<f64> {
+        let degrees = decimal_degrees(self.latitude.as_deref()?)?;
+        Some(match self.latitude_ref.as_deref() {
+            Some("S") => -degrees,
+            _ => degrees,
+        })
+    }
+
+    /// The longitude in decimal degrees, negative west of the prime meridian.
+    ///
+    /// Returns `None` if the longitude is missing or doesn't have three components.
+    pub fn longitude_degrees(&self) -> Option<f64> {
+        let degrees = decimal_degrees(self.longitude.as_deref()?)?;
+        Some(match self.longitude_ref.as_deref() {
+            Some("W") => -degrees,
+            _ => degrees,
+        })
+    }
+
+    /// The altitude in meters, negative below sea level.
+    pub fn altitude_meters(&self) -> Option<f64> {
+        let altitude = self.altitude?;
+        Some(match self.altitude_ref {
+            Some(1) => -altitude,
+            _ => altitude,
+        })
+    }
+}
+
+/// Convert degrees, minutes and seconds to decimal degrees.
+fn decimal_degrees(dms: &[f64]) -> Option<f64> {
+    let [degrees, minutes, seconds] = dms.try_into().ok()?;
+    Some(degrees + minutes / 60.0 + seconds / 3600.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_exif_from_tags() {
+        let tags = HashMap::from([
+            (Tag::from_u16_exhaustive(33434), TagValue::Rational(1, 1000)),
+            (
+                Tag::from_u16_exhaustive(34855),
+                TagValue::List(vec![TagValue::Short(100), TagValue::Short(200)]),
+            ),
+            (
+                Tag::from_u16_exhaustive(36864),
+                TagValue::List(b"0232".map(TagValue::Byte).to_vec()),
+            ),
+            (
+                Tag::from_u16_exhaustive(36867),
+                TagValue::Ascii("2024:06:01 12:34:56".into()),
+            ),
+            (Tag::from_u16_exhaustive(41728), TagValue::Byte(3)),
+        ]);
+        let exif = Exif::from_tags(tags).unwrap();
+        assert_eq!(exif.exposure_time, Some(0.001));
+        assert_eq!(exif.iso_speed, Some(100));
+        assert_eq!(exif.exif_version.as_deref(), Some("0232"));
+        assert_eq!(
+            exif.date_time_original.as_deref(),
+            Some("2024:06:01 12:34:56")
+        );
+        assert_eq!(exif.other_tags, HashMap::from([(41728, TagValue::Byte(3))]));
+
+        let tags = HashMap::from([(Tag::from_u16_exhaustive(36867), TagValue::Short(1))]);
+        assert!(Exif::from_tags(tags).is_err());
+    }
+
+    #[test]
+    fn test_gps_position() {
+        let dms = |d, m, s| {
+            TagValue::List(vec![
+                TagValue::Rational(d, 1),
+                TagValue::Rational(m, 1),
+                TagValue::Rational(s, 100),
+            ])
+        };
+        let tags = HashMap::from([
+            (Tag::from_u16_exhaustive(1), TagValue::Ascii("S".into())),
+            (Tag::from_u16_exhaustive(2), dms(33, 51, 3600)),
+            (Tag::from_u16_exhaustive(3), TagValue::Ascii("E".into())),
+            (Tag::from_u16_exhaustive(4), dms(151, 12, 9000)),
+            (Tag::from_u16_exhaustive(5), TagValue::Byte(1)),
+            (Tag::from_u16_exhaustive(6), TagValue::Rational(25, 2)),
+        ]);
+        let gps = Gps::from_tags(tags).unwrap();
+        assert_eq!(gps.latitude_degrees(), Some(-(33.0 + 51.0 / 60.0 + 0.01)));
+        assert_eq!(gps.longitude_degrees(), Some(151.0 + 12.0 / 60.0 + 0.025));
+        assert_eq!(gps.altitude_meters(), Some(-12.5));
+
+        let gps = Gps::from_tags(HashMap::new()).unwrap();
+        assert_eq!(gps.latitude_degrees(), None);
+        assert_eq!(gps.altitude_meters(), None);
+    }
+}
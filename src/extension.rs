@@ -0,0 +1,302 @@
+//! Parsing of the tags of TIFF extensions, such as GeoTIFF or private vendor tags.
+//!
+//! An extension claims a set of tags. When an IFD is parsed, the claimed tags that are present
+//! are passed to the extension instead of landing in
+//! [`ImageFileDirectory::other_tags`][crate::ImageFileDirectory::other_tags], and the parsed
+//! result is available from [`ImageFileDirectory::extension`][crate::ImageFileDirectory::extension].
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::error::AsyncTiffResult;
+use crate::geo::GeoTiffExtension;
+use crate::metadata::ParseOptions;
+use crate::tag_value::TagValue;
+use crate::tags::Tag;
+
+/// The parsed tags of an extension.
+///
+/// This is implemented for every `Debug + PartialEq` type that can be shared between threads, so
+/// extensions can return their own structs.
+pub trait TiffExtension: Debug + Send + Sync + Any {
+    /// This value as [`Any`], to downcast it to its concrete type.
+    fn as_any(&self) -> &dyn Any;
+
+    /// Whether `other` is of the same type and equal to this value.
+    fn dyn_eq(&self, other: &dyn TiffExtension) -> bool;
+}
+
+impl<T: Debug + PartialEq + Send + Sync + Any> TiffExtension for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn dyn_eq(&self, other: &dyn TiffExtension) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+}
+
+/// The parsed tags of the extensions of an IFD, by extension name.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Extensions(pub(crate) HashMap<String, Arc<dyn TiffExtension>>);
+
+impl PartialEq for Extensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self.0.iter().all(|(name, extension)| {
+                other
+                    .0
+                    .get(name)
+                    .is_some_and(|other| extension.dyn_eq(other.as_ref()))
+            })
+    }
+}
+
+/// Parses the tags claimed by an extension into a [`TiffExtension`].
+pub trait TiffExtensionFactory: Debug + Send + Sync {
+    /// The name under which the parsed tags are stored on the IFD.
+    fn name(&self) -> &str;
+
+    /// The tags handled by this extension.
+    fn tags(&self) -> &[Tag];
+
+    /// Parse the claimed tags present in an IFD. This is only called if at least one of them is
+    /// present.
+    ///
+    /// Problems that don't invalidate the whole extension can be recorded in `warnings` instead
+    /// of failing when `options` are lenient. Errors fail the IFD in strict mode, and are recorded
+    /// as a warning otherwise.
+    fn parse_tags(
+        &self,
+        tags: HashMap<Tag, TagValue>,
+        options: &ParseOptions,
+        warnings: &mut Vec<String>,
+    ) -> AsyncTiffResult<Arc<dyn TiffExtension>>;
+}
+
+/// A registry of TIFF extensions, used when parsing IFDs through [`ParseOptions`].
+///
+/// The default registry includes the [`GeoTiffExtension`], which populates the geospatial
+/// accessors of [`ImageFileDirectory`][crate::ImageFileDirectory]. If a tag is claimed by several
+/// extensions, it is passed to the one registered first.
+///
+/// ```
+/// use std::collections::HashMap;
+/// use std::sync::Arc;
+///
+/// use async_tiff::error::AsyncTiffResult;
+/// use async_tiff::extension::{ExtensionRegistry, TiffExtension, TiffExtensionFactory};
+/// use async_tiff::metadata::ParseOptions;
+/// use async_tiff::tags::Tag;
+/// use async_tiff::TagValue;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct VendorMetadata {
+///     version: u32,
+/// }
+///
+/// #[derive(Debug)]
+/// struct VendorExtension;
+///
+/// impl TiffExtensionFactory for VendorExtension {
+///     fn name(&self) -> &str {
+///         "vendor"
+///     }
+///
+///     fn tags(&self) -> &[Tag] {
+///         &[Tag::Unknown(65000)]
+///     }
+///
+///     fn parse_tags(
+///         &self,
+///         mut tags: HashMap<Tag, TagValue>,
+///         _options: &ParseOptions,
+///         _warnings: &mut Vec<String>,
+///     ) -> AsyncTiffResult<Arc<dyn TiffExtension>> {
+///         let version = tags.remove(&Tag::Unknown(65000)).unwrap().into_u32()?;
+///         Ok(Arc::new(VendorMetadata { version }))
+///     }
+/// }
+///
+/// let mut registry = ExtensionRegistry::default();
+/// registry.register(Arc::new(VendorExtension));
+/// let options = ParseOptions::new().with_extensions(registry);
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExtensionRegistry {
+    factories: Vec<Arc<dyn TiffExtensionFactory>>,
+}
+
+impl ExtensionRegistry {
+    /// Create a new registry with no extensions registered.
+    ///
+    /// GeoTIFF tags are then kept in
+    /// [`other_tags`][crate::ImageFileDirectory::other_tags] like any unknown tag.
+    pub fn empty() -> Self {
+        Self { factories: vec![] }
+    }
+
+    /// Register an extension, returning the extension previously registered with the same name,
+    /// if any.
+    pub fn register(
+        &mut self,
+        factory: Arc<dyn TiffExtensionFactory>,
+    ) -> Option<Arc<dyn TiffExtensionFactory>> {
+        match self
+            .factories
+            .iter_mut()
+            .find(|existing| existing.name() == factory.name())
+        {
+            Some(existing) => Some(std::mem::replace(existing, factory)),
+            None => {
+                self.factories.push(factory);
+                None
+            }
+        }
+    }
+
+    /// Remove the extension registered as `name`, returning it if there was one.
+    pub fn remove(&mut self, name: &str) -> Option<Arc<dyn TiffExtensionFactory>> {
+        let index = self.factories.iter().position(|f| f.name() == name)?;
+        Some(self.factories.remove(index))
+    }
+
+    /// Find the extension registered as `name`.
+    pub fn get(&self, name: &str) -> Option<&dyn TiffExtensionFactory> {
+        self.factories
+            .iter()
+            .find(|f| f.name() == name)
+            .map(|f| f.as_ref())
+    }
+
+    /// The registered extensions, in registration order.
+    pub(crate) fn factories(&self) -> &[Arc<dyn TiffExtensionFactory>] {
+        &self.factories
+    }
+
+    /// The index of the extension claiming `tag`, if any.
+    pub(crate) fn claim(&self, tag: Tag) -> Option<usize> {
+        self.factories.iter().position(|f| f.tags().contains(&tag))
+    }
+}
+
+impl Default for ExtensionRegistry {
+    fn default() -> Self {
+        Self {
+            factories: vec![Arc::new(GeoTiffExtension)],
+        }
+    }
+}
+
+impl PartialEq for ExtensionRegistry {
+    fn eq(&self, other: &Self) -> bool {
+        self.factories.len() == other.factories.len()
+            && self
+                .factories
+                .iter()
+                .zip(&other.factories)
+                .all(|(a, b)| Arc::ptr_eq(a, b) || (a.name() == b.name() && a.tags() == b.tags()))
+    }
+}
+
+impl Eq for ExtensionRegistry {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::geo::GeoTiff;
+    use crate::reader::Endianness;
+    use crate::ImageFileDirectory;
+
+    #[derive(Debug, PartialEq)]
+    struct Version(u32);
+
+    #[derive(Debug)]
+    struct VersionExtension;
+
+    impl TiffExtensionFactory for VersionExtension {
+        fn name(&self) -> &str {
+            "version"
+        }
+
+        fn tags(&self) -> &[Tag] {
+            &[Tag::Unknown(65000)]
+        }
+
+        fn parse_tags(
+            &self,
+            mut tags: HashMap<Tag, TagValue>,
+            _options: &ParseOptions,
+            _warnings: &mut Vec<String>,
+        ) -> AsyncTiffResult<Arc<dyn TiffExtension>> {
+            let version = tags.remove(&Tag::Unknown(65000)).unwrap().into_u32()?;
+            Ok(Arc::new(Version(version)))
+        }
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = ExtensionRegistry::default();
+        assert!(registry.get(GeoTiffExtension::NAME).is_some());
+        assert!(registry.register(Arc::new(VersionExtension)).is_none());
+        assert!(registry.register(Arc::new(VersionExtension)).is_some());
+        assert_eq!(registry.factories().len(), 2);
+        assert_eq!(registry.claim(Tag::Unknown(65000)), Some(1));
+        assert_eq!(registry.claim(Tag::ModelTiepoint), Some(0));
+
+        assert!(registry.remove(GeoTiffExtension::NAME).is_some());
+        assert_eq!(registry.claim(Tag::ModelTiepoint), None);
+        assert_ne!(registry, ExtensionRegistry::default());
+    }
+
+    #[test]
+    fn test_parse_ifd() {
+        let tags = || {
+            HashMap::from([
+                (Tag::ImageWidth, TagValue::Short(4)),
+                (Tag::ImageLength, TagValue::Short(4)),
+                (Tag::BitsPerSample, TagValue::Short(8)),
+                (Tag::SamplesPerPixel, TagValue::Short(1)),
+                (Tag::PhotometricInterpretation, TagValue::Short(1)),
+                (
+                    Tag::ModelPixelScale,
+                    TagValue::List(vec![TagValue::Double(2.0); 3]),
+                ),
+                (Tag::Unknown(65000), TagValue::Unsigned(3)),
+            ])
+        };
+        let parse = |options: &ParseOptions| {
+            ImageFileDirectory::from_tags_with_options(tags(), Endianness::LittleEndian, options)
+                .unwrap()
+        };
+
+        let mut registry = ExtensionRegistry::default();
+        registry.register(Arc::new(VersionExtension));
+        let ifd = parse(&ParseOptions::new().with_extensions(registry));
+        assert_eq!(ifd.extension::<Version>("version"), Some(&Version(3)));
+        assert_eq!(ifd.model_pixel_scale(), Some([2.0; 3].as_slice()));
+        assert_eq!(
+            ifd.extension::<GeoTiff>(GeoTiffExtension::NAME)
+                .unwrap()
+                .model_pixel_scale,
+            Some(vec![2.0; 3])
+        );
+        assert!(ifd.other_tags().is_empty());
+
+        // Without extensions, their tags are kept as unknown tags
+        let ifd = parse(&ParseOptions::new().with_extensions(ExtensionRegistry::empty()));
+        assert!(ifd.extensions().is_empty());
+        assert_eq!(ifd.model_pixel_scale(), None);
+        assert_eq!(ifd.other_tags().len(), 2);
+    }
+
+    #[test]
+    fn test_dyn_eq() {
+        let a = Version(1);
+        assert!(a.dyn_eq(&Version(1)));
+        assert!(!a.dyn_eq(&Version(2)));
+        assert!(!a.dyn_eq(&1u32));
+    }
+}
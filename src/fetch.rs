@@ -0,0 +1,296 @@
+use std::ops::Range;
+
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::reader::AsyncFileReader;
+
+/// Options controlling how the byte ranges of multiple tiles are requested, used by
+/// [`ImageFileDirectory::fetch_tiles_with_options`][crate::ImageFileDirectory::fetch_tiles_with_options].
+///
+/// By default all ranges are passed to [`AsyncFileReader::get_byte_ranges`] at once, leaving
+/// parallelism and request merging to the reader.
+#[derive(Debug, Clone, Default)]
+pub struct FetchOptions {
+    concurrency: Option<usize>,
+    coalesce: Option<u64>,
+    max_coalesced_size: Option<u64>,
+}
+
+impl FetchOptions {
+    /// Create new FetchOptions with the default behavior.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issue one request per range with at most `concurrency` requests in flight, instead of
+    /// passing all ranges to the reader at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = Some(concurrency.max(1));
+        self
+    }
+
+    /// Merge ranges that are separated by at most `gap` bytes into a single request.
+    ///
+    /// Tiles that are stored next to each other are then fetched together, at the cost of also
+    /// fetching the bytes between them.
+    pub fn with_coalesce(mut self, gap: u64) -> Self {
+        self.coalesce = Some(gap);
+        self
+    }
+
+    /// Don't merge ranges into requests larger than `size` bytes when coalescing.
+    ///
+    /// Ranges that are larger than `size` on their own are still fetched in a single request.
+    pub fn with_max_coalesced_size(mut self, size: u64) -> Self {
+        self.max_coalesced_size = Some(size);
+        self
+    }
+
+    /// The maximum number of requests in flight, if limited.
+    pub fn concurrency(&self) -> Option<usize> {
+        self.concurrency
+    }
+
+    /// The maximum gap in bytes between merged ranges, if coalescing.
+    pub fn coalesce(&self) -> Option<u64> {
+        self.coalesce
+    }
+
+    /// The maximum size in bytes of a merged request, if limited.
+    pub fn max_coalesced_size(&self) -> Option<u64> {
+        self.max_coalesced_size
+    }
+}
+
+/// Fetch `ranges` from `reader` according to `options`, returning buffers in the same order.
+pub(crate) async fn fetch_ranges(
+    reader: &dyn AsyncFileReader,
+    ranges: Vec<Range<u64>>,
+    options: &FetchOptions,
+) -> AsyncTiffResult<Vec<Bytes>> {
+    let Some(gap) = options.coalesce else {
+        return fetch_uncoalesced(reader, ranges, options.concurrency).await;
+    };
+
+    let max_size = options.max_coalesced_size.unwrap_or(u64::MAX);
+    let (merged, locations) = coalesce_ranges(&ranges, gap, max_size);
+    let buffers = fetch_uncoalesced(reader, merged, options.concurrency).await?;
+    locations
+        .into_iter()
+        .zip(&ranges)
+        .map(|((idx, start), range)| {
+            // Readers may return fewer bytes than requested at the end of the file
+            let len = (range.end - range.start) as usize;
+            let buffer = &buffers[idx];
+            if start + len > buffer.len() {
+                return Err(AsyncTiffError::EndOfFile(
+                    len as u64,
+                    buffer.len().saturating_sub(start) as u64,
+                ));
+            }
+            Ok(buffer.slice(start..start + len))
+        })
+        .collect()
+}
+
+/// Fetch `ranges` like [`fetch_ranges`], but with a separate result for each range.
+///
+/// If any request fails, every range is requested again on its own, so that one failing range
+/// doesn't fail the ranges it was merged or batched with.
+pub(crate) async fn fetch_ranges_with_errors(
+    reader: &dyn AsyncFileReader,
+    ranges: Vec<Range<u64>>,
+    options: &FetchOptions,
+) -> Vec<AsyncTiffResult<Bytes>> {
+    if let Ok(buffers) = fetch_ranges(reader, ranges.clone(), options).await {
+        return buffers.into_iter().map(Ok).collect();
+    }
+    let concurrency = options.concurrency.unwrap_or(ranges.len()).max(1);
+    futures::stream::iter(ranges)
+        .map(|range| reader.get_bytes(range))
+        .buffered(concurrency)
+        .collect()
+        .await
+}
+
+async fn fetch_uncoalesced(
+    reader: &dyn AsyncFileReader,
+    ranges: Vec<Range<u64>>,
+    concurrency: Option<usize>,
+) -> AsyncTiffResult<Vec<Bytes>> {
+    match concurrency {
+        None => reader.get_byte_ranges(ranges).await,
+        Some(concurrency) => {
+            futures::stream::iter(ranges)
+                .map(|range| reader.get_bytes(range))
+                .buffered(concurrency)
+                .try_collect()
+                .await
+        }
+    }
+}
+
+/// Merge ranges separated by at most `gap` bytes, as long as the merged range is at most
+/// `max_size` bytes long.
+///
+/// Returns the merged ranges sorted by offset, and for each input range the index of the merged
+/// range containing it and its offset within that merged range.
+fn coalesce_ranges(
+    ranges: &[Range<u64>],
+    gap: u64,
+    max_size: u64,
+) -> (Vec<Range<u64>>, Vec<(usize, usize)>) {
+    let mut order = (0..ranges.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| ranges[i].start);
+
+    let mut merged: Vec<Range<u64>> = vec![];
+    let mut locations = vec![(0, 0); ranges.len()];
+    for i in order {
+        let range = &ranges[i];
+        match merged.last_mut() {
+            Some(last)
+                if range.start <= last.end.saturating_add(gap)
+                    && range.end.max(last.end) - last.start <= max_size =>
+            {
+                last.end = last.end.max(range.end);
+            }
+            _ => merged.push(range.clone()),
+        }
+        let idx = merged.len() - 1;
+        locations[i] = (idx, (range.start - merged[idx].start) as usize);
+    }
+    (merged, locations)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::decoder::DecoderRegistry;
+    use crate::reader::MemoryReader;
+    use crate::test::util::open_tiff;
+
+    #[test]
+    fn test_coalesce_ranges() {
+        let ranges = vec![100..110, 0..10, 12..20, 5..8];
+        let (merged, locations) = coalesce_ranges(&ranges, 2, u64::MAX);
+        assert_eq!(merged, vec![0..20, 100..110]);
+        assert_eq!(locations, vec![(1, 0), (0, 0), (0, 12), (0, 5)]);
+
+        let (merged, _) = coalesce_ranges(&ranges, 1, u64::MAX);
+        assert_eq!(merged, vec![0..10, 12..20, 100..110]);
+
+        // Overlapping ranges are still merged when they fit, but 12..20 would exceed the limit
+        let (merged, locations) = coalesce_ranges(&ranges, 2, 10);
+        assert_eq!(merged, vec![0..10, 12..20, 100..110]);
+        assert_eq!(locations, vec![(2, 0), (0, 0), (1, 0), (0, 5)]);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_coalesced_past_eof() {
+        let reader = MemoryReader::from((0..15u8).collect::<Vec<_>>());
+        let options = FetchOptions::new().with_coalesce(0);
+        let result = fetch_ranges(&reader, vec![0..10, 10..20], &options).await;
+        assert!(matches!(result, Err(AsyncTiffError::EndOfFile(10, 5))));
+
+        let results = fetch_ranges_with_errors(&reader, vec![0..10, 20..30], &options).await;
+        assert_eq!(results[0].as_ref().unwrap().len(), 10);
+        assert!(results[1].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_tiles_with_options() {
+        let (reader, tiff) = open_tiff("image-tiff/tiled-rgb-u8.tif").await;
+        let ifd = &tiff.ifds()[0];
+        let (tiles_x, tiles_y) = ifd.tile_count().unwrap();
+        let xy = (0..tiles_y)
+            .flat_map(|y| (0..tiles_x).map(move |x| (x, y)))
+            .rev()
+            .collect::<Vec<_>>();
+        let expected = ifd.fetch_tiles(&xy, reader.as_ref()).await.unwrap();
+
+        for options in [
+            FetchOptions::new().with_concurrency(2),
+            FetchOptions::new().with_coalesce(1024),
+            FetchOptions::new().with_concurrency(3).with_coalesce(0),
+            FetchOptions::new()
+                .with_coalesce(1024)
+                .with_max_coalesced_size(4096),
+        ] {
+            let tiles = ifd
+                .fetch_tiles_with_options(&xy, reader.as_ref(), &options)
+                .await
+                .unwrap();
+            assert_eq!(tiles.len(), expected.len());
+            for (tile, expected) in tiles.iter().zip(&expected) {
+                assert_eq!((tile.x(), tile.y()), (expected.x(), expected.y()));
+                assert_eq!(
+                    format!("{:?}", tile.compressed_bytes()),
+                    format!("{:?}", expected.compressed_bytes())
+                );
+            }
+        }
+    }
+
+    /// A reader that fails every request overlapping `bad`.
+    #[derive(Debug)]
+    struct FailingReader {
+        inner: Arc<dyn AsyncFileReader>,
+        bad: Range<u64>,
+    }
+
+    #[async_trait]
+    impl AsyncFileReader for FailingReader {
+        async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+            if range.start < self.bad.end && self.bad.start < range.end {
+                return Err(AsyncTiffError::General("bad range".to_string()));
+            }
+            self.inner.get_bytes(range).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fetch_tiles_with_errors() {
+        let (reader, tiff) = open_tiff("image-tiff/tiled-rgb-u8.tif").await;
+        let ifd = &tiff.ifds()[0];
+        let Some(crate::TileByteRange::Chunky(bad)) = ifd.tile_byte_range(1, 0) else {
+            panic!("expected a chunky tile");
+        };
+        let reader = FailingReader { inner: reader, bad };
+        let xy = [(0, 0), (1, 0), (0, 1), (100, 0)];
+
+        for options in [
+            FetchOptions::new(),
+            FetchOptions::new().with_concurrency(2),
+            FetchOptions::new().with_coalesce(u64::MAX),
+        ] {
+            let tiles = ifd.fetch_tiles_with_errors(&xy, &reader, &options).await;
+            assert_eq!(tiles.len(), 4);
+            assert_eq!(tiles[0].as_ref().unwrap().x(), 0);
+            assert!(matches!(tiles[1], Err(AsyncTiffError::General(_))));
+            assert_eq!(tiles[2].as_ref().unwrap().y(), 1);
+            assert!(matches!(
+                tiles[3],
+                Err(AsyncTiffError::TileIndexError(100, 0))
+            ));
+        }
+
+        let arrays = ifd
+            .read_tiles_with_errors(
+                &xy,
+                &reader,
+                &DecoderRegistry::default(),
+                &FetchOptions::new(),
+            )
+            .await;
+        assert!(arrays[0].is_ok());
+        assert!(arrays[1].is_err());
+        assert!(arrays[2].is_ok());
+        assert!(arrays[3].is_err());
+    }
+}
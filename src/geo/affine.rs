@@ -0,0 +1,145 @@
+/// An affine transform from pixel to model (CRS) coordinates.
+///
+/// The coefficients follow the convention of the Python `affine` package used by rasterio:
+///
+/// ```text
+/// x = a * col + b * row + c
+/// y = d * col + e * row + f
+/// ```
+///
+/// Pixel coordinates refer to the top-left corner of a pixel, so the center of the pixel at
+/// column `i` and row `j` is at `(i + 0.5, j + 0.5)`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AffineTransform {
+    /// Pixel width.
+    pub a: f64,
+    /// Row rotation.
+    pub b: f64,
+    /// The x coordinate of the top-left corner of the image.
+    pub c: f64,
+    /// Column rotation.
+    pub d: f64,
+    /// Pixel height, negative for north-up images.
+    pub e: f64,
+    /// The y coordinate of the top-left corner of the image.
+    pub f: f64,
+}
+
+impl AffineTransform {
+    /// Construct a transform from its coefficients, in `affine` package order.
+    pub fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    /// Construct a transform from a GDAL geotransform:
+    /// `[origin_x, pixel_width, row_rotation, origin_y, column_rotation, pixel_height]`.
+    pub fn from_gdal(geotransform: [f64; 6]) -> Self {
+        let [c, a, b, f, d, e] = geotransform;
+        Self { a, b, c, d, e, f }
+    }
+
+    /// This transform as a GDAL geotransform.
+    pub fn to_gdal(&self) -> [f64; 6] {
+        [self.c, self.a, self.b, self.f, self.d, self.e]
+    }
+
+    /// Map a pixel position to model coordinates.
+    pub fn pixel_to_crs(&self, col: f64, row: f64) -> (f64, f64) {
+        (
+            self.a * col + self.b * row + self.c,
+            self.d * col + self.e * row + self.f,
+        )
+    }
+
+    /// Map model coordinates to a fractional pixel position.
+    ///
+    /// Returns `None` if the transform isn't invertible.
+    pub fn crs_to_pixel(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        Some(self.inverse()?.pixel_to_crs(x, y))
+    }
+
+    /// The transform from model coordinates to pixel positions, or `None` if this transform
+    /// collapses the image to a line or point.
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.a * self.e - self.b * self.d;
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+        let a = self.e / determinant;
+        let b = -self.b / determinant;
+        let d = -self.d / determinant;
+        let e = self.a / determinant;
+        Some(Self {
+            a,
+            b,
+            c: -a * self.c - b * self.f,
+            d,
+            e,
+            f: -d * self.c - e * self.f,
+        })
+    }
+
+    /// The bounding box in model coordinates of the pixel rectangle from `(col_min, row_min)`
+    /// to `(col_max, row_max)`, as `[min_x, min_y, max_x, max_y]`.
+    pub fn bounds(&self, col_min: f64, row_min: f64, col_max: f64, row_max: f64) -> [f64; 4] {
+        let corners = [
+            (col_min, row_min),
+            (col_max, row_min),
+            (col_min, row_max),
+            (col_max, row_max),
+        ]
+        .map(|(col, row)| self.pixel_to_crs(col, row));
+        let mut bounds = [
+            f64::INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NEG_INFINITY,
+        ];
+        for (x, y) in corners {
+            bounds[0] = bounds[0].min(x);
+            bounds[1] = bounds[1].min(y);
+            bounds[2] = bounds[2].max(x);
+            bounds[3] = bounds[3].max(y);
+        }
+        bounds
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_gdal_round_trip() {
+        let geotransform = [100.0, 2.0, 0.5, 200.0, 0.25, -2.0];
+        let transform = AffineTransform::from_gdal(geotransform);
+        assert_eq!(
+            transform,
+            AffineTransform::new(2.0, 0.5, 100.0, 0.25, -2.0, 200.0)
+        );
+        assert_eq!(transform.to_gdal(), geotransform);
+    }
+
+    #[test]
+    fn test_pixel_crs_round_trip() {
+        let transform = AffineTransform::new(2.0, 0.5, 100.0, 0.25, -2.0, 200.0);
+        assert_eq!(transform.pixel_to_crs(0.0, 0.0), (100.0, 200.0));
+        assert_eq!(transform.pixel_to_crs(10.0, 4.0), (122.0, 194.5));
+
+        let (col, row) = transform.crs_to_pixel(122.0, 194.5).unwrap();
+        assert!((col - 10.0).abs() < 1e-12);
+        assert!((row - 4.0).abs() < 1e-12);
+
+        let degenerate = AffineTransform::new(1.0, 1.0, 0.0, 1.0, 1.0, 0.0);
+        assert!(degenerate.crs_to_pixel(0.0, 0.0).is_none());
+    }
+
+    #[test]
+    fn test_bounds() {
+        let transform = AffineTransform::from_gdal([100.0, 2.0, 0.0, 200.0, 0.0, -2.0]);
+        assert_eq!(
+            transform.bounds(0.0, 0.0, 10.0, 5.0),
+            [100.0, 190.0, 120.0, 200.0]
+        );
+    }
+}
@@ -0,0 +1,513 @@
+//! Coordinate reference systems described by GeoTIFF keys.
+//!
+//! Most GeoTIFFs reference a CRS of the EPSG registry by code, which [`Crs::from_geo_keys`]
+//! returns as [`Crs::Epsg`]. User-defined CRSs are built from the datum, ellipsoid and
+//! projection keys and returned as [PROJJSON], which PROJ-based libraries like pyproj and
+//! rasterio accept directly. This is done in pure Rust, without a copy of the EPSG database, so
+//! only user-defined CRSs whose every component is either user-defined or one of a few common
+//! EPSG datums and ellipsoids are supported.
+//!
+//! [PROJJSON]: https://proj.org/en/stable/specifications/projjson.html
+
+use std::fmt;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::geo::GeoKeyDirectory;
+use crate::manifest::json_string;
+
+/// The value of a geo key that marks a component as user-defined by other keys.
+pub const USER_DEFINED: u16 = 32767;
+
+const MODEL_TYPE_PROJECTED: u16 = 1;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+
+const PROJJSON_SCHEMA: &str = "https://proj.org/schemas/v0.7/projjson.schema.json";
+
+/// A coordinate reference system, as described by a [`GeoKeyDirectory`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Crs {
+    /// A CRS of the EPSG registry.
+    Epsg(u16),
+    /// A user-defined CRS, as a PROJJSON document.
+    ProjJson(String),
+}
+
+impl Crs {
+    /// Derive the CRS of `geo_keys`, returning an error if it is user-defined in a way that
+    /// isn't supported.
+    pub fn from_geo_keys(geo_keys: &GeoKeyDirectory) -> AsyncTiffResult<Self> {
+        if let Some(code) = epsg_code(geo_keys) {
+            return Ok(Self::Epsg(code));
+        }
+        let projjson = match geo_keys.model_type {
+            Some(MODEL_TYPE_PROJECTED) => projected_crs(geo_keys)?,
+            Some(MODEL_TYPE_GEOGRAPHIC) => {
+                let geographic = geographic_crs(geo_keys)?;
+                format!(
+                    r#"{{"$schema":"{PROJJSON_SCHEMA}","type":"GeographicCRS",{}}}"#,
+                    &geographic[1..geographic.len() - 1]
+                )
+            }
+            model_type => return Err(unsupported(format!("model type {model_type:?}"))),
+        };
+        Ok(Self::ProjJson(projjson))
+    }
+
+    /// The EPSG code of this CRS, if it is part of the EPSG registry.
+    pub fn epsg(&self) -> Option<u16> {
+        match self {
+            Self::Epsg(code) => Some(*code),
+            Self::ProjJson(_) => None,
+        }
+    }
+}
+
+/// Formats the CRS as `EPSG:<code>` or as PROJJSON, both of which can be passed to
+/// `pyproj.CRS.from_user_input` or `rasterio.crs.CRS.from_user_input`.
+impl fmt::Display for Crs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Epsg(code) => write!(f, "EPSG:{code}"),
+            Self::ProjJson(projjson) => f.write_str(projjson),
+        }
+    }
+}
+
+/// The EPSG code of the CRS of `geo_keys`, if it isn't user-defined.
+pub(crate) fn epsg_code(geo_keys: &GeoKeyDirectory) -> Option<u16> {
+    let code = match geo_keys.model_type {
+        Some(MODEL_TYPE_PROJECTED) => geo_keys.projected_type,
+        Some(MODEL_TYPE_GEOGRAPHIC) => geo_keys.geographic_type,
+        _ => geo_keys.projected_type.or(geo_keys.geographic_type),
+    };
+    code.filter(|&code| code != 0 && code != USER_DEFINED)
+}
+
+fn unsupported(what: String) -> AsyncTiffError {
+    AsyncTiffError::General(format!("unsupported user-defined CRS: {what}"))
+}
+
+/// A common geographic CRS: EPSG code, name, datum code.
+const GEOGRAPHIC_CRSS: &[(u16, &str, u16)] = &[
+    (4326, "WGS 84", 6326),
+    (4269, "NAD83", 6269),
+    (4258, "ETRS89", 6258),
+    (4267, "NAD27", 6267),
+];
+
+/// A common datum: EPSG code, name, ellipsoid code.
+const DATUMS: &[(u16, &str, u16)] = &[
+    (6326, "World Geodetic System 1984", 7030),
+    (6269, "North American Datum 1983", 7019),
+    (6258, "European Terrestrial Reference System 1989", 7019),
+    (6267, "North American Datum 1927", 7008),
+];
+
+/// A common ellipsoid: EPSG code, name, semi-major axis in metres, inverse flattening.
+const ELLIPSOIDS: &[(u16, &str, f64, f64)] = &[
+    (7030, "WGS 84", 6378137.0, 298.257223563),
+    (7019, "GRS 1980", 6378137.0, 298.257222101),
+    (7008, "Clarke 1866", 6378206.4, 294.978698213898),
+    (7022, "International 1924", 6378388.0, 297.0),
+    (7004, "Bessel 1841", 6377397.155, 299.1528128),
+    (7043, "WGS 72", 6378135.0, 298.26),
+];
+
+fn epsg_id(code: u16) -> String {
+    format!(r#"{{"authority":"EPSG","code":{code}}}"#)
+}
+
+fn name(citation: Option<&str>) -> String {
+    json_string(citation.unwrap_or("unknown"))
+}
+
+/// The geographic CRS of `geo_keys`, as a PROJJSON object without a type.
+fn geographic_crs(geo_keys: &GeoKeyDirectory) -> AsyncTiffResult<String> {
+    let known = geo_keys
+        .geographic_type
+        .filter(|&code| code != USER_DEFINED)
+        .map(|code| {
+            GEOGRAPHIC_CRSS
+                .iter()
+                .find(|(epsg, _, _)| *epsg == code)
+                .ok_or(unsupported(format!("geographic CRS EPSG:{code}")))
+        })
+        .transpose()?;
+    let datum_code = geo_keys
+        .geog_geodetic_datum
+        .filter(|&code| code != USER_DEFINED)
+        .or(known.map(|(_, _, datum)| *datum));
+    let datum = datum_code
+        .map(|code| {
+            DATUMS
+                .iter()
+                .find(|(epsg, _, _)| *epsg == code)
+                .ok_or(unsupported(format!("datum EPSG:{code}")))
+        })
+        .transpose()?;
+
+    let ellipsoid = ellipsoid(geo_keys, datum.map(|(_, _, ellipsoid)| *ellipsoid))?;
+    let prime_meridian = match (
+        geo_keys.geog_prime_meridian,
+        geo_keys.geog_prime_meridian_long,
+    ) {
+        (_, Some(longitude)) if longitude != 0.0 => {
+            format!(r#","prime_meridian":{{"name":"unknown","longitude":{longitude}}}"#)
+        }
+        (None | Some(8901) | Some(USER_DEFINED), _) => String::new(),
+        (Some(code), _) => return Err(unsupported(format!("prime meridian EPSG:{code}"))),
+    };
+    let datum_name = match datum {
+        Some((_, name, _)) => json_string(name),
+        None => name(geo_keys.geog_citation.as_deref()),
+    };
+    let datum_id = datum
+        .map(|(code, _, _)| format!(r#","id":{}"#, epsg_id(*code)))
+        .unwrap_or_default();
+    let crs_name = match known {
+        Some((_, name, _)) => json_string(name),
+        None => name(geo_keys.geog_citation.as_deref()),
+    };
+    let crs_id = known
+        .map(|(code, _, _)| format!(r#","id":{}"#, epsg_id(*code)))
+        .unwrap_or_default();
+    let unit = angular_unit(geo_keys)?;
+    Ok(format!(
+        concat!(
+            r#"{{"name":{},"datum":{{"type":"GeodeticReferenceFrame","name":{},"ellipsoid":{}{}{}}},"#,
+            r#""coordinate_system":{{"subtype":"ellipsoidal","axis":["#,
+            r#"{{"name":"Geodetic latitude","abbreviation":"Lat","direction":"north","unit":{}}},"#,
+            r#"{{"name":"Geodetic longitude","abbreviation":"Lon","direction":"east","unit":{}}}]}}{}}}"#
+        ),
+        crs_name, datum_name, ellipsoid, prime_meridian, datum_id, unit, unit, crs_id
+    ))
+}
+
+/// The ellipsoid of `geo_keys`, as a PROJJSON object, falling back to the ellipsoid of the datum.
+fn ellipsoid(geo_keys: &GeoKeyDirectory, datum_ellipsoid: Option<u16>) -> AsyncTiffResult<String> {
+    if let Some(semi_major_axis) = geo_keys.geog_semi_major_axis {
+        let shape = match (geo_keys.geog_inv_flattening, geo_keys.geog_semi_minor_axis) {
+            (Some(inverse_flattening), _) => {
+                format!(r#""inverse_flattening":{inverse_flattening}"#)
+            }
+            (None, Some(semi_minor_axis)) => format!(r#""semi_minor_axis":{semi_minor_axis}"#),
+            (None, None) => r#""inverse_flattening":0"#.to_string(),
+        };
+        return Ok(format!(
+            r#"{{"name":"unknown","semi_major_axis":{semi_major_axis},{shape}}}"#
+        ));
+    }
+    let code = geo_keys
+        .geog_ellipsoid
+        .filter(|&code| code != USER_DEFINED)
+        .or(datum_ellipsoid)
+        .ok_or(unsupported("no ellipsoid".to_string()))?;
+    let (_, name, semi_major_axis, inverse_flattening) = ELLIPSOIDS
+        .iter()
+        .find(|(epsg, ..)| *epsg == code)
+        .ok_or(unsupported(format!("ellipsoid EPSG:{code}")))?;
+    Ok(format!(
+        r#"{{"name":{},"semi_major_axis":{semi_major_axis},"inverse_flattening":{inverse_flattening},"id":{}}}"#,
+        json_string(name),
+        epsg_id(code)
+    ))
+}
+
+/// The unit of angles, which applies to the geographic axes and projection parameters.
+fn angular_unit(geo_keys: &GeoKeyDirectory) -> AsyncTiffResult<String> {
+    match geo_keys.geog_angular_units {
+        None | Some(9102) => Ok(r#""degree""#.to_string()),
+        Some(9101) => Ok(r#""radian""#.to_string()),
+        Some(USER_DEFINED) => match geo_keys.geog_angular_unit_size {
+            Some(size) => Ok(format!(
+                r#"{{"type":"AngularUnit","name":"unknown","conversion_factor":{size}}}"#
+            )),
+            None => Err(unsupported("angular unit without size".to_string())),
+        },
+        Some(code) => Err(unsupported(format!("angular unit EPSG:{code}"))),
+    }
+}
+
+/// The unit of the projected axes and of false eastings and northings.
+fn linear_unit(geo_keys: &GeoKeyDirectory) -> AsyncTiffResult<String> {
+    match geo_keys.proj_linear_units {
+        None | Some(9001) => Ok(r#""metre""#.to_string()),
+        Some(9002) => Ok(
+            r#"{"type":"LinearUnit","name":"foot","conversion_factor":0.3048}"#.to_string(),
+        ),
+        Some(9003) => Ok(
+            r#"{"type":"LinearUnit","name":"US survey foot","conversion_factor":0.304800609601219}"#
+                .to_string(),
+        ),
+        Some(USER_DEFINED) => match geo_keys.proj_linear_unit_size {
+            Some(size) => Ok(format!(
+                r#"{{"type":"LinearUnit","name":"unknown","conversion_factor":{size}}}"#
+            )),
+            None => Err(unsupported("linear unit without size".to_string())),
+        },
+        Some(code) => Err(unsupported(format!("linear unit EPSG:{code}"))),
+    }
+}
+
+/// The kind of a projection parameter, which determines its unit.
+#[derive(Clone, Copy)]
+enum Kind {
+    Angle,
+    Length,
+    Scale,
+}
+
+/// The user-defined projected CRS of `geo_keys`, as a PROJJSON document.
+fn projected_crs(geo_keys: &GeoKeyDirectory) -> AsyncTiffResult<String> {
+    if geo_keys
+        .projection
+        .is_some_and(|projection| projection != USER_DEFINED)
+    {
+        return Err(unsupported(format!(
+            "projection EPSG:{}",
+            geo_keys.projection.unwrap_or_default()
+        )));
+    }
+    let k = geo_keys;
+    let nat_origin = [
+        (
+            "Latitude of natural origin",
+            8801,
+            Kind::Angle,
+            k.proj_nat_origin_lat,
+        ),
+        (
+            "Longitude of natural origin",
+            8802,
+            Kind::Angle,
+            k.proj_nat_origin_long,
+        ),
+        (
+            "Scale factor at natural origin",
+            8805,
+            Kind::Scale,
+            k.proj_scale_at_nat_origin.or(Some(1.0)),
+        ),
+        ("False easting", 8806, Kind::Length, k.proj_false_easting),
+        ("False northing", 8807, Kind::Length, k.proj_false_northing),
+    ];
+    let false_origin = [
+        (
+            "Latitude of false origin",
+            8821,
+            Kind::Angle,
+            k.proj_false_origin_lat.or(k.proj_nat_origin_lat),
+        ),
+        (
+            "Longitude of false origin",
+            8822,
+            Kind::Angle,
+            k.proj_false_origin_long.or(k.proj_nat_origin_long),
+        ),
+        (
+            "Latitude of 1st standard parallel",
+            8823,
+            Kind::Angle,
+            k.proj_std_parallel1,
+        ),
+        (
+            "Latitude of 2nd standard parallel",
+            8824,
+            Kind::Angle,
+            k.proj_std_parallel2,
+        ),
+        (
+            "Easting at false origin",
+            8826,
+            Kind::Length,
+            k.proj_false_origin_easting.or(k.proj_false_easting),
+        ),
+        (
+            "Northing at false origin",
+            8827,
+            Kind::Length,
+            k.proj_false_origin_northing.or(k.proj_false_northing),
+        ),
+    ];
+    let (method, method_code, parameters) = match k.proj_coord_trans {
+        Some(1) => ("Transverse Mercator", 9807, nat_origin.to_vec()),
+        Some(7) => ("Mercator (variant A)", 9804, nat_origin.to_vec()),
+        Some(8) => ("Lambert Conic Conformal (2SP)", 9802, false_origin.to_vec()),
+        Some(9) => ("Lambert Conic Conformal (1SP)", 9801, nat_origin.to_vec()),
+        Some(11) => ("Albers Equal Area", 9822, false_origin.to_vec()),
+        Some(15) => {
+            let mut parameters = nat_origin.to_vec();
+            parameters[1].3 = k.proj_straight_vert_pole_long.or(k.proj_nat_origin_long);
+            ("Polar Stereographic (variant A)", 9810, parameters)
+        }
+        Some(10) => (
+            "Lambert Azimuthal Equal Area",
+            9820,
+            vec![
+                (
+                    "Latitude of natural origin",
+                    8801,
+                    Kind::Angle,
+                    k.proj_center_lat.or(k.proj_nat_origin_lat),
+                ),
+                (
+                    "Longitude of natural origin",
+                    8802,
+                    Kind::Angle,
+                    k.proj_center_long.or(k.proj_nat_origin_long),
+                ),
+                nat_origin[3],
+                nat_origin[4],
+            ],
+        ),
+        Some(17) => (
+            "Equidistant Cylindrical",
+            1028,
+            vec![
+                (
+                    "Latitude of 1st standard parallel",
+                    8823,
+                    Kind::Angle,
+                    k.proj_std_parallel1,
+                ),
+                (
+                    "Longitude of natural origin",
+                    8802,
+                    Kind::Angle,
+                    k.proj_center_long.or(k.proj_nat_origin_long),
+                ),
+                nat_origin[3],
+                nat_origin[4],
+            ],
+        ),
+        coord_trans => {
+            return Err(unsupported(format!(
+                "coordinate transformation {coord_trans:?}"
+            )))
+        }
+    };
+
+    let angular_unit = angular_unit(geo_keys)?;
+    let linear_unit = linear_unit(geo_keys)?;
+    let parameters = parameters
+        .into_iter()
+        .map(|(name, code, kind, value)| {
+            let unit = match kind {
+                Kind::Angle => &angular_unit,
+                Kind::Length => &linear_unit,
+                Kind::Scale => r#""unity""#,
+            };
+            format!(
+                r#"{{"name":"{name}","value":{},"unit":{unit},"id":{}}}"#,
+                value.unwrap_or(0.0),
+                epsg_id(code)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",");
+
+    Ok(format!(
+        concat!(
+            r#"{{"$schema":"{}","type":"ProjectedCRS","name":{},"base_crs":{},"#,
+            r#""conversion":{{"name":"unknown","method":{{"name":"{}","id":{}}},"parameters":[{}]}},"#,
+            r#""coordinate_system":{{"subtype":"Cartesian","axis":["#,
+            r#"{{"name":"Easting","abbreviation":"E","direction":"east","unit":{}}},"#,
+            r#"{{"name":"Northing","abbreviation":"N","direction":"north","unit":{}}}]}}}}"#
+        ),
+        PROJJSON_SCHEMA,
+        name(k.proj_citation.as_deref().or(k.citation.as_deref())),
+        geographic_crs(geo_keys)?,
+        method,
+        epsg_id(method_code),
+        parameters,
+        linear_unit,
+        linear_unit
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::geo::GeoKeyTag;
+    use crate::tag_value::TagValue;
+
+    fn geo_keys(keys: &[(GeoKeyTag, TagValue)]) -> GeoKeyDirectory {
+        GeoKeyDirectory::from_tags(keys.iter().cloned().collect(), HashMap::new()).unwrap()
+    }
+
+    #[test]
+    fn test_epsg_crs() {
+        let keys = geo_keys(&[
+            (GeoKeyTag::ModelType, TagValue::Short(1)),
+            (GeoKeyTag::GeographicType, TagValue::Short(4326)),
+            (GeoKeyTag::ProjectedType, TagValue::Short(32631)),
+        ]);
+        let crs = Crs::from_geo_keys(&keys).unwrap();
+        assert_eq!(crs, Crs::Epsg(32631));
+        assert_eq!(crs.to_string(), "EPSG:32631");
+
+        let keys = geo_keys(&[
+            (GeoKeyTag::ModelType, TagValue::Short(2)),
+            (GeoKeyTag::GeographicType, TagValue::Short(4269)),
+        ]);
+        assert_eq!(keys.crs().unwrap().epsg(), Some(4269));
+    }
+
+    #[test]
+    fn test_user_defined_crs() {
+        // UTM zone 31N on WGS 84, spelled out
+        let keys = geo_keys(&[
+            (GeoKeyTag::ModelType, TagValue::Short(1)),
+            (GeoKeyTag::GeographicType, TagValue::Short(4326)),
+            (GeoKeyTag::ProjectedType, TagValue::Short(USER_DEFINED)),
+            (
+                GeoKeyTag::ProjCitation,
+                TagValue::Ascii("custom UTM".into()),
+            ),
+            (GeoKeyTag::Projection, TagValue::Short(USER_DEFINED)),
+            (GeoKeyTag::ProjCoordTrans, TagValue::Short(1)),
+            (GeoKeyTag::ProjLinearUnits, TagValue::Short(9001)),
+            (GeoKeyTag::ProjNatOriginLat, TagValue::Double(0.0)),
+            (GeoKeyTag::ProjNatOriginLong, TagValue::Double(3.0)),
+            (GeoKeyTag::ProjScaleAtNatOrigin, TagValue::Double(0.9996)),
+            (GeoKeyTag::ProjFalseEasting, TagValue::Double(500000.0)),
+            (GeoKeyTag::ProjFalseNorthing, TagValue::Double(0.0)),
+        ]);
+        let Crs::ProjJson(projjson) = Crs::from_geo_keys(&keys).unwrap() else {
+            panic!("expected a user-defined CRS");
+        };
+        assert!(projjson.starts_with(r#"{"$schema":"#));
+        assert!(projjson.contains(r#""type":"ProjectedCRS","name":"custom UTM""#));
+        assert!(projjson
+            .contains(r#"{"name":"Transverse Mercator","id":{"authority":"EPSG","code":9807}}"#));
+        assert!(projjson.contains(r#"{"name":"Longitude of natural origin","value":3,"unit":"degree","id":{"authority":"EPSG","code":8802}}"#));
+        assert!(
+            projjson.contains(r#""semi_major_axis":6378137,"inverse_flattening":298.257223563"#)
+        );
+        assert!(projjson.contains(r#""id":{"authority":"EPSG","code":4326}"#));
+        assert_eq!(projjson.matches('{').count(), projjson.matches('}').count());
+
+        // A geographic CRS on a user-defined ellipsoid
+        let keys = geo_keys(&[
+            (GeoKeyTag::ModelType, TagValue::Short(2)),
+            (GeoKeyTag::GeographicType, TagValue::Short(USER_DEFINED)),
+            (GeoKeyTag::GeogCitation, TagValue::Ascii("sphere".into())),
+            (GeoKeyTag::GeogSemiMajorAxis, TagValue::Double(6371000.0)),
+            (GeoKeyTag::GeogSemiMinorAxis, TagValue::Double(6371000.0)),
+        ]);
+        let projjson = keys.crs().unwrap().to_string();
+        assert!(projjson.contains(r#""type":"GeographicCRS","name":"sphere""#));
+        assert!(projjson.contains(r#""semi_major_axis":6371000,"semi_minor_axis":6371000"#));
+
+        // Unknown coordinate transformations are reported
+        let keys = geo_keys(&[
+            (GeoKeyTag::ModelType, TagValue::Short(1)),
+            (GeoKeyTag::GeographicType, TagValue::Short(4326)),
+            (GeoKeyTag::ProjectedType, TagValue::Short(USER_DEFINED)),
+            (GeoKeyTag::ProjCoordTrans, TagValue::Short(99)),
+        ]);
+        assert!(keys.crs().is_err());
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::{AsyncTiffResult, TiffError};
+use crate::extension::{TiffExtension, TiffExtensionFactory};
+use crate::geo::GeoKeyDirectory;
+use crate::ifd::parse_geo_key_directory;
+use crate::metadata::ParseOptions;
+use crate::tag_value::TagValue;
+use crate::tags::Tag;
+
+/// The GeoTIFF tags of an IFD, parsed by the [`GeoTiffExtension`].
+///
+/// These are also available from the geospatial accessors of
+/// [`ImageFileDirectory`][crate::ImageFileDirectory].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeoTiff {
+    /// The GeoKeyDirectory, resolved against the GeoAsciiParams and GeoDoubleParams tags.
+    pub geo_key_directory: Option<GeoKeyDirectory>,
+    /// The ModelPixelScale tag.
+    pub model_pixel_scale: Option<Vec<f64>>,
+    /// The ModelTiepoint tag.
+    pub model_tiepoint: Option<Vec<f64>>,
+    /// The ModelTransformation tag.
+    pub model_transformation: Option<Vec<f64>>,
+}
+
+/// The extension parsing GeoTIFF tags into a [`GeoTiff`].
+///
+/// This is registered in the default [`ExtensionRegistry`][crate::extension::ExtensionRegistry].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GeoTiffExtension;
+
+impl GeoTiffExtension {
+    /// The name of this extension.
+    pub const NAME: &str = "geotiff";
+}
+
+const GEOTIFF_TAGS: [Tag; 6] = [
+    Tag::GeoKeyDirectory,
+    Tag::GeoDoubleParams,
+    Tag::GeoAsciiParams,
+    Tag::ModelPixelScale,
+    Tag::ModelTiepoint,
+    Tag::ModelTransformation,
+];
+
+impl TiffExtensionFactory for GeoTiffExtension {
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn tags(&self) -> &[Tag] {
+        &GEOTIFF_TAGS
+    }
+
+    fn parse_tags(
+        &self,
+        tags: HashMap<Tag, TagValue>,
+        options: &ParseOptions,
+        warnings: &mut Vec<String>,
+    ) -> AsyncTiffResult<Arc<dyn TiffExtension>> {
+        let mut geo_key_directory_data = None;
+        let mut geo_ascii_params: Option<String> = None;
+        let mut geo_double_params: Option<Vec<f64>> = None;
+        let mut geotiff = GeoTiff::default();
+
+        // http://geotiff.maptools.org/spec/geotiff2.4.html
+        let mut parse_tag = |tag: Tag, value: TagValue| {
+            match tag {
+                Tag::GeoKeyDirectory => geo_key_directory_data = Some(value.into_u16_vec()?),
+                Tag::ModelPixelScale => geotiff.model_pixel_scale = Some(value.into_f64_vec()?),
+                Tag::ModelTiepoint => geotiff.model_tiepoint = Some(value.into_f64_vec()?),
+                Tag::ModelTransformation => {
+                    geotiff.model_transformation = Some(value.into_f64_vec()?)
+                }
+                Tag::GeoAsciiParams => geo_ascii_params = Some(value.into_string()?),
+                Tag::GeoDoubleParams => geo_double_params = Some(value.into_f64_vec()?),
+                _ => unreachable!("{tag:?} is not a GeoTIFF tag"),
+            };
+            Ok::<_, TiffError>(())
+        };
+        for (tag, value) in tags {
+            if let Err(err) = parse_tag(tag, value) {
+                if options.strict() {
+                    return Err(err.into());
+                }
+                warnings.push(format!("Skipping tag {tag:?}: {err}"));
+            }
+        }
+
+        // The GeoKeyDirectory refers to the values of GeoAsciiParams and GeoDoubleParams, so it
+        // is parsed after all other tags.
+        if let Some(data) = geo_key_directory_data {
+            match parse_geo_key_directory(
+                &data,
+                geo_ascii_params.as_deref(),
+                geo_double_params.as_deref(),
+            ) {
+                Ok(geo_key_directory) => geotiff.geo_key_directory = Some(geo_key_directory),
+                Err(err) if !options.strict() => {
+                    warnings.push(format!("Skipping GeoKeyDirectory: {err}"));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(Arc::new(geotiff))
+    }
+}
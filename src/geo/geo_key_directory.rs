@@ -5,8 +5,10 @@ use std::collections::HashMap;
 
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 
-use crate::error::{TiffError, TiffResult};
+use crate::error::{AsyncTiffResult, TiffError, TiffResult};
+use crate::geo::Crs;
 use crate::tag_value::TagValue;
+use crate::tags::Tag;
 
 /// Geospatial TIFF tag variants
 #[derive(Clone, Copy, Debug, PartialEq, TryFromPrimitive, IntoPrimitive, Eq, Hash)]
@@ -131,11 +133,20 @@ pub struct GeoKeyDirectory {
     pub vertical_citation: Option<String>,
     pub vertical_datum: Option<u16>,
     pub vertical_units: Option<u16>,
+
+    /// Geo keys that aren't part of the GeoTIFF spec, by key id.
+    ///
+    /// Some GeoTIFFs include keys that were proposed but never standardized. They are kept
+    /// here rather than failing the whole directory.
+    pub other_geo_keys: HashMap<u16, TagValue>,
 }
 
 impl GeoKeyDirectory {
     /// Construct a new [`GeoKeyDirectory`] from tag values.
-    pub(crate) fn from_tags(mut tag_data: HashMap<GeoKeyTag, TagValue>) -> TiffResult<Self> {
+    pub(crate) fn from_tags(
+        mut tag_data: HashMap<GeoKeyTag, TagValue>,
+        other_geo_keys: HashMap<u16, TagValue>,
+    ) -> TiffResult<Self> {
         let mut model_type = None;
         let mut raster_type = None;
         let mut citation = None;
@@ -295,9 +306,84 @@ impl GeoKeyDirectory {
             vertical_citation,
             vertical_datum,
             vertical_units,
+
+            other_geo_keys,
         })
     }
 
+    /// Encode this directory as the values of the `GeoKeyDirectory`, `GeoDoubleParams` and
+    /// `GeoAsciiParams` tags. The double and ASCII params are empty if no key needs them.
+    pub(crate) fn to_tags(&self) -> (Vec<u16>, Vec<f64>, String) {
+        let mut keys = GeoKeyWriter::default();
+        keys.short(GeoKeyTag::ModelType, self.model_type);
+        keys.short(GeoKeyTag::RasterType, self.raster_type);
+        keys.ascii(GeoKeyTag::Citation, self.citation.as_deref());
+        keys.short(GeoKeyTag::GeographicType, self.geographic_type);
+        keys.ascii(GeoKeyTag::GeogCitation, self.geog_citation.as_deref());
+        keys.short(GeoKeyTag::GeogGeodeticDatum, self.geog_geodetic_datum);
+        keys.short(GeoKeyTag::GeogPrimeMeridian, self.geog_prime_meridian);
+        keys.short(GeoKeyTag::GeogLinearUnits, self.geog_linear_units);
+        keys.double(GeoKeyTag::GeogLinearUnitSize, self.geog_linear_unit_size);
+        keys.short(GeoKeyTag::GeogAngularUnits, self.geog_angular_units);
+        keys.double(GeoKeyTag::GeogAngularUnitSize, self.geog_angular_unit_size);
+        keys.short(GeoKeyTag::GeogEllipsoid, self.geog_ellipsoid);
+        keys.double(GeoKeyTag::GeogSemiMajorAxis, self.geog_semi_major_axis);
+        keys.double(GeoKeyTag::GeogSemiMinorAxis, self.geog_semi_minor_axis);
+        keys.double(GeoKeyTag::GeogInvFlattening, self.geog_inv_flattening);
+        keys.short(GeoKeyTag::GeogAzimuthUnits, self.geog_azimuth_units);
+        keys.double(
+            GeoKeyTag::GeogPrimeMeridianLong,
+            self.geog_prime_meridian_long,
+        );
+        keys.short(GeoKeyTag::ProjectedType, self.projected_type);
+        keys.ascii(GeoKeyTag::ProjCitation, self.proj_citation.as_deref());
+        keys.short(GeoKeyTag::Projection, self.projection);
+        keys.short(GeoKeyTag::ProjCoordTrans, self.proj_coord_trans);
+        keys.short(GeoKeyTag::ProjLinearUnits, self.proj_linear_units);
+        keys.double(GeoKeyTag::ProjLinearUnitSize, self.proj_linear_unit_size);
+        keys.double(GeoKeyTag::ProjStdParallel1, self.proj_std_parallel1);
+        keys.double(GeoKeyTag::ProjStdParallel2, self.proj_std_parallel2);
+        keys.double(GeoKeyTag::ProjNatOriginLong, self.proj_nat_origin_long);
+        keys.double(GeoKeyTag::ProjNatOriginLat, self.proj_nat_origin_lat);
+        keys.double(GeoKeyTag::ProjFalseEasting, self.proj_false_easting);
+        keys.double(GeoKeyTag::ProjFalseNorthing, self.proj_false_northing);
+        keys.double(GeoKeyTag::ProjFalseOriginLong, self.proj_false_origin_long);
+        keys.double(GeoKeyTag::ProjFalseOriginLat, self.proj_false_origin_lat);
+        keys.double(
+            GeoKeyTag::ProjFalseOriginEasting,
+            self.proj_false_origin_easting,
+        );
+        keys.double(
+            GeoKeyTag::ProjFalseOriginNorthing,
+            self.proj_false_origin_northing,
+        );
+        keys.double(GeoKeyTag::ProjCenterLong, self.proj_center_long);
+        keys.double(GeoKeyTag::ProjCenterLat, self.proj_center_lat);
+        keys.double(GeoKeyTag::ProjCenterEasting, self.proj_center_easting);
+        keys.double(GeoKeyTag::ProjCenterNorthing, self.proj_center_northing);
+        keys.double(
+            GeoKeyTag::ProjScaleAtNatOrigin,
+            self.proj_scale_at_nat_origin,
+        );
+        keys.double(GeoKeyTag::ProjScaleAtCenter, self.proj_scale_at_center);
+        keys.double(GeoKeyTag::ProjAzimuthAngle, self.proj_azimuth_angle);
+        keys.double(
+            GeoKeyTag::ProjStraightVertPoleLong,
+            self.proj_straight_vert_pole_long,
+        );
+        keys.short(GeoKeyTag::Vertical, self.vertical);
+        keys.ascii(
+            GeoKeyTag::VerticalCitation,
+            self.vertical_citation.as_deref(),
+        );
+        keys.short(GeoKeyTag::VerticalDatum, self.vertical_datum);
+        keys.short(GeoKeyTag::VerticalUnits, self.vertical_units);
+        for (&key, value) in &self.other_geo_keys {
+            keys.other(key, value);
+        }
+        keys.finish()
+    }
+
     /// Return the EPSG code representing the crs of the image
     ///
     /// This will return either [`GeoKeyDirectory::projected_type`] or
@@ -309,4 +395,93 @@ impl GeoKeyDirectory {
             self.geographic_type
         }
     }
+
+    /// The coordinate reference system described by these keys.
+    ///
+    /// See [`Crs::from_geo_keys`].
+    pub fn crs(&self) -> AsyncTiffResult<Crs> {
+        Crs::from_geo_keys(self)
+    }
+}
+
+/// Accumulates geo keys, in ascending key order, into the on-disk representation.
+#[derive(Default)]
+struct GeoKeyWriter {
+    entries: Vec<[u16; 4]>,
+    doubles: Vec<f64>,
+    ascii: String,
+}
+
+impl GeoKeyWriter {
+    fn short(&mut self, tag: GeoKeyTag, value: Option<u16>) {
+        if let Some(value) = value {
+            self.entries.push([tag.into(), 0, 1, value]);
+        }
+    }
+
+    fn double(&mut self, tag: GeoKeyTag, value: Option<f64>) {
+        if let Some(value) = value {
+            let offset = self.doubles.len() as u16;
+            self.doubles.push(value);
+            self.entries
+                .push([tag.into(), Tag::GeoDoubleParams.to_u16(), 1, offset]);
+        }
+    }
+
+    fn ascii(&mut self, tag: GeoKeyTag, value: Option<&str>) {
+        if let Some(value) = value {
+            let offset = self.ascii.len() as u16;
+            // Each string is terminated by a `|`, which is included in the count.
+            self.ascii.push_str(value);
+            self.ascii.push('|');
+            self.entries.push([
+                tag.into(),
+                Tag::GeoAsciiParams.to_u16(),
+                value.len() as u16 + 1,
+                offset,
+            ]);
+        }
+    }
+
+    /// Write a key that isn't part of the spec, skipping values that can't be stored as a
+    /// short, doubles or a string.
+    fn other(&mut self, key: u16, value: &TagValue) {
+        match value {
+            TagValue::Short(value) => self.entries.push([key, 0, 1, *value]),
+            TagValue::Ascii(value) => {
+                let offset = self.ascii.len() as u16;
+                self.ascii.push_str(value);
+                self.ascii.push('|');
+                self.entries.push([
+                    key,
+                    Tag::GeoAsciiParams.to_u16(),
+                    value.len() as u16 + 1,
+                    offset,
+                ]);
+            }
+            TagValue::Double(_) | TagValue::List(_) => {
+                let Ok(values) = value.clone().into_f64_vec() else {
+                    return;
+                };
+                let offset = self.doubles.len() as u16;
+                self.entries.push([
+                    key,
+                    Tag::GeoDoubleParams.to_u16(),
+                    values.len() as u16,
+                    offset,
+                ]);
+                self.doubles.extend(values);
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> (Vec<u16>, Vec<f64>, String) {
+        // Keys must be sorted by id, which only unknown keys can break
+        self.entries.sort_by_key(|entry| entry[0]);
+        // Header: KeyDirectoryVersion, KeyRevision, MinorRevision, NumberOfKeys
+        let mut directory = vec![1, 1, 0, self.entries.len() as u16];
+        directory.extend(self.entries.into_iter().flatten());
+        (directory, self.doubles, self.ascii)
+    }
 }
@@ -1,6 +1,12 @@
 //! Support for GeoTIFF files.
 
+mod affine;
+pub mod crs;
+mod extension;
 mod geo_key_directory;
 
+pub use affine::AffineTransform;
+pub use crs::Crs;
+pub use extension::{GeoTiff, GeoTiffExtension};
 pub use geo_key_directory::GeoKeyDirectory;
 pub(crate) use geo_key_directory::GeoKeyTag;
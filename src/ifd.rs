@@ -3,17 +3,28 @@ use std::ops::Range;
 use std::sync::Arc;
 
 use bytes::Bytes;
+use futures::future::join_all;
+use futures::{Stream, StreamExt};
 use num_enum::TryFromPrimitive;
 
+use crate::decoder::DecoderRegistry;
 use crate::error::{AsyncTiffError, AsyncTiffResult, TiffError, TiffFormatError};
-use crate::geo::{GeoKeyDirectory, GeoKeyTag};
+use crate::exif::{Exif, Gps};
+use crate::extension::{Extensions, TiffExtension};
+use crate::fetch::{fetch_ranges, fetch_ranges_with_errors};
+use crate::geo::{AffineTransform, GeoKeyDirectory, GeoKeyTag, GeoTiff, GeoTiffExtension};
+use crate::jpeg_tables::JpegTables;
+use crate::metadata::ParseOptions;
 use crate::reader::{AsyncFileReader, Endianness};
 use crate::tag_value::TagValue;
 use crate::tags::{
-    Compression, ExtraSamples, PhotometricInterpretation, PlanarConfiguration, Predictor,
-    ResolutionUnit, SampleFormat, Tag,
+    Compression, ExtraSamples, FillOrder, Orientation, PhotometricInterpretation,
+    PlanarConfiguration, Predictor, ResolutionUnit, SampleFormat, Tag, Threshholding,
+};
+use crate::ycbcr::YCbCr;
+use crate::{
+    Array, ChunkRecord, CompressionStats, DataType, FetchOptions, Nodata, RasterBand, Tile, Window,
 };
-use crate::{DataType, Tile};
 
 const DOCUMENT_NAME: u16 = 269;
 
@@ -38,13 +49,17 @@ pub struct ImageFileDirectory {
 
     pub(crate) photometric_interpretation: PhotometricInterpretation,
 
+    pub(crate) threshholding: Option<Threshholding>,
+
+    pub(crate) fill_order: Option<FillOrder>,
+
     pub(crate) document_name: Option<String>,
 
     pub(crate) image_description: Option<String>,
 
     pub(crate) strip_offsets: Option<Vec<u64>>,
 
-    pub(crate) orientation: Option<u16>,
+    pub(crate) orientation: Option<Orientation>,
 
     /// The number of components per pixel.
     ///
@@ -133,8 +148,14 @@ pub struct ImageFileDirectory {
 
     pub(crate) jpeg_tables: Option<Bytes>,
 
+    pub(crate) ycbcr_coefficients: Option<Vec<f64>>,
+    pub(crate) ycbcr_subsampling: Option<[u16; 2]>,
+    pub(crate) reference_black_white: Option<Vec<f64>>,
+
     pub(crate) copyright: Option<String>,
 
+    pub(crate) xmp: Option<String>,
+
     // Geospatial tags
     pub(crate) geo_key_directory: Option<GeoKeyDirectory>,
     pub(crate) model_pixel_scale: Option<Vec<f64>>,
@@ -148,6 +169,19 @@ pub struct ImageFileDirectory {
 
     // Other
     pub(crate) lerc_parameters: Option<Vec<u32>>,
+
+    /// Tags parsed by registered extensions, by extension name
+    pub(crate) extensions: Extensions,
+
+    // Private IFDs, read by the metadata reader
+    pub(crate) exif: Option<Exif>,
+    pub(crate) gps: Option<Gps>,
+
+    /// Problems that were skipped while parsing this IFD in lenient mode.
+    pub(crate) parse_warnings: Vec<String>,
+
+    /// Entries whose field type is unknown, so their value couldn't be parsed.
+    pub(crate) raw_tags: Vec<RawTag>,
 }
 
 impl ImageFileDirectory {
@@ -156,12 +190,29 @@ impl ImageFileDirectory {
         tag_data: HashMap<Tag, TagValue>,
         endianness: Endianness,
     ) -> AsyncTiffResult<Self> {
+        Self::from_tags_with_options(tag_data, endianness, &ParseOptions::default())
+    }
+
+    /// Create a new ImageFileDirectory from tag data, with the strictness set by `options`.
+    ///
+    /// In lenient mode, tags with invalid values, an invalid GeoKeyDirectory, and missing
+    /// `SamplesPerPixel`, `BitsPerSample` and `PhotometricInterpretation` tags are recorded in
+    /// [`parse_warnings`][Self::parse_warnings] instead of failing. A missing image size is always
+    /// an error.
+    pub fn from_tags_with_options(
+        tag_data: HashMap<Tag, TagValue>,
+        endianness: Endianness,
+        options: &ParseOptions,
+    ) -> AsyncTiffResult<Self> {
+        let mut parse_warnings = vec![];
         let mut new_subfile_type = None;
         let mut image_width = None;
         let mut image_height = None;
         let mut bits_per_sample = None;
         let mut compression = None;
         let mut photometric_interpretation = None;
+        let mut threshholding = None;
+        let mut fill_order = None;
         let mut document_name = None;
         let mut image_description = None;
         let mut strip_offsets = None;
@@ -188,20 +239,18 @@ impl ImageFileDirectory {
         let mut extra_samples = None;
         let mut sample_format = None;
         let mut jpeg_tables = None;
+        let mut ycbcr_coefficients = None;
+        let mut ycbcr_subsampling = None;
+        let mut reference_black_white = None;
         let mut copyright = None;
-        let mut geo_key_directory_data = None;
-        let mut model_pixel_scale = None;
-        let mut model_tiepoint = None;
-        let mut model_transformation = None;
-        let mut geo_ascii_params: Option<String> = None;
-        let mut geo_double_params: Option<Vec<f64>> = None;
+        let mut xmp = None;
         let mut gdal_nodata = None;
         let mut gdal_metadata = None;
         let mut lerc_parameters = None;
 
         let mut other_tags = HashMap::new();
 
-        tag_data.into_iter().try_for_each(|(tag, value)| {
+        let mut parse_tag = |tag: Tag, value: TagValue| {
             match tag {
                 Tag::NewSubfileType => new_subfile_type = Some(value.into_u32()?),
                 Tag::ImageWidth => image_width = Some(value.into_u32()?),
@@ -216,7 +265,13 @@ impl ImageFileDirectory {
                 }
                 Tag::ImageDescription => image_description = Some(value.into_string()?),
                 Tag::StripOffsets => strip_offsets = Some(value.into_u64_vec()?),
-                Tag::Orientation => orientation = Some(value.into_u16()?),
+                Tag::Threshholding => threshholding = Threshholding::from_u16(value.into_u16()?),
+                Tag::FillOrder => {
+                    fill_order = Some(FillOrder::from_u16_exhaustive(value.into_u16()?))
+                }
+                Tag::Orientation => {
+                    orientation = Some(Orientation::from_u16_exhaustive(value.into_u16()?))
+                }
                 Tag::SamplesPerPixel => samples_per_pixel = Some(value.into_u16()?),
                 Tag::RowsPerStrip => rows_per_strip = Some(value.into_u32()?),
                 Tag::StripByteCounts => strip_byte_counts = Some(value.into_u64_vec()?),
@@ -224,11 +279,19 @@ impl ImageFileDirectory {
                 Tag::MaxSampleValue => max_sample_value = Some(value.into_u16_vec()?),
                 Tag::XResolution => match value {
                     TagValue::Rational(n, d) => x_resolution = Some(n as f64 / d as f64),
-                    _ => unreachable!("Expected rational type for XResolution."),
+                    _ => {
+                        return Err(TiffError::FormatError(
+                            TiffFormatError::InvalidTagValueType(tag),
+                        ))
+                    }
                 },
                 Tag::YResolution => match value {
                     TagValue::Rational(n, d) => y_resolution = Some(n as f64 / d as f64),
-                    _ => unreachable!("Expected rational type for YResolution."),
+                    _ => {
+                        return Err(TiffError::FormatError(
+                            TiffFormatError::InvalidTagValueType(tag),
+                        ))
+                    }
                 },
                 Tag::PlanarConfiguration => {
                     planar_configuration = PlanarConfiguration::from_u16(value.into_u16()?)
@@ -268,16 +331,25 @@ impl ImageFileDirectory {
                     );
                 }
                 Tag::JPEGTables => jpeg_tables = Some(value.into_u8_vec()?.into()),
+                Tag::YCbCrCoefficients => ycbcr_coefficients = Some(value.into_f64_vec()?),
+                Tag::YCbCrSubSampling => {
+                    let values = value.into_u16_vec()?;
+                    if let [horizontal, vertical] = values[..] {
+                        ycbcr_subsampling = Some([horizontal, vertical]);
+                    }
+                }
+                Tag::ReferenceBlackWhite => reference_black_white = Some(value.into_f64_vec()?),
                 Tag::Copyright => copyright = Some(value.into_string()?),
+                // Should be BYTE or UNDEFINED, but some writers use ASCII
+                Tag::Xmp => {
+                    xmp = Some(match value {
+                        TagValue::Ascii(packet) => packet,
+                        value => String::from_utf8_lossy(&value.into_u8_vec()?)
+                            .trim_end_matches('\0')
+                            .to_string(),
+                    })
+                }
 
-                // Geospatial tags
-                // http://geotiff.maptools.org/spec/geotiff2.4.html
-                Tag::GeoKeyDirectory => geo_key_directory_data = Some(value.into_u16_vec()?),
-                Tag::ModelPixelScale => model_pixel_scale = Some(value.into_f64_vec()?),
-                Tag::ModelTiepoint => model_tiepoint = Some(value.into_f64_vec()?),
-                Tag::ModelTransformation => model_transformation = Some(value.into_f64_vec()?),
-                Tag::GeoAsciiParams => geo_ascii_params = Some(value.into_string()?),
-                Tag::GeoDoubleParams => geo_double_params = Some(value.into_f64_vec()?),
                 Tag::GdalNodata => gdal_nodata = Some(value.into_string()?),
                 Tag::GdalMetadata => gdal_metadata = Some(value.into_string()?),
                 Tag::LercParameters => lerc_parameters = Some(value.into_u32_vec()?),
@@ -288,91 +360,75 @@ impl ImageFileDirectory {
                 }
             };
             Ok::<_, TiffError>(())
-        })?;
-
-        let mut geo_key_directory = None;
-
-        // We need to actually parse the GeoKeyDirectory after parsing all other tags because the
-        // GeoKeyDirectory relies on `GeoAsciiParamsTag` having been parsed.
-        if let Some(data) = geo_key_directory_data {
-            let mut chunks = data.chunks(4);
-
-            let header = chunks
-                .next()
-                .expect("If the geo key directory exists, a header should exist.");
-            let key_directory_version = header[0];
-            assert_eq!(key_directory_version, 1);
-
-            let key_revision = header[1];
-            assert_eq!(key_revision, 1);
-
-            let _key_minor_revision = header[2];
-            let number_of_keys = header[3];
-
-            let mut tags = HashMap::with_capacity(number_of_keys as usize);
-            for _ in 0..number_of_keys {
-                let chunk = chunks
-                    .next()
-                    .expect("There should be a chunk for each key.");
-
-                let key_id = chunk[0];
-                let tag_name = if let Ok(tag_name) = GeoKeyTag::try_from_primitive(key_id) {
-                    tag_name
-                } else {
-                    // Skip unknown GeoKeyTag ids. Some GeoTIFFs include keys that were proposed
-                    // but not included in the GeoTIFF spec. See
-                    // https://github.com/developmentseed/async-tiff/pull/131 and
-                    // https://github.com/virtual-zarr/virtual-tiff/issues/52
-                    continue;
-                };
-
-                let tag_location = chunk[1];
-                let count = chunk[2];
-                let value_offset = chunk[3];
-
-                if tag_location == 0 {
-                    tags.insert(tag_name, TagValue::Short(value_offset));
-                } else if Tag::from_u16_exhaustive(tag_location) == Tag::GeoAsciiParams {
-                    // If the tag_location points to the value of Tag::GeoAsciiParams, then we
-                    // need to extract a subslice from GeoAsciiParams
-
-                    let geo_ascii_params = geo_ascii_params
-                        .as_ref()
-                        .expect("GeoAsciiParamsTag exists but geo_ascii_params does not.");
-                    let value_offset = value_offset as usize;
-                    let mut s = &geo_ascii_params[value_offset..value_offset + count as usize];
-
-                    // It seems that this string subslice might always include the final |
-                    // character?
-                    if s.ends_with('|') {
-                        s = &s[0..s.len() - 1];
-                    }
-
-                    tags.insert(tag_name, TagValue::Ascii(s.to_string()));
-                } else if Tag::from_u16_exhaustive(tag_location) == Tag::GeoDoubleParams {
-                    // If the tag_location points to the value of Tag::GeoDoubleParams, then we
-                    // need to extract a subslice from GeoDoubleParams
-
-                    let geo_double_params = geo_double_params
-                        .as_ref()
-                        .expect("GeoDoubleParamsTag exists but geo_double_params does not.");
-                    let value_offset = value_offset as usize;
-                    let value = if count == 1 {
-                        TagValue::Double(geo_double_params[value_offset])
-                    } else {
-                        let x = geo_double_params[value_offset..value_offset + count as usize]
-                            .iter()
-                            .map(|val| TagValue::Double(*val))
-                            .collect();
-                        TagValue::List(x)
-                    };
-                    tags.insert(tag_name, value);
+        };
+        // Tags claimed by an extension are passed to it rather than parsed here
+        let registry = options.extensions();
+        let mut extension_tags = vec![HashMap::new(); registry.factories().len()];
+        for (tag, value) in tag_data {
+            if let Some(index) = registry.claim(tag) {
+                extension_tags[index].insert(tag, value);
+            } else if let Err(err) = parse_tag(tag, value) {
+                if options.strict() {
+                    return Err(err.into());
                 }
+                parse_warnings.push(format!("Skipping tag {tag:?}: {err}"));
             }
-            geo_key_directory = Some(GeoKeyDirectory::from_tags(tags)?);
         }
 
-        let samples_per_pixel = samples_per_pixel.expect("samples_per_pixel not found");
+        let mut extensions = HashMap::new();
+        for (factory, tags) in registry.factories().iter().zip(extension_tags) {
+            if tags.is_empty() {
+                continue;
+            }
+            match factory.parse_tags(tags, options, &mut parse_warnings) {
+                Ok(extension) => {
+                    extensions.insert(factory.name().to_string(), extension);
+                }
+                Err(err) if !options.strict() => {
+                    parse_warnings.push(format!("Skipping {} tags: {err}", factory.name()));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        let GeoTiff {
+            geo_key_directory,
+            model_pixel_scale,
+            model_tiepoint,
+            model_transformation,
+        } = extensions
+            .get(GeoTiffExtension::NAME)
+            .and_then(|extension| extension.as_any().downcast_ref::<GeoTiff>())
+            .cloned()
+            .unwrap_or_default();
+
+        let samples_per_pixel = required_or_default(
+            Tag::SamplesPerPixel,
+            samples_per_pixel,
+            1,
+            options,
+            &mut parse_warnings,
+        )?;
+        let bits_per_sample = required_or_default(
+            Tag::BitsPerSample,
+            bits_per_sample,
+            vec![1; samples_per_pixel as usize],
+            options,
+            &mut parse_warnings,
+        )?;
+        let photometric_interpretation = required_or_default(
+            Tag::PhotometricInterpretation,
+            photometric_interpretation,
+            if samples_per_pixel >= 3 {
+                PhotometricInterpretation::RGB
+            } else {
+                PhotometricInterpretation::BlackIsZero
+            },
+            options,
+            &mut parse_warnings,
+        )?;
+        let missing = |tag| TiffError::FormatError(TiffFormatError::RequiredTagNotFound(tag));
+        let image_width = image_width.ok_or(missing(Tag::ImageWidth))?;
+        let image_height = image_height.ok_or(missing(Tag::ImageLength))?;
         let planar_configuration = if let Some(planar_configuration) = planar_configuration {
             planar_configuration
         } else if samples_per_pixel == 1 {
@@ -382,17 +438,18 @@ impl ImageFileDirectory {
         } else {
             PlanarConfiguration::Chunky
         };
-        Ok(Self {
+        let ifd = Self {
             endianness,
             new_subfile_type,
-            image_width: image_width.expect("image_width not found"),
-            image_height: image_height.expect("image_height not found"),
-            bits_per_sample: bits_per_sample.expect("bits per sample not found"),
+            image_width,
+            image_height,
+            bits_per_sample,
             // Defaults to no compression
             // https://web.archive.org/web/20240329145331/https://www.awaresystems.be/imaging/tiff/tifftags/compression.html
             compression: compression.unwrap_or(Compression::None),
-            photometric_interpretation: photometric_interpretation
-                .expect("photometric interpretation not found"),
+            photometric_interpretation,
+            threshholding,
+            fill_order,
             document_name,
             image_description,
             strip_offsets,
@@ -422,7 +479,11 @@ impl ImageFileDirectory {
             sample_format: sample_format
                 .unwrap_or(vec![SampleFormat::Uint; samples_per_pixel as _]),
             copyright,
+            xmp,
             jpeg_tables,
+            ycbcr_coefficients,
+            ycbcr_subsampling,
+            reference_black_white,
             geo_key_directory,
             model_pixel_scale,
             model_tiepoint,
@@ -430,7 +491,212 @@ impl ImageFileDirectory {
             gdal_nodata,
             gdal_metadata,
             lerc_parameters,
+            exif: None,
+            gps: None,
+            extensions: Extensions(extensions),
             other_tags,
+            parse_warnings,
+            raw_tags: vec![],
+        };
+        if let Some(count) = ifd.chunk_count() {
+            options.limits().check_chunk_count(count)?;
+        }
+        Ok(ifd)
+    }
+
+    /// The tags describing this IFD, which [`from_tags`][Self::from_tags] parses back into an
+    /// equal IFD.
+    ///
+    /// Offsets are encoded as 64-bit values, so the tags can only be written to BigTIFF files.
+    /// Tags claimed by extensions other than GeoTIFF, entries with unknown field types and the
+    /// pointers to EXIF and GPS IFDs are not included.
+    pub(crate) fn to_tags(&self) -> Vec<(Tag, TagValue)> {
+        let shorts =
+            |values: &[u16]| TagValue::List(values.iter().map(|v| TagValue::Short(*v)).collect());
+        let longs = |values: &[u32]| {
+            TagValue::List(values.iter().map(|v| TagValue::Unsigned(*v)).collect())
+        };
+        let offsets = |values: &[u64]| {
+            TagValue::List(values.iter().map(|v| TagValue::UnsignedBig(*v)).collect())
+        };
+        let doubles =
+            |values: &[f64]| TagValue::List(values.iter().map(|v| TagValue::Double(*v)).collect());
+        let ascii = |value: &str| TagValue::Ascii(value.to_string());
+
+        let mut tags = vec![
+            (Tag::ImageWidth, TagValue::Unsigned(self.image_width)),
+            (Tag::ImageLength, TagValue::Unsigned(self.image_height)),
+            (Tag::BitsPerSample, shorts(&self.bits_per_sample)),
+            (Tag::Compression, TagValue::Short(self.compression.to_u16())),
+            (
+                Tag::PhotometricInterpretation,
+                TagValue::Short(self.photometric_interpretation.to_u16()),
+            ),
+            (
+                Tag::SamplesPerPixel,
+                TagValue::Short(self.samples_per_pixel),
+            ),
+            (
+                Tag::PlanarConfiguration,
+                TagValue::Short(self.planar_configuration.to_u16()),
+            ),
+            (
+                Tag::SampleFormat,
+                shorts(
+                    &self
+                        .sample_format
+                        .iter()
+                        .map(|f| f.to_u16())
+                        .collect::<Vec<_>>(),
+                ),
+            ),
+        ];
+        let mut push = |tag: Tag, value: Option<TagValue>| {
+            if let Some(value) = value {
+                tags.push((tag, value));
+            }
+        };
+        push(
+            Tag::NewSubfileType,
+            self.new_subfile_type.map(TagValue::Unsigned),
+        );
+        push(
+            Tag::Threshholding,
+            self.threshholding.map(|v| TagValue::Short(v.to_u16())),
+        );
+        push(
+            Tag::FillOrder,
+            self.fill_order.map(|v| TagValue::Short(v.to_u16())),
+        );
+        push(
+            Tag::Unknown(DOCUMENT_NAME),
+            self.document_name.as_deref().map(ascii),
+        );
+        push(
+            Tag::ImageDescription,
+            self.image_description.as_deref().map(ascii),
+        );
+        push(
+            Tag::StripOffsets,
+            self.strip_offsets.as_deref().map(offsets),
+        );
+        push(
+            Tag::Orientation,
+            self.orientation.map(|v| TagValue::Short(v.to_u16())),
+        );
+        push(
+            Tag::RowsPerStrip,
+            self.rows_per_strip.map(TagValue::Unsigned),
+        );
+        push(
+            Tag::StripByteCounts,
+            self.strip_byte_counts.as_deref().map(offsets),
+        );
+        push(
+            Tag::MinSampleValue,
+            self.min_sample_value.as_deref().map(shorts),
+        );
+        push(
+            Tag::MaxSampleValue,
+            self.max_sample_value.as_deref().map(shorts),
+        );
+        push(Tag::XResolution, self.x_resolution.map(to_rational));
+        push(Tag::YResolution, self.y_resolution.map(to_rational));
+        push(
+            Tag::ResolutionUnit,
+            self.resolution_unit.map(|v| TagValue::Short(v.to_u16())),
+        );
+        push(Tag::Software, self.software.as_deref().map(ascii));
+        push(Tag::DateTime, self.date_time.as_deref().map(ascii));
+        push(Tag::Artist, self.artist.as_deref().map(ascii));
+        push(Tag::HostComputer, self.host_computer.as_deref().map(ascii));
+        push(
+            Tag::Predictor,
+            self.predictor.map(|v| TagValue::Short(v.to_u16())),
+        );
+        push(Tag::ColorMap, self.color_map.as_deref().map(shorts));
+        push(Tag::TileWidth, self.tile_width.map(TagValue::Unsigned));
+        push(Tag::TileLength, self.tile_height.map(TagValue::Unsigned));
+        push(Tag::TileOffsets, self.tile_offsets.as_deref().map(offsets));
+        push(
+            Tag::TileByteCounts,
+            self.tile_byte_counts.as_deref().map(offsets),
+        );
+        push(
+            Tag::ExtraSamples,
+            self.extra_samples
+                .as_ref()
+                .map(|values| shorts(&values.iter().map(|v| v.to_u16()).collect::<Vec<_>>())),
+        );
+        push(
+            Tag::JPEGTables,
+            self.jpeg_tables
+                .as_ref()
+                .map(|tables| TagValue::List(tables.iter().map(|b| TagValue::Byte(*b)).collect())),
+        );
+        push(
+            Tag::YCbCrCoefficients,
+            self.ycbcr_coefficients.as_deref().map(doubles),
+        );
+        push(
+            Tag::YCbCrSubSampling,
+            self.ycbcr_subsampling.map(|v| shorts(&v)),
+        );
+        push(
+            Tag::ReferenceBlackWhite,
+            self.reference_black_white.as_deref().map(doubles),
+        );
+        push(Tag::Copyright, self.copyright.as_deref().map(ascii));
+        push(Tag::Xmp, self.xmp.as_deref().map(ascii));
+        push(
+            Tag::ModelPixelScale,
+            self.model_pixel_scale.as_deref().map(doubles),
+        );
+        push(
+            Tag::ModelTiepoint,
+            self.model_tiepoint.as_deref().map(doubles),
+        );
+        push(
+            Tag::ModelTransformation,
+            self.model_transformation.as_deref().map(doubles),
+        );
+        if let Some(geo_key_directory) = &self.geo_key_directory {
+            let (directory, double_params, ascii_params) = geo_key_directory.to_tags();
+            push(Tag::GeoKeyDirectory, Some(shorts(&directory)));
+            push(
+                Tag::GeoDoubleParams,
+                (!double_params.is_empty()).then(|| doubles(&double_params)),
+            );
+            push(
+                Tag::GeoAsciiParams,
+                (!ascii_params.is_empty()).then(|| ascii(&ascii_params)),
+            );
+        }
+        push(Tag::GdalNodata, self.gdal_nodata.as_deref().map(ascii));
+        push(Tag::GdalMetadata, self.gdal_metadata.as_deref().map(ascii));
+        push(
+            Tag::LercParameters,
+            self.lerc_parameters.as_deref().map(longs),
+        );
+        for (tag, value) in &self.other_tags {
+            // The private IFDs they point to are not part of this IFD
+            if !matches!(tag, Tag::ExifIfd | Tag::GpsIfd) {
+                push(*tag, Some(value.clone()));
+            }
+        }
+        tags
+    }
+
+    /// The number of tiles or strips, counting every band of planar images.
+    fn chunk_count(&self) -> Option<u64> {
+        let per_band = match (self.tile_count(), self.strip_count()) {
+            (Some((x, y)), _) => x as u64 * y as u64,
+            (None, Some(strips)) => strips as u64,
+            (None, None) => return None,
+        };
+        Some(match self.planar_configuration {
+            PlanarConfiguration::Chunky => per_band,
+            PlanarConfiguration::Planar => per_band * self.samples_per_pixel as u64,
         })
     }
 
@@ -470,6 +736,19 @@ impl ImageFileDirectory {
         self.photometric_interpretation
     }
 
+    /// For black and white TIFF files that represent shades of gray, the technique used to
+    /// convert from gray to black and white pixels.
+    /// <https://web.archive.org/web/20240329145250/https://www.awaresystems.be/imaging/tiff/tifftags/threshholding.html>
+    pub fn threshholding(&self) -> Option<Threshholding> {
+        self.threshholding
+    }
+
+    /// The logical order of bits within a byte.
+    /// <https://web.archive.org/web/20240329145250/https://www.awaresystems.be/imaging/tiff/tifftags/fillorder.html>
+    pub fn fill_order(&self) -> Option<FillOrder> {
+        self.fill_order
+    }
+
     /// Document name.
     pub fn document_name(&self) -> Option<&str> {
         self.document_name.as_deref()
@@ -489,7 +768,7 @@ impl ImageFileDirectory {
 
     /// The orientation of the image with respect to the rows and columns.
     /// <https://web.archive.org/web/20240329145250/https://www.awaresystems.be/imaging/tiff/tifftags/orientation.html>
-    pub fn orientation(&self) -> Option<u16> {
+    pub fn orientation(&self) -> Option<Orientation> {
         self.orientation
     }
 
@@ -636,18 +915,62 @@ impl ImageFileDirectory {
         &self.sample_format
     }
 
+    /// The data type of the decoded pixels, derived from
+    /// [`sample_format`][Self::sample_format] and [`bits_per_sample`][Self::bits_per_sample].
+    ///
+    /// Unsigned integer samples with different bit depths, such as `[8, 8, 8, 1]` for RGB with a
+    /// 1-bit mask, are widened to the smallest type that fits the widest sample.
+    ///
+    /// Returns `None` if the combination is not supported, or if samples have different formats.
+    pub fn data_type(&self) -> Option<DataType> {
+        DataType::from_tags(&self.sample_format, &self.bits_per_sample)
+            .or_else(|| DataType::from_mixed_tags(&self.sample_format, &self.bits_per_sample))
+    }
+
     /// JPEG quantization and/or Huffman tables.
     /// <https://web.archive.org/web/20240329145250/https://www.awaresystems.be/imaging/tiff/tifftags/jpegtables.html>
     pub fn jpeg_tables(&self) -> Option<&[u8]> {
         self.jpeg_tables.as_deref()
     }
 
+    /// The quantization and Huffman tables in the [`jpeg_tables`][Self::jpeg_tables] tag.
+    ///
+    /// Returns `None` if the tag is not present.
+    pub fn parsed_jpeg_tables(&self) -> Option<AsyncTiffResult<JpegTables>> {
+        self.jpeg_tables.as_deref().map(JpegTables::parse)
+    }
+
+    /// The coefficients used to compute luminance from RGB, as `[LumaRed, LumaGreen, LumaBlue]`.
+    /// <https://web.archive.org/web/20240329145250/https://www.awaresystems.be/imaging/tiff/tifftags/ycbcrcoefficients.html>
+    pub fn ycbcr_coefficients(&self) -> Option<&[f64]> {
+        self.ycbcr_coefficients.as_deref()
+    }
+
+    /// The horizontal and vertical subsampling factors of the chroma components of YCbCr data.
+    /// <https://web.archive.org/web/20240329145250/https://www.awaresystems.be/imaging/tiff/tifftags/ycbcrsubsampling.html>
+    pub fn ycbcr_subsampling(&self) -> Option<[u16; 2]> {
+        self.ycbcr_subsampling
+    }
+
+    /// The headroom and footroom of each component, as pairs of reference black and white
+    /// values.
+    /// <https://web.archive.org/web/20240329145250/https://www.awaresystems.be/imaging/tiff/tifftags/referenceblackwhite.html>
+    pub fn reference_black_white(&self) -> Option<&[f64]> {
+        self.reference_black_white.as_deref()
+    }
+
     /// Copyright notice.
     /// <https://web.archive.org/web/20240329145250/https://www.awaresystems.be/imaging/tiff/tifftags/copyright.html>
     pub fn copyright(&self) -> Option<&str> {
         self.copyright.as_deref()
     }
 
+    /// The XMP metadata packet, decoded as UTF-8 with invalid sequences replaced.
+    /// <https://web.archive.org/web/20240329145250/https://www.awaresystems.be/imaging/tiff/tifftags/xmp.html>
+    pub fn xmp(&self) -> Option<&str> {
+        self.xmp.as_deref()
+    }
+
     /// Geospatial tags
     /// <https://web.archive.org/web/20240329145313/https://www.awaresystems.be/imaging/tiff/tifftags/geokeydirectorytag.html>
     pub fn geo_key_directory(&self) -> Option<&GeoKeyDirectory> {
@@ -672,12 +995,136 @@ impl ImageFileDirectory {
         self.model_transformation.as_deref()
     }
 
+    /// The affine transform from pixel to model coordinates, in GDAL order:
+    /// `[origin_x, pixel_width, row_rotation, origin_y, column_rotation, pixel_height]`.
+    ///
+    /// This is derived from the `ModelTransformation` tag if present, and otherwise from the
+    /// `ModelTiepoint` and `ModelPixelScale` tags, in which case `pixel_height` is negative for
+    /// north-up images. Returns `None` if neither is available, and an error if the
+    /// transformation matrix can't be expressed as a 2D affine transform.
+    pub fn geotransform(&self) -> Option<AsyncTiffResult<[f64; 6]>> {
+        if let Some(transformation) = self.model_transformation.as_deref() {
+            return Some(transformation_to_geotransform(transformation));
+        }
+        let tiepoint = self.model_tiepoint.as_deref()?;
+        let scale = self.model_pixel_scale.as_deref()?;
+        let [i, j, _, x, y, _] = *tiepoint.get(..6)? else {
+            return None;
+        };
+        let [scale_x, scale_y] = *scale.get(..2)? else {
+            return None;
+        };
+        Some(Ok([
+            x - i * scale_x,
+            scale_x,
+            0.0,
+            y + j * scale_y,
+            0.0,
+            -scale_y,
+        ]))
+    }
+
+    /// The [`AffineTransform`] from pixel to model coordinates.
+    ///
+    /// Returns `None` if there is no [`geotransform`][Self::geotransform].
+    pub fn affine_transform(&self) -> Option<AsyncTiffResult<AffineTransform>> {
+        Some(self.geotransform()?.map(AffineTransform::from_gdal))
+    }
+
+    /// The bounding box of the image in model coordinates, as `[min_x, min_y, max_x, max_y]`.
+    ///
+    /// Returns `None` if there is no [`geotransform`][Self::geotransform].
+    pub fn native_bounds(&self) -> Option<AsyncTiffResult<[f64; 4]>> {
+        let (width, height) = (self.image_width as f64, self.image_height as f64);
+        Some(
+            self.affine_transform()?
+                .map(|transform| transform.bounds(0.0, 0.0, width, height)),
+        )
+    }
+
+    /// The bounding box of the tile at column `x` and row `y` in model coordinates, as
+    /// `[min_x, min_y, max_x, max_y]`.
+    ///
+    /// Tiles on the right and bottom edges are clipped to the image. Returns `None` if this
+    /// isn't a tiled TIFF or there is no [`geotransform`][Self::geotransform].
+    pub fn bounds_for_tile(&self, x: usize, y: usize) -> Option<AsyncTiffResult<[f64; 4]>> {
+        let (tile_width, tile_height) = (self.tile_width?, self.tile_height?);
+        let (x_count, y_count) = self.tile_count()?;
+        let transform = match self.affine_transform()? {
+            Ok(transform) => transform,
+            Err(err) => return Some(Err(err)),
+        };
+        if x >= x_count || y >= y_count {
+            return Some(Err(AsyncTiffError::TileIndexError(x as u32, y as u32)));
+        }
+        let col_min = x as u32 * tile_width;
+        let row_min = y as u32 * tile_height;
+        let col_max = (col_min + tile_width).min(self.image_width);
+        let row_max = (row_min + tile_height).min(self.image_height);
+        Some(Ok(transform.bounds(
+            col_min as f64,
+            row_min as f64,
+            col_max as f64,
+            row_max as f64,
+        )))
+    }
+
+    /// The `(x, y)` indices of the tiles that intersect `bbox`, given in model coordinates as
+    /// `[min_x, min_y, max_x, max_y]`, in row-major order.
+    ///
+    /// Tiles that only touch the edge of `bbox` aren't included. For rotated transforms, this
+    /// returns every tile intersecting the pixel-space envelope of `bbox`. Returns `None` if this
+    /// isn't a tiled TIFF or there is no [`geotransform`][Self::geotransform].
+    pub fn tiles_in_bounds(&self, bbox: [f64; 4]) -> Option<AsyncTiffResult<Vec<(usize, usize)>>> {
+        let (tile_width, tile_height) = (self.tile_width? as f64, self.tile_height? as f64);
+        let (x_count, y_count) = self.tile_count()?;
+        let transform = match self.affine_transform()? {
+            Ok(transform) => transform,
+            Err(err) => return Some(Err(err)),
+        };
+        let Some(inverse) = transform.inverse() else {
+            return Some(Err(AsyncTiffError::General(
+                "Geotransform is not invertible".to_string(),
+            )));
+        };
+        let [min_x, min_y, max_x, max_y] = bbox;
+        let [col_min, row_min, col_max, row_max] = inverse.bounds(min_x, min_y, max_x, max_y);
+        let tile_range = |min: f64, max: f64, size: f64, count: usize| {
+            let start = (min / size).floor().clamp(0.0, count as f64) as usize;
+            let end = (max / size).ceil().clamp(0.0, count as f64) as usize;
+            start..end
+        };
+        let cols = tile_range(col_min, col_max, tile_width, x_count);
+        let rows = tile_range(row_min, row_max, tile_height, y_count);
+        Some(Ok(rows
+            .flat_map(|y| cols.clone().map(move |x| (x, y)))
+            .collect()))
+    }
+
     /// GDAL NoData value
     /// <https://gdal.org/en/stable/drivers/raster/gtiff.html#nodata-value>
     pub fn gdal_nodata(&self) -> Option<&str> {
         self.gdal_nodata.as_deref()
     }
 
+    /// The GDAL NoData value parsed at the precision of `data_type`.
+    ///
+    /// The `GDAL_NODATA` tag is stored verbatim (see [`gdal_nodata`][Self::gdal_nodata]), so the
+    /// result is exact for every data type. Returns `None` if there is no nodata value or it
+    /// can't be represented as `data_type`.
+    pub fn nodata_for_dtype(&self, data_type: DataType) -> Option<Nodata> {
+        Nodata::parse(self.gdal_nodata.as_deref()?, data_type)
+    }
+
+    /// The GDAL NoData value parsed at the precision of this image's
+    /// [`data_type`][Self::data_type].
+    ///
+    /// Images without a known data type fall back to parsing the value as `f64`. Returns `None`
+    /// if there is no nodata value or it can't be represented in the data type.
+    pub fn nodata(&self) -> Option<Nodata> {
+        self.nodata_for_dtype(self.data_type().unwrap_or(DataType::Float64))
+    }
+
     /// GDAL Metadata XML information
     ///
     /// Non standard metadata items are grouped together into a XML string stored in the non
@@ -687,10 +1134,64 @@ impl ImageFileDirectory {
     }
 
     /// Tags for which this crate doesn't have a hard-coded enum variant.
+    ///
+    /// Tags claimed by a registered extension are in [`extensions`][Self::extensions] instead.
     pub fn other_tags(&self) -> &HashMap<Tag, TagValue> {
         &self.other_tags
     }
 
+    /// The tags parsed by the extensions registered in the
+    /// [`ParseOptions`], by extension name.
+    pub fn extensions(&self) -> &HashMap<String, Arc<dyn TiffExtension>> {
+        &self.extensions.0
+    }
+
+    /// The tags parsed by the extension registered as `name`, if they are of type `T`.
+    ///
+    /// Returns `None` if the extension isn't registered or none of its tags are present.
+    pub fn extension<T: TiffExtension>(&self, name: &str) -> Option<&T> {
+        self.extensions.0.get(name)?.as_any().downcast_ref()
+    }
+
+    /// The byte offsets of the child IFDs listed in the `SubIFDs` tag, if any.
+    ///
+    /// These can be read with
+    /// [`TiffMetadataReader::read_ifd_at`][crate::metadata::TiffMetadataReader::read_ifd_at].
+    pub fn sub_ifd_offsets(&self) -> Option<Vec<u64>> {
+        self.other_tags
+            .get(&Tag::SubIfds)
+            .and_then(|value| value.clone().into_u64_vec().ok())
+    }
+
+    /// The byte offset of the EXIF IFD, if any.
+    pub fn exif_ifd_offset(&self) -> Option<u64> {
+        self.other_tags
+            .get(&Tag::ExifIfd)
+            .and_then(|value| value.clone().into_u64().ok())
+    }
+
+    /// The byte offset of the GPS IFD, if any.
+    pub fn gps_ifd_offset(&self) -> Option<u64> {
+        self.other_tags
+            .get(&Tag::GpsIfd)
+            .and_then(|value| value.clone().into_u64().ok())
+    }
+
+    /// Capture settings from the EXIF IFD, if any.
+    ///
+    /// This is only read by [`TiffMetadataReader`][crate::metadata::TiffMetadataReader] together
+    /// with this IFD if [`ParseOptions::private_ifds`] is set, and is `None` otherwise.
+    pub fn exif(&self) -> Option<&Exif> {
+        self.exif.as_ref()
+    }
+
+    /// Position and motion of the camera from the GPS IFD, if any.
+    ///
+    /// Like [`exif`][Self::exif], this is only read if [`ParseOptions::private_ifds`] is set.
+    pub fn gps(&self) -> Option<&Gps> {
+        self.gps.as_ref()
+    }
+
     /// LERC parameters, used in [LERC]-compressed TIFFs.
     ///
     /// [LERC]: https://esri.github.io/lerc/
@@ -719,6 +1220,67 @@ impl ImageFileDirectory {
         self.color_map.as_ref()
     }
 
+    /// The ColorMap as one `[red, green, blue]` entry per pixel value, at the full 16-bit
+    /// precision stored in the file.
+    ///
+    /// Entry `i` is the color of pixels with the value `i`.
+    pub fn color_table(&self) -> Option<Vec<[u16; 3]>> {
+        let color_map = self.color_map.as_ref()?;
+        let num_entries = color_map.len() / 3;
+        let (red, rest) = color_map.split_at(num_entries);
+        let (green, blue) = rest.split_at(num_entries);
+        Some(
+            (0..num_entries)
+                .map(|i| [red[i], green[i], blue[i]])
+                .collect(),
+        )
+    }
+
+    /// The ColorMap scaled to 8 bits per channel, rounding to the nearest value.
+    ///
+    /// See [`color_table`][Self::color_table] for the full-precision table.
+    pub fn color_table_u8(&self) -> Option<Vec<[u8; 3]>> {
+        let scale = |v: u16| ((v as u32 * 255 + 32767) / 65535) as u8;
+        Some(
+            self.color_table()?
+                .into_iter()
+                .map(|[r, g, b]| [scale(r), scale(g), scale(b)])
+                .collect(),
+        )
+    }
+
+    /// The ColorMap scaled to 8 bits per channel with an alpha channel, where the
+    /// [`transparent_index`][Self::transparent_index] entry, if any, is fully transparent and all
+    /// other entries are opaque.
+    pub fn color_table_rgba_u8(&self) -> Option<Vec<[u8; 4]>> {
+        let transparent_index = self.transparent_index();
+        Some(
+            self.color_table_u8()?
+                .into_iter()
+                .enumerate()
+                .map(|(i, [r, g, b])| {
+                    let alpha = if Some(i) == transparent_index { 0 } else { 255 };
+                    [r, g, b, alpha]
+                })
+                .collect(),
+        )
+    }
+
+    /// The ColorMap entry that represents nodata pixels.
+    ///
+    /// This is the GDAL NoData value, if it is an integer indexing into the color table.
+    pub fn transparent_index(&self) -> Option<usize> {
+        let num_entries = self.color_map.as_ref()?.len() / 3;
+        let nodata = self
+            .gdal_nodata()?
+            .trim_matches(|c: char| c.is_whitespace() || c == '\0');
+        let index = nodata.parse::<usize>().ok().or_else(|| {
+            let value = nodata.parse::<f64>().ok()?;
+            (value >= 0.0 && value.fract() == 0.0).then_some(value as usize)
+        })?;
+        (index < num_entries).then_some(index)
+    }
+
     /// Find the byte range(s) for the tile located at `x` column and `y` row.
     pub fn tile_byte_range(&self, x: usize, y: usize) -> Option<TileByteRange> {
         TileByteRange::from_ifd_tile(self, x, y)
@@ -751,11 +1313,23 @@ impl ImageFileDirectory {
         &self,
         xy: &[(usize, usize)],
         reader: &dyn AsyncFileReader,
+    ) -> AsyncTiffResult<Vec<Tile>> {
+        self.fetch_tiles_with_options(xy, reader, &FetchOptions::default())
+            .await
+    }
+
+    /// Fetch the tiles located at `x` column and `y` row, controlling request concurrency and
+    /// merging with `options`.
+    pub async fn fetch_tiles_with_options(
+        &self,
+        xy: &[(usize, usize)],
+        reader: &dyn AsyncFileReader,
+        options: &FetchOptions,
     ) -> AsyncTiffResult<Vec<Tile>> {
         let byte_ranges = self
             .tiles_byte_ranges(xy)
             .ok_or(AsyncTiffError::General("Not a tiled TIFF".to_string()))?;
-        let compressed_bytes = byte_ranges.into_fetch(reader).await?;
+        let compressed_bytes = byte_ranges.into_fetch_with(reader, options).await?;
         Ok(compressed_bytes
             .into_iter()
             .zip(xy)
@@ -763,6 +1337,230 @@ impl ImageFileDirectory {
             .collect())
     }
 
+    /// Fetch the tiles located at `x` column and `y` row, with a separate result for each tile.
+    ///
+    /// Unlike [`fetch_tiles_with_options`][Self::fetch_tiles_with_options], a tile that can't be
+    /// fetched, e.g. because its index is out of bounds or its request fails, doesn't fail the
+    /// whole batch. This lets mosaicking pipelines fill failed tiles with nodata instead.
+    pub async fn fetch_tiles_with_errors(
+        &self,
+        xy: &[(usize, usize)],
+        reader: &dyn AsyncFileReader,
+        options: &FetchOptions,
+    ) -> Vec<AsyncTiffResult<Tile>> {
+        let byte_ranges = xy
+            .iter()
+            .map(|&(x, y)| self.checked_tile_byte_range(x, y))
+            .collect::<Vec<_>>();
+        let flat_ranges = byte_ranges
+            .iter()
+            .flatten()
+            .flat_map(|byte_range| match byte_range {
+                TileByteRange::Chunky(range) => std::slice::from_ref(range),
+                TileByteRange::Planar(ranges) => ranges.as_slice(),
+            })
+            .cloned()
+            .collect();
+        let mut buffers = fetch_ranges_with_errors(reader, flat_ranges, options)
+            .await
+            .into_iter();
+        byte_ranges
+            .into_iter()
+            .zip(xy)
+            .map(|(byte_range, &(x, y))| {
+                // Take every band before checking for errors, to keep the remaining buffers
+                // aligned with their tiles
+                let compressed_bytes = match byte_range? {
+                    TileByteRange::Chunky(_) => {
+                        CompressedBytes::Chunky(buffers.next().unwrap_or_else(|| {
+                            Err(AsyncTiffError::TileIndexError(x as u32, y as u32))
+                        })?)
+                    }
+                    TileByteRange::Planar(ranges) => CompressedBytes::Planar(
+                        buffers
+                            .by_ref()
+                            .take(ranges.len())
+                            .collect::<Vec<_>>()
+                            .into_iter()
+                            .collect::<AsyncTiffResult<_>>()?,
+                    ),
+                };
+                Ok(compressed_bytes.into_tile(x, y, self))
+            })
+            .collect()
+    }
+
+    /// Fetch and decode the tiles located at `x` column and `y` row, with a separate result for
+    /// each tile.
+    ///
+    /// Like [`fetch_tiles_with_errors`][Self::fetch_tiles_with_errors], a tile that can't be
+    /// fetched or decoded doesn't fail the whole batch.
+    pub async fn read_tiles_with_errors(
+        &self,
+        xy: &[(usize, usize)],
+        reader: &dyn AsyncFileReader,
+        decoder_registry: &DecoderRegistry,
+        options: &FetchOptions,
+    ) -> Vec<AsyncTiffResult<Array>> {
+        let tiles = self.fetch_tiles_with_errors(xy, reader, options).await;
+        join_all(
+            tiles
+                .into_iter()
+                .map(|tile| async move { tile?.decode_async(decoder_registry).await }),
+        )
+        .await
+    }
+
+    /// The byte range(s) of the tile at `x` column and `y` row, checking that it exists.
+    fn checked_tile_byte_range(&self, x: usize, y: usize) -> AsyncTiffResult<TileByteRange> {
+        let (tiles_per_row, tiles_per_col) = self
+            .tile_count()
+            .ok_or(AsyncTiffError::General("Not a tiled TIFF".to_string()))?;
+        if x >= tiles_per_row || y >= tiles_per_col {
+            return Err(AsyncTiffError::TileIndexError(x as u32, y as u32));
+        }
+        self.tile_byte_range(x, y).ok_or(AsyncTiffError::General(
+            "Missing tile offsets or byte counts".to_string(),
+        ))
+    }
+
+    /// Stream every tile of this IFD, with at most `concurrency` tile requests in flight.
+    ///
+    /// Tiles are yielded with their `(x, y)` position in the order their requests complete, so
+    /// whole images can be processed without first fetching every tile. The stream yields a
+    /// single error if this is not a tiled TIFF.
+    pub fn stream_tiles<'a>(
+        &'a self,
+        reader: &'a dyn AsyncFileReader,
+        concurrency: usize,
+    ) -> impl Stream<Item = AsyncTiffResult<(usize, usize, Tile)>> + Send + 'a {
+        let Some((x_count, y_count)) = self.tile_count() else {
+            return futures::stream::once(async {
+                Err(AsyncTiffError::General("Not a tiled TIFF".to_string()))
+            })
+            .left_stream();
+        };
+        futures::stream::iter((0..y_count).flat_map(move |y| (0..x_count).map(move |x| (x, y))))
+            .map(move |(x, y)| async move {
+                let tile = self.fetch_tile(x, y, reader).await?;
+                Ok((x, y, tile))
+            })
+            .buffer_unordered(concurrency.max(1))
+            .right_stream()
+    }
+
+    /// The number of rows in each strip, which defaults to the full image height if the
+    /// `RowsPerStrip` tag is missing.
+    pub fn strip_height(&self) -> u32 {
+        self.rows_per_strip
+            .unwrap_or(self.image_height)
+            .min(self.image_height)
+            .max(1)
+    }
+
+    /// Return the number of strips per band in the IFD
+    /// Returns `None` if this is not a stripped TIFF
+    pub fn strip_count(&self) -> Option<usize> {
+        self.strip_offsets.as_ref()?;
+        Some(self.image_height.div_ceil(self.strip_height()) as usize)
+    }
+
+    /// Find the byte range(s) for the strip at index `y`.
+    pub fn strip_byte_range(&self, y: usize) -> Option<TileByteRange> {
+        let strip_offsets = self.strip_offsets.as_deref()?;
+        let strip_byte_counts = self.strip_byte_counts.as_deref()?;
+        let strips_per_band = self.strip_count()?;
+        let range = |idx: usize| -> Option<Range<u64>> {
+            let offset = *strip_offsets.get(idx)?;
+            Some(offset..offset.saturating_add(*strip_byte_counts.get(idx)?))
+        };
+        match self.planar_configuration {
+            PlanarConfiguration::Chunky => Some(TileByteRange::Chunky(range(y)?)),
+            PlanarConfiguration::Planar => {
+                let band_ranges = (0..self.samples_per_pixel as usize)
+                    .map(|band| range(band * strips_per_band + y))
+                    .collect::<Option<Vec<_>>>()?;
+                Some(TileByteRange::Planar(band_ranges))
+            }
+        }
+    }
+
+    /// Fetch the strip at index `y` using the provided reader.
+    ///
+    /// The returned tile covers the full image width. Its height is `RowsPerStrip`, except for the
+    /// last strip, which only covers the remaining rows of the image.
+    pub async fn fetch_strip(
+        &self,
+        y: usize,
+        reader: &dyn AsyncFileReader,
+    ) -> AsyncTiffResult<Tile> {
+        let byte_ranges = self
+            .strip_byte_range(y)
+            .ok_or(AsyncTiffError::General("Not a stripped TIFF".to_string()))?;
+        let compressed_bytes = byte_ranges.into_fetch(reader).await?;
+        Ok(self.strip_into_tile(compressed_bytes, y))
+    }
+
+    /// Fetch the strips with indices in `strips` using the provided reader. See
+    /// [`fetch_strip`][Self::fetch_strip].
+    pub async fn fetch_strips(
+        &self,
+        strips: Range<usize>,
+        reader: &dyn AsyncFileReader,
+    ) -> AsyncTiffResult<Vec<Tile>> {
+        self.fetch_strips_with_options(strips, reader, &FetchOptions::default())
+            .await
+    }
+
+    /// Fetch the strips with indices in `strips`, controlling request concurrency and merging
+    /// with `options`.
+    pub async fn fetch_strips_with_options(
+        &self,
+        strips: Range<usize>,
+        reader: &dyn AsyncFileReader,
+        options: &FetchOptions,
+    ) -> AsyncTiffResult<Vec<Tile>> {
+        let strip_count = self
+            .strip_count()
+            .ok_or(AsyncTiffError::General("Not a stripped TIFF".to_string()))?;
+        if strips.end > strip_count {
+            return Err(AsyncTiffError::General(format!(
+                "Strips {strips:?} out of range for an image with {strip_count} strips"
+            )));
+        }
+        let mut chunky = vec![];
+        let mut planar = vec![];
+        for y in strips.clone() {
+            match self.strip_byte_range(y).ok_or(AsyncTiffError::General(
+                "Missing strip offsets or byte counts".to_string(),
+            ))? {
+                TileByteRange::Chunky(range) => chunky.push(range),
+                TileByteRange::Planar(ranges) => planar.push(ranges),
+            }
+        }
+        let byte_ranges = if planar.is_empty() {
+            TilesByteRanges::Chunky(chunky)
+        } else {
+            TilesByteRanges::Planar(planar)
+        };
+        let compressed_bytes = byte_ranges.into_fetch_with(reader, options).await?;
+        Ok(compressed_bytes
+            .into_iter()
+            .zip(strips)
+            .map(|(buffer, y)| self.strip_into_tile(buffer, y))
+            .collect())
+    }
+
+    /// A tile for the strip at index `y`, covering the full image width and only the remaining
+    /// rows for the last strip.
+    fn strip_into_tile(&self, compressed_bytes: CompressedBytes, y: usize) -> Tile {
+        let strip_height = self.strip_height();
+        let mut tile = compressed_bytes.into_tile(0, y, self);
+        tile.width = self.image_width;
+        tile.height = strip_height.min(self.image_height.saturating_sub(y as u32 * strip_height));
+        tile
+    }
+
     /// Return the number of x/y tiles in the IFD
     /// Returns `None` if this is not a tiled TIFF
     pub fn tile_count(&self) -> Option<(usize, usize)> {
@@ -770,6 +1568,282 @@ impl ImageFileDirectory {
         let y_count = (self.image_height as f64 / self.tile_height? as f64).ceil();
         Some((x_count as usize, y_count as usize))
     }
+
+    /// Summarize the compressed sizes of the tiles or strips in this IFD.
+    ///
+    /// This only uses metadata, so it can be used to spot empty or pathologically compressed
+    /// images before fetching any pixels. Returns `None` if the IFD has no byte counts, or if its
+    /// byte counts or chunk size overflow a `u64`.
+    pub fn compression_stats(&self) -> Option<CompressionStats> {
+        CompressionStats::from_ifd(self)
+    }
+
+    /// List the byte range and decoded layout of every tile or strip in this IFD, in the order
+    /// of the `TileOffsets` or `StripOffsets` tag.
+    ///
+    /// This only uses metadata, so it can be used to build virtual datasets that reference the
+    /// chunks of the file instead of copying them. Chunks that aren't stored have a length of
+    /// zero. The iterator is empty if the IFD has no chunk offsets.
+    pub fn chunk_records(&self) -> impl Iterator<Item = ChunkRecord> + '_ {
+        crate::manifest::chunk_records(self)
+    }
+
+    /// Problems that were skipped while parsing this IFD with lenient
+    /// [`ParseOptions`]. In strict mode, this only lists invalid EXIF or GPS IFDs, which are
+    /// skipped regardless of the mode.
+    pub fn parse_warnings(&self) -> &[String] {
+        &self.parse_warnings
+    }
+
+    /// The IFD entries with a field type this crate can't interpret, which are kept as raw bytes
+    /// instead of being parsed into a [`TagValue`].
+    ///
+    /// Entries of known types are available through the typed accessors or
+    /// [`other_tags`][Self::other_tags].
+    pub fn raw_tags(&self) -> &[RawTag] {
+        &self.raw_tags
+    }
+
+    /// Read an arbitrary pixel window of this image into a single [`Array`].
+    ///
+    /// The tiles or strips intersecting `window` are fetched concurrently and decoded, and their
+    /// overlapping parts are copied into the result. The result has the same axis order as a
+    /// decoded tile: `(height, width, bands)` for chunky and `(bands, height, width)` for planar
+    /// images.
+    pub async fn read_window(
+        &self,
+        window: Window,
+        reader: &dyn AsyncFileReader,
+        decoder_registry: &DecoderRegistry,
+    ) -> AsyncTiffResult<Array> {
+        self.read_window_with_options(window, reader, decoder_registry, &FetchOptions::default())
+            .await
+    }
+
+    /// Read a pixel window like [`read_window`][Self::read_window], controlling request
+    /// concurrency and merging with `options`.
+    pub async fn read_window_with_options(
+        &self,
+        window: Window,
+        reader: &dyn AsyncFileReader,
+        decoder_registry: &DecoderRegistry,
+        options: &FetchOptions,
+    ) -> AsyncTiffResult<Array> {
+        crate::window::read_window(self, window, reader, decoder_registry, options).await
+    }
+
+    /// Fetch the tiles or strips intersecting `window` without decoding them.
+    ///
+    /// Together with [`mosaic_window`][Self::mosaic_window], this splits
+    /// [`read_window`][Self::read_window] so that the tiles can be decoded elsewhere, such as in
+    /// parallel on a thread pool.
+    pub async fn fetch_window(
+        &self,
+        window: Window,
+        reader: &dyn AsyncFileReader,
+        options: &FetchOptions,
+    ) -> AsyncTiffResult<Vec<Tile>> {
+        crate::window::fetch_window(self, window, reader, options).await
+    }
+
+    /// Copy the decoded tiles or strips returned by [`fetch_window`][Self::fetch_window] into a
+    /// single array, like [`read_window`][Self::read_window].
+    ///
+    /// Each chunk is given as its `(x, y)` index, from [`Tile::x`] and [`Tile::y`], and its
+    /// decoded array.
+    pub fn mosaic_window(
+        &self,
+        window: Window,
+        chunks: impl IntoIterator<Item = (usize, usize, Array)>,
+    ) -> AsyncTiffResult<Array> {
+        crate::window::mosaic_window(self, window, chunks)
+    }
+
+    /// A view of the band at `index`, which reads a single band of this image. Returns `None` if
+    /// `index` is not less than [`samples_per_pixel`][Self::samples_per_pixel].
+    pub fn band(&self, index: usize) -> Option<RasterBand<'_>> {
+        RasterBand::new(self, index)
+    }
+}
+
+/// The value of a required tag, or `default` with a warning if it's missing in lenient mode.
+fn required_or_default<T: std::fmt::Debug>(
+    tag: Tag,
+    value: Option<T>,
+    default: T,
+    options: &ParseOptions,
+    warnings: &mut Vec<String>,
+) -> Result<T, TiffError> {
+    match value {
+        Some(value) => Ok(value),
+        None if !options.strict() => {
+            warnings.push(format!("Missing or invalid {tag:?}, assuming {default:?}"));
+            Ok(default)
+        }
+        None => Err(TiffError::FormatError(
+            TiffFormatError::RequiredTagNotFound(tag),
+        )),
+    }
+}
+
+/// The closest rational to `value` whose numerator and denominator fit in 32 bits, found with
+/// its continued fraction expansion.
+fn to_rational(value: f64) -> TagValue {
+    let (mut h0, mut h1, mut k0, mut k1) = (0u64, 1u64, 1u64, 0u64);
+    let mut x = value.abs();
+    loop {
+        let a = x.floor();
+        if a > u32::MAX as f64 {
+            break;
+        }
+        let a = a as u64;
+        let (h2, k2) = (a * h1 + h0, a * k1 + k0);
+        if h2 > u32::MAX as u64 || k2 > u32::MAX as u64 {
+            break;
+        }
+        (h0, h1, k0, k1) = (h1, h2, k1, k2);
+        let fract = x - a as f64;
+        if h1 as f64 / k1 as f64 == value.abs() || fract == 0.0 {
+            break;
+        }
+        x = 1.0 / fract;
+    }
+    TagValue::Rational(h1 as u32, k1.max(1) as u32)
+}
+
+/// The GDAL-ordered geotransform of a row-major 4×4 `ModelTransformation` matrix.
+///
+/// Since raster coordinates have no height, only the terms mapping pixel columns and rows to
+/// model x and y are used. A matrix that tilts the raster out of the model's x/y plane or
+/// applies a perspective projection can't be represented and returns an error.
+fn transformation_to_geotransform(matrix: &[f64]) -> AsyncTiffResult<[f64; 6]> {
+    let invalid = |msg: &str| {
+        AsyncTiffError::from(TiffError::FormatError(TiffFormatError::Format(format!(
+            "Invalid ModelTransformation: {msg}"
+        ))))
+    };
+    let [a, b, c, d, e, f, g, h, i, j, _, _, m, n, o, p] = *matrix else {
+        return Err(invalid(&format!(
+            "expected 16 values, got {}",
+            matrix.len()
+        )));
+    };
+    if [c, g, i, j] != [0.0; 4] {
+        return Err(invalid(
+            "rotation out of the x/y plane doesn't fit a 2D affine transform",
+        ));
+    }
+    if [m, n, o, p] != [0.0, 0.0, 0.0, 1.0] {
+        return Err(invalid("projective terms don't fit a 2D affine transform"));
+    }
+    Ok([d, a, b, h, e, f])
+}
+
+/// Parse the raw `GeoKeyDirectory` tag, resolving keys stored in `GeoAsciiParams` and
+/// `GeoDoubleParams`.
+///
+/// Keys that aren't part of the GeoTIFF spec are kept in
+/// [`GeoKeyDirectory::other_geo_keys`].
+pub(crate) fn parse_geo_key_directory(
+    data: &[u16],
+    geo_ascii_params: Option<&str>,
+    geo_double_params: Option<&[f64]>,
+) -> AsyncTiffResult<GeoKeyDirectory> {
+    let invalid = |msg: &str| {
+        AsyncTiffError::from(TiffError::FormatError(TiffFormatError::Format(format!(
+            "Invalid GeoKeyDirectory: {msg}"
+        ))))
+    };
+    let mut chunks = data.chunks_exact(4);
+
+    let header = chunks.next().ok_or_else(|| invalid("missing header"))?;
+    let key_directory_version = header[0];
+    let key_revision = header[1];
+    if key_directory_version != 1 || key_revision != 1 {
+        return Err(invalid(&format!(
+            "unsupported version {key_directory_version}.{key_revision}"
+        )));
+    }
+
+    let _key_minor_revision = header[2];
+    let number_of_keys = header[3];
+
+    let mut tags = HashMap::with_capacity(number_of_keys as usize);
+    let mut other_geo_keys = HashMap::new();
+    for _ in 0..number_of_keys {
+        let chunk = chunks
+            .next()
+            .ok_or_else(|| invalid("fewer keys than declared"))?;
+
+        // Keys that aren't part of the GeoTIFF spec are kept by id. Some GeoTIFFs include keys
+        // that were proposed but not included in the spec. See
+        // https://github.com/developmentseed/async-tiff/pull/131 and
+        // https://github.com/virtual-zarr/virtual-tiff/issues/52
+        let key_id = chunk[0];
+        let tag_name = GeoKeyTag::try_from_primitive(key_id).ok();
+        let key_name = || match tag_name {
+            Some(tag_name) => format!("{tag_name:?}"),
+            None => format!("geo key {key_id}"),
+        };
+
+        let tag_location = chunk[1];
+        let count = chunk[2] as usize;
+        let value_offset = chunk[3] as usize;
+
+        let value = if tag_location == 0 {
+            TagValue::Short(chunk[3])
+        } else if Tag::from_u16_exhaustive(tag_location) == Tag::GeoAsciiParams {
+            // If the tag_location points to the value of Tag::GeoAsciiParams, then we
+            // need to extract a subslice from GeoAsciiParams
+            let mut s = geo_ascii_params
+                .ok_or_else(|| invalid("GeoAsciiParams is missing"))?
+                .get(value_offset..value_offset + count)
+                .ok_or_else(|| invalid(&format!("{} is out of bounds", key_name())))?;
+
+            // It seems that this string subslice might always include the final |
+            // character?
+            if s.ends_with('|') {
+                s = &s[0..s.len() - 1];
+            }
+
+            TagValue::Ascii(s.to_string())
+        } else if Tag::from_u16_exhaustive(tag_location) == Tag::GeoDoubleParams {
+            // If the tag_location points to the value of Tag::GeoDoubleParams, then we
+            // need to extract a subslice from GeoDoubleParams
+            let values = geo_double_params
+                .ok_or_else(|| invalid("GeoDoubleParams is missing"))?
+                .get(value_offset..value_offset + count)
+                .ok_or_else(|| invalid(&format!("{} is out of bounds", key_name())))?;
+            if let [value] = values {
+                TagValue::Double(*value)
+            } else {
+                TagValue::List(values.iter().map(|val| TagValue::Double(*val)).collect())
+            }
+        } else {
+            continue;
+        };
+        match tag_name {
+            Some(tag_name) => tags.insert(tag_name, value),
+            None => other_geo_keys.insert(key_id, value),
+        };
+    }
+    Ok(GeoKeyDirectory::from_tags(tags, other_geo_keys)?)
+}
+
+/// An IFD entry with a field type that isn't defined by the TIFF spec, returned by
+/// [`ImageFileDirectory::raw_tags`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawTag {
+    /// The tag of the entry.
+    pub tag: Tag,
+    /// The declared field type code.
+    pub field_type: u16,
+    /// The declared number of values.
+    pub count: u64,
+    /// The raw value/offset field of the entry: 4 bytes, or 8 bytes in BigTIFF, in the byte order
+    /// of the file. Since the size of the type is unknown, this may either hold the value itself
+    /// or the offset of the value.
+    pub value: Bytes,
 }
 
 /// A description of the byte ranges for a tile, which may differ based on whether the TIFF is in
@@ -799,9 +1873,11 @@ impl TileByteRange {
         match ifd.planar_configuration {
             PlanarConfiguration::Chunky => {
                 let idx = (y * tiles_per_row) + x;
-                let offset = tile_offsets[idx];
-                let byte_count = tile_byte_counts[idx];
-                Some(TileByteRange::Chunky(offset..(offset + byte_count)))
+                let offset = *tile_offsets.get(idx)?;
+                let byte_count = *tile_byte_counts.get(idx)?;
+                Some(TileByteRange::Chunky(
+                    offset..offset.saturating_add(byte_count),
+                ))
             }
             PlanarConfiguration::Planar => {
                 let tiles_per_band = tiles_per_row * tiles_per_col;
@@ -809,11 +1885,11 @@ impl TileByteRange {
                 let band_ranges = (0..num_bands)
                     .map(|band| {
                         let band_idx = (band * tiles_per_band) + (y * tiles_per_row) + x;
-                        let offset = tile_offsets[band_idx];
-                        let byte_count = tile_byte_counts[band_idx];
-                        offset..(offset + byte_count)
+                        let offset = *tile_offsets.get(band_idx)?;
+                        let byte_count = *tile_byte_counts.get(band_idx)?;
+                        Some(offset..offset.saturating_add(byte_count))
                     })
-                    .collect::<Vec<_>>();
+                    .collect::<Option<Vec<_>>>()?;
                 Some(TileByteRange::Planar(band_ranges))
             }
         }
@@ -830,33 +1906,35 @@ pub enum TilesByteRanges {
 }
 
 impl TilesByteRanges {
-    async fn into_fetch(
+    async fn into_fetch_with(
         self,
         reader: &dyn AsyncFileReader,
+        options: &FetchOptions,
     ) -> AsyncTiffResult<Vec<CompressedBytes>> {
         match self {
             Self::Chunky(ranges) => {
-                let buffers = reader.get_byte_ranges(ranges).await?;
+                let buffers = fetch_ranges(reader, ranges, options).await?;
                 Ok(buffers.into_iter().map(CompressedBytes::Chunky).collect())
             }
             Self::Planar(ranges) => {
                 // Record how many bands each tile has, then flatten into a single fetch
                 let band_counts: Vec<usize> = ranges.iter().map(|r| r.len()).collect();
                 let flat_ranges: Vec<Range<u64>> = ranges.into_iter().flatten().collect();
-                let flat_buffers = reader.get_byte_ranges(flat_ranges).await?;
-                // Re-chunk the flat results back into per-tile band vecs
-                let mut flat_iter = flat_buffers.into_iter();
-                band_counts
-                    .into_iter()
-                    .map(|n| {
-                        let band_bytes: Vec<Bytes> = flat_iter.by_ref().take(n).collect();
-                        Ok(CompressedBytes::Planar(band_bytes))
-                    })
-                    .collect()
+                let flat_buffers = fetch_ranges(reader, flat_ranges, options).await?;
+                Ok(Self::regroup_bands(band_counts, flat_buffers))
             }
         }
     }
 
+    /// Re-chunk flat per-band buffers back into per-tile band vecs.
+    fn regroup_bands(band_counts: Vec<usize>, flat_buffers: Vec<Bytes>) -> Vec<CompressedBytes> {
+        let mut flat_iter = flat_buffers.into_iter();
+        band_counts
+            .into_iter()
+            .map(|n| CompressedBytes::Planar(flat_iter.by_ref().take(n).collect()))
+            .collect()
+    }
+
     fn from_ifd_tiles(ifd: &ImageFileDirectory, xy: &[(usize, usize)]) -> Option<Self> {
         if xy.is_empty() {
             return match ifd.planar_configuration {
@@ -907,7 +1985,7 @@ pub enum CompressedBytes {
 
 impl CompressedBytes {
     fn into_tile(self, x: usize, y: usize, ifd: &ImageFileDirectory) -> Tile {
-        let data_type = DataType::from_tags(&ifd.sample_format, &ifd.bits_per_sample);
+        let data_type = ifd.data_type();
         Tile {
             x,
             y,
@@ -917,6 +1995,17 @@ impl CompressedBytes {
             planar_configuration: ifd.planar_configuration,
             samples_per_pixel: ifd.samples_per_pixel,
             bits_per_sample: ifd.bits_per_sample[0],
+            mixed_bits_per_sample: ifd
+                .bits_per_sample
+                .iter()
+                .any(|&b| b != ifd.bits_per_sample[0])
+                .then(|| ifd.bits_per_sample.clone()),
+            sample_format: ifd
+                .sample_format
+                .first()
+                .copied()
+                .unwrap_or(SampleFormat::Uint),
+            fill_order: ifd.fill_order.unwrap_or(FillOrder::MsbToLsb),
             endianness: ifd.endianness,
             predictor: ifd.predictor.unwrap_or(Predictor::None),
             compressed_bytes: self,
@@ -924,6 +2013,8 @@ impl CompressedBytes {
             photometric_interpretation: ifd.photometric_interpretation,
             jpeg_tables: ifd.jpeg_tables.clone(),
             lerc_parameters: ifd.lerc_parameters.clone(),
+            ycbcr: YCbCr::from_ifd(ifd),
+            colormap: ifd.color_map.clone(),
         }
     }
 }
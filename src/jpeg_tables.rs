@@ -0,0 +1,268 @@
+//! Introspection of JPEG quantization and Huffman tables.
+//!
+//! JPEG-compressed TIFFs usually store the tables shared by all tiles in the `JPEGTables` tag as
+//! an "abbreviated" JPEG stream. Parsing it with [`JpegTables::parse`] (or
+//! [`ImageFileDirectory::parsed_jpeg_tables`][crate::ImageFileDirectory::parsed_jpeg_tables])
+//! allows assessing the lossy compression quality without decoding any tiles.
+
+use crate::error::{AsyncTiffResult, TiffError, TiffFormatError};
+
+/// The IJG (libjpeg) reference luminance quantization table, at quality 50.
+const STD_LUMINANCE_TABLE: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOS: u8 = 0xDA;
+const DQT: u8 = 0xDB;
+const DHT: u8 = 0xC4;
+
+/// A quantization table from a DQT segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuantizationTable {
+    /// The table destination identifier (0-3) that frame components refer to.
+    pub id: u8,
+    /// The precision of the values in bits, 8 or 16.
+    pub precision: u8,
+    /// The 64 quantization values, in zigzag order as stored in the stream.
+    pub values: [u16; 64],
+}
+
+/// Whether a Huffman table codes DC or AC coefficients.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HuffmanClass {
+    /// DC coefficient table.
+    Dc,
+    /// AC coefficient table.
+    Ac,
+}
+
+/// A Huffman table from a DHT segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HuffmanTable {
+    /// Whether this table codes DC or AC coefficients.
+    pub class: HuffmanClass,
+    /// The table destination identifier (0-3) that scan components refer to.
+    pub id: u8,
+    /// The number of codes of each length from 1 to 16 bits.
+    pub code_lengths: [u8; 16],
+    /// The symbol values, in order of increasing code length.
+    pub values: Vec<u8>,
+}
+
+/// The tables contained in a JPEG stream, such as the content of the `JPEGTables` tag.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct JpegTables {
+    /// Quantization tables, in the order they are defined.
+    pub quantization_tables: Vec<QuantizationTable>,
+    /// Huffman tables, in the order they are defined.
+    pub huffman_tables: Vec<HuffmanTable>,
+}
+
+impl JpegTables {
+    /// Parse the DQT and DHT segments of a JPEG stream.
+    ///
+    /// Parsing stops at the first start-of-scan or end-of-image marker, so this accepts both the
+    /// abbreviated stream of a `JPEGTables` tag and a complete JPEG tile.
+    pub fn parse(data: &[u8]) -> AsyncTiffResult<Self> {
+        let mut tables = Self::default();
+        let mut pos = 0;
+        while pos < data.len() {
+            if data[pos] != 0xFF {
+                return Err(format_error(format!(
+                    "expected JPEG marker at offset {pos}"
+                )));
+            }
+            let marker = *data
+                .get(pos + 1)
+                .ok_or_else(|| format_error("truncated JPEG marker".to_string()))?;
+            pos += 2;
+            match marker {
+                // Fill bytes before a marker
+                0xFF => pos -= 1,
+                SOI => {}
+                EOI | SOS => break,
+                // Standalone markers without a length
+                0x01 | 0xD0..=0xD7 => {}
+                _ => {
+                    let length = read_u16(data, pos)? as usize;
+                    if length < 2 || pos + length > data.len() {
+                        return Err(format_error(format!(
+                            "invalid length {length} of JPEG segment 0xFF{marker:02X}"
+                        )));
+                    }
+                    let segment = &data[pos + 2..pos + length];
+                    match marker {
+                        DQT => tables.parse_dqt(segment)?,
+                        DHT => tables.parse_dht(segment)?,
+                        _ => {}
+                    }
+                    pos += length;
+                }
+            }
+        }
+        Ok(tables)
+    }
+
+    fn parse_dqt(&mut self, mut segment: &[u8]) -> AsyncTiffResult<()> {
+        while let Some((&pq_tq, rest)) = segment.split_first() {
+            let precision = if pq_tq >> 4 == 0 { 8 } else { 16 };
+            let size = 64 * (precision as usize / 8);
+            if rest.len() < size {
+                return Err(format_error(
+                    "truncated JPEG quantization table".to_string(),
+                ));
+            }
+            let mut values = [0; 64];
+            for (i, value) in values.iter_mut().enumerate() {
+                *value = if precision == 8 {
+                    rest[i] as u16
+                } else {
+                    u16::from_be_bytes([rest[2 * i], rest[2 * i + 1]])
+                };
+            }
+            self.quantization_tables.push(QuantizationTable {
+                id: pq_tq & 0x0F,
+                precision,
+                values,
+            });
+            segment = &rest[size..];
+        }
+        Ok(())
+    }
+
+    fn parse_dht(&mut self, mut segment: &[u8]) -> AsyncTiffResult<()> {
+        while let Some((&tc_th, rest)) = segment.split_first() {
+            if rest.len() < 16 {
+                return Err(format_error("truncated JPEG Huffman table".to_string()));
+            }
+            let code_lengths: [u8; 16] = rest[..16].try_into().unwrap();
+            let num_values = code_lengths.iter().map(|&n| n as usize).sum::<usize>();
+            let values = rest
+                .get(16..16 + num_values)
+                .ok_or_else(|| format_error("truncated JPEG Huffman table".to_string()))?;
+            self.huffman_tables.push(HuffmanTable {
+                class: if tc_th >> 4 == 0 {
+                    HuffmanClass::Dc
+                } else {
+                    HuffmanClass::Ac
+                },
+                id: tc_th & 0x0F,
+                code_lengths,
+                values: values.to_vec(),
+            });
+            segment = &rest[16 + num_values..];
+        }
+        Ok(())
+    }
+
+    /// The quantization table with the given destination identifier.
+    pub fn quantization_table(&self, id: u8) -> Option<&QuantizationTable> {
+        // Later definitions replace earlier ones
+        self.quantization_tables.iter().rev().find(|t| t.id == id)
+    }
+
+    /// Whether any Huffman tables are defined. If not, each tile must define its own, or use the
+    /// default tables.
+    pub fn has_huffman_tables(&self) -> bool {
+        !self.huffman_tables.is_empty()
+    }
+
+    /// Estimate the IJG (libjpeg) quality setting, from 1 to 100, that produced the luminance
+    /// quantization table (table 0).
+    ///
+    /// This compares the table to the scaled IJG reference table, so it is only meaningful for
+    /// files written by libjpeg-based encoders such as GDAL. Values are clamped to 255 in 8-bit
+    /// tables, so very low qualities are overestimated. Returns `None` if there is no
+    /// quantization table 0.
+    pub fn estimated_quality(&self) -> Option<u8> {
+        let table = self.quantization_table(0)?;
+        let sum = table.values.iter().map(|&v| v as f64).sum::<f64>();
+        let std_sum = STD_LUMINANCE_TABLE.iter().map(|&v| v as f64).sum::<f64>();
+        // libjpeg scales the reference table by `scale / 100`, where scale is `5000 / quality`
+        // below quality 50 and `200 - 2 * quality` above.
+        let scale = sum * 100.0 / std_sum;
+        let quality = if scale <= 100.0 {
+            (200.0 - scale) / 2.0
+        } else {
+            5000.0 / scale
+        };
+        Some(quality.round().clamp(1.0, 100.0) as u8)
+    }
+}
+
+fn read_u16(data: &[u8], pos: usize) -> AsyncTiffResult<u16> {
+    let bytes = data
+        .get(pos..pos + 2)
+        .ok_or_else(|| format_error("truncated JPEG segment".to_string()))?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn format_error(message: String) -> crate::error::AsyncTiffError {
+    TiffError::FormatError(TiffFormatError::Format(message)).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::util::open_tiff;
+
+    /// Build an abbreviated JPEG stream with one luminance table at the given IJG quality.
+    fn tables_with_quality(quality: u32) -> Vec<u8> {
+        let scale = if quality < 50 {
+            5000 / quality
+        } else {
+            200 - 2 * quality
+        };
+        let mut data = vec![0xFF, SOI, 0xFF, DQT, 0, 67, 0];
+        data.extend(
+            STD_LUMINANCE_TABLE
+                .iter()
+                .map(|&v| ((v as u32 * scale + 50) / 100).clamp(1, 255) as u8),
+        );
+        data.extend([0xFF, DHT, 0, 21, 0x10]);
+        data.extend([0, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 2]);
+        data.extend([0xFF, EOI]);
+        data
+    }
+
+    #[test]
+    fn test_parse_tables() {
+        let tables = JpegTables::parse(&tables_with_quality(75)).unwrap();
+        assert_eq!(tables.quantization_tables.len(), 1);
+        assert_eq!(tables.quantization_tables[0].precision, 8);
+        assert_eq!(tables.huffman_tables.len(), 1);
+        assert_eq!(tables.huffman_tables[0].class, HuffmanClass::Ac);
+        assert_eq!(tables.huffman_tables[0].values, [1, 2]);
+        assert!(tables.has_huffman_tables());
+    }
+
+    #[test]
+    fn test_estimated_quality() {
+        for quality in [25, 50, 75, 90, 95] {
+            let tables = JpegTables::parse(&tables_with_quality(quality)).unwrap();
+            let estimate = tables.estimated_quality().unwrap() as i32;
+            assert!(
+                (estimate - quality as i32).abs() <= 1,
+                "{quality}: {estimate}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_truncated() {
+        let data = tables_with_quality(75);
+        assert!(JpegTables::parse(&data[..20]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parsed_jpeg_tables() {
+        let (_, tiff) = open_tiff("image-tiff/tiled-jpeg-rgb-u8.tif").await;
+        let tables = tiff.ifds()[0].parsed_jpeg_tables().unwrap().unwrap();
+        assert!(tables.quantization_table(0).is_some());
+        assert!(tables.estimated_quality().is_some());
+    }
+}
@@ -2,32 +2,69 @@
 #![warn(missing_docs)]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![cfg_attr(not(test), deny(unused_crate_dependencies))]
+#![cfg_attr(
+    not(test),
+    deny(clippy::dbg_macro, clippy::print_stdout, clippy::print_stderr)
+)]
 #![doc(
     html_logo_url = "https://github.com/developmentseed.png",
     html_favicon_url = "https://github.com/developmentseed.png?size=32"
 )]
 
 mod array;
+mod band;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod cog;
+mod compression_stats;
+pub mod config;
 mod data_type;
+pub mod dataset;
 pub mod decoder;
 pub mod error;
+pub mod exif;
+pub mod extension;
+mod fetch;
 pub mod geo;
 mod ifd;
+pub mod jpeg_tables;
+mod lru;
+mod manifest;
 pub mod metadata;
 #[cfg(feature = "ndarray")]
 pub mod ndarray;
+mod nodata;
+#[cfg(feature = "ome")]
+pub mod ome;
 mod predictor;
+mod pyramid;
 pub mod reader;
+pub mod render;
+#[cfg(feature = "sgilog")]
+mod sgilog;
+pub mod stats;
+pub mod summary;
 mod tag_value;
 pub mod tags;
 #[cfg(test)]
 mod test;
 mod tiff;
 mod tile;
+mod window;
+pub mod writer;
+mod ycbcr;
+pub mod zarr;
 
-pub use array::{Array, TypedArray};
+pub use array::{Array, ArrayView, TypedArray};
+pub use band::RasterBand;
+pub use compression_stats::CompressionStats;
 pub use data_type::DataType;
-pub use ifd::{CompressedBytes, ImageFileDirectory, TileByteRange, TilesByteRanges};
+pub use fetch::FetchOptions;
+pub use ifd::{CompressedBytes, ImageFileDirectory, RawTag, TileByteRange, TilesByteRanges};
+pub use manifest::ChunkRecord;
+pub use nodata::Nodata;
+pub use pyramid::Pyramid;
 pub use tag_value::TagValue;
 pub use tiff::TIFF;
-pub use tile::Tile;
+pub use tile::{DecodeOptions, Tile};
+pub use window::Window;
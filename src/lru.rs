@@ -0,0 +1,135 @@
+use std::borrow::Borrow;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+/// A map that evicts its least recently used entries once their total weight exceeds a capacity,
+/// used by the caches of this crate.
+///
+/// Lookups, insertions and each eviction take constant time. The map isn't synchronized; callers
+/// guard it with a mutex to share it.
+pub(crate) struct Lru<K, V> {
+    entries: lru::LruCache<K, V>,
+    weigh: fn(&V) -> u64,
+    capacity: u64,
+    weight: u64,
+}
+
+impl<K: Hash + Eq, V> Lru<K, V> {
+    /// Create an empty map holding entries up to a total weight of `capacity`, where each entry
+    /// weighs `weigh(value)`.
+    pub(crate) fn new(capacity: u64, weigh: fn(&V) -> u64) -> Self {
+        Self {
+            entries: lru::LruCache::unbounded(),
+            weigh,
+            capacity,
+            weight: 0,
+        }
+    }
+
+    /// Create an empty map holding up to `capacity` entries.
+    pub(crate) fn with_len(capacity: usize) -> Self {
+        Self::new(capacity as u64, |_| 1)
+    }
+
+    /// The value for `key`, marking it as the most recently used.
+    pub(crate) fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.get(key)
+    }
+
+    /// Insert `value` as the most recently used entry, evicting the least recently used entries
+    /// until the map fits its capacity.
+    ///
+    /// A value weighing more than the whole capacity isn't inserted, as it would only evict
+    /// everything else.
+    pub(crate) fn insert(&mut self, key: K, value: V) {
+        let weight = (self.weigh)(&value);
+        if weight > self.capacity {
+            return;
+        }
+        self.weight += weight;
+        if let Some((_, old)) = self.entries.push(key, value) {
+            self.weight -= (self.weigh)(&old);
+        }
+        while self.weight > self.capacity {
+            let Some((_, evicted)) = self.entries.pop_lru() else {
+                break;
+            };
+            self.weight -= (self.weigh)(&evicted);
+        }
+    }
+
+    /// Remove the entry for `key`, returning its value.
+    pub(crate) fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let value = self.entries.pop(key)?;
+        self.weight -= (self.weigh)(&value);
+        Some(value)
+    }
+
+    /// Remove all entries.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.weight = 0;
+    }
+
+    /// The number of entries.
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// The total weight of all entries.
+    pub(crate) fn weight(&self) -> u64 {
+        self.weight
+    }
+}
+
+impl<K: Hash + Eq, V> Debug for Lru<K, V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Lru")
+            .field("len", &self.entries.len())
+            .field("weight", &self.weight)
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut lru = Lru::new(10, |value: &Vec<u8>| value.len() as u64);
+        lru.insert("a", vec![0; 4]);
+        lru.insert("b", vec![0; 4]);
+        // Using "a" makes "b" the least recently used
+        assert!(lru.get("a").is_some());
+        lru.insert("c", vec![0; 4]);
+        assert!(lru.get("b").is_none());
+        assert_eq!((lru.len(), lru.weight()), (2, 8));
+
+        // Replacing a value updates the weight, and values larger than the capacity are skipped
+        lru.insert("a", vec![0; 2]);
+        lru.insert("d", vec![0; 11]);
+        assert_eq!((lru.len(), lru.weight()), (2, 6));
+
+        assert_eq!(lru.remove("c").unwrap().len(), 4);
+        assert_eq!(lru.weight(), 2);
+        lru.clear();
+        assert_eq!((lru.len(), lru.weight()), (0, 0));
+
+        let mut lru = Lru::with_len(2);
+        for i in 0..5 {
+            lru.insert(i, ());
+        }
+        assert_eq!(lru.len(), 2);
+        assert!(lru.get(&3).is_some() && lru.get(&4).is_some());
+    }
+}
@@ -0,0 +1,379 @@
+use std::fmt::Write;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::reader::Endianness;
+use crate::tags::{Compression, PlanarConfiguration, Predictor};
+use crate::{DataType, ImageFileDirectory, Nodata, TIFF};
+
+/// The location and layout of one compressed tile or strip, as listed by
+/// [`ImageFileDirectory::chunk_records`].
+///
+/// This is everything a virtual dataset builder (e.g. for kerchunk or VirtualiZarr) needs to
+/// reference the chunk without reading it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkRecord {
+    /// The tile column, or 0 for strips.
+    pub x: usize,
+    /// The tile row, or strip index.
+    pub y: usize,
+    /// The band stored in this chunk for planar images, or `None` for chunky images, whose
+    /// chunks hold every band.
+    pub band: Option<usize>,
+    /// The byte offset of the compressed chunk. Zero for chunks that aren't stored.
+    pub offset: u64,
+    /// The byte length of the compressed chunk. Zero for chunks that aren't stored, which decode
+    /// to the fill value.
+    pub length: u64,
+    /// The compression of the chunk.
+    pub compression: Compression,
+    /// The data type of the decoded samples, if supported.
+    pub data_type: Option<DataType>,
+    /// The shape of the decoded chunk: `(height, width, bands)` for chunky and
+    /// `(1, height, width)` for planar images.
+    pub shape: [usize; 3],
+}
+
+/// The chunk layout shared by all chunks of an IFD.
+struct ChunkGrid {
+    chunk_width: usize,
+    chunk_height: usize,
+    x_count: usize,
+    y_count: usize,
+    planar: bool,
+}
+
+impl ChunkGrid {
+    fn from_ifd(ifd: &ImageFileDirectory) -> Option<(Self, &[u64], &[u64])> {
+        let planar = ifd.planar_configuration() == PlanarConfiguration::Planar;
+        if let (Some((x_count, y_count)), Some(offsets), Some(byte_counts)) =
+            (ifd.tile_count(), ifd.tile_offsets(), ifd.tile_byte_counts())
+        {
+            let grid = Self {
+                chunk_width: ifd.tile_width()? as usize,
+                chunk_height: ifd.tile_height()? as usize,
+                x_count,
+                y_count,
+                planar,
+            };
+            return Some((grid, offsets, byte_counts));
+        }
+        let grid = Self {
+            chunk_width: ifd.image_width() as usize,
+            chunk_height: ifd.strip_height() as usize,
+            x_count: 1,
+            y_count: ifd.strip_count()?,
+            planar,
+        };
+        Some((grid, ifd.strip_offsets()?, ifd.strip_byte_counts()?))
+    }
+
+    fn chunk_shape(&self, samples_per_pixel: usize) -> [usize; 3] {
+        if self.planar {
+            [1, self.chunk_height, self.chunk_width]
+        } else {
+            [self.chunk_height, self.chunk_width, samples_per_pixel]
+        }
+    }
+}
+
+pub(crate) fn chunk_records(ifd: &ImageFileDirectory) -> impl Iterator<Item = ChunkRecord> + '_ {
+    let (grid, offsets, byte_counts) = match ChunkGrid::from_ifd(ifd) {
+        Some((grid, offsets, byte_counts)) => (Some(grid), offsets, byte_counts),
+        None => (None, &[][..], &[][..]),
+    };
+    let samples_per_pixel = ifd.samples_per_pixel() as usize;
+    let bands = match &grid {
+        Some(grid) if grid.planar => samples_per_pixel,
+        _ => 1,
+    };
+    let chunks_per_band = grid.as_ref().map_or(0, |grid| grid.x_count * grid.y_count);
+    (0..bands * chunks_per_band).filter_map(move |index| {
+        let grid = grid.as_ref()?;
+        let chunk = index % chunks_per_band;
+        Some(ChunkRecord {
+            x: chunk % grid.x_count,
+            y: chunk / grid.x_count,
+            band: grid.planar.then_some(index / chunks_per_band),
+            offset: *offsets.get(index)?,
+            length: *byte_counts.get(index)?,
+            compression: ifd.compression(),
+            data_type: ifd.data_type(),
+            shape: grid.chunk_shape(samples_per_pixel),
+        })
+    })
+}
+
+/// The shape of an IFD as a Zarr array, shared by the kerchunk and Zarr v3 translations.
+#[derive(Debug, Clone)]
+pub(crate) struct ArrayLayout {
+    pub(crate) data_type: DataType,
+    pub(crate) shape: Vec<usize>,
+    pub(crate) chunks: Vec<usize>,
+    pub(crate) dimensions: Vec<&'static str>,
+}
+
+impl ArrayLayout {
+    /// The layout of `ifd`, or an error created by `unsupported` if its chunks can't be read as
+    /// a regular grid of Zarr chunks without decoding.
+    ///
+    /// Single-band images are 2D, others have a band axis: `(y, x, band)` for chunky and
+    /// `(band, y, x)` for planar images.
+    pub(crate) fn from_ifd(
+        ifd: &ImageFileDirectory,
+        unsupported: impl Fn(String) -> AsyncTiffError,
+    ) -> AsyncTiffResult<Self> {
+        let (grid, _, _) =
+            ChunkGrid::from_ifd(ifd).ok_or(unsupported("no chunk offsets".to_string()))?;
+        let data_type = ifd
+            .data_type()
+            .filter(|data_type| *data_type != DataType::Bool)
+            .ok_or(unsupported(format!(
+                "bits per sample {:?}",
+                ifd.bits_per_sample()
+            )))?;
+        if !matches!(ifd.predictor(), None | Some(Predictor::None)) {
+            return Err(unsupported(format!("predictor {:?}", ifd.predictor())));
+        }
+        let height = ifd.image_height() as usize;
+        if ifd.tile_offsets().is_none() && !height.is_multiple_of(grid.chunk_height) {
+            return Err(unsupported(
+                "the last strip is shorter than the others".to_string(),
+            ));
+        }
+
+        let samples_per_pixel = ifd.samples_per_pixel() as usize;
+        let width = ifd.image_width() as usize;
+        let (shape, chunks, dimensions) = if samples_per_pixel == 1 {
+            (
+                vec![height, width],
+                vec![grid.chunk_height, grid.chunk_width],
+                vec!["y", "x"],
+            )
+        } else if grid.planar {
+            (
+                vec![samples_per_pixel, height, width],
+                vec![1, grid.chunk_height, grid.chunk_width],
+                vec!["band", "y", "x"],
+            )
+        } else {
+            (
+                vec![height, width, samples_per_pixel],
+                vec![grid.chunk_height, grid.chunk_width, samples_per_pixel],
+                vec!["y", "x", "band"],
+            )
+        };
+        Ok(Self {
+            data_type,
+            shape,
+            chunks,
+            dimensions,
+        })
+    }
+
+    /// The index of `record` in the chunk grid, along each dimension.
+    pub(crate) fn chunk_coords(&self, record: &ChunkRecord) -> impl Iterator<Item = usize> {
+        let coords = match (self.shape.len(), record.band) {
+            (2, _) => vec![record.y, record.x],
+            (_, Some(band)) => vec![band, record.y, record.x],
+            (_, None) => vec![record.y, record.x, 0],
+        };
+        coords.into_iter()
+    }
+}
+
+/// The JSON value of a Zarr fill value, using the strings Zarr defines for non-finite floats.
+pub(crate) fn json_fill_value(nodata: Option<Nodata>) -> Option<String> {
+    let nodata = nodata?;
+    let value = match nodata {
+        _ if nodata.is_nan() => r#""NaN""#.to_string(),
+        Nodata::Float32(value) if value.is_infinite() => infinity_json(value > 0.0),
+        Nodata::Float64(value) if value.is_infinite() => infinity_json(value > 0.0),
+        Nodata::Bool(value) => value.to_string(),
+        Nodata::UInt8(value) => value.to_string(),
+        Nodata::UInt16(value) => value.to_string(),
+        Nodata::UInt32(value) => value.to_string(),
+        Nodata::UInt64(value) => value.to_string(),
+        Nodata::Int8(value) => value.to_string(),
+        Nodata::Int16(value) => value.to_string(),
+        Nodata::Int32(value) => value.to_string(),
+        Nodata::Int64(value) => value.to_string(),
+        Nodata::Float32(value) => value.to_string(),
+        Nodata::Float64(value) => value.to_string(),
+    };
+    Some(value)
+}
+
+fn infinity_json(positive: bool) -> String {
+    match positive {
+        true => r#""Infinity""#.to_string(),
+        false => r#""-Infinity""#.to_string(),
+    }
+}
+
+/// Serialize the chunk manifest of every IFD of `tiff` as kerchunk references.
+pub(crate) fn to_kerchunk_json(tiff: &TIFF, url: &str) -> AsyncTiffResult<String> {
+    let mut refs = vec![(".zgroup".to_string(), json_string(r#"{"zarr_format":2}"#))];
+    for (index, ifd) in tiff.ifds().iter().enumerate() {
+        let unsupported = |what: String| {
+            AsyncTiffError::General(format!(
+                "IFD {index} can't be referenced by kerchunk: {what}"
+            ))
+        };
+        let layout = ArrayLayout::from_ifd(ifd, unsupported)?;
+        let compressor = match ifd.compression() {
+            Compression::None => "null",
+            Compression::Deflate | Compression::OldDeflate => r#"{"id":"zlib"}"#,
+            Compression::ZSTD => r#"{"id":"zstd"}"#,
+            Compression::LZW => r#"{"id":"imagecodecs_lzw"}"#,
+            compression => return Err(unsupported(format!("compression {compression:?}"))),
+        };
+        let data_type = layout.data_type;
+        let (shape, chunks) = (&layout.shape, &layout.chunks);
+        let dimensions = layout
+            .dimensions
+            .iter()
+            .map(|dimension| json_string(dimension))
+            .collect::<Vec<_>>()
+            .join(",");
+        let fill_value =
+            json_fill_value(ifd.nodata_for_dtype(data_type)).unwrap_or_else(|| "null".to_string());
+        let zarray = format!(
+            r#"{{"chunks":{chunks:?},"compressor":{compressor},"dtype":"{}","fill_value":{fill_value},"filters":null,"order":"C","shape":{shape:?},"zarr_format":2}}"#,
+            numpy_dtype(data_type, tiff.endianness())
+        );
+        refs.push((format!("{index}/.zarray"), json_string(&zarray)));
+        refs.push((
+            format!("{index}/.zattrs"),
+            json_string(&format!(r#"{{"_ARRAY_DIMENSIONS":[{dimensions}]}}"#)),
+        ));
+
+        for record in chunk_records(ifd).filter(|record| record.length > 0) {
+            let coords = layout.chunk_coords(&record).map(|i| i.to_string());
+            let key = format!("{index}/{}", coords.collect::<Vec<_>>().join("."));
+            let value = format!("[{},{},{}]", json_string(url), record.offset, record.length);
+            refs.push((key, value));
+        }
+    }
+
+    let mut json = r#"{"version":1,"refs":{"#.to_string();
+    for (i, (key, value)) in refs.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        write!(json, "{}:{value}", json_string(key)).unwrap();
+    }
+    json.push_str("}}");
+    Ok(json)
+}
+
+/// The numpy dtype string of `data_type`, e.g. `<u2`.
+fn numpy_dtype(data_type: DataType, endianness: Endianness) -> String {
+    let (kind, size) = match data_type {
+        DataType::Bool | DataType::UInt8 => ("u", 1),
+        DataType::UInt16 => ("u", 2),
+        DataType::UInt32 => ("u", 4),
+        DataType::UInt64 => ("u", 8),
+        DataType::Int8 => ("i", 1),
+        DataType::Int16 => ("i", 2),
+        DataType::Int32 => ("i", 4),
+        DataType::Int64 => ("i", 8),
+        DataType::Float32 => ("f", 4),
+        DataType::Float64 => ("f", 8),
+    };
+    let byte_order = match (size, endianness) {
+        (1, _) => '|',
+        (_, Endianness::LittleEndian) => '<',
+        (_, Endianness::BigEndian) => '>',
+    };
+    format!("{byte_order}{kind}{size}")
+}
+
+/// Quote and escape `value` as a JSON string.
+pub(crate) fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::util::open_tiff;
+
+    #[tokio::test]
+    async fn test_chunk_records() {
+        let (_, tiff) = open_tiff("image-tiff/tiled-rgb-u8.tif").await;
+        let ifd = &tiff.ifds()[0];
+        let records = ifd.chunk_records().collect::<Vec<_>>();
+        let (x_count, y_count) = ifd.tile_count().unwrap();
+        assert_eq!(records.len(), x_count * y_count);
+        let last = records.last().unwrap();
+        assert_eq!(
+            (last.x, last.y, last.band),
+            (x_count - 1, y_count - 1, None)
+        );
+        let crate::TileByteRange::Chunky(range) = ifd.tile_byte_range(last.x, last.y).unwrap()
+        else {
+            panic!("expected a chunky tile");
+        };
+        assert_eq!(range, last.offset..last.offset + last.length);
+        assert_eq!(last.data_type, Some(DataType::UInt8));
+        let (tile_width, tile_height) = (ifd.tile_width().unwrap(), ifd.tile_height().unwrap());
+        assert_eq!(last.shape, [tile_height as usize, tile_width as usize, 3]);
+
+        let (_, tiff) = open_tiff("image-tiff/planar-rgb-u8.tif").await;
+        let ifd = &tiff.ifds()[0];
+        let records = ifd.chunk_records().collect::<Vec<_>>();
+        let strip_count = ifd.strip_count().unwrap();
+        assert_eq!(records.len(), 3 * strip_count);
+        assert_eq!(records[strip_count].band, Some(1));
+        assert_eq!(records[strip_count].shape[0], 1);
+
+        let manifest = tiff.chunk_manifest().collect::<Vec<_>>();
+        assert_eq!(manifest.len(), tiff.ifds().len());
+    }
+
+    #[cfg(feature = "transcode")]
+    #[tokio::test]
+    async fn test_kerchunk_json() {
+        use crate::test::util::{open_tiff_path, temp_path};
+        use crate::writer::{transcode, TranscodeOptions};
+
+        // Predictors have no standard Zarr codec
+        let (reader, tiff) = open_tiff("image-tiff/tiled-rgb-u8.tif").await;
+        assert!(tiff.to_kerchunk_json("a.tif").is_err());
+
+        let path = temp_path("kerchunk.tif");
+        let options = TranscodeOptions::default().with_tile_size(16);
+        let file = std::fs::File::create(&path).unwrap();
+        transcode(&tiff, reader.as_ref(), &options, file)
+            .await
+            .unwrap();
+        let (_, tiff) = open_tiff_path(&path).await;
+        std::fs::remove_file(path).unwrap();
+        let ifd = &tiff.ifds()[0];
+        let json = tiff.to_kerchunk_json("s3://bucket/\"a\".tif").unwrap();
+        assert!(json.starts_with(r#"{"version":1,"refs":{".zgroup":"{\"zarr_format\":2}","#));
+        assert!(json.contains(r#"\"dtype\":\"|u1\""#));
+        assert!(json.contains(r#"\"compressor\":{\"id\":\"zlib\"}"#));
+        assert!(json.contains(r#"\"_ARRAY_DIMENSIONS\":[\"y\",\"x\",\"band\"]"#));
+        let first = ifd.chunk_records().next().unwrap();
+        assert!(json.contains(&format!(
+            r#""0/0.0.0":["s3://bucket/\"a\".tif",{},{}]"#,
+            first.offset, first.length
+        )));
+        assert!(json.ends_with("}}"));
+    }
+}
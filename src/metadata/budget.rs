@@ -0,0 +1,112 @@
+use std::ops::Range;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::metadata::MetadataFetch;
+
+/// A [`MetadataFetch`] wrapper that allows at most a fixed number of requests to the inner
+/// fetcher.
+///
+/// Once the budget is used up, further fetches fail with
+/// [`AsyncTiffError::RequestBudgetExceeded`], which lists every byte range requested so far.
+///
+/// This should wrap the raw reader, *underneath* any caching layer such as
+/// [`ReadaheadMetadataCache`][crate::metadata::cache::ReadaheadMetadataCache], so that only the
+/// requests that actually reach the data source are counted.
+#[derive(Debug)]
+pub struct RequestBudget<F: MetadataFetch> {
+    inner: F,
+    budget: usize,
+    requests: Mutex<Vec<Range<u64>>>,
+}
+
+impl<F: MetadataFetch> RequestBudget<F> {
+    /// Create a new RequestBudget allowing at most `budget` requests to `inner`.
+    pub fn new(inner: F, budget: usize) -> Self {
+        Self {
+            inner,
+            budget,
+            requests: Mutex::new(vec![]),
+        }
+    }
+
+    /// Access the inner MetadataFetch
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+
+    /// The maximum number of requests allowed.
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// The byte ranges requested so far, in order. Requests rejected for exceeding the budget are
+    /// included.
+    pub fn requests(&self) -> Vec<Range<u64>> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl<F: MetadataFetch> MetadataFetch for RequestBudget<F> {
+    async fn fetch(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+        {
+            let mut requests = self.requests.lock().unwrap();
+            requests.push(range.clone());
+            if requests.len() > self.budget {
+                return Err(AsyncTiffError::RequestBudgetExceeded {
+                    budget: self.budget,
+                    ranges: requests.clone(),
+                });
+            }
+        }
+        self.inner.fetch(range).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use object_store::local::LocalFileSystem;
+
+    use super::*;
+    use crate::metadata::cache::ReadaheadMetadataCache;
+    use crate::metadata::TiffMetadataReader;
+    use crate::reader::ObjectReader;
+
+    fn reader(filename: &str) -> ObjectReader {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let store = Arc::new(LocalFileSystem::new_with_prefix(&manifest_dir).unwrap());
+        ObjectReader::new(store, format!("fixtures/image-tiff/{filename}").into())
+    }
+
+    #[tokio::test]
+    async fn test_request_budget() {
+        let fetch =
+            ReadaheadMetadataCache::new(RequestBudget::new(reader("tiled-jpeg-ycbcr.tif"), 1));
+        let mut metadata_reader = TiffMetadataReader::try_open(&fetch).await.unwrap();
+        metadata_reader.read_all_ifds(&fetch).await.unwrap();
+        assert_eq!(fetch.inner().requests(), vec![0..32 * 1024]);
+    }
+
+    #[tokio::test]
+    async fn test_request_budget_exceeded() {
+        let fetch =
+            ReadaheadMetadataCache::new(RequestBudget::new(reader("tiled-jpeg-ycbcr.tif"), 1))
+                .with_initial_size(8);
+        let err = match TiffMetadataReader::try_open(&fetch).await {
+            Ok(mut metadata_reader) => metadata_reader.read_all_ifds(&fetch).await.unwrap_err(),
+            Err(err) => err,
+        };
+        let AsyncTiffError::RequestBudgetExceeded { budget, ranges } = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(budget, 1);
+        assert_eq!(ranges.len(), 2);
+    }
+}
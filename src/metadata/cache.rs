@@ -1,5 +1,6 @@
 //! Caching strategies for metadata fetching.
 
+use std::fmt::Debug;
 use std::ops::Range;
 use std::sync::Arc;
 
@@ -96,30 +97,49 @@ impl SequentialBlockCache {
     }
 }
 
-/// A MetadataFetch implementation that caches fetched data in exponentially growing chunks,
-/// sequentially from the beginning of the file.
-#[derive(Debug)]
-pub struct ReadaheadMetadataCache<F: MetadataFetch> {
-    inner: F,
-    cache: Arc<Mutex<SequentialBlockCache>>,
+/// A policy for how [`PrefetchCache`] fetches metadata from the underlying source.
+///
+/// The cache keeps a buffer that is contiguous from the start of the file. Whenever a request
+/// isn't covered by that buffer, it's extended by [`next_size`][Self::next_size] bytes (or more,
+/// if the request needs it). Separately, any [`extra_ranges`][Self::extra_ranges] are fetched on
+/// first use, which allows e.g. fetching both the header and a footer of a file whose metadata is
+/// stored at the end.
+pub trait PrefetchStrategy: Debug + Send + Sync + 'static {
+    /// The number of bytes to fetch from the start of the file on the first request.
+    fn initial_size(&self) -> u64;
+
+    /// The number of bytes to fetch on a cache miss, given that the first `cached_len` bytes of
+    /// the file are already cached.
+    fn next_size(&self, cached_len: u64) -> u64;
+
+    /// Absolute byte ranges to fetch alongside the first request and cache for the lifetime of
+    /// the cache. Defaults to none.
+    fn extra_ranges(&self) -> Vec<Range<u64>> {
+        vec![]
+    }
+}
+
+/// A [`PrefetchStrategy`] that fetches an initial block and then grows subsequent fetches
+/// exponentially.
+#[derive(Debug, Clone)]
+pub struct ReadaheadStrategy {
     initial: u64,
     multiplier: f64,
 }
 
-impl<F: MetadataFetch> ReadaheadMetadataCache<F> {
-    /// Create a new ReadaheadMetadataCache wrapping the given MetadataFetch
-    pub fn new(inner: F) -> Self {
+impl Default for ReadaheadStrategy {
+    fn default() -> Self {
         Self {
-            inner,
-            cache: Arc::new(Mutex::new(SequentialBlockCache::new())),
             initial: 32 * 1024,
             multiplier: 2.0,
         }
     }
+}
 
-    /// Access the inner MetadataFetch
-    pub fn inner(&self) -> &F {
-        &self.inner
+impl ReadaheadStrategy {
+    /// Create a new strategy with an initial size of 32 KiB and a multiplier of 2.0
+    pub fn new() -> Self {
+        Self::default()
     }
 
     /// Set the initial fetch size in bytes, otherwise defaults to 32 KiB
@@ -133,40 +153,152 @@ impl<F: MetadataFetch> ReadaheadMetadataCache<F> {
         self.multiplier = multiplier;
         self
     }
+}
 
-    fn next_fetch_size(&self, existing_len: u64) -> u64 {
-        if existing_len == 0 {
-            self.initial
-        } else {
-            (existing_len as f64 * self.multiplier).round() as u64
+impl PrefetchStrategy for ReadaheadStrategy {
+    fn initial_size(&self) -> u64 {
+        self.initial
+    }
+
+    fn next_size(&self, cached_len: u64) -> u64 {
+        (cached_len as f64 * self.multiplier).round() as u64
+    }
+}
+
+/// A [`PrefetchStrategy`] that fetches a fixed-size buffer up front, and afterwards only the
+/// bytes each request needs.
+#[derive(Debug, Clone)]
+pub struct PrefetchBufferStrategy {
+    size: u64,
+}
+
+impl PrefetchBufferStrategy {
+    /// Create a new strategy fetching `size` bytes on the first request
+    pub fn new(size: u64) -> Self {
+        Self { size }
+    }
+}
+
+impl PrefetchStrategy for PrefetchBufferStrategy {
+    fn initial_size(&self) -> u64 {
+        self.size
+    }
+
+    fn next_size(&self, _cached_len: u64) -> u64 {
+        0
+    }
+}
+
+#[derive(Debug)]
+struct PrefetchState {
+    sequential: SequentialBlockCache,
+    /// The fetched [`PrefetchStrategy::extra_ranges`], or `None` before the first fetch
+    extra: Option<Vec<(Range<u64>, Bytes)>>,
+}
+
+/// A MetadataFetch implementation that caches fetched data according to a [`PrefetchStrategy`].
+#[derive(Debug)]
+pub struct PrefetchCache<F: MetadataFetch, S: PrefetchStrategy> {
+    inner: F,
+    strategy: S,
+    state: Arc<Mutex<PrefetchState>>,
+}
+
+/// A MetadataFetch implementation that caches fetched data in exponentially growing chunks,
+/// sequentially from the beginning of the file.
+pub type ReadaheadMetadataCache<F> = PrefetchCache<F, ReadaheadStrategy>;
+
+/// A MetadataFetch implementation that fetches a fixed-size buffer from the beginning of the
+/// file, extending it only as far as later requests need.
+pub type PrefetchBuffer<F> = PrefetchCache<F, PrefetchBufferStrategy>;
+
+impl<F: MetadataFetch, S: PrefetchStrategy> PrefetchCache<F, S> {
+    /// Create a new cache wrapping the given MetadataFetch, using a custom strategy
+    pub fn with_strategy(inner: F, strategy: S) -> Self {
+        Self {
+            inner,
+            strategy,
+            state: Arc::new(Mutex::new(PrefetchState {
+                sequential: SequentialBlockCache::new(),
+                extra: None,
+            })),
         }
     }
+
+    /// Access the inner MetadataFetch
+    pub fn inner(&self) -> &F {
+        &self.inner
+    }
+
+    /// Access the prefetch strategy
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+}
+
+impl<F: MetadataFetch> PrefetchCache<F, ReadaheadStrategy> {
+    /// Create a new ReadaheadMetadataCache wrapping the given MetadataFetch
+    pub fn new(inner: F) -> Self {
+        Self::with_strategy(inner, ReadaheadStrategy::default())
+    }
+
+    /// Set the initial fetch size in bytes, otherwise defaults to 32 KiB
+    pub fn with_initial_size(mut self, initial: u64) -> Self {
+        self.strategy = self.strategy.with_initial_size(initial);
+        self
+    }
+
+    /// Set the multiplier for subsequent fetch sizes, otherwise defaults to 2.0
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.strategy = self.strategy.with_multiplier(multiplier);
+        self
+    }
 }
 
 #[async_trait]
-impl<F: MetadataFetch + Send + Sync> MetadataFetch for ReadaheadMetadataCache<F> {
+impl<F: MetadataFetch + Send + Sync, S: PrefetchStrategy> MetadataFetch for PrefetchCache<F, S> {
     async fn fetch(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
-        let mut cache = self.cache.lock().await;
+        let mut state = self.state.lock().await;
+
+        if state.extra.is_none() {
+            let mut extra = vec![];
+            for extra_range in self.strategy.extra_ranges() {
+                let bytes = self.inner.fetch(extra_range.clone()).await?;
+                extra.push((extra_range, bytes));
+            }
+            state.extra = Some(extra);
+        }
 
         // First check if we already have the range cached
-        if cache.contains(range.start..range.end) {
-            return Ok(cache.slice(range));
+        if state.sequential.contains(range.start..range.end) {
+            return Ok(state.sequential.slice(range));
+        }
+        for (extra_range, bytes) in state.extra.iter().flatten() {
+            let start = range.start.wrapping_sub(extra_range.start);
+            let end = range.end.wrapping_sub(extra_range.start);
+            if extra_range.start <= range.start && end <= bytes.len() as u64 {
+                return Ok(bytes.slice(start as usize..end as usize));
+            }
         }
 
         // Compute the correct fetch range
-        let start_len = cache.len;
+        let start_len = state.sequential.len;
         let needed = range.end.saturating_sub(start_len);
-        let fetch_size = self.next_fetch_size(start_len).max(needed);
-        let fetch_range = start_len..start_len + fetch_size;
+        let next_size = if start_len == 0 {
+            self.strategy.initial_size()
+        } else {
+            self.strategy.next_size(start_len)
+        };
+        let fetch_range = start_len..start_len + next_size.max(needed);
 
         // Perform the fetch while holding mutex
         // (this is OK because the mutex is async)
         let bytes = self.inner.fetch(fetch_range).await?;
 
         // Now append safely
-        cache.append_buffer(bytes);
+        state.sequential.append_buffer(bytes);
 
-        Ok(cache.slice(range))
+        Ok(state.sequential.slice(range))
     }
 }
 
@@ -239,6 +371,68 @@ mod test {
         assert_eq!(*cache.inner.num_fetches.lock().await, 3);
     }
 
+    #[tokio::test]
+    async fn test_prefetch_buffer() {
+        let data = Bytes::from_static(b"abcdefghijklmnopqrstuvwxyz");
+        let fetch = TestFetch::new(data.clone());
+        let cache = PrefetchBuffer::with_strategy(fetch, PrefetchBufferStrategy::new(4));
+
+        let result = cache.fetch(0..2).await.unwrap();
+        assert_eq!(result.as_ref(), b"ab");
+        assert_eq!(*cache.inner.num_fetches.lock().await, 1);
+
+        // A miss only fetches up to the end of the request
+        let result = cache.fetch(3..6).await.unwrap();
+        assert_eq!(result.as_ref(), b"def");
+        assert_eq!(*cache.inner.num_fetches.lock().await, 2);
+        let result = cache.fetch(6..7).await.unwrap();
+        assert_eq!(result.as_ref(), b"g");
+        assert_eq!(*cache.inner.num_fetches.lock().await, 3);
+    }
+
+    #[derive(Debug)]
+    struct HeaderAndFooter {
+        file_len: u64,
+    }
+
+    impl PrefetchStrategy for HeaderAndFooter {
+        fn initial_size(&self) -> u64 {
+            4
+        }
+
+        fn next_size(&self, cached_len: u64) -> u64 {
+            cached_len
+        }
+
+        fn extra_ranges(&self) -> Vec<Range<u64>> {
+            vec![10..14, self.file_len - 4..self.file_len]
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_strategy() {
+        let data = Bytes::from_static(b"abcdefghijklmnopqrstuvwxyz");
+        let fetch = TestFetch::new(data.clone());
+        let cache = PrefetchCache::with_strategy(fetch, HeaderAndFooter { file_len: 26 });
+
+        // The first request fetches the extra ranges and the header
+        let result = cache.fetch(0..2).await.unwrap();
+        assert_eq!(result.as_ref(), b"ab");
+        assert_eq!(*cache.inner.num_fetches.lock().await, 3);
+
+        // Requests within the extra ranges are served from the cache
+        let result = cache.fetch(23..26).await.unwrap();
+        assert_eq!(result.as_ref(), b"xyz");
+        let result = cache.fetch(11..13).await.unwrap();
+        assert_eq!(result.as_ref(), b"lm");
+        assert_eq!(*cache.inner.num_fetches.lock().await, 3);
+
+        // Requests spilling past an extra range fall back to sequential fetching
+        let result = cache.fetch(12..16).await.unwrap();
+        assert_eq!(result.as_ref(), b"mnop");
+        assert_eq!(*cache.inner.num_fetches.lock().await, 4);
+    }
+
     #[test]
     fn test_sequential_block_cache_empty_buffers() {
         let mut cache = SequentialBlockCache::new();
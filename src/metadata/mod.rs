@@ -56,10 +56,44 @@
 //! middleware when reading metadata. [`ReadaheadMetadataCache`][cache::ReadaheadMetadataCache] is
 //! an example of this, which fetches the first `N` bytes out of a file, and then multiplies the
 //! size of any subsequent fetches by a given `multiplier`.
+//!
+//! Custom policies can be plugged in by implementing
+//! [`PrefetchStrategy`][cache::PrefetchStrategy] and wrapping the source in a
+//! [`PrefetchCache`][cache::PrefetchCache].
+//!
+//! ### Limiting the number of requests
+//!
+//! Wrapping the underlying reader in a [`RequestBudget`] makes opening fail fast with
+//! [`AsyncTiffError::RequestBudgetExceeded`][crate::error::AsyncTiffError::RequestBudgetExceeded]
+//! when the file layout requires more byte-range requests than allowed. Combined with a large
+//! enough prefetch, this verifies that a well-formed COG can be opened in a single request:
+//!
+//! ```
+//! # tokio_test::block_on(async {
+//! # use std::env::current_dir;
+//! # use std::sync::Arc;
+//! # use object_store::local::LocalFileSystem;
+//! use async_tiff::metadata::{RequestBudget, TiffMetadataReader};
+//! use async_tiff::metadata::cache::ReadaheadMetadataCache;
+//! use async_tiff::reader::ObjectReader;
+//!
+//! # let store = Arc::new(LocalFileSystem::new_with_prefix(current_dir().unwrap()).unwrap());
+//! let reader = ObjectReader::new(store, "fixtures/image-tiff/tiled-jpeg-ycbcr.tif".into());
+//! let fetch = ReadaheadMetadataCache::new(RequestBudget::new(reader, 1));
+//!
+//! let mut metadata_reader = TiffMetadataReader::try_open(&fetch).await.unwrap();
+//! let ifds = metadata_reader.read_all_ifds(&fetch).await.unwrap();
+//! assert_eq!(fetch.inner().requests().len(), 1);
+//! # })
+//! ```
 
+mod budget;
 pub mod cache;
 mod fetch;
+mod options;
 mod reader;
 
+pub use budget::RequestBudget;
 pub use fetch::MetadataFetch;
-pub use reader::{ImageFileDirectoryReader, TiffMetadataReader};
+pub use options::{Limits, ParseOptions};
+pub use reader::{IfdError, ImageFileDirectoryReader, TiffMetadataReader};
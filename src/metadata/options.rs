@@ -0,0 +1,205 @@
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::extension::ExtensionRegistry;
+
+/// Options controlling how strictly IFD metadata is parsed.
+///
+/// By default parsing is strict, and any invalid tag fails the whole IFD. Many files in the wild
+/// have minor defects that don't prevent reading their image data, so in lenient mode such
+/// problems are collected as warnings instead, which can be inspected with
+/// [`ImageFileDirectory::parse_warnings`][crate::ImageFileDirectory::parse_warnings].
+///
+/// The options also hold the [`ExtensionRegistry`] used to parse the tags of TIFF extensions, the
+/// [`Limits`] that protect against hostile or corrupt files, and whether the private EXIF and GPS
+/// IFDs are read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseOptions {
+    strict: bool,
+    extensions: ExtensionRegistry,
+    limits: Limits,
+    private_ifds: bool,
+}
+
+impl ParseOptions {
+    /// Create strict parse options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create lenient parse options.
+    pub fn lenient() -> Self {
+        Self::default().with_strict(false)
+    }
+
+    /// Set whether parsing fails on the first invalid tag.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Whether parsing fails on the first invalid tag.
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Set the extensions that parse their tags. Defaults to
+    /// [`ExtensionRegistry::default`].
+    pub fn with_extensions(mut self, extensions: ExtensionRegistry) -> Self {
+        self.extensions = extensions;
+        self
+    }
+
+    /// The extensions that parse their tags.
+    pub fn extensions(&self) -> &ExtensionRegistry {
+        &self.extensions
+    }
+
+    /// Set the limits on the size of metadata. Defaults to [`Limits::default`].
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The limits on the size of metadata.
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    /// Set whether the EXIF and GPS IFDs referenced by an IFD are read along with it. Defaults to
+    /// `false`, as each of them costs an extra fetch.
+    pub fn with_private_ifds(mut self, private_ifds: bool) -> Self {
+        self.private_ifds = private_ifds;
+        self
+    }
+
+    /// Whether the EXIF and GPS IFDs referenced by an IFD are read along with it.
+    pub fn private_ifds(&self) -> bool {
+        self.private_ifds
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            strict: true,
+            extensions: ExtensionRegistry::default(),
+            limits: Limits::default(),
+            private_ifds: false,
+        }
+    }
+}
+
+/// Limits on the sizes claimed by a file, which prevent hostile or corrupt files from
+/// allocating unbounded memory.
+///
+/// Counts and sizes in TIFF metadata are trusted when allocating buffers, so a few corrupt bytes
+/// can claim billions of tag values or a multi-gigabyte strip. The metadata limits are enforced
+/// while reading IFDs through [`ParseOptions::with_limits`], and the decoded chunk size when
+/// decoding through [`DecoderRegistry::with_limits`][crate::decoder::DecoderRegistry::with_limits].
+/// Exceeding a limit fails with [`AsyncTiffError::LimitExceeded`].
+///
+/// The defaults are generous enough for any reasonable file, including large Cloud-Optimized
+/// GeoTIFFs. Use [`unlimited`][Self::unlimited] to turn all checks off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Limits {
+    max_tag_count: u64,
+    max_tag_value_bytes: u64,
+    max_chunk_count: u64,
+    max_decoded_chunk_bytes: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_tag_count: u16::MAX as u64,
+            max_tag_value_bytes: 256 * 1024 * 1024,
+            max_chunk_count: 16 * 1024 * 1024,
+            max_decoded_chunk_bytes: 1024 * 1024 * 1024,
+        }
+    }
+}
+
+impl Limits {
+    /// Create the default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create limits that allow everything.
+    pub fn unlimited() -> Self {
+        Self {
+            max_tag_count: u64::MAX,
+            max_tag_value_bytes: u64::MAX,
+            max_chunk_count: u64::MAX,
+            max_decoded_chunk_bytes: u64::MAX,
+        }
+    }
+
+    /// Set the maximum number of entries in one IFD, otherwise defaults to 65535, the most a
+    /// classic TIFF can hold.
+    pub fn with_max_tag_count(mut self, max: u64) -> Self {
+        self.max_tag_count = max;
+        self
+    }
+
+    /// Set the maximum size in bytes of the values of one tag, otherwise defaults to 256 MiB.
+    pub fn with_max_tag_value_bytes(mut self, max: u64) -> Self {
+        self.max_tag_value_bytes = max;
+        self
+    }
+
+    /// Set the maximum number of tiles or strips in one IFD, counting every band of planar
+    /// images, otherwise defaults to 16M.
+    pub fn with_max_chunk_count(mut self, max: u64) -> Self {
+        self.max_chunk_count = max;
+        self
+    }
+
+    /// Set the maximum size in bytes of one decoded tile or strip, otherwise defaults to 1 GiB.
+    pub fn with_max_decoded_chunk_bytes(mut self, max: u64) -> Self {
+        self.max_decoded_chunk_bytes = max;
+        self
+    }
+
+    /// The maximum number of entries in one IFD.
+    pub fn max_tag_count(&self) -> u64 {
+        self.max_tag_count
+    }
+
+    /// The maximum size in bytes of the values of one tag.
+    pub fn max_tag_value_bytes(&self) -> u64 {
+        self.max_tag_value_bytes
+    }
+
+    /// The maximum number of tiles or strips in one IFD.
+    pub fn max_chunk_count(&self) -> u64 {
+        self.max_chunk_count
+    }
+
+    /// The maximum size in bytes of one decoded tile or strip.
+    pub fn max_decoded_chunk_bytes(&self) -> u64 {
+        self.max_decoded_chunk_bytes
+    }
+
+    pub(crate) fn check_tag_count(&self, count: u64) -> AsyncTiffResult<()> {
+        check("tag count", count, self.max_tag_count)
+    }
+
+    pub(crate) fn check_tag_value_bytes(&self, bytes: u64) -> AsyncTiffResult<()> {
+        check("tag value bytes", bytes, self.max_tag_value_bytes)
+    }
+
+    pub(crate) fn check_chunk_count(&self, count: u64) -> AsyncTiffResult<()> {
+        check("chunk count", count, self.max_chunk_count)
+    }
+
+    pub(crate) fn check_decoded_chunk_bytes(&self, bytes: u64) -> AsyncTiffResult<()> {
+        check("decoded chunk bytes", bytes, self.max_decoded_chunk_bytes)
+    }
+}
+
+fn check(limit: &'static str, actual: u64, max: u64) -> AsyncTiffResult<()> {
+    if actual > max {
+        return Err(AsyncTiffError::LimitExceeded { limit, actual, max });
+    }
+    Ok(())
+}
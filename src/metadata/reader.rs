@@ -1,15 +1,17 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io::Read;
 
 use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
 
-use crate::error::{AsyncTiffError, AsyncTiffResult, TiffError, TiffFormatError};
+use crate::error::{AsyncTiffError, AsyncTiffResult, TiffError, TiffFormatError, TiffResult};
+use crate::exif::{Exif, Gps};
 use crate::metadata::fetch::MetadataCursor;
-use crate::metadata::MetadataFetch;
+use crate::metadata::{Limits, MetadataFetch, ParseOptions};
 use crate::reader::Endianness;
 use crate::tag_value::TagValue;
 use crate::tags::{Tag, Type};
-use crate::{ImageFileDirectory, TIFF};
+use crate::{ImageFileDirectory, RawTag, TIFF};
 
 /// Entry point to reading TIFF metadata.
 ///
@@ -24,6 +26,7 @@ pub struct TiffMetadataReader {
     endianness: Endianness,
     bigtiff: bool,
     next_ifd_offset: Option<u64>,
+    parse_options: ParseOptions,
 }
 
 impl TiffMetadataReader {
@@ -31,6 +34,10 @@ impl TiffMetadataReader {
     /// the bigtiff flag.
     ///
     /// This does not read any IFD metadata.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "open_metadata", level = "debug", skip_all)
+    )]
     pub async fn try_open<F: MetadataFetch>(fetch: &F) -> AsyncTiffResult<Self> {
         let magic_bytes = fetch.fetch(0..2).await?;
 
@@ -79,9 +86,16 @@ impl TiffMetadataReader {
             endianness,
             bigtiff,
             next_ifd_offset: Some(first_ifd_location),
+            parse_options: ParseOptions::default(),
         })
     }
 
+    /// Set how strictly the IFDs read by this reader are parsed.
+    pub fn with_parse_options(mut self, parse_options: ParseOptions) -> Self {
+        self.parse_options = parse_options;
+        self
+    }
+
     /// Returns the endianness of the file.
     pub fn endianness(&self) -> Endianness {
         self.endianness
@@ -112,11 +126,7 @@ impl TiffMetadataReader {
         fetch: &F,
     ) -> AsyncTiffResult<Option<ImageFileDirectory>> {
         if let Some(ifd_start) = self.next_ifd_offset {
-            let ifd_reader =
-                ImageFileDirectoryReader::open(fetch, ifd_start, self.bigtiff, self.endianness)
-                    .await?;
-            let ifd = ifd_reader.read(fetch).await?;
-            let next_ifd_offset = ifd_reader.finish(fetch).await?;
+            let (ifd, next_ifd_offset) = self.read_ifd_at(fetch, ifd_start).await?;
             self.next_ifd_offset = next_ifd_offset;
             Ok(Some(ifd))
         } else {
@@ -124,6 +134,26 @@ impl TiffMetadataReader {
         }
     }
 
+    /// Read the IFD starting at the absolute byte offset `offset`, returning it together with the
+    /// offset of the IFD that follows it, if any.
+    ///
+    /// This does not affect the position of [`read_next_ifd`][Self::read_next_ifd], so it can be
+    /// used to explore IFDs outside the main chain, such as those referenced by `SubIFDs` or EXIF
+    /// pointers.
+    pub async fn read_ifd_at<F: MetadataFetch>(
+        &self,
+        fetch: &F,
+        offset: u64,
+    ) -> AsyncTiffResult<(ImageFileDirectory, Option<u64>)> {
+        let ifd_reader =
+            ImageFileDirectoryReader::open(fetch, offset, self.bigtiff, self.endianness)
+                .await?
+                .with_parse_options(self.parse_options.clone());
+        let ifd = ifd_reader.read(fetch).await?;
+        let next_ifd_offset = ifd_reader.finish(fetch).await?;
+        Ok((ifd, next_ifd_offset))
+    }
+
     /// Read all IFDs from the file.
     pub async fn read_all_ifds<F: MetadataFetch>(
         &mut self,
@@ -136,6 +166,94 @@ impl TiffMetadataReader {
         Ok(ifds)
     }
 
+    /// Read all IFDs from the file, parsing up to `concurrency` IFDs at once.
+    ///
+    /// The chain of IFD offsets is walked first, which only reads the entry count and next IFD
+    /// offset of each IFD. The entries of the IFDs, including tag values stored outside the entry
+    /// tables, are then fetched concurrently. For files with thousands of IFDs, such as OME-TIFF
+    /// z-stacks, this hides most of the per-request latency of remote readers.
+    ///
+    /// The IFDs are returned in file order, like [`read_all_ifds`][Self::read_all_ifds].
+    pub async fn read_all_ifds_concurrent<F: MetadataFetch>(
+        &mut self,
+        fetch: &F,
+        concurrency: usize,
+    ) -> AsyncTiffResult<Vec<ImageFileDirectory>> {
+        let mut ifd_readers = vec![];
+        let mut visited = HashSet::new();
+        while let Some(offset) = self.next_ifd_offset {
+            if !visited.insert(offset) {
+                return Err(AsyncTiffError::General(format!(
+                    "IFD chain loops back to offset {offset}"
+                )));
+            }
+            let ifd_reader =
+                ImageFileDirectoryReader::open(fetch, offset, self.bigtiff, self.endianness)
+                    .await?
+                    .with_parse_options(self.parse_options.clone());
+            self.next_ifd_offset = ifd_reader.finish(fetch).await?;
+            ifd_readers.push(ifd_reader);
+        }
+
+        futures::stream::iter(&ifd_readers)
+            .map(|ifd_reader| ifd_reader.read(fetch))
+            .buffered(concurrency.max(1))
+            .try_collect()
+            .await
+    }
+
+    /// Read all IFDs from the file, skipping IFDs that can't be parsed.
+    ///
+    /// Unlike [`read_all_ifds`][Self::read_all_ifds], an IFD whose tags are invalid doesn't fail
+    /// the whole file: its error is recorded and reading continues with the next IFD in the
+    /// chain. Reading stops at the first IFD whose entry table or next IFD offset can't be read,
+    /// or that was already visited, since the rest of the chain can't be found from there.
+    pub async fn read_all_ifds_lossy<F: MetadataFetch>(
+        &mut self,
+        fetch: &F,
+    ) -> (Vec<ImageFileDirectory>, Vec<IfdError>) {
+        let mut ifds = vec![];
+        let mut errors = vec![];
+        let mut visited = HashSet::new();
+        let mut index = 0;
+        while let Some(offset) = self.next_ifd_offset.take() {
+            let error = |error| IfdError {
+                index,
+                offset,
+                error,
+            };
+            if !visited.insert(offset) {
+                errors.push(error(AsyncTiffError::General(format!(
+                    "IFD chain loops back to offset {offset}"
+                ))));
+                break;
+            }
+            let ifd_reader =
+                match ImageFileDirectoryReader::open(fetch, offset, self.bigtiff, self.endianness)
+                    .await
+                {
+                    Ok(ifd_reader) => ifd_reader.with_parse_options(self.parse_options.clone()),
+                    Err(err) => {
+                        errors.push(error(err));
+                        break;
+                    }
+                };
+            match ifd_reader.read(fetch).await {
+                Ok(ifd) => ifds.push(ifd),
+                Err(err) => errors.push(error(err)),
+            }
+            match ifd_reader.finish(fetch).await {
+                Ok(next_ifd_offset) => self.next_ifd_offset = next_ifd_offset,
+                Err(err) => {
+                    errors.push(error(err));
+                    break;
+                }
+            }
+            index += 1;
+        }
+        (ifds, errors)
+    }
+
     /// Read all IFDs from the file and return a complete TIFF structure.
     pub async fn read<F: MetadataFetch>(&mut self, fetch: &F) -> AsyncTiffResult<TIFF> {
         let ifds = self.read_all_ifds(fetch).await?;
@@ -143,6 +261,17 @@ impl TiffMetadataReader {
     }
 }
 
+/// An IFD that couldn't be read by [`TiffMetadataReader::read_all_ifds_lossy`].
+#[derive(Debug)]
+pub struct IfdError {
+    /// The position of the IFD in the IFD chain, counting unreadable IFDs.
+    pub index: usize,
+    /// The byte offset of the start of the IFD.
+    pub offset: u64,
+    /// The cause of the failure.
+    pub error: AsyncTiffError,
+}
+
 /// Reads the [`ImageFileDirectory`] metadata.
 ///
 /// TIFF metadata is not necessarily contiguous in the files: IFDs are normally all stored
@@ -163,6 +292,7 @@ pub struct ImageFileDirectoryReader {
     ifd_entry_byte_size: u64,
     /// The number of bytes that the value for the number of tags takes up.
     tag_count_byte_size: u64,
+    parse_options: ParseOptions,
 }
 
 impl ImageFileDirectoryReader {
@@ -200,9 +330,19 @@ impl ImageFileDirectoryReader {
             tag_count,
             tag_count_byte_size,
             ifd_start_offset,
+            parse_options: ParseOptions::default(),
         })
     }
 
+    /// Set how strictly the IFD is parsed by [`read`][Self::read].
+    ///
+    /// In lenient mode, entries that can't be read, e.g. because their values lie past the end of
+    /// the file, are skipped and recorded as warnings.
+    pub fn with_parse_options(mut self, parse_options: ParseOptions) -> Self {
+        self.parse_options = parse_options;
+        self
+    }
+
     /// Manually read the tag with the specified index.
     ///
     /// Panics if the tag index is out of range of the tag count.
@@ -217,26 +357,130 @@ impl ImageFileDirectoryReader {
         assert!(tag_idx < self.tag_count);
         let tag_offset =
             self.ifd_start_offset + self.tag_count_byte_size + (self.ifd_entry_byte_size * tag_idx);
-        let (tag_name, tag_value) =
-            read_tag(fetch, tag_offset, self.endianness, self.bigtiff).await?;
+        let (tag_name, tag_value) = read_tag(
+            fetch,
+            tag_offset,
+            self.endianness,
+            self.bigtiff,
+            self.parse_options.limits(),
+        )
+        .await?;
         Ok((tag_name, tag_value))
     }
 
     /// Read all tags out of this IFD.
     ///
+    /// If [`ParseOptions::private_ifds`] is set, the EXIF and GPS IFDs referenced by this IFD are
+    /// read as well.
+    ///
     /// Keep in mind that you'll still need to call [`finish`][Self::finish] to get the byte offset
     /// of the next IFD.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "read_ifd",
+            level = "debug",
+            skip_all,
+            fields(offset = self.ifd_start_offset, tags = self.tag_count)
+        )
+    )]
     pub async fn read<F: MetadataFetch>(&self, fetch: &F) -> AsyncTiffResult<ImageFileDirectory> {
+        let (tags, raw_tags, mut warnings) = self.read_entries(fetch).await?;
+        let mut ifd =
+            ImageFileDirectory::from_tags_with_options(tags, self.endianness, &self.parse_options)?;
+        warnings.append(&mut ifd.parse_warnings);
+
+        if self.parse_options.private_ifds() {
+            if let Some(offset) = ifd.exif_ifd_offset() {
+                ifd.exif = self
+                    .read_private_ifd(fetch, offset, "EXIF", Exif::from_tags, &mut warnings)
+                    .await?;
+            }
+            if let Some(offset) = ifd.gps_ifd_offset() {
+                ifd.gps = self
+                    .read_private_ifd(fetch, offset, "GPS", Gps::from_tags, &mut warnings)
+                    .await?;
+            }
+        }
+
+        ifd.parse_warnings = warnings;
+        ifd.raw_tags = raw_tags;
+        Ok(ifd)
+    }
+
+    /// Read the entries of this IFD, skipping unreadable entries in lenient mode.
+    async fn read_entries<F: MetadataFetch>(
+        &self,
+        fetch: &F,
+    ) -> AsyncTiffResult<(HashMap<Tag, TagValue>, Vec<RawTag>, Vec<String>)> {
+        self.parse_options
+            .limits()
+            .check_tag_count(self.tag_count)?;
         let mut tags = HashMap::with_capacity(self.tag_count as usize);
+        let mut raw_tags = vec![];
+        let mut warnings = vec![];
         for tag_idx in 0..self.tag_count {
-            let (tag, value) = self.read_tag(fetch, tag_idx).await?;
-            tags.insert(tag, value);
+            let tag_offset = self.ifd_start_offset
+                + self.tag_count_byte_size
+                + (self.ifd_entry_byte_size * tag_idx);
+            let limits = self.parse_options.limits();
+            match read_entry(fetch, tag_offset, self.endianness, self.bigtiff, limits).await {
+                Ok(Entry::Parsed(tag, value)) => {
+                    tags.insert(tag, value);
+                }
+                Ok(Entry::Raw(raw)) => raw_tags.push(raw),
+                // Only skip problems with the entry itself, not failed fetches
+                Err(
+                    err @ (AsyncTiffError::InternalTIFFError(_) | AsyncTiffError::EndOfFile(..)),
+                ) if !self.parse_options.strict() => {
+                    warnings.push(format!("Skipping IFD entry {tag_idx}: {err}"));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Ok((tags, raw_tags, warnings))
+    }
+
+    /// Read the private IFD, such as the EXIF IFD, at `offset` and parse its tags with `parse`.
+    ///
+    /// Private IFDs don't describe an image, so they are not parsed as an
+    /// [`ImageFileDirectory`]. Entries with unknown field types are dropped. A private IFD doesn't
+    /// affect reading the image, so an invalid one is skipped with a warning and `None` is
+    /// returned, even in strict mode.
+    async fn read_private_ifd<F: MetadataFetch, T>(
+        &self,
+        fetch: &F,
+        offset: u64,
+        name: &str,
+        parse: impl FnOnce(HashMap<Tag, TagValue>) -> TiffResult<T>,
+        warnings: &mut Vec<String>,
+    ) -> AsyncTiffResult<Option<T>> {
+        let result = async {
+            let reader = Self::open(fetch, offset, self.bigtiff, self.endianness)
+                .await?
+                .with_parse_options(self.parse_options.clone());
+            let (tags, _, mut entry_warnings) = reader.read_entries(fetch).await?;
+            let parsed = parse(tags)?;
+            warnings.append(&mut entry_warnings);
+            Ok(parsed)
+        }
+        .await;
+        match result {
+            Ok(parsed) => Ok(Some(parsed)),
+            // Only skip invalid IFDs, not failed fetches
+            Err(err @ (AsyncTiffError::InternalTIFFError(_) | AsyncTiffError::EndOfFile(..))) => {
+                warnings.push(format!("Skipping {name} IFD: {err}"));
+                Ok(None)
+            }
+            Err(err) => Err(err),
         }
-        ImageFileDirectory::from_tags(tags, self.endianness)
     }
 
     /// Finish this reader, reading the byte offset of the next IFD
-    pub async fn finish<F: MetadataFetch>(self, fetch: &F) -> AsyncTiffResult<Option<u64>> {
+    pub async fn finish<F: MetadataFetch>(&self, fetch: &F) -> AsyncTiffResult<Option<u64>> {
+        self.parse_options
+            .limits()
+            .check_tag_count(self.tag_count)?;
         // The byte offset for reading the next ifd
         let next_ifd_byte_offset = self.ifd_start_offset
             + self.tag_count_byte_size
@@ -259,30 +503,64 @@ impl ImageFileDirectoryReader {
     }
 }
 
+/// An IFD entry, which is either parsed or of a field type that can't be interpreted.
+enum Entry {
+    Parsed(Tag, TagValue),
+    Raw(RawTag),
+}
+
 /// Read a single tag from the cursor
 async fn read_tag<F: MetadataFetch>(
     fetch: &F,
     tag_offset: u64,
     endianness: Endianness,
     bigtiff: bool,
+    limits: &Limits,
 ) -> AsyncTiffResult<(Tag, TagValue)> {
+    match read_entry(fetch, tag_offset, endianness, bigtiff, limits).await? {
+        Entry::Parsed(tag, value) => Ok((tag, value)),
+        Entry::Raw(raw) => Err(TiffError::FormatError(TiffFormatError::Format(format!(
+            "unknown type {} for tag {:?}",
+            raw.field_type, raw.tag
+        )))
+        .into()),
+    }
+}
+
+/// Read a single IFD entry, keeping the raw bytes of entries with an unknown field type.
+async fn read_entry<F: MetadataFetch>(
+    fetch: &F,
+    tag_offset: u64,
+    endianness: Endianness,
+    bigtiff: bool,
+    limits: &Limits,
+) -> AsyncTiffResult<Entry> {
     let mut cursor = MetadataCursor::new_with_offset(fetch, endianness, tag_offset);
 
     let tag_name = Tag::from_u16_exhaustive(cursor.read_u16().await?);
 
     let tag_type_code = cursor.read_u16().await?;
-    let tag_type = Type::from_u16(tag_type_code).expect(
-        "Unknown tag type {tag_type_code}. TODO: we should skip entries with unknown tag types.",
-    );
     let count = if bigtiff {
         cursor.read_u64().await?
     } else {
         cursor.read_u32().await?.into()
     };
 
-    let tag_value = read_tag_value(&mut cursor, tag_type, count, bigtiff).await?;
+    let Some(tag_type) = Type::from_u16(tag_type_code) else {
+        // The value or offset field follows the tag, type and count
+        let value_start = tag_offset + if bigtiff { 12 } else { 8 };
+        let value_len = if bigtiff { 8 } else { 4 };
+        return Ok(Entry::Raw(RawTag {
+            tag: tag_name,
+            field_type: tag_type_code,
+            count,
+            value: fetch.fetch(value_start..value_start + value_len).await?,
+        }));
+    };
 
-    Ok((tag_name, tag_value))
+    let tag_value = read_tag_value(&mut cursor, tag_type, count, bigtiff, limits).await?;
+
+    Ok(Entry::Parsed(tag_name, tag_value))
 }
 
 /// Read a tag's value from the cursor
@@ -295,6 +573,7 @@ async fn read_tag_value<F: MetadataFetch>(
     tag_type: Type,
     count: u64,
     bigtiff: bool,
+    limits: &Limits,
 ) -> AsyncTiffResult<TagValue> {
     // Case 1: there are no values so we can return immediately.
     if count == 0 {
@@ -313,7 +592,10 @@ async fn read_tag_value<F: MetadataFetch>(
         | Type::IFD8 => 8,
     };
 
-    let value_byte_length = count.checked_mul(tag_size).unwrap();
+    let value_byte_length = count
+        .checked_mul(tag_size)
+        .ok_or(TiffError::FormatError(TiffFormatError::InvalidTag))?;
+    limits.check_tag_value_bytes(value_byte_length)?;
 
     // Case 2: there is one value.
     if count == 1 {
@@ -630,6 +912,7 @@ mod test {
     use async_trait::async_trait;
 
     use super::*;
+    use crate::test::util::tiff_with_ifds;
 
     #[async_trait]
     impl MetadataFetch for Bytes {
@@ -675,7 +958,7 @@ mod test {
         for (buf, byte_order, res) in cases {
                 let fetch = Bytes::copy_from_slice(&buf);
             assert_eq!(
-                read_tag(&fetch, 0, byte_order, false).await.unwrap(),
+                read_tag(&fetch, 0, byte_order, false, &Limits::default()).await.unwrap(),
                 (Tag::from_u16_exhaustive(0x01_01),res)
             );
         }
@@ -730,7 +1013,7 @@ mod test {
         for (buf, byte_order, res) in cases {
             let fetch = Bytes::copy_from_slice(&buf);
             assert_eq!(
-                read_tag(&fetch, 0, byte_order, true).await.unwrap(),
+                read_tag(&fetch, 0, byte_order, true, &Limits::default()).await.unwrap(),
                 (Tag::from_u16_exhaustive(0x0101), res)
             )
         }
@@ -767,7 +1050,7 @@ mod test {
             println!("testing {buf:?} to be {res:?}");
             let fetch = Bytes::copy_from_slice(&buf);
             assert_eq!(
-                read_tag(&fetch, 0, byte_order, false).await.unwrap(),
+                read_tag(&fetch, 0, byte_order, false, &Limits::default()).await.unwrap(),
                 (Tag::from_u16_exhaustive(0x0101), res)
             )
         }
@@ -810,7 +1093,7 @@ mod test {
         for (buf, byte_order, res) in cases {
             let fetch = Bytes::copy_from_slice(&buf);
             assert_eq!(
-                read_tag(&fetch, 0, byte_order, true).await.unwrap(),
+                read_tag(&fetch, 0, byte_order, true, &Limits::default()).await.unwrap(),
                 (Tag::from_u16_exhaustive(0x0101), res)
             )
         }
@@ -865,7 +1148,7 @@ mod test {
             println!("reading {buf:?} to be {res:?}");
             let fetch = Bytes::from_owner(buf);
             assert_eq!(
-                read_tag(&fetch, 0, byte_order, false).await.unwrap(),
+                read_tag(&fetch, 0, byte_order, false, &Limits::default()).await.unwrap(),
                 (Tag::from_u16_exhaustive(0x0101), res)
             )
         }
@@ -918,7 +1201,269 @@ mod test {
         for (buf, byte_order, res) in cases {
             println!("reading {buf:?} to be {res:?}");
             let fetch = Bytes::from_owner(buf);
-            assert_eq!(read_tag(&fetch, 0, byte_order, true).await.unwrap(), (Tag::from_u16_exhaustive(0x0101), res))
+            assert_eq!(read_tag(&fetch, 0, byte_order, true, &Limits::default()).await.unwrap(), (Tag::from_u16_exhaustive(0x0101), res))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_ifd_at() {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/image-tiff/tiled-jpeg-ycbcr.tif");
+        let fetch = Bytes::from(std::fs::read(path).unwrap());
+
+        let mut metadata_reader = TiffMetadataReader::try_open(&fetch).await.unwrap();
+        let first_offset = metadata_reader.next_ifd_offset().unwrap();
+        let (ifd, next_offset) = metadata_reader
+            .read_ifd_at(&fetch, first_offset)
+            .await
+            .unwrap();
+        // Random access doesn't advance the main chain
+        assert_eq!(metadata_reader.next_ifd_offset(), Some(first_offset));
+
+        let expected = metadata_reader
+            .read_next_ifd(&fetch)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ifd, expected);
+        assert_eq!(next_offset, metadata_reader.next_ifd_offset());
+    }
+
+    #[tokio::test]
+    async fn test_read_all_ifds_concurrent() {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/image-tiff/tiled-jpeg-ycbcr.tif");
+        let mut data = std::fs::read(path).unwrap();
+
+        // Chain copies of the first IFD, all pointing at the same tag values
+        if data.len() % 2 == 1 {
+            data.push(0);
+        }
+        let first_offset = u32::from_le_bytes(data[4..8].try_into().unwrap()) as usize;
+        let tag_count =
+            u16::from_le_bytes(data[first_offset..first_offset + 2].try_into().unwrap());
+        let ifd_len = 2 + 12 * tag_count as usize;
+        let ifd = data[first_offset..first_offset + ifd_len].to_vec();
+        let mut next_offset_pos = first_offset + ifd_len;
+        for _ in 0..5 {
+            let offset = data.len() as u32;
+            data[next_offset_pos..next_offset_pos + 4].copy_from_slice(&offset.to_le_bytes());
+            data.extend_from_slice(&ifd);
+            next_offset_pos = data.len();
+            data.extend_from_slice(&0u32.to_le_bytes());
+        }
+        let fetch = Bytes::from(data.clone());
+
+        let mut metadata_reader = TiffMetadataReader::try_open(&fetch).await.unwrap();
+        let expected = metadata_reader.read_all_ifds(&fetch).await.unwrap();
+        assert_eq!(expected.len(), 6);
+        for concurrency in [0, 1, 4] {
+            let mut metadata_reader = TiffMetadataReader::try_open(&fetch).await.unwrap();
+            let ifds = metadata_reader
+                .read_all_ifds_concurrent(&fetch, concurrency)
+                .await
+                .unwrap();
+            assert_eq!(ifds, expected);
+            assert!(!metadata_reader.has_next_ifd());
+        }
+
+        // An IFD pointing back at the first one
+        data[next_offset_pos..next_offset_pos + 4]
+            .copy_from_slice(&(first_offset as u32).to_le_bytes());
+        let fetch = Bytes::from(data);
+        let mut metadata_reader = TiffMetadataReader::try_open(&fetch).await.unwrap();
+        assert!(metadata_reader
+            .read_all_ifds_concurrent(&fetch, 4)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_all_ifds_lossy() {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/image-tiff/tiled-jpeg-ycbcr.tif");
+        let mut data = std::fs::read(path).unwrap();
+        let expected = {
+            let fetch = Bytes::from(data.clone());
+            let mut metadata_reader = TiffMetadataReader::try_open(&fetch).await.unwrap();
+            metadata_reader.read_all_ifds(&fetch).await.unwrap()
+        };
+
+        // Prepend an IFD with an ASCII ImageWidth, followed by the original chain
+        if data.len() % 2 == 1 {
+            data.push(0);
         }
+        let broken_offset = data.len() as u32;
+        let first_offset = u32::from_le_bytes(data[4..8].try_into().unwrap());
+        data.extend_from_slice(&1u16.to_le_bytes());
+        data.extend_from_slice(&[0, 1, 2, 0, 2, 0, 0, 0, b'a', 0, 0, 0]);
+        data.extend_from_slice(&first_offset.to_le_bytes());
+        data[4..8].copy_from_slice(&broken_offset.to_le_bytes());
+        let fetch = Bytes::from(data);
+
+        let mut metadata_reader = TiffMetadataReader::try_open(&fetch).await.unwrap();
+        assert!(metadata_reader.read_all_ifds(&fetch).await.is_err());
+
+        let mut metadata_reader = TiffMetadataReader::try_open(&fetch).await.unwrap();
+        let (ifds, errors) = metadata_reader.read_all_ifds_lossy(&fetch).await;
+        assert_eq!(ifds, expected);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].index, 0);
+        assert_eq!(errors[0].offset, broken_offset as u64);
+    }
+
+    #[tokio::test]
+    async fn test_lenient_parse_options() {
+        // A TIFF without SamplesPerPixel, BitsPerSample or PhotometricInterpretation, a SHORT
+        // XResolution, an entry of unknown type, and a GeoKeyDirectory of an unknown version
+        let mut data = tiff_with_ifds(&[&[
+            (256, 3, 1, 4),
+            (257, 3, 1, 4),
+            (282, 3, 1, 72),
+            (300, 99, 1, 0),
+            (34735, 3, 4, 74),
+        ]]);
+        for value in [2u16, 1, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        let fetch = Bytes::from(data);
+
+        let mut metadata_reader = TiffMetadataReader::try_open(&fetch).await.unwrap();
+        assert!(metadata_reader.read_all_ifds(&fetch).await.is_err());
+
+        let mut metadata_reader = TiffMetadataReader::try_open(&fetch)
+            .await
+            .unwrap()
+            .with_parse_options(ParseOptions::lenient());
+        let ifds = metadata_reader.read_all_ifds(&fetch).await.unwrap();
+        let ifd = &ifds[0];
+        assert_eq!((ifd.image_width(), ifd.image_height()), (4, 4));
+        assert_eq!(ifd.samples_per_pixel(), 1);
+        assert_eq!(ifd.bits_per_sample(), [1]);
+        assert_eq!(ifd.x_resolution(), None);
+        assert!(ifd.geo_key_directory().is_none());
+        assert_eq!(ifd.parse_warnings().len(), 5, "{:?}", ifd.parse_warnings());
+
+        // The entry of unknown type is kept as is
+        let raw_tags = ifd.raw_tags();
+        assert_eq!(raw_tags.len(), 1);
+        assert_eq!(raw_tags[0].tag, Tag::Unknown(300));
+        assert_eq!((raw_tags[0].field_type, raw_tags[0].count), (99, 1));
+        assert_eq!(raw_tags[0].value.as_ref(), [0, 0, 0, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_limits() {
+        // A 1x1000000 stripped image with one row per strip, whose StripByteCounts claims a
+        // billion values
+        let fetch = Bytes::from(tiff_with_ifds(&[&[
+            (256, 4, 1, 1),
+            (257, 4, 1, 1_000_000),
+            (273, 4, 1, 0),
+            (278, 4, 1, 1),
+            (279, 4, 1_000_000_000, 0),
+        ]]));
+
+        let read = |limits: Limits| {
+            let fetch = fetch.clone();
+            async move {
+                TiffMetadataReader::try_open(&fetch)
+                    .await
+                    .unwrap()
+                    .with_parse_options(ParseOptions::new().with_limits(limits))
+                    .read_all_ifds(&fetch)
+                    .await
+                    .unwrap_err()
+            }
+        };
+        let limit_of = |err: AsyncTiffError| match err {
+            AsyncTiffError::LimitExceeded { limit, .. } => limit,
+            err => panic!("unexpected error {err}"),
+        };
+
+        assert_eq!(limit_of(read(Limits::default()).await), "tag value bytes");
+        let limits = Limits::unlimited().with_max_tag_count(4);
+        assert_eq!(limit_of(read(limits).await), "tag count");
+
+        // Drop StripByteCounts, which leaves a million strips
+        let mut data = fetch.to_vec();
+        data[8] = 4;
+        data[58..62].fill(0);
+        let fetch = Bytes::from(data);
+        let options = ParseOptions::lenient().with_limits(Limits::new().with_max_chunk_count(1000));
+        let err = TiffMetadataReader::try_open(&fetch)
+            .await
+            .unwrap()
+            .with_parse_options(options)
+            .read_all_ifds(&fetch)
+            .await
+            .unwrap_err();
+        assert_eq!(limit_of(err), "chunk count");
+    }
+
+    #[tokio::test]
+    async fn test_exif_and_gps_ifds() {
+        // An IFD at 8 pointing to an EXIF IFD at 98 and a GPS IFD at 116, followed by the GPS
+        // altitude at 146
+        let tiff = |altitude_ref_type: u16| {
+            let mut data = tiff_with_ifds(&[
+                &[
+                    (256, 3, 1, 4),
+                    (257, 3, 1, 4),
+                    (258, 3, 1, 8),
+                    (262, 3, 1, 1),
+                    (277, 3, 1, 1),
+                    (34665, 4, 1, 98),
+                    (34853, 4, 1, 116),
+                ],
+                &[(34855, 3, 1, 400)],
+                &[(5, altitude_ref_type, 1, 1), (6, 5, 1, 146)],
+            ]);
+            data.extend_from_slice(&25u32.to_le_bytes());
+            data.extend_from_slice(&2u32.to_le_bytes());
+            Bytes::from(data)
+        };
+
+        // The private IFDs are only read on request
+        let fetch = tiff(1);
+        let mut metadata_reader = TiffMetadataReader::try_open(&fetch).await.unwrap();
+        let ifd = metadata_reader
+            .read_next_ifd(&fetch)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ifd.exif_ifd_offset(), Some(98));
+        assert!(ifd.exif().is_none());
+        assert!(ifd.gps().is_none());
+
+        let options = ParseOptions::new().with_private_ifds(true);
+        let mut metadata_reader = TiffMetadataReader::try_open(&fetch)
+            .await
+            .unwrap()
+            .with_parse_options(options.clone());
+        let ifd = metadata_reader
+            .read_next_ifd(&fetch)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ifd.exif().unwrap().iso_speed, Some(400));
+        let gps = ifd.gps().unwrap();
+        assert_eq!(gps.altitude_meters(), Some(-12.5));
+        assert_eq!(gps.latitude_degrees(), None);
+
+        // A SHORT AltitudeRef fails the GPS IFD, which is skipped even in strict mode
+        let fetch = tiff(3);
+        let mut metadata_reader = TiffMetadataReader::try_open(&fetch)
+            .await
+            .unwrap()
+            .with_parse_options(options);
+        let ifd = metadata_reader
+            .read_next_ifd(&fetch)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(ifd.exif().is_some());
+        assert!(ifd.gps().is_none());
+        assert_eq!(ifd.parse_warnings().len(), 1, "{:?}", ifd.parse_warnings());
     }
 }
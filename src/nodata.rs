@@ -0,0 +1,207 @@
+use crate::DataType;
+
+/// A GDAL NoData value parsed at the precision of the image's data type.
+///
+/// Parsing the `GDAL_NODATA` string directly into the target type, rather than going through
+/// `f64`, preserves the exact value. For example, `0.1` in a float32 image parses to the `f32`
+/// nearest to 0.1, which is what the pixels contain, while the `f64` 0.1 matches no pixel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[allow(missing_docs)]
+pub enum Nodata {
+    Bool(bool),
+    UInt8(u8),
+    UInt16(u16),
+    UInt32(u32),
+    UInt64(u64),
+    Int8(i8),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    Float32(f32),
+    Float64(f64),
+}
+
+impl Nodata {
+    /// Parse a `GDAL_NODATA` string as a value of `data_type`.
+    ///
+    /// Returns `None` if the string isn't a number or can't be represented in `data_type`,
+    /// e.g. a negative value for an unsigned type.
+    ///
+    /// ```
+    /// use async_tiff::{DataType, Nodata};
+    ///
+    /// assert_eq!(Nodata::parse("-9999", DataType::Int16), Some(Nodata::Int16(-9999)));
+    /// assert_eq!(Nodata::parse("-9999", DataType::UInt16), None);
+    /// assert!(Nodata::parse("nan", DataType::Float32).unwrap().is_nan());
+    /// ```
+    pub fn parse(value: &str, data_type: DataType) -> Option<Self> {
+        let value = value.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+        let nodata = match data_type {
+            DataType::Bool => Self::Bool(parse_int::<u8>(value)? != 0),
+            DataType::UInt8 => Self::UInt8(parse_int(value)?),
+            DataType::UInt16 => Self::UInt16(parse_int(value)?),
+            DataType::UInt32 => Self::UInt32(parse_int(value)?),
+            DataType::UInt64 => Self::UInt64(parse_int(value)?),
+            DataType::Int8 => Self::Int8(parse_int(value)?),
+            DataType::Int16 => Self::Int16(parse_int(value)?),
+            DataType::Int32 => Self::Int32(parse_int(value)?),
+            DataType::Int64 => Self::Int64(parse_int(value)?),
+            DataType::Float32 => Self::Float32(parse_float(value)?),
+            DataType::Float64 => Self::Float64(parse_float(value)?),
+        };
+        Some(nodata)
+    }
+
+    /// The data type of this value.
+    pub fn data_type(&self) -> DataType {
+        match self {
+            Self::Bool(_) => DataType::Bool,
+            Self::UInt8(_) => DataType::UInt8,
+            Self::UInt16(_) => DataType::UInt16,
+            Self::UInt32(_) => DataType::UInt32,
+            Self::UInt64(_) => DataType::UInt64,
+            Self::Int8(_) => DataType::Int8,
+            Self::Int16(_) => DataType::Int16,
+            Self::Int32(_) => DataType::Int32,
+            Self::Int64(_) => DataType::Int64,
+            Self::Float32(_) => DataType::Float32,
+            Self::Float64(_) => DataType::Float64,
+        }
+    }
+
+    /// Whether this is a floating point NaN.
+    pub fn is_nan(&self) -> bool {
+        match self {
+            Self::Float32(v) => v.is_nan(),
+            Self::Float64(v) => v.is_nan(),
+            _ => false,
+        }
+    }
+
+    /// The value converted to `f64`. This is lossy for 64-bit integers.
+    pub fn as_f64(&self) -> f64 {
+        match *self {
+            Self::Bool(v) => v as u8 as f64,
+            Self::UInt8(v) => v as f64,
+            Self::UInt16(v) => v as f64,
+            Self::UInt32(v) => v as f64,
+            Self::UInt64(v) => v as f64,
+            Self::Int8(v) => v as f64,
+            Self::Int16(v) => v as f64,
+            Self::Int32(v) => v as f64,
+            Self::Int64(v) => v as f64,
+            Self::Float32(v) => v as f64,
+            Self::Float64(v) => v,
+        }
+    }
+}
+
+/// Parse an integer, also accepting integral values written in float notation such as `0.0` or
+/// `-9.999e3`.
+fn parse_int<T: std::str::FromStr + TryFrom<i128>>(value: &str) -> Option<T> {
+    if let Ok(v) = value.parse() {
+        return Some(v);
+    }
+    let v = value.parse::<f64>().ok()?;
+    if v.fract() != 0.0 || !v.is_finite() {
+        return None;
+    }
+    T::try_from(v as i128).ok()
+}
+
+/// Parse a float, accepting the NaN and infinity spellings emitted by GDAL and MSVC.
+fn parse_float<T: std::str::FromStr + From<f32>>(value: &str) -> Option<T> {
+    if let Ok(v) = value.parse() {
+        return Some(v);
+    }
+    let lower = value.to_ascii_lowercase();
+    let (negative, unsigned) = match lower.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, lower.strip_prefix('+').unwrap_or(&lower)),
+    };
+    match unsigned {
+        "nan" | "1.#qnan" | "1.#snan" | "1.#ind" | "nan(ind)" => Some(T::from(f32::NAN)),
+        "1.#inf" | "inf" | "infinity" => Some(T::from(if negative {
+            f32::NEG_INFINITY
+        } else {
+            f32::INFINITY
+        })),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_float32_exact() {
+        let nodata = Nodata::parse("-3.4028234663852886e+38", DataType::Float32).unwrap();
+        assert_eq!(nodata, Nodata::Float32(f32::MIN));
+        let nodata = Nodata::parse("0.1", DataType::Float32).unwrap();
+        assert_eq!(nodata, Nodata::Float32(0.1f32));
+        assert_ne!(nodata.as_f64(), 0.1f64);
+    }
+
+    #[test]
+    fn test_ifd_nodata() {
+        use crate::tag_value::TagValue;
+        use crate::tags::Tag;
+        use crate::test::util::ifd_with_tags;
+
+        let ifd = |sample_format: u16| {
+            ifd_with_tags(
+                1,
+                1,
+                [
+                    (Tag::BitsPerSample, TagValue::Short(16)),
+                    (Tag::SampleFormat, TagValue::Short(sample_format)),
+                    (Tag::GdalNodata, TagValue::Ascii("-9999".to_string())),
+                ],
+            )
+        };
+        assert_eq!(ifd(2).nodata(), Some(Nodata::Int16(-9999)));
+        assert_eq!(ifd(1).nodata(), None);
+    }
+
+    #[test]
+    fn test_parse_nan_spellings() {
+        for value in ["nan", "NaN", "-nan", "1.#QNAN", "-1.#IND", "nan\0"] {
+            assert!(
+                Nodata::parse(value, DataType::Float32).unwrap().is_nan(),
+                "{value}"
+            );
+            assert!(
+                Nodata::parse(value, DataType::Float64).unwrap().is_nan(),
+                "{value}"
+            );
+        }
+        assert_eq!(
+            Nodata::parse("-inf", DataType::Float64),
+            Some(Nodata::Float64(f64::NEG_INFINITY))
+        );
+        assert_eq!(
+            Nodata::parse("1.#INF", DataType::Float32),
+            Some(Nodata::Float32(f32::INFINITY))
+        );
+    }
+
+    #[test]
+    fn test_parse_integers() {
+        assert_eq!(
+            Nodata::parse("255", DataType::UInt8),
+            Some(Nodata::UInt8(255))
+        );
+        assert_eq!(Nodata::parse("256", DataType::UInt8), None);
+        assert_eq!(
+            Nodata::parse("0.0", DataType::UInt8),
+            Some(Nodata::UInt8(0))
+        );
+        assert_eq!(Nodata::parse("-9999.5", DataType::Int16), None);
+        assert_eq!(Nodata::parse("nan", DataType::Int32), None);
+        assert_eq!(
+            Nodata::parse("18446744073709551615", DataType::UInt64),
+            Some(Nodata::UInt64(u64::MAX))
+        );
+    }
+}
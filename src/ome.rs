@@ -0,0 +1,602 @@
+//! Parsing of [OME-TIFF] metadata.
+//!
+//! OME-TIFF files store an OME-XML document in the `ImageDescription` of their first IFD. It
+//! describes one or more images, each a 5D stack of 2D planes indexed by channel (C), focal plane
+//! (Z) and timepoint (T), and which IFD holds each plane. Use [`OmeMetadata::from_tiff`] to
+//! parse it, and [`OmeMetadata::plane`] or [`OmePixels::planes`] to map between IFD indices and
+//! (C, Z, T) coordinates.
+//!
+//! Reduced-resolution versions of a plane are stored as SubIFDs of its IFD. Group them with
+//! [`Pyramid::from_ifds`][crate::Pyramid::from_ifds] and
+//! [`Pyramid::read_sub_ifds`][crate::Pyramid::read_sub_ifds].
+//!
+//! [OME-TIFF]: https://ome-model.readthedocs.io/en/stable/ome-tiff/specification.html
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader, XmlVersion};
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::TIFF;
+
+/// The OME-XML metadata of an OME-TIFF file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OmeMetadata {
+    /// The UUID of this file.
+    pub uuid: Option<String>,
+    /// The images described by the metadata, in document order.
+    pub images: Vec<OmeImage>,
+}
+
+/// An image of an OME-TIFF file, also known as a series.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OmeImage {
+    /// The `ID` of the image.
+    pub id: Option<String>,
+    /// The `Name` of the image.
+    pub name: Option<String>,
+    /// The dimensions and plane layout of the image.
+    pub pixels: OmePixels,
+}
+
+/// The `Pixels` element of an image: its dimensions, and where its planes are stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OmePixels {
+    /// The order in which planes are stored.
+    pub dimension_order: DimensionOrder,
+    /// The pixel type, such as `uint8` or `float`.
+    pub pixel_type: Option<String>,
+    /// The width of each plane, in pixels.
+    pub size_x: u32,
+    /// The height of each plane, in pixels.
+    pub size_y: u32,
+    /// The number of focal planes.
+    pub size_z: u32,
+    /// The number of channels, counting each sample of an RGB channel separately.
+    pub size_c: u32,
+    /// The number of timepoints.
+    pub size_t: u32,
+    /// The physical width of a pixel.
+    pub physical_size_x: Option<f64>,
+    /// The unit of [`physical_size_x`][Self::physical_size_x], `µm` if not given.
+    pub physical_size_x_unit: Option<String>,
+    /// The physical height of a pixel.
+    pub physical_size_y: Option<f64>,
+    /// The unit of [`physical_size_y`][Self::physical_size_y], `µm` if not given.
+    pub physical_size_y_unit: Option<String>,
+    /// The physical distance between focal planes.
+    pub physical_size_z: Option<f64>,
+    /// The unit of [`physical_size_z`][Self::physical_size_z], `µm` if not given.
+    pub physical_size_z_unit: Option<String>,
+    /// The channels of the image.
+    pub channels: Vec<OmeChannel>,
+    /// The blocks of planes stored in IFDs.
+    pub tiff_data: Vec<TiffData>,
+}
+
+/// A channel of an image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OmeChannel {
+    /// The `ID` of the channel.
+    pub id: Option<String>,
+    /// The `Name` of the channel.
+    pub name: Option<String>,
+    /// The number of samples stored in each pixel of this channel, 3 for RGB.
+    pub samples_per_pixel: Option<u32>,
+}
+
+/// A `TiffData` element, mapping a run of consecutive IFDs to consecutive planes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TiffData {
+    /// The first IFD of the run.
+    pub ifd: u32,
+    /// The Z index of the first plane of the run.
+    pub first_z: u32,
+    /// The C index of the first plane of the run.
+    pub first_c: u32,
+    /// The T index of the first plane of the run.
+    pub first_t: u32,
+    /// The number of planes in the run. `None` covers all remaining planes.
+    pub plane_count: Option<u32>,
+    /// The name of the file holding the IFDs.
+    pub file_name: Option<String>,
+    /// The UUID of the file holding the IFDs.
+    pub uuid: Option<String>,
+    /// Whether the IFDs are in another file of a multi-file dataset, because [`uuid`][Self::uuid]
+    /// differs from the UUID of this file.
+    pub external: bool,
+}
+
+/// The order in which the planes of an image are stored, from the fastest to the slowest
+/// varying dimension after X and Y.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(missing_docs)]
+pub enum DimensionOrder {
+    XYZCT,
+    XYZTC,
+    XYCTZ,
+    XYCZT,
+    XYTCZ,
+    XYTZC,
+}
+
+/// The location of a plane of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OmePlane {
+    /// The index of the IFD holding the plane.
+    pub ifd: usize,
+    /// The channel index.
+    pub c: u32,
+    /// The focal plane index.
+    pub z: u32,
+    /// The timepoint index.
+    pub t: u32,
+}
+
+impl OmeMetadata {
+    /// Parse the OME-XML in the `ImageDescription` of the first IFD of `tiff`.
+    ///
+    /// Returns `None` if the file is not an OME-TIFF.
+    pub fn from_tiff(tiff: &TIFF) -> AsyncTiffResult<Option<Self>> {
+        match tiff.ifds().first().and_then(|ifd| ifd.image_description()) {
+            Some(description) if description.contains("<OME") => Self::parse(description).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Parse an OME-XML document.
+    pub fn parse(xml: &str) -> AsyncTiffResult<Self> {
+        let mut reader = Reader::from_str(xml);
+        let mut uuid = None;
+        let mut images = vec![];
+        let mut in_uuid = false;
+        loop {
+            let event = reader.read_event().map_err(xml_error)?;
+            let (element, is_start) = match &event {
+                Event::Start(element) => (element, true),
+                Event::Empty(element) => (element, false),
+                Event::End(element) => {
+                    if element.local_name().as_ref() == b"UUID" {
+                        in_uuid = false;
+                    }
+                    continue;
+                }
+                Event::Text(text) if in_uuid => {
+                    let uuid = text.decode().map_err(xml_error)?.trim().to_string();
+                    if let Some(tiff_data) = last_tiff_data(&mut images) {
+                        tiff_data.uuid = Some(uuid);
+                    }
+                    continue;
+                }
+                Event::Eof => break,
+                _ => continue,
+            };
+            let attrs = Attributes::from_element(element)?;
+            match element.local_name().as_ref() {
+                b"OME" => uuid = attrs.get("UUID"),
+                b"Image" => images.push(OmeImage {
+                    id: attrs.get("ID"),
+                    name: attrs.get("Name"),
+                    pixels: OmePixels::default(),
+                }),
+                b"Pixels" => {
+                    let image = current_image(&mut images, "Pixels")?;
+                    image.pixels = OmePixels {
+                        channels: std::mem::take(&mut image.pixels.channels),
+                        ..OmePixels::from_attributes(&attrs)?
+                    };
+                }
+                // `LogicalChannel` is the name used by schemas before 2009
+                b"Channel" | b"LogicalChannel" => {
+                    current_image(&mut images, "Channel")?
+                        .pixels
+                        .channels
+                        .push(OmeChannel {
+                            id: attrs.get("ID"),
+                            name: attrs.get("Name"),
+                            samples_per_pixel: attrs.parse("SamplesPerPixel")?,
+                        });
+                }
+                b"TiffData" => {
+                    current_image(&mut images, "TiffData")?
+                        .pixels
+                        .tiff_data
+                        .push(TiffData {
+                            ifd: attrs.parse("IFD")?.unwrap_or(0),
+                            first_z: attrs.parse("FirstZ")?.unwrap_or(0),
+                            first_c: attrs.parse("FirstC")?.unwrap_or(0),
+                            first_t: attrs.parse("FirstT")?.unwrap_or(0),
+                            // A single plane if the IFD is given, all remaining planes otherwise
+                            plane_count: attrs.parse("PlaneCount")?.or(attrs.get("IFD").map(|_| 1)),
+                            file_name: None,
+                            uuid: None,
+                            external: false,
+                        });
+                }
+                b"UUID" => {
+                    if let Some(tiff_data) = last_tiff_data(&mut images) {
+                        tiff_data.file_name = attrs.get("FileName");
+                        in_uuid = is_start;
+                    }
+                }
+                _ => {}
+            }
+        }
+        if images.is_empty() {
+            return Err(AsyncTiffError::General(
+                "OME-XML does not contain any Image".to_string(),
+            ));
+        }
+        for tiff_data in images
+            .iter_mut()
+            .flat_map(|image| &mut image.pixels.tiff_data)
+        {
+            tiff_data.external = match (&uuid, &tiff_data.uuid) {
+                (Some(uuid), Some(other)) => uuid != other,
+                _ => false,
+            };
+        }
+        Ok(Self { uuid, images })
+    }
+
+    /// The image and plane stored in the IFD at index `ifd`, if any.
+    ///
+    /// Only planes stored in this file are considered; see [`OmePixels::planes`].
+    pub fn plane(&self, ifd: usize) -> Option<(usize, OmePlane)> {
+        self.images.iter().enumerate().find_map(|(index, image)| {
+            image
+                .pixels
+                .planes()
+                .into_iter()
+                .find(|plane| plane.ifd == ifd)
+                .map(|plane| (index, plane))
+        })
+    }
+}
+
+impl OmePixels {
+    fn from_attributes(attrs: &Attributes) -> AsyncTiffResult<Self> {
+        let size = |name| {
+            attrs.parse(name)?.ok_or(AsyncTiffError::General(format!(
+                "OME-XML Pixels is missing {name}"
+            )))
+        };
+        let dimension_order = match attrs.get("DimensionOrder").as_deref() {
+            Some("XYZCT") => DimensionOrder::XYZCT,
+            Some("XYZTC") => DimensionOrder::XYZTC,
+            Some("XYCTZ") => DimensionOrder::XYCTZ,
+            Some("XYCZT") | None => DimensionOrder::XYCZT,
+            Some("XYTCZ") => DimensionOrder::XYTCZ,
+            Some("XYTZC") => DimensionOrder::XYTZC,
+            Some(other) => {
+                return Err(AsyncTiffError::General(format!(
+                    "Unknown OME-XML DimensionOrder {other}"
+                )))
+            }
+        };
+        Ok(Self {
+            dimension_order,
+            // `PixelType` is the name used by schemas before 2009
+            pixel_type: attrs.get("Type").or(attrs.get("PixelType")),
+            size_x: size("SizeX")?,
+            size_y: size("SizeY")?,
+            size_z: size("SizeZ")?,
+            size_c: size("SizeC")?,
+            size_t: size("SizeT")?,
+            physical_size_x: attrs.parse("PhysicalSizeX")?,
+            physical_size_x_unit: attrs.get("PhysicalSizeXUnit"),
+            physical_size_y: attrs.parse("PhysicalSizeY")?,
+            physical_size_y_unit: attrs.get("PhysicalSizeYUnit"),
+            physical_size_z: attrs.parse("PhysicalSizeZ")?,
+            physical_size_z_unit: attrs.get("PhysicalSizeZUnit"),
+            channels: vec![],
+            tiff_data: vec![],
+        })
+    }
+
+    /// The number of channels stored as separate planes.
+    ///
+    /// This is smaller than [`size_c`][Self::size_c] when channels have several samples per
+    /// pixel, such as RGB channels stored as one plane.
+    pub fn effective_size_c(&self) -> u32 {
+        if self.channels.is_empty() {
+            self.size_c
+        } else {
+            self.channels.len() as u32
+        }
+    }
+
+    /// The number of planes of the image.
+    pub fn plane_count(&self) -> u32 {
+        self.effective_size_c() * self.size_z * self.size_t
+    }
+
+    /// The (C, Z, T) coordinates of the plane at `index` in [`dimension_order`][Self::dimension_order].
+    pub fn plane_coordinates(&self, index: u32) -> (u32, u32, u32) {
+        let [(_, a), (_, b), (_, _)] = self.dimensions();
+        let coordinates = [index % a, index / a % b, index / (a * b)];
+        let mut czt = (0, 0, 0);
+        for (&(dim, _), coordinate) in self.dimensions().iter().zip(coordinates) {
+            match dim {
+                'C' => czt.0 = coordinate,
+                'Z' => czt.1 = coordinate,
+                _ => czt.2 = coordinate,
+            }
+        }
+        czt
+    }
+
+    /// The index in [`dimension_order`][Self::dimension_order] of the plane at (C, Z, T)
+    /// coordinates `(c, z, t)`.
+    pub fn plane_index(&self, c: u32, z: u32, t: u32) -> u32 {
+        let [(d0, s0), (d1, s1), (d2, _)] = self.dimensions();
+        let coordinate = |dim| match dim {
+            'C' => c,
+            'Z' => z,
+            _ => t,
+        };
+        coordinate(d0) + s0 * (coordinate(d1) + s1 * coordinate(d2))
+    }
+
+    /// The IFD and (C, Z, T) coordinates of every plane stored in this file.
+    ///
+    /// Planes of [`external`][TiffData::external] `TiffData` elements are skipped. If there is no
+    /// `TiffData`, the planes are stored in consecutive IFDs starting at the first one.
+    pub fn planes(&self) -> Vec<OmePlane> {
+        let total = self.plane_count();
+        let plane = |ifd: u32, index: u32| {
+            let (c, z, t) = self.plane_coordinates(index);
+            OmePlane {
+                ifd: ifd as usize,
+                c,
+                z,
+                t,
+            }
+        };
+        if self.tiff_data.is_empty() {
+            return (0..total).map(|index| plane(index, index)).collect();
+        }
+        self.tiff_data
+            .iter()
+            .filter(|tiff_data| !tiff_data.external)
+            .flat_map(|tiff_data| {
+                let first =
+                    self.plane_index(tiff_data.first_c, tiff_data.first_z, tiff_data.first_t);
+                let count = tiff_data
+                    .plane_count
+                    .unwrap_or(total.saturating_sub(first))
+                    .min(total.saturating_sub(first));
+                (0..count).map(move |i| plane(tiff_data.ifd + i, first + i))
+            })
+            .collect()
+    }
+
+    /// The dimensions after X and Y with their sizes, from the fastest to the slowest varying.
+    fn dimensions(&self) -> [(char, u32); 3] {
+        let order = match self.dimension_order {
+            DimensionOrder::XYZCT => ['Z', 'C', 'T'],
+            DimensionOrder::XYZTC => ['Z', 'T', 'C'],
+            DimensionOrder::XYCTZ => ['C', 'T', 'Z'],
+            DimensionOrder::XYCZT => ['C', 'Z', 'T'],
+            DimensionOrder::XYTCZ => ['T', 'C', 'Z'],
+            DimensionOrder::XYTZC => ['T', 'Z', 'C'],
+        };
+        order.map(|dim| {
+            let size = match dim {
+                'C' => self.effective_size_c(),
+                'Z' => self.size_z,
+                _ => self.size_t,
+            };
+            // Guard against division by zero for malformed sizes
+            (dim, size.max(1))
+        })
+    }
+}
+
+impl Default for OmePixels {
+    fn default() -> Self {
+        Self {
+            dimension_order: DimensionOrder::XYCZT,
+            pixel_type: None,
+            size_x: 0,
+            size_y: 0,
+            size_z: 1,
+            size_c: 1,
+            size_t: 1,
+            physical_size_x: None,
+            physical_size_x_unit: None,
+            physical_size_y: None,
+            physical_size_y_unit: None,
+            physical_size_z: None,
+            physical_size_z_unit: None,
+            channels: vec![],
+            tiff_data: vec![],
+        }
+    }
+}
+
+/// The attributes of an element, keyed by local name.
+struct Attributes(Vec<(String, String)>);
+
+impl Attributes {
+    fn from_element(element: &BytesStart) -> AsyncTiffResult<Self> {
+        element
+            .attributes()
+            .map(|attr| {
+                let attr = attr.map_err(xml_error)?;
+                let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).into_owned();
+                let value = attr
+                    .normalized_value(XmlVersion::Implicit1_0)
+                    .map_err(xml_error)?;
+                Ok((key, value.into_owned()))
+            })
+            .collect::<AsyncTiffResult<_>>()
+            .map(Self)
+    }
+
+    fn get(&self, key: &str) -> Option<String> {
+        self.0
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    }
+
+    fn parse<T: std::str::FromStr>(&self, key: &str) -> AsyncTiffResult<Option<T>> {
+        self.get(key)
+            .map(|value| {
+                value.trim().parse().map_err(|_| {
+                    AsyncTiffError::General(format!("Invalid OME-XML {key} value {value:?}"))
+                })
+            })
+            .transpose()
+    }
+}
+
+fn current_image<'a>(
+    images: &'a mut [OmeImage],
+    element: &str,
+) -> AsyncTiffResult<&'a mut OmeImage> {
+    images.last_mut().ok_or(AsyncTiffError::General(format!(
+        "OME-XML {element} outside of an Image"
+    )))
+}
+
+fn last_tiff_data(images: &mut [OmeImage]) -> Option<&mut TiffData> {
+    images.last_mut()?.pixels.tiff_data.last_mut()
+}
+
+fn xml_error(err: impl std::fmt::Display) -> AsyncTiffError {
+    AsyncTiffError::General(format!("Invalid OME-XML: {err}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<OME xmlns="http://www.openmicroscopy.org/Schemas/OME/2016-06" UUID="urn:uuid:1234">
+  <Image ID="Image:0" Name="stack">
+    <Pixels ID="Pixels:0" DimensionOrder="XYZCT" Type="uint16" SizeX="512" SizeY="256"
+        SizeZ="3" SizeC="2" SizeT="1" PhysicalSizeX="0.5" PhysicalSizeY="0.5"
+        PhysicalSizeZ="2.0" PhysicalSizeZUnit="nm">
+      <Channel ID="Channel:0:0" Name="DAPI" SamplesPerPixel="1"/>
+      <Channel ID="Channel:0:1" Name="GFP" SamplesPerPixel="1"/>
+      <TiffData IFD="0" PlaneCount="6"/>
+    </Pixels>
+  </Image>
+  <Image ID="Image:1">
+    <Pixels DimensionOrder="XYCZT" Type="uint8" SizeX="64" SizeY="64" SizeZ="1" SizeC="1" SizeT="2">
+      <TiffData IFD="7" FirstT="1"/>
+      <TiffData IFD="6" FirstT="0">
+        <UUID>urn:uuid:1234</UUID>
+      </TiffData>
+      <TiffData IFD="0" FirstT="1">
+        <UUID FileName="other.ome.tif">urn:uuid:5678</UUID>
+      </TiffData>
+    </Pixels>
+  </Image>
+</OME>"#;
+
+    #[test]
+    fn test_parse() {
+        let metadata = OmeMetadata::parse(XML).unwrap();
+        assert_eq!(metadata.images.len(), 2);
+
+        let image = &metadata.images[0];
+        assert_eq!(image.name.as_deref(), Some("stack"));
+        let pixels = &image.pixels;
+        assert_eq!(pixels.dimension_order, DimensionOrder::XYZCT);
+        assert_eq!(pixels.pixel_type.as_deref(), Some("uint16"));
+        assert_eq!((pixels.size_x, pixels.size_y), (512, 256));
+        assert_eq!((pixels.size_z, pixels.size_c, pixels.size_t), (3, 2, 1));
+        assert_eq!(pixels.physical_size_x, Some(0.5));
+        assert_eq!(pixels.physical_size_z, Some(2.0));
+        assert_eq!(pixels.physical_size_z_unit.as_deref(), Some("nm"));
+        let names = pixels
+            .channels
+            .iter()
+            .map(|c| c.name.as_deref().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["DAPI", "GFP"]);
+
+        let tiff_data = &metadata.images[1].pixels.tiff_data;
+        assert_eq!(tiff_data[0].plane_count, Some(1));
+        assert_eq!(tiff_data[1].uuid.as_deref(), Some("urn:uuid:1234"));
+        assert!(!tiff_data[1].external);
+        assert_eq!(tiff_data[2].file_name.as_deref(), Some("other.ome.tif"));
+        assert!(tiff_data[2].external);
+    }
+
+    #[test]
+    fn test_planes() {
+        let metadata = OmeMetadata::parse(XML).unwrap();
+        let planes = metadata.images[0].pixels.planes();
+        // Z varies fastest, then C
+        let czt = planes.iter().map(|p| (p.c, p.z, p.t)).collect::<Vec<_>>();
+        assert_eq!(
+            czt,
+            [
+                (0, 0, 0),
+                (0, 1, 0),
+                (0, 2, 0),
+                (1, 0, 0),
+                (1, 1, 0),
+                (1, 2, 0)
+            ]
+        );
+        assert_eq!(metadata.images[0].pixels.plane_index(1, 2, 0), 5);
+
+        let planes = metadata.images[1].pixels.planes();
+        assert_eq!(
+            planes,
+            [
+                OmePlane {
+                    ifd: 7,
+                    c: 0,
+                    z: 0,
+                    t: 1
+                },
+                OmePlane {
+                    ifd: 6,
+                    c: 0,
+                    z: 0,
+                    t: 0
+                }
+            ]
+        );
+        assert_eq!(metadata.plane(7), Some((1, planes[0])));
+        assert_eq!(metadata.plane(3).unwrap().1.c, 1);
+        assert_eq!(metadata.plane(8), None);
+    }
+
+    #[test]
+    fn test_legacy_schema() {
+        // Abbreviated from the 2008-09 metadata in src/test/ome_tiff.rs
+        let xml = r#"<OME xmlns="http://www.openmicroscopy.org/Schemas/OME/2008-09" UUID="3af39f55">
+<Image ID="Image:0" Name="lg.jpg">
+<LogicalChannel ID="LogicalChannel:0" Name="Red" SamplesPerPixel="1"/>
+<LogicalChannel ID="LogicalChannel:1" Name="Green" SamplesPerPixel="1"/>
+<LogicalChannel ID="LogicalChannel:2" Name="Blue" SamplesPerPixel="1"/>
+<Pixels BigEndian="true" DimensionOrder="XYCZT" PixelType="uint8" SizeC="3" SizeT="1" SizeX="1024" SizeY="943" SizeZ="1">
+<TiffData>
+<UUID FileName="__omero_export__2069104425008571311.ome.tiff">3af39f55</UUID>
+</TiffData>
+</Pixels>
+</Image>
+</OME>"#;
+        let metadata = OmeMetadata::parse(xml).unwrap();
+        let pixels = &metadata.images[0].pixels;
+        assert_eq!(pixels.pixel_type.as_deref(), Some("uint8"));
+        assert_eq!(pixels.channels.len(), 3);
+        assert_eq!(pixels.tiff_data[0].plane_count, None);
+        assert_eq!(pixels.tiff_data[0].uuid.as_deref(), Some("3af39f55"));
+        let planes = pixels.planes();
+        assert_eq!(planes.len(), 3);
+        assert_eq!((planes[2].ifd, planes[2].c), (2, 2));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(OmeMetadata::parse("<OME></OME>").is_err());
+        assert!(OmeMetadata::parse(r#"<OME><Image><Pixels SizeX="a"/></Image></OME>"#).is_err());
+    }
+}
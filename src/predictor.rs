@@ -184,3 +184,47 @@ fn rev_predict_f64(input: &mut [u8], output: &mut [u8], samples: usize) {
         ])));
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_fix_endianness_big_endian() {
+        let mut buffer = vec![0x01, 0x02, 0x03, 0x04];
+        fix_endianness(&mut buffer, Endianness::BigEndian, 16);
+        assert_eq!(
+            bytemuck::pod_collect_to_vec::<u8, u16>(&buffer),
+            [0x0102, 0x0304]
+        );
+
+        let mut buffer = vec![0x01, 0x02, 0x03, 0x04];
+        fix_endianness(&mut buffer, Endianness::BigEndian, 32);
+        assert_eq!(
+            bytemuck::pod_collect_to_vec::<u8, u32>(&buffer),
+            [0x01020304]
+        );
+
+        let mut buffer = 1.5f64.to_be_bytes().to_vec();
+        fix_endianness(&mut buffer, Endianness::BigEndian, 64);
+        assert_eq!(bytemuck::pod_collect_to_vec::<u8, f64>(&buffer), [1.5]);
+
+        let mut buffer = 0x0102u16.to_le_bytes().to_vec();
+        fix_endianness(&mut buffer, Endianness::LittleEndian, 16);
+        assert_eq!(bytemuck::pod_collect_to_vec::<u8, u16>(&buffer), [0x0102]);
+    }
+
+    #[test]
+    fn test_unpredict_hdiff_big_endian() {
+        // Deltas of a 3-pixel row of 16-bit samples: 0x0100, +1, +0x0100
+        let buffer = [0x0100u16, 1, 0x0100]
+            .iter()
+            .flat_map(|v| v.to_be_bytes())
+            .collect();
+        let decoded = unpredict_hdiff(buffer, Endianness::BigEndian, 1, 16, 3);
+        assert_eq!(
+            bytemuck::pod_collect_to_vec::<u8, u16>(&decoded),
+            [0x0100, 0x0101, 0x0201]
+        );
+    }
+}
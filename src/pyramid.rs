@@ -0,0 +1,263 @@
+//! Grouping of a full-resolution image with its overviews.
+
+use std::borrow::Borrow;
+
+use crate::error::AsyncTiffResult;
+use crate::metadata::{MetadataFetch, TiffMetadataReader};
+use crate::{ImageFileDirectory, TIFF};
+
+/// `NewSubfileType` bit marking a reduced-resolution version of another image.
+const REDUCED_RESOLUTION: u32 = 1;
+/// `NewSubfileType` bit marking a transparency mask for another image.
+const TRANSPARENCY_MASK: u32 = 4;
+
+/// A full-resolution image together with its reduced-resolution overviews.
+///
+/// Levels are ordered from the full-resolution image (level 0) to the coarsest overview.
+/// Transparency masks are not part of the pyramid.
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// use std::env::current_dir;
+/// use std::sync::Arc;
+///
+/// use object_store::local::LocalFileSystem;
+///
+/// use async_tiff::reader::ObjectReader;
+/// use async_tiff::{Pyramid, TIFF};
+///
+/// let store = Arc::new(LocalFileSystem::new_with_prefix(current_dir().unwrap()).unwrap());
+/// let reader = ObjectReader::new(store, "fixtures/image-tiff/tiled-jpeg-ycbcr.tif".into());
+/// let tiff = TIFF::open(Arc::new(reader)).await.unwrap();
+///
+/// let pyramid = Pyramid::from_tiff(&tiff).unwrap();
+/// let level = pyramid.level_for_scale(4.0);
+/// let (width, height) = pyramid.dimensions(level);
+/// # })
+/// ```
+#[derive(Debug, Clone)]
+pub struct Pyramid {
+    levels: Vec<ImageFileDirectory>,
+    /// The index of each level among the IFDs the pyramid was built from, or `None` for
+    /// overviews read from SubIFDs.
+    indices: Vec<Option<usize>>,
+}
+
+impl Pyramid {
+    /// Group the IFDs of `tiff` into a pyramid.
+    ///
+    /// The first IFD that is neither an overview nor a mask is the full-resolution image, and all
+    /// overviews in the main IFD chain are its levels. Overviews stored as SubIFDs of the
+    /// full-resolution image are added by [`read_sub_ifds`][Self::read_sub_ifds].
+    ///
+    /// Returns `None` if there is no full-resolution image.
+    pub fn from_tiff(tiff: &TIFF) -> Option<Self> {
+        let indices = Self::level_indices(tiff.ifds())?;
+        let levels = indices.iter().map(|i| tiff.ifds()[*i].clone()).collect();
+        Some(Self::new(levels, indices))
+    }
+
+    /// Group the given IFDs into a pyramid, using the same rules as
+    /// [`from_tiff`][Self::from_tiff].
+    pub fn from_ifds(ifds: impl IntoIterator<Item = ImageFileDirectory>) -> Option<Self> {
+        let ifds = ifds.into_iter().collect::<Vec<_>>();
+        let indices = Self::level_indices(&ifds)?;
+        let mut ifds = ifds.into_iter().map(Some).collect::<Vec<_>>();
+        let levels = indices.iter().filter_map(|i| ifds[*i].take()).collect();
+        Some(Self::new(levels, indices))
+    }
+
+    /// The indices into `ifds` of the levels of the pyramid they form, from the full-resolution
+    /// image to the coarsest overview, using the same rules as [`from_tiff`][Self::from_tiff].
+    ///
+    /// Unlike building a [`Pyramid`], this doesn't copy the IFDs. Returns `None` if there is no
+    /// full-resolution image.
+    pub fn level_indices<I: Borrow<ImageFileDirectory>>(ifds: &[I]) -> Option<Vec<usize>> {
+        let mut full_resolution = None;
+        let mut overviews = vec![];
+        for (index, ifd) in ifds.iter().enumerate() {
+            let ifd = ifd.borrow();
+            if is_mask(ifd) {
+                continue;
+            }
+            if is_overview(ifd) {
+                overviews.push(index);
+            } else if full_resolution.is_none() {
+                full_resolution = Some(index);
+            }
+        }
+
+        // The full-resolution image stays first, even if an overview claims to be larger
+        overviews.sort_by_key(|index| std::cmp::Reverse(ifds[*index].borrow().image_width()));
+        let mut levels = vec![full_resolution?];
+        levels.extend(overviews);
+        Some(levels)
+    }
+
+    fn new(levels: Vec<ImageFileDirectory>, indices: Vec<usize>) -> Self {
+        Self {
+            levels,
+            indices: indices.into_iter().map(Some).collect(),
+        }
+    }
+
+    /// Read the overviews stored as SubIFDs of the full-resolution image and add them to the
+    /// pyramid.
+    ///
+    /// `metadata_reader` must have been opened on the same file as the IFDs of this pyramid.
+    pub async fn read_sub_ifds<F: MetadataFetch>(
+        mut self,
+        metadata_reader: &TiffMetadataReader,
+        fetch: &F,
+    ) -> AsyncTiffResult<Self> {
+        let mut overviews = vec![];
+        for offset in self.full_resolution().sub_ifd_offsets().unwrap_or_default() {
+            let (ifd, _) = metadata_reader.read_ifd_at(fetch, offset).await?;
+            if is_overview(&ifd) && !is_mask(&ifd) {
+                overviews.push(ifd);
+            }
+        }
+        self.add_overviews(overviews);
+        Ok(self)
+    }
+
+    fn add_overviews(&mut self, overviews: Vec<ImageFileDirectory>) {
+        let mut levels = std::mem::take(&mut self.levels)
+            .into_iter()
+            .zip(std::mem::take(&mut self.indices))
+            .chain(overviews.into_iter().map(|ifd| (ifd, None)))
+            .collect::<Vec<_>>();
+        // The full-resolution image stays first, even if an overview claims to be larger
+        levels[1..].sort_by_key(|(ifd, _)| std::cmp::Reverse(ifd.image_width()));
+        (self.levels, self.indices) = levels.into_iter().unzip();
+    }
+
+    /// All levels, from the full-resolution image to the coarsest overview.
+    pub fn levels(&self) -> &[ImageFileDirectory] {
+        &self.levels
+    }
+
+    /// The full-resolution image.
+    pub fn full_resolution(&self) -> &ImageFileDirectory {
+        &self.levels[0]
+    }
+
+    /// The reduced-resolution overviews, from finest to coarsest.
+    pub fn overviews(&self) -> &[ImageFileDirectory] {
+        &self.levels[1..]
+    }
+
+    /// The index of `level` among the IFDs the pyramid was built from, i.e. into
+    /// [`TIFF::ifds`] for a pyramid built with [`from_tiff`][Self::from_tiff]. Returns `None` for
+    /// overviews read from SubIFDs.
+    ///
+    /// Panics if `level` is out of range.
+    pub fn ifd_index(&self, level: usize) -> Option<usize> {
+        self.indices[level]
+    }
+
+    /// The number of levels, including the full-resolution image.
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Whether this pyramid has no levels. This is always `false`.
+    pub fn is_empty(&self) -> bool {
+        self.levels.is_empty()
+    }
+
+    /// The `(width, height)` in pixels of `level`.
+    ///
+    /// Panics if `level` is out of range.
+    pub fn dimensions(&self, level: usize) -> (u32, u32) {
+        let ifd = &self.levels[level];
+        (ifd.image_width(), ifd.image_height())
+    }
+
+    /// The `(x, y)` factors by which `level` is downsampled relative to the full-resolution
+    /// image. This is `(1.0, 1.0)` for level 0.
+    ///
+    /// Panics if `level` is out of range.
+    pub fn scale_factors(&self, level: usize) -> (f64, f64) {
+        let (full_width, full_height) = self.dimensions(0);
+        let (width, height) = self.dimensions(level);
+        (
+            full_width as f64 / width as f64,
+            full_height as f64 / height as f64,
+        )
+    }
+
+    /// The coarsest level that still has at least the resolution needed to render the image
+    /// downsampled by `scale` along the x axis.
+    ///
+    /// E.g. for a pyramid with levels downsampled by `[1, 2, 4, 8]`, a scale of `5.0` selects
+    /// level 2. Scales below the first overview select the full-resolution image.
+    pub fn level_for_scale(&self, scale: f64) -> usize {
+        level_for_scale(&self.levels, scale)
+    }
+}
+
+/// The coarsest of `levels`, ordered from the full-resolution image to the coarsest overview, that
+/// still has at least the resolution needed to render the image downsampled by `scale`.
+pub(crate) fn level_for_scale<I: Borrow<ImageFileDirectory>>(levels: &[I], scale: f64) -> usize {
+    let full_width = levels[0].borrow().image_width() as f64;
+    levels
+        .iter()
+        .map(Borrow::<ImageFileDirectory>::borrow)
+        .take_while(|ifd| full_width / ifd.image_width() as f64 <= scale)
+        .count()
+        .saturating_sub(1)
+}
+
+pub(crate) fn is_overview(ifd: &ImageFileDirectory) -> bool {
+    ifd.new_subfile_type()
+        .is_some_and(|t| t & REDUCED_RESOLUTION != 0)
+}
+
+pub(crate) fn is_mask(ifd: &ImageFileDirectory) -> bool {
+    ifd.new_subfile_type()
+        .is_some_and(|t| t & TRANSPARENCY_MASK != 0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tag_value::TagValue;
+    use crate::tags::Tag;
+    use crate::test::util::ifd_with_tags;
+
+    fn ifd(width: u32, new_subfile_type: u32) -> ImageFileDirectory {
+        let new_subfile_type = TagValue::Unsigned(new_subfile_type);
+        ifd_with_tags(width, width / 2, [(Tag::NewSubfileType, new_subfile_type)])
+    }
+
+    #[test]
+    fn test_pyramid_levels() {
+        let pyramid = Pyramid::from_ifds([
+            ifd(1024, 0),
+            ifd(1024, TRANSPARENCY_MASK),
+            ifd(128, REDUCED_RESOLUTION),
+            ifd(512, REDUCED_RESOLUTION),
+            ifd(512, REDUCED_RESOLUTION | TRANSPARENCY_MASK),
+            ifd(256, REDUCED_RESOLUTION),
+        ])
+        .unwrap();
+
+        assert_eq!(pyramid.len(), 4);
+        assert_eq!(pyramid.overviews().len(), 3);
+        assert_eq!(pyramid.dimensions(1), (512, 256));
+        assert_eq!(pyramid.scale_factors(0), (1.0, 1.0));
+        assert_eq!(pyramid.scale_factors(3), (8.0, 8.0));
+        assert_eq!(pyramid.ifd_index(0), Some(0));
+        assert_eq!(pyramid.ifd_index(1), Some(3));
+        assert_eq!(pyramid.ifd_index(3), Some(2));
+
+        assert_eq!(pyramid.level_for_scale(0.5), 0);
+        assert_eq!(pyramid.level_for_scale(1.9), 0);
+        assert_eq!(pyramid.level_for_scale(2.0), 1);
+        assert_eq!(pyramid.level_for_scale(5.0), 2);
+        assert_eq!(pyramid.level_for_scale(100.0), 3);
+
+        assert!(Pyramid::from_ifds([ifd(512, REDUCED_RESOLUTION)]).is_none());
+    }
+}
@@ -9,10 +9,36 @@ use async_trait::async_trait;
 use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
 use bytes::buf::Reader;
 use bytes::{Buf, Bytes};
-use futures::TryFutureExt;
+use futures::{Stream, StreamExt, TryFutureExt};
 
+#[cfg(feature = "reqwest")]
+use crate::config::AsyncTiffConfig;
+#[cfg(feature = "object_store")]
+use crate::error::AsyncTiffError;
 use crate::error::AsyncTiffResult;
 
+mod block_cache;
+mod data_cache;
+mod disk_cache;
+mod memory;
+#[cfg(all(feature = "mmap", unix))]
+mod mmap;
+mod range_check;
+mod recording;
+mod stats;
+mod throttle;
+
+pub use block_cache::BlockCacheReader;
+pub use data_cache::{DataCacheKey, DataCacheReader, ImageDataCache, LruDataCache};
+pub use disk_cache::CachingReader;
+pub use memory::MemoryReader;
+#[cfg(all(feature = "mmap", unix))]
+pub use mmap::MmapReader;
+pub use range_check::RangeCheckReader;
+pub use recording::{RecordingReader, RequestKind, RequestLog, RequestRecord};
+pub use stats::{MetricsHook, ReadStats, RequestEvent, StatsReader};
+pub use throttle::{Throttle, ThrottledReader};
+
 /// The asynchronous interface used to read COG files
 ///
 /// This was derived from the Parquet
@@ -26,6 +52,9 @@ use crate::error::AsyncTiffResult;
 /// 2. You can use [`TokioReader`] to implement [`AsyncFileReader`] for types that implement
 ///    [`tokio::io::AsyncRead`] and [`tokio::io::AsyncSeek`], for example [`tokio::fs::File`].
 ///
+/// 3. Readers must be `Send + Sync`, so a single reader can be shared as an
+///    `Arc<dyn AsyncFileReader>` between concurrent tasks, e.g. all request handlers of a server.
+///
 /// [`ObjectStore`]: object_store::ObjectStore
 ///
 /// [`tokio::fs::File`]: https://docs.rs/tokio/latest/tokio/fs/struct.File.html
@@ -49,6 +78,14 @@ pub trait AsyncFileReader: Debug + Send + Sync + 'static {
 
         Ok(result)
     }
+
+    /// The total size of the file in bytes, if the reader can learn it.
+    ///
+    /// This is used by [`RangeCheckReader`] to reject byte ranges past the end of the file before
+    /// requesting them. The default implementation returns `None`.
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        Ok(None)
+    }
 }
 
 /// This allows Box<dyn AsyncFileReader + '_> to be used as an AsyncFileReader,
@@ -61,6 +98,10 @@ impl AsyncFileReader for Box<dyn AsyncFileReader + '_> {
     async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
         self.as_ref().get_byte_ranges(ranges).await
     }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        self.as_ref().size().await
+    }
 }
 
 /// This allows Arc<dyn AsyncFileReader + '_> to be used as an AsyncFileReader,
@@ -73,6 +114,32 @@ impl AsyncFileReader for Arc<dyn AsyncFileReader + '_> {
     async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
         self.as_ref().get_byte_ranges(ranges).await
     }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        self.as_ref().size().await
+    }
+}
+
+/// Fetch `ranges` from `reader` as a stream, with at most `max_in_flight` requests running at once.
+///
+/// Unlike [`AsyncFileReader::get_byte_ranges`], which returns only once every range has arrived,
+/// this yields each buffer as soon as it is fetched, so large batch reads can process and drop
+/// buffers as they go. No new requests are started while the consumer isn't polling, which bounds
+/// memory use to roughly `max_in_flight` buffers.
+///
+/// Items are yielded in order of completion, not in the order of `ranges`, so each item includes
+/// the range it belongs to.
+pub fn stream_byte_ranges<'a, R: AsyncFileReader + ?Sized>(
+    reader: &'a R,
+    ranges: Vec<Range<u64>>,
+    max_in_flight: usize,
+) -> impl Stream<Item = AsyncTiffResult<(Range<u64>, Bytes)>> + Send + 'a {
+    futures::stream::iter(ranges)
+        .map(move |range| async move {
+            let bytes = reader.get_bytes(range.clone()).await?;
+            Ok((range, bytes))
+        })
+        .buffer_unordered(max_in_flight.max(1))
 }
 
 /// A wrapper for things that implement [AsyncRead] and [AsyncSeek] to also implement
@@ -98,6 +165,15 @@ impl<T: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin + Send + Debug> Toki
         Self(tokio::sync::Mutex::new(inner))
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "fetch",
+            level = "debug",
+            skip_all,
+            fields(start = range.start, bytes = range.end - range.start)
+        )
+    )]
     async fn make_range_request(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
         use std::io::SeekFrom;
 
@@ -128,9 +204,20 @@ impl<T: tokio::io::AsyncRead + tokio::io::AsyncSeek + Unpin + Send + Debug + 'st
     async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
         self.make_range_request(range).await
     }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        use std::io::SeekFrom;
+
+        use tokio::io::AsyncSeekExt;
+
+        let mut file = self.0.lock().await;
+        Ok(Some(file.seek(SeekFrom::End(0)).await?))
+    }
 }
 
 /// An AsyncFileReader that reads from an [`ObjectStore`][object_store::ObjectStore] instance.
+///
+/// Cloning is cheap, and clones share the same store.
 #[cfg(feature = "object_store")]
 #[derive(Clone, Debug)]
 pub struct ObjectReader {
@@ -146,6 +233,15 @@ impl ObjectReader {
         Self { store, path }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "fetch",
+            level = "debug",
+            skip_all,
+            fields(start = range.start, bytes = range.end - range.start)
+        )
+    )]
     async fn make_range_request(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
         use object_store::ObjectStoreExt;
 
@@ -164,6 +260,18 @@ impl AsyncFileReader for ObjectReader {
         self.make_range_request(range).await
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "fetch_ranges",
+            level = "debug",
+            skip_all,
+            fields(
+                ranges = ranges.len(),
+                bytes = ranges.iter().map(|r| r.end - r.start).sum::<u64>()
+            )
+        )
+    )]
     async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>>
     where
         Self: Send,
@@ -177,36 +285,411 @@ impl AsyncFileReader for ObjectReader {
             .await
             .map_err(|e| e.into())
     }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        use object_store::ObjectStoreExt;
+
+        let meta = self.store.head(&self.path).await?;
+        Ok(Some(meta.size))
+    }
+}
+
+/// Generate a module with an `ObjectReader` for a specific major version of `object_store`.
+///
+/// This lets downstream users whose `object_store` version doesn't match ours read from their
+/// stores without a breaking dependency bump on either side.
+macro_rules! versioned_object_reader {
+    ($(#[$meta:meta])* $module:ident, $krate:ident $(, $ext:ident)?) => {
+        $(#[$meta])*
+        pub mod $module {
+            use std::ops::Range;
+            use std::sync::Arc;
+
+            use async_trait::async_trait;
+            use bytes::Bytes;
+            $(use $krate::$ext;)?
+
+            use crate::error::{AsyncTiffError, AsyncTiffResult};
+            use crate::reader::AsyncFileReader;
+
+            #[doc = concat!(
+                "An AsyncFileReader that reads from an [`ObjectStore`][", stringify!($krate),
+                "::ObjectStore] instance."
+            )]
+            #[derive(Clone, Debug)]
+            pub struct ObjectReader {
+                store: Arc<dyn $krate::ObjectStore>,
+                path: $krate::path::Path,
+            }
+
+            impl ObjectReader {
+                /// Creates a new [`ObjectReader`] for the provided store and path.
+                pub fn new(store: Arc<dyn $krate::ObjectStore>, path: $krate::path::Path) -> Self {
+                    Self { store, path }
+                }
+            }
+
+            #[async_trait]
+            impl AsyncFileReader for ObjectReader {
+                async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+                    self.store
+                        .get_range(&self.path, range)
+                        .await
+                        .map_err(|e| AsyncTiffError::External(Box::new(e)))
+                }
+
+                async fn get_byte_ranges(
+                    &self,
+                    ranges: Vec<Range<u64>>,
+                ) -> AsyncTiffResult<Vec<Bytes>> {
+                    self.store
+                        .get_ranges(&self.path, &ranges)
+                        .await
+                        .map_err(|e| AsyncTiffError::External(Box::new(e)))
+                }
+
+                async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+                    let meta = self
+                        .store
+                        .head(&self.path)
+                        .await
+                        .map_err(|e| AsyncTiffError::External(Box::new(e)))?;
+                    Ok(Some(meta.size as u64))
+                }
+            }
+        }
+    };
+}
+
+versioned_object_reader!(
+    /// Support for `object_store` 0.12, enabled by the `object_store_0_12` feature.
+    ///
+    /// The top-level [`ObjectReader`] uses the current `object_store` version.
+    #[cfg(feature = "object_store_0_12")]
+    object_store_0_12,
+    object_store_0_12
+);
+
+versioned_object_reader!(
+    /// Support for `object_store` 0.13, enabled by the `object_store_0_13` feature.
+    ///
+    /// The top-level [`ObjectReader`] uses the current `object_store` version.
+    #[cfg(feature = "object_store_0_13")]
+    object_store_0_13,
+    object_store_0_13,
+    ObjectStoreExt
+);
+
+/// Construct an [`AsyncFileReader`] for a URL, creating the matching
+/// [`ObjectStore`][object_store::ObjectStore].
+///
+/// Supported schemes are `file://`, `http(s)://`, `s3://`, `gs://` and `az://`, plus the other
+/// URL forms understood by [`object_store::parse_url_opts`]. Local paths without a scheme are
+/// also accepted. Each remote scheme requires the corresponding crate feature (`http`, `aws`,
+/// `gcp` or `azure`) to be enabled.
+///
+/// `options` are passed to the store builder, e.g. `("aws_region", "us-west-2")` or
+/// `("skip_signature", "true")`.
+///
+/// ```no_run
+/// # async fn example() -> async_tiff::error::AsyncTiffResult<()> {
+/// use async_tiff::reader::from_url;
+///
+/// let reader = from_url("file:///data/image.tif", Vec::<(&str, &str)>::new())?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "object_store")]
+pub fn from_url<I, K, V>(url: &str, options: I) -> AsyncTiffResult<Arc<dyn AsyncFileReader>>
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: Into<String>,
+{
+    let url = match url::Url::parse(url) {
+        Ok(url) => url,
+        Err(url::ParseError::RelativeUrlWithoutBase) => {
+            let path = std::path::absolute(url)?;
+            url::Url::from_file_path(&path).map_err(|_| {
+                AsyncTiffError::General(format!("invalid file path: {}", path.display()))
+            })?
+        }
+        Err(err) => return Err(AsyncTiffError::General(format!("invalid URL {url}: {err}"))),
+    };
+    let (store, path) = object_store::parse_url_opts(&url, options)?;
+    Ok(Arc::new(ObjectReader::new(store.into(), path)))
 }
 
 /// An AsyncFileReader that reads from a URL using reqwest.
+///
+/// Use [`ReqwestReader::builder`] to tune the underlying connection pool for large numbers of
+/// concurrent range requests.
+///
+/// Cloning is cheap, and clones share the same connection pool.
 #[cfg(feature = "reqwest")]
 #[derive(Debug, Clone)]
 pub struct ReqwestReader {
     client: reqwest::Client,
     url: reqwest::Url,
+    retry: Option<RetryPolicy>,
 }
 
 #[cfg(feature = "reqwest")]
 impl ReqwestReader {
     /// Construct a new ReqwestReader from a reqwest client and URL.
     pub fn new(client: reqwest::Client, url: reqwest::Url) -> Self {
-        Self { client, url }
+        Self {
+            client,
+            url,
+            retry: AsyncTiffConfig::global().retry().cloned(),
+        }
     }
 
+    /// Retry failed requests according to `policy`, otherwise defaults to the
+    /// [`retry`][AsyncTiffConfig::retry] policy of the global config, which doesn't retry
+    /// requests unless configured.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Create a builder for a ReqwestReader with its own tuned client.
+    pub fn builder(url: reqwest::Url) -> ReqwestReaderBuilder {
+        ReqwestReaderBuilder::new(url)
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "fetch",
+            level = "debug",
+            skip_all,
+            fields(start = range.start, bytes = range.end - range.start)
+        )
+    )]
     async fn make_range_request(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
-        let url = self.url.clone();
-        let client = self.client.clone();
+        self.retrying(|| self.try_range_request(&range)).await
+    }
+
+    /// Call `request` until it succeeds or the retry policy gives up.
+    async fn retrying<T, F, Fut>(&self, mut request: F) -> AsyncTiffResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<T, RequestError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let err = match request().await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            let Some(policy) = self
+                .retry
+                .as_ref()
+                .filter(|policy| attempt < policy.max_attempts && policy.should_retry(&err.error))
+            else {
+                return Err(err.error.into());
+            };
+            let delay = match err.retry_after.filter(|_| policy.honor_retry_after) {
+                Some(retry_after) => retry_after.min(policy.max_backoff),
+                None => policy.backoff(attempt),
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn try_range_request(&self, range: &Range<u64>) -> Result<Bytes, RequestError> {
         // HTTP range is inclusive, so we need to subtract 1 from the end
         let range = format!("bytes={}-{}", range.start, range.end - 1);
-        let response = client
-            .get(url)
-            .header("Range", range)
+        let response = self
+            .client
+            .get(self.url.clone())
+            .header(reqwest::header::RANGE, range)
+            // Ranges refer to the stored bytes, so they must not be transfer-compressed
+            .header(reqwest::header::ACCEPT_ENCODING, "identity")
+            .send()
+            .await?;
+        Ok(check_status(response)?.bytes().await?)
+    }
+
+    /// Learn the file size from the `Content-Length` of a `HEAD` request or, if the server
+    /// doesn't answer it with a length, from the `Content-Range` of a request for the first byte.
+    async fn try_size_request(&self) -> Result<Option<u64>, RequestError> {
+        let header = |response: &reqwest::Response, name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok().map(str::to_string))
+        };
+        let response = self
+            .client
+            .head(self.url.clone())
+            .header(reqwest::header::ACCEPT_ENCODING, "identity")
+            .send()
+            .await?;
+        if response.status().is_success() {
+            let length = header(&response, reqwest::header::CONTENT_LENGTH);
+            if let Some(length) = length.and_then(|length| length.parse().ok()) {
+                return Ok(Some(length));
+            }
+        }
+
+        let response = self
+            .client
+            .get(self.url.clone())
+            .header(reqwest::header::RANGE, "bytes=0-0")
+            .header(reqwest::header::ACCEPT_ENCODING, "identity")
             .send()
-            .await?
-            .error_for_status()?;
-        let bytes = response.bytes().await?;
-        Ok(bytes)
+            .await?;
+        let response = check_status(response)?;
+        if response.status() == reqwest::StatusCode::PARTIAL_CONTENT {
+            // e.g. "bytes 0-0/1234", where the total may be "*" if unknown
+            Ok(header(&response, reqwest::header::CONTENT_RANGE)
+                .and_then(|range| range.rsplit_once('/')?.1.parse().ok()))
+        } else {
+            // The server ignored the range and would send the whole file
+            Ok(header(&response, reqwest::header::CONTENT_LENGTH)
+                .and_then(|length| length.parse().ok()))
+        }
+    }
+}
+
+/// Turn an error status of `response` into a [`RequestError`], with the delay requested by its
+/// `Retry-After` header.
+#[cfg(feature = "reqwest")]
+fn check_status(response: reqwest::Response) -> Result<reqwest::Response, RequestError> {
+    if let Err(error) = response.error_for_status_ref() {
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok()?.trim().parse().ok())
+            .map(std::time::Duration::from_secs);
+        return Err(RequestError { error, retry_after });
+    }
+    Ok(response)
+}
+
+/// A failed request, with the delay requested by the server's `Retry-After` header, if any.
+#[cfg(feature = "reqwest")]
+struct RequestError {
+    error: reqwest::Error,
+    retry_after: Option<std::time::Duration>,
+}
+
+#[cfg(feature = "reqwest")]
+impl From<reqwest::Error> for RequestError {
+    fn from(error: reqwest::Error) -> Self {
+        Self {
+            error,
+            retry_after: None,
+        }
+    }
+}
+
+/// When and how often a [`ReqwestReader`] retries failed requests.
+///
+/// Requests are retried after timeouts, connection errors and responses with one of the
+/// [`retry_statuses`][Self::with_retry_statuses], waiting `initial_backoff * 2^(attempt - 1)`
+/// between attempts, up to `max_backoff`. If the server sends a `Retry-After` header with a
+/// number of seconds, that delay is used instead.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use async_tiff::reader::{ReqwestReader, RetryPolicy};
+///
+/// let url = "https://example.com/image.tif".parse().unwrap();
+/// let reader = ReqwestReader::builder(url)
+///     .with_retry(
+///         RetryPolicy::new()
+///             .with_max_attempts(5)
+///             .with_initial_backoff(Duration::from_millis(200)),
+///     )
+///     .build()
+///     .unwrap();
+/// ```
+#[cfg(feature = "reqwest")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: std::time::Duration,
+    max_backoff: std::time::Duration,
+    retry_statuses: Vec<u16>,
+    honor_retry_after: bool,
+}
+
+#[cfg(feature = "reqwest")]
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: std::time::Duration::from_millis(100),
+            max_backoff: std::time::Duration::from_secs(10),
+            retry_statuses: vec![408, 429, 500, 502, 503, 504],
+            honor_retry_after: true,
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl RetryPolicy {
+    /// Create a new policy with the defaults: 3 attempts, starting with a 100 ms backoff.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the total number of attempts per request, including the first one, otherwise
+    /// defaults to 3.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay before the first retry, otherwise defaults to 100 ms.
+    pub fn with_initial_backoff(mut self, backoff: std::time::Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Set the maximum delay between attempts, otherwise defaults to 10 seconds.
+    pub fn with_max_backoff(mut self, backoff: std::time::Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Set the HTTP status codes that are retried, otherwise defaults to 408, 429, 500, 502, 503
+    /// and 504.
+    pub fn with_retry_statuses(mut self, statuses: impl IntoIterator<Item = u16>) -> Self {
+        self.retry_statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Whether to wait for the delay in a `Retry-After` header instead of the backoff, otherwise
+    /// defaults to true.
+    pub fn with_honor_retry_after(mut self, honor: bool) -> Self {
+        self.honor_retry_after = honor;
+        self
+    }
+
+    /// The total number of attempts per request.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// The delay after the failed attempt number `attempt`, starting at 1.
+    pub fn backoff(&self, attempt: u32) -> std::time::Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    fn should_retry(&self, error: &reqwest::Error) -> bool {
+        match error.status() {
+            Some(status) => self.retry_statuses.contains(&status.as_u16()),
+            None => error.is_timeout() || error.is_connect(),
+        }
     }
 }
 
@@ -216,6 +699,159 @@ impl AsyncFileReader for ReqwestReader {
     async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
         self.make_range_request(range).await
     }
+
+    /// Fetch all ranges concurrently, sharing the client's connection pool. Over HTTP/2 the
+    /// requests are multiplexed on a single connection.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "fetch_ranges",
+            level = "debug",
+            skip_all,
+            fields(
+                ranges = ranges.len(),
+                bytes = ranges.iter().map(|r| r.end - r.start).sum::<u64>()
+            )
+        )
+    )]
+    async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
+        futures::future::try_join_all(ranges.into_iter().map(|r| self.make_range_request(r))).await
+    }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        self.retrying(|| self.try_size_request()).await
+    }
+}
+
+/// A builder for a [`ReqwestReader`] with connection tuning options.
+///
+/// Compressed transfer encodings are always disabled, since byte ranges refer to the stored file.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use async_tiff::reader::ReqwestReader;
+///
+/// let url = "https://example.com/image.tif".parse().unwrap();
+/// let reader = ReqwestReader::builder(url)
+///     .with_pool_max_idle_per_host(64)
+///     .with_tcp_keepalive(Duration::from_secs(30))
+///     .with_http2_prior_knowledge(true)
+///     .build()
+///     .unwrap();
+/// ```
+#[cfg(feature = "reqwest")]
+#[derive(Debug, Clone)]
+pub struct ReqwestReaderBuilder {
+    url: reqwest::Url,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<std::time::Duration>,
+    tcp_keepalive: Option<std::time::Duration>,
+    connect_timeout: Option<std::time::Duration>,
+    timeout: Option<std::time::Duration>,
+    http2_prior_knowledge: bool,
+    http2_adaptive_window: bool,
+    retry: Option<RetryPolicy>,
+}
+
+#[cfg(feature = "reqwest")]
+impl ReqwestReaderBuilder {
+    fn new(url: reqwest::Url) -> Self {
+        Self {
+            url,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            tcp_keepalive: None,
+            connect_timeout: None,
+            timeout: None,
+            http2_prior_knowledge: false,
+            http2_adaptive_window: false,
+            retry: AsyncTiffConfig::global().retry().cloned(),
+        }
+    }
+
+    /// Set the maximum number of idle connections kept open per host, otherwise unlimited.
+    pub fn with_pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Set how long idle connections are kept open, otherwise defaults to 90 seconds.
+    pub fn with_pool_idle_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Enable TCP keep-alive with the given interval.
+    pub fn with_tcp_keepalive(mut self, interval: std::time::Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self
+    }
+
+    /// Set a timeout for establishing connections.
+    pub fn with_connect_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set a timeout for each request, from connecting until the response body is read.
+    pub fn with_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Only use HTTP/2, without negotiating the protocol first.
+    ///
+    /// All concurrent range requests to a host are then multiplexed on one connection. Only
+    /// enable this for servers known to support HTTP/2.
+    pub fn with_http2_prior_knowledge(mut self, enabled: bool) -> Self {
+        self.http2_prior_knowledge = enabled;
+        self
+    }
+
+    /// Use adaptive flow control for HTTP/2 connections, which improves throughput for large
+    /// responses on high-latency links.
+    pub fn with_http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.http2_adaptive_window = enabled;
+        self
+    }
+
+    /// Retry failed requests according to `policy`, otherwise defaults to the
+    /// [`retry`][AsyncTiffConfig::retry] policy of the global config.
+    pub fn with_retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Build the client and create the reader.
+    pub fn build(self) -> AsyncTiffResult<ReqwestReader> {
+        let mut builder = reqwest::Client::builder()
+            .no_gzip()
+            .http2_adaptive_window(self.http2_adaptive_window);
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(interval) = self.tcp_keepalive {
+            builder = builder.tcp_keepalive(interval);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        let reader = ReqwestReader::new(builder.build()?, self.url);
+        Ok(match self.retry {
+            Some(policy) => reader.with_retry(policy),
+            None => reader,
+        })
+    }
 }
 
 /// Endianness
@@ -348,3 +984,157 @@ impl Read for EndianAwareReader {
         self.reader.read(buf)
     }
 }
+
+#[cfg(all(test, feature = "object_store"))]
+mod test {
+    use super::*;
+
+    fn fixture(filename: &str) -> std::path::PathBuf {
+        std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures/image-tiff")
+            .join(filename)
+    }
+
+    #[tokio::test]
+    async fn test_from_url_local() {
+        let path = fixture("tiled-rgb-u8.tif");
+        let file_url = url::Url::from_file_path(&path).unwrap();
+        for url in [path.to_str().unwrap(), file_url.as_str()] {
+            let reader = from_url(url, Vec::<(&str, &str)>::new()).unwrap();
+            let magic = reader.get_bytes(0..2).await.unwrap();
+            assert_eq!(magic.as_ref(), b"II");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_byte_ranges() {
+        let reader = from_url(
+            fixture("tiled-rgb-u8.tif").to_str().unwrap(),
+            Vec::<(&str, &str)>::new(),
+        )
+        .unwrap();
+        let ranges = (0..10).map(|i| i * 100..i * 100 + 50).collect::<Vec<_>>();
+        let mut results = stream_byte_ranges(&reader, ranges.clone(), 3)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<AsyncTiffResult<Vec<_>>>()
+            .unwrap();
+        results.sort_by_key(|(range, _)| range.start);
+        let expected = reader.get_byte_ranges(ranges.clone()).await.unwrap();
+        assert_eq!(
+            results.iter().map(|(r, _)| r.clone()).collect::<Vec<_>>(),
+            ranges
+        );
+        assert_eq!(
+            results.into_iter().map(|(_, b)| b).collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_from_url_invalid() {
+        assert!(from_url("http://[invalid", Vec::<(&str, &str)>::new()).is_err());
+        // No store supports this scheme, whichever features are enabled
+        assert!(from_url("ftp://host/key.tif", Vec::<(&str, &str)>::new()).is_err());
+    }
+}
+
+#[cfg(all(test, feature = "reqwest"))]
+mod reqwest_test {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::time::Duration;
+
+    use super::*;
+
+    /// Serve one HTTP response per connection from `responses`, in order, and return the URL.
+    fn serve(responses: Vec<&'static str>) -> reqwest::Url {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/image.tif", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for (response, stream) in responses.into_iter().zip(listener.incoming()) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        url.parse().unwrap()
+    }
+
+    const UNAVAILABLE: &str =
+        "HTTP/1.1 503 Service Unavailable\r\nRetry-After: 0\r\nConnection: close\r\nContent-Length: 0\r\n\r\n";
+    const NOT_FOUND: &str =
+        "HTTP/1.1 404 Not Found\r\nConnection: close\r\nContent-Length: 0\r\n\r\n";
+    const OK: &str =
+        "HTTP/1.1 206 Partial Content\r\nConnection: close\r\nContent-Length: 2\r\n\r\nII";
+
+    #[tokio::test]
+    async fn test_reqwest_retry() {
+        let policy = RetryPolicy::new().with_initial_backoff(Duration::from_millis(1));
+
+        let url = serve(vec![UNAVAILABLE, UNAVAILABLE, OK]);
+        let reader = ReqwestReader::builder(url)
+            .with_retry(policy.clone())
+            .build()
+            .unwrap();
+        assert_eq!(reader.get_bytes(0..2).await.unwrap().as_ref(), b"II");
+
+        // Not retried without a policy, or for statuses not in the list
+        let url = serve(vec![UNAVAILABLE, OK]);
+        let reader = ReqwestReader::new(reqwest::Client::new(), url);
+        assert!(reader.get_bytes(0..2).await.is_err());
+        let url = serve(vec![NOT_FOUND, OK]);
+        let reader = ReqwestReader::new(reqwest::Client::new(), url).with_retry(policy.clone());
+        assert!(reader.get_bytes(0..2).await.is_err());
+
+        // Gives up after max_attempts
+        let url = serve(vec![UNAVAILABLE, UNAVAILABLE, OK]);
+        let reader =
+            ReqwestReader::new(reqwest::Client::new(), url).with_retry(policy.with_max_attempts(2));
+        assert!(reader.get_bytes(0..2).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reqwest_size() {
+        let url = serve(vec![
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 1234\r\n\r\n",
+        ]);
+        let reader = ReqwestReader::new(reqwest::Client::new(), url);
+        assert_eq!(reader.size().await.unwrap(), Some(1234));
+
+        // Falls back to the Content-Range of a request for the first byte
+        let url = serve(vec![
+            "HTTP/1.1 405 Method Not Allowed\r\nConnection: close\r\nContent-Length: 0\r\n\r\n",
+            "HTTP/1.1 206 Partial Content\r\nConnection: close\r\nContent-Range: bytes 0-0/5678\r\nContent-Length: 1\r\n\r\nI",
+        ]);
+        let reader = ReqwestReader::new(reqwest::Client::new(), url);
+        assert_eq!(reader.size().await.unwrap(), Some(5678));
+
+        // which lets RangeCheckReader reject ranges past the end of the file
+        let url = serve(vec![
+            "HTTP/1.1 200 OK\r\nConnection: close\r\nContent-Length: 1234\r\n\r\n",
+        ]);
+        let reader = RangeCheckReader::new(ReqwestReader::new(reqwest::Client::new(), url));
+        assert!(matches!(
+            reader.get_bytes(1000..2000).await,
+            Err(AsyncTiffError::InvalidByteRange { .. })
+        ));
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let policy = RetryPolicy::new()
+            .with_initial_backoff(Duration::from_millis(100))
+            .with_max_backoff(Duration::from_millis(500));
+        let backoff = (1..=5).map(|i| policy.backoff(i)).collect::<Vec<_>>();
+        assert_eq!(
+            backoff,
+            [100, 200, 400, 500, 500].map(Duration::from_millis)
+        );
+    }
+}
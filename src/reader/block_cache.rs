@@ -0,0 +1,262 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+
+use crate::error::AsyncTiffResult;
+use crate::lru::Lru;
+use crate::reader::AsyncFileReader;
+
+/// An [`AsyncFileReader`] that reads in fixed-size blocks and keeps recently used blocks in memory.
+///
+/// Every request is rounded out to whole blocks, and blocks that aren't cached yet are fetched
+/// from the inner reader, with adjacent missing blocks merged into a single request. This turns
+/// many small, scattered reads (e.g. of metadata or small tiles) into fewer block-aligned requests
+/// that are also friendly to HTTP caches, similar to GDAL's `/vsicurl/`.
+///
+/// Once more than [`capacity`][Self::with_capacity] blocks are cached, the least recently used
+/// blocks are evicted.
+///
+/// The cache is guarded by a mutex, so wrap the reader in an `Arc` to share one cache between
+/// concurrent tasks.
+#[derive(Debug)]
+pub struct BlockCacheReader<R: AsyncFileReader> {
+    inner: R,
+    block_size: u64,
+    cache: Mutex<Lru<u64, Bytes>>,
+}
+
+impl<R: AsyncFileReader> BlockCacheReader<R> {
+    /// Wrap `inner` with 16 KiB blocks and room for 1024 blocks (16 MiB).
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            block_size: 16 * 1024,
+            cache: Mutex::new(Lru::with_len(1024)),
+        }
+    }
+
+    /// Set the block size in bytes, otherwise defaults to 16 KiB.
+    ///
+    /// Panics if `block_size` is zero.
+    pub fn with_block_size(mut self, block_size: u64) -> Self {
+        assert!(block_size > 0, "block size must be non-zero");
+        self.block_size = block_size;
+        self
+    }
+
+    /// Set the maximum number of cached blocks, otherwise defaults to 1024.
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.cache = Mutex::new(Lru::with_len(capacity));
+        self
+    }
+
+    /// Access the inner reader.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// The block size in bytes.
+    pub fn block_size(&self) -> u64 {
+        self.block_size
+    }
+
+    /// The number of blocks currently cached.
+    pub fn cached_blocks(&self) -> usize {
+        self.cache.lock().unwrap().len()
+    }
+
+    fn block_range(&self, index: u64) -> Range<u64> {
+        index * self.block_size..(index + 1) * self.block_size
+    }
+
+    fn block_indices(&self, range: &Range<u64>) -> Range<u64> {
+        if range.is_empty() {
+            return 0..0;
+        }
+        range.start / self.block_size..range.end.div_ceil(self.block_size)
+    }
+
+    /// Slice `range` out of the blocks covering it. A short block ends the data, as at the end of
+    /// the file.
+    fn assemble(&self, range: &Range<u64>, blocks: &HashMap<u64, Bytes>) -> Bytes {
+        let indices = self.block_indices(range);
+        let mut out = BytesMut::with_capacity((range.end - range.start) as usize);
+        for index in indices.clone() {
+            let block = &blocks[&index];
+            let block_start = index * self.block_size;
+            let start = range
+                .start
+                .saturating_sub(block_start)
+                .min(block.len() as u64);
+            let end = (range.end - block_start).min(block.len() as u64);
+            let chunk = block.slice(start as usize..end as usize);
+            if indices.end - indices.start == 1 {
+                return chunk;
+            }
+            out.extend_from_slice(&chunk);
+            if (block.len() as u64) < self.block_size {
+                break;
+            }
+        }
+        out.freeze()
+    }
+}
+
+#[async_trait]
+impl<R: AsyncFileReader> AsyncFileReader for BlockCacheReader<R> {
+    async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+        let mut result = self.get_byte_ranges(vec![range]).await?;
+        Ok(result.remove(0))
+    }
+
+    async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
+        // Take the cached blocks up front, so that eviction during the fetch can't remove them
+        let mut blocks = HashMap::new();
+        let mut missing = ranges
+            .iter()
+            .flat_map(|range| self.block_indices(range))
+            .collect::<Vec<_>>();
+        missing.sort_unstable();
+        missing.dedup();
+        {
+            let mut cache = self.cache.lock().unwrap();
+            missing.retain(|index| match cache.get(index) {
+                Some(block) => {
+                    blocks.insert(*index, block.clone());
+                    false
+                }
+                None => true,
+            });
+        }
+
+        // Merge runs of adjacent missing blocks into single requests
+        let mut runs: Vec<Range<u64>> = vec![];
+        for index in missing {
+            match runs.last_mut() {
+                Some(run) if run.end == index => run.end += 1,
+                _ => runs.push(index..index + 1),
+            }
+        }
+        let fetch_ranges = runs
+            .iter()
+            .map(|run| run.start * self.block_size..run.end * self.block_size)
+            .collect();
+        let fetched = if runs.is_empty() {
+            vec![]
+        } else {
+            self.inner.get_byte_ranges(fetch_ranges).await?
+        };
+
+        let mut cache = self.cache.lock().unwrap();
+        for (run, bytes) in runs.into_iter().zip(fetched) {
+            for index in run.clone() {
+                let block_range = self.block_range(index);
+                let start =
+                    ((block_range.start - run.start * self.block_size) as usize).min(bytes.len());
+                let end =
+                    ((block_range.end - run.start * self.block_size) as usize).min(bytes.len());
+                let block = bytes.slice(start..end);
+                cache.insert(index, block.clone());
+                blocks.insert(index, block);
+            }
+        }
+        drop(cache);
+
+        Ok(ranges
+            .iter()
+            .map(|range| self.assemble(range, &blocks))
+            .collect())
+    }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        self.inner.size().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct CountingReader {
+        data: Bytes,
+        requests: Mutex<Vec<Range<u64>>>,
+        calls: AtomicUsize,
+    }
+
+    impl CountingReader {
+        fn new(len: u8) -> Self {
+            Self {
+                data: (0..len).collect::<Vec<_>>().into(),
+                requests: Mutex::new(vec![]),
+                calls: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl AsyncFileReader for CountingReader {
+        async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+            self.requests.lock().unwrap().push(range.clone());
+            let end = (range.end as usize).min(self.data.len());
+            Ok(self.data.slice((range.start as usize).min(end)..end))
+        }
+
+        async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let mut result = vec![];
+            for range in ranges {
+                result.push(self.get_bytes(range).await?);
+            }
+            Ok(result)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_block_cache_reader() {
+        let reader = BlockCacheReader::new(CountingReader::new(100))
+            .with_block_size(10)
+            .with_capacity(4);
+
+        // Rounded out to blocks 0 and 1
+        assert_eq!(
+            reader.get_bytes(5..15).await.unwrap().as_ref(),
+            &[5, 6, 7, 8, 9, 10, 11, 12, 13, 14]
+        );
+        assert_eq!(reader.inner().requests.lock().unwrap()[0], 0..20);
+
+        // Served from the cache
+        assert_eq!(reader.get_bytes(12..13).await.unwrap().as_ref(), &[12]);
+        assert_eq!(reader.inner().requests.lock().unwrap().len(), 1);
+
+        // Only the missing blocks are fetched, with adjacent blocks in one request
+        let results = reader
+            .get_byte_ranges(vec![8..12, 30..45, 18..22, 60..61])
+            .await
+            .unwrap();
+        assert_eq!(results[0].as_ref(), &[8, 9, 10, 11]);
+        assert_eq!(results[1].as_ref(), (30..45).collect::<Vec<u8>>());
+        assert_eq!(results[2].as_ref(), &[18, 19, 20, 21]);
+        assert_eq!(results[3].as_ref(), &[60]);
+        assert_eq!(
+            reader.inner().requests.lock().unwrap()[1..],
+            [20..50, 60..70]
+        );
+        assert_eq!(reader.inner().calls.load(Ordering::SeqCst), 2);
+
+        // Least recently used blocks are evicted
+        assert_eq!(reader.cached_blocks(), 4);
+
+        // Reads past the end of the data are truncated
+        assert_eq!(
+            reader.get_bytes(95..105).await.unwrap().as_ref(),
+            &[95, 96, 97, 98, 99]
+        );
+    }
+}
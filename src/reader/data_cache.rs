@@ -0,0 +1,209 @@
+use std::fmt::Debug;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::error::AsyncTiffResult;
+use crate::lru::Lru;
+use crate::reader::AsyncFileReader;
+
+/// The key of a cached byte range: the path of the file it was read from, and the range itself.
+pub type DataCacheKey = (Arc<str>, Range<u64>);
+
+/// A cache of byte ranges fetched for image data, used by [`DataCacheReader`].
+///
+/// Entries are keyed by file path as well as byte range, so one cache can be shared between the
+/// readers of many files, e.g. by a tile server re-serving the same COG tiles. Only exact ranges
+/// are looked up, which suits tiles that are always requested with the same byte range; use
+/// [`BlockCacheReader`][crate::reader::BlockCacheReader] to cache arbitrary ranges.
+pub trait ImageDataCache: Debug + Send + Sync + 'static {
+    /// The cached bytes for `key`, if any.
+    fn get(&self, key: &DataCacheKey) -> Option<Bytes>;
+
+    /// Cache `value` for `key`.
+    fn insert(&self, key: DataCacheKey, value: Bytes);
+}
+
+/// An in-memory [`ImageDataCache`] that evicts the least recently used ranges once it holds more
+/// than [`capacity`][Self::capacity] bytes.
+///
+/// The cache is guarded by a mutex, so it can be shared between readers and tasks as an
+/// `Arc<LruDataCache>`.
+#[derive(Debug)]
+pub struct LruDataCache {
+    capacity: u64,
+    entries: Mutex<Lru<DataCacheKey, Bytes>>,
+}
+
+impl LruDataCache {
+    /// Create an empty cache holding up to `capacity` bytes.
+    pub fn new(capacity: u64) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(Lru::new(capacity, |value| value.len() as u64)),
+        }
+    }
+
+    /// The maximum number of bytes held by the cache.
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    /// The number of bytes currently cached.
+    pub fn size(&self) -> u64 {
+        self.entries.lock().unwrap().weight()
+    }
+
+    /// The number of ranges currently cached.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ImageDataCache for LruDataCache {
+    fn get(&self, key: &DataCacheKey) -> Option<Bytes> {
+        self.entries.lock().unwrap().get(key).cloned()
+    }
+
+    fn insert(&self, key: DataCacheKey, value: Bytes) {
+        self.entries.lock().unwrap().insert(key, value);
+    }
+}
+
+/// An [`AsyncFileReader`] that serves repeated requests for the same byte ranges from an
+/// [`ImageDataCache`].
+///
+/// Ranges that aren't cached are fetched from the inner reader in a single
+/// [`get_byte_ranges`][AsyncFileReader::get_byte_ranges] call and then added to the cache.
+/// `path` identifies the file in the cache, so it must be unique among the readers sharing a
+/// cache.
+#[derive(Debug, Clone)]
+pub struct DataCacheReader<R: AsyncFileReader> {
+    inner: R,
+    path: Arc<str>,
+    cache: Arc<dyn ImageDataCache>,
+}
+
+impl<R: AsyncFileReader> DataCacheReader<R> {
+    /// Wrap `inner`, caching its ranges under `path` in `cache`.
+    pub fn new(inner: R, path: impl Into<Arc<str>>, cache: Arc<dyn ImageDataCache>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            cache,
+        }
+    }
+
+    /// Access the inner reader.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// The path identifying this file in the cache.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Access the cache of this reader.
+    pub fn cache(&self) -> &Arc<dyn ImageDataCache> {
+        &self.cache
+    }
+}
+
+#[async_trait]
+impl<R: AsyncFileReader> AsyncFileReader for DataCacheReader<R> {
+    async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+        let mut result = self.get_byte_ranges(vec![range]).await?;
+        Ok(result.remove(0))
+    }
+
+    async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
+        let mut result = ranges
+            .iter()
+            .map(|range| self.cache.get(&(self.path.clone(), range.clone())))
+            .collect::<Vec<_>>();
+        let missing = result
+            .iter()
+            .zip(&ranges)
+            .filter(|(cached, _)| cached.is_none())
+            .map(|(_, range)| range.clone())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            let fetched = self.inner.get_byte_ranges(missing.clone()).await?;
+            let mut fetched = missing.into_iter().zip(fetched);
+            for slot in result.iter_mut().filter(|slot| slot.is_none()) {
+                let Some((range, bytes)) = fetched.next() else {
+                    break;
+                };
+                self.cache.insert((self.path.clone(), range), bytes.clone());
+                *slot = Some(bytes);
+            }
+        }
+        Ok(result.into_iter().map(Option::unwrap_or_default).collect())
+    }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        self.inner.size().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct CountingReader {
+        requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl AsyncFileReader for CountingReader {
+        async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            Ok((range.start as u8..range.end as u8)
+                .collect::<Vec<_>>()
+                .into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_data_cache_reader() {
+        let cache = Arc::new(LruDataCache::new(8));
+        let a = DataCacheReader::new(CountingReader::default(), "a.tif", cache.clone());
+        let b = DataCacheReader::new(CountingReader::default(), "b.tif", cache.clone());
+
+        let ranges = vec![0..2, 4..6];
+        assert_eq!(
+            a.get_byte_ranges(ranges.clone()).await.unwrap()[1],
+            &[4, 5][..]
+        );
+        assert_eq!(a.get_byte_ranges(ranges).await.unwrap()[0], &[0, 1][..]);
+        assert_eq!(a.inner().requests.load(Ordering::SeqCst), 2);
+
+        // The same range of another file is not shared
+        b.get_bytes(0..2).await.unwrap();
+        assert_eq!(b.inner().requests.load(Ordering::SeqCst), 1);
+        assert_eq!((cache.len(), cache.size()), (3, 6));
+
+        // Evicts the least recently used range, 4..6 of a.tif
+        a.get_bytes(0..2).await.unwrap();
+        b.get_bytes(2..6).await.unwrap();
+        assert_eq!((cache.len(), cache.size()), (3, 8));
+        a.get_bytes(4..6).await.unwrap();
+        assert_eq!(a.inner().requests.load(Ordering::SeqCst), 3);
+
+        // Ranges larger than the cache are not cached
+        a.get_bytes(0..16).await.unwrap();
+        a.get_bytes(0..16).await.unwrap();
+        assert_eq!(a.inner().requests.load(Ordering::SeqCst), 5);
+    }
+}
@@ -0,0 +1,287 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::error::AsyncTiffResult;
+use crate::reader::AsyncFileReader;
+
+/// The extension of cached range files, so unrelated files in the directory are left alone.
+const EXTENSION: &str = "range";
+
+/// An [`AsyncFileReader`] that persists fetched byte ranges in a local directory, so that remote
+/// TIFFs can be reopened by later processes without refetching their metadata or tiles.
+///
+/// Each range is stored in its own file, named after a hash of the reader's `key` (e.g. the URL
+/// of the file) and the byte range, so the directory listing is the index of the cache. Only
+/// exact ranges are looked up. Several readers and processes can share a directory as long as
+/// keys are unique per file; entries are written to a temporary file first and then renamed into
+/// place.
+///
+/// Entries older than the [`ttl`][Self::with_ttl] are refetched. Once the directory holds more
+/// than [`max_size`][Self::with_max_size] bytes, the least recently written entries are removed.
+///
+/// The cache uses blocking filesystem calls, so it is meant for local disks. Failures to read or
+/// write the cache are treated as cache misses rather than errors.
+#[derive(Debug)]
+pub struct CachingReader<R: AsyncFileReader> {
+    inner: R,
+    dir: PathBuf,
+    key: String,
+    ttl: Option<Duration>,
+    max_size: Option<u64>,
+    /// Bytes written since the directory size was last checked
+    written: AtomicU64,
+}
+
+impl<R: AsyncFileReader> CachingReader<R> {
+    /// Wrap `inner`, caching its ranges under `key` in `dir`, which is created if it doesn't
+    /// exist.
+    pub fn try_new(
+        inner: R,
+        dir: impl Into<PathBuf>,
+        key: impl Into<String>,
+    ) -> AsyncTiffResult<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            inner,
+            dir,
+            key: key.into(),
+            ttl: None,
+            max_size: None,
+            written: AtomicU64::new(0),
+        })
+    }
+
+    /// Refetch entries that were written more than `ttl` ago, otherwise entries never expire.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Remove the oldest entries once the cache directory holds more than `max_size` bytes,
+    /// otherwise the cache grows without bounds.
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Access the inner reader.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// The cache directory.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The key identifying this file in the cache.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    fn entry_path(&self, range: &Range<u64>) -> PathBuf {
+        self.dir.join(format!(
+            "{:016x}-{}-{}.{EXTENSION}",
+            fnv1a(self.key.as_bytes()),
+            range.start,
+            range.end
+        ))
+    }
+
+    fn expired(&self, modified: SystemTime) -> bool {
+        self.ttl.is_some_and(|ttl| {
+            SystemTime::now()
+                .duration_since(modified)
+                .is_ok_and(|age| age > ttl)
+        })
+    }
+
+    fn read_entry(&self, range: &Range<u64>) -> Option<Bytes> {
+        let path = self.entry_path(range);
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+        if self.expired(modified) {
+            let _ = fs::remove_file(&path);
+            return None;
+        }
+        fs::read(path).ok().map(Bytes::from)
+    }
+
+    fn write_entry(&self, range: &Range<u64>, bytes: &Bytes) {
+        let path = self.entry_path(range);
+        let tmp = path.with_extension(format!("{}.tmp", std::process::id()));
+        if fs::write(&tmp, bytes)
+            .and_then(|_| fs::rename(&tmp, &path))
+            .is_err()
+        {
+            let _ = fs::remove_file(&tmp);
+            return;
+        }
+        let Some(max_size) = self.max_size else {
+            return;
+        };
+        // Only list the directory once enough has been written to possibly exceed the limit
+        let written = self
+            .written
+            .fetch_add(bytes.len() as u64, Ordering::Relaxed);
+        if written + bytes.len() as u64 > max_size / 8 {
+            self.written.store(0, Ordering::Relaxed);
+            let _ = self.evict(max_size);
+        }
+    }
+
+    /// Remove the least recently written entries until the directory holds at most `max_size`
+    /// bytes of entries.
+    fn evict(&self, max_size: u64) -> std::io::Result<()> {
+        let mut entries = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == EXTENSION) {
+                let metadata = fs::metadata(&path)?;
+                entries.push((metadata.modified()?, metadata.len(), path));
+            }
+        }
+        let mut size = entries.iter().map(|(_, len, _)| len).sum::<u64>();
+        entries.sort();
+        for (_, len, path) in entries {
+            if size <= max_size {
+                break;
+            }
+            match fs::remove_file(path) {
+                // Another reader may have removed it already
+                Ok(()) => size -= len,
+                Err(err) if err.kind() == ErrorKind::NotFound => size -= len,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<R: AsyncFileReader> AsyncFileReader for CachingReader<R> {
+    async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+        let mut result = self.get_byte_ranges(vec![range]).await?;
+        Ok(result.remove(0))
+    }
+
+    async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
+        let mut result = ranges
+            .iter()
+            .map(|range| self.read_entry(range))
+            .collect::<Vec<_>>();
+        let missing = result
+            .iter()
+            .zip(&ranges)
+            .filter(|(cached, _)| cached.is_none())
+            .map(|(_, range)| range.clone())
+            .collect::<Vec<_>>();
+        if !missing.is_empty() {
+            let fetched = self.inner.get_byte_ranges(missing.clone()).await?;
+            let mut fetched = missing.into_iter().zip(fetched);
+            for slot in result.iter_mut().filter(|slot| slot.is_none()) {
+                let Some((range, bytes)) = fetched.next() else {
+                    break;
+                };
+                self.write_entry(&range, &bytes);
+                *slot = Some(bytes);
+            }
+        }
+        Ok(result.into_iter().map(Option::unwrap_or_default).collect())
+    }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        self.inner.size().await
+    }
+}
+
+/// The 64-bit FNV-1a hash of `bytes`, which unlike the std hashers is stable across releases.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::test::util::temp_path;
+
+    #[derive(Debug, Default)]
+    struct CountingReader {
+        requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl AsyncFileReader for CountingReader {
+        async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            Ok((range.start as u8..range.end as u8)
+                .collect::<Vec<_>>()
+                .into())
+        }
+    }
+
+    fn requests(reader: &CachingReader<CountingReader>) -> usize {
+        reader.inner().requests.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn test_caching_reader() {
+        let dir = temp_path("cache");
+        let reader = CachingReader::try_new(CountingReader::default(), &dir, "a.tif").unwrap();
+        let result = reader.get_byte_ranges(vec![0..2, 4..8]).await.unwrap();
+        assert_eq!(result[1], &[4, 5, 6, 7][..]);
+        assert_eq!(requests(&reader), 2);
+
+        // A new reader, e.g. in another process, finds the cached ranges
+        let reader = CachingReader::try_new(CountingReader::default(), &dir, "a.tif").unwrap();
+        assert_eq!(reader.get_bytes(4..8).await.unwrap(), &[4, 5, 6, 7][..]);
+        assert_eq!(requests(&reader), 0);
+        let other = CachingReader::try_new(CountingReader::default(), &dir, "b.tif").unwrap();
+        other.get_bytes(4..8).await.unwrap();
+        assert_eq!(requests(&other), 1);
+
+        let reader = reader.with_ttl(Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(10));
+        reader.get_bytes(4..8).await.unwrap();
+        assert_eq!(requests(&reader), 1);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_caching_reader_max_size() {
+        let dir = temp_path("cache");
+        let reader = CachingReader::try_new(CountingReader::default(), &dir, "a.tif")
+            .unwrap()
+            .with_max_size(20);
+        for start in [0, 10, 20] {
+            reader.get_bytes(start..start + 10).await.unwrap();
+            // Make sure modification times differ
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let size = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().metadata().unwrap().len())
+            .sum::<u64>();
+        assert!(size <= 20);
+
+        // The oldest range was evicted
+        reader.get_bytes(20..30).await.unwrap();
+        assert_eq!(requests(&reader), 3);
+        reader.get_bytes(0..10).await.unwrap();
+        assert_eq!(requests(&reader), 4);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
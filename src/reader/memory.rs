@@ -0,0 +1,111 @@
+use std::ops::Range;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::reader::AsyncFileReader;
+
+/// An [`AsyncFileReader`] over a file that is already in memory.
+///
+/// This is useful for tests and for serverless handlers that receive a whole file, and doesn't
+/// need the `object_store` feature. Ranges are returned as zero-copy slices of the data.
+///
+/// Like object stores, a range that extends past the end of the data is truncated, so metadata
+/// caches can read ahead of small files. A range that starts past the end returns
+/// [`AsyncTiffError::EndOfFile`].
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// use std::sync::Arc;
+///
+/// use async_tiff::reader::{AsyncFileReader, MemoryReader};
+///
+/// let data = std::fs::read("fixtures/image-tiff/tiled-rgb-u8.tif").unwrap();
+/// let reader = Arc::new(MemoryReader::new(data)) as Arc<dyn AsyncFileReader>;
+/// let tiff = async_tiff::TIFF::open(reader).await.unwrap();
+/// assert!(!tiff.ifds().is_empty());
+/// # })
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct MemoryReader {
+    data: Bytes,
+}
+
+impl MemoryReader {
+    /// Create a reader over `data`.
+    pub fn new(data: impl Into<Bytes>) -> Self {
+        Self { data: data.into() }
+    }
+
+    /// Access the data of this reader.
+    pub fn data(&self) -> &Bytes {
+        &self.data
+    }
+
+    /// The size of the data in bytes.
+    pub fn len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    /// Whether the data is empty.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+impl From<Bytes> for MemoryReader {
+    fn from(data: Bytes) -> Self {
+        Self::new(data)
+    }
+}
+
+impl From<Vec<u8>> for MemoryReader {
+    fn from(data: Vec<u8>) -> Self {
+        Self::new(data)
+    }
+}
+
+#[async_trait]
+impl AsyncFileReader for MemoryReader {
+    async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+        if range.start > range.end || range.start > self.len() {
+            return Err(AsyncTiffError::EndOfFile(
+                range.end.saturating_sub(range.start),
+                0,
+            ));
+        }
+        let end = range.end.min(self.len());
+        Ok(self.data.slice(range.start as usize..end as usize))
+    }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        Ok(Some(self.len()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::metadata::MetadataFetch;
+
+    #[tokio::test]
+    async fn test_memory_reader() {
+        let reader = MemoryReader::from((0..100u8).collect::<Vec<_>>());
+        assert_eq!(reader.len(), 100);
+        assert_eq!(reader.size().await.unwrap(), Some(100));
+        assert_eq!(
+            reader.get_bytes(10..13).await.unwrap().as_ref(),
+            [10, 11, 12]
+        );
+        assert_eq!(reader.fetch(98..102).await.unwrap().as_ref(), [98, 99]);
+        assert!(reader.get_bytes(100..110).await.unwrap().is_empty());
+        assert!(matches!(
+            reader.get_bytes(101..110).await,
+            Err(AsyncTiffError::EndOfFile(9, 0))
+        ));
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 20..10;
+        assert!(reader.get_bytes(reversed).await.is_err());
+    }
+}
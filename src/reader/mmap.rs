@@ -0,0 +1,195 @@
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::ptr::NonNull;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::reader::AsyncFileReader;
+
+/// An [`AsyncFileReader`] over a memory-mapped local file, enabled by the `mmap` feature on Unix.
+///
+/// Byte ranges are returned as zero-copy slices of the mapping, avoiding the copies made when
+/// reading local files through [`ObjectReader`][crate::reader::ObjectReader] and a
+/// `LocalFileSystem`. The OS loads pages of the file on first access, so requests never block on
+/// IO up front; reading the returned bytes may instead page in data.
+///
+/// Like [`MemoryReader`][crate::reader::MemoryReader], ranges extending past the end of the file
+/// are clamped to it, and only ranges starting past the end are an error.
+///
+/// Cloning is cheap, and clones share the same mapping, which is unmapped once the last reader
+/// and every [`Bytes`] returned by it are dropped.
+#[derive(Debug, Clone)]
+pub struct MmapReader {
+    data: Bytes,
+}
+
+impl MmapReader {
+    /// Open and map the file at `path`.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while it's mapped, i.e. while this reader or
+    /// any bytes returned by it are alive. Doing so is undefined behavior, and truncation can
+    /// crash the process with `SIGBUS`.
+    pub unsafe fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        // SAFETY: upheld by the caller.
+        unsafe { Self::map(&File::open(path)?) }
+    }
+
+    /// Map the open `file`. The mapping stays valid after `file` is closed.
+    ///
+    /// # Safety
+    ///
+    /// See [`open`][Self::open].
+    pub unsafe fn map(file: &File) -> io::Result<Self> {
+        let len = usize::try_from(file.metadata()?.len())
+            .map_err(|_| io::Error::other("file is too large to map"))?;
+        // Empty mappings are invalid
+        if len == 0 {
+            return Ok(Self { data: Bytes::new() });
+        }
+        // SAFETY: a new read-only mapping of `len` bytes of a valid file descriptor.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        let ptr = NonNull::new(ptr as *mut u8).ok_or_else(io::Error::last_os_error)?;
+        Ok(Self {
+            data: Bytes::from_owner(Mapping { ptr, len }),
+        })
+    }
+
+    /// The size of the mapped file in bytes.
+    pub fn len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    /// Whether the mapped file is empty.
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+}
+
+#[async_trait]
+impl AsyncFileReader for MmapReader {
+    async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+        if range.start > range.end || range.start > self.len() {
+            return Err(AsyncTiffError::EndOfFile(
+                range.end.saturating_sub(range.start),
+                0,
+            ));
+        }
+        let end = range.end.min(self.len());
+        Ok(self.data.slice(range.start as usize..end as usize))
+    }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        Ok(Some(self.len()))
+    }
+}
+
+/// A read-only memory mapping, unmapped when dropped.
+struct Mapping {
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// SAFETY: the mapping is never written to, and can be unmapped from any thread.
+unsafe impl Send for Mapping {}
+unsafe impl Sync for Mapping {}
+
+impl AsRef<[u8]> for Mapping {
+    fn as_ref(&self) -> &[u8] {
+        // SAFETY: the mapping of `len` readable bytes lives until `self` is dropped.
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: the mapping was created by `mmap` with this length and is unmapped only here.
+        unsafe {
+            libc::munmap(self.ptr.as_ptr() as *mut libc::c_void, self.len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::BlockCacheReader;
+    use crate::test::util::temp_path;
+
+    #[tokio::test]
+    async fn test_mmap_reader() {
+        let path = temp_path("mmap.bin");
+        let data = (0..=255u8).collect::<Vec<_>>();
+        std::fs::write(&path, &data).unwrap();
+
+        // SAFETY: the file isn't modified while mapped.
+        let reader = unsafe { MmapReader::open(&path) }.unwrap();
+        assert_eq!(reader.len(), 256);
+        assert_eq!(reader.size().await.unwrap(), Some(256));
+        let bytes = reader.get_bytes(10..20).await.unwrap();
+        assert_eq!(bytes.as_ref(), &data[10..20]);
+        let ranges = reader.get_byte_ranges(vec![0..2, 254..256]).await.unwrap();
+        assert_eq!(ranges[1].as_ref(), [254, 255]);
+        assert_eq!(
+            reader.get_bytes(250..260).await.unwrap().as_ref(),
+            &data[250..]
+        );
+        assert!(matches!(
+            reader.get_bytes(260..270).await,
+            Err(AsyncTiffError::EndOfFile(10, 0))
+        ));
+
+        // Returned bytes keep the mapping alive
+        drop(reader);
+        assert_eq!(bytes.as_ref(), &data[10..20]);
+
+        let empty = temp_path("empty.bin");
+        std::fs::write(&empty, []).unwrap();
+        // SAFETY: as above.
+        let reader = unsafe { MmapReader::open(&empty) }.unwrap();
+        assert!(reader.is_empty());
+        assert_eq!(reader.get_bytes(0..0).await.unwrap().len(), 0);
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(empty).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_block_cache_over_mmap() {
+        // The last block extends past the end of a file whose size isn't a multiple of the
+        // block size
+        let path = temp_path("mmap_blocks.bin");
+        let data = (0..1000).map(|i| i as u8).collect::<Vec<_>>();
+        std::fs::write(&path, &data).unwrap();
+
+        // SAFETY: the file isn't modified while mapped.
+        let inner = unsafe { MmapReader::open(&path) }.unwrap();
+        let reader = BlockCacheReader::new(inner).with_block_size(256);
+        let bytes = reader.get_bytes(900..1000).await.unwrap();
+        assert_eq!(bytes.as_ref(), &data[900..]);
+        let ranges = reader
+            .get_byte_ranges(vec![0..10, 700..1000])
+            .await
+            .unwrap();
+        assert_eq!(ranges[1].as_ref(), &data[700..]);
+        std::fs::remove_file(path).unwrap();
+    }
+}
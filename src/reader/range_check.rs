@@ -0,0 +1,161 @@
+use std::ops::Range;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::sync::OnceCell;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::reader::AsyncFileReader;
+
+/// An [`AsyncFileReader`] that rejects invalid byte ranges before requesting them from an inner
+/// reader.
+///
+/// Corrupt files can contain tile or strip offsets and byte counts that turn into huge or reversed
+/// range requests. This reader returns [`AsyncTiffError::InvalidByteRange`] instead for ranges that
+/// end before they start or extend past the end of the file.
+///
+/// The file size is learned once, on the first request, from
+/// [`AsyncFileReader::size`] of the inner reader, e.g. with a `HEAD` request for
+/// [`ObjectReader`][crate::reader::ObjectReader]. If the inner reader can't tell the size, only
+/// reversed ranges are rejected. Use [`with_size`][Self::with_size] if the size is already known.
+#[derive(Debug)]
+pub struct RangeCheckReader<R: AsyncFileReader> {
+    inner: R,
+    size: OnceCell<Option<u64>>,
+}
+
+impl<R: AsyncFileReader> RangeCheckReader<R> {
+    /// Wrap `inner`, learning the file size from it on the first request.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            size: OnceCell::new(),
+        }
+    }
+
+    /// Wrap `inner`, checking ranges against a known file size.
+    pub fn with_size(inner: R, size: u64) -> Self {
+        Self {
+            inner,
+            size: OnceCell::new_with(Some(Some(size))),
+        }
+    }
+
+    /// Access the inner reader.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// The file size, requesting it from the inner reader if not known yet.
+    async fn file_size(&self) -> AsyncTiffResult<Option<u64>> {
+        self.size
+            .get_or_try_init(|| self.inner.size())
+            .await
+            .copied()
+    }
+
+    async fn check(&self, range: &Range<u64>) -> AsyncTiffResult<()> {
+        let file_size = if range.start > range.end {
+            None
+        } else {
+            match self.file_size().await? {
+                Some(size) if range.end > size => Some(size),
+                _ => return Ok(()),
+            }
+        };
+        Err(AsyncTiffError::InvalidByteRange {
+            range: range.clone(),
+            file_size,
+        })
+    }
+}
+
+#[async_trait]
+impl<R: AsyncFileReader> AsyncFileReader for RangeCheckReader<R> {
+    async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+        self.check(&range).await?;
+        self.inner.get_bytes(range).await
+    }
+
+    async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
+        for range in &ranges {
+            self.check(range).await?;
+        }
+        self.inner.get_byte_ranges(ranges).await
+    }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        self.file_size().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct CountingReader {
+        requests: AtomicUsize,
+        size_requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl AsyncFileReader for CountingReader {
+        async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+            self.requests.fetch_add(1, Ordering::Relaxed);
+            Ok(vec![0; (range.end - range.start) as usize].into())
+        }
+
+        async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+            self.size_requests.fetch_add(1, Ordering::Relaxed);
+            Ok(Some(100))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_range_check_reader() {
+        let reader = RangeCheckReader::new(CountingReader::default());
+        assert_eq!(reader.get_bytes(90..100).await.unwrap().len(), 10);
+        reader.get_byte_ranges(vec![0..10, 50..60]).await.unwrap();
+
+        let err = reader.get_bytes(90..101).await.unwrap_err();
+        assert!(matches!(
+            err,
+            AsyncTiffError::InvalidByteRange {
+                range: Range {
+                    start: 90,
+                    end: 101
+                },
+                file_size: Some(100),
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Invalid byte range 90..101 for a file of 100 bytes"
+        );
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 20..10;
+        let err = reader.get_byte_ranges(vec![0..10, reversed]).await;
+        assert!(matches!(
+            err,
+            Err(AsyncTiffError::InvalidByteRange {
+                file_size: None,
+                ..
+            })
+        ));
+
+        // Invalid ranges are never requested, and the size is only requested once
+        assert_eq!(reader.inner().requests.load(Ordering::Relaxed), 3);
+        assert_eq!(reader.inner().size_requests.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn test_range_check_reader_with_size() {
+        let reader = RangeCheckReader::with_size(CountingReader::default(), 50);
+        assert_eq!(reader.size().await.unwrap(), Some(50));
+        assert!(reader.get_bytes(40..60).await.is_err());
+        assert_eq!(reader.inner().size_requests.load(Ordering::Relaxed), 0);
+    }
+}
@@ -0,0 +1,321 @@
+use std::fmt::Write;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::error::AsyncTiffResult;
+use crate::reader::{AsyncFileReader, ImageDataCache};
+
+/// Whether a request recorded by a [`RecordingReader`] was for header metadata or image data.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum RequestKind {
+    /// A request for header metadata, e.g. while opening the file.
+    Metadata,
+    /// A request for tiles, strips or other image data.
+    #[default]
+    Image,
+}
+
+impl RequestKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Metadata => "metadata",
+            Self::Image => "image",
+        }
+    }
+}
+
+/// One byte range requested through a [`RecordingReader`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestRecord {
+    /// Whether the range was requested for metadata or image data.
+    pub kind: RequestKind,
+    /// The requested byte range.
+    pub range: Range<u64>,
+    /// When the request started, relative to the creation of the [`RequestLog`].
+    pub start: Duration,
+    /// The time spent waiting for the inner reader.
+    ///
+    /// Ranges requested together in one
+    /// [`get_byte_ranges`][AsyncFileReader::get_byte_ranges] call share the time of the call.
+    pub elapsed: Duration,
+    /// The number of bytes returned, which is zero if the request failed.
+    pub bytes: u64,
+    /// Whether the request succeeded.
+    pub success: bool,
+    /// Whether the range was already in the cache of the reader, if it
+    /// [records cache hits][RecordingReader::with_cache].
+    pub cache_hit: Option<bool>,
+}
+
+/// The requests made through one or more [`RecordingReader`]s, in the order they completed.
+///
+/// The log is guarded by a mutex, so one log can be shared between readers and tasks, e.g. a
+/// metadata and an image data reader of the same file.
+#[derive(Debug)]
+pub struct RequestLog {
+    origin: Instant,
+    records: Mutex<Vec<RequestRecord>>,
+}
+
+impl Default for RequestLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestLog {
+    /// Create an empty log, timing requests from now.
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            records: Mutex::new(vec![]),
+        }
+    }
+
+    /// A copy of the recorded requests.
+    pub fn records(&self) -> Vec<RequestRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    /// The number of recorded requests.
+    pub fn len(&self) -> usize {
+        self.records.lock().unwrap().len()
+    }
+
+    /// Whether no requests were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove all recorded requests.
+    pub fn clear(&self) {
+        self.records.lock().unwrap().clear();
+    }
+
+    /// The recorded requests as JSON, for replaying or inspecting the request pattern of a file.
+    ///
+    /// The document has a `requests` array with one object per request, holding its `kind`,
+    /// `offset`, `length`, `start_ms`, `elapsed_ms`, `bytes`, `success` and `cache_hit`, which is
+    /// `null` if unknown.
+    pub fn to_json(&self) -> String {
+        let records = self.records.lock().unwrap();
+        let mut json = String::from("{\"requests\":[");
+        for (i, record) in records.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            let cache_hit = match record.cache_hit {
+                Some(hit) => hit.to_string(),
+                None => "null".to_string(),
+            };
+            write!(
+                json,
+                "{{\"kind\":\"{}\",\"offset\":{},\"length\":{},\"start_ms\":{:.3},\"elapsed_ms\":{:.3},\"bytes\":{},\"success\":{},\"cache_hit\":{}}}",
+                record.kind.as_str(),
+                record.range.start,
+                record.range.end.saturating_sub(record.range.start),
+                record.start.as_secs_f64() * 1000.0,
+                record.elapsed.as_secs_f64() * 1000.0,
+                record.bytes,
+                record.success,
+                cache_hit,
+            )
+            .unwrap();
+        }
+        json.push_str("]}");
+        json
+    }
+
+    fn push(&self, records: impl IntoIterator<Item = RequestRecord>) {
+        self.records.lock().unwrap().extend(records);
+    }
+}
+
+/// An [`AsyncFileReader`] that records every byte range requested from an inner reader in a
+/// [`RequestLog`], e.g. to check how many requests a COG layout needs.
+///
+/// Requests are recorded as [`RequestKind::Image`] unless set [otherwise][Self::with_kind]. To
+/// tell metadata requests apart, wrap the reader passed to [`TIFF::open`][crate::TIFF::open] as
+/// [`RequestKind::Metadata`] and the reader used for tiles as [`RequestKind::Image`], sharing one
+/// log.
+///
+/// ```
+/// # tokio_test::block_on(async {
+/// use std::sync::Arc;
+///
+/// use async_tiff::reader::{
+///     AsyncFileReader, MemoryReader, RecordingReader, RequestKind, RequestLog,
+/// };
+///
+/// let data = std::fs::read("fixtures/image-tiff/tiled-rgb-u8.tif").unwrap();
+/// let inner = Arc::new(MemoryReader::new(data)) as Arc<dyn AsyncFileReader>;
+/// let log = Arc::new(RequestLog::new());
+/// let metadata = RecordingReader::with_log(inner.clone(), log.clone())
+///     .with_kind(RequestKind::Metadata);
+/// let image = RecordingReader::with_log(inner, log.clone());
+///
+/// let tiff = async_tiff::TIFF::open(Arc::new(metadata)).await.unwrap();
+/// tiff.ifds()[0].fetch_tile(0, 0, &image).await.unwrap();
+/// println!("{}", log.to_json());
+/// # })
+/// ```
+#[derive(Debug, Clone)]
+pub struct RecordingReader<R: AsyncFileReader> {
+    inner: R,
+    log: Arc<RequestLog>,
+    kind: RequestKind,
+    cache: Option<(Arc<dyn ImageDataCache>, Arc<str>)>,
+}
+
+impl<R: AsyncFileReader> RecordingReader<R> {
+    /// Wrap `inner`, recording its requests in a new log.
+    pub fn new(inner: R) -> Self {
+        Self::with_log(inner, Default::default())
+    }
+
+    /// Wrap `inner`, adding its requests to an existing log.
+    pub fn with_log(inner: R, log: Arc<RequestLog>) -> Self {
+        Self {
+            inner,
+            log,
+            kind: RequestKind::default(),
+            cache: None,
+        }
+    }
+
+    /// Record requests as `kind`.
+    pub fn with_kind(mut self, kind: RequestKind) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Record whether each range was already in `cache` under `path`, when wrapping a
+    /// [`DataCacheReader`][crate::reader::DataCacheReader] over the same cache and path.
+    pub fn with_cache(mut self, cache: Arc<dyn ImageDataCache>, path: impl Into<Arc<str>>) -> Self {
+        self.cache = Some((cache, path.into()));
+        self
+    }
+
+    /// Access the inner reader.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Access the log of this reader.
+    pub fn log(&self) -> &Arc<RequestLog> {
+        &self.log
+    }
+
+    fn cache_hit(&self, range: &Range<u64>) -> Option<bool> {
+        let (cache, path) = self.cache.as_ref()?;
+        Some(cache.get(&(path.clone(), range.clone())).is_some())
+    }
+
+    fn record(
+        &self,
+        ranges: Vec<(Range<u64>, Option<bool>)>,
+        result: Option<&[Bytes]>,
+        start: Instant,
+    ) {
+        let elapsed = start.elapsed();
+        let start = start.saturating_duration_since(self.log.origin);
+        self.log.push(
+            ranges
+                .into_iter()
+                .enumerate()
+                .map(|(i, (range, cache_hit))| RequestRecord {
+                    kind: self.kind,
+                    range,
+                    start,
+                    elapsed,
+                    bytes: result
+                        .and_then(|bytes| bytes.get(i))
+                        .map_or(0, |b| b.len() as u64),
+                    success: result.is_some(),
+                    cache_hit,
+                }),
+        );
+    }
+}
+
+#[async_trait]
+impl<R: AsyncFileReader> AsyncFileReader for RecordingReader<R> {
+    async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+        let cache_hit = self.cache_hit(&range);
+        let start = Instant::now();
+        let result = self.inner.get_bytes(range.clone()).await;
+        let bytes = result.as_ref().ok().map(std::slice::from_ref);
+        self.record(vec![(range, cache_hit)], bytes, start);
+        result
+    }
+
+    async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
+        let recorded = ranges
+            .iter()
+            .map(|range| (range.clone(), self.cache_hit(range)))
+            .collect();
+        let start = Instant::now();
+        let result = self.inner.get_byte_ranges(ranges).await;
+        self.record(recorded, result.as_deref().ok(), start);
+        result
+    }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        self.inner.size().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reader::{DataCacheReader, LruDataCache, MemoryReader};
+
+    #[tokio::test]
+    async fn test_recording_reader() {
+        let inner = MemoryReader::from((0..100u8).collect::<Vec<_>>());
+        let cache = Arc::new(LruDataCache::new(1000)) as Arc<dyn ImageDataCache>;
+        let cached = DataCacheReader::new(inner.clone(), "a.tif", cache.clone());
+        let log = Arc::new(RequestLog::new());
+        let metadata =
+            RecordingReader::with_log(inner, log.clone()).with_kind(RequestKind::Metadata);
+        let image = RecordingReader::with_log(cached, log.clone()).with_cache(cache, "a.tif");
+
+        metadata.get_bytes(0..16).await.unwrap();
+        image.get_byte_ranges(vec![20..30, 40..50]).await.unwrap();
+        image.get_bytes(20..30).await.unwrap();
+        assert!(metadata.get_bytes(200..210).await.is_err());
+
+        let records = log.records();
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[0].kind, RequestKind::Metadata);
+        assert_eq!(records[0].cache_hit, None);
+        assert_eq!(
+            records[1..4]
+                .iter()
+                .map(|r| (r.kind, r.range.clone(), r.bytes, r.cache_hit))
+                .collect::<Vec<_>>(),
+            [
+                (RequestKind::Image, 20..30, 10, Some(false)),
+                (RequestKind::Image, 40..50, 10, Some(false)),
+                (RequestKind::Image, 20..30, 10, Some(true)),
+            ]
+        );
+        assert!(!records[4].success);
+        assert_eq!(records[4].bytes, 0);
+
+        let json: serde_json::Value = serde_json::from_str(&log.to_json()).unwrap();
+        let requests = json["requests"].as_array().unwrap();
+        assert_eq!(requests.len(), 5);
+        assert_eq!(requests[0]["kind"], "metadata");
+        assert_eq!(requests[0]["length"], 16);
+        assert_eq!(requests[0]["cache_hit"], serde_json::Value::Null);
+        assert_eq!(requests[3]["cache_hit"], true);
+
+        log.clear();
+        assert!(log.is_empty());
+    }
+}
@@ -0,0 +1,253 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bytes::Bytes;
+
+use crate::error::AsyncTiffResult;
+use crate::reader::AsyncFileReader;
+
+/// Counters of the requests made through one or more [`StatsReader`]s.
+///
+/// All counters are atomic, so one `ReadStats` can be shared between readers and tasks.
+#[derive(Debug, Default)]
+pub struct ReadStats {
+    requests: AtomicU64,
+    bytes: AtomicU64,
+    elapsed_nanos: AtomicU64,
+}
+
+impl ReadStats {
+    /// Create a new set of counters, all starting at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of byte ranges requested.
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::Relaxed)
+    }
+
+    /// The total number of bytes returned.
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// The total time spent waiting for requests.
+    ///
+    /// Concurrent requests each contribute their own duration, so this can exceed the wall clock
+    /// time.
+    pub fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed_nanos.load(Ordering::Relaxed))
+    }
+
+    /// Reset all counters to zero.
+    pub fn reset(&self) {
+        self.requests.store(0, Ordering::Relaxed);
+        self.bytes.store(0, Ordering::Relaxed);
+        self.elapsed_nanos.store(0, Ordering::Relaxed);
+    }
+
+    fn record(&self, event: &RequestEvent) {
+        self.requests
+            .fetch_add(event.requests as u64, Ordering::Relaxed);
+        self.bytes.fetch_add(event.bytes, Ordering::Relaxed);
+        self.elapsed_nanos
+            .fetch_add(event.elapsed.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+/// A completed call to the inner reader of a [`StatsReader`], passed to its [`MetricsHook`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestEvent {
+    /// The number of byte ranges requested.
+    pub requests: usize,
+    /// The number of bytes returned, which is zero if the call failed.
+    pub bytes: u64,
+    /// The time spent waiting for the inner reader.
+    pub elapsed: Duration,
+    /// Whether the call succeeded.
+    pub success: bool,
+}
+
+/// A callback notified of every call made through a [`StatsReader`], e.g. to export request
+/// counts and latency to a metrics system.
+///
+/// Any `Fn(&RequestEvent)` closure can be used as a hook.
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// use async_tiff::reader::{RequestEvent, StatsReader};
+/// # use async_tiff::reader::AsyncFileReader;
+/// # fn wrap(reader: Arc<dyn AsyncFileReader>) {
+///
+/// let reader = StatsReader::new(reader).with_metrics_hook(Arc::new(|event: &RequestEvent| {
+///     println!("{} bytes in {:?}", event.bytes, event.elapsed);
+/// }));
+/// # }
+/// ```
+pub trait MetricsHook: Send + Sync {
+    /// Called after each call to the inner reader completes.
+    fn on_request(&self, event: &RequestEvent);
+}
+
+impl<F: Fn(&RequestEvent) + Send + Sync> MetricsHook for F {
+    fn on_request(&self, event: &RequestEvent) {
+        self(event)
+    }
+}
+
+/// An [`AsyncFileReader`] that counts the requests, bytes and time spent in an inner reader.
+///
+/// Every byte range counts as one request, even if the inner reader merges ranges passed to
+/// [`get_byte_ranges`][AsyncFileReader::get_byte_ranges]. Wrap separate readers around the same
+/// inner reader to track e.g. metadata and image data reads separately.
+///
+/// Cloning a `StatsReader` clones the inner reader, but the clone shares the same counters and
+/// [`MetricsHook`].
+#[derive(Clone)]
+pub struct StatsReader<R: AsyncFileReader> {
+    inner: R,
+    stats: Arc<ReadStats>,
+    hook: Option<Arc<dyn MetricsHook>>,
+}
+
+impl<R: AsyncFileReader> std::fmt::Debug for StatsReader<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StatsReader")
+            .field("inner", &self.inner)
+            .field("stats", &self.stats)
+            .field("hook", &self.hook.is_some())
+            .finish()
+    }
+}
+
+impl<R: AsyncFileReader> StatsReader<R> {
+    /// Wrap `inner` with new counters.
+    pub fn new(inner: R) -> Self {
+        Self::with_stats(inner, Default::default())
+    }
+
+    /// Wrap `inner`, adding to existing counters.
+    pub fn with_stats(inner: R, stats: Arc<ReadStats>) -> Self {
+        Self {
+            inner,
+            stats,
+            hook: None,
+        }
+    }
+
+    /// Call `hook` after each call to the inner reader, in addition to updating the counters.
+    pub fn with_metrics_hook(mut self, hook: Arc<dyn MetricsHook>) -> Self {
+        self.hook = Some(hook);
+        self
+    }
+
+    /// Access the inner reader.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Access the counters of this reader.
+    pub fn stats(&self) -> &Arc<ReadStats> {
+        &self.stats
+    }
+
+    fn record(&self, requests: usize, result: Option<&[Bytes]>, start: Instant) {
+        let event = RequestEvent {
+            requests,
+            bytes: result
+                .unwrap_or_default()
+                .iter()
+                .map(|b| b.len() as u64)
+                .sum(),
+            elapsed: start.elapsed(),
+            success: result.is_some(),
+        };
+        self.stats.record(&event);
+        if let Some(hook) = &self.hook {
+            hook.on_request(&event);
+        }
+    }
+}
+
+#[async_trait]
+impl<R: AsyncFileReader> AsyncFileReader for StatsReader<R> {
+    async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+        let start = Instant::now();
+        let result = self.inner.get_bytes(range).await;
+        let bytes = result.as_ref().ok().map(std::slice::from_ref);
+        self.record(1, bytes, start);
+        result
+    }
+
+    async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
+        let start = Instant::now();
+        let requests = ranges.len();
+        let result = self.inner.get_byte_ranges(ranges).await;
+        let bytes = result.as_deref().ok();
+        self.record(requests, bytes, start);
+        result
+    }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        self.inner.size().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug)]
+    struct ZeroReader;
+
+    #[async_trait]
+    impl AsyncFileReader for ZeroReader {
+        async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+            if range.end > 1000 {
+                return Err(crate::error::AsyncTiffError::EndOfFile(range.end, 1000));
+            }
+            Ok(vec![0; (range.end - range.start) as usize].into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stats_reader() {
+        let stats = Arc::new(ReadStats::new());
+        let reader = StatsReader::with_stats(ZeroReader, stats.clone());
+        reader.get_bytes(0..10).await.unwrap();
+        reader.get_byte_ranges(vec![10..15, 20..40]).await.unwrap();
+        assert_eq!(stats.requests(), 3);
+        assert_eq!(stats.bytes(), 35);
+
+        // Counters are shared between readers
+        let other = StatsReader::with_stats(ZeroReader, stats.clone());
+        other.get_bytes(0..5).await.unwrap();
+        assert_eq!(reader.stats().requests(), 4);
+        assert_eq!(reader.stats().bytes(), 40);
+
+        // Hooks see every call, including failed ones
+        let events = Arc::new(std::sync::Mutex::new(vec![]));
+        let hook_events = events.clone();
+        let reader = StatsReader::new(ZeroReader).with_metrics_hook(Arc::new(
+            move |event: &RequestEvent| hook_events.lock().unwrap().push(*event),
+        ));
+        reader.get_byte_ranges(vec![0..10, 10..15]).await.unwrap();
+        assert!(reader.get_bytes(990..1010).await.is_err());
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].requests, events[0].bytes), (2, 15));
+        assert!(events[0].success);
+        assert_eq!((events[1].requests, events[1].bytes), (1, 0));
+        assert!(!events[1].success);
+        assert_eq!(reader.stats().requests(), 3);
+
+        stats.reset();
+        assert_eq!(stats.requests(), 0);
+        assert_eq!(stats.elapsed(), Duration::ZERO);
+    }
+}
@@ -0,0 +1,216 @@
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::reader::AsyncFileReader;
+
+/// Limits on requests shared by any number of [`ThrottledReader`]s.
+///
+/// A throttle allows at most [`max_in_flight`][Self::max_in_flight] requests at once and, if
+/// [set][Self::with_bytes_per_second], paces requests so that on average no more than the given
+/// number of bytes per second is requested. Share one throttle as an `Arc<Throttle>` between the
+/// readers of all files of a batch job, so it doesn't exhaust the connection limits of the store
+/// or saturate the network.
+///
+/// Bandwidth limiting sleeps on the Tokio timer, so it requires a runtime with time enabled.
+#[derive(Debug)]
+pub struct Throttle {
+    semaphore: Semaphore,
+    max_in_flight: usize,
+    bytes_per_second: Option<u64>,
+    /// The time at which the bandwidth used by earlier requests has been paid off
+    next_start: Mutex<Option<Instant>>,
+}
+
+impl Throttle {
+    /// Create a throttle allowing at most `max_in_flight` requests at once, without a bandwidth
+    /// limit.
+    pub fn new(max_in_flight: usize) -> Self {
+        Self {
+            semaphore: Semaphore::new(max_in_flight),
+            max_in_flight,
+            bytes_per_second: None,
+            next_start: Mutex::new(None),
+        }
+    }
+
+    /// Request no more than `bytes_per_second` bytes per second on average.
+    ///
+    /// The limit applies to the requested byte ranges. A request is delayed until the requests
+    /// before it would have been transferred at this rate, so a single large request is not
+    /// delayed, but the requests after it are.
+    pub fn with_bytes_per_second(mut self, bytes_per_second: u64) -> Self {
+        self.bytes_per_second = Some(bytes_per_second);
+        self
+    }
+
+    /// The maximum number of requests in flight at once.
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight
+    }
+
+    /// The maximum average number of bytes requested per second, if any.
+    pub fn bytes_per_second(&self) -> Option<u64> {
+        self.bytes_per_second
+    }
+
+    /// The number of requests that can start without waiting.
+    pub fn available(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+
+    /// Stop all readers using this throttle, failing their pending and future requests.
+    pub fn close(&self) {
+        self.semaphore.close();
+    }
+
+    /// Wait until a request for `bytes` bytes may start, returning the permit to hold while it
+    /// runs.
+    async fn acquire(&self, bytes: u64) -> AsyncTiffResult<SemaphorePermit<'_>> {
+        if let Some(rate) = self.bytes_per_second.filter(|&rate| rate > 0) {
+            let start = {
+                let mut next_start = self.next_start.lock().unwrap();
+                let now = Instant::now();
+                let start = next_start.map_or(now, |next| next.max(now));
+                *next_start = Some(start + Duration::from_secs_f64(bytes as f64 / rate as f64));
+                start
+            };
+            tokio::time::sleep_until(start).await;
+        }
+        self.semaphore
+            .acquire()
+            .await
+            .map_err(|_| AsyncTiffError::General("Throttle was closed".to_string()))
+    }
+}
+
+/// An [`AsyncFileReader`] that limits the requests to an inner reader with a shared [`Throttle`].
+///
+/// Each call to the inner reader holds one permit of the throttle until it completes, including a
+/// [`get_byte_ranges`][AsyncFileReader::get_byte_ranges] call for several ranges, which counts
+/// the bytes of all its ranges against the bandwidth limit.
+#[derive(Debug, Clone)]
+pub struct ThrottledReader<R: AsyncFileReader> {
+    inner: R,
+    throttle: Arc<Throttle>,
+}
+
+impl<R: AsyncFileReader> ThrottledReader<R> {
+    /// Wrap `inner`, limiting its requests with `throttle`.
+    pub fn new(inner: R, throttle: Arc<Throttle>) -> Self {
+        Self { inner, throttle }
+    }
+
+    /// Access the inner reader.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    /// Access the throttle of this reader.
+    pub fn throttle(&self) -> &Arc<Throttle> {
+        &self.throttle
+    }
+}
+
+#[async_trait]
+impl<R: AsyncFileReader> AsyncFileReader for ThrottledReader<R> {
+    async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+        let _permit = self
+            .throttle
+            .acquire(range.end.saturating_sub(range.start))
+            .await?;
+        self.inner.get_bytes(range).await
+    }
+
+    async fn get_byte_ranges(&self, ranges: Vec<Range<u64>>) -> AsyncTiffResult<Vec<Bytes>> {
+        let bytes = ranges
+            .iter()
+            .map(|range| range.end.saturating_sub(range.start))
+            .sum();
+        let _permit = self.throttle.acquire(bytes).await?;
+        self.inner.get_byte_ranges(ranges).await
+    }
+
+    async fn size(&self) -> AsyncTiffResult<Option<u64>> {
+        let _permit = self.throttle.acquire(0).await?;
+        self.inner.size().await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// A reader that records the highest number of requests in flight at once.
+    #[derive(Debug, Default)]
+    struct SlowReader {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl AsyncFileReader for SlowReader {
+        async fn get_bytes(&self, range: Range<u64>) -> AsyncTiffResult<Bytes> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(Bytes::from(vec![0; (range.end - range.start) as usize]))
+        }
+    }
+
+    fn readers(
+        count: usize,
+        throttle: &Arc<Throttle>,
+    ) -> (Arc<SlowReader>, Vec<impl AsyncFileReader>) {
+        let inner = Arc::new(SlowReader::default());
+        let readers = (0..count)
+            .map(|_| {
+                let inner = inner.clone() as Arc<dyn AsyncFileReader>;
+                ThrottledReader::new(inner, throttle.clone())
+            })
+            .collect();
+        (inner, readers)
+    }
+
+    #[tokio::test]
+    async fn test_throttle_in_flight() {
+        let throttle = Arc::new(Throttle::new(2));
+        let (inner, readers) = readers(3, &throttle);
+        let requests = readers
+            .iter()
+            .flat_map(|reader| (0..4).map(move |i| reader.get_bytes(i..i + 1)));
+        let results = futures::future::try_join_all(requests).await.unwrap();
+        assert_eq!(results.len(), 12);
+        assert_eq!(inner.max_in_flight.load(Ordering::SeqCst), 2);
+        assert_eq!(throttle.available(), 2);
+
+        throttle.close();
+        assert!(readers[0].get_bytes(0..1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_throttle_bandwidth() {
+        let throttle = Arc::new(Throttle::new(8).with_bytes_per_second(1000));
+        assert_eq!(throttle.bytes_per_second(), Some(1000));
+        let (_, readers) = readers(2, &throttle);
+
+        // Each request waits until the 50 bytes of each request before it took 50ms
+        let start = Instant::now();
+        let requests = readers.iter().map(|reader| reader.get_bytes(0..50));
+        futures::future::try_join_all(requests).await.unwrap();
+        readers[0]
+            .get_byte_ranges(vec![0..25, 25..50])
+            .await
+            .unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,397 @@
+//! Rendering of decoded images as 8-bit RGBA.
+//!
+//! [`render_rgba`] turns a decoded [`Array`] into an image that can be encoded as PNG or WebP
+//! directly: palette images are expanded through their colormap, grayscale is copied into the
+//! red, green and blue channels, and the alpha channel combines `ExtraSamples` alpha, the
+//! nodata value and an optional internal transparency mask.
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::tags::{ExtraSamples, PhotometricInterpretation, PlanarConfiguration};
+use crate::ycbcr::YCbCr;
+use crate::{Array, DataType, ImageFileDirectory, TypedArray};
+
+/// Options for [`render_rgba`].
+#[derive(Debug, Clone, Default)]
+pub struct RenderOptions {
+    bands: Option<Vec<usize>>,
+    rescale: Option<(f64, f64)>,
+}
+
+impl RenderOptions {
+    /// Create new RenderOptions, which render the image according to its photometric
+    /// interpretation without rescaling.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render the given bands instead of the color bands of the photometric interpretation:
+    /// one band as grayscale or three bands as red, green and blue.
+    ///
+    /// This is useful for multispectral images, and disables the colormap of palette images.
+    pub fn with_bands(mut self, bands: Vec<usize>) -> Self {
+        self.bands = Some(bands);
+        self
+    }
+
+    /// Linearly map color values from `min..=max` to `0..=255`, clamping values outside the
+    /// range.
+    ///
+    /// Without a range, integer values are mapped from the full range of their bit depth, and
+    /// floating point images can't be rendered.
+    pub fn with_rescale(mut self, min: f64, max: f64) -> Self {
+        self.rescale = Some((min, max));
+        self
+    }
+}
+
+/// How the color bands of a pixel map to red, green and blue.
+enum Color {
+    Gray { invert: bool },
+    Rgb,
+    Palette(Vec<[u8; 3]>),
+}
+
+/// Render `array`, decoded from `ifd`, as a chunky 8-bit RGBA array of shape
+/// `(height, width, 4)`.
+///
+/// Pixels are transparent where the `ExtraSamples` alpha is zero, where every color band equals
+/// the image's [`nodata`][ImageFileDirectory::nodata] value or is NaN, and where `mask` is
+/// `false` or zero. `mask` is the decoded transparency mask of the same area, with a single
+/// band. Associated (premultiplied) alpha is converted to straight alpha.
+pub fn render_rgba(
+    ifd: &ImageFileDirectory,
+    array: &Array,
+    mask: Option<&Array>,
+    options: &RenderOptions,
+) -> AsyncTiffResult<Array> {
+    let planar = ifd.planar_configuration() == PlanarConfiguration::Planar;
+    let [d0, d1, d2] = array.shape();
+    let (height, width, band_count) = if planar { (d1, d2, d0) } else { (d0, d1, d2) };
+    let values = to_f64(array.data());
+    let sample = |band: usize, pixel: usize| {
+        if planar {
+            values[band * height * width + pixel]
+        } else {
+            values[pixel * band_count + band]
+        }
+    };
+
+    let (color_bands, color) = color_bands(ifd, band_count, options)?;
+    if let Some(&band) = color_bands.iter().find(|&&band| band >= band_count) {
+        return Err(AsyncTiffError::General(format!(
+            "Band {band} out of range for an image with {band_count} bands"
+        )));
+    }
+    let alpha = alpha_band(ifd);
+    let color_range = match (options.rescale, &color) {
+        (_, Color::Palette(_)) => None,
+        (Some(range), _) => Some(range),
+        (None, _) => Some(integer_range(ifd, array, color_bands[0])?),
+    };
+    let alpha_range = alpha
+        .map(|(band, _)| integer_range(ifd, array, band))
+        .transpose()?;
+
+    let mask = match mask {
+        Some(mask) => {
+            let [mask_height, mask_width, _] = mask.shape();
+            if (mask_height, mask_width) != (height, width) {
+                return Err(AsyncTiffError::General(format!(
+                    "Mask of {mask_width}x{mask_height} pixels doesn't match image of {width}x{height} pixels"
+                )));
+            }
+            Some(to_f64(mask.data()))
+        }
+        None => None,
+    };
+    let nodata = ifd.nodata().map(|nodata| nodata.as_f64());
+
+    let mut rgba = Vec::with_capacity(height * width * 4);
+    for pixel in 0..height * width {
+        let colors: Vec<f64> = color_bands
+            .iter()
+            .map(|&band| sample(band, pixel))
+            .collect();
+        let is_nodata = colors
+            .iter()
+            .all(|&value| value.is_nan() || nodata == Some(value));
+        let mut opacity = match (alpha, alpha_range) {
+            (Some((band, _)), Some(range)) => normalize(sample(band, pixel), range),
+            _ => 1.0,
+        };
+        if is_nodata || mask.as_ref().is_some_and(|mask| mask[pixel] == 0.0) {
+            opacity = 0.0;
+        }
+
+        let [r, g, b] = match &color {
+            Color::Palette(table) => {
+                let index = colors[0];
+                let entry = table.get(index as usize).filter(|_| index >= 0.0);
+                match entry {
+                    Some(&entry) => entry,
+                    None if opacity == 0.0 => [0; 3],
+                    None => {
+                        return Err(AsyncTiffError::General(format!(
+                            "Pixel value {index} out of range for a colormap with {} entries",
+                            table.len()
+                        )))
+                    }
+                }
+            }
+            _ => {
+                let range = color_range.unwrap_or((0.0, 255.0));
+                let channel = |value: f64| {
+                    let mut value = normalize(value, range);
+                    if matches!(color, Color::Gray { invert: true }) {
+                        value = 1.0 - value;
+                    }
+                    if matches!(alpha, Some((_, ExtraSamples::AssociatedAlpha))) && opacity > 0.0 {
+                        value = (value / opacity).min(1.0);
+                    }
+                    to_u8(value)
+                };
+                match color {
+                    Color::Rgb => [channel(colors[0]), channel(colors[1]), channel(colors[2])],
+                    _ => [channel(colors[0]); 3],
+                }
+            }
+        };
+        rgba.extend([r, g, b, to_u8(opacity)]);
+    }
+    Array::try_new(rgba, [height, width, 4], Some(DataType::UInt8))
+}
+
+/// The bands holding the color of each pixel, and how they map to red, green and blue.
+fn color_bands(
+    ifd: &ImageFileDirectory,
+    band_count: usize,
+    options: &RenderOptions,
+) -> AsyncTiffResult<(Vec<usize>, Color)> {
+    if let Some(bands) = &options.bands {
+        return match bands.len() {
+            1 => Ok((bands.clone(), Color::Gray { invert: false })),
+            3 => Ok((bands.clone(), Color::Rgb)),
+            n => Err(AsyncTiffError::General(format!(
+                "Expected 1 or 3 bands to render, got {n}"
+            ))),
+        };
+    }
+    match ifd.photometric_interpretation() {
+        PhotometricInterpretation::BlackIsZero | PhotometricInterpretation::TransparencyMask => {
+            Ok((vec![0], Color::Gray { invert: false }))
+        }
+        PhotometricInterpretation::WhiteIsZero => Ok((vec![0], Color::Gray { invert: true })),
+        PhotometricInterpretation::RGB if band_count >= 3 => Ok((vec![0, 1, 2], Color::Rgb)),
+        // YCbCr is converted to RGB when decoding
+        PhotometricInterpretation::YCbCr if band_count >= 3 && YCbCr::decodes_to_rgb(ifd) => {
+            Ok((vec![0, 1, 2], Color::Rgb))
+        }
+        PhotometricInterpretation::RGBPalette => {
+            let table = ifd.color_table_u8().ok_or(AsyncTiffError::General(
+                "Palette image has no ColorMap".to_string(),
+            ))?;
+            Ok((vec![0], Color::Palette(table)))
+        }
+        photometric_interpretation => Err(AsyncTiffError::General(format!(
+            "Can't render {photometric_interpretation:?} images as RGBA"
+        ))),
+    }
+}
+
+/// The band and kind of the first alpha channel in `ExtraSamples`.
+fn alpha_band(ifd: &ImageFileDirectory) -> Option<(usize, ExtraSamples)> {
+    let extra_samples = ifd.extra_samples()?;
+    let first_extra = ifd.samples_per_pixel() as usize - extra_samples.len();
+    extra_samples
+        .iter()
+        .position(|sample| {
+            matches!(
+                sample,
+                ExtraSamples::AssociatedAlpha | ExtraSamples::UnassociatedAlpha
+            )
+        })
+        .map(|index| (first_extra + index, extra_samples[index]))
+}
+
+/// The full range of values of `band`, from its bit depth for unsigned integers.
+fn integer_range(
+    ifd: &ImageFileDirectory,
+    array: &Array,
+    band: usize,
+) -> AsyncTiffResult<(f64, f64)> {
+    let bits_per_sample = ifd.bits_per_sample();
+    let bits = bits_per_sample
+        .get(band)
+        .or(bits_per_sample.first())
+        .copied()
+        .unwrap_or(8);
+    match array.data_type() {
+        Some(DataType::Bool) => Ok((0.0, 1.0)),
+        None
+        | Some(DataType::UInt8)
+        | Some(DataType::UInt16)
+        | Some(DataType::UInt32)
+        | Some(DataType::UInt64) => Ok((0.0, 2f64.powi(bits as i32) - 1.0)),
+        Some(DataType::Int8) => Ok((i8::MIN as f64, i8::MAX as f64)),
+        Some(DataType::Int16) => Ok((i16::MIN as f64, i16::MAX as f64)),
+        Some(DataType::Int32) => Ok((i32::MIN as f64, i32::MAX as f64)),
+        Some(DataType::Int64) => Ok((i64::MIN as f64, i64::MAX as f64)),
+        Some(DataType::Float32) | Some(DataType::Float64) => Err(AsyncTiffError::General(
+            "Floating point images need a rescale range to be rendered".to_string(),
+        )),
+    }
+}
+
+/// `value` mapped from `min..=max` to `0..=1`.
+fn normalize(value: f64, (min, max): (f64, f64)) -> f64 {
+    if max > min {
+        ((value - min) / (max - min)).clamp(0.0, 1.0)
+    } else {
+        (value >= max) as u8 as f64
+    }
+}
+
+fn to_u8(value: f64) -> u8 {
+    (value * 255.0).round() as u8
+}
+
+fn to_f64(data: &TypedArray) -> Vec<f64> {
+    macro_rules! convert {
+        ($values:expr) => {
+            $values.iter().map(|&value| value as f64).collect()
+        };
+    }
+    match data {
+        TypedArray::Bool(values) => values.iter().map(|&value| value as u8 as f64).collect(),
+        TypedArray::UInt8(values) => convert!(values),
+        TypedArray::UInt16(values) => convert!(values),
+        TypedArray::UInt32(values) => convert!(values),
+        TypedArray::UInt64(values) => convert!(values),
+        TypedArray::Int8(values) => convert!(values),
+        TypedArray::Int16(values) => convert!(values),
+        TypedArray::Int32(values) => convert!(values),
+        TypedArray::Int64(values) => convert!(values),
+        TypedArray::Float32(values) => convert!(values),
+        TypedArray::Float64(values) => convert!(values),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::reader::Endianness;
+    use crate::tag_value::TagValue;
+    use crate::tags::Tag;
+
+    fn test_ifd(tags: impl IntoIterator<Item = (Tag, TagValue)>) -> ImageFileDirectory {
+        let mut tags: HashMap<_, _> = tags.into_iter().collect();
+        tags.entry(Tag::ImageWidth).or_insert(TagValue::Unsigned(2));
+        tags.entry(Tag::ImageLength)
+            .or_insert(TagValue::Unsigned(1));
+        ImageFileDirectory::from_tags(tags, Endianness::LittleEndian).unwrap()
+    }
+
+    fn shorts(values: &[u16]) -> TagValue {
+        TagValue::List(values.iter().copied().map(TagValue::Short).collect())
+    }
+
+    fn pixels(rgba: &Array) -> Vec<[u8; 4]> {
+        rgba.data()
+            .as_ref()
+            .chunks_exact(4)
+            .map(|pixel| pixel.try_into().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_render_palette() {
+        let mut color_map = vec![0; 3 * 256];
+        // Entry 1 is orange, entry 2 is blue
+        color_map[1] = 65535;
+        color_map[256 + 1] = 32896;
+        color_map[512 + 2] = 65535;
+        let ifd = test_ifd([
+            (Tag::BitsPerSample, TagValue::Short(8)),
+            (Tag::PhotometricInterpretation, TagValue::Short(3)),
+            (Tag::SamplesPerPixel, TagValue::Short(1)),
+            (Tag::ColorMap, shorts(&color_map)),
+        ]);
+        let array = Array::try_new(vec![1, 2], [1, 2, 1], Some(DataType::UInt8)).unwrap();
+        let rgba = render_rgba(&ifd, &array, None, &RenderOptions::new()).unwrap();
+        assert_eq!(rgba.shape(), [1, 2, 4]);
+        assert_eq!(pixels(&rgba), [[255, 128, 0, 255], [0, 0, 255, 255]]);
+    }
+
+    #[test]
+    fn test_render_alpha_nodata_and_mask() {
+        let ifd = test_ifd([
+            (Tag::ImageWidth, TagValue::Unsigned(4)),
+            (Tag::BitsPerSample, shorts(&[8, 8, 8, 8])),
+            (Tag::PhotometricInterpretation, TagValue::Short(2)),
+            (Tag::SamplesPerPixel, TagValue::Short(4)),
+            (Tag::ExtraSamples, TagValue::Short(2)),
+            (Tag::GdalNodata, TagValue::Ascii("0".to_string())),
+        ]);
+        #[rustfmt::skip]
+        let data = vec![
+            10, 20, 30, 255,
+            40, 50, 60, 128,
+            0, 0, 0, 255,
+            70, 80, 90, 255,
+        ];
+        let array = Array::try_new(data, [1, 4, 4], Some(DataType::UInt8)).unwrap();
+        let mask = Array::try_new(vec![0b1110_0000], [1, 4, 1], Some(DataType::Bool)).unwrap();
+        let rgba = render_rgba(&ifd, &array, Some(&mask), &RenderOptions::new()).unwrap();
+        assert_eq!(
+            pixels(&rgba),
+            [
+                [10, 20, 30, 255],
+                [40, 50, 60, 128],
+                [0, 0, 0, 0],
+                [70, 80, 90, 0],
+            ]
+        );
+
+        let small_mask = Array::try_new(vec![0], [1, 2, 1], Some(DataType::Bool)).unwrap();
+        assert!(render_rgba(&ifd, &array, Some(&small_mask), &RenderOptions::new()).is_err());
+    }
+
+    #[test]
+    fn test_render_rescale_and_bands() {
+        let ifd = test_ifd([
+            (Tag::BitsPerSample, TagValue::Short(16)),
+            (Tag::PhotometricInterpretation, TagValue::Short(0)),
+            (Tag::SamplesPerPixel, TagValue::Short(1)),
+        ]);
+        let data = [0u16, 65535].iter().flat_map(|v| v.to_ne_bytes()).collect();
+        let array = Array::try_new(data, [1, 2, 1], Some(DataType::UInt16)).unwrap();
+        // WhiteIsZero is inverted
+        let rgba = render_rgba(&ifd, &array, None, &RenderOptions::new()).unwrap();
+        assert_eq!(pixels(&rgba), [[255, 255, 255, 255], [0, 0, 0, 255]]);
+
+        // Three bands of a planar float image, rescaled from 0..=1
+        let ifd = test_ifd([
+            (Tag::BitsPerSample, shorts(&[32; 4])),
+            (Tag::SampleFormat, shorts(&[3; 4])),
+            (Tag::PhotometricInterpretation, TagValue::Short(1)),
+            (Tag::SamplesPerPixel, TagValue::Short(4)),
+            (Tag::PlanarConfiguration, TagValue::Short(2)),
+        ]);
+        let data = [0.0f32, 1.0, 0.5, 0.5, 1.0, 0.0, 2.0, -1.0]
+            .iter()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect();
+        let array = Array::try_new(data, [4, 1, 2], Some(DataType::Float32)).unwrap();
+        assert!(render_rgba(&ifd, &array, None, &RenderOptions::new()).is_err());
+        let options = RenderOptions::new()
+            .with_bands(vec![3, 1, 0])
+            .with_rescale(0.0, 1.0);
+        let rgba = render_rgba(&ifd, &array, None, &options).unwrap();
+        assert_eq!(pixels(&rgba), [[255, 128, 0, 255], [0, 128, 255, 255]]);
+
+        let options = RenderOptions::new().with_bands(vec![4]);
+        assert!(render_rgba(&ifd, &array, None, &options).is_err());
+    }
+}
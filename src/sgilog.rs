@@ -0,0 +1,200 @@
+//! Decoding of SGI LogL and LogLuv data compressed with the run-length encoding of libtiff's
+//! `COMPRESSION_SGILOG`, enabled by the `sgilog` feature.
+//!
+//! Each row stores the bytes of its pixels as separate planes, most significant byte first, each
+//! encoded as runs of a repeated byte or literal bytes. LogL pixels are 16-bit logarithmic
+//! luminance values, and LogLuv pixels add 8-bit CIE u' and v' indices to make 32 bits.
+//!
+//! Like libtiff, pixels are returned as the data type declared by the IFD: 16-bit integers hold
+//! the raw log luminance and the chromaticities scaled by 2^15, while 32-bit floats hold CIE XYZ
+//! values, or only Y for LogL.
+
+use crate::error::{AsyncTiffError, AsyncTiffResult, TiffError, TiffUnsupportedError};
+use crate::tags::PhotometricInterpretation;
+
+/// The scale of the encoded u' and v' chromaticity indices.
+const UV_SCALE: f64 = 410.0;
+
+/// Decode SGILOG-compressed `data` of `width` by `height` pixels to native-endian samples with
+/// `bits_per_sample` bits.
+pub(crate) fn decode(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    photometric_interpretation: PhotometricInterpretation,
+    bits_per_sample: u16,
+) -> AsyncTiffResult<Vec<u8>> {
+    let (planes, samples) = match photometric_interpretation {
+        PhotometricInterpretation::LogL => (2, 1),
+        PhotometricInterpretation::LogLuv => (4, 3),
+        other => {
+            return Err(TiffError::UnsupportedError(
+                TiffUnsupportedError::UnsupportedInterpretation(other),
+            )
+            .into())
+        }
+    };
+    if bits_per_sample != 16 && bits_per_sample != 32 {
+        return Err(
+            TiffError::UnsupportedError(TiffUnsupportedError::InterpretationWithBits(
+                photometric_interpretation,
+                vec![bits_per_sample as u8],
+            ))
+            .into(),
+        );
+    }
+
+    let mut out = Vec::with_capacity(width * height * samples * bits_per_sample as usize / 8);
+    let mut pixels = vec![0u32; width];
+    let mut data = data;
+    for row in 0..height {
+        pixels.fill(0);
+        for plane in 0..planes {
+            let shift = 8 * (planes - 1 - plane);
+            decode_plane(&mut data, &mut pixels, shift).ok_or_else(|| {
+                AsyncTiffError::General(format!("SGILOG data ended before row {row}"))
+            })?;
+        }
+        for &pixel in &pixels {
+            match (photometric_interpretation, bits_per_sample) {
+                (PhotometricInterpretation::LogL, 16) => {
+                    out.extend_from_slice(&(pixel as u16).to_ne_bytes())
+                }
+                (PhotometricInterpretation::LogL, _) => {
+                    out.extend_from_slice(&(log_l16_to_y(pixel as u16) as f32).to_ne_bytes())
+                }
+                (_, 16) => {
+                    let (u, v) = uv(pixel);
+                    for sample in [(pixel >> 16) as i16, scale_uv(u), scale_uv(v)] {
+                        out.extend_from_slice(&sample.to_ne_bytes());
+                    }
+                }
+                _ => {
+                    for sample in log_luv32_to_xyz(pixel) {
+                        out.extend_from_slice(&sample.to_ne_bytes());
+                    }
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Decode one byte plane of a row from the start of `data`, OR-ing each byte shifted by `shift`
+/// into `pixels`. Returns `None` if `data` ends before the row is complete.
+fn decode_plane(data: &mut &[u8], pixels: &mut [u32], shift: usize) -> Option<()> {
+    let mut i = 0;
+    while i < pixels.len() {
+        let (&header, rest) = data.split_first()?;
+        if header >= 128 {
+            // A run of one byte repeated `header - 126` times
+            let (&value, rest) = rest.split_first()?;
+            let end = (i + header as usize - 126).min(pixels.len());
+            pixels[i..end]
+                .iter_mut()
+                .for_each(|p| *p |= (value as u32) << shift);
+            i = end;
+            *data = rest;
+        } else {
+            // `header` literal bytes
+            let len = (header as usize).min(pixels.len() - i);
+            let literal = rest.get(..len)?;
+            pixels[i..i + len]
+                .iter_mut()
+                .zip(literal)
+                .for_each(|(p, &value)| *p |= (value as u32) << shift);
+            i += len;
+            *data = &rest[len..];
+        }
+    }
+    Some(())
+}
+
+/// The luminance Y of a 16-bit log luminance value, whose top bit is the sign.
+fn log_l16_to_y(value: u16) -> f64 {
+    let exponent = value & 0x7fff;
+    if exponent == 0 {
+        return 0.0;
+    }
+    let y = (std::f64::consts::LN_2 / 256.0 * (exponent as f64 + 0.5)
+        - std::f64::consts::LN_2 * 64.0)
+        .exp();
+    if value & 0x8000 != 0 {
+        -y
+    } else {
+        y
+    }
+}
+
+/// The u' and v' chromaticity of a 32-bit LogLuv pixel.
+fn uv(pixel: u32) -> (f64, f64) {
+    let u = ((pixel >> 8) & 0xff) as f64 + 0.5;
+    let v = (pixel & 0xff) as f64 + 0.5;
+    (u / UV_SCALE, v / UV_SCALE)
+}
+
+/// Scale a chromaticity to the 16-bit integer representation of libtiff.
+fn scale_uv(value: f64) -> i16 {
+    (value * 32768.0) as i16
+}
+
+/// The CIE XYZ values of a 32-bit LogLuv pixel.
+fn log_luv32_to_xyz(pixel: u32) -> [f32; 3] {
+    let y = log_l16_to_y((pixel >> 16) as u16);
+    if y <= 0.0 {
+        return [0.0; 3];
+    }
+    let (u, v) = uv(pixel);
+    let s = 1.0 / (6.0 * u - 16.0 * v + 12.0);
+    let (x_chroma, y_chroma) = (9.0 * u * s, 4.0 * v * s);
+    [
+        (x_chroma / y_chroma * y) as f32,
+        y as f32,
+        ((1.0 - x_chroma - y_chroma) / y_chroma * y) as f32,
+    ]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_log_l() {
+        // A run of three 0x40 high bytes, then three literal low bytes
+        let data = [129, 0x40, 3, 0x00, 0x80, 0xff];
+        let decoded = decode(&data, 3, 1, PhotometricInterpretation::LogL, 16).unwrap();
+        let values = decoded
+            .chunks_exact(2)
+            .map(|b| u16::from_ne_bytes([b[0], b[1]]))
+            .collect::<Vec<_>>();
+        assert_eq!(values, [0x4000, 0x4080, 0x40ff]);
+
+        // Log luminance 0x4000 is Y = 2^(0.5 / 256)
+        let decoded = decode(&data, 3, 1, PhotometricInterpretation::LogL, 32).unwrap();
+        let y = f32::from_ne_bytes(decoded[..4].try_into().unwrap());
+        assert!((y - 2f32.powf(0.5 / 256.0)).abs() < 1e-6);
+
+        assert!(decode(&data[..5], 3, 1, PhotometricInterpretation::LogL, 16).is_err());
+        assert!(decode(&data, 3, 1, PhotometricInterpretation::RGB, 16).is_err());
+    }
+
+    #[test]
+    fn test_decode_log_luv() {
+        // One pixel with literal bytes in each of the four planes
+        let data = [1, 0x40, 1, 0x00, 1, 0x56, 1, 0xc2];
+        let decoded = decode(&data, 1, 1, PhotometricInterpretation::LogLuv, 16).unwrap();
+        let values = decoded
+            .chunks_exact(2)
+            .map(|b| i16::from_ne_bytes([b[0], b[1]]))
+            .collect::<Vec<_>>();
+        assert_eq!(values, [0x4000, 6913, 15544]);
+
+        let decoded = decode(&data, 1, 1, PhotometricInterpretation::LogLuv, 32).unwrap();
+        let xyz = decoded
+            .chunks_exact(4)
+            .map(|b| f32::from_ne_bytes(b.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        assert!((xyz[1] - 2f32.powf(0.5 / 256.0)).abs() < 1e-6);
+        assert!(xyz[0] > 0.0 && xyz[2] > 0.0);
+    }
+}
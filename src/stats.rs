@@ -0,0 +1,434 @@
+//! Per-band pixel statistics of an image.
+
+use futures::{StreamExt, TryStreamExt};
+
+use crate::decoder::DecoderRegistry;
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::reader::AsyncFileReader;
+use crate::tags::PlanarConfiguration;
+use crate::{Array, ImageFileDirectory, TypedArray, Window};
+
+/// Options for [`compute_statistics`].
+#[derive(Debug, Clone)]
+pub struct StatisticsOptions {
+    sample: Option<usize>,
+    histogram_bins: Option<usize>,
+    histogram_range: Option<(f64, f64)>,
+    concurrency: usize,
+}
+
+impl Default for StatisticsOptions {
+    fn default() -> Self {
+        Self {
+            sample: None,
+            histogram_bins: None,
+            histogram_range: None,
+            concurrency: 8,
+        }
+    }
+}
+
+impl StatisticsOptions {
+    /// Create new StatisticsOptions, which read every tile or strip and compute no histogram.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only read up to `max_chunks` tiles or strips, spread evenly over the image, which gives
+    /// approximate statistics of large images at a fraction of the cost.
+    pub fn with_sample(mut self, max_chunks: usize) -> Self {
+        self.sample = Some(max_chunks.max(1));
+        self
+    }
+
+    /// Also compute a histogram of each band with `bins` equally sized bins.
+    ///
+    /// Unless a range is set with [`with_histogram_range`][Self::with_histogram_range], the
+    /// histogram spans the minimum to maximum value of each band, which requires reading the
+    /// image a second time.
+    pub fn with_histogram(mut self, bins: usize) -> Self {
+        self.histogram_bins = Some(bins.max(1));
+        self
+    }
+
+    /// Bin the histogram between `min` and `max`, ignoring values outside that range.
+    pub fn with_histogram_range(mut self, min: f64, max: f64) -> Self {
+        self.histogram_range = Some((min, max));
+        self
+    }
+
+    /// Fetch and decode at most `concurrency` tiles or strips at once. The default is 8.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+}
+
+/// Statistics of the valid pixels of one band, computed by [`compute_statistics`].
+///
+/// Pixels equal to the image's nodata value and NaN pixels are not valid. If a band has no valid
+/// pixels, `min`, `max`, `mean` and `stddev` are NaN.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BandStatistics {
+    /// The smallest valid value.
+    pub min: f64,
+    /// The largest valid value.
+    pub max: f64,
+    /// The mean of the valid values.
+    pub mean: f64,
+    /// The population standard deviation of the valid values.
+    pub stddev: f64,
+    /// The number of valid pixels.
+    pub valid_count: u64,
+    /// The number of pixels that were skipped as nodata or NaN.
+    pub nodata_count: u64,
+    /// The histogram of the valid values, if requested.
+    pub histogram: Option<Histogram>,
+}
+
+/// A histogram of equally sized bins between `min` and `max`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// The lower edge of the first bin.
+    pub min: f64,
+    /// The upper edge of the last bin, which is included in the last bin.
+    pub max: f64,
+    /// The number of values in each bin.
+    pub counts: Vec<u64>,
+}
+
+impl Histogram {
+    fn new(min: f64, max: f64, bins: usize) -> Self {
+        Self {
+            min,
+            max,
+            counts: vec![0; bins],
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        if !(self.min..=self.max).contains(&value) {
+            return;
+        }
+        let bins = self.counts.len();
+        let bin = if self.max > self.min {
+            ((value - self.min) / (self.max - self.min) * bins as f64) as usize
+        } else {
+            0
+        };
+        self.counts[bin.min(bins - 1)] += 1;
+    }
+}
+
+/// Compute per-band statistics of `ifd`, reading its tiles or strips from `reader`.
+///
+/// Pixels equal to the image's [`nodata`][ImageFileDirectory::nodata] value are skipped, as are
+/// NaN pixels of floating point images. The result has one entry per band of the decoded image.
+pub async fn compute_statistics(
+    ifd: &ImageFileDirectory,
+    reader: &dyn AsyncFileReader,
+    decoder_registry: &DecoderRegistry,
+    options: &StatisticsOptions,
+) -> AsyncTiffResult<Vec<BandStatistics>> {
+    let windows = chunk_windows(ifd, options.sample)?;
+    let nodata = ifd.nodata().map(|nodata| nodata.as_f64());
+    let planar = ifd.planar_configuration() == PlanarConfiguration::Planar;
+
+    let mut bands: Vec<Accumulator> = vec![];
+    let histogram = |range: (f64, f64), bins| Histogram::new(range.0, range.1, bins);
+    let fixed_histogram = options
+        .histogram_bins
+        .zip(options.histogram_range)
+        .map(|(bins, range)| histogram(range, bins));
+    read_chunks(ifd, reader, decoder_registry, &windows, options, |array| {
+        for_each_value(array, planar, |band, value| {
+            if band >= bands.len() {
+                bands.resize_with(band + 1, || Accumulator::new(fixed_histogram.clone()));
+            }
+            bands[band].add(value, nodata);
+        })
+    })
+    .await?;
+
+    if let (Some(bins), None) = (options.histogram_bins, options.histogram_range) {
+        for band in &mut bands {
+            band.histogram = Some(histogram((band.min, band.max), bins));
+        }
+        read_chunks(ifd, reader, decoder_registry, &windows, options, |array| {
+            for_each_value(array, planar, |band, value| {
+                let band = &mut bands[band];
+                if band.is_valid(value, nodata) {
+                    if let Some(histogram) = &mut band.histogram {
+                        histogram.add(value);
+                    }
+                }
+            })
+        })
+        .await?;
+    }
+
+    Ok(bands.into_iter().map(Accumulator::finish).collect())
+}
+
+/// The windows of the tiles or strips to read, keeping at most `sample` of them.
+fn chunk_windows(ifd: &ImageFileDirectory, sample: Option<usize>) -> AsyncTiffResult<Vec<Window>> {
+    let (width, height) = (ifd.image_width(), ifd.image_height());
+    let (chunk_width, chunk_height, x_count, y_count) =
+        match (ifd.tile_width(), ifd.tile_height(), ifd.tile_count()) {
+            (Some(tile_width), Some(tile_height), Some((x_count, y_count))) => {
+                (tile_width, tile_height, x_count, y_count)
+            }
+            _ => {
+                let strip_count = ifd.strip_count().ok_or(AsyncTiffError::General(
+                    "IFD has neither tiles nor strips".to_string(),
+                ))?;
+                (width, ifd.strip_height(), 1, strip_count)
+            }
+        };
+    let count = x_count * y_count;
+    let indices: Vec<usize> = match sample {
+        Some(sample) if sample < count => (0..sample).map(|i| i * count / sample).collect(),
+        _ => (0..count).collect(),
+    };
+    Ok(indices
+        .into_iter()
+        .map(|i| {
+            let col_off = (i % x_count) as u32 * chunk_width;
+            let row_off = (i / x_count) as u32 * chunk_height;
+            Window::new(
+                col_off,
+                row_off,
+                chunk_width.min(width - col_off),
+                chunk_height.min(height - row_off),
+            )
+        })
+        .collect())
+}
+
+/// Read and decode `windows` concurrently, passing each array to `f` as it becomes available.
+async fn read_chunks(
+    ifd: &ImageFileDirectory,
+    reader: &dyn AsyncFileReader,
+    decoder_registry: &DecoderRegistry,
+    windows: &[Window],
+    options: &StatisticsOptions,
+    mut f: impl FnMut(&Array),
+) -> AsyncTiffResult<()> {
+    let mut arrays = futures::stream::iter(windows)
+        .map(|window| ifd.read_window(*window, reader, decoder_registry))
+        .buffer_unordered(options.concurrency);
+    while let Some(array) = arrays.try_next().await? {
+        f(&array);
+    }
+    Ok(())
+}
+
+/// Call `f` with the band index and value of every element of `array`.
+fn for_each_value(array: &Array, planar: bool, mut f: impl FnMut(usize, f64)) {
+    let [_, d1, d2] = array.shape();
+    let band_of = |i: usize| if planar { i / (d1 * d2) } else { i % d2 };
+    macro_rules! visit {
+        ($values:expr) => {
+            for (i, &value) in $values.iter().enumerate() {
+                f(band_of(i), value as f64)
+            }
+        };
+    }
+    match array.data() {
+        TypedArray::Bool(values) => {
+            for (i, &value) in values.iter().enumerate() {
+                f(band_of(i), value as u8 as f64)
+            }
+        }
+        TypedArray::UInt8(values) => visit!(values),
+        TypedArray::UInt16(values) => visit!(values),
+        TypedArray::UInt32(values) => visit!(values),
+        TypedArray::UInt64(values) => visit!(values),
+        TypedArray::Int8(values) => visit!(values),
+        TypedArray::Int16(values) => visit!(values),
+        TypedArray::Int32(values) => visit!(values),
+        TypedArray::Int64(values) => visit!(values),
+        TypedArray::Float32(values) => visit!(values),
+        TypedArray::Float64(values) => visit!(values),
+    }
+}
+
+/// Running statistics of one band, using Welford's algorithm for the variance.
+#[derive(Debug, Clone)]
+struct Accumulator {
+    min: f64,
+    max: f64,
+    mean: f64,
+    m2: f64,
+    valid_count: u64,
+    nodata_count: u64,
+    histogram: Option<Histogram>,
+}
+
+impl Accumulator {
+    fn new(histogram: Option<Histogram>) -> Self {
+        Self {
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            mean: 0.0,
+            m2: 0.0,
+            valid_count: 0,
+            nodata_count: 0,
+            histogram,
+        }
+    }
+
+    fn is_valid(&self, value: f64, nodata: Option<f64>) -> bool {
+        !value.is_nan() && nodata != Some(value)
+    }
+
+    fn add(&mut self, value: f64, nodata: Option<f64>) {
+        if !self.is_valid(value, nodata) {
+            self.nodata_count += 1;
+            return;
+        }
+        self.valid_count += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        let delta = value - self.mean;
+        self.mean += delta / self.valid_count as f64;
+        self.m2 += delta * (value - self.mean);
+        if let Some(histogram) = &mut self.histogram {
+            histogram.add(value);
+        }
+    }
+
+    fn finish(self) -> BandStatistics {
+        let (min, max, mean, stddev) = if self.valid_count > 0 {
+            let variance = self.m2 / self.valid_count as f64;
+            (self.min, self.max, self.mean, variance.sqrt())
+        } else {
+            (f64::NAN, f64::NAN, f64::NAN, f64::NAN)
+        };
+        BandStatistics {
+            min,
+            max,
+            mean,
+            stddev,
+            valid_count: self.valid_count,
+            nodata_count: self.nodata_count,
+            histogram: self.histogram,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::util::open_tiff;
+
+    /// Statistics computed from the whole image read as one window.
+    async fn expected(filename: &str) -> Vec<(f64, f64, f64, u64)> {
+        let (reader, tiff) = open_tiff(filename).await;
+        let ifd = &tiff.ifds()[0];
+        let window = Window::new(0, 0, ifd.image_width(), ifd.image_height());
+        let array = ifd
+            .read_window(window, reader.as_ref(), &DecoderRegistry::default())
+            .await
+            .unwrap();
+        let planar = ifd.planar_configuration() == PlanarConfiguration::Planar;
+        let mut bands: Vec<Vec<f64>> = vec![];
+        for_each_value(&array, planar, |band, value| {
+            if band >= bands.len() {
+                bands.resize(band + 1, vec![]);
+            }
+            bands[band].push(value);
+        });
+        bands
+            .into_iter()
+            .map(|values| {
+                let n = values.len();
+                let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                (min, max, values.iter().sum::<f64>() / n as f64, n as u64)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_compute_statistics() {
+        for filename in [
+            "image-tiff/tiled-rgb-u8.tif",
+            "image-tiff/planar-rgb-u8.tif",
+            "image-tiff/gradient-1c-32b-float.tiff",
+        ] {
+            let (reader, tiff) = open_tiff(filename).await;
+            let stats = compute_statistics(
+                &tiff.ifds()[0],
+                reader.as_ref(),
+                &DecoderRegistry::default(),
+                &StatisticsOptions::new(),
+            )
+            .await
+            .unwrap();
+            let expected = expected(filename).await;
+            assert_eq!(stats.len(), expected.len(), "{filename}");
+            for (band, (min, max, mean, count)) in stats.iter().zip(expected) {
+                assert_eq!((band.min, band.max, band.valid_count), (min, max, count));
+                assert!((band.mean - mean).abs() < 1e-6 * mean.abs().max(1.0));
+                assert!(band.stddev >= 0.0);
+                assert_eq!(band.nodata_count, 0);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_statistics_nodata_and_histogram() {
+        let (reader, tiff) = open_tiff("image-tiff/tiled-rgb-u8.tif").await;
+        let mut ifd = tiff.ifds()[0].clone();
+        let all = compute_statistics(
+            &ifd,
+            reader.as_ref(),
+            &DecoderRegistry::default(),
+            &StatisticsOptions::new().with_histogram(256),
+        )
+        .await
+        .unwrap();
+        let histogram = all[0].histogram.as_ref().unwrap();
+        assert_eq!(histogram.counts.iter().sum::<u64>(), all[0].valid_count);
+        assert_eq!((histogram.min, histogram.max), (all[0].min, all[0].max));
+
+        // Use the minimum of the first band as nodata
+        ifd.gdal_nodata = Some(all[0].min.to_string());
+        let stats = compute_statistics(
+            &ifd,
+            reader.as_ref(),
+            &DecoderRegistry::default(),
+            &StatisticsOptions::new()
+                .with_histogram_range(0.0, 256.0)
+                .with_histogram(256),
+        )
+        .await
+        .unwrap();
+        assert!(stats[0].nodata_count > 0);
+        assert_eq!(
+            stats[0].valid_count + stats[0].nodata_count,
+            all[0].valid_count
+        );
+        assert!(stats[0].min > all[0].min);
+        let counts = &stats[0].histogram.as_ref().unwrap().counts;
+        assert_eq!(counts[all[0].min as usize], 0);
+        assert_eq!(counts.iter().sum::<u64>(), stats[0].valid_count);
+
+        // A single sampled tile
+        let sampled = compute_statistics(
+            &ifd,
+            reader.as_ref(),
+            &DecoderRegistry::default(),
+            &StatisticsOptions::new().with_sample(1),
+        )
+        .await
+        .unwrap();
+        let tile_width = ifd.tile_width().unwrap().min(ifd.image_width());
+        let tile_height = ifd.tile_height().unwrap().min(ifd.image_height());
+        assert_eq!(
+            sampled[1].valid_count + sampled[1].nodata_count,
+            tile_width as u64 * tile_height as u64
+        );
+    }
+}
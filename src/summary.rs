@@ -0,0 +1,391 @@
+//! Summaries of the images in a TIFF, for catalogs like [STAC].
+//!
+//! An [`IfdSummary`] collects the dimensions, data type, CRS, georeferencing, compression and
+//! nodata value of one IFD, which is everything the STAC [projection] and [raster] extensions
+//! describe. With the `serde` feature, the summary can be serialized to any format, and
+//! [`IfdSummary::to_stac_json`] writes it as the properties of a STAC item or asset.
+//!
+//! ```
+//! # tokio_test::block_on(async {
+//! use std::sync::Arc;
+//!
+//! use async_tiff::reader::{AsyncFileReader, MemoryReader};
+//! use async_tiff::summary::summarize;
+//! use async_tiff::TIFF;
+//!
+//! let data = std::fs::read("fixtures/image-tiff/geo-5b.tif").unwrap();
+//! let reader = Arc::new(MemoryReader::new(data)) as Arc<dyn AsyncFileReader>;
+//! let tiff = TIFF::open(reader).await.unwrap();
+//!
+//! let summary = &summarize(&tiff)[0];
+//! assert_eq!(summary.bands, 5);
+//! # })
+//! ```
+//!
+//! [STAC]: https://stacspec.org
+//! [projection]: https://github.com/stac-extensions/projection
+//! [raster]: https://github.com/stac-extensions/raster
+
+use crate::geo::Crs;
+use crate::pyramid::is_overview;
+use crate::tags::Compression;
+use crate::{DataType, ImageFileDirectory, Nodata, Pyramid, TIFF};
+
+/// A summary of one IFD.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct IfdSummary {
+    /// The index of the IFD in the file.
+    pub index: usize,
+    /// The width of the image in pixels.
+    pub width: u32,
+    /// The height of the image in pixels.
+    pub height: u32,
+    /// The number of bands, i.e. samples per pixel.
+    pub bands: u16,
+    /// The data type of the samples, if supported.
+    pub data_type: Option<DataType>,
+    /// The EPSG code of the CRS, if it is part of the EPSG registry.
+    pub epsg: Option<u16>,
+    /// The user-defined CRS as a PROJJSON document, if it isn't part of the EPSG registry.
+    pub projjson: Option<String>,
+    /// The affine transform from pixel to CRS coordinates in GDAL order, if georeferenced.
+    pub geotransform: Option<[f64; 6]>,
+    /// The bounding box in CRS coordinates, as `[min_x, min_y, max_x, max_y]`.
+    pub bounds: Option<[f64; 4]>,
+    /// The size of a pixel in CRS units, as `[x, y]`.
+    pub resolution: Option<[f64; 2]>,
+    /// The compression of the image data.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_compression"))]
+    pub compression: Compression,
+    /// The GDAL nodata value.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_nodata"))]
+    pub nodata: Option<Nodata>,
+    /// Whether this IFD is a reduced-resolution overview of another image.
+    pub overview: bool,
+    /// The `(width, height)` of the overviews of this image, from finest to coarsest. Only set
+    /// for the full-resolution image by [`summarize`].
+    pub overview_levels: Vec<(u32, u32)>,
+}
+
+impl IfdSummary {
+    /// Summarize the IFD at `index` of a file. [`overview_levels`][Self::overview_levels] is
+    /// left empty, as overviews are separate IFDs.
+    ///
+    /// A CRS that can't be derived from the geo keys is left unset.
+    pub fn from_ifd(index: usize, ifd: &ImageFileDirectory) -> Self {
+        let crs = ifd
+            .geo_key_directory()
+            .and_then(|geo_keys| geo_keys.crs().ok());
+        let geotransform = ifd.geotransform().and_then(Result::ok);
+        Self {
+            index,
+            width: ifd.image_width(),
+            height: ifd.image_height(),
+            bands: ifd.samples_per_pixel(),
+            data_type: ifd.data_type(),
+            epsg: crs.as_ref().and_then(Crs::epsg),
+            projjson: match crs {
+                Some(Crs::ProjJson(projjson)) => Some(projjson),
+                _ => None,
+            },
+            geotransform,
+            bounds: ifd.native_bounds().and_then(Result::ok),
+            resolution: geotransform.map(|gt| [gt[1].hypot(gt[4]), gt[2].hypot(gt[5])]),
+            compression: ifd.compression(),
+            nodata: ifd.nodata(),
+            overview: is_overview(ifd),
+            overview_levels: vec![],
+        }
+    }
+
+    /// The STAC properties describing this image, as a JSON object.
+    ///
+    /// This holds the `proj:code`, `proj:projjson`, `proj:shape`, `proj:transform` and
+    /// `proj:bbox` fields of the projection extension, where known, and `raster:bands` with the
+    /// data type, nodata value and spatial resolution of every band.
+    #[cfg(feature = "serde")]
+    pub fn to_stac_json(&self) -> String {
+        let band = StacBand {
+            data_type: self.data_type.map(stac_data_type),
+            nodata: self.nodata,
+            spatial_resolution: self.resolution.filter(|[x, y]| x == y).map(|[x, _]| x),
+        };
+        let properties = StacProperties {
+            code: self.epsg.map(|epsg| format!("EPSG:{epsg}")),
+            projjson: self
+                .projjson
+                .as_deref()
+                .and_then(|projjson| serde_json::from_str(projjson).ok()),
+            shape: [self.height, self.width],
+            // STAC uses the coefficient order of rasterio's Affine
+            transform: self
+                .geotransform
+                .map(|[c, a, b, f, d, e]| [a, b, c, d, e, f, 0.0, 0.0, 1.0]),
+            bbox: self.bounds,
+            bands: vec![band; self.bands as usize],
+        };
+        serde_json::to_string(&properties).unwrap()
+    }
+}
+
+/// The STAC properties of an image, see [`IfdSummary::to_stac_json`].
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize)]
+struct StacProperties {
+    #[serde(rename = "proj:code", skip_serializing_if = "Option::is_none")]
+    code: Option<String>,
+    #[serde(rename = "proj:projjson", skip_serializing_if = "Option::is_none")]
+    projjson: Option<serde_json::Value>,
+    #[serde(rename = "proj:shape")]
+    shape: [u32; 2],
+    #[serde(rename = "proj:transform", skip_serializing_if = "Option::is_none")]
+    transform: Option<[f64; 9]>,
+    #[serde(rename = "proj:bbox", skip_serializing_if = "Option::is_none")]
+    bbox: Option<[f64; 4]>,
+    #[serde(rename = "raster:bands")]
+    bands: Vec<StacBand>,
+}
+
+/// A band in the STAC raster extension.
+#[cfg(feature = "serde")]
+#[derive(Clone, serde::Serialize)]
+struct StacBand {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data_type: Option<&'static str>,
+    #[serde(
+        serialize_with = "serialize_nodata",
+        skip_serializing_if = "Option::is_none"
+    )]
+    nodata: Option<Nodata>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spatial_resolution: Option<f64>,
+}
+
+/// Summarize every IFD of `tiff`, listing the overviews of the full-resolution image.
+pub fn summarize(tiff: &TIFF) -> Vec<IfdSummary> {
+    let mut summaries = tiff
+        .ifds()
+        .iter()
+        .enumerate()
+        .map(|(index, ifd)| IfdSummary::from_ifd(index, ifd))
+        .collect::<Vec<_>>();
+    if let Some(levels) = Pyramid::level_indices(tiff.ifds()) {
+        summaries[levels[0]].overview_levels = levels[1..]
+            .iter()
+            .map(|i| {
+                (
+                    tiff.ifds()[*i].image_width(),
+                    tiff.ifds()[*i].image_height(),
+                )
+            })
+            .collect();
+    }
+    summaries
+}
+
+/// Summarize every IFD of `tiff` as a JSON array of STAC properties, see
+/// [`IfdSummary::to_stac_json`].
+#[cfg(feature = "serde")]
+pub fn to_stac_json(tiff: &TIFF) -> String {
+    let properties = summarize(tiff)
+        .iter()
+        .map(IfdSummary::to_stac_json)
+        .collect::<Vec<_>>();
+    format!("[{}]", properties.join(","))
+}
+
+/// The name of `data_type` in the STAC raster extension.
+#[cfg(feature = "serde")]
+fn stac_data_type(data_type: DataType) -> &'static str {
+    match data_type {
+        // Bit masks have no STAC data type, and are read as bytes
+        DataType::Bool | DataType::UInt8 => "uint8",
+        DataType::UInt16 => "uint16",
+        DataType::UInt32 => "uint32",
+        DataType::UInt64 => "uint64",
+        DataType::Int8 => "int8",
+        DataType::Int16 => "int16",
+        DataType::Int32 => "int32",
+        DataType::Int64 => "int64",
+        DataType::Float32 => "float32",
+        DataType::Float64 => "float64",
+    }
+}
+
+/// The name of `compression`, following the lowercase names of libtiff and GDAL, or `None` for a
+/// compression method without a well-known name.
+pub fn compression_name(compression: Compression) -> Option<&'static str> {
+    let name = match compression {
+        Compression::None => "none",
+        Compression::Huffman => "ccittrle",
+        Compression::Fax3 => "ccittfax3",
+        Compression::Fax4 => "ccittfax4",
+        Compression::LZW => "lzw",
+        Compression::JPEG => "ojpeg",
+        Compression::ModernJPEG => "jpeg",
+        Compression::Deflate | Compression::OldDeflate => "deflate",
+        Compression::PackBits => "packbits",
+        Compression::LERC => "lerc",
+        Compression::LZMA => "lzma",
+        Compression::WebP => "webp",
+        Compression::JPEG2k => "jpeg2000",
+        Compression::SGILog => "sgilog",
+        Compression::SGILog24 => "sgilog24",
+        Compression::ZSTD => "zstd",
+        Compression::Unknown(_) => return None,
+    };
+    Some(name)
+}
+
+/// Serialize `compression` by its name, or as its numeric code if it has none.
+#[cfg(feature = "serde")]
+fn serialize_compression<S: serde::Serializer>(
+    compression: &Compression,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match compression_name(*compression) {
+        Some(name) => serializer.serialize_str(name),
+        None => serializer.serialize_u16(compression.to_u16()),
+    }
+}
+
+/// Serialize `nodata` as a number, or as `"nan"`, `"inf"` or `"-inf"` like STAC if it isn't
+/// finite.
+#[cfg(feature = "serde")]
+fn serialize_nodata<S: serde::Serializer>(
+    nodata: &Option<Nodata>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let Some(nodata) = *nodata else {
+        return serializer.serialize_none();
+    };
+    match nodata {
+        _ if nodata.is_nan() => serializer.serialize_str("nan"),
+        Nodata::Bool(value) => serializer.serialize_bool(value),
+        Nodata::UInt8(value) => serializer.serialize_u8(value),
+        Nodata::UInt16(value) => serializer.serialize_u16(value),
+        Nodata::UInt32(value) => serializer.serialize_u32(value),
+        Nodata::UInt64(value) => serializer.serialize_u64(value),
+        Nodata::Int8(value) => serializer.serialize_i8(value),
+        Nodata::Int16(value) => serializer.serialize_i16(value),
+        Nodata::Int32(value) => serializer.serialize_i32(value),
+        Nodata::Int64(value) => serializer.serialize_i64(value),
+        _ if nodata.as_f64() == f64::INFINITY => serializer.serialize_str("inf"),
+        _ if nodata.as_f64() == f64::NEG_INFINITY => serializer.serialize_str("-inf"),
+        Nodata::Float32(value) => serializer.serialize_f32(value),
+        Nodata::Float64(value) => serializer.serialize_f64(value),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::util::open_tiff;
+
+    #[tokio::test]
+    async fn test_summary() {
+        let (_, tiff) = open_tiff("image-tiff/geo-5b.tif").await;
+        let ifd = &tiff.ifds()[0];
+        let summary = &summarize(&tiff)[0];
+        assert_eq!(summary.index, 0);
+        assert_eq!(
+            (summary.width, summary.height),
+            (ifd.image_width(), ifd.image_height())
+        );
+        assert_eq!(summary.bands, 5);
+        assert_eq!(summary.data_type, ifd.data_type());
+        assert_eq!(summary.epsg, ifd.geo_key_directory().unwrap().epsg_code());
+        let geotransform = ifd.geotransform().unwrap().unwrap();
+        assert_eq!(summary.geotransform, Some(geotransform));
+        assert_eq!(
+            summary.resolution,
+            Some([geotransform[1].abs(), geotransform[5].abs()])
+        );
+        assert!(!summary.overview);
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_summary_stac_json() {
+        let (_, tiff) = open_tiff("image-tiff/geo-5b.tif").await;
+        let summary = &summarize(&tiff)[0];
+        let json = summary.to_stac_json();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value["proj:shape"],
+            serde_json::json!([summary.height, summary.width])
+        );
+        let [c, a, b, f, d, e] = summary.geotransform.unwrap();
+        assert_eq!(
+            value["proj:transform"],
+            serde_json::json!([a, b, c, d, e, f, 0.0, 0.0, 1.0])
+        );
+        let bands = value["raster:bands"].as_array().unwrap();
+        assert_eq!(bands.len(), 5);
+        assert_eq!(
+            bands[0]["data_type"],
+            stac_data_type(summary.data_type.unwrap())
+        );
+        if let Some(epsg) = summary.epsg {
+            assert_eq!(value["proj:code"], format!("EPSG:{epsg}"));
+        }
+        assert!(to_stac_json(&tiff).starts_with(&format!("[{json}")));
+
+        let (_, tiff) = open_tiff("image-tiff/tiled-jpeg-ycbcr.tif").await;
+        assert!(!summarize(&tiff)[0]
+            .to_stac_json()
+            .contains("proj:transform"));
+    }
+
+    #[tokio::test]
+    async fn test_summary_overviews() {
+        let (_, tiff) = open_tiff("image-tiff/tiled-jpeg-ycbcr.tif").await;
+        let summaries = summarize(&tiff);
+        let pyramid = Pyramid::from_tiff(&tiff).unwrap();
+        assert_eq!(
+            summaries[0].overview_levels.len(),
+            pyramid.overviews().len()
+        );
+        assert!(summaries[1..].iter().all(|s| s.overview_levels.is_empty()));
+        assert_eq!(
+            summaries.iter().filter(|s| s.overview).count(),
+            pyramid.overviews().len()
+        );
+        assert_eq!(summaries[0].epsg, None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[tokio::test]
+    async fn test_summary_serde() {
+        let (_, tiff) = open_tiff("image-tiff/geo-5b.tif").await;
+        let summary = &summarize(&tiff)[0];
+        let value = serde_json::to_value(summary).unwrap();
+        assert_eq!(value["bands"], 5);
+        assert_eq!(
+            value["compression"],
+            compression_name(summary.compression).unwrap()
+        );
+        assert_eq!(
+            value["data_type"],
+            stac_data_type(summary.data_type.unwrap())
+        );
+
+        let summary = IfdSummary {
+            compression: Compression::Unknown(60000),
+            nodata: Some(Nodata::Float32(f32::NEG_INFINITY)),
+            ..summary.clone()
+        };
+        let value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(value["compression"], 60000);
+        assert_eq!(value["nodata"], "-inf");
+    }
+
+    #[test]
+    fn test_compression_name() {
+        assert_eq!(compression_name(Compression::ModernJPEG), Some("jpeg"));
+        assert_eq!(compression_name(Compression::OldDeflate), Some("deflate"));
+        assert_eq!(compression_name(Compression::ZSTD), Some("zstd"));
+        assert_eq!(compression_name(Compression::Unknown(60000)), None);
+    }
+}
@@ -116,6 +116,8 @@ pub enum Tag(u16) unknown("A private or extension tag") {
     StripByteCounts = 279,
     StripOffsets = 273,
     SubfileType = 255,
+    /// Offsets of child IFDs, e.g. overviews of this image
+    SubIfds = 330,
     Threshholding = 263,
     XResolution = 282,
     YResolution = 283,
@@ -130,6 +132,13 @@ pub enum Tag(u16) unknown("A private or extension tag") {
     SMaxSampleValue = 341,
     // JPEG
     JPEGTables = 347,
+    // YCbCr
+    YCbCrCoefficients = 529,
+    YCbCrSubSampling = 530,
+    YCbCrPositioning = 531,
+    ReferenceBlackWhite = 532,
+    /// XMP metadata packet
+    Xmp = 700,
     // GeoTIFF
     ModelPixelScale = 33550, // (SoftDesk)
     ModelTransformation = 34264, // (JPL Carto Group)
@@ -137,6 +146,11 @@ pub enum Tag(u16) unknown("A private or extension tag") {
     GeoKeyDirectory = 34735, // (SPOT)
     GeoDoubleParams = 34736, // (SPOT)
     GeoAsciiParams = 34737, // (SPOT)
+    // EXIF
+    /// Offset of the EXIF IFD
+    ExifIfd = 34665,
+    /// Offset of the GPS IFD
+    GpsIfd = 34853,
     /// GDAL-specific NoData value
     GdalNodata = 42113,
     GdalMetadata = 42112, // XML metadata string
@@ -206,6 +220,9 @@ pub enum Compression(u16) unknown("A custom compression method") {
     // https://github.com/OSGeo/gdal/blob/4769b527b275fdb286cba95c8b35bbd131168e54/frmts/gtiff/gtiff.h#L136C26-L136C31
     WebP = 50001,
     JPEG2k = 34712,
+    // SGI LogLuv, with run-length encoding or packed into 24 bits per pixel
+    SGILog = 34676,
+    SGILog24 = 34677,
 
     // Self-assigned by libtiff
     ZSTD = 0xC350,
@@ -232,6 +249,43 @@ pub enum PhotometricInterpretation(u16) {
     CMYK = 5,
     YCbCr = 6,
     CIELab = 8,
+    /// SGI logarithmic luminance, for high dynamic range grayscale
+    LogL = 32844,
+    /// SGI logarithmic luminance and CIE u', v' chromaticity, for high dynamic range color
+    LogLuv = 32845,
+}
+}
+
+tags! {
+/// The logical order of bits within a byte.
+pub enum FillOrder(u16) unknown("An invalid fill order") {
+    /// Lower column values are stored in the higher-order bits of the byte
+    MsbToLsb = 1,
+    /// Lower column values are stored in the lower-order bits of the byte
+    LsbToMsb = 2,
+}
+}
+
+tags! {
+/// The orientation of the image with respect to the rows and columns, named after where the
+/// 0th row and 0th column are located.
+pub enum Orientation(u16) unknown("An invalid orientation") {
+    /// The 0th row is the visual top and the 0th column the visual left-hand side
+    TopLeft = 1,
+    /// The 0th row is the visual top and the 0th column the visual right-hand side
+    TopRight = 2,
+    /// The 0th row is the visual bottom and the 0th column the visual right-hand side
+    BottomRight = 3,
+    /// The 0th row is the visual bottom and the 0th column the visual left-hand side
+    BottomLeft = 4,
+    /// The 0th row is the visual left-hand side and the 0th column the visual top
+    LeftTop = 5,
+    /// The 0th row is the visual right-hand side and the 0th column the visual top
+    RightTop = 6,
+    /// The 0th row is the visual right-hand side and the 0th column the visual bottom
+    RightBottom = 7,
+    /// The 0th row is the visual left-hand side and the 0th column the visual bottom
+    LeftBottom = 8,
 }
 }
 
@@ -267,6 +321,18 @@ pub enum ResolutionUnit(u16) {
 }
 }
 
+tags! {
+/// The technique used to convert from gray to black and white pixels in bilevel images.
+pub enum Threshholding(u16) {
+    /// No dithering or halftoning has been applied
+    Bilevel = 1,
+    /// An ordered dither or halftone technique has been applied
+    Halftone = 2,
+    /// A randomized process such as error diffusion has been applied
+    ErrorDiffuse = 3,
+}
+}
+
 tags! {
 /// The format of sample values in each pixel (unsigned int, signed int, or floating point).
 pub enum SampleFormat(u16) unknown("An unknown extension sample format") {
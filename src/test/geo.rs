@@ -6,6 +6,10 @@ use object_store::local::LocalFileSystem;
 use crate::metadata::cache::ReadaheadMetadataCache;
 use crate::metadata::TiffMetadataReader;
 use crate::reader::{AsyncFileReader, ObjectReader};
+use crate::tags::Tag;
+use crate::test::util::{open_tiff, open_tiff_path, temp_copy};
+use crate::writer::TiffEditor;
+use crate::TagValue;
 
 #[tokio::test]
 async fn test_parse_file_with_unknown_geokey() {
@@ -19,8 +23,210 @@ async fn test_parse_file_with_unknown_geokey() {
     let mut metadata_reader = TiffMetadataReader::try_open(&prefetch_reader)
         .await
         .unwrap();
-    let _ = metadata_reader
+    let ifds = metadata_reader
         .read_all_ifds(&prefetch_reader)
         .await
         .unwrap();
+
+    // GeogTOWGS84GeoKey isn't part of the GeoTIFF spec
+    let geo_key_directory = ifds[0].geo_key_directory().unwrap();
+    let towgs84 = geo_key_directory.other_geo_keys.get(&2062).unwrap();
+    assert!(matches!(towgs84, TagValue::List(values) if values.len() == 3 || values.len() == 7));
+    assert!(ifds[0].parse_warnings().is_empty());
+}
+
+#[tokio::test]
+async fn test_geotransform_and_bounds() {
+    let (_, tiff) = open_tiff("image-tiff/geo-5b.tif").await;
+    let ifd = &tiff.ifds()[0];
+    let tiepoint = ifd.model_tiepoint().unwrap();
+    let scale = ifd.model_pixel_scale().unwrap();
+
+    let geotransform = ifd.geotransform().unwrap().unwrap();
+    assert_eq!(
+        geotransform,
+        [
+            tiepoint[3] - tiepoint[0] * scale[0],
+            scale[0],
+            0.0,
+            tiepoint[4] + tiepoint[1] * scale[1],
+            0.0,
+            -scale[1]
+        ]
+    );
+
+    let [min_x, min_y, max_x, max_y] = ifd.native_bounds().unwrap().unwrap();
+    assert_eq!(min_x, geotransform[0]);
+    assert_eq!(max_y, geotransform[3]);
+    assert!((max_x - min_x - ifd.image_width() as f64 * scale[0]).abs() < 1e-9);
+    assert!((max_y - min_y - ifd.image_height() as f64 * scale[1]).abs() < 1e-9);
+
+    let transform = ifd.affine_transform().unwrap().unwrap();
+    assert_eq!(transform.to_gdal(), geotransform);
+    let (col, row) = transform.crs_to_pixel(max_x, min_y).unwrap();
+    assert!((col - ifd.image_width() as f64).abs() < 1e-6);
+    assert!((row - ifd.image_height() as f64).abs() < 1e-6);
+}
+
+fn doubles(values: &[f64]) -> TagValue {
+    TagValue::List(values.iter().copied().map(TagValue::Double).collect())
+}
+
+/// A copy of a tiled fixture with 2×2 pixels whose top-left corner is at (100, 200).
+fn georeferenced_tiled_copy() -> PathBuf {
+    let path = temp_copy("image-tiff/tiled-rect-rgb-u8.tif");
+    let mut editor = TiffEditor::open(&path).unwrap();
+    editor
+        .set_tag(0, Tag::ModelPixelScale, doubles(&[2.0, 2.0, 0.0]))
+        .unwrap();
+    editor
+        .set_tag(
+            0,
+            Tag::ModelTiepoint,
+            doubles(&[0.0, 0.0, 0.0, 100.0, 200.0, 0.0]),
+        )
+        .unwrap();
+    editor.save().unwrap();
+    path
+}
+
+#[tokio::test]
+async fn test_bounds_for_tile() {
+    let path = georeferenced_tiled_copy();
+    let (_, tiff) = open_tiff_path(&path).await;
+    let ifd = &tiff.ifds()[0];
+    let (tile_width, tile_height) = (ifd.tile_width().unwrap(), ifd.tile_height().unwrap());
+    let (x_count, y_count) = ifd.tile_count().unwrap();
+    let [min_x, min_y, max_x, max_y] = ifd.native_bounds().unwrap().unwrap();
+
+    assert_eq!(
+        ifd.bounds_for_tile(0, 0).unwrap().unwrap(),
+        [
+            100.0,
+            200.0 - 2.0 * tile_height as f64,
+            100.0 + 2.0 * tile_width as f64,
+            200.0
+        ]
+    );
+    // The last tile is clipped to the image
+    let last = ifd
+        .bounds_for_tile(x_count - 1, y_count - 1)
+        .unwrap()
+        .unwrap();
+    assert_eq!(last[1], min_y);
+    assert_eq!(last[2], max_x);
+    assert!(last[0] > min_x && last[3] < max_y);
+
+    assert!(ifd.bounds_for_tile(x_count, 0).unwrap().is_err());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn test_tiles_in_bounds() {
+    let path = georeferenced_tiled_copy();
+    let (_, tiff) = open_tiff_path(&path).await;
+    let ifd = &tiff.ifds()[0];
+    let (x_count, y_count) = ifd.tile_count().unwrap();
+
+    let all = ifd
+        .tiles_in_bounds(ifd.native_bounds().unwrap().unwrap())
+        .unwrap()
+        .unwrap();
+    assert_eq!(all.len(), x_count * y_count);
+    assert_eq!(all[0], (0, 0));
+    assert_eq!(all[1], (1, 0));
+
+    // A box inside the first tile, and one straddling the corner of the first four tiles
+    let [tile_min_x, tile_min_y, tile_max_x, tile_max_y] =
+        ifd.bounds_for_tile(0, 0).unwrap().unwrap();
+    let inside = [
+        tile_min_x + 1.0,
+        tile_min_y + 1.0,
+        tile_max_x - 1.0,
+        tile_max_y - 1.0,
+    ];
+    assert_eq!(ifd.tiles_in_bounds(inside).unwrap().unwrap(), vec![(0, 0)]);
+    let corner = [
+        tile_max_x - 1.0,
+        tile_min_y - 1.0,
+        tile_max_x + 1.0,
+        tile_min_y + 1.0,
+    ];
+    assert_eq!(
+        ifd.tiles_in_bounds(corner).unwrap().unwrap(),
+        vec![(0, 0), (1, 0), (0, 1), (1, 1)]
+    );
+
+    // Touching the edge of a tile doesn't intersect it
+    let touching = [
+        tile_max_x,
+        tile_min_y + 1.0,
+        tile_max_x + 1.0,
+        tile_max_y - 1.0,
+    ];
+    assert_eq!(
+        ifd.tiles_in_bounds(touching).unwrap().unwrap(),
+        vec![(1, 0)]
+    );
+
+    let outside = [0.0, 0.0, 50.0, 50.0];
+    assert!(ifd.tiles_in_bounds(outside).unwrap().unwrap().is_empty());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn test_geotransform_from_model_transformation() {
+    let path = temp_copy("image-tiff/geo-5b.tif");
+    #[rustfmt::skip]
+    let rotated = vec![
+        2.0, 1.0, 0.0, 100.0,
+        1.0, -2.0, 0.0, 200.0,
+        0.0, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ];
+    let mut editor = TiffEditor::open(&path).unwrap();
+    editor.remove_tag(0, Tag::ModelTiepoint).unwrap();
+    editor.remove_tag(0, Tag::ModelPixelScale).unwrap();
+    editor
+        .set_tag(
+            0,
+            Tag::ModelTransformation,
+            TagValue::List(rotated.into_iter().map(TagValue::Double).collect()),
+        )
+        .unwrap();
+    editor.save().unwrap();
+
+    let (_, tiff) = open_tiff_path(&path).await;
+    let ifd = &tiff.ifds()[0];
+    assert_eq!(
+        ifd.geotransform().unwrap().unwrap(),
+        [100.0, 2.0, 1.0, 200.0, 1.0, -2.0]
+    );
+
+    // Tilting the raster out of the x/y plane can't be represented
+    #[rustfmt::skip]
+    let tilted = vec![
+        2.0, 0.0, 0.0, 100.0,
+        0.0, -2.0, 0.0, 200.0,
+        0.5, 0.0, 0.0, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    ];
+    let mut editor = TiffEditor::open(&path).unwrap();
+    editor
+        .set_tag(
+            0,
+            Tag::ModelTransformation,
+            TagValue::List(tilted.into_iter().map(TagValue::Double).collect()),
+        )
+        .unwrap();
+    editor.save().unwrap();
+
+    let (_, tiff) = open_tiff_path(&path).await;
+    let ifd = &tiff.ifds()[0];
+    assert!(ifd.geotransform().unwrap().is_err());
+    assert!(ifd.native_bounds().unwrap().is_err());
+
+    std::fs::remove_file(path).unwrap();
 }
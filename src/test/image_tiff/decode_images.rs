@@ -1,7 +1,14 @@
 extern crate tiff;
 
-use crate::tags::PhotometricInterpretation;
-use crate::test::util::open_tiff;
+use crate::config::AsyncTiffConfig;
+use crate::decoder::DecoderRegistry;
+use crate::metadata::ParseOptions;
+use crate::tags::{
+    Compression, FillOrder, Orientation, PhotometricInterpretation, PlanarConfiguration, Tag,
+};
+use crate::test::util::{ifd_with_tags, open_tiff, open_tiff_path, temp_copy};
+use crate::writer::TiffEditor;
+use crate::{DataType, DecodeOptions, FetchOptions, TagValue, TypedArray, Window, TIFF};
 
 #[tokio::test]
 async fn cmyk_u8() {
@@ -14,6 +21,49 @@ async fn cmyk_u8() {
     assert!(ifd.bits_per_sample().iter().all(|x| *x == 8));
 }
 
+#[tokio::test]
+async fn test_orientation_fill_order() {
+    let (_, tiff) = open_tiff("image-tiff/cmyk-3c-8b.tiff").await;
+    let ifd = &tiff.ifds()[0];
+    assert_eq!(ifd.orientation(), Some(Orientation::TopLeft));
+    assert_eq!(ifd.fill_order(), Some(FillOrder::MsbToLsb));
+    assert_eq!(ifd.threshholding(), None);
+
+    // Invalid values are kept rather than dropped
+    let ifd = ifd_with_tags(
+        1,
+        1,
+        [
+            (Tag::Orientation, TagValue::Short(9)),
+            (Tag::FillOrder, TagValue::Short(0)),
+        ],
+    );
+    assert_eq!(ifd.orientation(), Some(Orientation::Unknown(9)));
+    assert_eq!(ifd.fill_order(), Some(FillOrder::Unknown(0)));
+}
+
+#[tokio::test]
+async fn test_color_table() {
+    let (_, tiff) = open_tiff("image-tiff/palette-1c-8b.tiff").await;
+    let ifd = &tiff.ifds()[0];
+    let colormap = ifd.colormap().unwrap();
+    let table = ifd.color_table().unwrap();
+    assert_eq!(table.len(), 256);
+    assert_eq!(table[1], [colormap[1], colormap[257], colormap[513]]);
+
+    let table_u8 = ifd.color_table_u8().unwrap();
+    for (full, scaled) in table.iter().zip(&table_u8) {
+        for (f, s) in full.iter().zip(scaled) {
+            assert_eq!(*s, (*f as f64 / 257.0).round() as u8);
+        }
+    }
+    assert!(ifd
+        .color_table_rgba_u8()
+        .unwrap()
+        .iter()
+        .all(|entry| entry[3] == 255));
+}
+
 #[tokio::test]
 async fn test_cmyk_u16() {
     let (_, tiff) = open_tiff("image-tiff/cmyk-3c-16b.tiff").await;
@@ -49,13 +99,58 @@ async fn test_gray_u8() {
 
 #[tokio::test]
 async fn test_gray_u12() {
-    let (_, tiff) = open_tiff("image-tiff/12bit.cropped.tiff").await;
+    let (reader, tiff) = open_tiff("image-tiff/12bit.cropped.tiff").await;
     let ifd = &tiff.ifds()[0];
     assert!(matches!(
         ifd.photometric_interpretation(),
         PhotometricInterpretation::BlackIsZero
     ));
     assert!(ifd.bits_per_sample().iter().all(|x| *x == 12));
+
+    let options = DecodeOptions::new().with_unpack_bits(true);
+    let strip = ifd.fetch_strip(0, reader.as_ref()).await.unwrap();
+    let array = strip
+        .decode_with_options(&DecoderRegistry::default(), &options)
+        .unwrap();
+    assert_eq!(array.data_type(), Some(DataType::UInt16));
+    assert_eq!(array.shape()[1..], [ifd.image_width() as usize, 1]);
+    let TypedArray::UInt16(data) = array.data() else {
+        panic!("expected UInt16");
+    };
+    assert_eq!(data[..8], [8, 9, 0, 0, 2, 11, 0, 4]);
+    assert_eq!(data.iter().max(), Some(&27));
+}
+
+#[tokio::test]
+async fn test_logluv() {
+    let (reader, tiff) = open_tiff("image-tiff/logluv-3c-16b.tiff").await;
+    let ifd = &tiff.ifds()[0];
+    assert_eq!(
+        ifd.photometric_interpretation(),
+        PhotometricInterpretation::LogLuv
+    );
+    assert_eq!(ifd.compression(), Compression::SGILog);
+
+    let strip = ifd.fetch_strip(0, reader.as_ref()).await.unwrap();
+    let result = strip.decode(&DecoderRegistry::default());
+    #[cfg(not(feature = "sgilog"))]
+    assert!(matches!(
+        result,
+        Err(crate::error::AsyncTiffError::InternalTIFFError(
+            crate::error::TiffError::UnsupportedError(
+                crate::error::TiffUnsupportedError::UnsupportedCompression(Compression::SGILog)
+            )
+        ))
+    ));
+    #[cfg(feature = "sgilog")]
+    {
+        let array = result.unwrap();
+        assert_eq!(array.shape(), [1, 1, 3]);
+        let TypedArray::Int16(data) = array.data() else {
+            panic!("expected Int16");
+        };
+        assert_eq!(data, &[0, 6913, 15544]);
+    }
 }
 
 #[tokio::test]
@@ -253,6 +348,44 @@ async fn test_string_tags() {
     }
 }
 
+#[tokio::test]
+async fn test_exif_ifd() {
+    let (reader, tiff) = open_tiff("image-tiff/predictor-3-gray-f32.tif").await;
+    assert!(tiff.ifds()[0].exif().is_none());
+
+    let config =
+        AsyncTiffConfig::new().with_parse_options(ParseOptions::new().with_private_ifds(true));
+    let tiff = TIFF::open_with_config(reader, &config).await.unwrap();
+    let ifd = &tiff.ifds()[0];
+    let exif = ifd.exif().unwrap();
+    assert_eq!(exif.date_time_original, None);
+    assert_eq!(exif.other_tags.get(&40962), Some(&TagValue::Unsigned(200)));
+    assert_eq!(exif.other_tags.get(&40963), Some(&TagValue::Unsigned(200)));
+    assert!(ifd.gps().is_none());
+}
+
+#[tokio::test]
+async fn test_xmp() {
+    let path = temp_copy("image-tiff/minisblack-1c-8b.tiff");
+    let packet = b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><caf\xc3\xa9\xff/></x:xmpmeta>\0";
+    let mut editor = TiffEditor::open(&path).unwrap();
+    editor
+        .set_tag(
+            0,
+            Tag::Xmp,
+            TagValue::List(packet.iter().copied().map(TagValue::Byte).collect()),
+        )
+        .unwrap();
+    editor.save().unwrap();
+
+    let (_, tiff) = open_tiff_path(&path).await;
+    assert_eq!(
+        tiff.ifds()[0].xmp(),
+        Some("<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><caf\u{e9}\u{fffd}/></x:xmpmeta>")
+    );
+    std::fs::remove_file(path).unwrap();
+}
+
 // #[test]
 // fn test_decode_data() {
 //     let mut image_data = Vec::new();
@@ -320,12 +453,6 @@ async fn test_string_tags() {
 //     });
 // }
 
-// #[test]
-// fn issue_69() {
-//     test_image_sum_u16("issue_69_lzw.tiff", ColorType::Gray(16), 1015486);
-//     test_image_sum_u16("issue_69_packbits.tiff", ColorType::Gray(16), 1015486);
-// }
-
 // // TODO: GrayA support
 // //#[test]
 // //fn test_gray_alpha_u8()
@@ -642,3 +769,213 @@ async fn test_string_tags() {
 //     // gdal_translate -co COMPRESS=ZSTD -co ZSTD_LEVEL=20 int16.tif int16_zstd.tif
 //     test_image_sum_i16("int16_zstd.tif", ColorType::Gray(16), 354396);
 // }
+
+#[tokio::test]
+async fn test_fetch_strips() {
+    let registry = crate::decoder::DecoderRegistry::default();
+    for filename in ["image-tiff/rgb-3c-8b.tiff", "image-tiff/planar-rgb-u8.tif"] {
+        let (reader, tiff) = open_tiff(filename).await;
+        let ifd = &tiff.ifds()[0];
+        let count = ifd.strip_count().unwrap();
+        let strips = ifd.fetch_strips(0..count, reader.as_ref()).await.unwrap();
+        assert_eq!(strips.len(), count);
+
+        let mut rows = 0;
+        for (y, strip) in strips.into_iter().enumerate() {
+            assert_eq!((strip.x(), strip.y()), (0, y));
+            let single = ifd.fetch_strip(y, reader.as_ref()).await.unwrap();
+            let array = strip.decode(&registry).unwrap();
+            let single = single.decode(&registry).unwrap();
+            assert_eq!(array.shape(), single.shape());
+            assert_eq!(array.data().as_ref(), single.data().as_ref());
+            let planar = ifd.planar_configuration() == PlanarConfiguration::Planar;
+            rows += array.shape()[if planar { 1 } else { 0 }];
+        }
+        assert_eq!(rows, ifd.image_height() as usize, "{filename}");
+
+        assert!(ifd
+            .fetch_strips(0..count + 1, reader.as_ref())
+            .await
+            .is_err());
+    }
+}
+
+#[tokio::test]
+async fn test_issue_69_packbits() {
+    let registry = crate::decoder::DecoderRegistry::default();
+    for filename in [
+        "image-tiff/issue_69_lzw.tiff",
+        "image-tiff/issue_69_packbits.tiff",
+    ] {
+        let (reader, tiff) = open_tiff(filename).await;
+        let ifd = &tiff.ifds()[0];
+        let count = ifd.strip_count().unwrap();
+        let mut sum = 0u64;
+        for strip in ifd.fetch_strips(0..count, reader.as_ref()).await.unwrap() {
+            let array = strip.decode(&registry).unwrap();
+            let crate::TypedArray::UInt16(data) = array.data() else {
+                panic!("expected u16 data in {filename}");
+            };
+            sum += data.iter().map(|v| *v as u64).sum::<u64>();
+        }
+        assert_eq!(sum, 1015486, "{filename}");
+    }
+}
+
+#[tokio::test]
+async fn test_read_window() {
+    let registry = crate::decoder::DecoderRegistry::default();
+    for filename in [
+        "image-tiff/tiled-rect-rgb-u8.tif",
+        "image-tiff/rgb-3c-8b.tiff",
+        "image-tiff/planar-rgb-u8.tif",
+    ] {
+        let (reader, tiff) = open_tiff(filename).await;
+        let ifd = &tiff.ifds()[0];
+        let (width, height) = (ifd.image_width(), ifd.image_height());
+        let planar = ifd.planar_configuration() == PlanarConfiguration::Planar;
+
+        let full = ifd
+            .read_window(Window::new(0, 0, width, height), reader.as_ref(), &registry)
+            .await
+            .unwrap();
+        let bands = ifd.samples_per_pixel() as usize;
+        let (width, height) = (width as usize, height as usize);
+        let expected_shape = if planar {
+            [bands, height, width]
+        } else {
+            [height, width, bands]
+        };
+        assert_eq!(full.shape(), expected_shape, "{filename}");
+
+        // The first tile or strip is copied unchanged
+        let first = match ifd.tile_count() {
+            Some(_) => ifd.fetch_tile(0, 0, reader.as_ref()).await.unwrap(),
+            None => ifd.fetch_strip(0, reader.as_ref()).await.unwrap(),
+        };
+        let first = first.decode(&registry).unwrap();
+        let [d0, d1, d2] = first.shape();
+        let (first_height, first_width) = if planar { (d1, d2) } else { (d0, d1) };
+        let (first_height, first_width) = (first_height.min(height), first_width.min(width));
+        let ranges = |rows: std::ops::Range<usize>, cols: std::ops::Range<usize>| {
+            if planar {
+                [0..bands, rows, cols]
+            } else {
+                [rows, cols, 0..bands]
+            }
+        };
+        assert_eq!(
+            full.view()
+                .slice(ranges(0..first_height, 0..first_width))
+                .unwrap()
+                .to_array()
+                .data()
+                .as_ref(),
+            first
+                .view()
+                .slice(ranges(0..first_height, 0..first_width))
+                .unwrap()
+                .to_array()
+                .data()
+                .as_ref(),
+            "{filename}"
+        );
+
+        // A window spanning several tiles or strips matches the same part of the full image
+        let window = Window::new(3, 5, width as u32 / 2, height as u32 - 7);
+        let part = ifd
+            .read_window(window, reader.as_ref(), &registry)
+            .await
+            .unwrap();
+        let rows = window.row_off as usize..(window.row_off + window.height) as usize;
+        let cols = window.col_off as usize..(window.col_off + window.width) as usize;
+        let expected = full.view().slice(ranges(rows, cols)).unwrap().to_array();
+        assert_eq!(part.shape(), expected.shape(), "{filename}");
+        assert_eq!(part.data().as_ref(), expected.data().as_ref(), "{filename}");
+
+        // Fetching and mosaicking separately gives the same result, in any order of chunks
+        let chunks = ifd
+            .fetch_window(window, reader.as_ref(), &FetchOptions::default())
+            .await
+            .unwrap();
+        let decoded = chunks
+            .into_iter()
+            .rev()
+            .map(|tile| (tile.x(), tile.y(), tile.decode(&registry).unwrap()));
+        let mosaic = ifd.mosaic_window(window, decoded).unwrap();
+        assert_eq!(mosaic.data().as_ref(), part.data().as_ref(), "{filename}");
+
+        let out_of_bounds = Window::new(1, 0, width as u32, 1);
+        assert!(ifd
+            .read_window(out_of_bounds, reader.as_ref(), &registry)
+            .await
+            .is_err());
+
+        // A window whose end doesn't fit in u32 is out of bounds rather than wrapping around
+        let overflowing = Window::new(u32::MAX, 0, 2, 1);
+        assert!(overflowing.cols().is_err());
+        assert!(ifd
+            .read_window(overflowing, reader.as_ref(), &registry)
+            .await
+            .is_err());
+    }
+}
+
+#[tokio::test]
+async fn test_decode_big_endian() {
+    use tiff::decoder::{Decoder, DecodingResult};
+
+    let registry = crate::decoder::DecoderRegistry::default();
+    for filename in [
+        "image-tiff/minisblack-1c-16b.tiff",
+        "image-tiff/rgb-3c-16b.tiff",
+    ] {
+        let (reader, tiff) = open_tiff(filename).await;
+        assert_eq!(tiff.endianness(), crate::reader::Endianness::BigEndian);
+        let ifd = &tiff.ifds()[0];
+        let window = Window::new(0, 0, ifd.image_width(), ifd.image_height());
+        let array = ifd
+            .read_window(window, reader.as_ref(), &registry)
+            .await
+            .unwrap();
+        assert_eq!(array.data_type(), Some(crate::DataType::UInt16));
+        let crate::TypedArray::UInt16(data) = array.data() else {
+            panic!("expected u16 data in {filename}");
+        };
+
+        let file = std::fs::File::open(format!("fixtures/{filename}")).unwrap();
+        let DecodingResult::U16(expected) = Decoder::new(file).unwrap().read_image().unwrap()
+        else {
+            panic!("expected u16 data in {filename}");
+        };
+        assert_eq!(data, &expected, "{filename}");
+    }
+}
+
+#[tokio::test]
+async fn test_stream_tiles() {
+    use futures::{StreamExt, TryStreamExt};
+
+    let (reader, tiff) = open_tiff("image-tiff/tiled-rgb-u8.tif").await;
+    let ifd = &tiff.ifds()[0];
+    let (x_count, y_count) = ifd.tile_count().unwrap();
+    let mut tiles = ifd
+        .stream_tiles(reader.as_ref(), 2)
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+    assert_eq!(tiles.len(), x_count * y_count);
+    tiles.sort_by_key(|(x, y, _)| (*y, *x));
+    for (i, (x, y, tile)) in tiles.iter().enumerate() {
+        assert_eq!((*x, *y), (i % x_count, i / x_count));
+        assert_eq!((tile.x(), tile.y()), (*x, *y));
+    }
+
+    let (reader, tiff) = open_tiff("image-tiff/rgb-3c-8b.tiff").await;
+    let results = tiff.ifds()[0]
+        .stream_tiles(reader.as_ref(), 2)
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+}
@@ -2,4 +2,6 @@ mod geo;
 mod geotiff_test_data;
 mod image_tiff;
 mod ome_tiff;
+mod send_sync;
+mod silent;
 pub(crate) mod util;
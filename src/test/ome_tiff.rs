@@ -58,4 +58,12 @@ async fn test_ome_tiff_single_channel() {
 
     assert!(ifd.bits_per_sample().iter().all(|x| *x == 8));
     assert_eq!(ifd.software(), Some("LOCI Bio-Formats"));
+
+    #[cfg(feature = "ome")]
+    {
+        let metadata = crate::ome::OmeMetadata::from_tiff(&tiff).unwrap().unwrap();
+        let planes = metadata.images[0].pixels.planes();
+        let channels = planes.iter().map(|p| (p.ifd, p.c)).collect::<Vec<_>>();
+        assert_eq!(channels, [(0, 0), (1, 1), (2, 2)]);
+    }
 }
@@ -0,0 +1,47 @@
+//! Compile-time checks that the public types can be shared between threads.
+
+use crate::decoder::DecoderRegistry;
+use crate::metadata::cache::ReadaheadMetadataCache;
+use crate::metadata::TiffMetadataReader;
+#[cfg(feature = "object_store")]
+use crate::reader::ObjectReader;
+#[cfg(feature = "reqwest")]
+use crate::reader::ReqwestReader;
+use crate::reader::{AsyncFileReader, BlockCacheReader, StatsReader};
+use crate::{Array, ImageFileDirectory, Pyramid, Tile, TIFF};
+
+fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+fn assert_clone<T: Clone>() {}
+
+#[test]
+fn test_public_types_are_send_sync() {
+    assert_send_sync::<TIFF>();
+    assert_send_sync::<ImageFileDirectory>();
+    assert_send_sync::<Pyramid>();
+    assert_send_sync::<Tile>();
+    assert_send_sync::<Array>();
+    assert_send_sync::<DecoderRegistry>();
+    assert_send_sync::<TiffMetadataReader>();
+    assert_send_sync::<ReadaheadMetadataCache<std::sync::Arc<dyn AsyncFileReader>>>();
+    assert_send_sync::<BlockCacheReader<std::sync::Arc<dyn AsyncFileReader>>>();
+    assert_send_sync::<StatsReader<std::sync::Arc<dyn AsyncFileReader>>>();
+    #[cfg(feature = "object_store")]
+    assert_send_sync::<ObjectReader>();
+    #[cfg(feature = "reqwest")]
+    assert_send_sync::<ReqwestReader>();
+}
+
+#[test]
+fn test_shared_types_are_clone() {
+    assert_clone::<TIFF>();
+    assert_clone::<ImageFileDirectory>();
+    assert_clone::<Pyramid>();
+    assert_clone::<Tile>();
+    assert_clone::<DecoderRegistry>();
+    assert_clone::<StatsReader<std::sync::Arc<dyn AsyncFileReader>>>();
+    #[cfg(feature = "object_store")]
+    assert_clone::<ObjectReader>();
+    #[cfg(feature = "reqwest")]
+    assert_clone::<ReqwestReader>();
+}
@@ -0,0 +1,75 @@
+//! Checks that the library doesn't write to stdout or stderr.
+//!
+//! Output captured by the test harness can't be inspected, so the test runs itself in a child
+//! process and checks the output of that process instead.
+
+use std::process::Command;
+
+use bytes::Bytes;
+
+use crate::metadata::{ParseOptions, TiffMetadataReader};
+use crate::test::util::{open_tiff, tiff_with_ifds};
+
+/// Set in the child process to parse the IFDs instead of spawning another child.
+const CHILD_ENV: &str = "ASYNC_TIFF_TEST_SILENT_CHILD";
+const BEGIN: &str = "<<<begin>>>";
+const END: &str = "<<<end>>>";
+
+/// Parse IFDs of valid files, and of a malformed file in lenient mode, which collects warnings.
+async fn parse_ifds() {
+    for filename in [
+        "image-tiff/tiled-jpeg-ycbcr.tif",
+        "image-tiff/geo-5b.tif",
+        "image-tiff/predictor-3-gray-f32.tif",
+    ] {
+        open_tiff(filename).await;
+    }
+
+    // A TIFF without SamplesPerPixel, BitsPerSample or PhotometricInterpretation, a SHORT
+    // XResolution, and an entry of unknown type
+    let fetch = Bytes::from(tiff_with_ifds(&[&[
+        (256, 3, 1, 4),
+        (257, 3, 1, 4),
+        (282, 3, 1, 72),
+        (300, 99, 1, 0),
+    ]]));
+    let options = ParseOptions::lenient().with_private_ifds(true);
+    let ifds = TiffMetadataReader::try_open(&fetch)
+        .await
+        .unwrap()
+        .with_parse_options(options)
+        .read_all_ifds(&fetch)
+        .await
+        .unwrap();
+    assert!(!ifds[0].parse_warnings().is_empty());
+}
+
+#[test]
+fn test_parsing_is_silent() {
+    if std::env::var_os(CHILD_ENV).is_some() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        print!("{BEGIN}");
+        runtime.block_on(parse_ifds());
+        print!("{END}");
+        return;
+    }
+
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "test::silent::test_parsing_is_silent",
+            "--nocapture",
+            "--test-threads=1",
+        ])
+        .env(CHILD_ENV, "1")
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(output.status.success(), "{stdout}\n{stderr}");
+
+    let (_, parsing) = stdout.split_once(BEGIN).expect("the test didn't run");
+    let (parsing, _) = parsing.split_once(END).unwrap();
+    assert_eq!(parsing, "", "parsing wrote to stdout");
+    assert_eq!(stderr, "", "parsing wrote to stderr");
+}
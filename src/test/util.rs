@@ -1,11 +1,15 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use object_store::local::LocalFileSystem;
 
 use crate::metadata::TiffMetadataReader;
-use crate::reader::{AsyncFileReader, ObjectReader};
-use crate::TIFF;
+use crate::reader::{AsyncFileReader, Endianness, ObjectReader};
+use crate::tag_value::TagValue;
+use crate::tags::Tag;
+use crate::{ImageFileDirectory, TIFF};
 
 const TEST_IMAGE_DIR: &str = "fixtures/";
 
@@ -18,3 +22,68 @@ pub(crate) async fn open_tiff(filename: &str) -> (Arc<dyn AsyncFileReader>, TIFF
     let mut metadata_reader = TiffMetadataReader::try_open(&reader).await.unwrap();
     (reader.clone(), metadata_reader.read(&reader).await.unwrap())
 }
+
+pub(crate) async fn open_tiff_path(path: &Path) -> (Arc<dyn AsyncFileReader>, TIFF) {
+    let store = Arc::new(LocalFileSystem::new());
+    let path = object_store::path::Path::from_absolute_path(path).unwrap();
+    let reader = Arc::new(ObjectReader::new(store, path)) as Arc<dyn AsyncFileReader>;
+    let mut metadata_reader = TiffMetadataReader::try_open(&reader).await.unwrap();
+    (reader.clone(), metadata_reader.read(&reader).await.unwrap())
+}
+
+/// A unique path in the temporary directory ending in `filename`.
+pub(crate) fn temp_path(filename: &str) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "async-tiff-{}-{}-{filename}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+    ))
+}
+
+/// Copy a fixture to a unique temporary path, so that tests can modify it.
+pub(crate) fn temp_copy(filename: &str) -> PathBuf {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let src = manifest_dir.join(TEST_IMAGE_DIR).join(filename);
+    let dst = temp_path(&src.file_name().unwrap().to_string_lossy());
+    std::fs::copy(src, &dst).unwrap();
+    dst
+}
+
+/// A little-endian classic TIFF with `ifds` stored one after the other from offset 8, each given
+/// as `(tag, field type, count, value)` entries and followed by a next IFD offset of 0.
+///
+/// Only the first IFD is reachable from the header; tests refer to the others through offset tags
+/// and append any out-of-line tag data to the returned bytes.
+pub(crate) fn tiff_with_ifds(ifds: &[&[(u16, u16, u32, u32)]]) -> Vec<u8> {
+    let mut data = b"II\x2a\x00\x08\x00\x00\x00".to_vec();
+    for entries in ifds {
+        data.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+        for &(tag, field_type, count, value) in entries.iter() {
+            data.extend_from_slice(&tag.to_le_bytes());
+            data.extend_from_slice(&field_type.to_le_bytes());
+            data.extend_from_slice(&count.to_le_bytes());
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&0u32.to_le_bytes());
+    }
+    data
+}
+
+/// A little-endian IFD of a `width` by `height` single-band 8-bit grayscale image, with `tags`
+/// added to or replacing these defaults.
+pub(crate) fn ifd_with_tags(
+    width: u32,
+    height: u32,
+    tags: impl IntoIterator<Item = (Tag, TagValue)>,
+) -> ImageFileDirectory {
+    let mut ifd_tags = HashMap::from([
+        (Tag::ImageWidth, TagValue::Unsigned(width)),
+        (Tag::ImageLength, TagValue::Unsigned(height)),
+        (Tag::BitsPerSample, TagValue::Short(8)),
+        (Tag::PhotometricInterpretation, TagValue::Short(1)),
+        (Tag::SamplesPerPixel, TagValue::Short(1)),
+    ]);
+    ifd_tags.extend(tags);
+    ImageFileDirectory::from_tags(ifd_tags, Endianness::LittleEndian).unwrap()
+}
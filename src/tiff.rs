@@ -1,7 +1,21 @@
+use std::sync::Arc;
+
+use bytes::Bytes;
+
+use crate::config::{AsyncTiffConfig, MetadataCacheMode};
+use crate::error::AsyncTiffResult;
 use crate::ifd::ImageFileDirectory;
-use crate::reader::Endianness;
+use crate::metadata::cache::{PrefetchBuffer, PrefetchBufferStrategy, ReadaheadMetadataCache};
+use crate::metadata::{MetadataFetch, RequestBudget, TiffMetadataReader};
+use crate::pyramid::level_for_scale;
+use crate::reader::{AsyncFileReader, Endianness, MemoryReader};
+use crate::{ChunkRecord, Pyramid};
 
 /// A TIFF file.
+///
+/// A `TIFF` only holds parsed metadata, so it can be cloned and shared between threads freely.
+/// Data is read through a separate [`AsyncFileReader`], which can be shared as an
+/// `Arc<dyn AsyncFileReader>`.
 #[derive(Debug, Clone)]
 pub struct TIFF {
     endianness: Endianness,
@@ -14,6 +28,23 @@ impl TIFF {
         Self { ifds, endianness }
     }
 
+    /// Read all metadata from `reader` using the process-wide defaults of
+    /// [`AsyncTiffConfig::global`].
+    pub async fn open(reader: Arc<dyn AsyncFileReader>) -> AsyncTiffResult<Self> {
+        Self::open_with_config(reader, &AsyncTiffConfig::global()).await
+    }
+
+    /// Read all metadata from `reader`, prefetching and limiting requests according to `config`.
+    pub async fn open_with_config(
+        reader: Arc<dyn AsyncFileReader>,
+        config: &AsyncTiffConfig,
+    ) -> AsyncTiffResult<Self> {
+        match config.request_budget() {
+            Some(budget) => read_tiff(RequestBudget::new(reader, budget), config).await,
+            None => read_tiff(reader, config).await,
+        }
+    }
+
     /// Access the underlying Image File Directories.
     pub fn ifds(&self) -> &[ImageFileDirectory] {
         &self.ifds
@@ -24,6 +55,32 @@ impl TIFF {
         self.endianness
     }
 
+    /// The index into [`ifds`][Self::ifds] of the coarsest image that still has at least the
+    /// resolution needed to render the full-resolution image downsampled by `decimation`.
+    ///
+    /// Only the full-resolution image and its overviews are considered, as grouped by
+    /// [`Pyramid::from_tiff`]. Returns `None` if there is no full-resolution image.
+    pub fn select_by_decimation(&self, decimation: f64) -> Option<usize> {
+        let indices = Pyramid::level_indices(&self.ifds)?;
+        let levels = indices.iter().map(|i| &self.ifds[*i]).collect::<Vec<_>>();
+        Some(indices[level_for_scale(&levels, decimation)])
+    }
+
+    /// The index into [`ifds`][Self::ifds] of the coarsest image whose pixel size is at most
+    /// `target_resolution`, in the units of the `ModelPixelScale` tag.
+    ///
+    /// Overviews rarely carry their own georeferencing, so their pixel size is derived from
+    /// `ModelPixelScale` of the full-resolution image and the ratio of image widths. Returns
+    /// `None` if the full-resolution image has no `ModelPixelScale`.
+    pub fn select_overview(&self, target_resolution: f64) -> Option<usize> {
+        let full_resolution = Pyramid::level_indices(&self.ifds)?[0];
+        let resolution = self.ifds[full_resolution]
+            .model_pixel_scale()?
+            .first()?
+            .abs();
+        self.select_by_decimation(target_resolution / resolution)
+    }
+
     /// Returns the minimum prefetch size that covers all metadata.
     ///
     /// Computed as the minimum non-zero offset across every IFD's `TileOffsets`
@@ -49,6 +106,84 @@ impl TIFF {
             .min()
             .expect("TIFF spec requires every IFD to have StripOffsets or TileOffsets")
     }
+
+    /// The [`chunk_records`][ImageFileDirectory::chunk_records] of every IFD, together with the
+    /// index of the IFD.
+    pub fn chunk_manifest(
+        &self,
+    ) -> impl Iterator<Item = (usize, impl Iterator<Item = ChunkRecord> + '_)> + '_ {
+        self.ifds
+            .iter()
+            .enumerate()
+            .map(|(index, ifd)| (index, ifd.chunk_records()))
+    }
+
+    /// Serialize the chunks of every IFD as [kerchunk] references to the file at `url`.
+    ///
+    /// Each IFD becomes a Zarr v2 array named after its index. Images are 2D for single-band
+    /// images, `(y, x, band)` for chunky and `(band, y, x)` for planar images. Chunks that aren't
+    /// stored are omitted, so they read as the nodata value.
+    ///
+    /// Returns an error if an IFD can't be described by standard Zarr codecs: only
+    /// uncompressed, Deflate, LZW and ZSTD chunks without a predictor are supported, and strips
+    /// must all have the same height.
+    ///
+    /// [kerchunk]: https://fsspec.github.io/kerchunk/spec.html
+    pub fn to_kerchunk_json(&self, url: &str) -> AsyncTiffResult<String> {
+        crate::manifest::to_kerchunk_json(self, url)
+    }
+
+    /// Serialize the parsed metadata of every IFD, including tile and strip offsets, so that it
+    /// can be persisted, e.g. in a database, and restored with
+    /// [`from_serialized`][Self::from_serialized] to read tiles without fetching or parsing the
+    /// header of the file again.
+    ///
+    /// The result is a BigTIFF file without image data, in the byte order of this file, whose
+    /// IFDs hold the tags of the IFDs of this file. EXIF and GPS metadata, entries with unknown
+    /// field types and tags claimed by extensions other than GeoTIFF are not kept.
+    pub fn serialize(&self) -> AsyncTiffResult<Vec<u8>> {
+        crate::writer::encode_snapshot(self)
+    }
+
+    /// Restore a TIFF serialized with [`serialize`][Self::serialize].
+    ///
+    /// The IFDs are parsed from `data` in memory, so nothing is fetched from the original file.
+    pub fn from_serialized(data: impl Into<Bytes>) -> AsyncTiffResult<Self> {
+        let reader = MemoryReader::new(data);
+        futures::executor::block_on(async {
+            TiffMetadataReader::try_open(&reader)
+                .await?
+                .read(&reader)
+                .await
+        })
+    }
+}
+
+async fn read_tiff<F: MetadataFetch>(fetch: F, config: &AsyncTiffConfig) -> AsyncTiffResult<TIFF> {
+    match config.metadata_cache() {
+        MetadataCacheMode::Readahead => {
+            let fetch = ReadaheadMetadataCache::new(fetch)
+                .with_initial_size(config.prefetch())
+                .with_multiplier(config.multiplier());
+            read_metadata(&fetch, config).await
+        }
+        MetadataCacheMode::Prefetch => {
+            let strategy = PrefetchBufferStrategy::new(config.prefetch());
+            read_metadata(&PrefetchBuffer::with_strategy(fetch, strategy), config).await
+        }
+        MetadataCacheMode::None => read_metadata(&fetch, config).await,
+    }
+}
+
+async fn read_metadata<F: MetadataFetch>(
+    fetch: &F,
+    config: &AsyncTiffConfig,
+) -> AsyncTiffResult<TIFF> {
+    TiffMetadataReader::try_open(fetch)
+        .await?
+        .with_parse_options(config.parse_options().clone())
+        .read(fetch)
+        .await
 }
 
 #[cfg(test)]
@@ -65,6 +200,90 @@ mod test {
     use crate::reader::{AsyncFileReader, ObjectReader};
     use crate::TypedArray;
 
+    #[test]
+    fn test_select_overview() {
+        use crate::tag_value::TagValue;
+        use crate::tags::Tag;
+        use crate::test::util::ifd_with_tags;
+
+        let ifd = |width: u32, new_subfile_type: u32| {
+            let mut tags = vec![(Tag::NewSubfileType, TagValue::Unsigned(new_subfile_type))];
+            if new_subfile_type == 0 {
+                let scale = [10.0, 10.0, 0.0].map(TagValue::Double).to_vec();
+                tags.push((Tag::ModelPixelScale, TagValue::List(scale)));
+            }
+            ifd_with_tags(width, width, tags)
+        };
+        // Full resolution, a mask, and overviews at 2x, 4x and 8x
+        let tiff = TIFF::new(
+            vec![
+                ifd(1024, 0),
+                ifd(1024, 4),
+                ifd(512, 1),
+                ifd(256, 1),
+                ifd(128, 1),
+            ],
+            Endianness::LittleEndian,
+        );
+
+        assert_eq!(tiff.select_by_decimation(1.0), Some(0));
+        assert_eq!(tiff.select_by_decimation(3.0), Some(2));
+        assert_eq!(tiff.select_overview(5.0), Some(0));
+        assert_eq!(tiff.select_overview(40.0), Some(3));
+        assert_eq!(tiff.select_overview(79.0), Some(3));
+        assert_eq!(tiff.select_overview(1000.0), Some(4));
+
+        // Identical overviews are told apart by their position
+        let duplicates = TIFF::new(
+            vec![ifd(1024, 0), ifd(512, 1), ifd(512, 1)],
+            Endianness::LittleEndian,
+        );
+        assert_eq!(duplicates.select_by_decimation(2.0), Some(2));
+
+        let ungeoreferenced = TIFF::new(vec![ifd(1024, 1)], Endianness::LittleEndian);
+        assert_eq!(ungeoreferenced.select_overview(10.0), None);
+    }
+
+    #[tokio::test]
+    async fn test_serialize_round_trip() {
+        use crate::decoder::DecoderRegistry;
+        use crate::test::util::open_tiff;
+        use crate::Window;
+
+        for path in [
+            "image-tiff/tiled-rgb-u8.tif",
+            "image-tiff/tiled-jpeg-ycbcr.tif",
+            "image-tiff/planar-rgb-u8.tif",
+            "image-tiff/geo-5b.tif",
+            "image-tiff/palette-1c-8b.tiff",
+            "image-tiff/rgb-3c-16b.tiff",
+            "image-tiff/bigtiff/BigTIFFMotorola.tif",
+        ] {
+            let (reader, tiff) = open_tiff(path).await;
+            let serialized = tiff.serialize().unwrap();
+            let restored = TIFF::from_serialized(serialized.clone()).unwrap();
+            assert_eq!(restored.endianness(), tiff.endianness(), "{path}");
+            assert_eq!(restored.ifds(), tiff.ifds(), "{path}");
+            assert_eq!(restored.serialize().unwrap(), serialized, "{path}");
+
+            // Tiles are read from the original file using the restored offsets
+            let registry = DecoderRegistry::default();
+            let (ifd, expected) = (&restored.ifds()[0], &tiff.ifds()[0]);
+            let window = Window::new(0, 0, ifd.image_width().min(8), ifd.image_height().min(8));
+            let array = ifd.read_window(window, reader.as_ref(), &registry).await;
+            let expected = expected
+                .read_window(window, reader.as_ref(), &registry)
+                .await;
+            assert_eq!(
+                array.unwrap().data().as_ref(),
+                expected.unwrap().data().as_ref(),
+                "{path}"
+            );
+        }
+
+        assert!(TIFF::from_serialized(b"not a tiff".to_vec()).is_err());
+    }
+
     #[tokio::test]
     async fn test_header_byte_size_matches_min_tile_offset() {
         use crate::test::util::open_tiff;
@@ -1,13 +1,130 @@
+use std::io::Read;
+use std::ops::Range;
+use std::sync::Arc;
+
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
 use bytes::Bytes;
+use flate2::bufread::ZlibDecoder;
 
 use crate::array::Array;
-use crate::decoder::DecoderRegistry;
-use crate::error::{AsyncTiffResult, TiffError, TiffUnsupportedError};
+use crate::decoder::{DecodeContext, Decoder, DecoderRegistry};
+use crate::error::{AsyncTiffError, AsyncTiffResult, TiffError, TiffUnsupportedError};
 use crate::ifd::CompressedBytes;
 use crate::predictor::{fix_endianness, unpredict_float, unpredict_hdiff};
 use crate::reader::Endianness;
-use crate::tags::{Compression, PhotometricInterpretation, PlanarConfiguration, Predictor};
-use crate::DataType;
+use crate::tags::{
+    Compression, FillOrder, PhotometricInterpretation, PlanarConfiguration, Predictor, SampleFormat,
+};
+use crate::ycbcr::YCbCr;
+use crate::{DataType, TypedArray};
+
+/// Options for decoding a [`Tile`] with [`Tile::decode_with_options`].
+///
+/// ```
+/// use async_tiff::DecodeOptions;
+///
+/// // Decode only the first and third band, unpacking 12-bit samples to u16
+/// let options = DecodeOptions::new()
+///     .with_bands([0, 2])
+///     .with_unpack_bits(true);
+/// assert_eq!(options.bands(), Some([0, 2].as_slice()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    unpack_bits: bool,
+    reverse_predictor: bool,
+    native_endianness: bool,
+    expand_colormap: bool,
+    bands: Option<Vec<usize>>,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self {
+            unpack_bits: false,
+            reverse_predictor: true,
+            native_endianness: true,
+            expand_colormap: false,
+            bands: None,
+        }
+    }
+}
+
+impl DecodeOptions {
+    /// Create new DecodeOptions, which return samples as [`Tile::decode`] does.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expand unsigned integer samples of 1 to 7 bits into `u8` and of 9 to 15 bits, such as
+    /// 12-bit sensor data, into `u16` arrays, following the image's
+    /// [`FillOrder`]. Defaults to `false`, which decodes 1-bit images to boolean masks and
+    /// returns an error for other such bit depths.
+    pub fn with_unpack_bits(mut self, unpack_bits: bool) -> Self {
+        self.unpack_bits = unpack_bits;
+        self
+    }
+
+    /// Whether samples that aren't whole bytes are unpacked.
+    pub fn unpack_bits(&self) -> bool {
+        self.unpack_bits
+    }
+
+    /// Whether to reverse the predictor of the image. Defaults to `true`; with `false`, samples
+    /// keep the differences stored in the file, e.g. to reverse the predictor elsewhere.
+    ///
+    /// This is the only way to opt out of predictor reversal: [`Tile::decompress`] decodes with
+    /// this and [`with_native_endianness`][Self::with_native_endianness] set to `false`.
+    pub fn with_reverse_predictor(mut self, reverse_predictor: bool) -> Self {
+        self.reverse_predictor = reverse_predictor;
+        self
+    }
+
+    /// Whether the predictor of the image is reversed.
+    pub fn reverse_predictor(&self) -> bool {
+        self.reverse_predictor
+    }
+
+    /// Whether to convert samples to native byte order. Defaults to `true`; with `false`, the
+    /// bytes of the array keep the byte order of the file.
+    pub fn with_native_endianness(mut self, native_endianness: bool) -> Self {
+        self.native_endianness = native_endianness;
+        self
+    }
+
+    /// Whether samples are converted to native byte order.
+    pub fn native_endianness(&self) -> bool {
+        self.native_endianness
+    }
+
+    /// Whether to map the palette indices of [`RGBPalette`][PhotometricInterpretation::RGBPalette]
+    /// images to 16-bit RGB through their colormap. Defaults to `false`. Other images are not
+    /// affected.
+    pub fn with_expand_colormap(mut self, expand_colormap: bool) -> Self {
+        self.expand_colormap = expand_colormap;
+        self
+    }
+
+    /// Whether palette images are expanded to RGB.
+    pub fn expand_colormap(&self) -> bool {
+        self.expand_colormap
+    }
+
+    /// Only return the bands at the given indices, in this order. By default all bands are
+    /// returned.
+    ///
+    /// Bands are selected after expanding the colormap, so they refer to the red, green and blue
+    /// bands of palette images. Selecting a band that doesn't exist is an error when decoding.
+    pub fn with_bands(mut self, bands: impl IntoIterator<Item = usize>) -> Self {
+        self.bands = Some(bands.into_iter().collect());
+        self
+    }
+
+    /// The indices of the bands to return, or `None` for all bands.
+    pub fn bands(&self) -> Option<&[usize]> {
+        self.bands.as_deref()
+    }
+}
 
 /// A TIFF Tile response.
 ///
@@ -24,6 +141,11 @@ pub struct Tile {
     pub(crate) data_type: Option<DataType>,
     pub(crate) samples_per_pixel: u16,
     pub(crate) bits_per_sample: u16,
+    /// The bits of each sample, only set if they differ between samples.
+    pub(crate) mixed_bits_per_sample: Option<Vec<u16>>,
+    /// The format of the first sample, used to unpack samples that aren't whole bytes.
+    pub(crate) sample_format: SampleFormat,
+    pub(crate) fill_order: FillOrder,
     pub(crate) endianness: Endianness,
     pub(crate) width: u32,
     pub(crate) height: u32,
@@ -36,6 +158,10 @@ pub struct Tile {
     /// LERC parameters from the LercParameters tag: [version, compression_type, ...]
     /// compression_type: 0 = none, 1 = deflate, 2 = zstd
     pub(crate) lerc_parameters: Option<Vec<u32>>,
+    /// Chroma subsampling and color conversion for YCbCr data not compressed with JPEG.
+    pub(crate) ycbcr: Option<YCbCr>,
+    /// The colormap of palette images, used to expand them to RGB.
+    pub(crate) colormap: Option<Arc<[u16]>>,
 }
 
 impl Tile {
@@ -73,81 +199,1035 @@ impl Tile {
         self.jpeg_tables.as_ref()
     }
 
+    /// Access the predictor tag of the IFD producing this tile.
+    ///
+    /// [`decode`][Self::decode] reverses the predictor, while
+    /// [`decompress`][Self::decompress] leaves it applied.
+    pub fn predictor(&self) -> Predictor {
+        self.predictor
+    }
+
     /// Decode this tile to an [`Array`].
     ///
+    /// The predictor of the IFD, if any, is reversed and samples are converted to native byte
+    /// order. Use [`decompress`][Self::decompress] to get the buffer as stored instead.
+    ///
     /// Decoding is separate from data fetching so that sync and async operations do not block the
     /// same runtime.
     pub fn decode(self, decoder_registry: &DecoderRegistry) -> AsyncTiffResult<Array> {
-        let decoder = decoder_registry
-            .as_ref()
-            .get(&self.compression_method)
-            .ok_or(TiffError::UnsupportedError(
-                TiffUnsupportedError::UnsupportedCompression(self.compression_method),
-            ))?;
+        self.decode_with_options(decoder_registry, &DecodeOptions::default())
+    }
+
+    /// Decode this tile to an [`Array`] like [`decode`][Self::decode], on the
+    /// [`DecodeExecutor`][crate::decoder::DecodeExecutor] of `decoder_registry` if it has one.
+    ///
+    /// Without an executor, the tile is decoded when the returned future is first polled,
+    /// blocking the calling task until it completes.
+    pub async fn decode_async(self, decoder_registry: &DecoderRegistry) -> AsyncTiffResult<Array> {
+        self.decode_async_with_options(decoder_registry, &DecodeOptions::default())
+            .await
+    }
 
+    /// Decode this tile like [`decode_async`][Self::decode_async], configured by `options` like
+    /// [`decode_with_options`][Self::decode_with_options].
+    pub async fn decode_async_with_options(
+        self,
+        decoder_registry: &DecoderRegistry,
+        options: &DecodeOptions,
+    ) -> AsyncTiffResult<Array> {
+        let Some(executor) = decoder_registry.executor() else {
+            return self.decode_with_options(decoder_registry, options);
+        };
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let registry = decoder_registry.clone();
+        let options = options.clone();
+        executor.execute(Box::new(move || {
+            // The receiver is gone if the decode was cancelled
+            let _ = sender.send(self.decode_with_options(&registry, &options));
+        }));
+        receiver.await.map_err(|_| {
+            AsyncTiffError::General("Decode task was dropped by the executor".to_string())
+        })?
+    }
+
+    /// Decode this tile to an [`Array`] like [`decode`][Self::decode], configuring predictor and
+    /// byte order handling, bit depth unpacking, colormap expansion and the returned bands with
+    /// `options`.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "decode",
+            level = "debug",
+            skip_all,
+            fields(x = self.x, y = self.y, compression = ?self.compression_method)
+        )
+    )]
+    pub fn decode_with_options(
+        self,
+        decoder_registry: &DecoderRegistry,
+        options: &DecodeOptions,
+    ) -> AsyncTiffResult<Array> {
+        let mut array = self.decode_array(decoder_registry, options)?;
+        if options.expand_colormap()
+            && self.photometric_interpretation == PhotometricInterpretation::RGBPalette
+        {
+            if let Some(colormap) = &self.colormap {
+                array = expand_colormap(&array, colormap, self.planar_configuration)?;
+            }
+        }
+        if let Some(bands) = options.bands() {
+            let axis = match self.planar_configuration {
+                PlanarConfiguration::Chunky => 2,
+                PlanarConfiguration::Planar => 0,
+            };
+            array = array.take(axis, bands)?;
+        }
+        Ok(array)
+    }
+
+    /// Decode every band of this tile according to `options`.
+    fn decode_array(
+        &self,
+        decoder_registry: &DecoderRegistry,
+        options: &DecodeOptions,
+    ) -> AsyncTiffResult<Array> {
+        #[cfg(feature = "sgilog")]
+        if self.compression_method == Compression::SGILog {
+            return self.decode_sgilog(decoder_registry);
+        }
+        let decoder = self.decoder(decoder_registry)?;
+
+        if options.unpack_bits() {
+            if let Some(data_type) = self.unpacked_data_type() {
+                return self.decode_unpacked(decoder, data_type);
+            }
+        }
+
+        if let Some(sample_bits) = &self.mixed_bits_per_sample {
+            return self.decode_mixed(decoder, sample_bits);
+        }
+        if let Some(ycbcr) = &self.ycbcr {
+            return self.decode_ycbcr(decoder, ycbcr);
+        }
+
+        let mut decoded = Vec::new();
+        let shape = self.decode_samples(decoder, &mut decoded, options)?;
+        Array::try_new(decoded, shape, self.data_type)
+    }
+
+    /// Decode this tile into `output`, replacing its contents, and return the shape of the
+    /// decoded data.
+    ///
+    /// `output` receives the bytes of the [`Array`] that [`decode`][Self::decode] would return,
+    /// in native byte order. Reusing one buffer across tiles, e.g. from a
+    /// [`BufferPool`][crate::decoder::BufferPool], avoids allocating for every tile with decoders
+    /// that implement [`Decoder::decode_into`].
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "decode_into",
+            level = "debug",
+            skip_all,
+            fields(x = self.x, y = self.y, compression = ?self.compression_method)
+        )
+    )]
+    pub fn decode_into(
+        &self,
+        decoder_registry: &DecoderRegistry,
+        output: &mut Vec<u8>,
+    ) -> AsyncTiffResult<[usize; 3]> {
+        #[cfg(feature = "sgilog")]
+        if self.compression_method == Compression::SGILog {
+            let array = self.decode_sgilog(decoder_registry)?;
+            output.clear();
+            output.extend_from_slice(array.data().as_ref());
+            return Ok(array.shape());
+        }
+        let decoder = self.decoder(decoder_registry)?;
+
+        let array = if let Some(sample_bits) = &self.mixed_bits_per_sample {
+            self.decode_mixed(decoder, sample_bits)?
+        } else if let Some(ycbcr) = &self.ycbcr {
+            self.decode_ycbcr(decoder, ycbcr)?
+        } else if self.data_type == Some(DataType::Bool) {
+            // Bit masks are expanded to one byte per sample
+            let mut decoded = Vec::new();
+            let shape = self.decode_samples(decoder, &mut decoded, &DecodeOptions::default())?;
+            Array::try_new(decoded, shape, self.data_type)?
+        } else {
+            output.clear();
+            return self.decode_samples(decoder, output, &DecodeOptions::default());
+        };
+        output.clear();
+        output.extend_from_slice(array.data().as_ref());
+        Ok(array.shape())
+    }
+
+    /// Decompress this tile into the empty `output`, reverse the predictor and convert samples
+    /// to native byte order as configured by `options`, returning the shape of the decoded data.
+    fn decode_samples(
+        &self,
+        decoder: &dyn Decoder,
+        output: &mut Vec<u8>,
+        options: &DecodeOptions,
+    ) -> AsyncTiffResult<[usize; 3]> {
         let samples = self.samples_per_pixel as usize;
         let bits_per_sample = self.bits_per_sample;
         // tile_width is the full encoded tile width — predictor must use this, not the cropped width
         let tile_width = self.width as usize;
 
-        let mut decoded_tile = match &self.compressed_bytes {
-            CompressedBytes::Chunky(bytes) => decoder.decode_tile(
-                bytes.clone(),
-                self.photometric_interpretation,
-                self.jpeg_tables.as_deref(),
-                self.samples_per_pixel,
-                bits_per_sample,
-                self.lerc_parameters.as_deref(),
-            )?,
-            CompressedBytes::Planar(band_bytes) => {
-                let bytes_per_sample = (bits_per_sample as usize).div_ceil(8);
-                let total_size =
-                    band_bytes.len() * tile_width * (self.height as usize) * bytes_per_sample;
-                let mut result = Vec::with_capacity(total_size);
-
-                for band_data in band_bytes {
-                    let decoded_band = decoder.decode_tile(
-                        band_data.clone(),
-                        self.photometric_interpretation,
-                        self.jpeg_tables.as_deref(),
-                        1,
-                        bits_per_sample,
-                        self.lerc_parameters.as_deref(),
-                    )?;
-                    result.extend_from_slice(&decoded_band);
-                }
+        self.decompress_into(decoder, output)?;
+
+        // Apply predictor on the full encoded tile width, then crop afterward.
+        let predictor = match options.reverse_predictor() {
+            true => self.predictor,
+            false => Predictor::None,
+        };
+        match predictor {
+            Predictor::None if !options.native_endianness() => {}
+            Predictor::None => fix_endianness(output, self.endianness, bits_per_sample),
+            Predictor::Horizontal => {
+                *output = unpredict_hdiff(
+                    std::mem::take(output),
+                    self.endianness,
+                    samples,
+                    bits_per_sample,
+                    tile_width,
+                )
+            }
+            Predictor::FloatingPoint => {
+                *output =
+                    unpredict_float(std::mem::take(output), samples, bits_per_sample, tile_width)?
+            }
+        }
+        // Reversing a predictor yields native byte order, so swap the bytes back if needed
+        if predictor != Predictor::None && !options.native_endianness() {
+            fix_endianness(output, self.endianness, bits_per_sample);
+        }
+
+        Ok(infer_shape(
+            self.planar_configuration,
+            self.width as _,
+            self.height as _,
+            samples,
+        ))
+    }
 
-                debug_assert_eq!(result.len(), total_size);
-                result
+    /// Decompress this tile without reversing the predictor or converting the byte order.
+    ///
+    /// This is [`decode_with_options`][Self::decode_with_options] with
+    /// [`with_reverse_predictor(false)`][DecodeOptions::with_reverse_predictor] and
+    /// [`with_native_endianness(false)`][DecodeOptions::with_native_endianness], returning the
+    /// bytes instead of an [`Array`]: the samples exactly as encoded in the file, with planar
+    /// bands concatenated. Subsampled YCbCr data is returned as stored, without upsampling the
+    /// chroma.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "decompress",
+            level = "debug",
+            skip_all,
+            fields(x = self.x, y = self.y, compression = ?self.compression_method)
+        )
+    )]
+    pub fn decompress(self, decoder_registry: &DecoderRegistry) -> AsyncTiffResult<Vec<u8>> {
+        let decoder = self.decoder(decoder_registry)?;
+        if self.mixed_bits_per_sample.is_some() || self.ycbcr.is_some() {
+            let sample_bits = self.mixed_bits_per_sample.as_deref().unwrap_or_default();
+            let CompressedBytes::Chunky(bytes) = &self.compressed_bytes else {
+                return Err(TiffError::UnsupportedError(
+                    TiffUnsupportedError::InconsistentBitsPerSample(
+                        sample_bits.iter().map(|&b| b as u8).collect(),
+                    ),
+                )
+                .into());
+            };
+            return decoder
+                .decode(bytes.clone(), &self.decode_context(self.samples_per_pixel))
+                .map_err(|err| self.decoding_error(err));
+        }
+        let options = DecodeOptions::new()
+            .with_reverse_predictor(false)
+            .with_native_endianness(false);
+        let mut decompressed = Vec::new();
+        self.decode_samples(decoder, &mut decompressed, &options)?;
+        Ok(decompressed)
+    }
+
+    /// The context passed to decoders for a buffer of this tile with `samples_per_pixel` samples.
+    ///
+    /// Decoders may produce at most the decoded size of the buffer, which
+    /// [`check_limits`][Self::check_limits] has checked against the registry's limits.
+    fn decode_context(&self, samples_per_pixel: u16) -> DecodeContext<'_> {
+        let pixel_bits = match &self.mixed_bits_per_sample {
+            Some(sample_bits) => sample_bits.iter().map(|&b| b as usize).sum(),
+            None => samples_per_pixel as usize * self.bits_per_sample as usize,
+        };
+        DecodeContext::new(
+            self.width,
+            self.height,
+            samples_per_pixel,
+            self.bits_per_sample,
+        )
+        .with_sample_format(self.sample_format)
+        .with_predictor(self.predictor)
+        .with_planar_configuration(self.planar_configuration)
+        .with_endianness(self.endianness)
+        .with_photometric_interpretation(self.photometric_interpretation)
+        .with_jpeg_tables(self.jpeg_tables.as_deref())
+        .with_lerc_parameters(self.lerc_parameters.as_deref())
+        .with_max_decoded_bytes(self.expected_decoded_len(pixel_bits) as u64)
+    }
+
+    /// Find the decoder for this tile, checking its decoded size against the registry's limits.
+    fn decoder<'a>(
+        &self,
+        decoder_registry: &'a DecoderRegistry,
+    ) -> AsyncTiffResult<&'a dyn Decoder> {
+        self.check_limits(decoder_registry)?;
+        decoder_registry
+            .get(self.compression_method, self.photometric_interpretation)
+            .ok_or(
+                TiffError::UnsupportedError(TiffUnsupportedError::UnsupportedCompression(
+                    self.compression_method,
+                ))
+                .into(),
+            )
+    }
+
+    fn check_limits(&self, decoder_registry: &DecoderRegistry) -> AsyncTiffResult<()> {
+        // Planar tiles hold every band, so their size is the same as for chunky tiles
+        let pixel_bits = match &self.mixed_bits_per_sample {
+            Some(sample_bits) => sample_bits.iter().map(|&b| b as u64).sum(),
+            None => self.samples_per_pixel as u64 * self.bits_per_sample as u64,
+        };
+        let decoded_bytes = (self.width as u64 * pixel_bits).div_ceil(8) * self.height as u64;
+        decoder_registry
+            .limits()
+            .check_decoded_chunk_bytes(decoded_bytes)
+    }
+
+    /// Decompress every band of this tile, appending them to `output`.
+    fn decompress_into(&self, decoder: &dyn Decoder, output: &mut Vec<u8>) -> AsyncTiffResult<()> {
+        let bits_per_sample = self.bits_per_sample;
+        let (chunks, samples) = match &self.compressed_bytes {
+            CompressedBytes::Chunky(bytes) => (std::slice::from_ref(bytes), self.samples_per_pixel),
+            CompressedBytes::Planar(band_bytes) => (band_bytes.as_slice(), 1),
+        };
+        for bytes in chunks {
+            let start = output.len();
+            decoder
+                .decode_into(bytes.clone(), &self.decode_context(samples), output)
+                .map_err(|err| self.decoding_error(err))?;
+            self.check_decoded_len(output, start, samples as usize * bits_per_sample as usize)?;
+        }
+        Ok(())
+    }
+
+    /// Decode only the rows in `rows` of this tile to an [`Array`] with a height of
+    /// `rows.len()`.
+    ///
+    /// Uncompressed, Deflate and LZW data is only decompressed up to the last requested row, so
+    /// reading a small window from a large strip is cheap. Other compressions decode the whole
+    /// tile and crop the result.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "decode_rows",
+            level = "debug",
+            skip_all,
+            fields(x = self.x, y = self.y, compression = ?self.compression_method)
+        )
+    )]
+    pub fn decode_rows(
+        self,
+        rows: Range<u32>,
+        decoder_registry: &DecoderRegistry,
+    ) -> AsyncTiffResult<Array> {
+        if rows.is_empty() || rows.end > self.height {
+            return Err(AsyncTiffError::General(format!(
+                "invalid row range {rows:?} for a tile with {} rows",
+                self.height
+            )));
+        }
+
+        self.check_limits(decoder_registry)?;
+
+        let streamable = matches!(
+            self.compression_method,
+            Compression::None | Compression::Deflate | Compression::LZW
+        );
+        if !streamable || self.mixed_bits_per_sample.is_some() || self.ycbcr.is_some() {
+            let planar = self.planar_configuration;
+            let array = self.decode(decoder_registry)?;
+            let [d0, d1, d2] = array.shape();
+            let rows = rows.start as usize..rows.end as usize;
+            let ranges = match planar {
+                PlanarConfiguration::Chunky => [rows, 0..d1, 0..d2],
+                PlanarConfiguration::Planar => [0..d0, rows, 0..d2],
+            };
+            return Ok(array.view().slice(ranges)?.to_array());
+        }
+
+        let pixel_bits = match self.planar_configuration {
+            PlanarConfiguration::Chunky => {
+                self.samples_per_pixel as usize * self.bits_per_sample as usize
             }
+            PlanarConfiguration::Planar => self.bits_per_sample as usize,
+        };
+        let row_bytes = (self.width as usize * pixel_bits).div_ceil(8);
+        let skip = rows.start as usize * row_bytes;
+        let len = rows.end as usize * row_bytes;
+        let decode_prefix = |bytes: &Bytes| -> AsyncTiffResult<Bytes> {
+            let mut decoded = decompress_prefix(self.compression_method, bytes, len)
+                .map_err(|err| self.decoding_error(err))?;
+            // Too short data is reported by `decode` below
+            let skip = skip.min(decoded.len());
+            Ok(Bytes::from(decoded.split_off(skip)))
+        };
+        let compressed_bytes = match &self.compressed_bytes {
+            CompressedBytes::Chunky(bytes) => CompressedBytes::Chunky(decode_prefix(bytes)?),
+            CompressedBytes::Planar(band_bytes) => CompressedBytes::Planar(
+                band_bytes
+                    .iter()
+                    .map(decode_prefix)
+                    .collect::<AsyncTiffResult<_>>()?,
+            ),
         };
 
-        // Apply predictor on the full encoded tile width, then crop afterward.
-        let decoded = match self.predictor {
-            Predictor::None => {
-                fix_endianness(&mut decoded_tile, self.endianness, bits_per_sample);
-                decoded_tile
-            }
-            Predictor::Horizontal => unpredict_hdiff(
-                decoded_tile,
-                self.endianness,
-                samples,
-                bits_per_sample,
-                tile_width,
+        // Both predictors only operate within a row, so the cropped rows can be decoded as an
+        // uncompressed tile of their own.
+        Tile {
+            height: rows.end - rows.start,
+            compressed_bytes,
+            compression_method: Compression::None,
+            ..self
+        }
+        .decode(decoder_registry)
+    }
+
+    /// Serialize this tile, including its compressed bytes and everything needed to decode it,
+    /// so that it can be sent to another process or machine and decoded there.
+    ///
+    /// Use [`from_serialized`][Self::from_serialized] to restore the tile. The format is only
+    /// meant to be read by the same version of this crate.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(SERIALIZED_TILE_MAGIC);
+        out.write_u64::<LittleEndian>(self.x as u64).unwrap();
+        out.write_u64::<LittleEndian>(self.y as u64).unwrap();
+        out.push(self.data_type.map_or(0, |data_type| {
+            DATA_TYPES.iter().position(|d| *d == data_type).unwrap() as u8 + 1
+        }));
+        out.write_u16::<LittleEndian>(self.samples_per_pixel)
+            .unwrap();
+        out.write_u16::<LittleEndian>(self.bits_per_sample).unwrap();
+        write_u16_list(&mut out, self.mixed_bits_per_sample.as_deref());
+        out.write_u16::<LittleEndian>(self.sample_format.to_u16())
+            .unwrap();
+        out.write_u16::<LittleEndian>(self.fill_order.to_u16())
+            .unwrap();
+        out.push(match self.endianness {
+            Endianness::LittleEndian => 0,
+            Endianness::BigEndian => 1,
+        });
+        out.write_u32::<LittleEndian>(self.width).unwrap();
+        out.write_u32::<LittleEndian>(self.height).unwrap();
+        out.write_u16::<LittleEndian>(self.planar_configuration.to_u16())
+            .unwrap();
+        out.write_u16::<LittleEndian>(self.predictor.to_u16())
+            .unwrap();
+        out.write_u16::<LittleEndian>(self.compression_method.to_u16())
+            .unwrap();
+        out.write_u16::<LittleEndian>(self.photometric_interpretation.to_u16())
+            .unwrap();
+        write_bytes(&mut out, self.jpeg_tables.as_deref());
+        match &self.lerc_parameters {
+            Some(params) => {
+                out.push(1);
+                out.write_u32::<LittleEndian>(params.len() as u32).unwrap();
+                params
+                    .iter()
+                    .for_each(|p| out.write_u32::<LittleEndian>(*p).unwrap());
+            }
+            None => out.push(0),
+        }
+        match &self.ycbcr {
+            Some(ycbcr) => {
+                out.push(1);
+                ycbcr
+                    .subsampling
+                    .iter()
+                    .for_each(|v| out.write_u16::<LittleEndian>(*v).unwrap());
+                ycbcr
+                    .coefficients
+                    .iter()
+                    .chain(&ycbcr.reference_black_white)
+                    .for_each(|v| out.write_f64::<LittleEndian>(*v).unwrap());
+            }
+            None => out.push(0),
+        }
+        match &self.colormap {
+            Some(colormap) => {
+                out.push(1);
+                out.write_u32::<LittleEndian>(colormap.len() as u32)
+                    .unwrap();
+                colormap
+                    .iter()
+                    .for_each(|v| out.write_u16::<LittleEndian>(*v).unwrap());
+            }
+            None => out.push(0),
+        }
+        match &self.compressed_bytes {
+            CompressedBytes::Chunky(bytes) => {
+                out.push(0);
+                write_bytes(&mut out, Some(bytes));
+            }
+            CompressedBytes::Planar(band_bytes) => {
+                out.push(1);
+                out.write_u32::<LittleEndian>(band_bytes.len() as u32)
+                    .unwrap();
+                band_bytes
+                    .iter()
+                    .for_each(|bytes| write_bytes(&mut out, Some(bytes)));
+            }
+        }
+        out
+    }
+
+    /// Restore a tile serialized with [`serialize`][Self::serialize].
+    pub fn from_serialized(data: &[u8]) -> AsyncTiffResult<Self> {
+        let invalid =
+            |what: &str| AsyncTiffError::General(format!("invalid serialized tile: {what}"));
+        let mut data = data
+            .strip_prefix(SERIALIZED_TILE_MAGIC)
+            .ok_or_else(|| invalid("missing header"))?;
+        let data = &mut data;
+
+        let x = data.read_u64::<LittleEndian>()? as usize;
+        let y = data.read_u64::<LittleEndian>()? as usize;
+        let data_type = match data.read_u8()? {
+            0 => None,
+            code => Some(
+                *DATA_TYPES
+                    .get(code as usize - 1)
+                    .ok_or_else(|| invalid("unknown data type"))?,
             ),
-            Predictor::FloatingPoint => {
-                unpredict_float(decoded_tile, samples, bits_per_sample, tile_width)?
+        };
+        let samples_per_pixel = data.read_u16::<LittleEndian>()?;
+        let bits_per_sample = data.read_u16::<LittleEndian>()?;
+        let mixed_bits_per_sample = read_u16_list(data)?;
+        let sample_format = SampleFormat::from_u16_exhaustive(data.read_u16::<LittleEndian>()?);
+        let fill_order = FillOrder::from_u16(data.read_u16::<LittleEndian>()?)
+            .ok_or_else(|| invalid("unknown fill order"))?;
+        let endianness = match data.read_u8()? {
+            0 => Endianness::LittleEndian,
+            1 => Endianness::BigEndian,
+            _ => return Err(invalid("unknown endianness")),
+        };
+        let width = data.read_u32::<LittleEndian>()?;
+        let height = data.read_u32::<LittleEndian>()?;
+        let planar_configuration = PlanarConfiguration::from_u16(data.read_u16::<LittleEndian>()?)
+            .ok_or_else(|| invalid("unknown planar configuration"))?;
+        let predictor = Predictor::from_u16(data.read_u16::<LittleEndian>()?)
+            .ok_or_else(|| invalid("unknown predictor"))?;
+        let compression_method = Compression::from_u16_exhaustive(data.read_u16::<LittleEndian>()?);
+        let photometric_interpretation =
+            PhotometricInterpretation::from_u16(data.read_u16::<LittleEndian>()?)
+                .ok_or_else(|| invalid("unknown photometric interpretation"))?;
+        let jpeg_tables = read_bytes(data)?;
+        let lerc_parameters = match data.read_u8()? {
+            0 => None,
+            _ => {
+                let len = data.read_u32::<LittleEndian>()?;
+                Some(
+                    (0..len)
+                        .map(|_| data.read_u32::<LittleEndian>())
+                        .collect::<std::io::Result<_>>()?,
+                )
+            }
+        };
+        let ycbcr = match data.read_u8()? {
+            0 => None,
+            _ => {
+                let mut ycbcr = YCbCr::default();
+                for v in &mut ycbcr.subsampling {
+                    *v = data.read_u16::<LittleEndian>()?;
+                }
+                for v in ycbcr
+                    .coefficients
+                    .iter_mut()
+                    .chain(&mut ycbcr.reference_black_white)
+                {
+                    *v = data.read_f64::<LittleEndian>()?;
+                }
+                if ycbcr.subsampling.contains(&0) {
+                    return Err(invalid("invalid YCbCr subsampling"));
+                }
+                Some(ycbcr)
+            }
+        };
+        let colormap = match data.read_u8()? {
+            0 => None,
+            _ => {
+                let len = data.read_u32::<LittleEndian>()?;
+                Some(
+                    (0..len)
+                        .map(|_| data.read_u16::<LittleEndian>())
+                        .collect::<std::io::Result<_>>()?,
+                )
             }
         };
+        let compressed_bytes = match data.read_u8()? {
+            0 => CompressedBytes::Chunky(read_bytes(data)?.ok_or_else(|| invalid("missing data"))?),
+            _ => {
+                let bands = data.read_u32::<LittleEndian>()?;
+                CompressedBytes::Planar(
+                    (0..bands)
+                        .map(|_| read_bytes(data)?.ok_or_else(|| invalid("missing data")))
+                        .collect::<AsyncTiffResult<_>>()?,
+                )
+            }
+        };
+        if !data.is_empty() {
+            return Err(invalid("trailing bytes"));
+        }
+
+        Ok(Self {
+            x,
+            y,
+            data_type,
+            samples_per_pixel,
+            bits_per_sample,
+            mixed_bits_per_sample,
+            sample_format,
+            fill_order,
+            endianness,
+            width,
+            height,
+            planar_configuration,
+            predictor,
+            compressed_bytes,
+            compression_method,
+            photometric_interpretation,
+            jpeg_tables,
+            lerc_parameters,
+            ycbcr,
+            colormap,
+        })
+    }
+}
+
+const SERIALIZED_TILE_MAGIC: &[u8] = b"ATIFTIL4";
+
+/// The data types in the order of their code in a serialized tile, offset by one to leave 0 for
+/// an unknown data type.
+const DATA_TYPES: [DataType; 11] = [
+    DataType::Bool,
+    DataType::UInt8,
+    DataType::UInt16,
+    DataType::UInt32,
+    DataType::UInt64,
+    DataType::Int8,
+    DataType::Int16,
+    DataType::Int32,
+    DataType::Int64,
+    DataType::Float32,
+    DataType::Float64,
+];
+
+/// Write an optional byte buffer as a presence flag, length and contents.
+fn write_bytes(out: &mut Vec<u8>, bytes: Option<&[u8]>) {
+    match bytes {
+        Some(bytes) => {
+            out.push(1);
+            out.write_u64::<LittleEndian>(bytes.len() as u64).unwrap();
+            out.extend_from_slice(bytes);
+        }
+        None => out.push(0),
+    }
+}
+
+fn read_bytes(data: &mut &[u8]) -> AsyncTiffResult<Option<Bytes>> {
+    if data.read_u8()? == 0 {
+        return Ok(None);
+    }
+    let len = data.read_u64::<LittleEndian>()? as usize;
+    if data.len() < len {
+        return Err(AsyncTiffError::General(
+            "invalid serialized tile: truncated data".to_string(),
+        ));
+    }
+    let (bytes, rest) = data.split_at(len);
+    *data = rest;
+    Ok(Some(Bytes::copy_from_slice(bytes)))
+}
+
+fn write_u16_list(out: &mut Vec<u8>, values: Option<&[u16]>) {
+    match values {
+        Some(values) => {
+            out.push(1);
+            out.write_u16::<LittleEndian>(values.len() as u16).unwrap();
+            values
+                .iter()
+                .for_each(|v| out.write_u16::<LittleEndian>(*v).unwrap());
+        }
+        None => out.push(0),
+    }
+}
+
+fn read_u16_list(data: &mut &[u8]) -> AsyncTiffResult<Option<Vec<u16>>> {
+    if data.read_u8()? == 0 {
+        return Ok(None);
+    }
+    let len = data.read_u16::<LittleEndian>()?;
+    Ok(Some(
+        (0..len)
+            .map(|_| data.read_u16::<LittleEndian>())
+            .collect::<std::io::Result<_>>()?,
+    ))
+}
+
+/// Decompress at most the first `len` bytes of a chunk.
+fn decompress_prefix(
+    compression: Compression,
+    bytes: &[u8],
+    len: usize,
+) -> AsyncTiffResult<Vec<u8>> {
+    match compression {
+        Compression::Deflate => {
+            let mut decoded = Vec::with_capacity(len);
+            ZlibDecoder::new(bytes)
+                .take(len as u64)
+                .read_to_end(&mut decoded)?;
+            Ok(decoded)
+        }
+        Compression::LZW => {
+            let mut decoder =
+                weezl::decode::Decoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8);
+            let mut decoded = vec![0; len];
+            let (mut consumed_in, mut consumed_out) = (0, 0);
+            while consumed_out < len {
+                let result =
+                    decoder.decode_bytes(&bytes[consumed_in..], &mut decoded[consumed_out..]);
+                consumed_in += result.consumed_in;
+                consumed_out += result.consumed_out;
+                match result.status? {
+                    weezl::LzwStatus::Ok => {}
+                    weezl::LzwStatus::Done | weezl::LzwStatus::NoProgress => break,
+                }
+            }
+            decoded.truncate(consumed_out);
+            Ok(decoded)
+        }
+        _ => Ok(bytes[..len.min(bytes.len())].to_vec()),
+    }
+}
+
+impl Tile {
+    /// Decode a chunky tile whose samples have different bit depths, widening every sample to
+    /// the tile's data type.
+    fn decode_mixed(&self, decoder: &dyn Decoder, sample_bits: &[u16]) -> AsyncTiffResult<Array> {
+        let unsupported = || {
+            TiffError::UnsupportedError(TiffUnsupportedError::InconsistentBitsPerSample(
+                sample_bits.iter().map(|&b| b as u8).collect(),
+            ))
+        };
+        let (CompressedBytes::Chunky(bytes), Some(data_type)) =
+            (&self.compressed_bytes, self.data_type)
+        else {
+            return Err(unsupported().into());
+        };
+        if self.predictor != Predictor::None {
+            return Err(unsupported().into());
+        }
+
+        let mut decoded = decoder
+            .decode(bytes.clone(), &self.decode_context(self.samples_per_pixel))
+            .map_err(|err| self.decoding_error(err))?;
+        let pixel_bits = sample_bits.iter().map(|&b| b as usize).sum();
+        self.check_decoded_len(&mut decoded, 0, pixel_bits)?;
 
+        let data = unpack_mixed_samples(
+            &decoded,
+            self.width as usize,
+            sample_bits,
+            self.endianness,
+            data_type.size(),
+        );
         let shape = infer_shape(
             self.planar_configuration,
             self.width as _,
             self.height as _,
-            samples,
+            sample_bits.len(),
+        );
+        Array::try_new(data, shape, Some(data_type))
+    }
+
+    /// The type that unsigned samples of this tile are unpacked to, if they aren't whole bytes.
+    fn unpacked_data_type(&self) -> Option<DataType> {
+        if self.mixed_bits_per_sample.is_some()
+            || self.ycbcr.is_some()
+            || self.sample_format != SampleFormat::Uint
+        {
+            return None;
+        }
+        match self.bits_per_sample {
+            1..=7 => Some(DataType::UInt8),
+            9..=15 => Some(DataType::UInt16),
+            _ => None,
+        }
+    }
+
+    /// Decode a tile whose samples aren't whole bytes, expanding every sample to `data_type`.
+    fn decode_unpacked(
+        &self,
+        decoder: &dyn Decoder,
+        data_type: DataType,
+    ) -> AsyncTiffResult<Array> {
+        if self.predictor != Predictor::None {
+            return Err(AsyncTiffError::General(format!(
+                "predictor {:?} is not supported for {}-bit samples",
+                self.predictor, self.bits_per_sample
+            )));
+        }
+        let mut decoded = Vec::new();
+        self.decompress_into(decoder, &mut decoded)?;
+        if self.fill_order == FillOrder::LsbToMsb {
+            decoded.iter_mut().for_each(|b| *b = b.reverse_bits());
+        }
+
+        // Rows of planar tiles hold one band, and each row is padded to whole bytes
+        let row_samples = match self.planar_configuration {
+            PlanarConfiguration::Chunky => self.samples_per_pixel as usize,
+            PlanarConfiguration::Planar => 1,
+        };
+        let data = unpack_mixed_samples(
+            &decoded,
+            self.width as usize,
+            &vec![self.bits_per_sample; row_samples],
+            self.endianness,
+            data_type.size(),
+        );
+        let shape = infer_shape(
+            self.planar_configuration,
+            self.width as _,
+            self.height as _,
+            self.samples_per_pixel as _,
+        );
+        Array::try_new(data, shape, Some(data_type))
+    }
+
+    /// The number of bytes a decoder must produce for one chunk of this tile with `pixel_bits`
+    /// bits per pixel. Rows are padded to whole bytes.
+    fn expected_decoded_len(&self, pixel_bits: usize) -> usize {
+        (self.width as usize * pixel_bits).div_ceil(8) * self.height as usize
+    }
+
+    /// Decode a tile of subsampled YCbCr data to chunky RGB.
+    fn decode_ycbcr(&self, decoder: &dyn Decoder, ycbcr: &YCbCr) -> AsyncTiffResult<Array> {
+        if self.predictor != Predictor::None {
+            return Err(AsyncTiffError::General(format!(
+                "predictor {:?} is not supported for subsampled YCbCr data",
+                self.predictor
+            )));
+        }
+        let CompressedBytes::Chunky(bytes) = &self.compressed_bytes else {
+            return Err(AsyncTiffError::General(
+                "planar YCbCr data is not supported".to_string(),
+            ));
+        };
+        let decoded = decoder
+            .decode(bytes.clone(), &self.decode_context(self.samples_per_pixel))
+            .map_err(|err| self.decoding_error(err))?;
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let expected_bytes = ycbcr.encoded_len(width, height);
+        if decoded.len() < expected_bytes {
+            return Err(AsyncTiffError::DecodedSizeMismatch {
+                x: self.x,
+                y: self.y,
+                actual_bytes: decoded.len(),
+                expected_bytes,
+            });
+        }
+        let rgb = ycbcr.upsample_to_rgb(&decoded, width, height);
+        Array::try_new(rgb, [height, width, 3], self.data_type)
+    }
+
+    /// Decode a tile of SGI LogL or LogLuv data compressed with SGILOG.
+    ///
+    /// The run-length encoding is restarted on every row, so it's decoded here rather than by a
+    /// [`Decoder`], which doesn't know the tile width.
+    #[cfg(feature = "sgilog")]
+    fn decode_sgilog(&self, decoder_registry: &DecoderRegistry) -> AsyncTiffResult<Array> {
+        self.check_limits(decoder_registry)?;
+        let CompressedBytes::Chunky(bytes) = &self.compressed_bytes else {
+            return Err(TiffError::UnsupportedError(
+                TiffUnsupportedError::UnsupportedPlanarConfig(Some(self.planar_configuration)),
+            )
+            .into());
+        };
+        let (width, height) = (self.width as usize, self.height as usize);
+        let decoded = crate::sgilog::decode(
+            bytes,
+            width,
+            height,
+            self.photometric_interpretation,
+            self.bits_per_sample,
+        )
+        .map_err(|err| self.decoding_error(err))?;
+        let shape = infer_shape(
+            self.planar_configuration,
+            width,
+            height,
+            self.samples_per_pixel as usize,
         );
         Array::try_new(decoded, shape, self.data_type)
     }
+
+    /// Attach the indices of this tile to an error returned by its decoder.
+    ///
+    /// Unsupported features are passed through unchanged, since they aren't caused by the data of
+    /// this particular tile.
+    fn decoding_error(&self, err: AsyncTiffError) -> AsyncTiffError {
+        match err {
+            AsyncTiffError::InternalTIFFError(TiffError::UnsupportedError(_))
+            | AsyncTiffError::TileDecodingError { .. } => err,
+            err => AsyncTiffError::TileDecodingError {
+                x: self.x,
+                y: self.y,
+                source: Box::new(err),
+            },
+        }
+    }
+
+    /// Check that a decoder produced enough bytes for this tile.
+    ///
+    /// Trailing bytes beyond the expected size are dropped: some writers store the last strip of
+    /// an image at its full height, or pad chunks. Only the bytes from `start` are checked.
+    fn check_decoded_len(
+        &self,
+        decoded: &mut Vec<u8>,
+        start: usize,
+        pixel_bits: usize,
+    ) -> AsyncTiffResult<()> {
+        let expected_bytes = self.expected_decoded_len(pixel_bits);
+        let actual_bytes = decoded.len() - start;
+        if actual_bytes < expected_bytes {
+            return Err(AsyncTiffError::DecodedSizeMismatch {
+                x: self.x,
+                y: self.y,
+                actual_bytes,
+                expected_bytes,
+            });
+        }
+        decoded.truncate(start + expected_bytes);
+        Ok(())
+    }
+}
+
+/// Unpack rows of interleaved samples with differing bit widths into samples of `out_size` bytes
+/// each, in native endianness.
+///
+/// Byte-aligned samples whose width is a multiple of 8 are read in the file's byte order; all
+/// other samples are read most significant bit first, as for sub-byte images.
+fn unpack_mixed_samples(
+    data: &[u8],
+    width: usize,
+    sample_bits: &[u16],
+    endianness: Endianness,
+    out_size: usize,
+) -> Vec<u8> {
+    let pixel_bits = sample_bits.iter().map(|&b| b as usize).sum::<usize>();
+    let row_bytes = (width * pixel_bits).div_ceil(8);
+    let mut out = Vec::with_capacity(data.len() / row_bytes * width * sample_bits.len() * out_size);
+    for row in data.chunks_exact(row_bytes) {
+        let mut bit = 0usize;
+        for _ in 0..width {
+            for &bits in sample_bits {
+                let bits = bits as usize;
+                let value = if bit.is_multiple_of(8) && bits.is_multiple_of(8) {
+                    let bytes = &row[bit / 8..(bit + bits) / 8];
+                    let fold = |acc: u64, b: &u8| (acc << 8) | *b as u64;
+                    match endianness {
+                        Endianness::BigEndian => bytes.iter().fold(0, fold),
+                        Endianness::LittleEndian => bytes.iter().rev().fold(0, fold),
+                    }
+                } else {
+                    (bit..bit + bits).fold(0, |acc, b| {
+                        (acc << 1) | ((row[b / 8] >> (7 - b % 8)) & 1) as u64
+                    })
+                };
+                bit += bits;
+                match out_size {
+                    1 => out.push(value as u8),
+                    2 => out.extend_from_slice(&(value as u16).to_ne_bytes()),
+                    4 => out.extend_from_slice(&(value as u32).to_ne_bytes()),
+                    _ => out.extend_from_slice(&value.to_ne_bytes()),
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Map the palette indices of the single band of `array` to 16-bit RGB through a TIFF
+/// `colormap`, which holds all red values followed by all green and all blue values.
+fn expand_colormap(
+    array: &Array,
+    colormap: &[u16],
+    planar_configuration: PlanarConfiguration,
+) -> AsyncTiffResult<Array> {
+    let indices: Vec<usize> = match array.data() {
+        TypedArray::Bool(data) => data.iter().map(|&i| i as usize).collect(),
+        TypedArray::UInt8(data) => data.iter().map(|&i| i as usize).collect(),
+        TypedArray::UInt16(data) => data.iter().map(|&i| i as usize).collect(),
+        _ => {
+            return Err(AsyncTiffError::General(
+                "colormap expansion requires unsigned 8 or 16-bit indices".to_string(),
+            ))
+        }
+    };
+    let entries = colormap.len() / 3;
+    if let Some(index) = indices.iter().find(|&&i| i >= entries) {
+        return Err(AsyncTiffError::General(format!(
+            "palette index {index} out of range for a colormap with {entries} entries"
+        )));
+    }
+    let [d0, d1, d2] = array.shape();
+    let (shape, data) = match planar_configuration {
+        PlanarConfiguration::Chunky if d2 == 1 => (
+            [d0, d1, 3],
+            indices
+                .iter()
+                .flat_map(|&i| {
+                    [
+                        colormap[i],
+                        colormap[entries + i],
+                        colormap[2 * entries + i],
+                    ]
+                })
+                .collect(),
+        ),
+        PlanarConfiguration::Planar if d0 == 1 => (
+            [3, d1, d2],
+            (0..3)
+                .flat_map(|c| indices.iter().map(move |&i| colormap[c * entries + i]))
+                .collect(),
+        ),
+        _ => {
+            return Err(AsyncTiffError::General(format!(
+                "colormap expansion requires a single band, got an array of shape {:?}",
+                array.shape()
+            )))
+        }
+    };
+    Ok(Array {
+        data: TypedArray::UInt16(data),
+        shape,
+        data_type: Some(DataType::UInt16),
+    })
 }
 
 fn infer_shape(
@@ -161,3 +1241,588 @@ fn infer_shape(
         PlanarConfiguration::Planar => [samples_per_pixel, height, width],
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn uncompressed_tile(data: &'static [u8]) -> Tile {
+        Tile {
+            x: 1,
+            y: 2,
+            data_type: Some(DataType::UInt8),
+            samples_per_pixel: 1,
+            bits_per_sample: 8,
+            mixed_bits_per_sample: None,
+            sample_format: SampleFormat::Uint,
+            fill_order: FillOrder::MsbToLsb,
+            endianness: Endianness::LittleEndian,
+            width: 4,
+            height: 2,
+            planar_configuration: PlanarConfiguration::Chunky,
+            predictor: Predictor::None,
+            compressed_bytes: CompressedBytes::Chunky(Bytes::from_static(data)),
+            compression_method: Compression::None,
+            photometric_interpretation: PhotometricInterpretation::BlackIsZero,
+            jpeg_tables: None,
+            lerc_parameters: None,
+            ycbcr: None,
+            colormap: None,
+        }
+    }
+
+    #[test]
+    fn test_decoded_size_mismatch() {
+        let registry = DecoderRegistry::default();
+        let err = uncompressed_tile(&[0; 7]).decode(&registry).unwrap_err();
+        assert!(matches!(
+            err,
+            AsyncTiffError::DecodedSizeMismatch {
+                x: 1,
+                y: 2,
+                actual_bytes: 7,
+                expected_bytes: 8
+            }
+        ));
+
+        // Trailing padding is dropped
+        let array = uncompressed_tile(&[1; 10]).decode(&registry).unwrap();
+        assert_eq!(array.shape(), [2, 4, 1]);
+    }
+
+    #[test]
+    fn test_decoded_chunk_limit() {
+        use crate::metadata::Limits;
+
+        let registry =
+            DecoderRegistry::default().with_limits(Limits::new().with_max_decoded_chunk_bytes(7));
+        let err = uncompressed_tile(&[0; 8]).decode(&registry).unwrap_err();
+        assert!(matches!(
+            err,
+            AsyncTiffError::LimitExceeded {
+                actual: 8,
+                max: 7,
+                ..
+            }
+        ));
+        let err = uncompressed_tile(&[0; 8])
+            .decode_rows(0..1, &registry)
+            .unwrap_err();
+        assert!(matches!(err, AsyncTiffError::LimitExceeded { .. }));
+
+        let registry = registry.with_limits(Limits::new().with_max_decoded_chunk_bytes(8));
+        assert!(uncompressed_tile(&[0; 8]).decode(&registry).is_ok());
+    }
+
+    #[test]
+    fn test_corrupt_lzw() {
+        let registry = DecoderRegistry::default();
+        let lzw_tile = |data: Vec<u8>| Tile {
+            compressed_bytes: CompressedBytes::Chunky(data.into()),
+            compression_method: Compression::LZW,
+            ..uncompressed_tile(&[])
+        };
+        let encoded = weezl::encode::Encoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+            .encode(&[1, 2, 3, 4, 5, 6, 7, 8])
+            .unwrap();
+        let array = lzw_tile(encoded.clone()).decode(&registry).unwrap();
+        assert_eq!(array.data().as_ref(), [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        // Truncated data is an error rather than a panic, whether it's cut in the middle of a
+        // code or not
+        for len in 0..encoded.len() {
+            let err = lzw_tile(encoded[..len].to_vec())
+                .decode(&registry)
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                AsyncTiffError::TileDecodingError { x: 1, y: 2, .. }
+                    | AsyncTiffError::DecodedSizeMismatch { x: 1, y: 2, .. }
+            ));
+        }
+        let truncated = encoded[..encoded.len() / 2].to_vec();
+        assert!(lzw_tile(truncated).decode_rows(1..2, &registry).is_err());
+
+        // Invalid codes are reported with the indices of the tile
+        for err in [
+            lzw_tile(vec![0xFF; 4]).decode(&registry).unwrap_err(),
+            lzw_tile(vec![0xFF; 4])
+                .decode_rows(0..1, &registry)
+                .unwrap_err(),
+            lzw_tile(vec![0xFF; 4])
+                .decode_into(&registry, &mut Vec::new())
+                .unwrap_err(),
+        ] {
+            let AsyncTiffError::TileDecodingError { x: 1, y: 2, source } = err else {
+                panic!("unexpected error {err:?}");
+            };
+            assert!(matches!(*source, AsyncTiffError::LZWDecodingError(_)));
+        }
+    }
+
+    #[test]
+    fn test_unpack_mixed_samples() {
+        // Two RGB pixels with a 1-bit mask each: 25 bits per pixel, 50 bits per row
+        let mut bits = String::new();
+        for (r, g, b, mask) in [(10u8, 20u8, 30u8, 1), (40, 50, 60, 0)] {
+            bits += &format!("{r:08b}{g:08b}{b:08b}{mask:b}");
+        }
+        let data = format!("{bits:0<56}")
+            .as_bytes()
+            .chunks(8)
+            .map(|byte| u8::from_str_radix(std::str::from_utf8(byte).unwrap(), 2).unwrap())
+            .collect::<Vec<_>>();
+        let unpacked = unpack_mixed_samples(&data, 2, &[8, 8, 8, 1], Endianness::LittleEndian, 1);
+        assert_eq!(unpacked, [10, 20, 30, 1, 40, 50, 60, 0]);
+
+        let unpacked = unpack_mixed_samples(
+            &[0x34, 0x12, 0x56],
+            1,
+            &[16, 8],
+            Endianness::LittleEndian,
+            2,
+        );
+        assert_eq!(
+            unpacked,
+            [0x1234u16.to_ne_bytes(), 0x56u16.to_ne_bytes()].concat()
+        );
+    }
+
+    #[test]
+    fn test_decode_mixed_bits_per_sample() {
+        let mut tile = uncompressed_tile(&[0x12, 0x80, 0x34, 0x00]);
+        tile.width = 1;
+        tile.samples_per_pixel = 2;
+        tile.mixed_bits_per_sample = Some(vec![8, 1]);
+        let array = tile.decode(&DecoderRegistry::default()).unwrap();
+        assert_eq!(array.shape(), [2, 1, 2]);
+        assert_eq!(array.data().as_ref(), [0x12, 1, 0x34, 0]);
+    }
+
+    #[test]
+    fn test_decode_unpack_bits() {
+        use crate::TypedArray;
+
+        let registry = DecoderRegistry::default();
+        let options = DecodeOptions::new().with_unpack_bits(true);
+        let packed_tile = |data: &'static [u8], width, bits_per_sample| Tile {
+            data_type: None,
+            width,
+            bits_per_sample,
+            ..uncompressed_tile(data)
+        };
+
+        // Rows of three 4-bit samples are padded to two bytes
+        let tile = packed_tile(&[0x12, 0x30, 0x45, 0x60], 3, 4);
+        assert!(tile.clone().decode(&registry).is_err());
+        let array = tile.decode_with_options(&registry, &options).unwrap();
+        assert_eq!(array.shape(), [2, 3, 1]);
+        assert_eq!(array.data_type(), Some(DataType::UInt8));
+        assert_eq!(array.data().as_ref(), [1, 2, 3, 4, 5, 6]);
+
+        let tile = packed_tile(&[0xAB, 0xC1, 0x23, 0x00, 0x0F, 0xFF], 2, 12);
+        let array = tile.decode_with_options(&registry, &options).unwrap();
+        assert_eq!(array.data_type(), Some(DataType::UInt16));
+        let TypedArray::UInt16(data) = array.data() else {
+            panic!("expected UInt16");
+        };
+        assert_eq!(data, &[0xABC, 0x123, 0x000, 0xFFF]);
+
+        // Bilevel data with the least significant bit first
+        let mut tile = packed_tile(&[0b0000_0101, 0b0000_0010], 3, 1);
+        tile.data_type = Some(DataType::Bool);
+        tile.fill_order = FillOrder::LsbToMsb;
+        let array = tile.decode_with_options(&registry, &options).unwrap();
+        assert_eq!(array.data().as_ref(), [1, 0, 1, 0, 1, 0]);
+
+        let mut tile = packed_tile(&[0x12, 0x30, 0x45, 0x60], 3, 4);
+        tile.predictor = Predictor::Horizontal;
+        assert!(tile.decode_with_options(&registry, &options).is_err());
+    }
+
+    #[test]
+    fn test_decode_options() {
+        let registry = DecoderRegistry::default();
+
+        // Two chunky bands, selected in reverse order
+        let mut tile = uncompressed_tile(&[1, 10, 2, 20, 3, 30, 4, 40]);
+        tile.samples_per_pixel = 2;
+        tile.width = 2;
+        let options = DecodeOptions::new().with_bands([1, 0, 1]);
+        let array = tile
+            .clone()
+            .decode_with_options(&registry, &options)
+            .unwrap();
+        assert_eq!(array.shape(), [2, 2, 3]);
+        assert_eq!(
+            array.data().as_ref(),
+            [10, 1, 10, 20, 2, 20, 30, 3, 30, 40, 4, 40]
+        );
+        let options = DecodeOptions::new().with_bands([2]);
+        assert!(tile.decode_with_options(&registry, &options).is_err());
+
+        // The predictor is kept
+        let mut tile = uncompressed_tile(&[1, 1, 1, 1, 5, 0, 0, 255]);
+        tile.predictor = Predictor::Horizontal;
+        let options = DecodeOptions::new().with_reverse_predictor(false);
+        let array = tile.decode_with_options(&registry, &options).unwrap();
+        assert_eq!(array.data().as_ref(), [1, 1, 1, 1, 5, 0, 0, 255]);
+
+        // Big-endian 16-bit samples, with and without the predictor reversed
+        let mut tile = uncompressed_tile(&[0, 1, 0, 1, 0, 1, 0, 1, 0, 2, 0, 2, 0, 2, 0, 2]);
+        tile.data_type = Some(DataType::UInt16);
+        tile.bits_per_sample = 16;
+        tile.endianness = Endianness::BigEndian;
+        let options = DecodeOptions::new().with_native_endianness(false);
+        let array = tile
+            .clone()
+            .decode_with_options(&registry, &options)
+            .unwrap();
+        assert_eq!(array.data().as_ref()[..4], [0, 1, 0, 1]);
+        tile.predictor = Predictor::Horizontal;
+        let array = tile.decode_with_options(&registry, &options).unwrap();
+        assert_eq!(array.data().as_ref()[..8], [0, 1, 0, 2, 0, 3, 0, 4]);
+    }
+
+    #[test]
+    fn test_decode_expand_colormap() {
+        let registry = DecoderRegistry::default();
+        let mut tile = uncompressed_tile(&[0, 1, 1, 0, 1, 1, 0, 0]);
+        tile.photometric_interpretation = PhotometricInterpretation::RGBPalette;
+        tile.colormap = Some(Arc::from([100, 200, 300, 400, 500, 600].as_slice()));
+
+        let options = DecodeOptions::new().with_expand_colormap(true);
+        let array = tile
+            .clone()
+            .decode_with_options(&registry, &options)
+            .unwrap();
+        assert_eq!(array.shape(), [2, 4, 3]);
+        let TypedArray::UInt16(data) = array.data() else {
+            panic!("expected UInt16");
+        };
+        assert_eq!(data[..6], [100, 300, 500, 200, 400, 600]);
+
+        // Bands are selected from the expanded colors
+        let options = options.with_bands([2]);
+        let array = tile
+            .clone()
+            .decode_with_options(&registry, &options)
+            .unwrap();
+        let TypedArray::UInt16(data) = array.data() else {
+            panic!("expected UInt16");
+        };
+        assert_eq!(data, &[500, 600, 600, 500, 600, 600, 500, 500]);
+
+        // The colormap survives serialization, and indices beyond it are an error
+        let tile = Tile::from_serialized(&tile.serialize()).unwrap();
+        assert_eq!(
+            tile.colormap.as_deref(),
+            Some([100, 200, 300, 400, 500, 600].as_slice())
+        );
+        let mut tile = uncompressed_tile(&[0, 1, 2, 0, 0, 0, 0, 0]);
+        tile.photometric_interpretation = PhotometricInterpretation::RGBPalette;
+        tile.colormap = Some(Arc::from([0u16; 6].as_slice()));
+        let options = DecodeOptions::new().with_expand_colormap(true);
+        assert!(tile.decode_with_options(&registry, &options).is_err());
+    }
+
+    #[test]
+    fn test_decompress_keeps_predictor() {
+        let registry = DecoderRegistry::default();
+        let mut tile = uncompressed_tile(&[1, 1, 1, 1, 5, 0, 0, 255]);
+        tile.predictor = Predictor::Horizontal;
+        assert_eq!(tile.predictor(), Predictor::Horizontal);
+
+        let raw = tile.clone().decompress(&registry).unwrap();
+        assert_eq!(raw, [1, 1, 1, 1, 5, 0, 0, 255]);
+        let options = DecodeOptions::new()
+            .with_reverse_predictor(false)
+            .with_native_endianness(false);
+        let array = tile
+            .clone()
+            .decode_with_options(&registry, &options)
+            .unwrap();
+        assert_eq!(array.data().as_ref(), raw);
+        let array = tile.decode(&registry).unwrap();
+        assert_eq!(array.data().as_ref(), [1, 2, 3, 4, 5, 5, 5, 4]);
+    }
+
+    #[test]
+    fn test_decode_into() {
+        use std::io::Write;
+
+        use crate::decoder::BufferPool;
+
+        let registry = DecoderRegistry::default();
+        let pool = BufferPool::new(1);
+
+        let mut tile = uncompressed_tile(&[1, 1, 1, 1, 5, 0, 0, 255]);
+        tile.predictor = Predictor::Horizontal;
+        let mut output = pool.take();
+        let shape = tile.decode_into(&registry, &mut output).unwrap();
+        assert_eq!(shape, [2, 4, 1]);
+        assert_eq!(output, [1, 2, 3, 4, 5, 5, 5, 4]);
+        pool.recycle(output);
+
+        // A reused buffer is replaced, keeping its allocation
+        let mut deflate =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        deflate.write_all(&[9; 8]).unwrap();
+        let mut tile = uncompressed_tile(&[]);
+        tile.compressed_bytes = CompressedBytes::Chunky(deflate.finish().unwrap().into());
+        tile.compression_method = Compression::Deflate;
+        let mut output = pool.take();
+        let capacity = output.capacity();
+        tile.decode_into(&registry, &mut output).unwrap();
+        assert_eq!(output, [9; 8]);
+        assert_eq!(output.capacity(), capacity);
+        assert_eq!(output, tile.decode(&registry).unwrap().data().as_ref());
+        pool.recycle(output);
+        pool.recycle(Vec::new());
+        assert_eq!((pool.hits(), pool.misses(), pool.discarded()), (1, 1, 1));
+        assert_eq!(pool.len(), 1);
+        assert!(pool.pooled_bytes() >= 8);
+
+        // Each band of a planar tile is checked separately
+        let mut tile = uncompressed_tile(&[]);
+        tile.planar_configuration = PlanarConfiguration::Planar;
+        tile.samples_per_pixel = 2;
+        tile.compressed_bytes = CompressedBytes::Planar(vec![
+            Bytes::from_static(&[1; 9]),
+            Bytes::from_static(&[2; 8]),
+        ]);
+        let mut output = vec![7; 3];
+        let shape = tile.decode_into(&registry, &mut output).unwrap();
+        assert_eq!(shape, [2, 2, 4]);
+        assert_eq!(output, [[1; 8], [2; 8]].concat());
+
+        // Decoders without an override of decode_into fall back to decode
+        let mut registry = DecoderRegistry::empty();
+        registry
+            .as_mut()
+            .insert(Compression::None, Arc::new(InvertDecoder));
+        uncompressed_tile(&[0; 8])
+            .decode_into(&registry, &mut output)
+            .unwrap();
+        assert_eq!(output, [255; 8]);
+
+        // and still reuse pooled buffers
+        let pool = BufferPool::new(1);
+        pool.recycle(Vec::with_capacity(8));
+        let mut output = pool.take();
+        let allocation = output.as_ptr();
+        uncompressed_tile(&[0; 8])
+            .decode_into(&registry, &mut output)
+            .unwrap();
+        assert_eq!(output, [255; 8]);
+        assert_eq!(output.as_ptr(), allocation);
+        assert_eq!((pool.hits(), pool.misses()), (1, 0));
+    }
+
+    #[derive(Debug)]
+    struct InvertDecoder;
+
+    impl Decoder for InvertDecoder {
+        fn decode(&self, buffer: Bytes, _context: &DecodeContext<'_>) -> AsyncTiffResult<Vec<u8>> {
+            Ok(buffer.iter().map(|b| !b).collect())
+        }
+    }
+
+    /// Runs tasks on new threads, counting them, or drops them.
+    #[derive(Debug, Default)]
+    struct ThreadExecutor {
+        tasks: std::sync::atomic::AtomicUsize,
+        drop_tasks: bool,
+    }
+
+    impl crate::decoder::DecodeExecutor for ThreadExecutor {
+        fn execute(&self, task: Box<dyn FnOnce() + Send>) {
+            self.tasks
+                .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            if !self.drop_tasks {
+                std::thread::spawn(task);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_decode_async() {
+        let registry = DecoderRegistry::default();
+        let array = uncompressed_tile(&[3; 8])
+            .decode_async(&registry)
+            .await
+            .unwrap();
+        assert_eq!(array.data().as_ref(), [3; 8]);
+
+        let executor = Arc::new(ThreadExecutor::default());
+        let registry = DecoderRegistry::default().with_executor(executor.clone());
+        let array = uncompressed_tile(&[3; 8])
+            .decode_async(&registry)
+            .await
+            .unwrap();
+        assert_eq!(array.data().as_ref(), [3; 8]);
+        assert!(uncompressed_tile(&[0; 7])
+            .decode_async(&registry)
+            .await
+            .is_err());
+        assert_eq!(executor.tasks.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+        let registry = DecoderRegistry::default().with_executor(Arc::new(ThreadExecutor {
+            drop_tasks: true,
+            ..Default::default()
+        }));
+        assert!(uncompressed_tile(&[3; 8])
+            .decode_async(&registry)
+            .await
+            .is_err());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_decode_spawn_blocking() {
+        let registry =
+            DecoderRegistry::default().with_executor(Arc::new(crate::decoder::SpawnBlocking));
+        let options = DecodeOptions::default().with_bands(vec![0]);
+        let array = uncompressed_tile(&[5; 8])
+            .decode_async_with_options(&registry, &options)
+            .await
+            .unwrap();
+        assert_eq!(array.data().as_ref(), [5; 8]);
+    }
+
+    #[test]
+    fn test_decoder_for_photometric() {
+        let mut registry = DecoderRegistry::default();
+        registry.insert_for_photometric(
+            Compression::None,
+            PhotometricInterpretation::WhiteIsZero,
+            Arc::new(InvertDecoder),
+        );
+
+        // Falls back to the decoder registered for the compression method
+        let array = uncompressed_tile(&[0; 8]).decode(&registry).unwrap();
+        assert_eq!(array.data().as_ref(), [0; 8]);
+
+        let mut tile = uncompressed_tile(&[0; 8]);
+        tile.photometric_interpretation = PhotometricInterpretation::WhiteIsZero;
+        let array = tile.decode(&registry).unwrap();
+        assert_eq!(array.data().as_ref(), [255; 8]);
+    }
+
+    #[test]
+    fn test_decode_rows() {
+        use std::io::Write;
+
+        // 4x6 image with 2 samples per pixel, horizontal predictor applied per row
+        let pixels = (0..48).map(|i| (i * 7 % 256) as u8).collect::<Vec<_>>();
+        let mut predicted = pixels.clone();
+        for row in predicted.chunks_exact_mut(8) {
+            for i in (2..8).rev() {
+                row[i] = row[i].wrapping_sub(row[i - 2]);
+            }
+        }
+
+        let mut deflate =
+            flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        deflate.write_all(&predicted).unwrap();
+        let mut lzw = Vec::new();
+        weezl::encode::Encoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8)
+            .into_vec(&mut lzw)
+            .encode_all(&predicted)
+            .status
+            .unwrap();
+
+        let registry = DecoderRegistry::default();
+        for (compression, bytes) in [
+            (Compression::None, predicted.clone()),
+            (Compression::Deflate, deflate.finish().unwrap()),
+            (Compression::LZW, lzw),
+        ] {
+            let mut tile = uncompressed_tile(&[]);
+            tile.samples_per_pixel = 2;
+            tile.height = 6;
+            tile.predictor = Predictor::Horizontal;
+            tile.compression_method = compression;
+            tile.compressed_bytes = CompressedBytes::Chunky(bytes.into());
+
+            let array = tile.clone().decode_rows(2..4, &registry).unwrap();
+            assert_eq!(array.shape(), [2, 4, 2], "{compression:?}");
+            assert_eq!(array.data().as_ref(), &pixels[16..32], "{compression:?}");
+
+            assert!(tile.clone().decode_rows(5..7, &registry).is_err());
+            assert!(tile.decode_rows(3..3, &registry).is_err());
+        }
+    }
+
+    #[test]
+    fn test_decode_ycbcr() {
+        let mut tile = uncompressed_tile(&[
+            10, 20, 30, 40, 128, 128, //
+            50, 60, 70, 80, 128, 128,
+        ]);
+        tile.samples_per_pixel = 3;
+        tile.photometric_interpretation = PhotometricInterpretation::YCbCr;
+        tile.ycbcr = Some(YCbCr::default());
+
+        let registry = DecoderRegistry::default();
+        let array = tile.clone().decode(&registry).unwrap();
+        assert_eq!(array.shape(), [2, 4, 3]);
+        assert_eq!(
+            array.data().as_ref(),
+            [
+                10, 10, 10, 20, 20, 20, 50, 50, 50, 60, 60, 60, //
+                30, 30, 30, 40, 40, 40, 70, 70, 70, 80, 80, 80,
+            ]
+        );
+
+        let array = tile.clone().decode_rows(1..2, &registry).unwrap();
+        assert_eq!(array.shape(), [1, 4, 3]);
+        assert_eq!(array.data().as_ref()[..6], [30, 30, 30, 40, 40, 40]);
+
+        assert_eq!(tile.decompress(&registry).unwrap().len(), 12);
+
+        let mut tile = uncompressed_tile(&[0; 11]);
+        tile.samples_per_pixel = 3;
+        tile.ycbcr = Some(YCbCr::default());
+        let restored = Tile::from_serialized(&tile.serialize()).unwrap();
+        assert_eq!(restored.ycbcr, tile.ycbcr);
+        assert!(matches!(
+            tile.decode(&registry).unwrap_err(),
+            AsyncTiffError::DecodedSizeMismatch {
+                actual_bytes: 11,
+                expected_bytes: 12,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let mut tile = uncompressed_tile(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        tile.jpeg_tables = Some(Bytes::from_static(&[0xff, 0xd8]));
+        tile.lerc_parameters = Some(vec![4, 1]);
+        let serialized = tile.serialize();
+        let restored = Tile::from_serialized(&serialized).unwrap();
+        assert_eq!(restored.serialize(), serialized);
+        assert_eq!((restored.x(), restored.y()), (1, 2));
+        assert_eq!(restored.jpeg_tables(), tile.jpeg_tables());
+        let array = restored.decode(&DecoderRegistry::default()).unwrap();
+        assert_eq!(array.data().as_ref(), [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let mut tile = uncompressed_tile(&[]);
+        tile.planar_configuration = PlanarConfiguration::Planar;
+        tile.mixed_bits_per_sample = Some(vec![8, 16]);
+        tile.compressed_bytes =
+            CompressedBytes::Planar(vec![Bytes::from_static(&[1]), Bytes::from_static(&[2, 3])]);
+        let serialized = tile.serialize();
+        assert_eq!(
+            Tile::from_serialized(&serialized).unwrap().serialize(),
+            serialized
+        );
+
+        assert!(Tile::from_serialized(&serialized[..serialized.len() - 1]).is_err());
+        assert!(Tile::from_serialized(b"not a tile").is_err());
+    }
+}
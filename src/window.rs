@@ -0,0 +1,189 @@
+use std::ops::Range;
+
+use futures::future::try_join_all;
+
+use crate::decoder::DecoderRegistry;
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::reader::AsyncFileReader;
+use crate::tags::PlanarConfiguration;
+use crate::{Array, DataType, FetchOptions, ImageFileDirectory, Tile, TypedArray};
+
+/// A rectangular window of an image, in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    /// The column of the left edge of the window.
+    pub col_off: u32,
+    /// The row of the top edge of the window.
+    pub row_off: u32,
+    /// The width of the window.
+    pub width: u32,
+    /// The height of the window.
+    pub height: u32,
+}
+
+impl Window {
+    /// Create a new window from its offset and size.
+    pub fn new(col_off: u32, row_off: u32, width: u32, height: u32) -> Self {
+        Self {
+            col_off,
+            row_off,
+            width,
+            height,
+        }
+    }
+
+    /// The columns covered by this window.
+    ///
+    /// Returns an error if the window extends past the largest possible image width.
+    pub fn cols(&self) -> AsyncTiffResult<Range<u32>> {
+        self.range(self.col_off, self.width)
+    }
+
+    /// The rows covered by this window.
+    ///
+    /// Returns an error if the window extends past the largest possible image height.
+    pub fn rows(&self) -> AsyncTiffResult<Range<u32>> {
+        self.range(self.row_off, self.height)
+    }
+
+    fn range(&self, offset: u32, size: u32) -> AsyncTiffResult<Range<u32>> {
+        let end = offset
+            .checked_add(size)
+            .ok_or_else(|| AsyncTiffError::General(format!("Window {self:?} out of bounds")))?;
+        Ok(offset..end)
+    }
+}
+
+/// The data type of `ifd`, if `window` lies within the image.
+fn check_window(ifd: &ImageFileDirectory, window: Window) -> AsyncTiffResult<DataType> {
+    let (image_width, image_height) = (ifd.image_width(), ifd.image_height());
+    let (cols, rows) = (window.cols()?, window.rows()?);
+    if cols.is_empty() || rows.is_empty() || cols.end > image_width || rows.end > image_height {
+        return Err(AsyncTiffError::General(format!(
+            "Window {window:?} out of bounds for a {image_width}x{image_height} image"
+        )));
+    }
+    ifd.data_type().ok_or(AsyncTiffError::General(
+        "read_window requires a known data type".to_string(),
+    ))
+}
+
+/// The size of the tiles, or of the strips, of `ifd`.
+fn chunk_size(ifd: &ImageFileDirectory) -> (u32, u32) {
+    match (ifd.tile_width(), ifd.tile_height()) {
+        (Some(tile_width), Some(tile_height)) => (tile_width, tile_height),
+        _ => (ifd.image_width(), ifd.strip_height()),
+    }
+}
+
+/// Fetch and decode the tiles or strips of `ifd` intersecting `window`, and mosaic them into a
+/// single array.
+pub(crate) async fn read_window(
+    ifd: &ImageFileDirectory,
+    window: Window,
+    reader: &dyn AsyncFileReader,
+    decoder_registry: &DecoderRegistry,
+    options: &FetchOptions,
+) -> AsyncTiffResult<Array> {
+    let chunks = fetch_window(ifd, window, reader, options).await?;
+    let decoded = try_join_all(chunks.into_iter().map(|tile| async move {
+        let (x, y) = (tile.x(), tile.y());
+        let array = tile.decode_async(decoder_registry).await?;
+        AsyncTiffResult::Ok((x, y, array))
+    }))
+    .await?;
+    mosaic_window(ifd, window, decoded)
+}
+
+/// Fetch the tiles or strips of `ifd` intersecting `window`, without decoding them.
+pub(crate) async fn fetch_window(
+    ifd: &ImageFileDirectory,
+    window: Window,
+    reader: &dyn AsyncFileReader,
+    options: &FetchOptions,
+) -> AsyncTiffResult<Vec<Tile>> {
+    check_window(ifd, window)?;
+    let (cols, rows) = (window.cols()?, window.rows()?);
+    let (chunk_width, chunk_height) = chunk_size(ifd);
+    if ifd.tile_width().is_some() && ifd.tile_height().is_some() {
+        let xy = (rows.start / chunk_height..=(rows.end - 1) / chunk_height)
+            .flat_map(|y| {
+                (cols.start / chunk_width..=(cols.end - 1) / chunk_width)
+                    .map(move |x| (x as usize, y as usize))
+            })
+            .collect::<Vec<_>>();
+        ifd.fetch_tiles_with_options(&xy, reader, options).await
+    } else {
+        let strips =
+            (rows.start / chunk_height) as usize..((rows.end - 1) / chunk_height) as usize + 1;
+        ifd.fetch_strips_with_options(strips, reader, options).await
+    }
+}
+
+/// Copy the parts of the decoded `(x, y, array)` tiles or strips of `ifd` overlapping `window`
+/// into a single array.
+pub(crate) fn mosaic_window(
+    ifd: &ImageFileDirectory,
+    window: Window,
+    chunks: impl IntoIterator<Item = (usize, usize, Array)>,
+) -> AsyncTiffResult<Array> {
+    let data_type = check_window(ifd, window)?;
+    let (cols, rows) = (window.cols()?, window.rows()?);
+    let (chunk_width, chunk_height) = chunk_size(ifd);
+
+    let bands = ifd.samples_per_pixel() as usize;
+    let planar = ifd.planar_configuration() == PlanarConfiguration::Planar;
+    let size = data_type.size();
+    let (width, height) = (window.width as usize, window.height as usize);
+    let (col_off, row_off) = (window.col_off as usize, window.row_off as usize);
+    let mut out = vec![0; bands * width * height * size];
+    for (x, y, array) in chunks {
+        let x0 = x * chunk_width as usize;
+        let y0 = y * chunk_height as usize;
+        let data = array.data().as_ref();
+        let [d0, d1, d2] = array.shape();
+        let (array_height, array_width) = if planar { (d1, d2) } else { (d0, d1) };
+
+        // The part of the window covered by this tile, in image coordinates
+        let tile_cols = x0.max(col_off)..(x0 + array_width).min(cols.end as usize);
+        let tile_rows = y0.max(row_off)..(y0 + array_height).min(rows.end as usize);
+        if tile_cols.is_empty() {
+            continue;
+        }
+        if planar {
+            let len = tile_cols.len() * size;
+            for band in 0..bands {
+                for row in tile_rows.clone() {
+                    let src = ((band * array_height + row - y0) * array_width + tile_cols.start
+                        - x0)
+                        * size;
+                    let dst = ((band * height + row - row_off) * width + tile_cols.start - col_off)
+                        * size;
+                    out[dst..dst + len].copy_from_slice(&data[src..src + len]);
+                }
+            }
+        } else {
+            let pixel_size = bands * size;
+            let len = tile_cols.len() * pixel_size;
+            for row in tile_rows {
+                let src = ((row - y0) * array_width + tile_cols.start - x0) * pixel_size;
+                let dst = ((row - row_off) * width + tile_cols.start - col_off) * pixel_size;
+                out[dst..dst + len].copy_from_slice(&data[src..src + len]);
+            }
+        }
+    }
+
+    let data = match data_type {
+        DataType::Bool => TypedArray::Bool(out.into_iter().map(|b| b != 0).collect()),
+        _ => TypedArray::try_new(out, Some(data_type))?,
+    };
+    Ok(Array {
+        data,
+        shape: if planar {
+            [bands, height, width]
+        } else {
+            [height, width, bands]
+        },
+        data_type: Some(data_type),
+    })
+}
@@ -0,0 +1,740 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult, TiffError, TiffFormatError};
+use crate::metadata::Limits;
+use crate::reader::Endianness;
+use crate::tag_value::TagValue;
+use crate::tags::Tag;
+use crate::writer::entry::{encode_tag_value, EncodedValue, EndianWriter};
+use crate::writer::NewIfd;
+
+/// Edit tag values of an existing local TIFF file in place.
+///
+/// Edits are staged in memory and written by [`save`][Self::save]. Image data is never
+/// rewritten: unchanged entries keep pointing at their existing values, new values that don't
+/// fit inline are appended to the end of the file, and an IFD whose entry count changed is
+/// written anew at the end of the file and re-linked into the IFD chain.
+///
+/// New IFDs, such as overviews or masks, can be added to the end of the IFD chain with
+/// [`append_ifd`][Self::append_ifd].
+///
+/// ```no_run
+/// use async_tiff::writer::TiffEditor;
+///
+/// let mut editor = TiffEditor::open("image.tif").unwrap();
+/// editor.set_gdal_nodata(0, "-9999").unwrap();
+/// editor.set_image_description(0, "Reprocessed").unwrap();
+/// editor.save().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct TiffEditor {
+    pub(crate) file: File,
+    pub(crate) endianness: Endianness,
+    pub(crate) bigtiff: bool,
+    pub(crate) ifds: Vec<EditableIfd>,
+}
+
+/// A single entry of an IFD being edited.
+#[derive(Debug, Clone)]
+pub(crate) enum EditableEntry {
+    /// An entry as found in the file. `field` is the raw inline value or offset.
+    Existing {
+        field_type: u16,
+        count: u64,
+        field: Vec<u8>,
+    },
+    /// An entry whose value has not been written to the file yet.
+    Pending(EncodedValue),
+}
+
+/// The entries of an IFD being edited, along with its location in the IFD chain.
+#[derive(Debug)]
+pub(crate) struct EditableIfd {
+    /// The byte offset of this IFD.
+    pub(crate) offset: u64,
+    /// The byte offset of the pointer that refers to this IFD: either the file header or the
+    /// "next IFD" field of the previous IFD.
+    pub(crate) pointer_offset: u64,
+    /// The byte offset of the IFD following this one, or 0 if this is the last IFD.
+    pub(crate) next_ifd_offset: u64,
+    /// The number of entries this IFD has on disk.
+    pub(crate) on_disk_len: usize,
+    /// Entries keyed (and therefore sorted) by tag code.
+    pub(crate) entries: BTreeMap<u16, EditableEntry>,
+    pub(crate) dirty: bool,
+}
+
+impl TiffEditor {
+    /// Open the TIFF file at `path` for editing, reading the entry tables of all of its IFDs.
+    pub fn open(path: impl AsRef<Path>) -> AsyncTiffResult<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+
+        let mut magic_bytes = [0; 2];
+        read_exact_at(&mut file, 0, &mut magic_bytes)?;
+        let endianness = match &magic_bytes {
+            b"II" => Endianness::LittleEndian,
+            b"MM" => Endianness::BigEndian,
+            _ => {
+                return Err(AsyncTiffError::General(format!(
+                    "unexpected magic bytes {magic_bytes:?}"
+                )))
+            }
+        };
+
+        let mut editor = Self {
+            file,
+            endianness,
+            bigtiff: false,
+            ifds: vec![],
+        };
+
+        let (mut pointer_offset, mut ifd_offset) = match editor.read_u16(2)? {
+            42 => (4, editor.read_u32(4)? as u64),
+            43 => {
+                if editor.read_u16(4)? != 8 || editor.read_u16(6)? != 0 {
+                    return Err(
+                        TiffError::FormatError(TiffFormatError::TiffSignatureNotFound).into(),
+                    );
+                }
+                editor.bigtiff = true;
+                (8, editor.read_u64(8)?)
+            }
+            _ => return Err(TiffError::FormatError(TiffFormatError::TiffSignatureInvalid).into()),
+        };
+
+        while ifd_offset != 0 {
+            if editor.ifds.iter().any(|ifd| ifd.offset == ifd_offset) {
+                return Err(TiffError::FormatError(TiffFormatError::CycleInOffsets).into());
+            }
+            let ifd = editor.read_ifd(ifd_offset, pointer_offset)?;
+            pointer_offset = editor.next_pointer_offset(ifd_offset, ifd.on_disk_len);
+            ifd_offset = ifd.next_ifd_offset;
+            editor.ifds.push(ifd);
+        }
+
+        Ok(editor)
+    }
+
+    /// The byte order of the file being edited.
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Whether the file being edited is a BigTIFF.
+    pub fn bigtiff(&self) -> bool {
+        self.bigtiff
+    }
+
+    /// The number of IFDs in the file.
+    pub fn ifd_count(&self) -> usize {
+        self.ifds.len()
+    }
+
+    /// Whether the IFD at `ifd_index` contains `tag`, including staged edits.
+    pub fn contains_tag(&self, ifd_index: usize, tag: Tag) -> AsyncTiffResult<bool> {
+        Ok(self.ifd(ifd_index)?.entries.contains_key(&tag.to_u16()))
+    }
+
+    /// Set `tag` to `value` in the IFD at `ifd_index`, adding the tag if it doesn't exist yet.
+    ///
+    /// The TIFF field type is inferred from the [`TagValue`] variant.
+    pub fn set_tag(&mut self, ifd_index: usize, tag: Tag, value: TagValue) -> AsyncTiffResult<()> {
+        let encoded = encode_tag_value(&value, self.endianness, self.bigtiff)?;
+        let ifd = self.ifd_mut(ifd_index)?;
+        ifd.entries
+            .insert(tag.to_u16(), EditableEntry::Pending(encoded));
+        ifd.dirty = true;
+        Ok(())
+    }
+
+    /// Remove `tag` from the IFD at `ifd_index`, returning whether it was present.
+    ///
+    /// The bytes of the removed value stay in the file but are no longer referenced.
+    pub fn remove_tag(&mut self, ifd_index: usize, tag: Tag) -> AsyncTiffResult<bool> {
+        let ifd = self.ifd_mut(ifd_index)?;
+        let removed = ifd.entries.remove(&tag.to_u16()).is_some();
+        ifd.dirty |= removed;
+        Ok(removed)
+    }
+
+    /// Set the GDAL nodata value, as its string representation, e.g. `"-9999"` or `"nan"`.
+    pub fn set_gdal_nodata(&mut self, ifd_index: usize, nodata: &str) -> AsyncTiffResult<()> {
+        self.set_tag(
+            ifd_index,
+            Tag::GdalNodata,
+            TagValue::Ascii(nodata.to_string()),
+        )
+    }
+
+    /// Set the ImageDescription tag.
+    pub fn set_image_description(
+        &mut self,
+        ifd_index: usize,
+        description: &str,
+    ) -> AsyncTiffResult<()> {
+        self.set_tag(
+            ifd_index,
+            Tag::ImageDescription,
+            TagValue::Ascii(description.to_string()),
+        )
+    }
+
+    /// Set the DateTime tag. The TIFF spec requires the format `"YYYY:MM:DD HH:MM:SS"`.
+    pub fn set_date_time(&mut self, ifd_index: usize, date_time: &str) -> AsyncTiffResult<()> {
+        self.set_tag(
+            ifd_index,
+            Tag::DateTime,
+            TagValue::Ascii(date_time.to_string()),
+        )
+    }
+
+    /// Append a new IFD, such as an overview or a mask, to the end of the IFD chain, returning
+    /// its index.
+    ///
+    /// The image data of `ifd` is written to the end of the file immediately; the IFD itself is
+    /// written and linked into the chain by [`save`][Self::save].
+    pub fn append_ifd(&mut self, ifd: NewIfd) -> AsyncTiffResult<usize> {
+        let (offsets_tag, byte_counts_tag) = ifd.chunk_tags();
+        let (tags, chunks) = ifd.into_parts();
+
+        let mut entries = BTreeMap::new();
+        for (tag, value) in tags {
+            if tag == offsets_tag || tag == byte_counts_tag {
+                return Err(AsyncTiffError::General(format!(
+                    "{tag:?} is computed from the chunks and cannot be set manually"
+                )));
+            }
+            let encoded = encode_tag_value(&value, self.endianness, self.bigtiff)?;
+            entries.insert(tag.to_u16(), EditableEntry::Pending(encoded));
+        }
+
+        let mut offsets = Vec::with_capacity(chunks.len());
+        let mut byte_counts = Vec::with_capacity(chunks.len());
+        for chunk in chunks {
+            offsets.push(self.append(&chunk)?);
+            byte_counts.push(chunk.len() as u64);
+        }
+        for (tag, values) in [(offsets_tag, offsets), (byte_counts_tag, byte_counts)] {
+            let value = self.offsets_value(values);
+            let encoded = encode_tag_value(&value, self.endianness, self.bigtiff)?;
+            entries.insert(tag.to_u16(), EditableEntry::Pending(encoded));
+        }
+
+        // The pointer to the new IFD is the "next IFD" field of the current last IFD, or the
+        // first-IFD offset in the header if there are no IFDs yet. If the last IFD is itself
+        // relocated or new, `save` updates this before writing.
+        let header_pointer_offset = if self.bigtiff { 8 } else { 4 };
+        let pointer_offset = self
+            .ifds
+            .last()
+            .map(|last| self.next_pointer_offset(last.offset, last.on_disk_len))
+            .unwrap_or(header_pointer_offset);
+        self.ifds.push(EditableIfd {
+            offset: 0,
+            pointer_offset,
+            next_ifd_offset: 0,
+            on_disk_len: 0,
+            entries,
+            dirty: true,
+        });
+        Ok(self.ifds.len() - 1)
+    }
+
+    /// Encode chunk offsets or byte counts as LONG if possible, and LONG8 otherwise.
+    fn offsets_value(&self, values: Vec<u64>) -> TagValue {
+        if values.iter().all(|v| *v <= u32::MAX as u64) {
+            TagValue::List(
+                values
+                    .into_iter()
+                    .map(|v| TagValue::Unsigned(v as u32))
+                    .collect(),
+            )
+        } else {
+            TagValue::List(values.into_iter().map(TagValue::UnsignedBig).collect())
+        }
+    }
+
+    /// Write all staged edits to the file.
+    ///
+    /// The editor can continue to be used after saving.
+    pub fn save(&mut self) -> AsyncTiffResult<()> {
+        for ifd_index in 0..self.ifds.len() {
+            if !self.ifds[ifd_index].dirty {
+                continue;
+            }
+
+            self.write_pending_values(ifd_index)?;
+
+            let ifd = &self.ifds[ifd_index];
+            let table = self.encode_ifd_table(ifd)?;
+            let (offset, pointer_offset) = (ifd.offset, ifd.pointer_offset);
+            if ifd.entries.len() == ifd.on_disk_len {
+                // Same number of entries: overwrite the table where it is.
+                self.write_at(offset, &table)?;
+            } else {
+                // The IFD changed size: write it at the end of the file and re-link it.
+                let new_offset = self.append(&table)?;
+                let mut pointer = EndianWriter::new(self.endianness);
+                pointer.write_offset(new_offset, self.bigtiff)?;
+                self.write_at(pointer_offset, &pointer.into_inner())?;
+
+                let len = self.ifds[ifd_index].entries.len();
+                let next_pointer_offset = self.next_pointer_offset(new_offset, len);
+                if let Some(next) = self.ifds.get_mut(ifd_index + 1) {
+                    next.pointer_offset = next_pointer_offset;
+                }
+                if let Some(previous) = ifd_index.checked_sub(1) {
+                    self.ifds[previous].next_ifd_offset = new_offset;
+                }
+                let ifd = &mut self.ifds[ifd_index];
+                ifd.offset = new_offset;
+                ifd.on_disk_len = len;
+            }
+            self.ifds[ifd_index].dirty = false;
+        }
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn ifd(&self, ifd_index: usize) -> AsyncTiffResult<&EditableIfd> {
+        self.ifds.get(ifd_index).ok_or_else(|| {
+            AsyncTiffError::General(format!(
+                "IFD index {ifd_index} out of range for file with {} IFDs",
+                self.ifds.len()
+            ))
+        })
+    }
+
+    fn ifd_mut(&mut self, ifd_index: usize) -> AsyncTiffResult<&mut EditableIfd> {
+        let len = self.ifds.len();
+        self.ifds.get_mut(ifd_index).ok_or_else(|| {
+            AsyncTiffError::General(format!(
+                "IFD index {ifd_index} out of range for file with {len} IFDs"
+            ))
+        })
+    }
+
+    /// The size of the value/offset field of an IFD entry.
+    fn field_size(&self) -> usize {
+        if self.bigtiff {
+            8
+        } else {
+            4
+        }
+    }
+
+    /// The byte offset of the "next IFD" pointer of an IFD at `ifd_offset` with `len` entries.
+    pub(crate) fn next_pointer_offset(&self, ifd_offset: u64, len: usize) -> u64 {
+        let (count_size, entry_size) = if self.bigtiff { (8, 20) } else { (2, 12) };
+        ifd_offset + count_size + entry_size * len as u64
+    }
+
+    fn read_ifd(&mut self, offset: u64, pointer_offset: u64) -> AsyncTiffResult<EditableIfd> {
+        let (entry_count, count_size) = if self.bigtiff {
+            (self.read_u64(offset)?, 8)
+        } else {
+            (self.read_u16(offset)? as u64, 2)
+        };
+        Limits::default().check_tag_count(entry_count)?;
+        let entry_size = 4 + 2 * self.field_size() as u64;
+        let table_size = entry_count.checked_mul(entry_size).ok_or_else(|| {
+            AsyncTiffError::General(format!("IFD at offset {offset} has too many entries"))
+        })?;
+        let mut table = vec![0; table_size as usize];
+        read_exact_at(&mut self.file, offset + count_size, &mut table)?;
+
+        let mut entries = BTreeMap::new();
+        for raw in table.chunks_exact(entry_size as usize) {
+            let tag = self.decode_u16(&raw[0..2]);
+            let field_type = self.decode_u16(&raw[2..4]);
+            let (count, field) = if self.bigtiff {
+                (self.decode_u64(&raw[4..12]), raw[12..20].to_vec())
+            } else {
+                (self.decode_u32(&raw[4..8]) as u64, raw[8..12].to_vec())
+            };
+            entries.insert(
+                tag,
+                EditableEntry::Existing {
+                    field_type,
+                    count,
+                    field,
+                },
+            );
+        }
+
+        // Count duplicate tags too, which take up space on disk but not in `entries`
+        let on_disk_len = entry_count as usize;
+        let next_ifd_offset = if self.bigtiff {
+            self.read_u64(self.next_pointer_offset(offset, on_disk_len))?
+        } else {
+            self.read_u32(self.next_pointer_offset(offset, on_disk_len))? as u64
+        };
+
+        Ok(EditableIfd {
+            offset,
+            pointer_offset,
+            next_ifd_offset,
+            on_disk_len,
+            entries,
+            dirty: false,
+        })
+    }
+
+    /// Resolve all pending entries of an IFD, appending values that don't fit inline.
+    pub(crate) fn write_pending_values(&mut self, ifd_index: usize) -> AsyncTiffResult<()> {
+        let pending = self.ifds[ifd_index]
+            .entries
+            .iter()
+            .filter_map(|(tag, entry)| match entry {
+                EditableEntry::Pending(encoded) => Some((*tag, encoded.clone())),
+                EditableEntry::Existing { .. } => None,
+            })
+            .collect::<Vec<_>>();
+
+        for (tag, encoded) in pending {
+            let field = if encoded.data.len() <= self.field_size() {
+                let mut field = encoded.data;
+                field.resize(self.field_size(), 0);
+                field
+            } else {
+                let offset = self.append(&encoded.data)?;
+                let mut field = EndianWriter::new(self.endianness);
+                field.write_offset(offset, self.bigtiff)?;
+                field.into_inner()
+            };
+            self.ifds[ifd_index].entries.insert(
+                tag,
+                EditableEntry::Existing {
+                    field_type: encoded.field_type.to_u16(),
+                    count: encoded.count,
+                    field,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Encode the full IFD (entry count, entries and next IFD offset).
+    ///
+    /// All pending values must have been resolved by `write_pending_values` first.
+    pub(crate) fn encode_ifd_table(&self, ifd: &EditableIfd) -> AsyncTiffResult<Vec<u8>> {
+        let mut writer = EndianWriter::new(self.endianness);
+        if self.bigtiff {
+            writer.write_u64(ifd.entries.len() as u64);
+        } else {
+            let len = u16::try_from(ifd.entries.len()).map_err(|_| {
+                AsyncTiffError::General(format!("too many IFD entries: {}", ifd.entries.len()))
+            })?;
+            writer.write_u16(len);
+        }
+        for (tag, entry) in ifd.entries.iter() {
+            let EditableEntry::Existing {
+                field_type,
+                count,
+                field,
+            } = entry
+            else {
+                unreachable!("pending values must be written before encoding the IFD")
+            };
+            writer.write_u16(*tag);
+            writer.write_u16(*field_type);
+            if self.bigtiff {
+                writer.write_u64(*count);
+            } else {
+                writer.write_u32(*count as u32);
+            }
+            writer.write_bytes(field);
+        }
+        writer.write_offset(ifd.next_ifd_offset, self.bigtiff)?;
+        Ok(writer.into_inner())
+    }
+
+    /// Append `data` to the end of the file on a word boundary, returning its offset.
+    pub(crate) fn append(&mut self, data: &[u8]) -> AsyncTiffResult<u64> {
+        let mut offset = self.file.seek(SeekFrom::End(0))?;
+        if offset % 2 != 0 {
+            self.file.write_all(&[0])?;
+            offset += 1;
+        }
+        self.file.write_all(data)?;
+        Ok(offset)
+    }
+
+    pub(crate) fn write_at(&mut self, offset: u64, data: &[u8]) -> AsyncTiffResult<()> {
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(data)?;
+        Ok(())
+    }
+
+    fn read_u16(&mut self, offset: u64) -> AsyncTiffResult<u16> {
+        let mut buf = [0; 2];
+        read_exact_at(&mut self.file, offset, &mut buf)?;
+        Ok(self.decode_u16(&buf))
+    }
+
+    fn read_u32(&mut self, offset: u64) -> AsyncTiffResult<u32> {
+        let mut buf = [0; 4];
+        read_exact_at(&mut self.file, offset, &mut buf)?;
+        Ok(self.decode_u32(&buf))
+    }
+
+    fn read_u64(&mut self, offset: u64) -> AsyncTiffResult<u64> {
+        let mut buf = [0; 8];
+        read_exact_at(&mut self.file, offset, &mut buf)?;
+        Ok(self.decode_u64(&buf))
+    }
+
+    fn decode_u16(&self, buf: &[u8]) -> u16 {
+        let buf = buf.try_into().unwrap();
+        match self.endianness {
+            Endianness::LittleEndian => u16::from_le_bytes(buf),
+            Endianness::BigEndian => u16::from_be_bytes(buf),
+        }
+    }
+
+    fn decode_u32(&self, buf: &[u8]) -> u32 {
+        let buf = buf.try_into().unwrap();
+        match self.endianness {
+            Endianness::LittleEndian => u32::from_le_bytes(buf),
+            Endianness::BigEndian => u32::from_be_bytes(buf),
+        }
+    }
+
+    fn decode_u64(&self, buf: &[u8]) -> u64 {
+        let buf = buf.try_into().unwrap();
+        match self.endianness {
+            Endianness::LittleEndian => u64::from_le_bytes(buf),
+            Endianness::BigEndian => u64::from_be_bytes(buf),
+        }
+    }
+}
+
+fn read_exact_at(file: &mut File, offset: u64, buf: &mut [u8]) -> AsyncTiffResult<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(buf)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::tags::{PhotometricInterpretation, Tag};
+    use crate::test::util::{open_tiff_path, temp_copy, temp_path, tiff_with_ifds};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_edit_ascii_tags() {
+        let path = temp_copy("image-tiff/tiled-rgb-u8.tif");
+        let (reader, original) = open_tiff_path(&path).await;
+        let original_tile = original.ifds()[0]
+            .fetch_tile(0, 0, reader.as_ref())
+            .await
+            .unwrap()
+            .decode(&Default::default())
+            .unwrap();
+
+        let mut editor = TiffEditor::open(&path).unwrap();
+        assert_eq!(editor.ifd_count(), original.ifds().len());
+        editor.set_gdal_nodata(0, "0").unwrap();
+        editor
+            .set_image_description(0, "a description that is too long to fit inline")
+            .unwrap();
+        editor.set_date_time(0, "2024:01:02 03:04:05").unwrap();
+        editor.save().unwrap();
+
+        let (reader, edited) = open_tiff_path(&path).await;
+        let ifd = &edited.ifds()[0];
+        assert_eq!(ifd.gdal_nodata(), Some("0"));
+        assert_eq!(
+            ifd.image_description(),
+            Some("a description that is too long to fit inline")
+        );
+        assert_eq!(ifd.date_time(), Some("2024:01:02 03:04:05"));
+        assert_eq!(
+            ifd.photometric_interpretation(),
+            PhotometricInterpretation::RGB
+        );
+
+        let edited_tile = ifd
+            .fetch_tile(0, 0, reader.as_ref())
+            .await
+            .unwrap()
+            .decode(&Default::default())
+            .unwrap();
+        assert_eq!(original_tile.data().as_ref(), edited_tile.data().as_ref());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_replace_and_remove_tag() {
+        let path = temp_copy("image-tiff/tiled-rgb-u8.tif");
+
+        let mut editor = TiffEditor::open(&path).unwrap();
+        editor.set_gdal_nodata(0, "1").unwrap();
+        editor.save().unwrap();
+        // Replacing a value keeps the entry count, so the IFD is patched where it is.
+        let offset = editor.ifds[0].offset;
+        editor.set_gdal_nodata(0, "255").unwrap();
+        editor.save().unwrap();
+        assert_eq!(editor.ifds[0].offset, offset);
+        drop(editor);
+
+        let (_, edited) = open_tiff_path(&path).await;
+        assert_eq!(edited.ifds()[0].gdal_nodata(), Some("255"));
+
+        let mut editor = TiffEditor::open(&path).unwrap();
+        assert!(editor.remove_tag(0, Tag::GdalNodata).unwrap());
+        assert!(!editor.remove_tag(0, Tag::GdalNodata).unwrap());
+        assert!(!editor.contains_tag(0, Tag::GdalNodata).unwrap());
+        editor.save().unwrap();
+
+        let (_, edited) = open_tiff_path(&path).await;
+        assert_eq!(edited.ifds()[0].gdal_nodata(), None);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_append_overview_and_mask() {
+        let path = temp_copy("image-tiff/tiled-rgb-u8.tif");
+        let (_, original) = open_tiff_path(&path).await;
+        let full = &original.ifds()[0];
+        let width = full.image_width().div_ceil(2);
+        let height = full.image_height().div_ceil(2);
+        let tile_width = full.tile_width().unwrap();
+        let tile_height = full.tile_height().unwrap();
+        let tile_count = (width.div_ceil(tile_width) * height.div_ceil(tile_height)) as usize;
+        let tile_size = (tile_width * tile_height) as usize;
+
+        let overview = NewIfd::overview_of(full, width, height, 256)
+            .with_tag(Tag::Compression, TagValue::Short(1))
+            .with_tag(Tag::Predictor, TagValue::Short(1))
+            .with_chunks((0..tile_count).map(|i| vec![i as u8; tile_size * 3]));
+        let mask = NewIfd::tiled(width, height, tile_width, tile_height)
+            .mask()
+            .with_tag(Tag::PhotometricInterpretation, TagValue::Short(4))
+            .with_tag(Tag::SamplesPerPixel, TagValue::Short(1))
+            .with_tag(Tag::BitsPerSample, TagValue::Short(8))
+            .with_chunks((0..tile_count).map(|_| vec![255; tile_size]));
+
+        let mut editor = TiffEditor::open(&path).unwrap();
+        assert_eq!(editor.append_ifd(overview).unwrap(), original.ifds().len());
+        editor.append_ifd(mask).unwrap();
+        editor.save().unwrap();
+
+        let (reader, edited) = open_tiff_path(&path).await;
+        assert_eq!(edited.ifds().len(), original.ifds().len() + 2);
+
+        let overview = &edited.ifds()[original.ifds().len()];
+        assert_eq!(overview.new_subfile_type(), Some(1));
+        assert_eq!(overview.image_width(), width);
+        assert_eq!(
+            overview.photometric_interpretation(),
+            full.photometric_interpretation()
+        );
+        assert_eq!(
+            overview.tile_count().unwrap().0 * overview.tile_count().unwrap().1,
+            tile_count
+        );
+        let tile = overview
+            .fetch_tile(1, 0, reader.as_ref())
+            .await
+            .unwrap()
+            .decode(&Default::default())
+            .unwrap();
+        assert_eq!(tile.data().as_ref(), vec![1; tile_size * 3]);
+
+        let mask = &edited.ifds()[original.ifds().len() + 1];
+        assert_eq!(mask.new_subfile_type(), Some(4));
+        let tile = mask
+            .fetch_tile(0, 0, reader.as_ref())
+            .await
+            .unwrap()
+            .decode(&Default::default())
+            .unwrap();
+        assert_eq!(tile.data().as_ref(), vec![255; tile_size]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_append_to_empty_file() {
+        let headers: [&[u8]; 2] = [
+            b"II\x2a\x00\x00\x00\x00\x00",
+            b"II\x2b\x00\x08\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+        ];
+        for header in headers {
+            let path = temp_path("no_ifds.tif");
+            std::fs::write(&path, header).unwrap();
+
+            let mut editor = TiffEditor::open(&path).unwrap();
+            assert_eq!(editor.ifd_count(), 0);
+            let ifd = NewIfd::stripped(2, 1, 1)
+                .with_tag(Tag::PhotometricInterpretation, TagValue::Short(1))
+                .with_tag(Tag::SamplesPerPixel, TagValue::Short(1))
+                .with_tag(Tag::BitsPerSample, TagValue::Short(8))
+                .with_chunks([vec![7, 9]]);
+            assert_eq!(editor.append_ifd(ifd).unwrap(), 0);
+            editor.save().unwrap();
+
+            let data = std::fs::read(&path).unwrap();
+            assert_eq!(&data[..4], &header[..4]);
+
+            let (reader, tiff) = open_tiff_path(&path).await;
+            assert_eq!(tiff.ifds().len(), 1);
+            let strip = tiff.ifds()[0]
+                .fetch_strip(0, reader.as_ref())
+                .await
+                .unwrap()
+                .decode(&Default::default())
+                .unwrap();
+            assert_eq!(strip.data().as_ref(), [7, 9]);
+
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_append_rejects_chunk_tags() {
+        let path = temp_copy("image-tiff/tiled-rgb-u8.tif");
+        let mut editor = TiffEditor::open(&path).unwrap();
+        let ifd = NewIfd::stripped(1, 1, 1).with_tag(Tag::StripOffsets, TagValue::Unsigned(0));
+        assert!(editor.append_ifd(ifd).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_open_malformed_ifds() {
+        // A BigTIFF IFD claiming u64::MAX entries
+        let path = temp_path("huge_ifd.tif");
+        let mut data = b"II\x2b\x00\x08\x00\x00\x00".to_vec();
+        data.extend_from_slice(&16u64.to_le_bytes());
+        data.extend_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&path, data).unwrap();
+        assert!(matches!(
+            TiffEditor::open(&path),
+            Err(AsyncTiffError::LimitExceeded { .. })
+        ));
+        std::fs::remove_file(path).unwrap();
+
+        // Duplicate tags don't move the next IFD pointer
+        let path = temp_path("duplicate_tags.tif");
+        let data = tiff_with_ifds(&[&[(256, 3, 1, 5), (256, 3, 1, 6)]]);
+        std::fs::write(&path, data).unwrap();
+        let editor = TiffEditor::open(&path).unwrap();
+        assert_eq!(editor.ifd_count(), 1);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_ifd_index_out_of_range() {
+        let path = temp_copy("image-tiff/tiled-rgb-u8.tif");
+        let mut editor = TiffEditor::open(&path).unwrap();
+        assert!(editor.set_gdal_nodata(10, "0").is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+//! Compression of image chunks.
+
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+
+use crate::error::{AsyncTiffResult, TiffError, TiffUnsupportedError};
+use crate::tags::Compression;
+
+/// Whether [`encode_chunk`] supports writing data with `compression`.
+pub(crate) fn is_supported(compression: Compression) -> bool {
+    matches!(
+        compression,
+        Compression::None | Compression::Deflate | Compression::LZW | Compression::ZSTD
+    )
+}
+
+/// Compress one tile or strip.
+pub(crate) fn encode_chunk(compression: Compression, data: &[u8]) -> AsyncTiffResult<Vec<u8>> {
+    match compression {
+        Compression::None => Ok(data.to_vec()),
+        Compression::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data)?;
+            Ok(encoder.finish()?)
+        }
+        Compression::LZW => {
+            let mut encoder =
+                weezl::encode::Encoder::with_tiff_size_switch(weezl::BitOrder::Msb, 8);
+            let mut encoded = Vec::new();
+            encoder
+                .into_vec(&mut encoded)
+                .encode_all(data)
+                .status
+                .map_err(std::io::Error::other)?;
+            Ok(encoded)
+        }
+        Compression::ZSTD => Ok(zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL)?),
+        other => Err(
+            TiffError::UnsupportedError(TiffUnsupportedError::UnsupportedCompression(other)).into(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::decoder::{DecodeContext, DecoderRegistry};
+
+    #[test]
+    fn test_encode_round_trip() {
+        let data = (0..4096).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        let registry = DecoderRegistry::default();
+        for compression in [
+            Compression::None,
+            Compression::Deflate,
+            Compression::LZW,
+            Compression::ZSTD,
+        ] {
+            assert!(is_supported(compression));
+            let encoded = encode_chunk(compression, &data).unwrap();
+            let decoded = registry.as_ref()[&compression]
+                .decode(encoded.into(), &DecodeContext::new(64, 64, 1, 8))
+                .unwrap();
+            assert_eq!(decoded, data, "{compression:?}");
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Encoding of IFD entries to their on-disk representation.
+
+use std::collections::BTreeMap;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::reader::Endianness;
+use crate::tag_value::TagValue;
+use crate::tags::Type;
+
+/// A byte buffer that writes primitives in a fixed byte order.
+#[derive(Debug)]
+pub(crate) struct EndianWriter {
+    buf: Vec<u8>,
+    endianness: Endianness,
+}
+
+macro_rules! write_fn {
+    ($name:ident, $ty:ty) => {
+        pub(crate) fn $name(&mut self, value: $ty) {
+            match self.endianness {
+                Endianness::LittleEndian => self.buf.extend_from_slice(&value.to_le_bytes()),
+                Endianness::BigEndian => self.buf.extend_from_slice(&value.to_be_bytes()),
+            }
+        }
+    };
+}
+
+impl EndianWriter {
+    pub(crate) fn new(endianness: Endianness) -> Self {
+        Self {
+            buf: Vec::new(),
+            endianness,
+        }
+    }
+
+    write_fn!(write_u16, u16);
+    write_fn!(write_i16, i16);
+    write_fn!(write_u32, u32);
+    write_fn!(write_i32, i32);
+    write_fn!(write_u64, u64);
+    write_fn!(write_i64, i64);
+    write_fn!(write_f32, f32);
+    write_fn!(write_f64, f64);
+
+    pub(crate) fn write_u8(&mut self, value: u8) {
+        self.buf.push(value);
+    }
+
+    pub(crate) fn write_i8(&mut self, value: i8) {
+        self.buf.push(value as u8);
+    }
+
+    pub(crate) fn write_bytes(&mut self, value: &[u8]) {
+        self.buf.extend_from_slice(value);
+    }
+
+    /// Write an offset, which is 8 bytes in BigTIFF and 4 bytes otherwise.
+    pub(crate) fn write_offset(&mut self, value: u64, bigtiff: bool) -> AsyncTiffResult<()> {
+        if bigtiff {
+            self.write_u64(value);
+        } else {
+            let value = u32::try_from(value).map_err(|_| {
+                AsyncTiffError::General(format!(
+                    "offset {value} does not fit in a non-BigTIFF file"
+                ))
+            })?;
+            self.write_u32(value);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub(crate) fn into_inner(self) -> Vec<u8> {
+        self.buf
+    }
+}
+
+/// The on-disk type, count and value bytes of a tag value, not yet placed in the file.
+#[derive(Debug, Clone)]
+pub(crate) struct EncodedValue {
+    pub(crate) field_type: Type,
+    pub(crate) count: u64,
+    pub(crate) data: Vec<u8>,
+}
+
+/// Encode a [`TagValue`] into the byte representation used in a TIFF file.
+///
+/// Lists must be homogeneous: every element must encode to the same TIFF type.
+pub(crate) fn encode_tag_value(
+    value: &TagValue,
+    endianness: Endianness,
+    bigtiff: bool,
+) -> AsyncTiffResult<EncodedValue> {
+    let mut writer = EndianWriter::new(endianness);
+    let (field_type, count) = match value {
+        TagValue::Ascii(s) => {
+            writer.write_bytes(s.as_bytes());
+            writer.write_u8(0);
+            (Type::ASCII, writer.len() as u64)
+        }
+        TagValue::List(values) => {
+            let Some(first) = values.first() else {
+                return Err(AsyncTiffError::General(
+                    "cannot encode an empty tag value list".to_string(),
+                ));
+            };
+            let field_type = scalar_type(first, bigtiff)?;
+            for v in values {
+                if scalar_type(v, bigtiff)? != field_type {
+                    return Err(AsyncTiffError::General(format!(
+                        "cannot encode a tag value list with mixed types: {values:?}"
+                    )));
+                }
+                write_scalar(&mut writer, v);
+            }
+            (field_type, values.len() as u64)
+        }
+        scalar => {
+            let field_type = scalar_type(scalar, bigtiff)?;
+            write_scalar(&mut writer, scalar);
+            (field_type, 1)
+        }
+    };
+    Ok(EncodedValue {
+        field_type,
+        count,
+        data: writer.into_inner(),
+    })
+}
+
+/// Encode a complete IFD located at `ifd_offset`: the entry count, the entries sorted by tag
+/// code, the offset of the next IFD, and then all values that don't fit inline.
+///
+/// The length of the result only depends on the entries, not on `ifd_offset`, as long as
+/// `ifd_offset` is even.
+pub(crate) fn encode_ifd(
+    entries: &BTreeMap<u16, EncodedValue>,
+    ifd_offset: u64,
+    next_ifd_offset: u64,
+    endianness: Endianness,
+    bigtiff: bool,
+) -> AsyncTiffResult<Vec<u8>> {
+    let (count_size, entry_size, field_size) = if bigtiff { (8, 20, 8) } else { (2, 12, 4) };
+    let table_len = count_size + entry_size * entries.len() as u64 + field_size;
+
+    let mut table = EndianWriter::new(endianness);
+    let mut values = EndianWriter::new(endianness);
+    if bigtiff {
+        table.write_u64(entries.len() as u64);
+    } else {
+        let len = u16::try_from(entries.len()).map_err(|_| {
+            AsyncTiffError::General(format!("too many IFD entries: {}", entries.len()))
+        })?;
+        table.write_u16(len);
+    }
+    for (tag, value) in entries {
+        table.write_u16(*tag);
+        table.write_u16(value.field_type.to_u16());
+        if bigtiff {
+            table.write_u64(value.count);
+        } else {
+            let count = u32::try_from(value.count)
+                .map_err(|_| AsyncTiffError::General(format!("too many values for tag {tag}")))?;
+            table.write_u32(count);
+        }
+        if value.data.len() as u64 <= field_size {
+            table.write_bytes(&value.data);
+            table.write_bytes(&vec![0; field_size as usize - value.data.len()]);
+        } else {
+            table.write_offset(ifd_offset + table_len + values.len() as u64, bigtiff)?;
+            values.write_bytes(&value.data);
+            // Keep values on word boundaries
+            if !values.len().is_multiple_of(2) {
+                values.write_u8(0);
+            }
+        }
+    }
+    table.write_offset(next_ifd_offset, bigtiff)?;
+    table.write_bytes(&values.into_inner());
+    Ok(table.into_inner())
+}
+
+fn scalar_type(value: &TagValue, bigtiff: bool) -> AsyncTiffResult<Type> {
+    let field_type = match value {
+        TagValue::Byte(_) => Type::BYTE,
+        TagValue::Short(_) => Type::SHORT,
+        TagValue::SignedByte(_) => Type::SBYTE,
+        TagValue::SignedShort(_) => Type::SSHORT,
+        TagValue::Signed(_) => Type::SLONG,
+        TagValue::SignedBig(_) => Type::SLONG8,
+        TagValue::Unsigned(_) => Type::LONG,
+        TagValue::UnsignedBig(_) => Type::LONG8,
+        TagValue::Float(_) => Type::FLOAT,
+        TagValue::Double(_) => Type::DOUBLE,
+        TagValue::Rational(_, _) => Type::RATIONAL,
+        TagValue::SRational(_, _) => Type::SRATIONAL,
+        TagValue::Ifd(_) => Type::IFD,
+        TagValue::IfdBig(_) => Type::IFD8,
+        other => {
+            return Err(AsyncTiffError::General(format!(
+                "cannot encode tag value {other:?}"
+            )))
+        }
+    };
+    if !bigtiff && matches!(field_type, Type::LONG8 | Type::SLONG8 | Type::IFD8) {
+        return Err(AsyncTiffError::General(format!(
+            "{field_type:?} values can only be written to BigTIFF files"
+        )));
+    }
+    Ok(field_type)
+}
+
+fn write_scalar(writer: &mut EndianWriter, value: &TagValue) {
+    match *value {
+        TagValue::Byte(v) => writer.write_u8(v),
+        TagValue::Short(v) => writer.write_u16(v),
+        TagValue::SignedByte(v) => writer.write_i8(v),
+        TagValue::SignedShort(v) => writer.write_i16(v),
+        TagValue::Signed(v) => writer.write_i32(v),
+        TagValue::SignedBig(v) => writer.write_i64(v),
+        TagValue::Unsigned(v) => writer.write_u32(v),
+        TagValue::UnsignedBig(v) => writer.write_u64(v),
+        TagValue::Float(v) => writer.write_f32(v),
+        TagValue::Double(v) => writer.write_f64(v),
+        TagValue::Rational(n, d) => {
+            writer.write_u32(n);
+            writer.write_u32(d);
+        }
+        TagValue::SRational(n, d) => {
+            writer.write_i32(n);
+            writer.write_i32(d);
+        }
+        TagValue::Ifd(v) => writer.write_u32(v),
+        TagValue::IfdBig(v) => writer.write_u64(v),
+        // Rejected by `scalar_type`
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encode_ascii_is_nul_terminated() {
+        let encoded = encode_tag_value(
+            &TagValue::Ascii("-9999".to_string()),
+            Endianness::LittleEndian,
+            false,
+        )
+        .unwrap();
+        assert_eq!(encoded.field_type, Type::ASCII);
+        assert_eq!(encoded.count, 6);
+        assert_eq!(encoded.data, b"-9999\0");
+    }
+
+    #[test]
+    fn test_encode_list_endianness() {
+        let value = TagValue::List(vec![TagValue::Short(1), TagValue::Short(256)]);
+        let le = encode_tag_value(&value, Endianness::LittleEndian, false).unwrap();
+        let be = encode_tag_value(&value, Endianness::BigEndian, false).unwrap();
+        assert_eq!(le.field_type, Type::SHORT);
+        assert_eq!(le.count, 2);
+        assert_eq!(le.data, [1, 0, 0, 1]);
+        assert_eq!(be.data, [0, 1, 1, 0]);
+    }
+
+    #[test]
+    fn test_encode_rejects_invalid_values() {
+        let mixed = TagValue::List(vec![TagValue::Short(1), TagValue::Unsigned(1)]);
+        assert!(encode_tag_value(&mixed, Endianness::LittleEndian, false).is_err());
+        let big = TagValue::UnsignedBig(1);
+        assert!(encode_tag_value(&big, Endianness::LittleEndian, false).is_err());
+        assert!(encode_tag_value(&big, Endianness::LittleEndian, true).is_ok());
+    }
+}
@@ -0,0 +1,185 @@
+use bytes::Bytes;
+
+use crate::tag_value::TagValue;
+use crate::tags::{Compression, Tag};
+use crate::ImageFileDirectory;
+
+/// `NewSubfileType` bit marking a reduced-resolution version of another image.
+const REDUCED_RESOLUTION: u32 = 1;
+/// `NewSubfileType` bit marking a transparency mask for another image.
+const TRANSPARENCY_MASK: u32 = 4;
+
+/// A new IFD, including its already-encoded image data, to append to an existing file with
+/// [`TiffEditor::append_ifd`][crate::writer::TiffEditor::append_ifd].
+///
+/// The chunk offset and byte count tags (`TileOffsets`/`TileByteCounts` or
+/// `StripOffsets`/`StripByteCounts`) are filled in when the chunks are written, so they must not
+/// be set manually.
+#[derive(Debug, Clone)]
+pub struct NewIfd {
+    tiled: bool,
+    tags: Vec<(Tag, TagValue)>,
+    chunks: Vec<Bytes>,
+}
+
+impl NewIfd {
+    /// Create a new tiled IFD with the given image and tile size.
+    pub fn tiled(image_width: u32, image_height: u32, tile_width: u32, tile_height: u32) -> Self {
+        Self::new(image_width, image_height, true)
+            .with_tag(Tag::TileWidth, TagValue::Unsigned(tile_width))
+            .with_tag(Tag::TileLength, TagValue::Unsigned(tile_height))
+    }
+
+    /// Create a new stripped IFD with the given image size and number of rows per strip.
+    pub fn stripped(image_width: u32, image_height: u32, rows_per_strip: u32) -> Self {
+        Self::new(image_width, image_height, false)
+            .with_tag(Tag::RowsPerStrip, TagValue::Unsigned(rows_per_strip))
+    }
+
+    fn new(image_width: u32, image_height: u32, tiled: bool) -> Self {
+        Self {
+            tiled,
+            tags: vec![],
+            chunks: vec![],
+        }
+        .with_tag(Tag::ImageWidth, TagValue::Unsigned(image_width))
+        .with_tag(Tag::ImageLength, TagValue::Unsigned(image_height))
+        .with_tag(
+            Tag::Compression,
+            TagValue::Short(Compression::None.to_u16()),
+        )
+    }
+
+    /// Create a tiled overview of `ifd` with the given size.
+    ///
+    /// All tags describing the pixel format (bits per sample, sample format, photometric
+    /// interpretation, compression, predictor, etc.) are copied from `ifd`, and the IFD is marked
+    /// as a reduced-resolution image. The tile size is copied as well if `ifd` is tiled.
+    pub fn overview_of(
+        ifd: &ImageFileDirectory,
+        image_width: u32,
+        image_height: u32,
+        default_tile_size: u32,
+    ) -> Self {
+        let tile_width = ifd.tile_width().unwrap_or(default_tile_size);
+        let tile_height = ifd.tile_height().unwrap_or(default_tile_size);
+        let mut new_ifd = Self::tiled(image_width, image_height, tile_width, tile_height)
+            .with_new_subfile_type(REDUCED_RESOLUTION)
+            .with_tag(
+                Tag::Compression,
+                TagValue::Short(ifd.compression().to_u16()),
+            )
+            .with_tag(
+                Tag::PhotometricInterpretation,
+                TagValue::Short(ifd.photometric_interpretation().to_u16()),
+            )
+            .with_tag(
+                Tag::SamplesPerPixel,
+                TagValue::Short(ifd.samples_per_pixel()),
+            )
+            .with_tag(
+                Tag::BitsPerSample,
+                TagValue::List(
+                    ifd.bits_per_sample()
+                        .iter()
+                        .map(|b| TagValue::Short(*b))
+                        .collect(),
+                ),
+            )
+            .with_tag(
+                Tag::SampleFormat,
+                TagValue::List(
+                    ifd.sample_format()
+                        .iter()
+                        .map(|f| TagValue::Short(f.to_u16()))
+                        .collect(),
+                ),
+            )
+            .with_tag(
+                Tag::PlanarConfiguration,
+                TagValue::Short(ifd.planar_configuration().to_u16()),
+            );
+        if let Some(predictor) = ifd.predictor() {
+            new_ifd = new_ifd.with_tag(Tag::Predictor, TagValue::Short(predictor.to_u16()));
+        }
+        if let Some(extra_samples) = ifd.extra_samples() {
+            new_ifd = new_ifd.with_tag(
+                Tag::ExtraSamples,
+                TagValue::List(
+                    extra_samples
+                        .iter()
+                        .map(|s| TagValue::Short(s.to_u16()))
+                        .collect(),
+                ),
+            );
+        }
+        if let Some(jpeg_tables) = ifd.jpeg_tables() {
+            new_ifd = new_ifd.with_tag(
+                Tag::JPEGTables,
+                TagValue::List(jpeg_tables.iter().map(|b| TagValue::Byte(*b)).collect()),
+            );
+        }
+        if let Some(nodata) = ifd.gdal_nodata() {
+            new_ifd = new_ifd.with_tag(Tag::GdalNodata, TagValue::Ascii(nodata.to_string()));
+        }
+        new_ifd
+    }
+
+    /// Set a tag, replacing any previous value of the same tag.
+    pub fn with_tag(mut self, tag: Tag, value: TagValue) -> Self {
+        self.tags.retain(|(t, _)| *t != tag);
+        self.tags.push((tag, value));
+        self
+    }
+
+    /// Set the `NewSubfileType` tag.
+    pub fn with_new_subfile_type(self, new_subfile_type: u32) -> Self {
+        self.with_tag(Tag::NewSubfileType, TagValue::Unsigned(new_subfile_type))
+    }
+
+    /// Mark this IFD as a reduced-resolution overview.
+    pub fn overview(self) -> Self {
+        self.with_new_subfile_type(REDUCED_RESOLUTION)
+    }
+
+    /// Mark this IFD as a transparency mask.
+    pub fn mask(self) -> Self {
+        self.with_new_subfile_type(TRANSPARENCY_MASK)
+    }
+
+    /// Add one encoded tile or strip. Chunks must be added in the order they are stored in the
+    /// offsets array: row-major, and band by band for planar images.
+    pub fn with_chunk(mut self, chunk: impl Into<Bytes>) -> Self {
+        self.chunks.push(chunk.into());
+        self
+    }
+
+    /// Add multiple encoded tiles or strips. See [`with_chunk`][Self::with_chunk].
+    pub fn with_chunks(mut self, chunks: impl IntoIterator<Item = impl Into<Bytes>>) -> Self {
+        self.chunks.extend(chunks.into_iter().map(Into::into));
+        self
+    }
+
+    /// The tags of this IFD.
+    pub fn tags(&self) -> &[(Tag, TagValue)] {
+        &self.tags
+    }
+
+    /// Whether this IFD stores its data in tiles rather than strips.
+    pub fn is_tiled(&self) -> bool {
+        self.tiled
+    }
+
+    pub(crate) fn into_parts(self) -> (Vec<(Tag, TagValue)>, Vec<Bytes>) {
+        (self.tags, self.chunks)
+    }
+
+    /// The tags holding the chunk offsets and byte counts for this layout.
+    pub(crate) fn chunk_tags(&self) -> (Tag, Tag) {
+        if self.tiled {
+            (Tag::TileOffsets, Tag::TileByteCounts)
+        } else {
+            (Tag::StripOffsets, Tag::StripByteCounts)
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Write TIFF files.
+//!
+//! [`TiffEditor`] modifies existing local files in place, and `transcode`, enabled by the
+//! `transcode` feature, writes a new Cloud-Optimized GeoTIFF from any readable TIFF.
+
+mod editor;
+#[cfg(feature = "transcode")]
+mod encode;
+mod entry;
+mod ifd;
+mod snapshot;
+#[cfg(feature = "transcode")]
+mod transcode;
+
+pub use editor::TiffEditor;
+pub use ifd::NewIfd;
+pub(crate) use snapshot::encode_snapshot;
+#[cfg(feature = "transcode")]
+pub use transcode::{transcode, TranscodeOptions};
@@ -0,0 +1,46 @@
+use std::collections::BTreeMap;
+
+use crate::error::AsyncTiffResult;
+use crate::reader::Endianness;
+use crate::writer::entry::{encode_ifd, encode_tag_value, EndianWriter};
+use crate::TIFF;
+
+/// Encode the metadata of `tiff` as a BigTIFF file without image data, in the byte order of the
+/// original file, holding the [tags][crate::ImageFileDirectory::to_tags] of each IFD.
+///
+/// Tile and strip offsets still refer to the original file.
+pub(crate) fn encode_snapshot(tiff: &TIFF) -> AsyncTiffResult<Vec<u8>> {
+    let endianness = tiff.endianness();
+    let mut out = EndianWriter::new(endianness);
+    out.write_bytes(match endianness {
+        Endianness::LittleEndian => b"II",
+        Endianness::BigEndian => b"MM",
+    });
+    out.write_u16(43);
+    out.write_u16(8);
+    out.write_u16(0);
+    out.write_u64(16);
+
+    let ifds = tiff.ifds();
+    for (i, ifd) in ifds.iter().enumerate() {
+        let mut entries = BTreeMap::new();
+        for (tag, value) in ifd.to_tags() {
+            entries.insert(tag.to_u16(), encode_tag_value(&value, endianness, true)?);
+        }
+        let offset = out.len() as u64;
+        let next_offset = if i + 1 < ifds.len() {
+            // The length of an IFD doesn't depend on the offset of the next one
+            offset + encode_ifd(&entries, offset, 0, endianness, true)?.len() as u64
+        } else {
+            0
+        };
+        out.write_bytes(&encode_ifd(
+            &entries,
+            offset,
+            next_offset,
+            endianness,
+            true,
+        )?);
+    }
+    Ok(out.into_inner())
+}
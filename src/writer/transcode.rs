@@ -0,0 +1,805 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{Seek, SeekFrom, Write};
+use std::sync::Arc;
+
+use crate::decoder::DecoderRegistry;
+use crate::error::{AsyncTiffError, AsyncTiffResult, TiffError, TiffUnsupportedError};
+use crate::reader::{AsyncFileReader, Endianness};
+use crate::tag_value::TagValue;
+use crate::tags::{Compression, PhotometricInterpretation, PlanarConfiguration, Tag};
+use crate::writer::encode::{encode_chunk, is_supported};
+use crate::writer::entry::{encode_ifd, encode_tag_value, EncodedValue, EndianWriter};
+use crate::ycbcr::YCbCr;
+use crate::{ImageFileDirectory, TIFF};
+
+/// Files with more uncompressed image data than this are written as BigTIFF by default.
+const BIGTIFF_THRESHOLD: u64 = 0xF000_0000;
+
+/// Options for [`transcode`].
+#[derive(Debug, Clone)]
+pub struct TranscodeOptions {
+    tile_size: u32,
+    compression: Compression,
+    overviews: bool,
+    bigtiff: Option<bool>,
+    decoder_registry: Arc<DecoderRegistry>,
+}
+
+impl Default for TranscodeOptions {
+    fn default() -> Self {
+        Self {
+            tile_size: 512,
+            compression: Compression::Deflate,
+            overviews: true,
+            bigtiff: None,
+            decoder_registry: Arc::new(DecoderRegistry::default()),
+        }
+    }
+}
+
+impl TranscodeOptions {
+    /// Set the width and height of output tiles. Must be a multiple of 16. Defaults to 512.
+    pub fn with_tile_size(mut self, tile_size: u32) -> Self {
+        self.tile_size = tile_size;
+        self
+    }
+
+    /// Set the compression of output tiles. Defaults to [`Compression::Deflate`].
+    ///
+    /// Supported compressions are `None`, `Deflate`, `LZW` and `ZSTD`.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Whether to generate overviews. Defaults to `true`.
+    pub fn with_overviews(mut self, overviews: bool) -> Self {
+        self.overviews = overviews;
+        self
+    }
+
+    /// Force writing a BigTIFF or a classic TIFF. By default a BigTIFF is written when the
+    /// uncompressed image data approaches 4GB.
+    pub fn with_bigtiff(mut self, bigtiff: bool) -> Self {
+        self.bigtiff = Some(bigtiff);
+        self
+    }
+
+    /// Set the decoder registry used to decode the source image.
+    pub fn with_decoder_registry(mut self, decoder_registry: Arc<DecoderRegistry>) -> Self {
+        self.decoder_registry = decoder_registry;
+        self
+    }
+}
+
+/// Read the full-resolution image of `tiff` and write it as a Cloud-Optimized GeoTIFF to
+/// `writer`, returning the writer.
+///
+/// The source may be tiled or stripped, chunky or planar, and use any compression supported by
+/// the decoder registry. The output is always tiled, pixel-interleaved and compressed according
+/// to `options`, with all IFDs at the start of the file so that readers can fetch the metadata of
+/// every level in a single request. GeoTIFF tags, nodata and GDAL metadata are preserved.
+///
+/// Overviews are generated by halving the image until it fits in a single tile, using nearest
+/// neighbor resampling.
+///
+/// Tiles are streamed: only about one row of source chunks and one row of output tiles per level
+/// are held in memory at once. The compressed tiles of each level are spilled to a temporary file
+/// and copied to `writer` once the whole image has been read, so that the image data is ordered
+/// from the smallest overview to the full-resolution image. This needs temporary disk space about
+/// the size of the output. `writer` is written from its start.
+///
+/// Requires the `transcode` feature.
+pub async fn transcode<W: Write + Seek>(
+    tiff: &TIFF,
+    reader: &dyn AsyncFileReader,
+    options: &TranscodeOptions,
+    mut writer: W,
+) -> AsyncTiffResult<W> {
+    let ifd = tiff
+        .ifds()
+        .first()
+        .ok_or(AsyncTiffError::General("TIFF has no IFDs".to_string()))?;
+    validate(ifd, options)?;
+
+    let data_type = ifd.data_type().ok_or(AsyncTiffError::General(format!(
+        "unsupported sample format {:?} with bits per sample {:?}",
+        ifd.sample_format(),
+        ifd.bits_per_sample()
+    )))?;
+    let pixel_size = data_type.size() * ifd.samples_per_pixel() as usize;
+
+    let mut sizes = vec![(ifd.image_width(), ifd.image_height())];
+    if options.overviews {
+        let (mut width, mut height) = sizes[0];
+        while width > options.tile_size || height > options.tile_size {
+            (width, height) = (width.div_ceil(2), height.div_ceil(2));
+            sizes.push((width, height));
+        }
+    }
+
+    let bigtiff = options.bigtiff.unwrap_or_else(|| {
+        let total = sizes
+            .iter()
+            .map(|(w, h)| *w as u64 * *h as u64 * pixel_size as u64)
+            .sum::<u64>();
+        total > BIGTIFF_THRESHOLD
+    });
+    // Decoded data is in native byte order, so write the file in native byte order too.
+    let endianness = if cfg!(target_endian = "little") {
+        Endianness::LittleEndian
+    } else {
+        Endianness::BigEndian
+    };
+
+    let mut levels = sizes
+        .iter()
+        .enumerate()
+        .map(|(i, (width, height))| {
+            let tags = level_tags(ifd, i == 0, *width, *height, options)?;
+            let mut entries = BTreeMap::new();
+            for (tag, value) in tags {
+                entries.insert(tag.to_u16(), encode_tag_value(&value, endianness, bigtiff)?);
+            }
+            Level::new(*width, *height, options.tile_size, entries)
+        })
+        .collect::<AsyncTiffResult<Vec<_>>>()?;
+
+    // Lay out the header and all IFDs, with placeholder chunk offsets and byte counts, at the
+    // start of the file.
+    let mut header = EndianWriter::new(endianness);
+    header.write_bytes(match endianness {
+        Endianness::LittleEndian => b"II",
+        Endianness::BigEndian => b"MM",
+    });
+    if bigtiff {
+        header.write_u16(43);
+        header.write_u16(8);
+        header.write_u16(0);
+    } else {
+        header.write_u16(42);
+    }
+    let mut ifd_offset = header.len() as u64 + if bigtiff { 8 } else { 4 };
+    header.write_offset(ifd_offset, bigtiff)?;
+    writer.seek(SeekFrom::Start(0))?;
+    writer.write_all(&header.into_inner())?;
+    let mut ifd_offsets = Vec::with_capacity(levels.len());
+    for level in levels.iter_mut() {
+        level.set_chunk_tags(endianness, bigtiff)?;
+        let placeholder = encode_ifd(&level.entries, ifd_offset, 0, endianness, bigtiff)?;
+        writer.write_all(&placeholder)?;
+        ifd_offsets.push(ifd_offset);
+        ifd_offset += placeholder.len() as u64;
+    }
+
+    let mut pipeline = Pipeline {
+        levels,
+        tile_size: options.tile_size as usize,
+        pixel_size,
+        compression: options.compression,
+    };
+
+    let mut source = SourceRows::new(ifd, reader, &options.decoder_registry, pixel_size)?;
+    let height = ifd.image_height();
+    let mut row = 0;
+    while row < height {
+        let end = (row + options.tile_size).min(height);
+        let rows = source.read_rows(row, end).await?;
+        pipeline.push_rows(0, &rows)?;
+        row = end;
+    }
+
+    // Copy the tiles of each level after the IFDs, from the smallest overview to the
+    // full-resolution image, moving their offsets from the spill file to the output.
+    let mut levels = pipeline.levels;
+    let mut position = ifd_offset;
+    for level in levels.iter_mut().rev() {
+        level.data.seek(SeekFrom::Start(0))?;
+        position += std::io::copy(&mut level.data, &mut writer)?;
+        let start = position - level.data_len;
+        for offset in level.tile_offsets.iter_mut() {
+            *offset += start;
+        }
+    }
+
+    // Fill in the chunk offsets and byte counts now that all tiles have been written.
+    let level_count = levels.len();
+    for (i, mut level) in levels.into_iter().enumerate() {
+        level.set_chunk_tags(endianness, bigtiff)?;
+        let next_ifd_offset = if i + 1 < level_count {
+            ifd_offsets[i + 1]
+        } else {
+            0
+        };
+        let encoded = encode_ifd(
+            &level.entries,
+            ifd_offsets[i],
+            next_ifd_offset,
+            endianness,
+            bigtiff,
+        )?;
+        writer.seek(SeekFrom::Start(ifd_offsets[i]))?;
+        writer.write_all(&encoded)?;
+    }
+    writer.seek(SeekFrom::End(0))?;
+    writer.flush()?;
+    Ok(writer)
+}
+
+fn validate(ifd: &ImageFileDirectory, options: &TranscodeOptions) -> AsyncTiffResult<()> {
+    if !is_supported(options.compression) {
+        return Err(
+            TiffError::UnsupportedError(TiffUnsupportedError::UnsupportedCompression(
+                options.compression,
+            ))
+            .into(),
+        );
+    }
+    if options.tile_size == 0 || !options.tile_size.is_multiple_of(16) {
+        return Err(AsyncTiffError::General(format!(
+            "tile size must be a positive multiple of 16, got {}",
+            options.tile_size
+        )));
+    }
+    if ifd.bits_per_sample().iter().any(|bits| bits % 8 != 0) {
+        return Err(AsyncTiffError::General(format!(
+            "transcoding images with {:?} bits per sample is not supported",
+            ifd.bits_per_sample()
+        )));
+    }
+    if ifd.tile_offsets().is_none() && ifd.strip_offsets().is_none() {
+        return Err(AsyncTiffError::General(
+            "IFD has neither tile nor strip offsets".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The tags of one output level, excluding tile offsets and byte counts.
+fn level_tags(
+    ifd: &ImageFileDirectory,
+    full_resolution: bool,
+    width: u32,
+    height: u32,
+    options: &TranscodeOptions,
+) -> AsyncTiffResult<Vec<(Tag, TagValue)>> {
+    let shorts =
+        |values: Vec<u16>| TagValue::List(values.into_iter().map(TagValue::Short).collect());
+    let data_type_bits = ifd
+        .data_type()
+        .map_or(ifd.bits_per_sample()[0], |data_type| {
+            data_type.size() as u16 * 8
+        });
+    let doubles =
+        |values: &[f64]| TagValue::List(values.iter().copied().map(TagValue::Double).collect());
+
+    // YCbCr data is converted to RGB when decoding.
+    let photometric_interpretation = match ifd.photometric_interpretation() {
+        PhotometricInterpretation::YCbCr if YCbCr::decodes_to_rgb(ifd) => {
+            PhotometricInterpretation::RGB
+        }
+        other => other,
+    };
+
+    let mut tags = vec![
+        (Tag::ImageWidth, TagValue::Unsigned(width)),
+        (Tag::ImageLength, TagValue::Unsigned(height)),
+        // Samples with differing bit depths are widened to a common type when decoding.
+        (
+            Tag::BitsPerSample,
+            shorts(vec![data_type_bits; ifd.samples_per_pixel() as usize]),
+        ),
+        (
+            Tag::Compression,
+            TagValue::Short(options.compression.to_u16()),
+        ),
+        (
+            Tag::PhotometricInterpretation,
+            TagValue::Short(photometric_interpretation.to_u16()),
+        ),
+        (
+            Tag::SamplesPerPixel,
+            TagValue::Short(ifd.samples_per_pixel()),
+        ),
+        (
+            Tag::PlanarConfiguration,
+            TagValue::Short(PlanarConfiguration::Chunky.to_u16()),
+        ),
+        (Tag::TileWidth, TagValue::Unsigned(options.tile_size)),
+        (Tag::TileLength, TagValue::Unsigned(options.tile_size)),
+        (
+            Tag::SampleFormat,
+            shorts(ifd.sample_format().iter().map(|f| f.to_u16()).collect()),
+        ),
+    ];
+    if !full_resolution {
+        tags.push((Tag::NewSubfileType, TagValue::Unsigned(1)));
+    }
+    if let Some(extra_samples) = ifd.extra_samples() {
+        tags.push((
+            Tag::ExtraSamples,
+            shorts(extra_samples.iter().map(|s| s.to_u16()).collect()),
+        ));
+    }
+    if let Some(colormap) = ifd.colormap() {
+        tags.push((Tag::ColorMap, shorts(colormap.to_vec())));
+    }
+    if let Some(nodata) = ifd.gdal_nodata() {
+        tags.push((Tag::GdalNodata, TagValue::Ascii(nodata.to_string())));
+    }
+
+    if full_resolution {
+        if let Some(metadata) = ifd.gdal_metadata() {
+            tags.push((Tag::GdalMetadata, TagValue::Ascii(metadata.to_string())));
+        }
+        if let Some(scale) = ifd.model_pixel_scale() {
+            tags.push((Tag::ModelPixelScale, doubles(scale)));
+        }
+        if let Some(tiepoint) = ifd.model_tiepoint() {
+            tags.push((Tag::ModelTiepoint, doubles(tiepoint)));
+        }
+        if let Some(transformation) = ifd.model_transformation() {
+            tags.push((Tag::ModelTransformation, doubles(transformation)));
+        }
+        if let Some(geo_key_directory) = ifd.geo_key_directory() {
+            let (directory, double_params, ascii_params) = geo_key_directory.to_tags();
+            tags.push((Tag::GeoKeyDirectory, shorts(directory)));
+            if !double_params.is_empty() {
+                tags.push((Tag::GeoDoubleParams, doubles(&double_params)));
+            }
+            if !ascii_params.is_empty() {
+                tags.push((Tag::GeoAsciiParams, TagValue::Ascii(ascii_params)));
+            }
+        }
+    }
+    Ok(tags)
+}
+
+/// One resolution level of the output file.
+struct Level {
+    width: usize,
+    height: usize,
+    tiles_across: usize,
+    tile_count: usize,
+    /// Rows received but not yet written as tiles.
+    rows: Vec<u8>,
+    buffered_rows: usize,
+    received_rows: usize,
+    /// The compressed tiles written so far, in a temporary file.
+    data: File,
+    data_len: u64,
+    /// Tile offsets, relative to the start of `data` until the tiles are copied to the output.
+    tile_offsets: Vec<u64>,
+    tile_byte_counts: Vec<u64>,
+    entries: BTreeMap<u16, EncodedValue>,
+}
+
+impl Level {
+    fn new(
+        width: u32,
+        height: u32,
+        tile_size: u32,
+        entries: BTreeMap<u16, EncodedValue>,
+    ) -> AsyncTiffResult<Self> {
+        let tiles_across = width.div_ceil(tile_size) as usize;
+        let tile_count = tiles_across * height.div_ceil(tile_size) as usize;
+        Ok(Self {
+            width: width as usize,
+            height: height as usize,
+            tiles_across,
+            tile_count,
+            rows: vec![],
+            buffered_rows: 0,
+            received_rows: 0,
+            data: tempfile::tempfile()?,
+            data_len: 0,
+            tile_offsets: Vec::with_capacity(tile_count),
+            tile_byte_counts: Vec::with_capacity(tile_count),
+            entries,
+        })
+    }
+
+    /// Store the current tile offsets and byte counts, padded with zeros to the tile count, in
+    /// the IFD entries.
+    ///
+    /// The field type only depends on `bigtiff`, so the IFD has the same size before and after
+    /// the tiles are written.
+    fn set_chunk_tags(&mut self, endianness: Endianness, bigtiff: bool) -> AsyncTiffResult<()> {
+        for (tag, values) in [
+            (Tag::TileOffsets, &self.tile_offsets),
+            (Tag::TileByteCounts, &self.tile_byte_counts),
+        ] {
+            let padded = values
+                .iter()
+                .copied()
+                .chain(std::iter::repeat(0))
+                .take(self.tile_count);
+            let value = if bigtiff {
+                TagValue::List(padded.map(TagValue::UnsignedBig).collect())
+            } else {
+                let values = padded
+                    .map(|v| {
+                        u32::try_from(v).map(TagValue::Unsigned).map_err(|_| {
+                            AsyncTiffError::General(format!(
+                                "offset {v} does not fit in a non-BigTIFF file"
+                            ))
+                        })
+                    })
+                    .collect::<AsyncTiffResult<_>>()?;
+                TagValue::List(values)
+            };
+            self.entries
+                .insert(tag.to_u16(), encode_tag_value(&value, endianness, bigtiff)?);
+        }
+        Ok(())
+    }
+}
+
+/// Assembles rows into tiles for every level and writes them to the spill file of the level.
+struct Pipeline {
+    levels: Vec<Level>,
+    tile_size: usize,
+    pixel_size: usize,
+    compression: Compression,
+}
+
+impl Pipeline {
+    /// Add full rows of pixels to the level at `level_idx`, writing any completed row of tiles
+    /// and passing downsampled rows on to the next level.
+    fn push_rows(&mut self, level_idx: usize, rows: &[u8]) -> AsyncTiffResult<()> {
+        let level = &mut self.levels[level_idx];
+        let row_size = level.width * self.pixel_size;
+        let row_count = rows.len() / row_size;
+
+        // Nearest neighbor: keep every other pixel of every other row.
+        let mut downsampled = vec![];
+        for (i, row) in rows.chunks_exact(row_size).enumerate() {
+            if (level.received_rows + i).is_multiple_of(2) {
+                for pixel in row.chunks_exact(self.pixel_size).step_by(2) {
+                    downsampled.extend_from_slice(pixel);
+                }
+            }
+        }
+
+        level.rows.extend_from_slice(rows);
+        level.buffered_rows += row_count;
+        level.received_rows += row_count;
+        while level.buffered_rows >= self.tile_size
+            || (level.received_rows == level.height && level.buffered_rows > 0)
+        {
+            let tile_rows = level.buffered_rows.min(self.tile_size);
+            write_tile_row(
+                level,
+                tile_rows,
+                self.tile_size,
+                self.pixel_size,
+                self.compression,
+            )?;
+            level.rows.drain(..tile_rows * row_size);
+            level.buffered_rows -= tile_rows;
+        }
+
+        if level_idx + 1 < self.levels.len() && !downsampled.is_empty() {
+            self.push_rows(level_idx + 1, &downsampled)?;
+        }
+        Ok(())
+    }
+}
+
+/// Cut the first `tile_rows` buffered rows of `level` into tiles, padding partial tiles with
+/// zeros, and write them to the spill file of the level.
+fn write_tile_row(
+    level: &mut Level,
+    tile_rows: usize,
+    tile_size: usize,
+    pixel_size: usize,
+    compression: Compression,
+) -> AsyncTiffResult<()> {
+    let row_size = level.width * pixel_size;
+    let tile_row_size = tile_size * pixel_size;
+    for tile_x in 0..level.tiles_across {
+        let x_start = tile_x * tile_size;
+        let valid_width = tile_size.min(level.width - x_start) * pixel_size;
+        let mut tile = vec![0; tile_size * tile_row_size];
+        for row in 0..tile_rows {
+            let src = row * row_size + x_start * pixel_size;
+            tile[row * tile_row_size..][..valid_width]
+                .copy_from_slice(&level.rows[src..src + valid_width]);
+        }
+        let encoded = encode_chunk(compression, &tile)?;
+        level.data.write_all(&encoded)?;
+        level.tile_offsets.push(level.data_len);
+        level.tile_byte_counts.push(encoded.len() as u64);
+        level.data_len += encoded.len() as u64;
+    }
+    Ok(())
+}
+
+/// Reads rows of pixels from the source image, in pixel-interleaved layout, regardless of how
+/// the source is chunked.
+struct SourceRows<'a> {
+    ifd: &'a ImageFileDirectory,
+    reader: &'a dyn AsyncFileReader,
+    decoder_registry: &'a DecoderRegistry,
+    pixel_size: usize,
+    tiled: bool,
+    chunk_width: usize,
+    chunk_height: usize,
+    chunks_across: usize,
+    /// The index and the decoded pixels of the most recently read row of chunks.
+    cached: Option<(usize, Vec<u8>)>,
+}
+
+impl<'a> SourceRows<'a> {
+    fn new(
+        ifd: &'a ImageFileDirectory,
+        reader: &'a dyn AsyncFileReader,
+        decoder_registry: &'a DecoderRegistry,
+        pixel_size: usize,
+    ) -> AsyncTiffResult<Self> {
+        let (tiled, chunk_width, chunk_height) = match (ifd.tile_width(), ifd.tile_height()) {
+            (Some(tile_width), Some(tile_height)) if ifd.tile_offsets().is_some() => {
+                (true, tile_width, tile_height)
+            }
+            _ => (false, ifd.image_width(), ifd.strip_height()),
+        };
+        Ok(Self {
+            ifd,
+            reader,
+            decoder_registry,
+            pixel_size,
+            tiled,
+            chunk_width: chunk_width as usize,
+            chunk_height: chunk_height as usize,
+            chunks_across: ifd.image_width().div_ceil(chunk_width) as usize,
+            cached: None,
+        })
+    }
+
+    /// Read the rows `start..end` of the image.
+    async fn read_rows(&mut self, start: u32, end: u32) -> AsyncTiffResult<Vec<u8>> {
+        let row_size = self.ifd.image_width() as usize * self.pixel_size;
+        let (start, end) = (start as usize, end as usize);
+        let mut out = Vec::with_capacity((end - start) * row_size);
+        let mut row = start;
+        while row < end {
+            let chunk_y = row / self.chunk_height;
+            let band_start = chunk_y * self.chunk_height;
+            let band_end = (band_start + self.chunk_height).min(self.ifd.image_height() as usize);
+            let take_end = end.min(band_end);
+            let band = self.chunk_row(chunk_y).await?;
+            out.extend_from_slice(
+                &band[(row - band_start) * row_size..(take_end - band_start) * row_size],
+            );
+            row = take_end;
+        }
+        Ok(out)
+    }
+
+    /// Decode the row of chunks at `chunk_y` into full-width rows of pixels.
+    async fn chunk_row(&mut self, chunk_y: usize) -> AsyncTiffResult<&[u8]> {
+        if !matches!(self.cached, Some((y, _)) if y == chunk_y) {
+            let tiles = if self.tiled {
+                let xy = (0..self.chunks_across)
+                    .map(|x| (x, chunk_y))
+                    .collect::<Vec<_>>();
+                self.ifd.fetch_tiles(&xy, self.reader).await?
+            } else {
+                vec![self.ifd.fetch_strip(chunk_y, self.reader).await?]
+            };
+
+            let image_width = self.ifd.image_width() as usize;
+            let row_size = image_width * self.pixel_size;
+            let band_start = chunk_y * self.chunk_height;
+            let band_rows = self
+                .chunk_height
+                .min(self.ifd.image_height() as usize - band_start);
+            let mut band = vec![0; band_rows * row_size];
+            let planar = self.ifd.planar_configuration() == PlanarConfiguration::Planar;
+
+            for (chunk_x, tile) in tiles.into_iter().enumerate() {
+                let array = tile.decode(self.decoder_registry)?;
+                let [_, chunk_height, chunk_width] = if planar {
+                    array.shape()
+                } else {
+                    let [h, w, s] = array.shape();
+                    [s, h, w]
+                };
+                let data = array.data().as_ref();
+                let data = if planar {
+                    planar_to_chunky(
+                        data,
+                        self.ifd.samples_per_pixel() as usize,
+                        chunk_height * chunk_width,
+                        self.pixel_size,
+                    )
+                } else {
+                    data.to_vec()
+                };
+
+                let x_start = chunk_x * self.chunk_width;
+                let valid_width = chunk_width.min(image_width - x_start) * self.pixel_size;
+                for row in 0..band_rows.min(chunk_height) {
+                    let src = row * chunk_width * self.pixel_size;
+                    let dst = row * row_size + x_start * self.pixel_size;
+                    band[dst..dst + valid_width].copy_from_slice(&data[src..src + valid_width]);
+                }
+            }
+            self.cached = Some((chunk_y, band));
+        }
+        Ok(&self.cached.as_ref().unwrap().1)
+    }
+}
+
+/// Convert band-sequential data to pixel-interleaved data.
+fn planar_to_chunky(data: &[u8], samples: usize, pixels: usize, pixel_size: usize) -> Vec<u8> {
+    let sample_size = pixel_size / samples;
+    let band_size = pixels * sample_size;
+    let mut out = vec![0; pixels * pixel_size];
+    for (band, band_data) in data.chunks_exact(band_size).take(samples).enumerate() {
+        for (pixel, sample) in band_data.chunks_exact(sample_size).enumerate() {
+            let dst = pixel * pixel_size + band * sample_size;
+            out[dst..dst + sample_size].copy_from_slice(sample);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs::File;
+    use std::path::{Path, PathBuf};
+
+    use tiff::decoder::{Decoder, DecodingResult};
+
+    use super::*;
+    use crate::test::util::{open_tiff, open_tiff_path, temp_path};
+
+    async fn transcode_fixture(filename: &str, options: &TranscodeOptions) -> PathBuf {
+        let (reader, tiff) = open_tiff(filename).await;
+        let path = temp_path("transcoded.tif");
+        let file = File::create(&path).unwrap();
+        transcode(&tiff, reader.as_ref(), options, file)
+            .await
+            .unwrap();
+        path
+    }
+
+    /// Decode every image in the file with the `tiff` crate, as native-endian bytes.
+    fn reference_images(path: &Path) -> Vec<((u32, u32), Vec<u8>)> {
+        let mut decoder = Decoder::new(File::open(path).unwrap()).unwrap();
+        let mut images = vec![];
+        loop {
+            let dimensions = decoder.dimensions().unwrap();
+            let data = match decoder.read_image().unwrap() {
+                DecodingResult::U8(data) => data,
+                DecodingResult::U16(data) => data.iter().flat_map(|v| v.to_ne_bytes()).collect(),
+                _ => unimplemented!(),
+            };
+            images.push((dimensions, data));
+            if !decoder.more_images() {
+                return images;
+            }
+            decoder.next_image().unwrap();
+        }
+    }
+
+    fn fixture_path(filename: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .join(filename)
+    }
+
+    #[tokio::test]
+    async fn test_transcode_preserves_pixels() {
+        for (filename, compression) in [
+            ("image-tiff/rgb-3c-8b.tiff", Compression::Deflate),
+            ("image-tiff/minisblack-1c-16b.tiff", Compression::LZW),
+            ("image-tiff/planar-rgb-u8.tif", Compression::Deflate),
+            ("image-tiff/tiled-rgb-u8.tif", Compression::None),
+        ] {
+            let options = TranscodeOptions::default()
+                .with_compression(compression)
+                .with_overviews(false);
+            let path = transcode_fixture(filename, &options).await;
+
+            let expected = reference_images(&fixture_path(filename));
+            let actual = reference_images(&path);
+            assert_eq!(actual.len(), 1, "{filename}");
+            assert_eq!(actual[0].0, expected[0].0, "{filename}");
+            if filename.contains("planar") {
+                // The `tiff` crate only decodes the first plane of planar images.
+                let first_band = actual[0].1.iter().step_by(3).copied().collect::<Vec<_>>();
+                assert_eq!(first_band, expected[0].1, "{filename}");
+            } else {
+                assert_eq!(actual[0].1, expected[0].1, "{filename}");
+            }
+
+            let (_, transcoded) = open_tiff_path(&path).await;
+            let ifd = &transcoded.ifds()[0];
+            assert_eq!(ifd.compression(), compression);
+            assert_eq!(ifd.tile_width(), Some(512));
+            assert_eq!(ifd.planar_configuration(), PlanarConfiguration::Chunky);
+
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transcode_overviews() {
+        let options = TranscodeOptions::default().with_tile_size(16);
+        let path = transcode_fixture("image-tiff/rgb-3c-8b.tiff", &options).await;
+
+        let (_, transcoded) = open_tiff_path(&path).await;
+        let ifds = transcoded.ifds();
+        assert!(ifds.len() > 1);
+        // IFDs are at the start of the file, before any tile data.
+        let first_tile = ifds
+            .iter()
+            .flat_map(|ifd| ifd.tile_offsets().unwrap().iter().copied())
+            .min()
+            .unwrap();
+        assert!(ifds.iter().all(|ifd| ifd
+            .tile_offsets()
+            .unwrap()
+            .iter()
+            .all(|o| *o >= first_tile)));
+        // The image data is ordered from the smallest overview to the full-resolution image.
+        for pair in ifds.windows(2) {
+            assert!(pair[1].tile_offsets().unwrap()[0] < pair[0].tile_offsets().unwrap()[0]);
+        }
+        let last = ifds.last().unwrap();
+        assert!(last.image_width() <= 16 && last.image_height() <= 16);
+        assert!(ifds[1..]
+            .iter()
+            .all(|ifd| ifd.new_subfile_type() == Some(1)));
+
+        let images = reference_images(&path);
+        assert_eq!(images.len(), ifds.len());
+        for pair in images.windows(2) {
+            let ((width, _), full) = &pair[0];
+            let ((overview_width, overview_height), overview) = &pair[1];
+            for y in 0..*overview_height as usize {
+                for x in 0..*overview_width as usize {
+                    let src = ((2 * y) * *width as usize + 2 * x) * 3;
+                    let dst = (y * *overview_width as usize + x) * 3;
+                    assert_eq!(overview[dst..dst + 3], full[src..src + 3]);
+                }
+            }
+        }
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_transcode_preserves_geo_tags() {
+        let filename = "other/geogtowgs_subset_USGS_13_s14w171.tif";
+        let path = transcode_fixture(filename, &TranscodeOptions::default()).await;
+
+        let (_, original) = open_tiff(filename).await;
+        let (_, transcoded) = open_tiff_path(&path).await;
+        let (original, transcoded) = (&original.ifds()[0], &transcoded.ifds()[0]);
+        assert!(original.geo_key_directory().is_some());
+        assert_eq!(transcoded.geo_key_directory(), original.geo_key_directory());
+        assert_eq!(transcoded.model_pixel_scale(), original.model_pixel_scale());
+        assert_eq!(transcoded.model_tiepoint(), original.model_tiepoint());
+        assert_eq!(transcoded.gdal_nodata(), original.gdal_nodata());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_transcode_rejects_unsupported_compression() {
+        let (reader, tiff) = open_tiff("image-tiff/rgb-3c-8b.tiff").await;
+        let options = TranscodeOptions::default().with_compression(Compression::ModernJPEG);
+        let result = transcode(
+            &tiff,
+            reader.as_ref(),
+            &options,
+            std::io::Cursor::new(vec![]),
+        )
+        .await;
+        assert!(result.is_err());
+    }
+}
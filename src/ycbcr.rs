@@ -0,0 +1,179 @@
+//! Chroma upsampling and YCbCr to RGB conversion for uncompressed, Deflate and LZW tiles.
+//!
+//! JPEG-compressed YCbCr tiles are converted by the JPEG decoder and never reach this module.
+//! Other compressions store the subsampled components as data units of `h * v` luma samples
+//! followed by one Cb and one Cr sample, where `[h, v]` is the YCbCr subsampling of the IFD.
+
+use crate::ifd::ImageFileDirectory;
+use crate::tags::{Compression, PhotometricInterpretation, PlanarConfiguration, SampleFormat};
+
+/// The default luma coefficients from CCIR Recommendation 601-1.
+const DEFAULT_COEFFICIENTS: [f64; 3] = [0.299, 0.587, 0.114];
+
+/// The default reference black and white, which maps the full 8-bit range of every component.
+const DEFAULT_REFERENCE_BLACK_WHITE: [f64; 6] = [0.0, 255.0, 128.0, 255.0, 128.0, 255.0];
+
+/// Parameters for converting 8-bit YCbCr samples to RGB.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct YCbCr {
+    pub(crate) subsampling: [u16; 2],
+    pub(crate) coefficients: [f64; 3],
+    pub(crate) reference_black_white: [f64; 6],
+}
+
+impl Default for YCbCr {
+    fn default() -> Self {
+        Self {
+            subsampling: [2, 2],
+            coefficients: DEFAULT_COEFFICIENTS,
+            reference_black_white: DEFAULT_REFERENCE_BLACK_WHITE,
+        }
+    }
+}
+
+impl YCbCr {
+    /// The conversion parameters for tiles of `ifd`, or `None` if its tiles aren't converted.
+    ///
+    /// Only chunky 8-bit unsigned YCbCr data with three samples per pixel is converted. JPEG
+    /// tiles are excluded since the JPEG decoder already returns RGB.
+    pub(crate) fn from_ifd(ifd: &ImageFileDirectory) -> Option<Self> {
+        let convertible = ifd.photometric_interpretation == PhotometricInterpretation::YCbCr
+            && !matches!(ifd.compression, Compression::JPEG | Compression::ModernJPEG)
+            && ifd.planar_configuration == PlanarConfiguration::Chunky
+            && ifd.samples_per_pixel == 3
+            && ifd.bits_per_sample.iter().all(|&bits| bits == 8)
+            && ifd.sample_format.iter().all(|f| *f == SampleFormat::Uint);
+        if !convertible {
+            return None;
+        }
+
+        let mut ycbcr = Self::default();
+        if let Some([horizontal, vertical]) = ifd.ycbcr_subsampling {
+            // Other factors are invalid, so fall back to the default rather than misreading data
+            if [1, 2, 4].contains(&horizontal) && [1, 2, 4].contains(&vertical) {
+                ycbcr.subsampling = [horizontal, vertical];
+            }
+        }
+        if let Some(Ok(coefficients)) = ifd.ycbcr_coefficients.as_deref().map(TryInto::try_into) {
+            ycbcr.coefficients = coefficients;
+        }
+        if let Some(Ok(reference)) = ifd.reference_black_white.as_deref().map(TryInto::try_into) {
+            ycbcr.reference_black_white = reference;
+        }
+        Some(ycbcr)
+    }
+
+    /// Whether the tiles of `ifd` decode to RGB, either through the JPEG decoder or through this
+    /// conversion.
+    pub(crate) fn decodes_to_rgb(ifd: &ImageFileDirectory) -> bool {
+        ifd.photometric_interpretation == PhotometricInterpretation::YCbCr
+            && (matches!(ifd.compression, Compression::JPEG | Compression::ModernJPEG)
+                || Self::from_ifd(ifd).is_some())
+    }
+
+    /// The number of bytes of subsampled data in a tile of `width` by `height` pixels.
+    ///
+    /// Partial data units at the right and bottom edges are padded to whole units.
+    pub(crate) fn encoded_len(&self, width: usize, height: usize) -> usize {
+        let [h, v] = self.subsampling.map(usize::from);
+        width.div_ceil(h) * height.div_ceil(v) * (h * v + 2)
+    }
+
+    /// Upsample the chroma of the subsampled `data` of a tile of `width` by `height` pixels and
+    /// convert it to chunky 8-bit RGB.
+    ///
+    /// `data` must hold at least [`encoded_len`][Self::encoded_len] bytes.
+    pub(crate) fn upsample_to_rgb(&self, data: &[u8], width: usize, height: usize) -> Vec<u8> {
+        let [h, v] = self.subsampling.map(usize::from);
+        let units_per_row = width.div_ceil(h);
+        let unit_len = h * v + 2;
+        let mut rgb = vec![0; width * height * 3];
+        for (unit_index, unit) in data
+            .chunks_exact(unit_len)
+            .take(self.encoded_len(width, height) / unit_len)
+            .enumerate()
+        {
+            let unit_col = (unit_index % units_per_row) * h;
+            let unit_row = (unit_index / units_per_row) * v;
+            let (cb, cr) = (unit[h * v], unit[h * v + 1]);
+            for dy in 0..v {
+                let row = unit_row + dy;
+                if row >= height {
+                    break;
+                }
+                for dx in 0..h {
+                    let col = unit_col + dx;
+                    if col >= width {
+                        break;
+                    }
+                    let offset = (row * width + col) * 3;
+                    rgb[offset..offset + 3].copy_from_slice(&self.pixel(unit[dy * h + dx], cb, cr));
+                }
+            }
+        }
+        rgb
+    }
+
+    /// Convert a single YCbCr sample to RGB.
+    fn pixel(&self, y: u8, cb: u8, cr: u8) -> [u8; 3] {
+        let [luma_red, luma_green, luma_blue] = self.coefficients;
+        let [y_black, y_white, cb_black, cb_white, cr_black, cr_white] = self.reference_black_white;
+        let y = (y as f64 - y_black) * 255.0 / (y_white - y_black);
+        let cb = (cb as f64 - cb_black) * 127.0 / (cb_white - cb_black);
+        let cr = (cr as f64 - cr_black) * 127.0 / (cr_white - cr_black);
+
+        let red = cr * (2.0 - 2.0 * luma_red) + y;
+        let blue = cb * (2.0 - 2.0 * luma_blue) + y;
+        let green = (y - luma_blue * blue - luma_red * red) / luma_green;
+        [red, green, blue].map(|value| value.round().clamp(0.0, 255.0) as u8)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_encoded_len() {
+        let ycbcr = YCbCr::default();
+        assert_eq!(ycbcr.encoded_len(4, 4), 4 * 6);
+        // Partial units at the edges are padded
+        assert_eq!(ycbcr.encoded_len(3, 3), 4 * 6);
+
+        let ycbcr = YCbCr {
+            subsampling: [4, 1],
+            ..Default::default()
+        };
+        assert_eq!(ycbcr.encoded_len(6, 2), 4 * 6);
+    }
+
+    #[test]
+    fn test_to_rgb() {
+        let ycbcr = YCbCr::default();
+        // Two 2x2 units: neutral gray, then (nearly) pure red
+        let data = [
+            10, 20, 30, 40, 128, 128, //
+            76, 76, 76, 76, 85, 255,
+        ];
+        let rgb = ycbcr.upsample_to_rgb(&data, 4, 2);
+        assert_eq!(
+            rgb,
+            [
+                10, 10, 10, 20, 20, 20, 254, 0, 0, 254, 0, 0, //
+                30, 30, 30, 40, 40, 40, 254, 0, 0, 254, 0, 0,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_to_rgb_partial_unit() {
+        let ycbcr = YCbCr::default();
+        // A 3x1 tile padded to two 2x2 units; luma samples outside the tile are ignored
+        let data = [
+            50, 60, 0, 0, 128, 128, //
+            70, 0, 0, 0, 128, 128,
+        ];
+        let rgb = ycbcr.upsample_to_rgb(&data, 3, 1);
+        assert_eq!(rgb, [50, 50, 50, 60, 60, 60, 70, 70, 70]);
+    }
+}
@@ -0,0 +1,393 @@
+//! Translation of TIFF images to [Zarr v3] arrays, to serve a TIFF as a Zarr store without
+//! rewriting its data.
+//!
+//! Each IFD becomes an array whose chunks are the compressed tiles or strips of the file, passed
+//! through unchanged with the codec that decompresses them. A [`ZarrStore`] maps store keys to
+//! metadata documents or to the byte range of a chunk, so a thin shim, e.g. an HTTP handler, can
+//! answer Zarr reads with range requests against the original file.
+//!
+//! Like [`TIFF::to_kerchunk_json`], only uncompressed, Deflate, LZW and ZSTD chunks without a
+//! predictor can be described, and strips must all have the same height.
+//!
+//! ```
+//! # tokio_test::block_on(async {
+//! use std::sync::Arc;
+//!
+//! use async_tiff::reader::{AsyncFileReader, MemoryReader};
+//! use async_tiff::zarr::{ZarrEntry, ZarrStore};
+//! use async_tiff::TIFF;
+//!
+//! let data = std::fs::read("fixtures/image-tiff/tiled-rect-rgb-u8.tif").unwrap();
+//! let reader = Arc::new(MemoryReader::new(data)) as Arc<dyn AsyncFileReader>;
+//! let tiff = TIFF::open(reader.clone()).await.unwrap();
+//! let store = ZarrStore::try_new(&tiff).unwrap();
+//!
+//! let array = &store.arrays()[0];
+//! assert_eq!(array.dimension_names(), ["y", "x", "band"]);
+//! assert!(matches!(store.get("0/zarr.json"), Some(ZarrEntry::Metadata(_))));
+//!
+//! // Chunks are the compressed tiles, served unchanged
+//! let Some(ZarrEntry::Chunk(range)) = store.get("0/c/0/0/0") else {
+//!     panic!("expected a chunk");
+//! };
+//! let chunk = reader.get_bytes(range.clone()).await.unwrap();
+//! assert_eq!(chunk.len() as u64, range.end - range.start);
+//! # })
+//! ```
+//!
+//! [Zarr v3]: https://zarr-specs.readthedocs.io/en/latest/v3/core/index.html
+
+use std::ops::Range;
+
+use crate::error::{AsyncTiffError, AsyncTiffResult};
+use crate::manifest::{chunk_records, json_fill_value, json_string, ArrayLayout};
+use crate::reader::Endianness;
+use crate::tags::Compression;
+use crate::{ChunkRecord, DataType, ImageFileDirectory, TileByteRange, TIFF};
+
+/// The metadata document of the group holding one array per IFD.
+const GROUP_METADATA: &str = r#"{"zarr_format":3,"node_type":"group","attributes":{}}"#;
+
+/// The description of one IFD as a Zarr v3 array.
+#[derive(Debug, Clone)]
+pub struct ZarrArray<'a> {
+    ifd: &'a ImageFileDirectory,
+    layout: ArrayLayout,
+    codec: Option<&'static str>,
+}
+
+impl<'a> ZarrArray<'a> {
+    /// Describe `ifd` as a Zarr array.
+    ///
+    /// Returns an error if its chunks can't be decoded by standard Zarr codecs.
+    pub fn try_new(ifd: &'a ImageFileDirectory) -> AsyncTiffResult<Self> {
+        Self::describe(ifd, "IFD")
+    }
+
+    /// Describe `ifd`, called `name` in errors.
+    fn describe(ifd: &'a ImageFileDirectory, name: &str) -> AsyncTiffResult<Self> {
+        let unsupported = |what: String| {
+            AsyncTiffError::General(format!("{name} can't be described as a Zarr array: {what}"))
+        };
+        let layout = ArrayLayout::from_ifd(ifd, unsupported)?;
+        let codec = match ifd.compression() {
+            Compression::None => None,
+            Compression::Deflate | Compression::OldDeflate => {
+                Some(r#"{"name":"numcodecs.zlib","configuration":{"level":6}}"#)
+            }
+            Compression::ZSTD => {
+                Some(r#"{"name":"zstd","configuration":{"level":0,"checksum":false}}"#)
+            }
+            Compression::LZW => Some(r#"{"name":"imagecodecs_lzw"}"#),
+            compression => return Err(unsupported(format!("compression {compression:?}"))),
+        };
+        Ok(Self { ifd, layout, codec })
+    }
+
+    /// The IFD described by this array.
+    pub fn ifd(&self) -> &'a ImageFileDirectory {
+        self.ifd
+    }
+
+    /// The shape of the array: `(y, x)` for single-band images, `(y, x, band)` for chunky and
+    /// `(band, y, x)` for planar images.
+    pub fn shape(&self) -> &[usize] {
+        &self.layout.shape
+    }
+
+    /// The shape of each chunk, i.e. of one tile or strip.
+    pub fn chunk_shape(&self) -> &[usize] {
+        &self.layout.chunks
+    }
+
+    /// The names of the dimensions of [`shape`][Self::shape].
+    pub fn dimension_names(&self) -> &[&'static str] {
+        &self.layout.dimensions
+    }
+
+    /// The data type of the array's elements.
+    pub fn data_type(&self) -> DataType {
+        self.layout.data_type
+    }
+
+    /// The `zarr.json` metadata document of the array.
+    ///
+    /// The codecs are the `bytes` codec with the byte order of the file, followed by the codec
+    /// of the TIFF's compression. The fill value is the GDAL nodata value, or zero.
+    pub fn metadata_json(&self) -> String {
+        let data_type = self.layout.data_type;
+        let bytes = match (data_type.size(), self.ifd.endianness) {
+            (1, _) => r#"{"name":"bytes"}"#,
+            (_, Endianness::LittleEndian) => {
+                r#"{"name":"bytes","configuration":{"endian":"little"}}"#
+            }
+            (_, Endianness::BigEndian) => r#"{"name":"bytes","configuration":{"endian":"big"}}"#,
+        };
+        let codecs = std::iter::once(bytes)
+            .chain(self.codec)
+            .collect::<Vec<_>>()
+            .join(",");
+        let fill_value =
+            json_fill_value(self.ifd.nodata_for_dtype(data_type)).unwrap_or_else(|| {
+                match data_type {
+                    DataType::Float32 | DataType::Float64 => "0.0",
+                    _ => "0",
+                }
+                .to_string()
+            });
+        let dimension_names = self
+            .layout
+            .dimensions
+            .iter()
+            .map(|name| json_string(name))
+            .collect::<Vec<_>>()
+            .join(",");
+        format!(
+            r#"{{"zarr_format":3,"node_type":"array","shape":{:?},"data_type":"{}","chunk_grid":{{"name":"regular","configuration":{{"chunk_shape":{:?}}}}},"chunk_key_encoding":{{"name":"default","configuration":{{"separator":"/"}}}},"fill_value":{fill_value},"codecs":[{codecs}],"dimension_names":[{dimension_names}],"attributes":{{}}}}"#,
+            self.layout.shape,
+            zarr_data_type(data_type),
+            self.layout.chunks,
+        )
+    }
+
+    /// The key of the chunk holding `record`, relative to the array, e.g. `c/0/1`.
+    pub fn chunk_key(&self, record: &ChunkRecord) -> String {
+        let coords = self.layout.chunk_coords(record).map(|i| i.to_string());
+        format!("c/{}", coords.collect::<Vec<_>>().join("/"))
+    }
+
+    /// The keys of the stored chunks of this array, with their records.
+    ///
+    /// Chunks that aren't stored in the file are skipped, as Zarr reads missing chunks as the
+    /// fill value.
+    pub fn chunks(&self) -> impl Iterator<Item = (String, ChunkRecord)> + '_ {
+        chunk_records(self.ifd)
+            .filter(|record| record.length > 0)
+            .map(|record| (self.chunk_key(&record), record))
+    }
+
+    /// The byte range in the file of the chunk with `key`, relative to the array.
+    ///
+    /// Returns `None` if `key` isn't a chunk of this array or the chunk isn't stored.
+    pub fn chunk_byte_range(&self, key: &str) -> Option<Range<u64>> {
+        let coords = key
+            .strip_prefix("c/")?
+            .split('/')
+            .map(|i| i.parse::<usize>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let (band, y, x) = match (coords.as_slice(), self.layout.dimensions.as_slice()) {
+            (&[y, x], [_, _]) => (0, y, x),
+            (&[y, x, 0], [_, _, "band"]) => (0, y, x),
+            (&[band, y, x], ["band", _, _]) => (band, y, x),
+            _ => return None,
+        };
+        let range = match self.ifd.tile_count() {
+            Some((x_count, y_count)) if x < x_count && y < y_count => {
+                self.ifd.tile_byte_range(x, y)?
+            }
+            None if x == 0 && y < self.ifd.strip_count()? => self.ifd.strip_byte_range(y)?,
+            _ => return None,
+        };
+        let range = match range {
+            TileByteRange::Chunky(range) => range,
+            TileByteRange::Planar(mut ranges) => {
+                (band < ranges.len()).then(|| ranges.swap_remove(band))?
+            }
+        };
+        (!range.is_empty()).then_some(range)
+    }
+}
+
+/// A value of a [`ZarrStore`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZarrEntry {
+    /// A `zarr.json` metadata document.
+    Metadata(String),
+    /// The byte range in the file of a chunk, to be returned unchanged.
+    Chunk(Range<u64>),
+}
+
+/// A read-only Zarr v3 store backed by the chunks of a TIFF.
+///
+/// The root is a group holding one array per IFD, named after the index of the IFD.
+#[derive(Debug, Clone)]
+pub struct ZarrStore<'a> {
+    arrays: Vec<ZarrArray<'a>>,
+}
+
+impl<'a> ZarrStore<'a> {
+    /// Describe every IFD of `tiff` as a Zarr array.
+    ///
+    /// Returns an error if any IFD can't be described, see [`ZarrArray::try_new`].
+    pub fn try_new(tiff: &'a TIFF) -> AsyncTiffResult<Self> {
+        let arrays = tiff
+            .ifds()
+            .iter()
+            .enumerate()
+            .map(|(index, ifd)| ZarrArray::describe(ifd, &format!("IFD {index}")))
+            .collect::<AsyncTiffResult<_>>()?;
+        Ok(Self { arrays })
+    }
+
+    /// The arrays of this store, in IFD order.
+    pub fn arrays(&self) -> &[ZarrArray<'a>] {
+        &self.arrays
+    }
+
+    /// The value at `key`, or `None` if there is no such key or the chunk isn't stored.
+    pub fn get(&self, key: &str) -> Option<ZarrEntry> {
+        if key == "zarr.json" {
+            return Some(ZarrEntry::Metadata(GROUP_METADATA.to_string()));
+        }
+        let (index, key) = key.split_once('/')?;
+        let array = self.arrays.get(index.parse::<usize>().ok()?)?;
+        if key == "zarr.json" {
+            return Some(ZarrEntry::Metadata(array.metadata_json()));
+        }
+        array.chunk_byte_range(key).map(ZarrEntry::Chunk)
+    }
+
+    /// Every key of this store: the metadata documents followed by the stored chunks.
+    pub fn keys(&self) -> impl Iterator<Item = String> + '_ {
+        let metadata = (0..self.arrays.len()).map(|index| format!("{index}/zarr.json"));
+        let chunks = self.arrays.iter().enumerate().flat_map(|(index, array)| {
+            array.chunks().map(move |(key, _)| format!("{index}/{key}"))
+        });
+        std::iter::once("zarr.json".to_string())
+            .chain(metadata)
+            .chain(chunks)
+    }
+}
+
+/// The Zarr v3 name of `data_type`.
+fn zarr_data_type(data_type: DataType) -> &'static str {
+    match data_type {
+        DataType::Bool => "bool",
+        DataType::UInt8 => "uint8",
+        DataType::UInt16 => "uint16",
+        DataType::UInt32 => "uint32",
+        DataType::UInt64 => "uint64",
+        DataType::Int8 => "int8",
+        DataType::Int16 => "int16",
+        DataType::Int32 => "int32",
+        DataType::Int64 => "int64",
+        DataType::Float32 => "float32",
+        DataType::Float64 => "float64",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::tags::Tag;
+    use crate::test::util::{open_tiff_path, temp_copy};
+    use crate::writer::{NewIfd, TiffEditor};
+    use crate::TagValue;
+
+    #[cfg(feature = "transcode")]
+    #[tokio::test]
+    async fn test_zarr_store() {
+        use crate::test::util::{open_tiff, temp_path};
+        use crate::writer::{transcode, TranscodeOptions};
+
+        // Predictors have no standard Zarr codec
+        let (reader, tiff) = open_tiff("image-tiff/tiled-rgb-u8.tif").await;
+        assert!(ZarrStore::try_new(&tiff).is_err());
+
+        let path = temp_path("zarr.tif");
+        let options = TranscodeOptions::default().with_tile_size(16);
+        let file = std::fs::File::create(&path).unwrap();
+        transcode(&tiff, reader.as_ref(), &options, file)
+            .await
+            .unwrap();
+        let (_, tiff) = open_tiff_path(&path).await;
+        std::fs::remove_file(path).unwrap();
+        let ifd = &tiff.ifds()[0];
+
+        let store = ZarrStore::try_new(&tiff).unwrap();
+        let array = &store.arrays()[0];
+        let (width, height) = (ifd.image_width() as usize, ifd.image_height() as usize);
+        assert_eq!(array.shape(), [height, width, 3]);
+        assert_eq!(array.chunk_shape(), [16, 16, 3]);
+        assert_eq!(array.data_type(), DataType::UInt8);
+
+        let Some(ZarrEntry::Metadata(json)) = store.get("0/zarr.json") else {
+            panic!("expected array metadata");
+        };
+        assert!(json.starts_with(r#"{"zarr_format":3,"node_type":"array","#));
+        assert!(json.contains(r#""data_type":"uint8""#));
+        assert!(json.contains(r#""chunk_shape":[16, 16, 3]"#));
+        assert!(json.contains(r#""codecs":[{"name":"bytes"},{"name":"numcodecs.zlib""#));
+        assert!(json.contains(r#""dimension_names":["y","x","band"]"#));
+        assert_eq!(
+            store.get("zarr.json"),
+            Some(ZarrEntry::Metadata(GROUP_METADATA.to_string()))
+        );
+
+        let record = ifd.chunk_records().nth(1).unwrap();
+        assert_eq!(array.chunk_key(&record), "c/0/1/0");
+        assert_eq!(
+            store.get("0/c/0/1/0"),
+            Some(ZarrEntry::Chunk(
+                record.offset..record.offset + record.length
+            ))
+        );
+        for key in [
+            "0/c/0/1/1",
+            "0/c/0/1",
+            "0/c/0/99/0",
+            "99/zarr.json",
+            "0/c/a/1/0",
+        ] {
+            assert_eq!(store.get(key), None, "{key}");
+        }
+
+        let keys = store.keys().collect::<Vec<_>>();
+        assert_eq!(keys[..2], ["zarr.json", "0/zarr.json"]);
+        assert!(keys.contains(&"0/c/0/1/0".to_string()));
+        assert!(keys.iter().all(|key| store.get(key).is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_zarr_planar() {
+        // The last strip of this image is shorter than the others
+        let path = temp_copy("image-tiff/planar-rgb-u8.tif");
+        let (_, tiff) = open_tiff_path(&path).await;
+        assert!(ZarrArray::try_new(&tiff.ifds()[0]).is_err());
+
+        // Two uncompressed strips of 2 rows per band
+        let strips = (0..6u8).map(|i| vec![i; 8]);
+        let ifd = NewIfd::stripped(4, 4, 2)
+            .with_tag(Tag::SamplesPerPixel, TagValue::Short(3))
+            .with_tag(
+                Tag::BitsPerSample,
+                TagValue::List(vec![TagValue::Short(8); 3]),
+            )
+            .with_tag(Tag::PlanarConfiguration, TagValue::Short(2))
+            .with_tag(Tag::PhotometricInterpretation, TagValue::Short(2))
+            .with_tag(Tag::Compression, TagValue::Short(1))
+            .with_chunks(strips);
+        let mut editor = TiffEditor::open(&path).unwrap();
+        let index = editor.append_ifd(ifd).unwrap();
+        editor.save().unwrap();
+        let (_, tiff) = open_tiff_path(&path).await;
+        std::fs::remove_file(path).unwrap();
+        let ifd = &tiff.ifds()[index];
+
+        let array = ZarrArray::try_new(ifd).unwrap();
+        assert_eq!(array.shape(), [3, 4, 4]);
+        assert_eq!(array.chunk_shape(), [1, 2, 4]);
+        assert_eq!(array.dimension_names(), ["band", "y", "x"]);
+        let record = ifd.chunk_records().nth(3).unwrap();
+        assert_eq!(record.band, Some(1));
+        assert_eq!(array.chunk_key(&record), "c/1/1/0");
+        assert_eq!(
+            array.chunk_byte_range("c/1/1/0"),
+            Some(record.offset..record.offset + record.length)
+        );
+        for key in ["c/3/0/0", "c/0/2/0", "c/0/0/1", "0/0/0"] {
+            assert_eq!(array.chunk_byte_range(key), None, "{key}");
+        }
+        assert_eq!(array.chunks().count(), 6);
+    }
+}